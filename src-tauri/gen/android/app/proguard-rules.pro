@@ -11,6 +11,16 @@
 # ClassNotFoundException: com.karelian.aventura.MainActivity
 -keep class com.karelian.aventura.MainActivity { *; }
 
+# Plugins are loaded by name from Rust, and their @Command methods and
+# argument classes are reached by reflection
+-keep class com.karelian.aventura.SyncServicePlugin { *; }
+-keep class com.karelian.aventura.DeviceStatePlugin { *; }
+-keep class com.karelian.aventura.SyncBlePlugin { *; }
+-keep class com.karelian.aventura.Ble*Args { *; }
+-keep class com.karelian.aventura.DirectLinkPlugin { *; }
+-keep class com.karelian.aventura.StartSyncServiceArgs { *; }
+-keep class com.karelian.aventura.SyncForegroundService { *; }
+
 # If your project uses WebView with JS, uncomment the following
 # and specify the fully qualified class name to the JavaScript interface
 # class:
@@ -0,0 +1,46 @@
+package com.karelian.aventura
+
+import android.app.Activity
+import android.content.Context
+import android.content.Intent
+import android.content.IntentFilter
+import android.net.ConnectivityManager
+import android.os.BatteryManager
+import android.os.PowerManager
+import app.tauri.annotation.Command
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+/**
+ * Reports battery, charging, power-saver and metered-network state so the
+ * Rust job queue can hold back model calls that would drain the phone.
+ * Registered from `jobs::throttle` with `register_android_plugin`.
+ */
+@TauriPlugin
+class DeviceStatePlugin(private val activity: Activity) : Plugin(activity) {
+
+    @Command
+    fun powerState(invoke: Invoke) {
+        // Sticky broadcast: reading it doesn't register a receiver
+        val battery = activity.registerReceiver(null, IntentFilter(Intent.ACTION_BATTERY_CHANGED))
+        val level = battery?.getIntExtra(BatteryManager.EXTRA_LEVEL, -1) ?: -1
+        val scale = battery?.getIntExtra(BatteryManager.EXTRA_SCALE, -1) ?: -1
+        val status = battery?.getIntExtra(BatteryManager.EXTRA_STATUS, -1) ?: -1
+        val charging = status == BatteryManager.BATTERY_STATUS_CHARGING ||
+            status == BatteryManager.BATTERY_STATUS_FULL
+
+        val connectivity = activity.getSystemService(ConnectivityManager::class.java)
+        val power = activity.getSystemService(Context.POWER_SERVICE) as PowerManager
+
+        val result = JSObject()
+        if (level >= 0 && scale > 0) {
+            result.put("batteryPercent", level * 100 / scale)
+        }
+        result.put("charging", charging)
+        result.put("metered", connectivity?.isActiveNetworkMetered ?: false)
+        result.put("powerSave", power.isPowerSaveMode)
+        invoke.resolve(result)
+    }
+}
@@ -0,0 +1,187 @@
+package com.karelian.aventura
+
+import android.Manifest
+import android.annotation.SuppressLint
+import android.app.Activity
+import android.content.Context
+import android.content.pm.PackageManager
+import android.net.wifi.WifiManager
+import android.net.wifi.p2p.WifiP2pManager
+import android.os.Build
+import android.os.Handler
+import android.os.Looper
+import androidx.core.app.ActivityCompat
+import androidx.core.content.ContextCompat
+import app.tauri.annotation.Command
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.net.Inet4Address
+import java.net.NetworkInterface
+
+/**
+ * Creates a network of the phone's own so another device can reach the
+ * sync server without a shared router: a WiFi Direct group where the
+ * hardware supports it, otherwise a local-only hotspot. Either way the
+ * other device joins it like any WiFi network and the normal HTTP sync
+ * runs over it. Registered from `sync::direct`.
+ */
+@SuppressLint("MissingPermission") // checked by ensurePermissions()
+@TauriPlugin
+class DirectLinkPlugin(private val activity: Activity) : Plugin(activity) {
+
+    companion object {
+        private const val PERMISSION_REQUEST = 4208
+        /** Android always gives the WiFi Direct group owner this address */
+        private const val GROUP_OWNER_ADDRESS = "192.168.49.1"
+        private const val GROUP_INFO_ATTEMPTS = 10
+    }
+
+    private val main = Handler(Looper.getMainLooper())
+    private val p2p = activity.getSystemService(Context.WIFI_P2P_SERVICE) as? WifiP2pManager
+    private val wifi = activity.applicationContext.getSystemService(Context.WIFI_SERVICE) as WifiManager
+    private var channel: WifiP2pManager.Channel? = null
+    private var hotspot: WifiManager.LocalOnlyHotspotReservation? = null
+    private var current: JSObject? = null
+
+    private fun ensurePermissions(invoke: Invoke): Boolean {
+        val permission = if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.TIRAMISU) {
+            Manifest.permission.NEARBY_WIFI_DEVICES
+        } else {
+            Manifest.permission.ACCESS_FINE_LOCATION
+        }
+        if (ContextCompat.checkSelfPermission(activity, permission) != PackageManager.PERMISSION_GRANTED) {
+            ActivityCompat.requestPermissions(activity, arrayOf(permission), PERMISSION_REQUEST)
+            invoke.reject("Allow access to nearby WiFi devices for Aventura, then try again")
+            return false
+        }
+        return true
+    }
+
+    private fun link(mode: String, ssid: String?, passphrase: String?, address: String?): JSObject {
+        val result = JSObject()
+        result.put("mode", mode)
+        result.put("ssid", ssid)
+        result.put("passphrase", passphrase)
+        result.put("ownerAddress", address)
+        return result
+    }
+
+    private fun ipv4Of(iface: NetworkInterface?): String? =
+        iface?.inetAddresses?.toList()?.firstOrNull { it is Inet4Address }?.hostAddress
+
+    /** The hotspot's interface isn't reported, so look for a likely one */
+    private fun hotspotAddress(): String? =
+        NetworkInterface.getNetworkInterfaces()?.toList()
+            ?.filter { it.isUp && !it.isLoopback }
+            ?.firstOrNull { iface ->
+                listOf("ap", "swlan", "softap", "wlan1").any { iface.name.startsWith(it) }
+            }
+            ?.let { ipv4Of(it) }
+
+    private fun stopAll() {
+        val manager = p2p
+        val ch = channel
+        if (manager != null && ch != null) {
+            manager.removeGroup(ch, null)
+        }
+        channel = null
+        hotspot?.close()
+        hotspot = null
+        current = null
+    }
+
+    private fun started(invoke: Invoke, result: JSObject) {
+        current = result
+        invoke.resolve(result)
+    }
+
+    @Command
+    fun start(invoke: Invoke) {
+        if (!ensurePermissions(invoke)) return
+        main.post {
+            stopAll()
+            val manager = p2p
+            if (manager == null || !activity.packageManager.hasSystemFeature(PackageManager.FEATURE_WIFI_DIRECT)) {
+                startHotspot(invoke)
+                return@post
+            }
+            val ch = manager.initialize(activity, Looper.getMainLooper(), null)
+            channel = ch
+            manager.createGroup(ch, object : WifiP2pManager.ActionListener {
+                override fun onSuccess() {
+                    awaitGroup(invoke, manager, ch, GROUP_INFO_ATTEMPTS)
+                }
+
+                override fun onFailure(reason: Int) {
+                    channel = null
+                    startHotspot(invoke)
+                }
+            })
+        }
+    }
+
+    /** The group's credentials show up shortly after it's created */
+    private fun awaitGroup(invoke: Invoke, manager: WifiP2pManager, ch: WifiP2pManager.Channel, attempts: Int) {
+        manager.requestGroupInfo(ch) { group ->
+            if (group?.passphrase != null) {
+                val address = ipv4Of(NetworkInterface.getByName(group.`interface`)) ?: GROUP_OWNER_ADDRESS
+                started(invoke, link("wifiDirect", group.networkName, group.passphrase, address))
+            } else if (attempts > 0) {
+                main.postDelayed({ awaitGroup(invoke, manager, ch, attempts - 1) }, 500)
+            } else {
+                stopAll()
+                startHotspot(invoke)
+            }
+        }
+    }
+
+    @Suppress("DEPRECATION")
+    private fun startHotspot(invoke: Invoke) {
+        if (Build.VERSION.SDK_INT < Build.VERSION_CODES.O) {
+            invoke.reject("This device can't create a direct connection")
+            return
+        }
+        wifi.startLocalOnlyHotspot(object : WifiManager.LocalOnlyHotspotCallback() {
+            override fun onStarted(reservation: WifiManager.LocalOnlyHotspotReservation) {
+                hotspot = reservation
+                val (ssid, passphrase) = if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.R) {
+                    val config = reservation.softApConfiguration
+                    config.ssid to config.passphrase
+                } else {
+                    val config = reservation.wifiConfiguration
+                    config?.SSID to config?.preSharedKey
+                }
+                val address = hotspotAddress()
+                if (address == null) {
+                    stopAll()
+                    invoke.reject("Couldn't find this device's address on the hotspot")
+                    return
+                }
+                started(invoke, link("hotspot", ssid, passphrase, address))
+            }
+
+            override fun onFailed(reason: Int) {
+                invoke.reject("Failed to start a direct connection (hotspot error $reason). Turn off mobile hotspot and try again.")
+            }
+        }, main)
+    }
+
+    @Command
+    fun stop(invoke: Invoke) {
+        main.post {
+            stopAll()
+            invoke.resolve()
+        }
+    }
+
+    @Command
+    fun status(invoke: Invoke) {
+        main.post {
+            val result = JSObject()
+            result.put("link", current)
+            invoke.resolve(result)
+        }
+    }
+}
@@ -0,0 +1,586 @@
+package com.karelian.aventura
+
+import android.Manifest
+import android.annotation.SuppressLint
+import android.app.Activity
+import android.bluetooth.BluetoothDevice
+import android.bluetooth.BluetoothGatt
+import android.bluetooth.BluetoothGattCallback
+import android.bluetooth.BluetoothGattCharacteristic
+import android.bluetooth.BluetoothGattDescriptor
+import android.bluetooth.BluetoothGattServer
+import android.bluetooth.BluetoothGattServerCallback
+import android.bluetooth.BluetoothGattService
+import android.bluetooth.BluetoothManager
+import android.bluetooth.BluetoothProfile
+import android.bluetooth.le.AdvertiseCallback
+import android.bluetooth.le.AdvertiseData
+import android.bluetooth.le.AdvertiseSettings
+import android.bluetooth.le.ScanCallback
+import android.bluetooth.le.ScanFilter
+import android.bluetooth.le.ScanResult
+import android.bluetooth.le.ScanSettings
+import android.content.Context
+import android.content.pm.PackageManager
+import android.os.Build
+import android.os.Handler
+import android.os.Looper
+import android.os.ParcelUuid
+import android.util.Base64
+import androidx.core.app.ActivityCompat
+import androidx.core.content.ContextCompat
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSArray
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.io.ByteArrayOutputStream
+import java.nio.ByteBuffer
+import java.util.ArrayDeque
+import java.util.UUID
+
+@InvokeArg
+class BleAdvertiseArgs {
+    var pairing: String? = null
+}
+
+@InvokeArg
+class BleScanArgs {
+    var seconds: Int = 8
+}
+
+@InvokeArg
+class BleAddressArgs {
+    var address: String? = null
+}
+
+@InvokeArg
+class BleRequestArgs {
+    var address: String? = null
+    var payload: String? = null
+}
+
+@InvokeArg
+class BleRespondArgs {
+    var id: Int = 0
+    var payload: String? = null
+}
+
+/**
+ * Bluetooth LE link for syncing without a shared WiFi network.
+ *
+ * The advertising side runs a GATT server with three characteristics:
+ * pairing data (read), requests (write) and responses (notify). Messages
+ * are a 4-byte big-endian length followed by the payload, split to fit the
+ * MTU. Requests are handed to Rust through `nextRequest` and answered with
+ * `respond`; the protocol itself stays in `sync::core`.
+ *
+ * The scanning side opens one GATT connection per call, which is slow but
+ * keeps every exchange independent. Registered from `sync::ble`.
+ */
+@SuppressLint("MissingPermission") // checked by ensurePermissions()
+@TauriPlugin
+class SyncBlePlugin(private val activity: Activity) : Plugin(activity) {
+
+    companion object {
+        val SERVICE_UUID: UUID = UUID.fromString("7a1c0001-5d3e-4b8f-9c2a-a7e0b7a00001")
+        val PAIRING_UUID: UUID = UUID.fromString("7a1c0002-5d3e-4b8f-9c2a-a7e0b7a00001")
+        val REQUEST_UUID: UUID = UUID.fromString("7a1c0003-5d3e-4b8f-9c2a-a7e0b7a00001")
+        val RESPONSE_UUID: UUID = UUID.fromString("7a1c0004-5d3e-4b8f-9c2a-a7e0b7a00001")
+        val CCCD_UUID: UUID = UUID.fromString("00002902-0000-1000-8000-00805f9b34fb")
+        private const val PERMISSION_REQUEST = 4207
+        private const val REQUEST_TIMEOUT_MS = 60_000L
+    }
+
+    private val main = Handler(Looper.getMainLooper())
+    private val bluetooth: BluetoothManager? =
+        activity.getSystemService(Context.BLUETOOTH_SERVICE) as? BluetoothManager
+
+    // -- Permissions -----------------------------------------------------------
+
+    private fun requiredPermissions(): Array<String> =
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.S) {
+            arrayOf(
+                Manifest.permission.BLUETOOTH_SCAN,
+                Manifest.permission.BLUETOOTH_CONNECT,
+                Manifest.permission.BLUETOOTH_ADVERTISE
+            )
+        } else {
+            arrayOf(Manifest.permission.ACCESS_FINE_LOCATION)
+        }
+
+    /** Rejects `invoke` and asks for whatever is missing */
+    private fun ensurePermissions(invoke: Invoke): Boolean {
+        val missing = requiredPermissions().filter {
+            ContextCompat.checkSelfPermission(activity, it) != PackageManager.PERMISSION_GRANTED
+        }
+        if (missing.isNotEmpty()) {
+            ActivityCompat.requestPermissions(activity, missing.toTypedArray(), PERMISSION_REQUEST)
+            invoke.reject("Allow Bluetooth access for Aventura, then try again")
+            return false
+        }
+        if (bluetooth?.adapter?.isEnabled != true) {
+            invoke.reject("Turn on Bluetooth to sync without WiFi")
+            return false
+        }
+        return true
+    }
+
+    // -- Framing ---------------------------------------------------------------
+
+    private fun frame(payload: ByteArray): ByteArray =
+        ByteBuffer.allocate(4 + payload.size).putInt(payload.size).put(payload).array()
+
+    private fun chunks(data: ByteArray, size: Int): ArrayDeque<ByteArray> {
+        val queue = ArrayDeque<ByteArray>()
+        var offset = 0
+        while (offset < data.size) {
+            val end = minOf(offset + size, data.size)
+            queue.add(data.copyOfRange(offset, end))
+            offset = end
+        }
+        return queue
+    }
+
+    /** Collects chunks until a whole framed message has arrived */
+    private class Assembler {
+        private val buffer = ByteArrayOutputStream()
+
+        fun add(chunk: ByteArray): ByteArray? {
+            buffer.write(chunk)
+            val bytes = buffer.toByteArray()
+            if (bytes.size < 4) return null
+            val length = ByteBuffer.wrap(bytes, 0, 4).int
+            if (bytes.size - 4 < length) return null
+            buffer.reset()
+            return bytes.copyOfRange(4, 4 + length)
+        }
+    }
+
+    // -- Advertising side ------------------------------------------------------
+
+    private class Incoming(val id: Int, val device: BluetoothDevice, val payload: ByteArray)
+
+    private var server: BluetoothGattServer? = null
+    private var pairing: ByteArray = ByteArray(0)
+    private var nextId = 1
+    private val assemblers = mutableMapOf<String, Assembler>()
+    private val mtus = mutableMapOf<String, Int>()
+    private val queue = ArrayDeque<Incoming>()
+    private val waiting = mutableMapOf<Int, BluetoothDevice>()
+    private val outgoing = mutableMapOf<String, ArrayDeque<ByteArray>>()
+    private var pendingNext: Invoke? = null
+
+    private val advertiseCallback = object : AdvertiseCallback() {
+        override fun onStartFailure(errorCode: Int) {
+            stopServer()
+        }
+    }
+
+    private val serverCallback = object : BluetoothGattServerCallback() {
+        override fun onConnectionStateChange(device: BluetoothDevice, status: Int, newState: Int) {
+            if (newState == BluetoothProfile.STATE_DISCONNECTED) {
+                main.post {
+                    assemblers.remove(device.address)
+                    outgoing.remove(device.address)
+                    mtus.remove(device.address)
+                }
+            }
+        }
+
+        override fun onMtuChanged(device: BluetoothDevice, mtu: Int) {
+            main.post { mtus[device.address] = mtu }
+        }
+
+        override fun onCharacteristicReadRequest(
+            device: BluetoothDevice, requestId: Int, offset: Int,
+            characteristic: BluetoothGattCharacteristic
+        ) {
+            // Long reads arrive in pieces; the client stitches them together
+            val value = if (characteristic.uuid == PAIRING_UUID && offset <= pairing.size) {
+                pairing.copyOfRange(offset, pairing.size)
+            } else {
+                ByteArray(0)
+            }
+            server?.sendResponse(device, requestId, BluetoothGatt.GATT_SUCCESS, offset, value)
+        }
+
+        override fun onCharacteristicWriteRequest(
+            device: BluetoothDevice, requestId: Int, characteristic: BluetoothGattCharacteristic,
+            preparedWrite: Boolean, responseNeeded: Boolean, offset: Int, value: ByteArray
+        ) {
+            if (responseNeeded) {
+                server?.sendResponse(device, requestId, BluetoothGatt.GATT_SUCCESS, offset, null)
+            }
+            if (characteristic.uuid != REQUEST_UUID) return
+            main.post {
+                val assembler = assemblers.getOrPut(device.address) { Assembler() }
+                val message = assembler.add(value) ?: return@post
+                queue.add(Incoming(nextId++, device, message))
+                deliverNext()
+            }
+        }
+
+        override fun onDescriptorWriteRequest(
+            device: BluetoothDevice, requestId: Int, descriptor: BluetoothGattDescriptor,
+            preparedWrite: Boolean, responseNeeded: Boolean, offset: Int, value: ByteArray
+        ) {
+            if (responseNeeded) {
+                server?.sendResponse(device, requestId, BluetoothGatt.GATT_SUCCESS, offset, null)
+            }
+        }
+
+        override fun onNotificationSent(device: BluetoothDevice, status: Int) {
+            main.post { sendNextChunk(device) }
+        }
+    }
+
+    private fun deliverNext() {
+        val invoke = pendingNext ?: return
+        val incoming = queue.poll() ?: return
+        pendingNext = null
+        waiting[incoming.id] = incoming.device
+        val result = JSObject()
+        result.put("id", incoming.id)
+        result.put("payload", Base64.encodeToString(incoming.payload, Base64.NO_WRAP))
+        invoke.resolve(result)
+    }
+
+    @Suppress("DEPRECATION")
+    private fun sendNextChunk(device: BluetoothDevice) {
+        val pending = outgoing[device.address] ?: return
+        val chunk = pending.poll()
+        if (chunk == null) {
+            outgoing.remove(device.address)
+            return
+        }
+        val characteristic = server?.getService(SERVICE_UUID)?.getCharacteristic(RESPONSE_UUID) ?: return
+        characteristic.value = chunk
+        server?.notifyCharacteristicChanged(device, characteristic, false)
+    }
+
+    private fun stopServer() {
+        bluetooth?.adapter?.bluetoothLeAdvertiser?.stopAdvertising(advertiseCallback)
+        server?.close()
+        server = null
+        queue.clear()
+        waiting.clear()
+        outgoing.clear()
+        assemblers.clear()
+        pendingNext?.let {
+            val result = JSObject()
+            result.put("stopped", true)
+            it.resolve(result)
+        }
+        pendingNext = null
+    }
+
+    @Command
+    fun startAdvertising(invoke: Invoke) {
+        if (!ensurePermissions(invoke)) return
+        val args = invoke.parseArgs(BleAdvertiseArgs::class.java)
+        val advertiser = bluetooth?.adapter?.bluetoothLeAdvertiser
+        if (advertiser == null) {
+            invoke.reject("This device can't advertise over Bluetooth LE")
+            return
+        }
+        main.post {
+            stopServer()
+            pairing = (args.pairing ?: "").toByteArray(Charsets.UTF_8)
+
+            val service = BluetoothGattService(SERVICE_UUID, BluetoothGattService.SERVICE_TYPE_PRIMARY)
+            service.addCharacteristic(BluetoothGattCharacteristic(
+                PAIRING_UUID,
+                BluetoothGattCharacteristic.PROPERTY_READ,
+                BluetoothGattCharacteristic.PERMISSION_READ
+            ))
+            service.addCharacteristic(BluetoothGattCharacteristic(
+                REQUEST_UUID,
+                BluetoothGattCharacteristic.PROPERTY_WRITE,
+                BluetoothGattCharacteristic.PERMISSION_WRITE
+            ))
+            val response = BluetoothGattCharacteristic(
+                RESPONSE_UUID,
+                BluetoothGattCharacteristic.PROPERTY_NOTIFY,
+                BluetoothGattCharacteristic.PERMISSION_READ
+            )
+            response.addDescriptor(BluetoothGattDescriptor(
+                CCCD_UUID,
+                BluetoothGattDescriptor.PERMISSION_READ or BluetoothGattDescriptor.PERMISSION_WRITE
+            ))
+            service.addCharacteristic(response)
+
+            server = bluetooth?.openGattServer(activity, serverCallback)?.also { it.addService(service) }
+            if (server == null) {
+                invoke.reject("Failed to open the Bluetooth GATT server")
+                return@post
+            }
+
+            val settings = AdvertiseSettings.Builder()
+                .setAdvertiseMode(AdvertiseSettings.ADVERTISE_MODE_LOW_LATENCY)
+                .setConnectable(true)
+                .build()
+            val data = AdvertiseData.Builder()
+                .addServiceUuid(ParcelUuid(SERVICE_UUID))
+                .setIncludeDeviceName(false)
+                .build()
+            val scanResponse = AdvertiseData.Builder().setIncludeDeviceName(true).build()
+            advertiser.startAdvertising(settings, data, scanResponse, advertiseCallback)
+            invoke.resolve()
+        }
+    }
+
+    @Command
+    fun stopAdvertising(invoke: Invoke) {
+        main.post {
+            stopServer()
+            invoke.resolve()
+        }
+    }
+
+    @Command
+    fun status(invoke: Invoke) {
+        main.post {
+            val result = JSObject()
+            result.put("advertising", server != null)
+            invoke.resolve(result)
+        }
+    }
+
+    /** Resolves with the next request, or `{ stopped: true }` once advertising ends */
+    @Command
+    fun nextRequest(invoke: Invoke) {
+        main.post {
+            if (server == null) {
+                val result = JSObject()
+                result.put("stopped", true)
+                invoke.resolve(result)
+                return@post
+            }
+            pendingNext?.reject("Superseded by a newer nextRequest")
+            pendingNext = invoke
+            deliverNext()
+        }
+    }
+
+    @Command
+    fun respond(invoke: Invoke) {
+        val args = invoke.parseArgs(BleRespondArgs::class.java)
+        main.post {
+            val device = waiting.remove(args.id)
+            if (device == null) {
+                // The client disconnected while Rust was answering
+                invoke.resolve()
+                return@post
+            }
+            val payload = Base64.decode(args.payload ?: "", Base64.NO_WRAP)
+            val size = (mtus[device.address] ?: 23) - 3
+            outgoing[device.address] = chunks(frame(payload), size)
+            sendNextChunk(device)
+            invoke.resolve()
+        }
+    }
+
+    // -- Scanning side ---------------------------------------------------------
+
+    @Command
+    fun scan(invoke: Invoke) {
+        if (!ensurePermissions(invoke)) return
+        val args = invoke.parseArgs(BleScanArgs::class.java)
+        val scanner = bluetooth?.adapter?.bluetoothLeScanner
+        if (scanner == null) {
+            invoke.reject("This device can't scan for Bluetooth LE devices")
+            return
+        }
+        val found = linkedMapOf<String, JSObject>()
+        val callback = object : ScanCallback() {
+            override fun onScanResult(callbackType: Int, result: ScanResult) {
+                val device = JSObject()
+                device.put("address", result.device.address)
+                device.put("name", result.scanRecord?.deviceName ?: result.device.name)
+                device.put("rssi", result.rssi)
+                found[result.device.address] = device
+            }
+        }
+        val filters = listOf(ScanFilter.Builder().setServiceUuid(ParcelUuid(SERVICE_UUID)).build())
+        val settings = ScanSettings.Builder().setScanMode(ScanSettings.SCAN_MODE_LOW_LATENCY).build()
+        scanner.startScan(filters, settings, callback)
+        main.postDelayed({
+            scanner.stopScan(callback)
+            val devices = JSArray()
+            found.values.forEach { devices.put(it) }
+            val result = JSObject()
+            result.put("devices", devices)
+            invoke.resolve(result)
+        }, args.seconds.coerceIn(1, 30) * 1000L)
+    }
+
+    /**
+     * Connects to `address`, runs `exchange` once the response notifications
+     * are enabled, and always disconnects afterwards.
+     */
+    private abstract inner class Exchange(private val invoke: Invoke) : BluetoothGattCallback() {
+        protected var gatt: BluetoothGatt? = null
+        protected var mtu = 23
+        private var finished = false
+        private val timeout = Runnable { fail("The other device stopped responding") }
+
+        fun start(address: String) {
+            val device = try {
+                bluetooth?.adapter?.getRemoteDevice(address)
+            } catch (e: IllegalArgumentException) {
+                null
+            }
+            if (device == null) {
+                invoke.reject("Unknown Bluetooth address: $address")
+                return
+            }
+            main.postDelayed(timeout, REQUEST_TIMEOUT_MS)
+            gatt = device.connectGatt(activity, false, this, BluetoothDevice.TRANSPORT_LE)
+        }
+
+        abstract fun ready(gatt: BluetoothGatt, service: BluetoothGattService)
+
+        protected fun succeed(result: JSObject) = main.post {
+            if (finished) return@post
+            finished = true
+            main.removeCallbacks(timeout)
+            gatt?.disconnect()
+            gatt?.close()
+            invoke.resolve(result)
+        }
+
+        protected fun fail(message: String) = main.post {
+            if (finished) return@post
+            finished = true
+            main.removeCallbacks(timeout)
+            gatt?.disconnect()
+            gatt?.close()
+            invoke.reject(message)
+        }
+
+        override fun onConnectionStateChange(gatt: BluetoothGatt, status: Int, newState: Int) {
+            when (newState) {
+                BluetoothProfile.STATE_CONNECTED -> gatt.requestMtu(517)
+                BluetoothProfile.STATE_DISCONNECTED -> fail("Bluetooth connection lost")
+            }
+        }
+
+        override fun onMtuChanged(gatt: BluetoothGatt, mtu: Int, status: Int) {
+            if (status == BluetoothGatt.GATT_SUCCESS) this.mtu = mtu
+            gatt.discoverServices()
+        }
+
+        override fun onServicesDiscovered(gatt: BluetoothGatt, status: Int) {
+            val service = gatt.getService(SERVICE_UUID)
+            if (service == null) {
+                fail("That device isn't sharing Aventura stories")
+                return
+            }
+            ready(gatt, service)
+        }
+    }
+
+    @Command
+    fun readPairing(invoke: Invoke) {
+        if (!ensurePermissions(invoke)) return
+        val args = invoke.parseArgs(BleAddressArgs::class.java)
+        object : Exchange(invoke) {
+            override fun ready(gatt: BluetoothGatt, service: BluetoothGattService) {
+                gatt.readCharacteristic(service.getCharacteristic(PAIRING_UUID))
+            }
+
+            @Deprecated("Deprecated in API 33")
+            override fun onCharacteristicRead(
+                gatt: BluetoothGatt, characteristic: BluetoothGattCharacteristic, status: Int
+            ) {
+                @Suppress("DEPRECATION")
+                read(characteristic.value ?: ByteArray(0), status)
+            }
+
+            override fun onCharacteristicRead(
+                gatt: BluetoothGatt, characteristic: BluetoothGattCharacteristic,
+                value: ByteArray, status: Int
+            ) {
+                read(value, status)
+            }
+
+            private fun read(value: ByteArray, status: Int) {
+                if (status != BluetoothGatt.GATT_SUCCESS) {
+                    fail("Failed to read pairing data over Bluetooth")
+                    return
+                }
+                val result = JSObject()
+                result.put("pairing", String(value, Charsets.UTF_8))
+                succeed(result)
+            }
+        }.start(args.address ?: "")
+    }
+
+    @Command
+    fun request(invoke: Invoke) {
+        if (!ensurePermissions(invoke)) return
+        val args = invoke.parseArgs(BleRequestArgs::class.java)
+        val payload = Base64.decode(args.payload ?: "", Base64.NO_WRAP)
+        object : Exchange(invoke) {
+            private val assembler = Assembler()
+            private var pending = ArrayDeque<ByteArray>()
+            private var requestCharacteristic: BluetoothGattCharacteristic? = null
+
+            @Suppress("DEPRECATION")
+            override fun ready(gatt: BluetoothGatt, service: BluetoothGattService) {
+                requestCharacteristic = service.getCharacteristic(REQUEST_UUID)
+                val response = service.getCharacteristic(RESPONSE_UUID)
+                gatt.setCharacteristicNotification(response, true)
+                val cccd = response.getDescriptor(CCCD_UUID)
+                cccd.value = BluetoothGattDescriptor.ENABLE_NOTIFICATION_VALUE
+                gatt.writeDescriptor(cccd)
+            }
+
+            override fun onDescriptorWrite(gatt: BluetoothGatt, descriptor: BluetoothGattDescriptor, status: Int) {
+                pending = chunks(frame(payload), mtu - 3)
+                writeNext(gatt)
+            }
+
+            @Suppress("DEPRECATION")
+            private fun writeNext(gatt: BluetoothGatt) {
+                val chunk = pending.poll() ?: return
+                val characteristic = requestCharacteristic ?: return
+                characteristic.writeType = BluetoothGattCharacteristic.WRITE_TYPE_DEFAULT
+                characteristic.value = chunk
+                gatt.writeCharacteristic(characteristic)
+            }
+
+            override fun onCharacteristicWrite(
+                gatt: BluetoothGatt, characteristic: BluetoothGattCharacteristic, status: Int
+            ) {
+                if (status != BluetoothGatt.GATT_SUCCESS) {
+                    fail("Failed to send over Bluetooth")
+                    return
+                }
+                writeNext(gatt)
+            }
+
+            @Deprecated("Deprecated in API 33")
+            override fun onCharacteristicChanged(gatt: BluetoothGatt, characteristic: BluetoothGattCharacteristic) {
+                @Suppress("DEPRECATION")
+                received(characteristic.value ?: ByteArray(0))
+            }
+
+            override fun onCharacteristicChanged(
+                gatt: BluetoothGatt, characteristic: BluetoothGattCharacteristic, value: ByteArray
+            ) {
+                received(value)
+            }
+
+            private fun received(chunk: ByteArray) {
+                val message = assembler.add(chunk) ?: return
+                val result = JSObject()
+                result.put("payload", Base64.encodeToString(message, Base64.NO_WRAP))
+                succeed(result)
+            }
+        }.start(args.address ?: "")
+    }
+}
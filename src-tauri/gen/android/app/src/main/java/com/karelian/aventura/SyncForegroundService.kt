@@ -0,0 +1,129 @@
+package com.karelian.aventura
+
+import android.app.Notification
+import android.app.NotificationChannel
+import android.app.NotificationManager
+import android.app.PendingIntent
+import android.app.Service
+import android.content.Context
+import android.content.Intent
+import android.net.wifi.WifiManager
+import android.os.Build
+import android.os.IBinder
+import android.os.PowerManager
+import androidx.core.app.NotificationCompat
+
+/**
+ * A foreground service that keeps the process, CPU and WiFi awake while
+ * the local sync server is running, so a PC can pull or push stories with
+ * the app in the background or the screen off.
+ *
+ * Started and stopped from Rust through [SyncServicePlugin]. The server
+ * itself runs in the Rust process; this service only keeps Android from
+ * suspending or killing it.
+ */
+class SyncForegroundService : Service() {
+
+    companion object {
+        const val CHANNEL_ID = "sync_channel"
+        const val NOTIFICATION_ID = 1002
+        const val EXTRA_ADDRESS = "address"
+        private const val LOCK_TAG = "aventura:sync"
+
+        /** Whether the service is in the foreground; read by [SyncServicePlugin] */
+        @Volatile
+        var running = false
+            private set
+    }
+
+    private var wakeLock: PowerManager.WakeLock? = null
+    private var wifiLock: WifiManager.WifiLock? = null
+
+    override fun onCreate() {
+        super.onCreate()
+        createNotificationChannel()
+    }
+
+    override fun onStartCommand(intent: Intent?, flags: Int, startId: Int): Int {
+        val address = intent?.getStringExtra(EXTRA_ADDRESS)
+        startForeground(NOTIFICATION_ID, buildNotification(address))
+        acquireLocks()
+        running = true
+        // The server doesn't survive the process, so don't come back without it
+        return START_NOT_STICKY
+    }
+
+    override fun onDestroy() {
+        running = false
+        releaseLocks()
+        super.onDestroy()
+    }
+
+    override fun onBind(intent: Intent?): IBinder? = null
+
+    // -- Notification ----------------------------------------------------------
+
+    private fun createNotificationChannel() {
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.O) {
+            val channel = NotificationChannel(
+                CHANNEL_ID,
+                "Story Sync",
+                NotificationManager.IMPORTANCE_LOW
+            ).apply {
+                description = "Shown while the sync server accepts connections in the background"
+                setShowBadge(false)
+            }
+            val manager = getSystemService(NotificationManager::class.java)
+            manager.createNotificationChannel(channel)
+        }
+    }
+
+    private fun buildNotification(address: String?): Notification {
+        val tapIntent = Intent(this, MainActivity::class.java).apply {
+            flags = Intent.FLAG_ACTIVITY_SINGLE_TOP or Intent.FLAG_ACTIVITY_CLEAR_TOP
+        }
+        val pendingIntent = PendingIntent.getActivity(
+            this, 0, tapIntent,
+            PendingIntent.FLAG_UPDATE_CURRENT or PendingIntent.FLAG_IMMUTABLE
+        )
+        val text = if (address != null) "Listening on $address. Tap to return."
+            else "Waiting for another device. Tap to return."
+
+        return NotificationCompat.Builder(this, CHANNEL_ID)
+            .setContentTitle("Sync server running")
+            .setContentText(text)
+            .setSmallIcon(R.mipmap.ic_launcher)
+            .setOngoing(true)
+            .setOnlyAlertOnce(true)
+            .setContentIntent(pendingIntent)
+            .setForegroundServiceBehavior(NotificationCompat.FOREGROUND_SERVICE_IMMEDIATE)
+            .build()
+    }
+
+    // -- Wake and WiFi locks ---------------------------------------------------
+
+    private fun acquireLocks() {
+        if (wakeLock == null) {
+            val pm = getSystemService(Context.POWER_SERVICE) as PowerManager
+            wakeLock = pm.newWakeLock(PowerManager.PARTIAL_WAKE_LOCK, LOCK_TAG).apply {
+                setReferenceCounted(false)
+                acquire()
+            }
+        }
+        if (wifiLock == null) {
+            val wm = applicationContext.getSystemService(Context.WIFI_SERVICE) as WifiManager
+            @Suppress("DEPRECATION")
+            wifiLock = wm.createWifiLock(WifiManager.WIFI_MODE_FULL_HIGH_PERF, LOCK_TAG).apply {
+                setReferenceCounted(false)
+                acquire()
+            }
+        }
+    }
+
+    private fun releaseLocks() {
+        wakeLock?.let { if (it.isHeld) it.release() }
+        wakeLock = null
+        wifiLock?.let { if (it.isHeld) it.release() }
+        wifiLock = null
+    }
+}
@@ -0,0 +1,51 @@
+package com.karelian.aventura
+
+import android.app.Activity
+import android.content.Intent
+import androidx.core.content.ContextCompat
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+@InvokeArg
+class StartSyncServiceArgs {
+    var address: String? = null
+}
+
+/**
+ * Lets the Rust sync commands start, stop and query [SyncForegroundService].
+ * Registered from `sync::service` with `register_android_plugin`.
+ */
+@TauriPlugin
+class SyncServicePlugin(private val activity: Activity) : Plugin(activity) {
+
+    private fun currentStatus(): JSObject {
+        val result = JSObject()
+        result.put("running", SyncForegroundService.running)
+        return result
+    }
+
+    @Command
+    fun start(invoke: Invoke) {
+        val args = invoke.parseArgs(StartSyncServiceArgs::class.java)
+        val intent = Intent(activity, SyncForegroundService::class.java).apply {
+            putExtra(SyncForegroundService.EXTRA_ADDRESS, args.address)
+        }
+        ContextCompat.startForegroundService(activity, intent)
+        invoke.resolve()
+    }
+
+    @Command
+    fun stop(invoke: Invoke) {
+        activity.stopService(Intent(activity, SyncForegroundService::class.java))
+        invoke.resolve()
+    }
+
+    @Command
+    fun status(invoke: Invoke) {
+        invoke.resolve(currentStatus())
+    }
+}
@@ -0,0 +1,4 @@
+-- Scenario templating: variable declarations and random tables for vault scenarios
+-- JSON: { variables: [{ name, label, required, defaultValue }], tables: { name: [options] } }
+-- Text fields may reference {{variable}}, {{random:table}}, and {{#if variable}}...{{/if}} blocks
+ALTER TABLE scenario_vault ADD COLUMN template TEXT;
@@ -0,0 +1,18 @@
+-- Persistent queue for Rust-side background jobs (extraction, updates, etc.)
+-- Jobs survive restarts: rows left 'running' are re-queued on startup
+CREATE TABLE IF NOT EXISTS background_jobs (
+    id TEXT PRIMARY KEY,
+    story_id TEXT,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL DEFAULT '{}',   -- JSON, kind-specific
+    status TEXT NOT NULL DEFAULT 'pending', -- 'pending' | 'running' | 'completed' | 'failed'
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    result TEXT,                          -- JSON, kind-specific
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_background_jobs_status ON background_jobs(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_background_jobs_story ON background_jobs(story_id);
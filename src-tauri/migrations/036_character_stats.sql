@@ -0,0 +1,58 @@
+-- Character stat sheets: numeric attributes, conditions, and held items per character
+
+CREATE TABLE IF NOT EXISTS character_stats (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    character_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    value REAL NOT NULL DEFAULT 0,
+    min_value REAL,
+    max_value REAL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+    UNIQUE(character_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS character_conditions (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    character_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    description TEXT,
+    source TEXT NOT NULL DEFAULT 'manual', -- 'manual' | 'extraction' | 'rule'
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+    UNIQUE(character_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS inventory_items (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    character_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    quantity INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+    UNIQUE(character_id, name)
+);
+
+-- Threshold rules: while stat <comparison> threshold holds, condition_name is applied
+CREATE TABLE IF NOT EXISTS stat_rules (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    stat_name TEXT NOT NULL COLLATE NOCASE,
+    comparison TEXT NOT NULL CHECK(comparison IN ('<=', '<', '>=', '>', '=')),
+    threshold REAL NOT NULL,
+    condition_name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_character_stats_story ON character_stats(story_id);
+CREATE INDEX IF NOT EXISTS idx_character_conditions_story ON character_conditions(story_id);
+CREATE INDEX IF NOT EXISTS idx_inventory_items_story ON inventory_items(story_id);
+CREATE INDEX IF NOT EXISTS idx_stat_rules_story ON stat_rules(story_id);
@@ -0,0 +1,48 @@
+-- Quest tracker: quests with ordered objectives, built on story beats
+
+CREATE TABLE IF NOT EXISTS quests (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    beat_id TEXT, -- optional story beat this quest tracks
+    title TEXT NOT NULL,
+    description TEXT,
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'completed', 'failed', 'abandoned')),
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    resolved_at INTEGER,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (beat_id) REFERENCES story_beats(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_quests_story ON quests(story_id, status);
+
+CREATE TABLE IF NOT EXISTS quest_objectives (
+    id TEXT PRIMARY KEY,
+    quest_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'active', 'completed', 'failed')),
+    completed_at INTEGER,
+    FOREIGN KEY (quest_id) REFERENCES quests(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_quest_objectives_quest ON quest_objectives(quest_id, position);
+
+-- Status changes proposed by background analysis of new entries
+CREATE TABLE IF NOT EXISTS quest_suggestions (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    quest_id TEXT NOT NULL,
+    objective_id TEXT,
+    entry_id TEXT NOT NULL,
+    suggested_status TEXT NOT NULL,
+    confidence REAL NOT NULL DEFAULT 0,
+    reason TEXT,
+    status TEXT NOT NULL DEFAULT 'pending', -- 'pending' | 'accepted' | 'dismissed'
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (quest_id) REFERENCES quests(id) ON DELETE CASCADE,
+    FOREIGN KEY (objective_id) REFERENCES quest_objectives(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_quest_suggestions_story ON quest_suggestions(story_id, status);
@@ -0,0 +1,7 @@
+-- Custom in-story calendars (month names/lengths, weeks, moons, eras); stories without a row use the default calendar
+CREATE TABLE IF NOT EXISTS story_calendars (
+    story_id TEXT PRIMARY KEY,
+    definition TEXT NOT NULL, -- JSON CalendarDefinition
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
@@ -0,0 +1,27 @@
+-- Seeded weather simulation: regions with a climate, and one persisted weather row per region per story day
+
+CREATE TABLE IF NOT EXISTS environment_regions (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    climate TEXT NOT NULL DEFAULT 'temperate',
+    location_name TEXT COLLATE NOCASE, -- region is active while this location is current
+    is_default INTEGER NOT NULL DEFAULT 0,
+    seed INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_environment_regions_story ON environment_regions(story_id);
+
+CREATE TABLE IF NOT EXISTS environment_weather (
+    region_id TEXT NOT NULL,
+    day INTEGER NOT NULL, -- days since the story start
+    season TEXT NOT NULL,
+    condition TEXT NOT NULL,
+    temperature REAL NOT NULL, -- degrees Celsius
+    wind TEXT NOT NULL,
+    source TEXT NOT NULL DEFAULT 'generated', -- 'generated' | 'manual'
+    PRIMARY KEY (region_id, day),
+    FOREIGN KEY (region_id) REFERENCES environment_regions(id) ON DELETE CASCADE
+);
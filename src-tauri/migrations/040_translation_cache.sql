@@ -0,0 +1,8 @@
+-- Cache of translated text keyed by content hash and target language, shared across stories
+CREATE TABLE IF NOT EXISTS translation_cache (
+    content_hash TEXT NOT NULL, -- SHA-256 of the source text
+    language TEXT NOT NULL,
+    translated TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (content_hash, language)
+);
@@ -0,0 +1,12 @@
+-- Per-story translation glossary: fixed renderings for names and invented terms
+CREATE TABLE IF NOT EXISTS translation_glossary (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    language TEXT NOT NULL,
+    source_term TEXT NOT NULL COLLATE NOCASE,
+    target_term TEXT NOT NULL,
+    notes TEXT,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    UNIQUE(story_id, language, source_term)
+);
@@ -0,0 +1,19 @@
+-- Ordered regex find/replace rules applied to model output and, optionally, to prompts
+CREATE TABLE IF NOT EXISTS postprocess_rules (
+    id TEXT PRIMARY KEY,
+    story_id TEXT, -- NULL = global rule
+    name TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    replacement TEXT NOT NULL DEFAULT '',
+    case_insensitive INTEGER NOT NULL DEFAULT 0,
+    multiline INTEGER NOT NULL DEFAULT 0,
+    apply_to_output INTEGER NOT NULL DEFAULT 1,
+    apply_to_prompt INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_postprocess_rules_story ON postprocess_rules(story_id, position);
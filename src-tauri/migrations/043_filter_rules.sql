@@ -0,0 +1,30 @@
+-- Banned phrases / slop lists enforced on generated output
+CREATE TABLE IF NOT EXISTS filter_rules (
+    id TEXT PRIMARY KEY,
+    story_id TEXT, -- NULL = global rule
+    phrase TEXT NOT NULL,
+    is_regex INTEGER NOT NULL DEFAULT 0,
+    action TEXT NOT NULL DEFAULT 'flag' CHECK (action IN ('flag', 'substitute', 'regenerate')),
+    substitution TEXT NOT NULL DEFAULT '',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_filter_rules_story ON filter_rules(story_id);
+
+-- One row per match in a finished response, used for filter statistics
+CREATE TABLE IF NOT EXISTS filter_hits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id TEXT NOT NULL,
+    story_id TEXT NOT NULL,
+    entry_id TEXT,
+    matched_text TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (rule_id) REFERENCES filter_rules(id) ON DELETE CASCADE,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_filter_hits_rule ON filter_hits(rule_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_filter_hits_story ON filter_hits(story_id, created_at);
@@ -0,0 +1,18 @@
+-- Exact prompts of recent model requests, for "what got sent?" debugging.
+-- Only the most recent requests are kept (pruned on insert).
+CREATE TABLE IF NOT EXISTS llm_request_log (
+    id TEXT PRIMARY KEY,
+    story_id TEXT,
+    source TEXT NOT NULL,      -- 'narrative' or the service that made the call
+    model TEXT,
+    messages TEXT NOT NULL,    -- JSON array of {role, content}
+    sections TEXT NOT NULL,    -- JSON array of {name, tokens}
+    lore TEXT NOT NULL,        -- JSON array of lore entries that fired and why
+    truncation TEXT NOT NULL,  -- JSON array of items dropped or cut for budget
+    total_tokens INTEGER NOT NULL,
+    response TEXT,
+    error TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_request_log_created ON llm_request_log(created_at);
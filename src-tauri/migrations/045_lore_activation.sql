@@ -0,0 +1,11 @@
+-- Per-story activation timers for lorebook entries (sticky / cooldown)
+CREATE TABLE IF NOT EXISTS lore_activation_state (
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    last_activated_turn INTEGER NOT NULL,
+    sticky_until INTEGER NOT NULL DEFAULT 0,
+    cooldown_until INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (story_id, entry_id),
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
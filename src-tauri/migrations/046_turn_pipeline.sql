@@ -0,0 +1,26 @@
+-- Per-story order and toggles of the post-response pipeline
+-- Format: [{ "step": "postprocess", "enabled": true }, ...]; NULL uses the default pipeline
+ALTER TABLE stories ADD COLUMN turn_pipeline TEXT;
+
+-- Post-response pipeline runs, saved after every step so a run interrupted
+-- by an app restart resumes at the step it was on
+CREATE TABLE IF NOT EXISTS turn_pipeline_runs (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    steps TEXT NOT NULL,
+    next_step INTEGER NOT NULL DEFAULT 0,
+    -- 'running' | 'completed' | 'failed'
+    status TEXT NOT NULL DEFAULT 'running',
+    text TEXT NOT NULL,
+    allowed_types TEXT NOT NULL DEFAULT '[]',
+    options TEXT NOT NULL DEFAULT '[]',
+    jobs TEXT NOT NULL DEFAULT '[]',
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_turn_pipeline_runs_story ON turn_pipeline_runs(story_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_turn_pipeline_runs_status ON turn_pipeline_runs(status);
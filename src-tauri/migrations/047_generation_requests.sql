@@ -0,0 +1,18 @@
+-- In-flight narrative requests, so a response cut off by the app being
+-- killed can be continued or retried on the next launch
+CREATE TABLE IF NOT EXISTS generation_requests (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    service TEXT NOT NULL,
+    messages TEXT NOT NULL,
+    params TEXT NOT NULL DEFAULT '{}',
+    partial TEXT NOT NULL DEFAULT '',
+    -- 'streaming' | 'completed' | 'failed' | 'cancelled' | 'interrupted'
+    status TEXT NOT NULL DEFAULT 'streaming',
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_generation_requests_status ON generation_requests(status, story_id);
@@ -0,0 +1,23 @@
+-- Named provider/model/sampler combinations
+CREATE TABLE IF NOT EXISTS model_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    api_profile_id TEXT,
+    model TEXT NOT NULL DEFAULT '',
+    temperature REAL,
+    max_tokens INTEGER,
+    params TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Which model profile a story uses for each phase
+-- ('narration', 'worldUpdate', 'summaries', 'translate', 'images')
+CREATE TABLE IF NOT EXISTS story_model_bindings (
+    story_id TEXT NOT NULL,
+    phase TEXT NOT NULL,
+    profile_id TEXT NOT NULL,
+    PRIMARY KEY (story_id, phase),
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (profile_id) REFERENCES model_profiles(id) ON DELETE CASCADE
+);
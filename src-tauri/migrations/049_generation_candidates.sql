@@ -0,0 +1,4 @@
+-- Groups the parallel candidates of one turn until one is chosen
+ALTER TABLE generation_requests ADD COLUMN candidate_group TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_generation_requests_group ON generation_requests(candidate_group);
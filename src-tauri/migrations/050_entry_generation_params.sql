@@ -0,0 +1,3 @@
+-- Service and sampling parameters (including the seed) an entry was generated with
+-- Format: { "service": "narrative", "params": { "model": ..., "temperature": ..., "seed": ... } }
+ALTER TABLE story_entries ADD COLUMN generation_params TEXT;
@@ -0,0 +1,16 @@
+-- Every generated variant of a story entry ("swipes"); the active one
+-- mirrors the entry's content
+CREATE TABLE IF NOT EXISTS entry_alternatives (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    generation_params TEXT,
+    active INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (entry_id) REFERENCES story_entries(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_alternatives_entry ON entry_alternatives(entry_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_entry_alternatives_story ON entry_alternatives(story_id);
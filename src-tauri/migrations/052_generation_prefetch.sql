@@ -0,0 +1,2 @@
+-- Action a request was speculatively generated for, cleared once it is served
+ALTER TABLE generation_requests ADD COLUMN prefetch_action TEXT;
@@ -0,0 +1,15 @@
+-- Prompt-cache usage reported by providers, one row per request
+CREATE TABLE IF NOT EXISTS prompt_cache_stats (
+    id TEXT PRIMARY KEY,
+    story_id TEXT,
+    source TEXT NOT NULL,       -- 'narrative' or the service that made the call
+    provider_type TEXT NOT NULL,
+    model TEXT NOT NULL,
+    prompt_tokens INTEGER NOT NULL,
+    cached_tokens INTEGER NOT NULL,
+    cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_cache_stats_story ON prompt_cache_stats(story_id);
+CREATE INDEX IF NOT EXISTS idx_prompt_cache_stats_created ON prompt_cache_stats(created_at);
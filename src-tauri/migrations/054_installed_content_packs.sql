@@ -0,0 +1,11 @@
+-- Community content packs (.aventurapack) installed into the vault
+CREATE TABLE IF NOT EXISTS installed_content_packs (
+    id TEXT PRIMARY KEY,        -- the pack's own id from its manifest
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    author TEXT,
+    manifest TEXT NOT NULL,     -- JSON manifest as installed
+    items TEXT NOT NULL,        -- JSON array of {kind, id, name} created by the install
+    installed_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Rows changed since the last incremental backup. Filled by triggers the
+-- backup engine installs on every table once incremental backups are used.
+CREATE TABLE IF NOT EXISTS backup_change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_name TEXT NOT NULL,
+    row_id INTEGER NOT NULL    -- rowid of the changed row
+);
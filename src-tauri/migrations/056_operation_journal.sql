@@ -0,0 +1,12 @@
+-- Undo history for destructive operations run through the Rust backend
+CREATE TABLE IF NOT EXISTS operation_journal (
+    id TEXT PRIMARY KEY,
+    story_id TEXT,              -- NULL for operations outside a story
+    kind TEXT NOT NULL,         -- 'deleteEntries', 'bulkLoreEdit', 'pruneBranch'
+    description TEXT NOT NULL,
+    changes TEXT NOT NULL,      -- JSON array of {table, row}: rows as they were before
+    row_count INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_operation_journal_story ON operation_journal(story_id, created_at);
@@ -0,0 +1,14 @@
+-- Configured image generation backends. Requests that don't name one go
+-- to the provider marked active.
+CREATE TABLE IF NOT EXISTS image_providers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    provider_type TEXT NOT NULL, -- 'openai', 'novelai', 'stability', 'webui'
+    base_url TEXT NOT NULL DEFAULT '',
+    api_key TEXT NOT NULL DEFAULT '',
+    model TEXT NOT NULL DEFAULT '',
+    options TEXT,                -- JSON object of extra request fields
+    is_active INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
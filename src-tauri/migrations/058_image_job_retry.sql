@@ -0,0 +1,6 @@
+-- A pending job isn't started before this time (milliseconds), so retries
+-- back off and rate-limited jobs wait, even across restarts
+ALTER TABLE background_jobs ADD COLUMN not_before INTEGER;
+
+-- Image requests allowed in flight per provider; NULL uses the backend's default
+ALTER TABLE image_providers ADD COLUMN max_concurrent INTEGER;
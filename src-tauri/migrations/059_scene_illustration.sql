@@ -0,0 +1,7 @@
+-- Per-story automatic scene illustration settings (JSON); NULL leaves it off
+ALTER TABLE stories ADD COLUMN illustration_settings TEXT;
+
+-- What created an embedded image: 'inline' (requested in the text) or
+-- 'scene' (automatic scene illustration), so the per-chapter budget only
+-- counts automatic ones
+ALTER TABLE embedded_images ADD COLUMN origin TEXT NOT NULL DEFAULT 'inline';
@@ -0,0 +1,25 @@
+-- Generation parameters of every generated image, so it can be reproduced
+-- or refined later. Embedded images share their id with their record;
+-- other images (portraits, variations) carry the id the generate call
+-- returned, and any image can be matched by its hash.
+CREATE TABLE IF NOT EXISTS image_metadata (
+    id TEXT PRIMARY KEY,
+    -- SHA-256 of the decoded image bytes
+    image_hash TEXT NOT NULL,
+    provider_id TEXT,
+    provider_type TEXT,
+    model TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    negative_prompt TEXT,
+    revised_prompt TEXT,
+    seed INTEGER,
+    sampler TEXT,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    strength REAL,
+    -- The provider's extra request fields (JSON object)
+    parameters TEXT NOT NULL DEFAULT '{}',
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_image_metadata_hash ON image_metadata(image_hash);
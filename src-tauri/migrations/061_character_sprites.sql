@@ -0,0 +1,22 @@
+-- Expression sprites: one image per character and mood, VN style
+CREATE TABLE IF NOT EXISTS character_sprites (
+    id TEXT PRIMARY KEY,
+    character_id TEXT NOT NULL,
+    story_id TEXT NOT NULL,
+    -- 'neutral' | 'happy' | 'sad' | 'angry' | 'surprised' | 'afraid'
+    mood TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    model TEXT NOT NULL DEFAULT '',
+    image_data TEXT NOT NULL DEFAULT '',
+    width INTEGER,
+    height INTEGER,
+    -- 'pending' | 'generating' | 'complete' | 'failed', as for embedded images
+    status TEXT NOT NULL DEFAULT 'pending',
+    error_message TEXT,
+    created_at INTEGER NOT NULL,
+    UNIQUE (character_id, mood),
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_character_sprites_story ON character_sprites(story_id);
@@ -0,0 +1,18 @@
+-- A music theme per chapter, picked from the user's library or generated
+CREATE TABLE IF NOT EXISTS chapter_themes (
+    chapter_id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    -- Scene mood the theme was chosen for, e.g. 'melancholy'
+    mood TEXT NOT NULL,
+    -- 'library' | 'api'
+    source TEXT NOT NULL,
+    title TEXT NOT NULL,
+    -- Absolute path: a library file, or a generated file in the music cache
+    file_path TEXT NOT NULL,
+    prompt TEXT,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_chapter_themes_story ON chapter_themes(story_id);
@@ -0,0 +1,25 @@
+-- Active writing and reading time per story per local day, kept by the
+-- Rust session tracker
+CREATE TABLE IF NOT EXISTS writing_activity (
+    story_id TEXT NOT NULL,
+    day TEXT NOT NULL,              -- local date, YYYY-MM-DD
+    writing_ms INTEGER NOT NULL DEFAULT 0,
+    reading_ms INTEGER NOT NULL DEFAULT 0,
+    words_written INTEGER NOT NULL DEFAULT 0,
+    goal_met_at INTEGER,            -- when the story's daily goal was reached
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (story_id, day),
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_writing_activity_day ON writing_activity(day);
+
+-- Days the daily goal across all stories was reached
+CREATE TABLE IF NOT EXISTS writing_goal_days (
+    day TEXT PRIMARY KEY,
+    words INTEGER NOT NULL,
+    met_at INTEGER NOT NULL
+);
+
+-- Daily word goal for one story, on top of the global one
+ALTER TABLE stories ADD COLUMN daily_word_goal INTEGER;
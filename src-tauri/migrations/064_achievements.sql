@@ -0,0 +1,9 @@
+-- Milestones the achievements engine has unlocked
+CREATE TABLE IF NOT EXISTS achievements (
+    id TEXT PRIMARY KEY,
+    progress INTEGER NOT NULL,      -- the measure when it unlocked
+    unlocked_at INTEGER NOT NULL
+);
+
+-- Set when the user marks a story as finished
+ALTER TABLE stories ADD COLUMN finished_at INTEGER;
@@ -0,0 +1,10 @@
+-- Opt-in usage counters. Only feature names and error categories are
+-- stored, never story content or error messages.
+CREATE TABLE IF NOT EXISTS analytics_counters (
+    category TEXT NOT NULL,         -- 'feature' | 'error'
+    name TEXT NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL,
+    PRIMARY KEY (category, name)
+);
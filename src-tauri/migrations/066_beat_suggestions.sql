@@ -0,0 +1,17 @@
+-- Beat resolutions proposed by the beat check after each turn
+CREATE TABLE IF NOT EXISTS beat_suggestions (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    beat_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    suggested_status TEXT NOT NULL,
+    confidence REAL NOT NULL DEFAULT 0,
+    reason TEXT,
+    beat_status TEXT,                       -- the beat's status when it was checked
+    status TEXT NOT NULL DEFAULT 'pending', -- 'pending' | 'accepted' | 'dismissed'
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (beat_id) REFERENCES story_beats(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_beat_suggestions_story ON beat_suggestions(story_id, status);
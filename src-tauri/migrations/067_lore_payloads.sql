@@ -0,0 +1,9 @@
+-- Turn each foreshadowing payload entry ripened on, per story
+CREATE TABLE IF NOT EXISTS lore_payload_state (
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    ripened_turn INTEGER NOT NULL,
+    ripened_at INTEGER NOT NULL,
+    PRIMARY KEY (story_id, entry_id),
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+-- Director mode: a story's arc plan and the drift checks run against it
+CREATE TABLE IF NOT EXISTS story_arcs (
+    story_id TEXT PRIMARY KEY,
+    plan TEXT NOT NULL,          -- JSON ArcPlan
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS arc_drift_checks (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    act INTEGER NOT NULL,
+    alignment REAL NOT NULL,     -- 0 (off-outline) to 1 (on it)
+    drifting INTEGER NOT NULL,
+    notes TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_arc_drift_checks_story ON arc_drift_checks(story_id, created_at);
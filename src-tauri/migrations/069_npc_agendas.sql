@@ -0,0 +1,17 @@
+-- What major NPCs are up to off-screen, simulated once per story day
+CREATE TABLE IF NOT EXISTS npc_agendas (
+    story_id TEXT NOT NULL,
+    character_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    goal TEXT NOT NULL,
+    location TEXT NOT NULL,
+    activity TEXT NOT NULL,
+    day INTEGER NOT NULL,        -- story day the agenda was last updated for
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (story_id, character_id),
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+);
+
+-- Last story day the simulation was queued for
+ALTER TABLE stories ADD COLUMN npc_simulated_day INTEGER;
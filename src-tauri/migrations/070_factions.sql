@@ -0,0 +1,36 @@
+-- Factions, who belongs to them and the player's standing with each
+
+CREATE TABLE IF NOT EXISTS factions (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_factions_story_name ON factions(story_id, name COLLATE NOCASE);
+
+CREATE TABLE IF NOT EXISTS faction_members (
+    faction_id TEXT NOT NULL,
+    character_id TEXT NOT NULL,
+    role TEXT,
+    PRIMARY KEY (faction_id, character_id),
+    FOREIGN KEY (faction_id) REFERENCES factions(id) ON DELETE CASCADE,
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+);
+
+-- Every change to the player's reputation; a faction's standing is their sum
+CREATE TABLE IF NOT EXISTS faction_reputation (
+    id TEXT PRIMARY KEY,
+    faction_id TEXT NOT NULL,
+    entry_id TEXT,               -- entry the change was extracted from
+    delta INTEGER NOT NULL,
+    reason TEXT,
+    source TEXT NOT NULL,        -- 'extraction' | 'manual'
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (faction_id) REFERENCES factions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_faction_reputation_faction ON faction_reputation(faction_id, created_at);
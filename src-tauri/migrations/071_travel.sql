@@ -0,0 +1,44 @@
+-- Location graph: travel times between locations, by name. Routes run both
+-- ways; each pair is stored once with its names in sorted order.
+CREATE TABLE IF NOT EXISTS location_routes (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    from_name TEXT NOT NULL,
+    to_name TEXT NOT NULL,
+    travel_minutes INTEGER NOT NULL,
+    notes TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_location_routes_pair
+    ON location_routes(story_id, from_name COLLATE NOCASE, to_name COLLATE NOCASE);
+
+-- Where the party was at the end of each entry, with the story clock then
+CREATE TABLE IF NOT EXISTS party_positions (
+    entry_id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    location_name TEXT NOT NULL,
+    story_minutes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_party_positions_story ON party_positions(story_id);
+
+-- Moves faster than the location graph allows
+CREATE TABLE IF NOT EXISTS travel_warnings (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    from_name TEXT NOT NULL,
+    to_name TEXT NOT NULL,
+    elapsed_minutes INTEGER NOT NULL,
+    required_minutes INTEGER NOT NULL,
+    dismissed INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_travel_warnings_story ON travel_warnings(story_id, dismissed);
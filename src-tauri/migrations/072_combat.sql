@@ -0,0 +1,17 @@
+-- Combat encounters resolved by the rules engine. Combatants and the round
+-- log are JSON; rolls are reproducible from the seed.
+CREATE TABLE IF NOT EXISTS combat_encounters (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active', -- 'active' | 'victory' | 'defeat' | 'fled' | 'ended'
+    seed INTEGER NOT NULL,
+    round INTEGER NOT NULL DEFAULT 0,
+    narrated_round INTEGER NOT NULL DEFAULT 0, -- last round a response has narrated
+    combatants TEXT NOT NULL,
+    rounds TEXT NOT NULL DEFAULT '[]',
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_combat_encounters_story ON combat_encounters(story_id, status);
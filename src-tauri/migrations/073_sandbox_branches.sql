@@ -0,0 +1,6 @@
+-- Sandbox ("what-if") branches: temporary forks hidden from branch lists and
+-- sync, deleted once they expire unless promoted to a regular branch.
+ALTER TABLE branches ADD COLUMN sandbox_expires_at INTEGER; -- NULL for regular branches
+
+CREATE INDEX IF NOT EXISTS idx_branches_sandbox ON branches(sandbox_expires_at)
+    WHERE sandbox_expires_at IS NOT NULL;
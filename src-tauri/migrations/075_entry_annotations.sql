@@ -0,0 +1,19 @@
+-- Margin notes on story entries, kept apart from the entry text
+CREATE TABLE IF NOT EXISTS entry_annotations (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    quote TEXT,                               -- the passage the note is about
+    instruct INTEGER NOT NULL DEFAULT 0,      -- give it to the model as a revision instruction
+    include_in_sync INTEGER NOT NULL DEFAULT 1,
+    include_in_export INTEGER NOT NULL DEFAULT 0,
+    resolved INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (entry_id) REFERENCES story_entries(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_annotations_entry ON entry_annotations(entry_id);
+CREATE INDEX IF NOT EXISTS idx_entry_annotations_story ON entry_annotations(story_id);
@@ -0,0 +1,3 @@
+-- Set when a chapter's entries change after it was summarized (split or
+-- merged), until its summary is written again
+ALTER TABLE chapters ADD COLUMN summary_stale INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- User export templates (Handlebars). The built-in layouts live in code.
+CREATE TABLE IF NOT EXISTS export_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    format TEXT NOT NULL,           -- text, markdown or html
+    body TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,12 @@
+-- Quality flags on story entries, used to pick turns for fine-tuning
+-- datasets. 'good' turns can be exported alone; 'bad' ones are left out.
+CREATE TABLE IF NOT EXISTS entry_quality (
+    entry_id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    flag TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    FOREIGN KEY (entry_id) REFERENCES story_entries(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_quality_story ON entry_quality(story_id);
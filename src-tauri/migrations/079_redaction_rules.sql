@@ -0,0 +1,16 @@
+-- Per-story redaction rules, applied on request when a story is exported
+-- or pushed to another device. Pattern rules mask regex matches; entity
+-- rules mask every name of a character, location, item or lore entry.
+CREATE TABLE IF NOT EXISTS redaction_rules (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    kind TEXT NOT NULL,             -- pattern or entity
+    pattern TEXT,                   -- regex, for pattern rules
+    entity_id TEXT,                 -- for entity rules
+    replacement TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_redaction_rules_story ON redaction_rules(story_id);
@@ -0,0 +1,4 @@
+-- Where a story came from (JSON: scenario id, name, creator and source URL,
+-- linked lorebook), credited in the attribution on shared exports. Kept on
+-- the story so the credit survives the scenario leaving the vault.
+ALTER TABLE stories ADD COLUMN provenance TEXT;
@@ -0,0 +1,17 @@
+-- Per-story pronunciation dictionary for text-to-speech. Each grapheme (a
+-- name or word as written) is read as a respelling ("Hermione" ->
+-- "her-MY-oh-nee") or, for engines that take SSML, an IPA phoneme string.
+CREATE TABLE IF NOT EXISTS pronunciations (
+    id TEXT PRIMARY KEY,
+    story_id TEXT NOT NULL,
+    grapheme TEXT NOT NULL,
+    phoneme TEXT NOT NULL,
+    alphabet TEXT NOT NULL DEFAULT 'respelling',  -- respelling or ipa
+    case_sensitive INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (story_id) REFERENCES stories(id) ON DELETE CASCADE,
+    UNIQUE(story_id, grapheme)
+);
+
+CREATE INDEX IF NOT EXISTS idx_pronunciations_story ON pronunciations(story_id);
@@ -0,0 +1,9 @@
+-- Encrypted shares uploaded to endpoints that keep files forever (S3 and
+-- WebDAV). The sender's app deletes each one once its link expires, if the
+-- receiver hasn't already.
+CREATE TABLE IF NOT EXISTS share_uploads (
+    name TEXT PRIMARY KEY,
+    endpoint TEXT NOT NULL,  -- s3 or webDav
+    expires_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use tauri::State;
+use uuid::Uuid;
+
+use super::store::{self, activate, max_alternatives, prune};
+use super::types::EntryAlternative;
+use crate::db::{now_millis, DbState};
+
+/// Store a newly generated variant of an entry (a regeneration). The first
+/// call also records the entry's current content, so the original is kept.
+/// The new variant becomes the entry's content unless `make_active` is
+/// false. Old inactive variants beyond `max_entry_alternatives` are pruned.
+#[tauri::command]
+pub async fn add_entry_alternative(
+    db: State<'_, DbState>,
+    entry_id: String,
+    content: String,
+    generation_params: Option<serde_json::Value>,
+    make_active: Option<bool>,
+) -> Result<EntryAlternative, String> {
+    let keep = max_alternatives(db.pool()).await?;
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let entry: Option<(String, String, Option<String>, i64)> = sqlx::query_as(
+        "SELECT story_id, content, generation_params, created_at FROM story_entries WHERE id = ?",
+    )
+    .bind(&entry_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load entry: {}", e))?;
+    let (story_id, original, original_params, created_at) =
+        entry.ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+
+    let existing: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM entry_alternatives WHERE entry_id = ?")
+            .bind(&entry_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load alternatives: {}", e))?;
+    let insert =
+        "INSERT INTO entry_alternatives (id, story_id, entry_id, content, generation_params, \
+                  active, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)";
+    if existing == 0 {
+        sqlx::query(insert)
+            .bind(Uuid::new_v4().to_string())
+            .bind(&story_id)
+            .bind(&entry_id)
+            .bind(&original)
+            .bind(&original_params)
+            .bind(true)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save alternative: {}", e))?;
+    }
+
+    let alternative = EntryAlternative {
+        id: Uuid::new_v4().to_string(),
+        story_id,
+        entry_id: entry_id.clone(),
+        content,
+        generation_params: generation_params.map(|p| p.to_string()),
+        active: false,
+        created_at: now_millis(),
+    };
+    sqlx::query(insert)
+        .bind(&alternative.id)
+        .bind(&alternative.story_id)
+        .bind(&alternative.entry_id)
+        .bind(&alternative.content)
+        .bind(&alternative.generation_params)
+        .bind(false)
+        .bind(alternative.created_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save alternative: {}", e))?;
+
+    let mut alternative = alternative;
+    if make_active.unwrap_or(true) {
+        activate(&mut tx, &alternative).await?;
+        alternative.active = true;
+    }
+    prune(&mut tx, &entry_id, keep).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit alternative: {}", e))?;
+    Ok(alternative)
+}
+
+/// An entry's variants, oldest first
+#[tauri::command]
+pub async fn list_entry_alternatives(
+    db: State<'_, DbState>,
+    entry_id: String,
+) -> Result<Vec<EntryAlternative>, String> {
+    store::list(db.pool(), &entry_id).await
+}
+
+/// Swipe to another variant: it becomes the entry's content
+#[tauri::command]
+pub async fn set_active_alternative(
+    db: State<'_, DbState>,
+    alternative_id: String,
+) -> Result<EntryAlternative, String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut alternative: EntryAlternative =
+        sqlx::query_as("SELECT * FROM entry_alternatives WHERE id = ?")
+            .bind(&alternative_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load alternative: {}", e))?
+            .ok_or_else(|| format!("Alternative not found: {}", alternative_id))?;
+    activate(&mut tx, &alternative).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit alternative: {}", e))?;
+    alternative.active = true;
+    Ok(alternative)
+}
+
+/// Delete an inactive variant
+#[tauri::command]
+pub async fn delete_entry_alternative(
+    db: State<'_, DbState>,
+    alternative_id: String,
+) -> Result<(), String> {
+    let result = sqlx::query("DELETE FROM entry_alternatives WHERE id = ? AND active = 0")
+        .bind(&alternative_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete alternative: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err("The active alternative cannot be deleted".to_string());
+    }
+    Ok(())
+}
+
+/// Keep at most `keep` variants (the active one included) of every entry in
+/// a story, or of one entry. Returns how many were removed.
+#[tauri::command]
+pub async fn prune_entry_alternatives(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: Option<String>,
+    keep: Option<i64>,
+) -> Result<u64, String> {
+    let keep = match keep {
+        Some(keep) => keep.max(1),
+        None => max_alternatives(db.pool()).await?,
+    };
+    let entry_ids: Vec<String> = match entry_id {
+        Some(id) => vec![id],
+        None => sqlx::query_scalar(
+            "SELECT DISTINCT entry_id FROM entry_alternatives WHERE story_id = ?",
+        )
+        .bind(&story_id)
+        .fetch_all(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load alternatives: {}", e))?,
+    };
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut removed = 0;
+    for entry_id in entry_ids {
+        removed += prune(&mut tx, &entry_id, keep).await?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit pruning: {}", e))?;
+    Ok(removed)
+}
+
+/// Every variant in a story, for export and sync
+#[tauri::command]
+pub async fn get_story_alternatives(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<EntryAlternative>, String> {
+    sqlx::query_as("SELECT * FROM entry_alternatives WHERE story_id = ? ORDER BY created_at, id")
+        .bind(&story_id)
+        .fetch_all(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load alternatives: {}", e))
+}
+
+/// Import exported variants into a newly imported story. `entry_ids` maps
+/// the exported entry ids to the imported ones; unmapped variants are skipped.
+#[tauri::command]
+pub async fn import_entry_alternatives(
+    db: State<'_, DbState>,
+    story_id: String,
+    alternatives: Vec<EntryAlternative>,
+    entry_ids: HashMap<String, String>,
+) -> Result<usize, String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut imported = 0;
+    for alternative in alternatives {
+        let Some(entry_id) = entry_ids.get(&alternative.entry_id) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO entry_alternatives (id, story_id, entry_id, content, generation_params, \
+             active, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&story_id)
+        .bind(entry_id)
+        .bind(&alternative.content)
+        .bind(&alternative.generation_params)
+        .bind(alternative.active)
+        .bind(alternative.created_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to import alternative: {}", e))?;
+        imported += 1;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit alternatives: {}", e))?;
+    Ok(imported)
+}
@@ -0,0 +1,68 @@
+use sqlx::{Sqlite, SqlitePool, Transaction};
+
+use super::types::EntryAlternative;
+use crate::llm::config::get_setting;
+
+/// Alternatives kept per entry when `max_entry_alternatives` is unset
+const DEFAULT_MAX_ALTERNATIVES: i64 = 10;
+
+pub async fn max_alternatives(pool: &SqlitePool) -> Result<i64, String> {
+    Ok(get_setting(pool, "max_entry_alternatives")
+        .await?
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_MAX_ALTERNATIVES)
+        .max(1))
+}
+
+pub async fn list(pool: &SqlitePool, entry_id: &str) -> Result<Vec<EntryAlternative>, String> {
+    sqlx::query_as("SELECT * FROM entry_alternatives WHERE entry_id = ? ORDER BY created_at, id")
+        .bind(entry_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load alternatives: {}", e))
+}
+
+/// Make an alternative the active one and copy it into its entry. The
+/// entry's translation no longer matches and is cleared.
+pub async fn activate(
+    tx: &mut Transaction<'_, Sqlite>,
+    alternative: &EntryAlternative,
+) -> Result<(), String> {
+    sqlx::query("UPDATE entry_alternatives SET active = (id = ?) WHERE entry_id = ?")
+        .bind(&alternative.id)
+        .bind(&alternative.entry_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to switch alternative: {}", e))?;
+    sqlx::query(
+        "UPDATE story_entries SET content = ?, generation_params = ?, translated_content = NULL, \
+         translation_language = NULL WHERE id = ?",
+    )
+    .bind(&alternative.content)
+    .bind(&alternative.generation_params)
+    .bind(&alternative.entry_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to update entry: {}", e))?;
+    Ok(())
+}
+
+/// Delete the oldest inactive alternatives of an entry beyond `keep`.
+/// Returns how many were removed.
+pub async fn prune(
+    tx: &mut Transaction<'_, Sqlite>,
+    entry_id: &str,
+    keep: i64,
+) -> Result<u64, String> {
+    let result = sqlx::query(
+        "DELETE FROM entry_alternatives WHERE entry_id = ?1 AND active = 0 AND id NOT IN \
+         (SELECT id FROM entry_alternatives WHERE entry_id = ?1 \
+          ORDER BY active DESC, created_at DESC LIMIT ?2)",
+    )
+    .bind(entry_id)
+    .bind(keep)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to prune alternatives: {}", e))?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// One generated variant of a story entry
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryAlternative {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    pub content: String,
+    /// Service and sampling parameters the variant was generated with
+    #[serde(default)]
+    pub generation_params: Option<String>,
+    #[serde(default)]
+    pub active: bool,
+    pub created_at: i64,
+}
@@ -0,0 +1,189 @@
+//! Local usage counters the user can opt in to and export. Counts are
+//! buffered in memory and written out every minute; nothing is recorded
+//! while analytics are off, and nothing ever leaves the device on its own.
+
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::types::{AnalyticsReport, AnalyticsSettings, Counter};
+use crate::db::{now_millis, DbState};
+use crate::llm::config::get_setting;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest name a counter may have
+const MAX_NAME_LEN: usize = 64;
+
+/// Pending counts by (category, name): count, first and last seen
+type Pending = HashMap<(&'static str, String), (i64, i64, i64)>;
+
+#[derive(Default)]
+pub struct Analytics {
+    enabled: AtomicBool,
+    pending: Mutex<Pending>,
+}
+
+/// Names are identifiers, so no story text can end up in a counter
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_.-".contains(c))
+}
+
+/// A coarse category for an error message; the message itself is dropped
+pub fn error_category(message: &str) -> &'static str {
+    let message = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|needle| message.contains(needle));
+    if has(&["timed out", "timeout"]) {
+        "timeout"
+    } else if has(&["429", "rate limit", "too many requests"]) {
+        "rate_limit"
+    } else if has(&["401", "403", "unauthorized", "forbidden", "api key"]) {
+        "auth"
+    } else if has(&["couldn't reach", "connect", "dns", "network"]) {
+        "network"
+    } else if has(&["not enough free space"]) {
+        "disk_space"
+    } else if has(&["invalid", "parse", "json", "serialize"]) {
+        "parse"
+    } else if has(&["database", "sqlite"]) {
+        "database"
+    } else if has(&["not found", "missing"]) {
+        "not_found"
+    } else {
+        "other"
+    }
+}
+
+impl Analytics {
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turn collection on or off for this session; off drops what hasn't
+    /// been written yet. The frontend keeps the choice in
+    /// `analytics_settings`, which is read again at launch.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.pending.lock().unwrap().clear();
+        }
+    }
+
+    fn count(&self, category: &'static str, name: String) {
+        if !self.enabled() || !valid_name(&name) {
+            return;
+        }
+        let now = now_millis();
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending.entry((category, name)).or_insert((0, now, now));
+        entry.0 += 1;
+        entry.2 = now;
+    }
+
+    /// Count a use of a feature, e.g. `image.generate`
+    pub fn record_feature(&self, feature: &str) {
+        self.count("feature", feature.to_string());
+    }
+
+    /// Count an error from `source` by its category
+    pub fn record_error(&self, source: &str, message: &str) {
+        self.count("error", format!("{}:{}", source, error_category(message)));
+    }
+
+    /// Write the buffered counts to the database
+    pub async fn flush(&self, pool: &SqlitePool) -> Result<(), String> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for ((category, name), (count, first_seen, last_seen)) in pending {
+            sqlx::query(
+                "INSERT INTO analytics_counters (category, name, count, first_seen, last_seen) \
+                 VALUES (?, ?, ?, ?, ?) ON CONFLICT(category, name) DO UPDATE SET \
+                 count = count + excluded.count, last_seen = excluded.last_seen",
+            )
+            .bind(category)
+            .bind(&name)
+            .bind(count)
+            .bind(first_seen)
+            .bind(last_seen)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to save analytics: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+async fn counters(pool: &SqlitePool, category: &str) -> Result<Vec<Counter>, String> {
+    let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT name, count, first_seen, last_seen FROM analytics_counters \
+         WHERE category = ? ORDER BY count DESC, name",
+    )
+    .bind(category)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load analytics: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(name, count, first_seen, last_seen)| Counter {
+            name,
+            count,
+            first_seen,
+            last_seen,
+        })
+        .collect())
+}
+
+pub async fn report(app: &AppHandle, pool: &SqlitePool) -> Result<AnalyticsReport, String> {
+    let analytics = app.state::<Analytics>();
+    analytics.flush(pool).await?;
+    Ok(AnalyticsReport {
+        generated_at: now_millis(),
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        enabled: analytics.enabled(),
+        features: counters(pool, "feature").await?,
+        errors: counters(pool, "error").await?,
+    })
+}
+
+pub async fn clear(app: &AppHandle, pool: &SqlitePool) -> Result<(), String> {
+    app.state::<Analytics>().pending.lock().unwrap().clear();
+    sqlx::query("DELETE FROM analytics_counters")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to clear analytics: {}", e))?;
+    Ok(())
+}
+
+/// Apply the saved opt-in and write out counts every minute
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The settings table only exists once the frontend has run migrations
+        let settings: AnalyticsSettings = loop {
+            match get_setting(&pool, "analytics_settings").await {
+                Ok(raw) => {
+                    break raw
+                        .and_then(|raw| serde_json::from_str(&raw).ok())
+                        .unwrap_or_default()
+                }
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        let analytics = app.state::<Analytics>();
+        analytics.set_enabled(settings.enabled);
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if let Err(e) = analytics.flush(&pool).await {
+                eprintln!("{}", e);
+            }
+        }
+    });
+}
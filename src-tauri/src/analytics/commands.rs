@@ -0,0 +1,56 @@
+use tauri::{AppHandle, State};
+
+use super::collector::{self, valid_name};
+use super::types::AnalyticsReport;
+use super::Analytics;
+use crate::db::DbState;
+
+/// Count a use of a frontend feature. Names are lowercase identifiers like
+/// `lorebook.import`; anything else is rejected.
+#[tauri::command]
+pub fn record_feature_usage(
+    analytics: State<'_, Analytics>,
+    feature: String,
+) -> Result<(), String> {
+    if !valid_name(&feature) {
+        return Err(format!("Invalid feature name: {}", feature));
+    }
+    analytics.record_feature(&feature);
+    Ok(())
+}
+
+/// Opt in or out for this session; the frontend keeps the choice in
+/// `analytics_settings`
+#[tauri::command]
+pub fn set_analytics_enabled(analytics: State<'_, Analytics>, enabled: bool) {
+    analytics.set_enabled(enabled);
+}
+
+/// What an exported report would contain
+#[tauri::command]
+pub async fn get_analytics_report(
+    app: AppHandle,
+    db: State<'_, DbState>,
+) -> Result<AnalyticsReport, String> {
+    collector::report(&app, db.pool()).await
+}
+
+/// Write the report as JSON to `path`, for attaching to an issue
+#[tauri::command]
+pub async fn export_analytics_report(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    path: String,
+) -> Result<(), String> {
+    let report = collector::report(&app, db.pool()).await?;
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize analytics: {}", e))?;
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write analytics report: {}", e))
+}
+
+#[tauri::command]
+pub async fn clear_analytics(app: AppHandle, db: State<'_, DbState>) -> Result<(), String> {
+    collector::clear(&app, db.pool()).await
+}
@@ -0,0 +1,5 @@
+pub mod collector;
+pub mod commands;
+pub mod types;
+
+pub use collector::Analytics;
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Analytics options, stored in `analytics_settings`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnalyticsSettings {
+    /// Off until the user opts in
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Counter {
+    pub name: String,
+    pub count: i64,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// Everything the collector holds, as written by `export_analytics_report`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsReport {
+    pub generated_at: i64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub enabled: bool,
+    /// Feature names and how often they were used
+    pub features: Vec<Counter>,
+    /// `source:category` pairs, e.g. `job.translation:rate_limit`
+    pub errors: Vec<Counter>,
+}
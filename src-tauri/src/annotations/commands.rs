@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use tauri::State;
+
+use super::store;
+use super::types::{AnnotationChanges, EntryAnnotation, NewAnnotation, SharePurpose};
+use crate::db::DbState;
+
+/// A story's annotations, or only those of `entry_id`
+#[tauri::command]
+pub async fn get_entry_annotations(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: Option<String>,
+) -> Result<Vec<EntryAnnotation>, String> {
+    store::load_annotations(db.pool(), &story_id, entry_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn add_entry_annotation(
+    db: State<'_, DbState>,
+    entry_id: String,
+    annotation: NewAnnotation,
+) -> Result<EntryAnnotation, String> {
+    store::add_annotation(db.pool(), &entry_id, &annotation).await
+}
+
+#[tauri::command]
+pub async fn update_entry_annotation(
+    db: State<'_, DbState>,
+    annotation_id: String,
+    changes: AnnotationChanges,
+) -> Result<EntryAnnotation, String> {
+    store::update_annotation(db.pool(), &annotation_id, &changes).await
+}
+
+#[tauri::command]
+pub async fn delete_entry_annotation(
+    db: State<'_, DbState>,
+    annotation_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM entry_annotations WHERE id = ?")
+        .bind(&annotation_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete annotation: {}", e))?;
+    Ok(())
+}
+
+/// Annotations to include when the story is synced or exported
+#[tauri::command]
+pub async fn get_shared_annotations(
+    db: State<'_, DbState>,
+    story_id: String,
+    purpose: SharePurpose,
+) -> Result<Vec<EntryAnnotation>, String> {
+    store::shared_annotations(db.pool(), &story_id, purpose).await
+}
+
+/// Import exported annotations into a newly imported story
+#[tauri::command]
+pub async fn import_entry_annotations(
+    db: State<'_, DbState>,
+    story_id: String,
+    annotations: Vec<EntryAnnotation>,
+    entry_ids: HashMap<String, String>,
+) -> Result<usize, String> {
+    store::import_annotations(db.pool(), &story_id, &annotations, &entry_ids).await
+}
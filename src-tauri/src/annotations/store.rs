@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::types::{AnnotationChanges, EntryAnnotation, NewAnnotation, SharePurpose};
+use crate::context::branch::visible_entries;
+use crate::db::now_millis;
+
+/// Characters of the entry quoted when a note has no passage of its own
+const ENTRY_EXCERPT_CHARS: usize = 120;
+
+pub async fn load_annotation(
+    pool: &SqlitePool,
+    annotation_id: &str,
+) -> Result<EntryAnnotation, String> {
+    sqlx::query_as("SELECT * FROM entry_annotations WHERE id = ?")
+        .bind(annotation_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load annotation: {}", e))?
+        .ok_or_else(|| format!("Annotation not found: {}", annotation_id))
+}
+
+/// A story's annotations, or one entry's
+pub async fn load_annotations(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: Option<&str>,
+) -> Result<Vec<EntryAnnotation>, String> {
+    sqlx::query_as(
+        "SELECT * FROM entry_annotations WHERE story_id = ? AND (? IS NULL OR entry_id = ?) \
+         ORDER BY created_at, id",
+    )
+    .bind(story_id)
+    .bind(entry_id)
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load annotations: {}", e))
+}
+
+fn clean(text: Option<&str>) -> Option<&str> {
+    text.map(str::trim).filter(|t| !t.is_empty())
+}
+
+pub async fn add_annotation(
+    pool: &SqlitePool,
+    entry_id: &str,
+    annotation: &NewAnnotation,
+) -> Result<EntryAnnotation, String> {
+    let body = clean(Some(&annotation.body)).ok_or("An annotation needs a note")?;
+    let story_id: String = sqlx::query_scalar("SELECT story_id FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let now = now_millis();
+    sqlx::query_as(
+        "INSERT INTO entry_annotations (id, story_id, entry_id, body, quote, instruct, \
+         include_in_sync, include_in_export, resolved, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?) RETURNING *",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&story_id)
+    .bind(entry_id)
+    .bind(body)
+    .bind(clean(annotation.quote.as_deref()))
+    .bind(annotation.instruct)
+    .bind(annotation.include_in_sync.unwrap_or(true))
+    .bind(annotation.include_in_export)
+    .bind(now)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to save annotation: {}", e))
+}
+
+pub async fn update_annotation(
+    pool: &SqlitePool,
+    annotation_id: &str,
+    changes: &AnnotationChanges,
+) -> Result<EntryAnnotation, String> {
+    let mut annotation = load_annotation(pool, annotation_id).await?;
+    if let Some(body) = &changes.body {
+        annotation.body = clean(Some(body))
+            .ok_or("An annotation needs a note")?
+            .to_string();
+    }
+    if let Some(quote) = &changes.quote {
+        annotation.quote = clean(Some(quote)).map(str::to_string);
+    }
+    annotation.instruct = changes.instruct.unwrap_or(annotation.instruct);
+    annotation.include_in_sync = changes
+        .include_in_sync
+        .unwrap_or(annotation.include_in_sync);
+    annotation.include_in_export = changes
+        .include_in_export
+        .unwrap_or(annotation.include_in_export);
+    annotation.resolved = changes.resolved.unwrap_or(annotation.resolved);
+    annotation.updated_at = now_millis();
+
+    sqlx::query(
+        "UPDATE entry_annotations SET body = ?, quote = ?, instruct = ?, include_in_sync = ?, \
+         include_in_export = ?, resolved = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(&annotation.body)
+    .bind(&annotation.quote)
+    .bind(annotation.instruct)
+    .bind(annotation.include_in_sync)
+    .bind(annotation.include_in_export)
+    .bind(annotation.resolved)
+    .bind(annotation.updated_at)
+    .bind(annotation_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update annotation: {}", e))?;
+    Ok(annotation)
+}
+
+/// Annotations flagged to go along when the story is synced or exported
+pub async fn shared_annotations(
+    pool: &SqlitePool,
+    story_id: &str,
+    purpose: SharePurpose,
+) -> Result<Vec<EntryAnnotation>, String> {
+    Ok(load_annotations(pool, story_id, None)
+        .await?
+        .into_iter()
+        .filter(|a| match purpose {
+            SharePurpose::Sync => a.include_in_sync,
+            SharePurpose::Export => a.include_in_export,
+        })
+        .collect())
+}
+
+/// Add imported annotations to a newly imported story. `entry_ids` maps the
+/// exported entry ids to the imported ones; unmapped annotations are skipped.
+pub async fn import_annotations(
+    pool: &SqlitePool,
+    story_id: &str,
+    annotations: &[EntryAnnotation],
+    entry_ids: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut imported = 0;
+    for annotation in annotations {
+        let Some(entry_id) = entry_ids.get(&annotation.entry_id) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO entry_annotations (id, story_id, entry_id, body, quote, instruct, \
+             include_in_sync, include_in_export, resolved, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(story_id)
+        .bind(entry_id)
+        .bind(&annotation.body)
+        .bind(&annotation.quote)
+        .bind(annotation.instruct)
+        .bind(annotation.include_in_sync)
+        .bind(annotation.include_in_export)
+        .bind(annotation.resolved)
+        .bind(annotation.created_at)
+        .bind(annotation.updated_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to import annotation: {}", e))?;
+        imported += 1;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit annotations: {}", e))?;
+    Ok(imported)
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(ENTRY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// Unresolved notes marked as revision instructions on entries of the
+/// current branch, in reading order, for the `revisionNotes` context block
+pub async fn revision_block(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    let annotations: Vec<EntryAnnotation> = sqlx::query_as(
+        "SELECT * FROM entry_annotations WHERE story_id = ? AND instruct = 1 AND resolved = 0 \
+         ORDER BY created_at, id",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load annotations: {}", e))?;
+    if annotations.is_empty() {
+        return Ok(None);
+    }
+
+    let mut lines = Vec::new();
+    for entry in visible_entries(pool, story_id).await? {
+        for annotation in annotations.iter().filter(|a| a.entry_id == entry.id) {
+            let passage = annotation
+                .quote
+                .as_deref()
+                .map(excerpt)
+                .unwrap_or_else(|| excerpt(&entry.content));
+            lines.push(format!("- At \"{}\": {}", passage, annotation.body));
+        }
+    }
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "Revision notes from the author:\n{}",
+        lines.join("\n")
+    )))
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// A writer's note on an entry ("fix pacing here"), never part of its text
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryAnnotation {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    pub body: String,
+    /// The passage the note is about
+    #[serde(default)]
+    pub quote: Option<String>,
+    /// Given to the model as a revision instruction while unresolved
+    #[serde(default)]
+    pub instruct: bool,
+    #[serde(default = "default_true")]
+    pub include_in_sync: bool,
+    #[serde(default)]
+    pub include_in_export: bool,
+    #[serde(default)]
+    pub resolved: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAnnotation {
+    pub body: String,
+    pub quote: Option<String>,
+    #[serde(default)]
+    pub instruct: bool,
+    pub include_in_sync: Option<bool>,
+    #[serde(default)]
+    pub include_in_export: bool,
+}
+
+/// Fields to change; the rest are left as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationChanges {
+    pub body: Option<String>,
+    /// An empty quote clears it
+    pub quote: Option<String>,
+    pub instruct: Option<bool>,
+    pub include_in_sync: Option<bool>,
+    pub include_in_export: Option<bool>,
+    pub resolved: Option<bool>,
+}
+
+/// Where annotations are going, deciding which are included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SharePurpose {
+    Sync,
+    Export,
+}
@@ -0,0 +1,24 @@
+use tauri::{AppHandle, State};
+
+use super::footer::footer;
+use super::store::load_attribution;
+use super::types::ExportAttribution;
+use crate::db::DbState;
+
+/// The attribution for a shared export of the story, with its footer
+/// rendered for `format` (`text`, `markdown` or `html`; text by default)
+#[tauri::command]
+pub async fn get_export_attribution(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    format: Option<String>,
+) -> Result<ExportAttribution, String> {
+    let app_version = app.package_info().version.to_string();
+    let attribution = load_attribution(db.pool(), &app_version, &story_id).await?;
+    let footer = footer(&attribution, format.as_deref().unwrap_or("text"));
+    Ok(ExportAttribution {
+        attribution,
+        footer,
+    })
+}
@@ -0,0 +1,96 @@
+use serde_json::Value;
+
+use super::types::Attribution;
+use crate::reader::html::escape;
+
+/// The attribution's lines, e.g. "Scenario: The Long Night by Mara"
+fn lines(attribution: &Attribution) -> Vec<String> {
+    let by = |author: &Option<String>| match author.as_deref().filter(|a| !a.trim().is_empty()) {
+        Some(author) => format!(" by {}", author),
+        None => String::new(),
+    };
+    let mut lines = vec![format!(
+        "Created with Aventura {} · exported {}",
+        attribution.app_version, attribution.exported_at
+    )];
+    if let Some(scenario) = &attribution.scenario {
+        let source = match &scenario.source_url {
+            Some(url) => format!(" ({})", url),
+            None => String::new(),
+        };
+        lines.push(format!(
+            "Scenario: {}{}{}",
+            scenario.name,
+            by(&scenario.creator),
+            source
+        ));
+    }
+    for pack in &attribution.packs {
+        let version = if pack.version.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", pack.version)
+        };
+        lines.push(format!(
+            "Content pack: {}{}{}",
+            pack.name,
+            version,
+            by(&pack.author)
+        ));
+    }
+    lines
+}
+
+/// The attribution block to append to an export in `format` (`text`,
+/// `markdown` or `html`)
+pub fn footer(attribution: &Attribution, format: &str) -> String {
+    let lines = lines(attribution);
+    match format {
+        "html" => format!(
+            "<footer class=\"attribution\">\n{}\n</footer>\n",
+            lines
+                .iter()
+                .map(|line| format!("<p>{}</p>", escape(line)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+        "markdown" => format!(
+            "\n---\n\n{}\n",
+            lines
+                .iter()
+                .map(|line| format!("*{}*  ", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+        _ => format!("\n---\n{}\n", lines.join("\n")),
+    }
+}
+
+/// Append the footer to a rendered export. HTML documents get it before
+/// their closing body tag.
+pub fn append(output: &mut String, attribution: &Attribution, format: &str) {
+    let footer = footer(attribution, format);
+    match output.rfind("</body>").filter(|_| format == "html") {
+        Some(at) => output.insert_str(at, &footer),
+        None => {
+            if !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&footer);
+        }
+    }
+}
+
+/// Add the attribution to an Aventura export JSON as `attribution`
+pub fn attach(story_json: &str, attribution: &Attribution) -> Result<String, String> {
+    let mut export: Value =
+        serde_json::from_str(story_json).map_err(|e| format!("Invalid story data: {}", e))?;
+    let fields = export
+        .as_object_mut()
+        .ok_or("Invalid story data: not an object")?;
+    fields.insert(
+        "attribution".to_string(),
+        serde_json::to_value(attribution).map_err(|e| e.to_string())?,
+    );
+    serde_json::to_string(&export).map_err(|e| format!("Failed to serialize story: {}", e))
+}
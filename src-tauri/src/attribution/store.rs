@@ -0,0 +1,96 @@
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use super::types::{Attribution, PackCredit, Provenance, ScenarioCredit};
+use crate::content_pack::commands::installed_from_row;
+use crate::content_pack::types::PackItemKind;
+
+/// Credit for the pack a vault item came from, from the `contentPack` its
+/// metadata carries. The author comes from the pack as installed.
+pub async fn pack_credit(
+    pool: &SqlitePool,
+    metadata: &Value,
+) -> Result<Option<PackCredit>, String> {
+    let Some(pack) = metadata.get("contentPack") else {
+        return Ok(None);
+    };
+    let text = |key: &str| pack.get(key).and_then(Value::as_str).map(str::to_string);
+    let Some(id) = text("id") else {
+        return Ok(None);
+    };
+    let author: Option<String> =
+        sqlx::query_scalar("SELECT author FROM installed_content_packs WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load installed packs: {}", e))?
+            .flatten();
+    Ok(Some(PackCredit {
+        name: text("name").unwrap_or_else(|| id.clone()),
+        version: text("version").unwrap_or_default(),
+        author,
+        id,
+    }))
+}
+
+/// What a shared export of the story credits: the app, the scenario it
+/// started from and the content packs behind its scenario, lorebook and
+/// prompt preset
+pub async fn load_attribution(
+    pool: &SqlitePool,
+    app_version: &str,
+    story_id: &str,
+) -> Result<Attribution, String> {
+    let (provenance, preset_pack_id): (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT provenance, pack_id FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load story: {}", e))?
+            .ok_or_else(|| format!("Story not found: {}", story_id))?;
+    let provenance: Provenance = provenance
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+
+    let scenario = provenance
+        .scenario_name
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| ScenarioCredit {
+            name,
+            creator: provenance.scenario_creator,
+            source_url: provenance.scenario_source_url,
+        });
+
+    let installed =
+        sqlx::query("SELECT * FROM installed_content_packs ORDER BY name COLLATE NOCASE")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load installed packs: {}", e))?;
+    let mut packs = provenance.packs;
+    for pack in installed.iter().map(installed_from_row) {
+        let used = pack.items.iter().any(|item| match item.kind {
+            PackItemKind::Scenario => provenance.scenario_id.as_deref() == Some(&item.id),
+            PackItemKind::Lorebook => provenance.lorebook_id.as_deref() == Some(&item.id),
+            PackItemKind::Preset => preset_pack_id.as_deref() == Some(&item.id),
+            PackItemKind::Character => false,
+        });
+        match packs.iter_mut().find(|p| p.id == pack.id) {
+            // Still installed; the author may have been filled in since
+            Some(credit) => credit.author = credit.author.take().or(pack.author),
+            None if used => packs.push(PackCredit {
+                id: pack.id,
+                name: pack.name,
+                version: pack.version,
+                author: pack.author,
+            }),
+            None => {}
+        }
+    }
+
+    Ok(Attribution {
+        app_version: app_version.to_string(),
+        scenario,
+        packs,
+        exported_at: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    })
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// `stories.provenance`: what a story was started from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    #[serde(default)]
+    pub scenario_id: Option<String>,
+    #[serde(default)]
+    pub scenario_name: Option<String>,
+    #[serde(default)]
+    pub scenario_creator: Option<String>,
+    #[serde(default)]
+    pub scenario_source_url: Option<String>,
+    #[serde(default)]
+    pub lorebook_id: Option<String>,
+    /// Content packs the scenario and lorebook were installed from, as they
+    /// were when the story started
+    #[serde(default)]
+    pub packs: Vec<PackCredit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioCredit {
+    pub name: String,
+    pub creator: Option<String>,
+    pub source_url: Option<String>,
+}
+
+/// An installed content pack the story's scenario, lorebook or preset
+/// pack came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackCredit {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: Option<String>,
+}
+
+/// Provenance of a shared story
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attribution {
+    pub app_version: String,
+    pub scenario: Option<ScenarioCredit>,
+    pub packs: Vec<PackCredit>,
+    /// Local date of the export, e.g. 2026-10-17
+    pub exported_at: String,
+}
+
+/// An attribution with the footer rendered for an export format
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportAttribution {
+    pub attribution: Attribution,
+    pub footer: String,
+}
@@ -0,0 +1,121 @@
+use tauri::{AppHandle, State};
+
+use super::music;
+use super::packs;
+use super::select;
+use super::types::{AmbienceStatus, ChapterTheme, SoundPack};
+use super::Ambience;
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+#[tauri::command]
+pub fn list_sound_packs(ambience: State<'_, Ambience>) -> Vec<SoundPack> {
+    packs::list(ambience.packs_dir())
+}
+
+/// Install a sound pack archive (a zip with a `pack.json`)
+#[tauri::command]
+pub async fn install_sound_pack(
+    ambience: State<'_, Ambience>,
+    path: String,
+) -> Result<SoundPack, String> {
+    let packs_dir = ambience.packs_dir().to_path_buf();
+    tokio::task::spawn_blocking(move || packs::install(&packs_dir, path.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to install sound pack: {}", e))?
+}
+
+#[tauri::command]
+pub fn remove_sound_pack(ambience: State<'_, Ambience>, pack_id: String) -> Result<(), String> {
+    packs::remove(ambience.packs_dir(), &pack_id)
+}
+
+#[tauri::command]
+pub fn get_ambience(ambience: State<'_, Ambience>) -> AmbienceStatus {
+    ambience.status()
+}
+
+/// Play the ambience for a scene tag, or stop it with `None`
+#[tauri::command]
+pub fn set_ambience(
+    app: AppHandle,
+    ambience: State<'_, Ambience>,
+    tag: Option<String>,
+) -> Result<AmbienceStatus, String> {
+    ambience.play(&app, tag.as_deref())
+}
+
+/// Master volume for this session, from 0 to 1. The frontend keeps the
+/// choice in `ambience_settings`, which is read again at launch.
+#[tauri::command]
+pub fn set_ambience_volume(
+    app: AppHandle,
+    ambience: State<'_, Ambience>,
+    volume: f32,
+) -> AmbienceStatus {
+    ambience.set_volume(&app, volume)
+}
+
+/// Crossfade length for this session; saved like the volume
+#[tauri::command]
+pub fn set_ambience_crossfade(
+    app: AppHandle,
+    ambience: State<'_, Ambience>,
+    crossfade_ms: u64,
+) -> AmbienceStatus {
+    ambience.set_crossfade(&app, crossfade_ms)
+}
+
+/// Pick the ambience from the story's latest passages now
+#[tauri::command]
+pub async fn update_ambience(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Option<AmbienceStatus>, String> {
+    select::update(&app, db.pool(), &story_id).await
+}
+
+/// Queue a theme for a chapter, picked from the music library or generated
+/// by the music API. Returns the job id.
+#[tauri::command]
+pub async fn queue_chapter_theme(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    chapter_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::ChapterTheme,
+            serde_json::json!({ "chapterId": chapter_id }),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn get_chapter_theme(
+    db: State<'_, DbState>,
+    chapter_id: String,
+) -> Result<Option<ChapterTheme>, String> {
+    music::get_theme(db.pool(), &chapter_id).await
+}
+
+/// Copy a chapter's theme to `path`
+#[tauri::command]
+pub async fn download_chapter_theme(
+    db: State<'_, DbState>,
+    chapter_id: String,
+    path: String,
+) -> Result<(), String> {
+    let theme = music::get_theme(db.pool(), &chapter_id)
+        .await?
+        .ok_or("This chapter has no theme")?;
+    tokio::fs::copy(&theme.file_path, &path)
+        .await
+        .map_err(|e| format!("Failed to save theme: {}", e))?;
+    Ok(())
+}
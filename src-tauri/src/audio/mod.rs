@@ -0,0 +1,8 @@
+pub mod commands;
+pub mod music;
+pub mod packs;
+pub mod player;
+pub mod select;
+pub mod types;
+
+pub use player::Ambience;
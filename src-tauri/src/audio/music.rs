@@ -0,0 +1,314 @@
+//! Chapter themes: the chapter's mood is classified from its summary and
+//! tone, then a track is picked from the user's music library or
+//! generated by an API and cached. Runs as a background job.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::packs::AUDIO_EXTENSIONS;
+use super::types::{ChapterTheme, MusicSettings, MusicSource};
+use crate::db::now_millis;
+use crate::images::sprites::score_mood;
+use crate::images::types::Mood;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::config::get_setting;
+use crate::paths::DataPaths;
+use crate::storage::space;
+
+/// Generated tracks, under the machine-local data folder
+const CACHE_DIR: &str = "music-cache";
+
+/// Generation can take a while for longer tracks
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<MusicSettings, String> {
+    Ok(get_setting(pool, "music_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Music mood for a scene mood: the library folder name and how it's
+/// described to a generator
+fn music_mood(mood: Mood) -> (&'static str, &'static str) {
+    match mood {
+        Mood::Neutral => ("calm", "calm, gentle, understated"),
+        Mood::Happy => ("uplifting", "uplifting, warm, bright"),
+        Mood::Sad => ("melancholy", "melancholic, slow, bittersweet"),
+        Mood::Angry => ("intense", "intense, driving percussion, urgent"),
+        Mood::Surprised => ("mysterious", "mysterious, curious, shimmering"),
+        Mood::Afraid => ("tense", "tense, ominous, suspenseful"),
+    }
+}
+
+fn theme_from_row(r: &SqliteRow) -> ChapterTheme {
+    ChapterTheme {
+        chapter_id: r.get("chapter_id"),
+        story_id: r.get("story_id"),
+        mood: r.get("mood"),
+        source: match r.get::<String, _>("source").as_str() {
+            "api" => MusicSource::Api,
+            _ => MusicSource::Library,
+        },
+        title: r.get("title"),
+        file_path: r.get("file_path"),
+        prompt: r.get("prompt"),
+        created_at: r.get("created_at"),
+    }
+}
+
+pub async fn get_theme(
+    pool: &SqlitePool,
+    chapter_id: &str,
+) -> Result<Option<ChapterTheme>, String> {
+    let row = sqlx::query("SELECT * FROM chapter_themes WHERE chapter_id = ?")
+        .bind(chapter_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load chapter theme: {}", e))?;
+    Ok(row.as_ref().map(theme_from_row))
+}
+
+fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+fn audio_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && is_audio(path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A library track for the mood, avoiding the ones `used` lists (other
+/// chapters' themes) while there are others to choose from
+fn pick_from_library(library: &Path, folder: &str, used: &[String]) -> Result<PathBuf, String> {
+    let mut files = audio_files(&library.join(folder));
+    if files.is_empty() {
+        files = audio_files(library);
+    }
+    let fresh: Vec<&PathBuf> = files
+        .iter()
+        .filter(|f| !used.contains(&f.to_string_lossy().into_owned()))
+        .collect();
+    let pool: Vec<&PathBuf> = if fresh.is_empty() {
+        files.iter().collect()
+    } else {
+        fresh
+    };
+    pool.choose(&mut rand::thread_rng())
+        .map(|path| path.to_path_buf())
+        .ok_or_else(|| {
+            format!(
+                "No music found for '{}' in {}. Add audio files to a '{}' folder there.",
+                folder,
+                library.display(),
+                folder
+            )
+        })
+}
+
+fn extension_for(content_type: &str) -> &'static str {
+    match content_type.split(';').next().unwrap_or("").trim() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/ogg" => "ogg",
+        "audio/flac" => "flac",
+        "audio/webm" => "webm",
+        "audio/mp4" | "audio/aac" => "m4a",
+        _ => "mp3",
+    }
+}
+
+/// Generate a track, or reuse the cached one for the same prompt
+async fn generate(settings: &MusicSettings, cache: &Path, prompt: &str) -> Result<PathBuf, String> {
+    let url = settings
+        .api_url
+        .as_deref()
+        .filter(|url| !url.trim().is_empty())
+        .ok_or("Set the music API address in the music settings first")?;
+    let key = hex::encode(Sha256::digest(
+        format!("{}\n{}\n{}", url, settings.duration_secs, prompt).as_bytes(),
+    ));
+    if let Some(cached) = audio_files(cache)
+        .into_iter()
+        .find(|path| path.file_stem().and_then(|s| s.to_str()) == Some(key.as_str()))
+    {
+        return Ok(cached);
+    }
+
+    let client = reqwest::Client::new();
+    let mut call = client
+        .post(url)
+        .json(&json!({ "prompt": prompt, "duration": settings.duration_secs }))
+        .timeout(REQUEST_TIMEOUT);
+    if !settings.api_key.is_empty() {
+        call = call.bearer_auth(&settings.api_key);
+    }
+    let response = call
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Music request failed: {}", e))?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let (bytes, content_type) = if content_type.starts_with("application/json") {
+        let payload: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid music response: {}", e))?;
+        let format = payload
+            .get("format")
+            .and_then(Value::as_str)
+            .map_or("audio/mpeg".to_string(), |f| format!("audio/{}", f));
+        match (
+            payload.get("audio"),
+            payload.get("url").or(payload.get("audio_url")),
+        ) {
+            (Some(Value::String(audio)), _) => (
+                STANDARD
+                    .decode(audio)
+                    .map_err(|e| format!("Invalid music response: {}", e))?,
+                format,
+            ),
+            (_, Some(Value::String(url))) => {
+                let download = client
+                    .get(url)
+                    .timeout(REQUEST_TIMEOUT)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| format!("Failed to download music: {}", e))?;
+                let content_type = download
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_string();
+                let bytes = download
+                    .bytes()
+                    .await
+                    .map_err(|e| format!("Failed to download music: {}", e))?;
+                (bytes.to_vec(), content_type)
+            }
+            _ => return Err("The music response has no audio".to_string()),
+        }
+    } else {
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Invalid music response: {}", e))?;
+        (bytes.to_vec(), content_type)
+    };
+
+    std::fs::create_dir_all(cache).map_err(|e| format!("Failed to create music cache: {}", e))?;
+    space::ensure_free(cache, bytes.len() as u64)?;
+    let path = cache.join(format!("{}.{}", key, extension_for(&content_type)));
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|e| format!("Failed to save music: {}", e))?;
+    Ok(path)
+}
+
+/// Background job: pick or generate a theme for a chapter and attach it
+pub async fn run_theme_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<Value, String> {
+    let chapter_id = job
+        .payload
+        .get("chapterId")
+        .and_then(|v| v.as_str())
+        .ok_or("Chapter theme job is missing chapterId")?;
+    let settings = load_settings(pool).await?;
+    let row: (String, Option<String>, String, Option<String>) = sqlx::query_as(
+        "SELECT story_id, title, summary, emotional_tone FROM chapters WHERE id = ?",
+    )
+    .bind(chapter_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load chapter: {}", e))?
+    .ok_or_else(|| format!("Chapter not found: {}", chapter_id))?;
+    let (story_id, title, summary, tone) = row;
+
+    // The tone is the chapter summarizer's own read of the mood
+    let mood = score_mood(&format!("{}\n{}", tone.unwrap_or_default(), summary));
+    let (folder, description) = music_mood(mood);
+    let title = title.filter(|t| !t.trim().is_empty());
+
+    let (path, prompt) = match settings.source {
+        MusicSource::Library => {
+            let library = settings
+                .library_path
+                .as_deref()
+                .filter(|path| !path.trim().is_empty())
+                .ok_or("Choose a music library folder in the music settings first")?;
+            let used: Vec<String> = sqlx::query_scalar(
+                "SELECT file_path FROM chapter_themes WHERE story_id = ? AND chapter_id != ?",
+            )
+            .bind(&story_id)
+            .bind(chapter_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load chapter themes: {}", e))?;
+            (pick_from_library(Path::new(library), folder, &used)?, None)
+        }
+        MusicSource::Api => {
+            let mut prompt = format!("{}, instrumental, loopable background theme", description);
+            if !settings.style.trim().is_empty() {
+                prompt = format!("{}, {}", prompt, settings.style.trim());
+            }
+            let cache = app.state::<DataPaths>().local.join(CACHE_DIR);
+            (generate(&settings, &cache, &prompt).await?, Some(prompt))
+        }
+    };
+    let track_title = match (&title, settings.source) {
+        (Some(title), MusicSource::Api) => format!("{} theme", title),
+        _ => path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Theme")
+            .to_string(),
+    };
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO chapter_themes (chapter_id, story_id, mood, source, title, \
+         file_path, prompt, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(chapter_id)
+    .bind(&story_id)
+    .bind(folder)
+    .bind(match settings.source {
+        MusicSource::Library => "library",
+        MusicSource::Api => "api",
+    })
+    .bind(&track_title)
+    .bind(path.to_string_lossy().as_ref())
+    .bind(&prompt)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save chapter theme: {}", e))?;
+    let theme = get_theme(pool, chapter_id)
+        .await?
+        .ok_or("Failed to save chapter theme")?;
+    serde_json::to_value(&theme).map_err(|e| format!("Failed to serialize chapter theme: {}", e))
+}
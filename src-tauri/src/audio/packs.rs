@@ -0,0 +1,157 @@
+//! Sound packs: zip archives with a `pack.json` manifest and the audio
+//! files it lists, unpacked into their own folder under the app's data.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::types::{PackManifest, SoundPack};
+use crate::storage::space;
+
+const MANIFEST_PATH: &str = "pack.json";
+
+pub const AUDIO_EXTENSIONS: &[&str] = &["ogg", "opus", "mp3", "wav", "flac", "m4a", "webm"];
+
+/// Largest audio file a pack may hold
+const MAX_FILE_BYTES: u64 = 200 * 1024 * 1024;
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn read_manifest(dir: &Path) -> Result<PackManifest, String> {
+    let raw = std::fs::read(dir.join(MANIFEST_PATH))
+        .map_err(|e| format!("Failed to read sound pack: {}", e))?;
+    serde_json::from_slice(&raw).map_err(|e| format!("Invalid sound pack manifest: {}", e))
+}
+
+/// Installed packs, by name. Folders without a readable manifest are
+/// skipped.
+pub fn list(packs_dir: &Path) -> Vec<SoundPack> {
+    let Ok(entries) = std::fs::read_dir(packs_dir) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<SoundPack> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let manifest = read_manifest(&dir).ok()?;
+            Some(SoundPack {
+                manifest,
+                size: space::dir_size(&dir),
+                path: dir.to_string_lossy().into_owned(),
+            })
+        })
+        .collect();
+    packs.sort_by_key(|p| p.manifest.name.to_lowercase());
+    packs
+}
+
+/// Install a pack archive, replacing an installed pack with the same id
+pub fn install(packs_dir: &Path, archive: &Path) -> Result<SoundPack, String> {
+    let file =
+        std::fs::File::open(archive).map_err(|e| format!("Failed to open sound pack: {}", e))?;
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Not a valid sound pack: {}", e))?;
+
+    let manifest: PackManifest = {
+        let mut entry = zip
+            .by_name(MANIFEST_PATH)
+            .map_err(|_| "The sound pack has no pack.json")?;
+        let mut raw = Vec::new();
+        entry
+            .read_to_end(&mut raw)
+            .map_err(|e| format!("Failed to read sound pack: {}", e))?;
+        serde_json::from_slice(&raw).map_err(|e| format!("Invalid sound pack manifest: {}", e))?
+    };
+    if !valid_id(&manifest.id) {
+        return Err(format!("Invalid sound pack id: {}", manifest.id));
+    }
+    if manifest.tracks.is_empty() {
+        return Err("The sound pack has no tracks".to_string());
+    }
+    let mut needed = 0;
+    for track in &manifest.tracks {
+        let extension = Path::new(&track.file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        if !AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(format!("{} isn't a supported audio file", track.file));
+        }
+        let entry = zip
+            .by_name(&track.file)
+            .map_err(|_| format!("The sound pack is missing {}", track.file))?;
+        if entry.size() > MAX_FILE_BYTES {
+            return Err(format!("{} is too large", track.file));
+        }
+        needed += entry.size();
+    }
+    std::fs::create_dir_all(packs_dir)
+        .map_err(|e| format!("Failed to create sound pack folder: {}", e))?;
+    space::ensure_free(packs_dir, needed)?;
+
+    // Unpack beside the final folder, then swap it in
+    let target = packs_dir.join(&manifest.id);
+    let partial = packs_dir.join(format!(".{}.partial", manifest.id));
+    let _ = std::fs::remove_dir_all(&partial);
+    let unpacked = unpack(&mut zip, &manifest, &partial);
+    if let Err(e) = unpacked {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+    if target.exists() {
+        std::fs::remove_dir_all(&target)
+            .map_err(|e| format!("Failed to replace the installed pack: {}", e))?;
+    }
+    std::fs::rename(&partial, &target)
+        .map_err(|e| format!("Failed to install sound pack: {}", e))?;
+    Ok(SoundPack {
+        size: space::dir_size(&target),
+        path: target.to_string_lossy().into_owned(),
+        manifest,
+    })
+}
+
+/// Copy the manifest and the files it lists, nothing else
+fn unpack(
+    zip: &mut zip::ZipArchive<std::fs::File>,
+    manifest: &PackManifest,
+    dir: &Path,
+) -> Result<(), String> {
+    let files =
+        std::iter::once(MANIFEST_PATH).chain(manifest.tracks.iter().map(|t| t.file.as_str()));
+    for name in files {
+        let mut entry = zip
+            .by_name(name)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        let relative: PathBuf = entry
+            .enclosed_name()
+            .ok_or_else(|| format!("Sound pack contains an unsafe path: {}", name))?;
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create sound pack folder: {}", e))?;
+        }
+        let mut out =
+            std::fs::File::create(&path).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        std::io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+pub fn remove(packs_dir: &Path, id: &str) -> Result<(), String> {
+    if !valid_id(id) {
+        return Err(format!("Invalid sound pack id: {}", id));
+    }
+    let dir = packs_dir.join(id);
+    if !dir.exists() {
+        return Err(format!("Sound pack not found: {}", id));
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove sound pack: {}", e))
+}
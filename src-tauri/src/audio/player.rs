@@ -0,0 +1,128 @@
+use rand::seq::SliceRandom;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::packs;
+use super::select::load_settings;
+use super::types::AmbienceStatus;
+use crate::db::DbState;
+
+/// What ambience plays. Audio output is left to the webview, which follows
+/// the `ambience` event; this keeps the state and picks the files.
+pub struct Ambience {
+    packs_dir: PathBuf,
+    status: Mutex<AmbienceStatus>,
+}
+
+impl Ambience {
+    pub fn new(packs_dir: PathBuf) -> Self {
+        Self {
+            packs_dir,
+            status: Mutex::new(AmbienceStatus::default()),
+        }
+    }
+
+    pub fn packs_dir(&self) -> &Path {
+        &self.packs_dir
+    }
+
+    pub fn status(&self) -> AmbienceStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn update(&self, app: &AppHandle, change: impl FnOnce(&mut AmbienceStatus)) -> AmbienceStatus {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            change(&mut status);
+            status.clone()
+        };
+        if let Err(e) = app.emit("ambience", &status) {
+            eprintln!("Failed to emit ambience event: {}", e);
+        }
+        status
+    }
+
+    /// Play a track for `tag`, or stop with `None`. A tag that is already
+    /// playing keeps its track; otherwise one of the installed tracks for
+    /// it is picked at random.
+    pub fn play(&self, app: &AppHandle, tag: Option<&str>) -> Result<AmbienceStatus, String> {
+        let Some(tag) = tag else {
+            return Ok(self.update(app, |status| {
+                status.tag = None;
+                status.pack_id = None;
+                status.track = None;
+                status.volume = 0.0;
+            }));
+        };
+        let current = self.status();
+        if current
+            .tag
+            .as_deref()
+            .is_some_and(|playing| playing.eq_ignore_ascii_case(tag))
+        {
+            return Ok(current);
+        }
+        let candidates: Vec<_> = packs::list(&self.packs_dir)
+            .into_iter()
+            .flat_map(|pack| {
+                let dir = PathBuf::from(&pack.path);
+                let id = pack.manifest.id.clone();
+                pack.manifest
+                    .tracks
+                    .into_iter()
+                    .filter(|track| track.tag.eq_ignore_ascii_case(tag))
+                    .map(move |track| (id.clone(), dir.join(&track.file), track.volume))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let (pack_id, file, volume) = candidates
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .ok_or_else(|| format!("No installed sound pack has a track for '{}'", tag))?;
+        Ok(self.update(app, |status| {
+            status.tag = Some(tag.to_lowercase());
+            status.pack_id = Some(pack_id);
+            status.track = Some(file.to_string_lossy().into_owned());
+            status.volume = status.master_volume * volume.clamp(0.0, 1.0);
+        }))
+    }
+
+    pub fn set_volume(&self, app: &AppHandle, volume: f32) -> AmbienceStatus {
+        let volume = volume.clamp(0.0, 1.0);
+        self.update(app, |status| {
+            // Keep the track's own level relative to the master volume
+            let track_volume = if status.master_volume > 0.0 {
+                status.volume / status.master_volume
+            } else {
+                1.0
+            };
+            status.master_volume = volume;
+            if status.track.is_some() {
+                status.volume = volume * track_volume;
+            }
+        })
+    }
+
+    pub fn set_crossfade(&self, app: &AppHandle, crossfade_ms: u64) -> AmbienceStatus {
+        self.update(app, |status| status.crossfade_ms = crossfade_ms)
+    }
+}
+
+/// Apply the saved volume and crossfade at launch
+pub fn restore(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The settings table only exists once the frontend has run migrations
+        let settings = loop {
+            match load_settings(&pool).await {
+                Ok(settings) => break settings,
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        let ambience = app.state::<Ambience>();
+        ambience.set_volume(&app, settings.volume);
+        ambience.set_crossfade(&app, settings.crossfade_ms);
+    });
+}
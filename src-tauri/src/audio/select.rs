@@ -0,0 +1,168 @@
+//! Picking the ambience for a story from its latest passages: keywords
+//! for each scene tag, weighted toward the newest passage, with the
+//! passage's mood nudging toward tense tags.
+
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+use super::packs;
+use super::types::{AmbienceSettings, AmbienceStatus};
+use super::Ambience;
+use crate::context::branch::visible_entries;
+use crate::images::sprites::score_mood;
+use crate::images::types::Mood;
+use crate::llm::config::get_setting;
+
+/// Built-in words for common tags; packs can add their own
+const KEYWORDS: &[(&str, &str)] = &[
+    (
+        "tavern",
+        "tavern inn ale mead beer bartender barkeep pub tankard innkeeper barmaid",
+    ),
+    (
+        "forest",
+        "forest woods tree trees grove thicket undergrowth pine oak leaves glade",
+    ),
+    (
+        "battle",
+        "battle fight sword clash attack charge arrow blood shield soldier combat war",
+    ),
+    (
+        "city",
+        "city street market crowd merchant square alley town cobblestone",
+    ),
+    ("cave", "cave cavern tunnel stalactite underground dripping"),
+    ("dungeon", "dungeon cell chains torch crypt catacomb prison"),
+    ("sea", "sea ocean ship deck wave sail harbor shore tide"),
+    ("rain", "rain storm thunder downpour drizzle lightning"),
+    ("night", "night moon star crickets midnight darkness"),
+    ("camp", "campfire camp tent embers bedroll"),
+];
+
+/// Tags a tense or frightened passage leans toward
+const TENSE_TAGS: &[&str] = &["battle", "combat", "tension", "danger", "chase"];
+
+/// Weight of each older passage relative to the one after it
+const RECENCY_DECAY: f32 = 0.6;
+
+/// Score a tag needs before it's picked at all
+const MIN_SCORE: f32 = 2.0;
+
+/// How much a new tag has to outscore the playing one to replace it, so
+/// a single stray word doesn't switch the ambience
+const SWITCH_MARGIN: f32 = 1.5;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<AmbienceSettings, String> {
+    Ok(get_setting(pool, "ambience_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Keyword matches, allowing a plural "s"
+fn count_matches(words: &[String], keywords: &[String]) -> usize {
+    words
+        .iter()
+        .filter(|word| {
+            keywords
+                .iter()
+                .any(|k| *word == k || word.strip_suffix('s') == Some(k.as_str()))
+        })
+        .count()
+}
+
+/// Score each tag against passages, oldest first
+pub fn score_tags(tags: &HashMap<String, Vec<String>>, passages: &[&str]) -> HashMap<String, f32> {
+    let mut scores: HashMap<String, f32> = HashMap::new();
+    let mut weight = 1.0;
+    for (newest, passage) in passages.iter().rev().enumerate() {
+        let words: Vec<String> = passage
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let tense = newest == 0 && matches!(score_mood(passage), Mood::Angry | Mood::Afraid);
+        for (tag, keywords) in tags {
+            let mut score = count_matches(&words, keywords) as f32;
+            if tense && TENSE_TAGS.contains(&tag.as_str()) {
+                score += 1.5;
+            }
+            *scores.entry(tag.clone()).or_default() += score * weight;
+        }
+        weight *= RECENCY_DECAY;
+    }
+    scores
+}
+
+/// The tag to play for the passages, if one stands out; the playing tag
+/// stays unless another clearly beats it
+pub fn pick(
+    tags: &HashMap<String, Vec<String>>,
+    passages: &[&str],
+    playing: Option<&str>,
+) -> Option<String> {
+    let scores = score_tags(tags, passages);
+    let (best, score) = scores
+        .iter()
+        .filter(|(_, score)| **score >= MIN_SCORE)
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    let current = playing
+        .and_then(|tag| scores.get(tag))
+        .copied()
+        .unwrap_or(0.0);
+    if Some(best.as_str()) != playing && current * SWITCH_MARGIN >= *score {
+        return playing.map(str::to_string);
+    }
+    Some(best.clone())
+}
+
+/// Tags of the installed packs with the words that suggest them
+fn installed_tags(ambience: &Ambience) -> HashMap<String, Vec<String>> {
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for pack in packs::list(ambience.packs_dir()) {
+        for track in pack.manifest.tracks {
+            let tag = track.tag.to_lowercase();
+            let keywords = tags.entry(tag.clone()).or_insert_with(|| {
+                let built_in = KEYWORDS
+                    .iter()
+                    .find(|(name, _)| *name == tag)
+                    .map_or("", |(_, words)| *words);
+                std::iter::once(tag.as_str())
+                    .chain(built_in.split_whitespace())
+                    .map(str::to_string)
+                    .collect()
+            });
+            keywords.extend(track.keywords.iter().map(|k| k.to_lowercase()));
+        }
+    }
+    tags
+}
+
+/// Switch the ambience to suit the story's latest passages, when it's on
+/// and set to follow the story. Returns the status when it was checked.
+pub async fn update(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Option<AmbienceStatus>, String> {
+    let settings = load_settings(pool).await?;
+    if !settings.enabled || !settings.auto_select {
+        return Ok(None);
+    }
+    let ambience = app.state::<Ambience>();
+    let tags = installed_tags(&ambience);
+    if tags.is_empty() {
+        return Ok(None);
+    }
+    let entries = visible_entries(pool, story_id).await?;
+    let recent: Vec<&str> = entries[entries.len().saturating_sub(settings.recent_entries.max(1))..]
+        .iter()
+        .map(|e| e.content.as_str())
+        .collect();
+    let playing = ambience.status().tag;
+    match pick(&tags, &recent, playing.as_deref()) {
+        Some(tag) => ambience.play(app, Some(&tag)).map(Some),
+        None => Ok(Some(ambience.status())),
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+/// `pack.json` at the root of a sound pack archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackManifest {
+    /// Folder name the pack installs to; letters, digits, `-` and `_`
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub version: String,
+    pub tracks: Vec<PackTrack>,
+}
+
+/// A looping ambience track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackTrack {
+    /// Scene tag the track plays for, e.g. "tavern", "forest", "battle"
+    pub tag: String,
+    /// Audio file inside the pack
+    pub file: String,
+    /// Loudness relative to the pack's other tracks, from 0 to 1
+    #[serde(default = "full_volume")]
+    pub volume: f32,
+    /// Words that suggest the tag, on top of the built-in ones
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+fn full_volume() -> f32 {
+    1.0
+}
+
+/// An installed pack
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoundPack {
+    #[serde(flatten)]
+    pub manifest: PackManifest,
+    pub path: String,
+    pub size: u64,
+}
+
+/// Ambience options, stored in `ambience_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AmbienceSettings {
+    pub enabled: bool,
+    /// Pick the tag from the story after each response
+    pub auto_select: bool,
+    /// Master volume, from 0 to 1
+    pub volume: f32,
+    /// How long one track fades into the next
+    pub crossfade_ms: u64,
+    /// Passages the tag is picked from
+    pub recent_entries: usize,
+}
+
+impl Default for AmbienceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_select: true,
+            volume: 0.5,
+            crossfade_ms: 3000,
+            recent_entries: 3,
+        }
+    }
+}
+
+/// What should be playing, emitted on `ambience` whenever it changes. The
+/// webview plays `track` on a loop and fades between tracks over
+/// `crossfade_ms`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmbienceStatus {
+    /// `None` when nothing plays
+    pub tag: Option<String>,
+    pub pack_id: Option<String>,
+    /// Absolute path of the audio file
+    pub track: Option<String>,
+    pub master_volume: f32,
+    /// Master volume times the track's own volume
+    pub volume: f32,
+    pub crossfade_ms: u64,
+}
+
+impl Default for AmbienceStatus {
+    fn default() -> Self {
+        let settings = AmbienceSettings::default();
+        Self {
+            tag: None,
+            pack_id: None,
+            track: None,
+            master_volume: settings.volume,
+            volume: 0.0,
+            crossfade_ms: settings.crossfade_ms,
+        }
+    }
+}
+
+/// Where chapter themes come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MusicSource {
+    /// Files from a folder with one subfolder per mood
+    #[default]
+    Library,
+    /// A music generation API
+    Api,
+}
+
+/// Chapter theme options, stored in `music_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MusicSettings {
+    pub enabled: bool,
+    pub source: MusicSource,
+    /// Folder with `calm`, `uplifting`, `melancholy`, `intense`,
+    /// `mysterious` and `tense` subfolders; files directly in it are used
+    /// for any mood without its own
+    pub library_path: Option<String>,
+    /// Endpoint taking `{"prompt", "duration"}` and answering with audio,
+    /// or JSON with `audio` (base64) or `url`
+    pub api_url: Option<String>,
+    pub api_key: String,
+    /// Added to every generation prompt, e.g. "orchestral, medieval"
+    pub style: String,
+    pub duration_secs: u32,
+}
+
+impl Default for MusicSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: MusicSource::Library,
+            library_path: None,
+            api_url: None,
+            api_key: String::new(),
+            style: String::new(),
+            duration_secs: 60,
+        }
+    }
+}
+
+/// A chapter's theme (a row of `chapter_themes`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterTheme {
+    pub chapter_id: String,
+    pub story_id: String,
+    pub mood: String,
+    pub source: MusicSource,
+    pub title: String,
+    pub file_path: String,
+    pub prompt: Option<String>,
+    pub created_at: i64,
+}
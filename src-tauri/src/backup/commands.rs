@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use super::types::{BackupManifest, BackupRunResult, BackupSummary, ChainEntry, ChainVerification};
+use super::{incremental, keychain, s3};
+use crate::db::DbState;
+use crate::file_import::progress::ImportTask;
+use crate::paths::DataPaths;
+
+/// Store the access keys for the S3 backup bucket in the OS keychain
+#[tauri::command]
+pub async fn set_s3_backup_credentials(
+    access_key_id: String,
+    secret_access_key: String,
+) -> Result<(), String> {
+    keychain::set_secret(s3::ACCESS_KEY_SECRET, access_key_id.trim())?;
+    keychain::set_secret(s3::SECRET_KEY_SECRET, secret_access_key.trim())
+}
+
+#[tauri::command]
+pub async fn clear_s3_backup_credentials() -> Result<(), String> {
+    keychain::delete_secret(s3::ACCESS_KEY_SECRET)?;
+    keychain::delete_secret(s3::SECRET_KEY_SECRET)
+}
+
+#[tauri::command]
+pub async fn has_s3_backup_credentials() -> Result<bool, String> {
+    Ok(keychain::get_secret(s3::ACCESS_KEY_SECRET)?.is_some()
+        && keychain::get_secret(s3::SECRET_KEY_SECRET)?.is_some())
+}
+
+#[tauri::command]
+pub async fn run_s3_backup(
+    app: AppHandle,
+    db: State<'_, DbState>,
+) -> Result<BackupRunResult, String> {
+    let settings = s3::load_settings(db.pool()).await?;
+    let client = s3::client(&settings)?;
+    s3::run_backup(
+        db.pool(),
+        &client,
+        &settings,
+        app.package_info().version.to_string(),
+    )
+    .await
+}
+
+/// Backups in the bucket, newest first
+#[tauri::command]
+pub async fn list_s3_backups(db: State<'_, DbState>) -> Result<Vec<BackupSummary>, String> {
+    let settings = s3::load_settings(db.pool()).await?;
+    s3::list_backups(&s3::client(&settings)?, &settings).await
+}
+
+/// The stories in one backup, for choosing what to restore
+#[tauri::command]
+pub async fn get_s3_backup(
+    db: State<'_, DbState>,
+    backup_id: String,
+) -> Result<BackupManifest, String> {
+    let settings = s3::load_settings(db.pool()).await?;
+    s3::load_manifest(&s3::client(&settings)?, &settings, &backup_id).await
+}
+
+/// Restore one story from a backup, replacing the current version or,
+/// with `as_copy`, next to it. Returns the restored story's id. Reports
+/// `import-progress` under `import_id` and stops on `cancel_import`.
+#[tauri::command]
+pub async fn restore_s3_backup_story(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    backup_id: String,
+    story_id: String,
+    as_copy: bool,
+    import_id: Option<String>,
+) -> Result<String, String> {
+    let mut task = ImportTask::start(&app, import_id, "backup");
+    let result = async {
+        let settings = s3::load_settings(db.pool()).await?;
+        let client = s3::client(&settings)?;
+        s3::restore_story(
+            db.pool(),
+            &client,
+            &settings,
+            &backup_id,
+            &story_id,
+            as_copy,
+            &mut task,
+        )
+        .await
+    }
+    .await;
+    task.finish(result)
+}
+
+fn incremental_dir(paths: &DataPaths) -> PathBuf {
+    paths.backups().join("incremental")
+}
+
+/// Back up the database locally, storing only what changed since the last
+/// backup unless `full` is set or the database was migrated since
+#[tauri::command]
+pub async fn run_incremental_backup(
+    paths: State<'_, DataPaths>,
+    db: State<'_, DbState>,
+    full: bool,
+) -> Result<ChainEntry, String> {
+    incremental::run_backup(db.pool(), &incremental_dir(&paths), full).await
+}
+
+/// Local incremental backups, oldest first
+#[tauri::command]
+pub async fn list_incremental_backups(
+    paths: State<'_, DataPaths>,
+) -> Result<Vec<ChainEntry>, String> {
+    Ok(incremental::load_index(&incremental_dir(&paths))?.entries)
+}
+
+/// Rebuild the database as it was at `backup_id` into a separate file and
+/// return its path. The live database isn't touched.
+#[tauri::command]
+pub async fn reconstruct_incremental_backup(
+    paths: State<'_, DataPaths>,
+    backup_id: String,
+) -> Result<String, String> {
+    let dir = incremental_dir(&paths);
+    let index = incremental::load_index(&dir)?;
+    let restored = dir.join("restored");
+    std::fs::create_dir_all(&restored)
+        .map_err(|e| format!("Failed to create restore folder: {}", e))?;
+    let target = restored.join(format!("aventura-{}.db", backup_id));
+    incremental::reconstruct(&dir, &index, &backup_id, &target).await?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn verify_backup_chain(paths: State<'_, DataPaths>) -> Result<ChainVerification, String> {
+    incremental::verify_chain(&incremental_dir(&paths)).await
+}
+
+/// Restore one story from a local backup without rolling back anything
+/// else. With `as_copy` the current story is kept and the backup is added
+/// beside it. Returns the restored story's id. Reports `import-progress`
+/// under `import_id` and stops on `cancel_import`.
+#[tauri::command]
+pub async fn restore_story_from_backup(
+    app: AppHandle,
+    paths: State<'_, DataPaths>,
+    db: State<'_, DbState>,
+    backup_id: String,
+    story_id: String,
+    as_copy: bool,
+    import_id: Option<String>,
+) -> Result<String, String> {
+    let dir = incremental_dir(&paths);
+    let mut task = ImportTask::start(&app, import_id, "backup");
+    let result =
+        incremental::restore_story(db.pool(), &dir, &backup_id, &story_id, as_copy, &mut task)
+            .await;
+    task.finish(result)
+}
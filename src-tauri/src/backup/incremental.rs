@@ -0,0 +1,695 @@
+//! Local incremental backups. The first backup copies the whole database
+//! (`VACUUM INTO`); later ones store only rows changed since the one before,
+//! found through `backup_change_log`, which triggers on every table fill.
+//! Any backup can be turned back into a database by taking the full copy
+//! its chain starts from and replaying the diffs up to it.
+//!
+//! Files in the backup directory:
+//! - `index.json`: every backup, oldest first
+//! - `{backup_id}.full.zip`: `aventura.db` as of that backup
+//! - `{backup_id}.diff.zip`: `changes.json`, a `DiffArchive`
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::snapshot::{self, bind_value, quote, row_to_json, table_columns};
+use super::types::{
+    BackupKind, ChainEntry, ChainIndex, ChainProblem, ChainVerification, DiffArchive, TableDiff,
+};
+use crate::db::now_millis;
+use crate::file_import::progress::ImportTask;
+use crate::storage::space;
+
+const CHANGE_LOG: &str = "backup_change_log";
+
+/// Tables whose changes aren't replayed: the log itself and migration
+/// bookkeeping, which a full backup always carries
+const UNTRACKED: &[&str] = &[CHANGE_LOG, "_sqlx_migrations"];
+
+const INDEX_FILE: &str = "index.json";
+const DATABASE_ENTRY: &str = "aventura.db";
+const CHANGES_ENTRY: &str = "changes.json";
+
+/// Rowids per statement, under SQLite's bound parameter limit
+const ROWID_CHUNK: usize = 500;
+
+async fn tracked_tables(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' \
+         AND sql NOT LIKE 'CREATE VIRTUAL%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read schema: {}", e))?;
+    Ok(tables
+        .into_iter()
+        .filter(|name| !UNTRACKED.contains(&name.as_str()))
+        .collect())
+}
+
+fn trigger_name(table: &str, event: &str) -> String {
+    quote(&format!("backup_log_{}_{}", table, event))
+}
+
+/// Make sure every table logs its changes. Returns how many tables had no
+/// triggers yet; their earlier changes weren't logged.
+async fn install_triggers(pool: &SqlitePool) -> Result<usize, String> {
+    let existing: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'trigger' AND name LIKE 'backup_log_%'",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read schema: {}", e))?;
+
+    let mut installed = 0;
+    for table in tracked_tables(pool).await? {
+        let events = ["insert", "update", "delete"];
+        if events
+            .iter()
+            .all(|event| existing.contains(&format!("backup_log_{}_{}", table, event)))
+        {
+            continue;
+        }
+        let name = table.replace('\'', "''");
+        let statements = [
+            format!(
+                "CREATE TRIGGER IF NOT EXISTS {} AFTER INSERT ON {} BEGIN \
+                 INSERT INTO {} (table_name, row_id) VALUES ('{}', NEW.rowid); END",
+                trigger_name(&table, "insert"),
+                quote(&table),
+                CHANGE_LOG,
+                name
+            ),
+            // An update can move a row to a new rowid; log both ends
+            format!(
+                "CREATE TRIGGER IF NOT EXISTS {} AFTER UPDATE ON {} BEGIN \
+                 INSERT INTO {log} (table_name, row_id) VALUES ('{name}', NEW.rowid); \
+                 INSERT INTO {log} (table_name, row_id) SELECT '{name}', OLD.rowid \
+                 WHERE OLD.rowid <> NEW.rowid; END",
+                trigger_name(&table, "update"),
+                quote(&table),
+                log = CHANGE_LOG,
+                name = name
+            ),
+            format!(
+                "CREATE TRIGGER IF NOT EXISTS {} AFTER DELETE ON {} BEGIN \
+                 INSERT INTO {} (table_name, row_id) VALUES ('{}', OLD.rowid); END",
+                trigger_name(&table, "delete"),
+                quote(&table),
+                CHANGE_LOG,
+                name
+            ),
+        ];
+        for sql in statements {
+            sqlx::query(&sql)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to track changes to {}: {}", table, e))?;
+        }
+        installed += 1;
+    }
+    Ok(installed)
+}
+
+/// Identifies the table layout, so diffs are never replayed onto a
+/// database migrated in between
+async fn schema_hash(pool: &SqlitePool) -> Result<String, String> {
+    let definitions: Vec<String> = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type IN ('table', 'index') \
+         AND name NOT LIKE 'sqlite_%' AND sql IS NOT NULL ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read schema: {}", e))?;
+    Ok(hex::encode(Sha256::digest(definitions.join("\n"))))
+}
+
+pub fn load_index(dir: &Path) -> Result<ChainIndex, String> {
+    match std::fs::read(dir.join(INDEX_FILE)) {
+        Ok(bytes) => {
+            serde_json::from_slice(&bytes).map_err(|e| format!("Invalid backup index: {}", e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ChainIndex::default()),
+        Err(e) => Err(format!("Failed to read backup index: {}", e)),
+    }
+}
+
+fn save_index(dir: &Path, index: &ChainIndex) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(index)
+        .map_err(|e| format!("Failed to serialize backup index: {}", e))?;
+    let partial = dir.join(format!("{}.partial", INDEX_FILE));
+    std::fs::write(&partial, bytes)
+        .and_then(|_| std::fs::rename(&partial, dir.join(INDEX_FILE)))
+        .map_err(|e| format!("Failed to write backup index: {}", e))
+}
+
+fn hash_file(path: &Path) -> Result<(String, u64), String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+fn zip_options() -> zip::write::SimpleFileOptions {
+    zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true)
+}
+
+/// Back up the database at `pool` into `dir`: a diff against the latest
+/// backup when possible, otherwise (or with `force_full`) a full copy
+pub async fn run_backup(
+    pool: &SqlitePool,
+    dir: &Path,
+    force_full: bool,
+) -> Result<ChainEntry, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create backup folder: {}", e))?;
+    let mut index = load_index(dir)?;
+    let untracked = install_triggers(pool).await?;
+    let schema_hash = schema_hash(pool).await?;
+    let backup_id = Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
+
+    let parent = index
+        .entries
+        .last()
+        .filter(|last| !force_full && untracked == 0 && last.schema_hash == schema_hash)
+        .map(|last| last.backup_id.clone());
+    let (entry, covered_seq) = match parent {
+        Some(parent) => {
+            space::ensure_free(dir, 0)?;
+            diff_backup(pool, dir, backup_id, parent, schema_hash).await?
+        }
+        None => {
+            // The uncompressed copy and the archive exist side by side
+            space::ensure_free(dir, space::used_bytes(pool).await * 2)?;
+            full_backup(pool, dir, backup_id, schema_hash).await?
+        }
+    };
+    index.entries.push(entry.clone());
+    save_index(dir, &index)?;
+
+    // Only now that the backup is recorded can its changes be forgotten
+    sqlx::query(&format!("DELETE FROM {} WHERE seq <= ?", CHANGE_LOG))
+        .bind(covered_seq)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to clear change log: {}", e))?;
+    Ok(entry)
+}
+
+async fn last_seq(conn: impl sqlx::SqliteExecutor<'_>) -> Result<i64, String> {
+    sqlx::query_scalar::<_, Option<i64>>(&format!("SELECT MAX(seq) FROM {}", CHANGE_LOG))
+        .fetch_one(conn)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Failed to read change log: {}", e))
+}
+
+/// Returns the entry and the last change log position it includes
+async fn full_backup(
+    pool: &SqlitePool,
+    dir: &Path,
+    backup_id: String,
+    schema_hash: String,
+) -> Result<(ChainEntry, i64), String> {
+    // Changes logged after this point may also be in the copy; replaying
+    // them again in the next diff is harmless
+    let covered_seq = last_seq(pool).await?;
+    let copy = dir.join(format!("{}.db.partial", backup_id));
+    let _ = std::fs::remove_file(&copy);
+    sqlx::query("VACUUM INTO ?")
+        .bind(copy.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to copy database: {}", e))?;
+
+    let file = format!("{}.full.zip", backup_id);
+    let archive = dir.join(&file);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let zipped = zip_file(&copy, &archive);
+        let _ = std::fs::remove_file(&copy);
+        zipped.and_then(|_| hash_file(&archive))
+    })
+    .await
+    .map_err(|e| format!("Failed to write backup: {}", e))?;
+    let (sha256, size) = result?;
+
+    Ok((
+        ChainEntry {
+            backup_id,
+            kind: BackupKind::Full,
+            parent: None,
+            file,
+            sha256,
+            size,
+            created_at: now_millis(),
+            schema_hash,
+            changed_rows: 0,
+        },
+        covered_seq,
+    ))
+}
+
+fn zip_file(source: &Path, archive: &Path) -> Result<(), String> {
+    let mut input =
+        File::open(source).map_err(|e| format!("Failed to read database copy: {}", e))?;
+    let output = File::create(archive).map_err(|e| format!("Failed to write backup: {}", e))?;
+    let mut zip = zip::ZipWriter::new(output);
+    zip.start_file(DATABASE_ENTRY, zip_options())
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    std::io::copy(&mut input, &mut zip).map_err(|e| format!("Failed to write backup: {}", e))?;
+    zip.finish()
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    Ok(())
+}
+
+async fn diff_backup(
+    pool: &SqlitePool,
+    dir: &Path,
+    backup_id: String,
+    parent: String,
+    schema_hash: String,
+) -> Result<(ChainEntry, i64), String> {
+    // One read transaction, so the rows match the log position exactly
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to read changes: {}", e))?;
+    let covered_seq = last_seq(&mut *tx).await?;
+    let changes: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT DISTINCT table_name, row_id FROM {} WHERE seq <= ? ORDER BY table_name, row_id",
+        CHANGE_LOG
+    ))
+    .bind(covered_seq)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to read change log: {}", e))?;
+
+    let mut by_table: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for (table, row_id) in changes {
+        by_table.entry(table).or_default().push(row_id);
+    }
+    let mut changed_rows = 0;
+    let mut tables = BTreeMap::new();
+    for (table, row_ids) in by_table {
+        let diff = read_rows(&mut tx, &table, &row_ids).await?;
+        changed_rows += row_ids.len();
+        tables.insert(table, diff);
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to read changes: {}", e))?;
+
+    let archive = DiffArchive {
+        backup_id: backup_id.clone(),
+        parent: parent.clone(),
+        tables,
+    };
+    let json =
+        serde_json::to_vec(&archive).map_err(|e| format!("Failed to serialize changes: {}", e))?;
+    let file = format!("{}.diff.zip", backup_id);
+    let path = dir.join(&file);
+    let output = File::create(&path).map_err(|e| format!("Failed to write backup: {}", e))?;
+    let mut zip = zip::ZipWriter::new(output);
+    zip.start_file(CHANGES_ENTRY, zip_options())
+        .and_then(|_| zip.write_all(&json).map_err(Into::into))
+        .and_then(|_| zip.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    let (sha256, size) = hash_file(&path)?;
+
+    Ok((
+        ChainEntry {
+            backup_id,
+            kind: BackupKind::Diff,
+            parent: Some(parent),
+            file,
+            sha256,
+            size,
+            created_at: now_millis(),
+            schema_hash,
+            changed_rows,
+        },
+        covered_seq,
+    ))
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Current contents of the given rows; those that no longer exist were deleted
+async fn read_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    row_ids: &[i64],
+) -> Result<TableDiff, String> {
+    let mut diff = TableDiff::default();
+    for chunk in row_ids.chunks(ROWID_CHUNK) {
+        let sql = format!(
+            "SELECT rowid AS \"$rowid\", * FROM {} WHERE rowid IN ({})",
+            quote(table),
+            placeholders(chunk.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for row_id in chunk {
+            query = query.bind(row_id);
+        }
+        let rows = query
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+        let mut found = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let row = row_to_json(row)?;
+            found.extend(row.get("$rowid").and_then(serde_json::Value::as_i64));
+            diff.upserts.push(row);
+        }
+        diff.deletes
+            .extend(chunk.iter().filter(|row_id| !found.contains(row_id)));
+    }
+    Ok(diff)
+}
+
+/// Replay one diff onto a reconstructed database
+async fn apply_diff(conn: &mut SqliteConnection, diff: &DiffArchive) -> Result<(), String> {
+    for (table, changes) in &diff.tables {
+        for chunk in changes.deletes.chunks(ROWID_CHUNK) {
+            let sql = format!(
+                "DELETE FROM {} WHERE rowid IN ({})",
+                quote(table),
+                placeholders(chunk.len())
+            );
+            let mut query = sqlx::query(&sql);
+            for row_id in chunk {
+                query = query.bind(row_id);
+            }
+            query
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to replay {}: {}", table, e))?;
+        }
+
+        let columns = table_columns(&mut *conn, table).await?;
+        for row in &changes.upserts {
+            let Some(row_id) = row.get("$rowid").and_then(serde_json::Value::as_i64) else {
+                continue;
+            };
+            let present: Vec<(&String, &serde_json::Value)> = row
+                .iter()
+                .filter(|(name, _)| columns.contains(name))
+                .collect();
+            let sql = format!(
+                "INSERT OR REPLACE INTO {} (rowid{}) VALUES (?{})",
+                quote(table),
+                present
+                    .iter()
+                    .map(|(name, _)| format!(", {}", quote(name)))
+                    .collect::<String>(),
+                ", ?".repeat(present.len())
+            );
+            let mut query = sqlx::query(&sql).bind(row_id);
+            for (_, value) in &present {
+                query = bind_value(query, value);
+            }
+            query
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to replay {}: {}", table, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_entry(dir: &Path, entry: &ChainEntry, name: &str) -> Result<zip::ZipArchive<File>, String> {
+    let (sha256, _) = hash_file(&dir.join(&entry.file))?;
+    if sha256 != entry.sha256 {
+        return Err(format!(
+            "Backup {} is damaged (checksum mismatch)",
+            entry.backup_id
+        ));
+    }
+    let file = File::open(dir.join(&entry.file))
+        .map_err(|e| format!("Failed to read backup {}: {}", entry.backup_id, e))?;
+    let zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Backup {} is not a valid archive: {}", entry.backup_id, e))?;
+    if zip.index_for_name(name).is_none() {
+        return Err(format!("Backup {} is missing {}", entry.backup_id, name));
+    }
+    Ok(zip)
+}
+
+/// The backups to replay for `backup_id`, starting with its full copy
+fn chain_to(index: &ChainIndex, backup_id: &str) -> Result<Vec<ChainEntry>, String> {
+    let by_id: HashMap<&str, &ChainEntry> = index
+        .entries
+        .iter()
+        .map(|entry| (entry.backup_id.as_str(), entry))
+        .collect();
+    let mut chain = Vec::new();
+    let mut next = Some(backup_id);
+    while let Some(id) = next {
+        let entry = by_id
+            .get(id)
+            .ok_or_else(|| format!("Backup {} is missing from the chain", id))?;
+        if chain.len() > index.entries.len() {
+            return Err("The backup chain loops back on itself".to_string());
+        }
+        chain.push((*entry).clone());
+        next = entry.parent.as_deref();
+    }
+    chain.reverse();
+    match chain.first() {
+        Some(first) if first.kind == BackupKind::Full => Ok(chain),
+        _ => Err(format!(
+            "Backup {} has no full backup to start from",
+            backup_id
+        )),
+    }
+}
+
+/// Rebuild the database as of `backup_id` into a new file at `target`
+pub async fn reconstruct(
+    dir: &Path,
+    index: &ChainIndex,
+    backup_id: &str,
+    target: &Path,
+) -> Result<(), String> {
+    let chain = chain_to(index, backup_id)?;
+    let _ = std::fs::remove_file(target);
+    let mut base = read_entry(dir, &chain[0], DATABASE_ENTRY)?;
+    {
+        let mut input = base
+            .by_name(DATABASE_ENTRY)
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        let mut output =
+            File::create(target).map_err(|e| format!("Failed to write database: {}", e))?;
+        std::io::copy(&mut input, &mut output)
+            .map_err(|e| format!("Failed to write database: {}", e))?;
+    }
+
+    // Rows go back exactly as they were, so skip foreign key actions
+    let options = SqliteConnectOptions::new()
+        .filename(target)
+        .foreign_keys(false);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open rebuilt database: {}", e))?;
+    let result = replay(&pool, dir, &chain[1..]).await;
+    pool.close().await;
+    result
+}
+
+async fn replay(pool: &SqlitePool, dir: &Path, diffs: &[ChainEntry]) -> Result<(), String> {
+    for entry in diffs {
+        let mut zip = read_entry(dir, entry, CHANGES_ENTRY)?;
+        let mut json = Vec::new();
+        zip.by_name(CHANGES_ENTRY)
+            .map_err(|e| format!("Failed to read backup: {}", e))?
+            .read_to_end(&mut json)
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        let diff: DiffArchive = serde_json::from_slice(&json)
+            .map_err(|e| format!("Backup {} is damaged: {}", entry.backup_id, e))?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to replay backup: {}", e))?;
+        apply_diff(&mut tx, &diff).await?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to replay backup: {}", e))?;
+    }
+    sqlx::query(&format!("DELETE FROM {}", CHANGE_LOG))
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to clear change log: {}", e))?;
+    Ok(())
+}
+
+fn flag(problems: &mut Vec<ChainProblem>, backup_id: &str, message: String) {
+    problems.push(ChainProblem {
+        backup_id: backup_id.to_string(),
+        message,
+    });
+}
+
+/// Check every backup's file and links, then rebuild the newest state of
+/// each chain and run SQLite's integrity check on it
+pub async fn verify_chain(dir: &Path) -> Result<ChainVerification, String> {
+    let index = load_index(dir)?;
+    let mut problems = Vec::new();
+    for (i, entry) in index.entries.iter().enumerate() {
+        let earlier = &index.entries[..i];
+        match (&entry.kind, &entry.parent) {
+            (BackupKind::Full, None) => {}
+            (BackupKind::Full, Some(_)) => flag(
+                &mut problems,
+                &entry.backup_id,
+                "Full backup has a parent".to_string(),
+            ),
+            (BackupKind::Diff, None) => flag(
+                &mut problems,
+                &entry.backup_id,
+                "Incremental backup has no parent".to_string(),
+            ),
+            (BackupKind::Diff, Some(parent)) => {
+                match earlier.iter().find(|e| &e.backup_id == parent) {
+                    None => flag(
+                        &mut problems,
+                        &entry.backup_id,
+                        format!("Parent backup {} is missing", parent),
+                    ),
+                    Some(p) if p.schema_hash != entry.schema_hash => flag(
+                        &mut problems,
+                        &entry.backup_id,
+                        "Database layout differs from the parent backup".to_string(),
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+        match hash_file(&dir.join(&entry.file)) {
+            Ok((sha256, _)) if sha256 == entry.sha256 => {}
+            Ok(_) => flag(
+                &mut problems,
+                &entry.backup_id,
+                "Checksum mismatch".to_string(),
+            ),
+            Err(e) => flag(&mut problems, &entry.backup_id, e),
+        }
+    }
+
+    // Newest backup of each chain: nothing builds on it
+    let tips: Vec<&ChainEntry> = index
+        .entries
+        .iter()
+        .filter(|entry| {
+            !index
+                .entries
+                .iter()
+                .any(|e| e.parent.as_deref() == Some(entry.backup_id.as_str()))
+        })
+        .collect();
+    let scratch: PathBuf = dir.join("verify.db.partial");
+    for tip in tips {
+        let broken = chain_to(&index, &tip.backup_id).map(|chain| {
+            chain
+                .iter()
+                .any(|entry| problems.iter().any(|p| p.backup_id == entry.backup_id))
+        });
+        match broken {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => {
+                flag(&mut problems, &tip.backup_id, e);
+                continue;
+            }
+        }
+        if let Err(e) = check_rebuild(dir, &index, &tip.backup_id, &scratch).await {
+            flag(&mut problems, &tip.backup_id, e);
+        }
+    }
+    let _ = std::fs::remove_file(&scratch);
+
+    Ok(ChainVerification {
+        ok: problems.is_empty(),
+        checked: index.entries.len(),
+        problems,
+    })
+}
+
+async fn check_rebuild(
+    dir: &Path,
+    index: &ChainIndex,
+    backup_id: &str,
+    scratch: &Path,
+) -> Result<(), String> {
+    reconstruct(dir, index, backup_id, scratch).await?;
+    let options = SqliteConnectOptions::new()
+        .filename(scratch)
+        .read_only(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open rebuilt database: {}", e))?;
+    let result: Result<String, _> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&pool)
+        .await;
+    pool.close().await;
+    match result {
+        Ok(status) if status == "ok" => Ok(()),
+        Ok(status) => Err(format!("Rebuilt database is corrupt: {}", status)),
+        Err(e) => Err(format!("Failed to check rebuilt database: {}", e)),
+    }
+}
+
+/// Copy one story out of a backup into the live database, replacing the
+/// current version or, with `as_copy`, next to it. The rest of the database
+/// is left alone. Returns the restored story's id.
+pub async fn restore_story(
+    pool: &SqlitePool,
+    dir: &Path,
+    backup_id: &str,
+    story_id: &str,
+    as_copy: bool,
+    task: &mut ImportTask,
+) -> Result<String, String> {
+    let index = load_index(dir)?;
+    let scratch = dir.join(format!("restore-{}.db.partial", backup_id));
+    space::ensure_free(dir, space::used_bytes(pool).await)?;
+    reconstruct(dir, &index, backup_id, &scratch).await?;
+
+    let options = SqliteConnectOptions::new()
+        .filename(&scratch)
+        .read_only(true);
+    let snapshot = match SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+    {
+        Ok(backup) => {
+            let snapshot = snapshot::dump_story(&backup, story_id).await;
+            backup.close().await;
+            snapshot
+        }
+        Err(e) => Err(format!("Failed to open rebuilt database: {}", e)),
+    };
+    let _ = std::fs::remove_file(&scratch);
+
+    let mut snapshot = snapshot?;
+    if as_copy {
+        snapshot::make_copy(&mut snapshot)?;
+    }
+    snapshot::restore_snapshot(pool, &snapshot, task).await?;
+    Ok(snapshot.story_id)
+}
@@ -0,0 +1,59 @@
+//! Backup credentials live in the OS keychain rather than the settings
+//! table, which is plain text and goes into every backup. Android has no
+//! keychain backend, so remote backups aren't offered there.
+
+/// Keychain service every Aventura secret is filed under
+#[cfg(not(target_os = "android"))]
+const SERVICE: &str = "com.karelian.aventura";
+
+/// Secrets are kept per profile; the default profile keeps the bare names
+#[cfg(not(target_os = "android"))]
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+    let name = match crate::paths::active_profile() {
+        crate::profiles::store::DEFAULT_PROFILE => name.to_string(),
+        profile => format!("{}/{}", profile, name),
+    };
+    keyring::Entry::new(SERVICE, &name).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn set_secret(name: &str, secret: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to save to keychain: {}", e))
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn get_secret(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn delete_secret(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove from keychain: {}", e)),
+    }
+}
+
+#[cfg(target_os = "android")]
+const UNSUPPORTED: &str = "Saving credentials isn't supported on Android";
+
+#[cfg(target_os = "android")]
+pub fn set_secret(_name: &str, _secret: &str) -> Result<(), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(target_os = "android")]
+pub fn get_secret(_name: &str) -> Result<Option<String>, String> {
+    Ok(None)
+}
+
+#[cfg(target_os = "android")]
+pub fn delete_secret(_name: &str) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod incremental;
+pub mod keychain;
+pub mod s3;
+pub mod snapshot;
+pub mod types;
@@ -0,0 +1,202 @@
+//! Backups to S3-compatible storage (AWS, MinIO, Backblaze B2, R2...).
+//!
+//! Layout under the configured prefix:
+//! - `stories/{story_id}/{sha256}.zip`: one story archive, written once
+//! - `backups/{backup_id}.json`: a manifest naming the archive of each story
+//!
+//! A backup only uploads archives whose content changed since the last
+//! manifest, and nothing is ever overwritten, so expiring or versioning
+//! objects with bucket lifecycle rules is safe.
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use super::keychain;
+use super::snapshot;
+use super::types::{BackupManifest, BackupRunResult, BackupStory, BackupSummary, S3BackupSettings};
+use crate::db::now_millis;
+use crate::file_import::progress::ImportTask;
+use crate::llm::config::get_setting;
+use crate::remote::s3::{S3Client, S3Config};
+
+pub const ACCESS_KEY_SECRET: &str = "backup-s3-access-key-id";
+pub const SECRET_KEY_SECRET: &str = "backup-s3-secret-access-key";
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<S3BackupSettings, String> {
+    Ok(get_setting(pool, "backup_s3_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+pub fn client(settings: &S3BackupSettings) -> Result<S3Client, String> {
+    if settings.endpoint.is_empty() || settings.bucket.is_empty() {
+        return Err("Set up an S3 endpoint and bucket for backups first".to_string());
+    }
+    let access_key_id = keychain::get_secret(ACCESS_KEY_SECRET)?;
+    let secret_access_key = keychain::get_secret(SECRET_KEY_SECRET)?;
+    let (Some(access_key_id), Some(secret_access_key)) = (access_key_id, secret_access_key) else {
+        return Err("Enter the access keys for the backup bucket first".to_string());
+    };
+    Ok(S3Client::new(S3Config {
+        endpoint: settings.endpoint.clone(),
+        region: settings.region.clone(),
+        bucket: settings.bucket.clone(),
+        access_key_id,
+        secret_access_key,
+    }))
+}
+
+fn prefixed(settings: &S3BackupSettings, path: &str) -> String {
+    let prefix = settings.prefix.trim_matches('/');
+    if prefix.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", prefix, path)
+    }
+}
+
+fn manifest_key(settings: &S3BackupSettings, backup_id: &str) -> String {
+    prefixed(settings, &format!("backups/{}.json", backup_id))
+}
+
+/// Backup ids in the bucket, newest first. Ids are UTC timestamps, so they
+/// sort by age.
+async fn backup_ids(client: &S3Client, settings: &S3BackupSettings) -> Result<Vec<String>, String> {
+    let dir = prefixed(settings, "backups/");
+    let mut ids: Vec<String> = client
+        .list_keys(&dir)
+        .await?
+        .iter()
+        .filter_map(|key| key.strip_prefix(&dir)?.strip_suffix(".json"))
+        .map(str::to_string)
+        .collect();
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(ids)
+}
+
+pub async fn load_manifest(
+    client: &S3Client,
+    settings: &S3BackupSettings,
+    backup_id: &str,
+) -> Result<BackupManifest, String> {
+    let bytes = client
+        .get_object(&manifest_key(settings, backup_id))
+        .await?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Invalid backup manifest: {}", e))
+}
+
+/// Back up every story, uploading only archives the bucket doesn't have yet
+pub async fn run_backup(
+    pool: &SqlitePool,
+    client: &S3Client,
+    settings: &S3BackupSettings,
+    app_version: String,
+) -> Result<BackupRunResult, String> {
+    let previous = match backup_ids(client, settings).await?.first() {
+        Some(id) => Some(load_manifest(client, settings, id).await?),
+        None => None,
+    };
+    let story_ids: Vec<String> = sqlx::query_scalar("SELECT id FROM stories ORDER BY created_at")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to list stories: {}", e))?;
+
+    let mut result = BackupRunResult {
+        backup_id: Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string(),
+        uploaded: 0,
+        unchanged: 0,
+        bytes_uploaded: 0,
+    };
+    let mut stories = Vec::with_capacity(story_ids.len());
+    for story_id in story_ids {
+        let snapshot = snapshot::dump_story(pool, &story_id).await?;
+        let sha256 = snapshot::content_hash(&snapshot)?;
+        let existing = previous
+            .iter()
+            .flat_map(|manifest| &manifest.stories)
+            .find(|story| story.story_id == story_id && story.sha256 == sha256);
+
+        let (key, size) = match existing {
+            Some(story) => {
+                result.unchanged += 1;
+                (story.key.clone(), story.size)
+            }
+            None => {
+                let archive = snapshot::to_archive(&snapshot)?;
+                let key = prefixed(settings, &format!("stories/{}/{}.zip", story_id, sha256));
+                let size = archive.len() as u64;
+                client.put_object(&key, archive).await?;
+                result.uploaded += 1;
+                result.bytes_uploaded += size;
+                (key, size)
+            }
+        };
+        stories.push(BackupStory {
+            story_id,
+            title: snapshot.title,
+            updated_at: snapshot.updated_at,
+            sha256,
+            key,
+            size,
+        });
+    }
+
+    // The manifest goes last, so a backup interrupted midway never shows up
+    let manifest = BackupManifest {
+        backup_id: result.backup_id.clone(),
+        created_at: now_millis(),
+        app_version,
+        stories,
+    };
+    let body = serde_json::to_vec(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    client
+        .put_object(&manifest_key(settings, &manifest.backup_id), body)
+        .await?;
+    Ok(result)
+}
+
+pub async fn list_backups(
+    client: &S3Client,
+    settings: &S3BackupSettings,
+) -> Result<Vec<BackupSummary>, String> {
+    let mut summaries = Vec::new();
+    for backup_id in backup_ids(client, settings).await? {
+        let manifest = load_manifest(client, settings, &backup_id).await?;
+        summaries.push(BackupSummary {
+            backup_id,
+            created_at: manifest.created_at,
+            story_count: manifest.stories.len(),
+        });
+    }
+    Ok(summaries)
+}
+
+/// Download one story from a backup and write it into the database.
+/// Returns the restored story's id, which is new when `as_copy` is set.
+pub async fn restore_story(
+    pool: &SqlitePool,
+    client: &S3Client,
+    settings: &S3BackupSettings,
+    backup_id: &str,
+    story_id: &str,
+    as_copy: bool,
+    task: &mut ImportTask,
+) -> Result<String, String> {
+    let manifest = load_manifest(client, settings, backup_id).await?;
+    let story = manifest
+        .stories
+        .iter()
+        .find(|story| story.story_id == story_id)
+        .ok_or_else(|| format!("Backup {} has no story {}", backup_id, story_id))?;
+    let mut snapshot = snapshot::from_archive(&client.get_object(&story.key).await?)?;
+    if snapshot::content_hash(&snapshot)? != story.sha256 {
+        return Err("The backed up story is damaged (checksum mismatch)".to_string());
+    }
+    if as_copy {
+        snapshot::make_copy(&mut snapshot)?;
+    }
+    snapshot::restore_snapshot(pool, &snapshot, task).await?;
+    Ok(snapshot.story_id)
+}
@@ -0,0 +1,411 @@
+//! Story snapshots: every row belonging to one story, across all tables,
+//! as JSON. Tables are found from the schema (a `story_id` column, or a
+//! foreign key into a story table), so new story data is picked up without
+//! changes here. Images live in those rows as base64, so they come along.
+
+use aho_corasick::AhoCorasick;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Column, Row, SqliteConnection, SqlitePool, TypeInfo, ValueRef};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read, Write};
+use uuid::Uuid;
+
+use crate::db::now_millis;
+use crate::file_import::progress::ImportTask;
+use crate::storage::space;
+
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Logs and caches that belong to a story but aren't worth restoring
+const SKIPPED_TABLES: &[&str] = &[
+    "background_jobs",
+    "filter_hits",
+    "generation_requests",
+    "llm_request_log",
+    "operation_journal",
+    "prompt_cache_stats",
+    "turn_pipeline_runs",
+];
+
+/// Path of the snapshot inside a story archive
+const ARCHIVE_ENTRY: &str = "story.json";
+
+/// Largest snapshot read back from an archive
+const MAX_SNAPSHOT_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// A table holding story rows, and how to pick out one story's
+pub struct ScopedTable {
+    pub name: String,
+    /// SQL condition on the table with the story id bound as `?1`
+    pub condition: String,
+}
+
+pub type TableRows = Vec<Map<String, Value>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorySnapshot {
+    pub format_version: u32,
+    pub story_id: String,
+    pub title: String,
+    pub updated_at: i64,
+    pub tables: BTreeMap<String, TableRows>,
+}
+
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+async fn table_names(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_sqlx%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read schema: {}", e))
+}
+
+pub async fn table_columns(
+    conn: impl sqlx::SqliteExecutor<'_>,
+    table: &str,
+) -> Result<Vec<String>, String> {
+    sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| format!("Failed to read schema of {}: {}", table, e))
+}
+
+/// Tables holding story rows, parents before children
+pub async fn story_tables(pool: &SqlitePool) -> Result<Vec<ScopedTable>, String> {
+    let names: Vec<String> = table_names(pool)
+        .await?
+        .into_iter()
+        .filter(|name| !SKIPPED_TABLES.contains(&name.as_str()))
+        .collect();
+
+    let mut scoped = vec![ScopedTable {
+        name: "stories".to_string(),
+        condition: "id = ?1".to_string(),
+    }];
+    let mut foreign_keys = HashMap::new();
+    for name in names.iter().filter(|name| *name != "stories") {
+        if table_columns(pool, name)
+            .await?
+            .iter()
+            .any(|c| c == "story_id")
+        {
+            scoped.push(ScopedTable {
+                name: name.clone(),
+                condition: "story_id = ?1".to_string(),
+            });
+            continue;
+        }
+        let keys: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT \"from\", \"table\", \"to\" FROM pragma_foreign_key_list(?)")
+                .bind(name)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| format!("Failed to read schema of {}: {}", name, e))?;
+        foreign_keys.insert(name.clone(), keys);
+    }
+
+    // Children of story tables, then their children, and so on
+    loop {
+        let mut found = None;
+        'tables: for (name, keys) in &foreign_keys {
+            for (from, parent, to) in keys {
+                if let Some(parent) = scoped.iter().find(|t| &t.name == parent) {
+                    found = Some(ScopedTable {
+                        name: name.clone(),
+                        condition: format!(
+                            "{} IN (SELECT {} FROM {} WHERE {})",
+                            quote(from),
+                            quote(to),
+                            quote(&parent.name),
+                            parent.condition
+                        ),
+                    });
+                    break 'tables;
+                }
+            }
+        }
+        match found {
+            Some(table) => {
+                foreign_keys.remove(&table.name);
+                scoped.push(table);
+            }
+            None => return Ok(scoped),
+        }
+    }
+}
+
+pub fn row_to_json(row: &SqliteRow) -> Result<Map<String, Value>, String> {
+    let mut object = Map::new();
+    for column in row.columns() {
+        let i = column.ordinal();
+        let raw = row.try_get_raw(i).map_err(|e| e.to_string())?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(i).map_err(|e| e.to_string())?),
+                "REAL" => Value::from(row.try_get::<f64, _>(i).map_err(|e| e.to_string())?),
+                "BLOB" => {
+                    let bytes: Vec<u8> = row.try_get(i).map_err(|e| e.to_string())?;
+                    serde_json::json!({ "$blob": STANDARD.encode(bytes) })
+                }
+                _ => Value::from(row.try_get::<String, _>(i).map_err(|e| e.to_string())?),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(object)
+}
+
+/// Rows of `table` matching `condition` (with `?1` bound to `param`)
+pub async fn select_rows(
+    conn: impl sqlx::SqliteExecutor<'_>,
+    table: &str,
+    condition: &str,
+    param: &str,
+) -> Result<TableRows, String> {
+    let sql = format!(
+        "SELECT * FROM {} WHERE {} ORDER BY rowid",
+        quote(table),
+        condition
+    );
+    sqlx::query(&sql)
+        .bind(param)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", table, e))?
+        .iter()
+        .map(row_to_json)
+        .collect()
+}
+
+pub async fn dump_story(pool: &SqlitePool, story_id: &str) -> Result<StorySnapshot, String> {
+    let story: Option<(String, i64)> =
+        sqlx::query_as("SELECT title, updated_at FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load story: {}", e))?;
+    let (title, updated_at) = story.ok_or_else(|| format!("Story not found: {}", story_id))?;
+
+    let mut tables = BTreeMap::new();
+    for table in story_tables(pool).await? {
+        let rows = select_rows(pool, &table.name, &table.condition, story_id).await?;
+        if !rows.is_empty() {
+            tables.insert(table.name, rows);
+        }
+    }
+    Ok(StorySnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        story_id: story_id.to_string(),
+        title,
+        updated_at,
+        tables,
+    })
+}
+
+/// Identifies a snapshot's content, so unchanged stories can be skipped
+pub fn content_hash(snapshot: &StorySnapshot) -> Result<String, String> {
+    let bytes = serde_json::to_vec(&snapshot.tables)
+        .map_err(|e| format!("Failed to serialize story: {}", e))?;
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+pub fn to_archive(snapshot: &StorySnapshot) -> Result<Vec<u8>, String> {
+    let json =
+        serde_json::to_vec(snapshot).map_err(|e| format!("Failed to serialize story: {}", e))?;
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(ARCHIVE_ENTRY, options)
+        .and_then(|_| zip.write_all(&json).map_err(Into::into))
+        .map_err(|e| format!("Failed to write story archive: {}", e))?;
+    let cursor = zip
+        .finish()
+        .map_err(|e| format!("Failed to finish story archive: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+pub fn from_archive(bytes: &[u8]) -> Result<StorySnapshot, String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("Not a story archive: {}", e))?;
+    let entry = zip
+        .by_name(ARCHIVE_ENTRY)
+        .map_err(|e| format!("Not a story archive: {}", e))?;
+    let mut json = Vec::new();
+    entry
+        .take(MAX_SNAPSHOT_BYTES)
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Failed to read story archive: {}", e))?;
+    let snapshot: StorySnapshot =
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid story archive: {}", e))?;
+    if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+        return Err("This backup was made by a newer version of Aventura; please update".into());
+    }
+    Ok(snapshot)
+}
+
+/// Give every row a fresh id and rewrite references to the old ones,
+/// including inside JSON columns, so the snapshot can sit next to the story
+/// it was taken from
+pub fn make_copy(snapshot: &mut StorySnapshot) -> Result<(), String> {
+    let mut ids: Vec<(String, String)> = snapshot
+        .tables
+        .values()
+        .flatten()
+        .filter_map(|row| row.get("id").and_then(Value::as_str))
+        .map(|id| (id.to_string(), Uuid::new_v4().to_string()))
+        .collect();
+    ids.sort();
+    ids.dedup_by(|a, b| a.0 == b.0);
+    let old: Vec<&str> = ids.iter().map(|(old, _)| old.as_str()).collect();
+    let new: Vec<&str> = ids.iter().map(|(_, new)| new.as_str()).collect();
+    let replacer = AhoCorasick::new(&old).map_err(|e| format!("Failed to copy story: {}", e))?;
+
+    for row in snapshot.tables.values_mut().flatten() {
+        for value in row.values_mut() {
+            if let Value::String(text) = value {
+                *text = replacer.replace_all(text, &new);
+            }
+        }
+    }
+    if let Some(story) = snapshot
+        .tables
+        .get_mut("stories")
+        .and_then(|rows| rows.first_mut())
+    {
+        let title = format!("{} (restored)", snapshot.title);
+        story.insert("title".to_string(), Value::from(title.clone()));
+        story.insert("updated_at".to_string(), Value::from(now_millis()));
+        if let Some(id) = story.get("id").and_then(Value::as_str) {
+            snapshot.story_id = id.to_string();
+        }
+        snapshot.title = title;
+    }
+    Ok(())
+}
+
+pub fn bind_value<'q>(
+    query: sqlx::query::Query<'q, sqlx::Sqlite, SqliteArguments<'q>>,
+    value: &Value,
+) -> sqlx::query::Query<'q, sqlx::Sqlite, SqliteArguments<'q>> {
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        Value::Object(o) => match o.get("$blob").and_then(Value::as_str) {
+            Some(blob) => query.bind(STANDARD.decode(blob).unwrap_or_default()),
+            None => query.bind(value.to_string()),
+        },
+        Value::Array(_) => query.bind(value.to_string()),
+    }
+}
+
+/// Insert rows, keeping only columns the table still has so backups from
+/// other app versions restore as far as the schema allows. `on_row` runs
+/// after each row and can stop the insert by failing.
+pub async fn insert_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    rows: &[Map<String, Value>],
+    mut on_row: impl FnMut(&Map<String, Value>) -> Result<(), String>,
+) -> Result<(), String> {
+    let columns = table_columns(&mut *conn, table).await?;
+    for row in rows {
+        let present: Vec<(&String, &Value)> = row
+            .iter()
+            .filter(|(name, _)| columns.contains(name))
+            .collect();
+        if present.is_empty() {
+            continue;
+        }
+        let sql = format!(
+            "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+            quote(table),
+            present
+                .iter()
+                .map(|(name, _)| quote(name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            vec!["?"; present.len()].join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for (_, value) in &present {
+            query = bind_value(query, value);
+        }
+        query
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to restore {}: {}", table, e))?;
+        on_row(row)?;
+    }
+    Ok(())
+}
+
+/// Write a snapshot into the database, replacing whatever the story with
+/// the same id holds now. All or nothing: a failure or a cancelled `task`
+/// leaves the story as it was.
+pub async fn restore_snapshot(
+    pool: &SqlitePool,
+    snapshot: &StorySnapshot,
+    task: &mut ImportTask,
+) -> Result<(), String> {
+    // Written once to the log and once to the database itself
+    let size = serde_json::to_vec(&snapshot.tables).map_or(0, |rows| rows.len() as u64);
+    space::ensure_free_for_database(pool, size * 2).await?;
+    let tables = story_tables(pool).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start restore: {}", e))?;
+    // Rows reference each other in every direction (stories and branches),
+    // so check foreign keys once everything is in
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to start restore: {}", e))?;
+
+    for table in tables.iter().rev() {
+        let sql = format!(
+            "DELETE FROM {} WHERE {}",
+            quote(&table.name),
+            table.condition
+        );
+        sqlx::query(&sql)
+            .bind(&snapshot.story_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to clear {}: {}", table.name, e))?;
+    }
+    task.stage(
+        "writing",
+        Some(snapshot.tables.values().map(Vec::len).sum()),
+    )?;
+    for table in &tables {
+        if let Some(rows) = snapshot.tables.get(&table.name) {
+            insert_rows(&mut tx, &table.name, rows, |row| {
+                task.images(row.values().filter(|v| v.get("$blob").is_some()).count());
+                task.rows(1)
+            })
+            .await?;
+        }
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to finish restore: {}", e))
+}
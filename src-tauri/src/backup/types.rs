@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// Where S3 backups go. The access keys are kept in the keychain, not here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct S3BackupSettings {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Key prefix for everything Aventura writes, so a bucket can be shared
+    pub prefix: String,
+}
+
+impl Default for S3BackupSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            prefix: "aventura".to_string(),
+        }
+    }
+}
+
+/// One story in a backup, pointing at its archive. Archives are named by
+/// content hash and never rewritten, so bucket lifecycle rules (versioning,
+/// object lock, expiry) work on them as-is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStory {
+    pub story_id: String,
+    pub title: String,
+    pub updated_at: i64,
+    pub sha256: String,
+    pub key: String,
+    pub size: u64,
+}
+
+/// Lists the stories a backup holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub backup_id: String,
+    pub created_at: i64,
+    pub app_version: String,
+    pub stories: Vec<BackupStory>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub backup_id: String,
+    pub created_at: i64,
+    pub story_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRunResult {
+    pub backup_id: String,
+    /// Stories whose archive had to be uploaded
+    pub uploaded: usize,
+    /// Stories already in the bucket from an earlier backup
+    pub unchanged: usize,
+    pub bytes_uploaded: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupKind {
+    /// A copy of the whole database, starting a chain
+    Full,
+    /// Rows changed since the parent backup
+    Diff,
+}
+
+/// One local incremental backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainEntry {
+    pub backup_id: String,
+    pub kind: BackupKind,
+    pub parent: Option<String>,
+    /// Archive file name in the backup directory
+    pub file: String,
+    pub sha256: String,
+    pub size: u64,
+    pub created_at: i64,
+    /// Hash of the table definitions; a diff only applies to the same schema
+    pub schema_hash: String,
+    pub changed_rows: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChainIndex {
+    /// Oldest first
+    pub entries: Vec<ChainEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TableDiff {
+    /// Current contents of changed rows, with their rowid under `$rowid`
+    pub upserts: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Rowids of deleted rows
+    pub deletes: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffArchive {
+    pub backup_id: String,
+    pub parent: String,
+    pub tables: std::collections::BTreeMap<String, TableDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainProblem {
+    pub backup_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainVerification {
+    pub ok: bool,
+    /// Backups checked, including replaying each chain
+    pub checked: usize,
+    pub problems: Vec<ChainProblem>,
+}
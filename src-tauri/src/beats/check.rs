@@ -0,0 +1,130 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use super::store::{self, load_open_beats};
+use super::types::{BeatCheckResult, BeatResolution};
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You track story beats (planned plot threads) in an interactive story. \
+Given the open beats and the latest passage, report every beat the passage clearly completes or makes impossible. \
+Do not guess: only report what the passage shows. Respond with JSON only, in this shape: \
+{\"resolutions\": [{\"beatId\": \"<id>\", \"status\": \"completed\" | \"failed\", \"confidence\": <0 to 1>, \
+\"reason\": \"<short explanation>\"}]}. \
+Respond with {\"resolutions\": []} if no beat was resolved.";
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "beat_check",
+        schema: json!({
+            "type": "object",
+            "required": ["resolutions"],
+            "properties": {
+                "resolutions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["beatId", "status"],
+                        "properties": {
+                            "beatId": { "type": "string" },
+                            "status": { "enum": ["completed", "failed"] },
+                            "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                            "reason": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CheckReply {
+    resolutions: Vec<BeatResolution>,
+}
+
+/// Background job: ask the classification model which open beats an entry
+/// resolves. Each resolution is stored as a suggestion, applied straight
+/// away when it's confident enough, and the lot is emitted on `beat-check`.
+pub async fn run_beat_check_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Beat check job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let beats = load_open_beats(pool, &story_id).await?;
+    let mut result = BeatCheckResult {
+        story_id: story_id.clone(),
+        entry_id: entry_id.to_string(),
+        suggestions: Vec::new(),
+    };
+    if beats.is_empty() {
+        return Ok(json!(result));
+    }
+
+    let listing = beats
+        .iter()
+        .map(|b| match &b.description {
+            Some(description) => format!("Beat [{}]: {} - {}", b.id, b.title, description),
+            None => format!("Beat [{}]: {}", b.id, b.title),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = format!("Open beats:\n{}\n\nPassage:\n{}", listing, content);
+
+    let llm = config::resolve_service(pool, "beatCheck", "classification").await?;
+    let reply: CheckReply = complete_structured(
+        pool,
+        "beatCheck",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let settings = store::load_settings(pool).await?;
+    for resolution in reply.resolutions {
+        if resolution.confidence < settings.min_confidence
+            || !matches!(resolution.status.as_str(), "completed" | "failed")
+        {
+            continue;
+        }
+        // Drop ids the model invented
+        let Some(beat) = beats.iter().find(|b| b.id == resolution.beat_id) else {
+            continue;
+        };
+        let apply = settings
+            .auto_apply_confidence
+            .is_some_and(|threshold| resolution.confidence >= threshold);
+        let suggestion =
+            store::save_suggestion(pool, &story_id, entry_id, beat, &resolution, apply).await?;
+        result.suggestions.push(suggestion);
+    }
+    if let Err(e) = app.emit("beat-check", &result) {
+        eprintln!("Failed to emit beat check event: {}", e);
+    }
+    Ok(json!(result))
+}
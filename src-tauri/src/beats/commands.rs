@@ -0,0 +1,24 @@
+use tauri::State;
+
+use super::store;
+use super::types::BeatSuggestion;
+use crate::db::DbState;
+
+/// Get pending beat resolutions proposed by the beat check
+#[tauri::command]
+pub async fn get_beat_suggestions(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<BeatSuggestion>, String> {
+    store::load_suggestions(db.pool(), &story_id).await
+}
+
+/// Accept (resolving the beat) or dismiss a beat suggestion
+#[tauri::command]
+pub async fn resolve_beat_suggestion(
+    db: State<'_, DbState>,
+    suggestion_id: String,
+    accept: bool,
+) -> Result<(), String> {
+    store::resolve_suggestion(db.pool(), &suggestion_id, accept).await
+}
@@ -0,0 +1,4 @@
+pub mod check;
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,192 @@
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use super::types::{BeatCheckSettings, BeatResolution, BeatSuggestion, StoryBeat};
+use crate::context::branch::world_view;
+use crate::db::now_millis;
+use crate::llm::config::get_setting;
+
+/// The story's beats on the current branch
+pub async fn load_beats(pool: &SqlitePool, story_id: &str) -> Result<Vec<StoryBeat>, String> {
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        let rows: Vec<StoryBeat> = sqlx::query_as(
+            "SELECT id, title, description, type, status, overrides_id, deleted FROM story_beats \
+             WHERE story_id = ? AND branch_id IS ?",
+        )
+        .bind(story_id)
+        .bind(&branch)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story beats: {}", e))?;
+        layers.push(rows);
+    }
+    Ok(view.resolve(
+        layers,
+        |b| b.overrides_id.clone().unwrap_or_else(|| b.id.clone()),
+        |b| b.deleted != 0,
+    ))
+}
+
+/// Beats that are still open (pending or active)
+pub async fn load_open_beats(pool: &SqlitePool, story_id: &str) -> Result<Vec<StoryBeat>, String> {
+    Ok(load_beats(pool, story_id)
+        .await?
+        .into_iter()
+        .filter(|b| matches!(b.status.as_deref(), None | Some("pending" | "active")))
+        .collect())
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<BeatCheckSettings, String> {
+    Ok(get_setting(pool, "beat_check_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+async fn set_beat_status(
+    conn: &mut SqliteConnection,
+    beat_id: &str,
+    status: &str,
+) -> Result<(), String> {
+    sqlx::query("UPDATE story_beats SET status = ?, resolved_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(now_millis())
+        .bind(beat_id)
+        .execute(conn)
+        .await
+        .map_err(|e| format!("Failed to update story beat: {}", e))?;
+    Ok(())
+}
+
+/// Store a resolution for `beat`, replacing any still pending for it, and
+/// apply it at once when `apply` is set
+pub async fn save_suggestion(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: &str,
+    beat: &StoryBeat,
+    resolution: &BeatResolution,
+    apply: bool,
+) -> Result<BeatSuggestion, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    sqlx::query(
+        "UPDATE beat_suggestions SET status = 'dismissed' WHERE beat_id = ? AND status = 'pending'",
+    )
+    .bind(&beat.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update beat suggestions: {}", e))?;
+
+    let suggestion = BeatSuggestion {
+        id: Uuid::new_v4().to_string(),
+        beat_id: beat.id.clone(),
+        title: beat.title.clone(),
+        entry_id: entry_id.to_string(),
+        suggested_status: resolution.status.clone(),
+        confidence: resolution.confidence.clamp(0.0, 1.0),
+        reason: Some(resolution.reason.clone()).filter(|r| !r.is_empty()),
+        status: if apply { "accepted" } else { "pending" }.to_string(),
+        created_at: now_millis(),
+    };
+    sqlx::query(
+        "INSERT INTO beat_suggestions (id, story_id, beat_id, entry_id, suggested_status, \
+         confidence, reason, beat_status, status, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&suggestion.id)
+    .bind(story_id)
+    .bind(&suggestion.beat_id)
+    .bind(entry_id)
+    .bind(&suggestion.suggested_status)
+    .bind(suggestion.confidence)
+    .bind(&suggestion.reason)
+    .bind(&beat.status)
+    .bind(&suggestion.status)
+    .bind(suggestion.created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save beat suggestion: {}", e))?;
+    if apply {
+        set_beat_status(&mut tx, &beat.id, &resolution.status).await?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit beat suggestion: {}", e))?;
+    Ok(suggestion)
+}
+
+/// Pending suggestions for a story, newest first
+pub async fn load_suggestions(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<BeatSuggestion>, String> {
+    let rows = sqlx::query(
+        "SELECT s.id, s.beat_id, b.title, s.entry_id, s.suggested_status, s.confidence, \
+         s.reason, s.status, s.created_at FROM beat_suggestions s \
+         JOIN story_beats b ON b.id = s.beat_id \
+         WHERE s.story_id = ? AND s.status = 'pending' ORDER BY s.created_at DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load beat suggestions: {}", e))?;
+    Ok(rows
+        .iter()
+        .map(|r| BeatSuggestion {
+            id: r.get("id"),
+            beat_id: r.get("beat_id"),
+            title: r.get("title"),
+            entry_id: r.get("entry_id"),
+            suggested_status: r.get("suggested_status"),
+            confidence: r.get("confidence"),
+            reason: r.get("reason"),
+            status: r.get("status"),
+            created_at: r.get("created_at"),
+        })
+        .collect())
+}
+
+/// Accept (applying the resolution) or dismiss a suggestion. A beat that
+/// changed since it was checked is left alone.
+pub async fn resolve_suggestion(
+    pool: &SqlitePool,
+    suggestion_id: &str,
+    accept: bool,
+) -> Result<(), String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let (beat_id, suggested, checked, current): (String, String, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "SELECT s.beat_id, s.suggested_status, s.beat_status, b.status \
+             FROM beat_suggestions s JOIN story_beats b ON b.id = s.beat_id \
+             WHERE s.id = ? AND s.status = 'pending'",
+        )
+        .bind(suggestion_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load suggestion: {}", e))?
+        .ok_or_else(|| format!("Suggestion not found: {}", suggestion_id))?;
+
+    if accept {
+        if current != checked {
+            return Err("The beat has changed since it was checked".to_string());
+        }
+        set_beat_status(&mut tx, &beat_id, &suggested).await?;
+    }
+    sqlx::query("UPDATE beat_suggestions SET status = ? WHERE id = ?")
+        .bind(if accept { "accepted" } else { "dismissed" })
+        .bind(suggestion_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update suggestion: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit suggestion: {}", e))
+}
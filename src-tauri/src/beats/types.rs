@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// A story beat as seen on the current branch
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryBeat {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// 'pending' | 'active' | 'completed' | 'failed'
+    pub status: Option<String>,
+    #[serde(skip)]
+    pub overrides_id: Option<String>,
+    #[serde(skip)]
+    pub deleted: i64,
+}
+
+/// A beat the model judged resolved by an entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeatResolution {
+    pub beat_id: String,
+    /// 'completed' | 'failed'
+    pub status: String,
+    /// How sure the model is, from 0 to 1
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// A resolution waiting for the user, or already settled
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeatSuggestion {
+    pub id: String,
+    pub beat_id: String,
+    pub title: String,
+    pub entry_id: String,
+    /// 'completed' | 'failed'
+    pub suggested_status: String,
+    pub confidence: f64,
+    pub reason: Option<String>,
+    /// 'pending' | 'accepted' | 'dismissed'; accepted straight away when
+    /// the confidence reached the auto-apply threshold
+    pub status: String,
+    pub created_at: i64,
+}
+
+/// Beat check options, stored in `beat_check_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BeatCheckSettings {
+    /// Resolutions below this confidence are discarded
+    pub min_confidence: f64,
+    /// Resolutions at or above this confidence are applied without asking
+    pub auto_apply_confidence: Option<f64>,
+}
+
+impl Default for BeatCheckSettings {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.5,
+            auto_apply_confidence: None,
+        }
+    }
+}
+
+/// Emitted on `beat-check` after a beat check job
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeatCheckResult {
+    pub story_id: String,
+    pub entry_id: String,
+    pub suggestions: Vec<BeatSuggestion>,
+}
@@ -0,0 +1,55 @@
+use tauri::State;
+
+use super::store;
+use super::types::{Bookmark, BookmarkListing, BookmarkWindow};
+use crate::db::DbState;
+
+/// Bookmark an entry under a label ("the coronation scene"). Bookmarking an
+/// entry again relabels it.
+#[tauri::command]
+pub async fn add_bookmark(
+    db: State<'_, DbState>,
+    entry_id: String,
+    label: String,
+    pinned: Option<bool>,
+) -> Result<Bookmark, String> {
+    store::add_bookmark(db.pool(), &entry_id, &label, pinned.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn update_bookmark(
+    db: State<'_, DbState>,
+    bookmark_id: String,
+    label: Option<String>,
+    pinned: Option<bool>,
+) -> Result<Bookmark, String> {
+    store::update_bookmark(db.pool(), &bookmark_id, label.as_deref(), pinned).await
+}
+
+#[tauri::command]
+pub async fn delete_bookmark(db: State<'_, DbState>, bookmark_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM bookmarks WHERE id = ?")
+        .bind(&bookmark_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete bookmark: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_bookmarks(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<BookmarkListing>, String> {
+    store::list_bookmarks(db.pool(), &story_id).await
+}
+
+/// Find the page to open to jump to a bookmark
+#[tauri::command]
+pub async fn resolve_bookmark(
+    db: State<'_, DbState>,
+    bookmark_id: String,
+    page_size: Option<usize>,
+) -> Result<BookmarkWindow, String> {
+    store::resolve_bookmark(db.pool(), &bookmark_id, page_size).await
+}
@@ -0,0 +1,271 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::types::{Bookmark, BookmarkListing, BookmarkWindow};
+use crate::context::branch::segments;
+use crate::db::now_millis;
+
+/// Entries per page when the reader doesn't say
+pub const DEFAULT_PAGE_SIZE: usize = 40;
+
+const SNIPPET_CHARS: usize = 200;
+const BEFORE_CHARS: usize = 80;
+
+type Segment = (Option<String>, Option<i64>);
+
+fn collapse(text: &str) -> Vec<char> {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn head(text: &str, max: usize) -> String {
+    let chars = collapse(text);
+    if chars.len() <= max {
+        return chars.into_iter().collect();
+    }
+    format!("{}…", chars[..max].iter().collect::<String>().trim_end())
+}
+
+fn tail(text: &str, max: usize) -> String {
+    let chars = collapse(text);
+    if chars.len() <= max {
+        return chars.into_iter().collect();
+    }
+    format!(
+        "…{}",
+        chars[chars.len() - max..]
+            .iter()
+            .collect::<String>()
+            .trim_start()
+    )
+}
+
+/// Entries of one segment, only those before `below` when given
+async fn count_in(
+    pool: &SqlitePool,
+    story_id: &str,
+    (branch_id, max_position): &Segment,
+    below: Option<i64>,
+) -> Result<usize, String> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM story_entries WHERE story_id = ? AND branch_id IS ? \
+         AND (? IS NULL OR position <= ?) AND (? IS NULL OR position < ?)",
+    )
+    .bind(story_id)
+    .bind(branch_id)
+    .bind(max_position)
+    .bind(max_position)
+    .bind(below)
+    .bind(below)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count story entries: {}", e))?;
+    Ok(count as usize)
+}
+
+/// The segment holding an entry, if it's readable on the current branch
+fn segment_of(segments: &[Segment], branch_id: &Option<String>, position: i64) -> Option<usize> {
+    segments
+        .iter()
+        .position(|(b, max)| b == branch_id && max.is_none_or(|m| position <= m))
+}
+
+/// Where an entry sits among the current branch's entries. Counts rather
+/// than loads them, so it stays quick in long stories.
+async fn entry_index(
+    pool: &SqlitePool,
+    story_id: &str,
+    segments: &[Segment],
+    segment: usize,
+    position: i64,
+) -> Result<usize, String> {
+    let mut index = 0;
+    for earlier in &segments[..segment] {
+        index += count_in(pool, story_id, earlier, None).await?;
+    }
+    Ok(index + count_in(pool, story_id, &segments[segment], Some(position)).await?)
+}
+
+/// Content of the entry read just before the one at `position`
+async fn previous_content(
+    pool: &SqlitePool,
+    story_id: &str,
+    segments: &[Segment],
+    segment: usize,
+    position: i64,
+) -> Result<Option<String>, String> {
+    let mut below = Some(position);
+    for (branch_id, max_position) in segments[..=segment].iter().rev() {
+        let content: Option<String> = sqlx::query_scalar(
+            "SELECT content FROM story_entries WHERE story_id = ? AND branch_id IS ? \
+             AND (? IS NULL OR position <= ?) AND (? IS NULL OR position < ?) \
+             ORDER BY position DESC LIMIT 1",
+        )
+        .bind(story_id)
+        .bind(branch_id)
+        .bind(max_position)
+        .bind(max_position)
+        .bind(below)
+        .bind(below)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load story entries: {}", e))?;
+        if content.is_some() {
+            return Ok(content);
+        }
+        below = None;
+    }
+    Ok(None)
+}
+
+pub async fn load_bookmark(pool: &SqlitePool, bookmark_id: &str) -> Result<Bookmark, String> {
+    sqlx::query_as("SELECT * FROM bookmarks WHERE id = ?")
+        .bind(bookmark_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load bookmark: {}", e))?
+        .ok_or_else(|| format!("Bookmark not found: {}", bookmark_id))
+}
+
+/// Bookmark an entry, or relabel its bookmark if it has one
+pub async fn add_bookmark(
+    pool: &SqlitePool,
+    entry_id: &str,
+    label: &str,
+    pinned: bool,
+) -> Result<Bookmark, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err("A bookmark needs a label".to_string());
+    }
+    let story_id: String = sqlx::query_scalar("SELECT story_id FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    sqlx::query_as(
+        "INSERT INTO bookmarks (id, story_id, entry_id, label, pinned, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(entry_id) DO UPDATE SET label = excluded.label, pinned = excluded.pinned \
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&story_id)
+    .bind(entry_id)
+    .bind(label)
+    .bind(pinned)
+    .bind(now_millis())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to save bookmark: {}", e))
+}
+
+pub async fn update_bookmark(
+    pool: &SqlitePool,
+    bookmark_id: &str,
+    label: Option<&str>,
+    pinned: Option<bool>,
+) -> Result<Bookmark, String> {
+    let label = label.map(str::trim);
+    if label == Some("") {
+        return Err("A bookmark needs a label".to_string());
+    }
+    sqlx::query_as(
+        "UPDATE bookmarks SET label = COALESCE(?, label), pinned = COALESCE(?, pinned) \
+         WHERE id = ? RETURNING *",
+    )
+    .bind(label)
+    .bind(pinned)
+    .bind(bookmark_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to update bookmark: {}", e))?
+    .ok_or_else(|| format!("Bookmark not found: {}", bookmark_id))
+}
+
+/// A story's bookmarks, pinned first, then in reading order on the current
+/// branch; those only on other branches come last
+pub async fn list_bookmarks(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<BookmarkListing>, String> {
+    let rows: Vec<(String, Option<String>, i64, String)> = sqlx::query_as(
+        "SELECT b.id, e.branch_id, e.position, e.content FROM bookmarks b \
+         JOIN story_entries e ON e.id = b.entry_id WHERE b.story_id = ?",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load bookmarks: {}", e))?;
+    let segments = segments(pool, story_id).await?;
+
+    let mut listings = Vec::new();
+    for (id, branch_id, position, content) in rows {
+        let bookmark = load_bookmark(pool, &id).await?;
+        let (index, before) = match segment_of(&segments, &branch_id, position) {
+            Some(segment) => (
+                Some(entry_index(pool, story_id, &segments, segment, position).await?),
+                previous_content(pool, story_id, &segments, segment, position)
+                    .await?
+                    .map(|text| tail(&text, BEFORE_CHARS)),
+            ),
+            None => (None, None),
+        };
+        listings.push(BookmarkListing {
+            bookmark,
+            branch_id,
+            index,
+            before,
+            snippet: head(&content, SNIPPET_CHARS),
+        });
+    }
+    listings.sort_by_key(|l| {
+        (
+            !l.bookmark.pinned,
+            l.index.is_none(),
+            l.index,
+            l.bookmark.created_at,
+        )
+    });
+    Ok(listings)
+}
+
+/// The page of the current branch holding a bookmark's entry
+pub async fn resolve_bookmark(
+    pool: &SqlitePool,
+    bookmark_id: &str,
+    page_size: Option<usize>,
+) -> Result<BookmarkWindow, String> {
+    let bookmark = load_bookmark(pool, bookmark_id).await?;
+    let (branch_id, position): (Option<String>, i64) =
+        sqlx::query_as("SELECT branch_id, position FROM story_entries WHERE id = ?")
+            .bind(&bookmark.entry_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to load entry: {}", e))?;
+    let segments = segments(pool, &bookmark.story_id).await?;
+    let segment =
+        segment_of(&segments, &branch_id, position).ok_or("This bookmark is on another branch")?;
+
+    let index = entry_index(pool, &bookmark.story_id, &segments, segment, position).await?;
+    let mut total = 0;
+    for segment in &segments {
+        total += count_in(pool, &bookmark.story_id, segment, None).await?;
+    }
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let page = index / page_size;
+    Ok(BookmarkWindow {
+        entry_id: bookmark.entry_id,
+        index,
+        total,
+        page_size,
+        page: page + 1,
+        pages: total.div_ceil(page_size).max(1),
+        start: page * page_size,
+        end: ((page + 1) * page_size).min(total),
+    })
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    pub label: String,
+    pub pinned: bool,
+    pub created_at: i64,
+}
+
+/// A bookmark with where its entry sits and a bit of text around it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkListing {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    pub branch_id: Option<String>,
+    /// Index among the entries on the current branch; `None` when the entry
+    /// is only on another branch
+    pub index: Option<usize>,
+    /// End of the entry before it
+    pub before: Option<String>,
+    /// Start of the bookmarked entry
+    pub snippet: String,
+}
+
+/// The page of the current branch's entries holding a bookmarked entry
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkWindow {
+    pub entry_id: String,
+    pub index: usize,
+    /// Entries on the current branch
+    pub total: usize,
+    pub page_size: usize,
+    /// 1-based
+    pub page: usize,
+    pub pages: usize,
+    /// Entry indices of the page, end exclusive
+    pub start: usize,
+    pub end: usize,
+}
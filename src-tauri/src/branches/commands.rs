@@ -0,0 +1,14 @@
+use tauri::State;
+
+use super::tree;
+use super::types::BranchTree;
+use crate::db::DbState;
+
+/// Everything needed to draw a story's branch graph, without loading entries
+#[tauri::command]
+pub async fn get_branch_tree(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<BranchTree, String> {
+    tree::build(db.pool(), &story_id).await
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::SqlitePool;
+
+use super::types::{BranchNode, BranchTree};
+use crate::context::branch::{current_branch, lineage};
+
+/// Characters of a branch's first entry kept as its divergence summary
+const DIVERGENCE_CHARS: usize = 160;
+
+#[derive(sqlx::FromRow)]
+struct BranchRow {
+    id: String,
+    name: String,
+    parent_branch_id: Option<String>,
+    fork_entry_id: String,
+    fork_position: Option<i64>,
+    created_at: i64,
+    sandbox: bool,
+}
+
+/// Entry positions and activity of each branch (`None` is main)
+#[derive(Default)]
+struct Entries {
+    positions: HashMap<Option<String>, Vec<i64>>,
+    last_activity: HashMap<Option<String>, i64>,
+}
+
+impl Entries {
+    /// Entries readable on `branch` up to `bound`, the way visible_entries
+    /// walks the lineage: each ancestor up to where the next branch forks
+    fn visible(
+        &self,
+        branches: &HashMap<&str, &BranchRow>,
+        branch: Option<&str>,
+        bound: Option<i64>,
+    ) -> usize {
+        let mut count = 0;
+        let mut seen = HashSet::new();
+        let mut next = (branch, bound);
+        loop {
+            let (branch, bound) = next;
+            count += self
+                .positions
+                .get(&branch.map(str::to_string))
+                .map(|p| p.iter().filter(|&&x| bound.is_none_or(|b| x <= b)).count())
+                .unwrap_or(0);
+            let Some(row) = branch.and_then(|id| branches.get(id)) else {
+                return count;
+            };
+            if !seen.insert(row.id.as_str()) {
+                return count;
+            }
+            next = (parent_of(branches, row), row.fork_position);
+        }
+    }
+}
+
+/// A branch's parent, or main when it has none or it no longer exists
+fn parent_of<'a>(branches: &HashMap<&str, &'a BranchRow>, row: &'a BranchRow) -> Option<&'a str> {
+    row.parent_branch_id
+        .as_deref()
+        .filter(|p| branches.contains_key(p))
+}
+
+fn summary(content: &str) -> String {
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(DIVERGENCE_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// The story's branch graph: main first, then each branch after its parent.
+/// Sandboxes are left out unless the current branch is in or under one.
+pub async fn build(pool: &SqlitePool, story_id: &str) -> Result<BranchTree, String> {
+    let rows: Vec<BranchRow> = sqlx::query_as(
+        "SELECT b.id, b.name, b.parent_branch_id, b.fork_entry_id, e.position AS fork_position, \
+         b.created_at, b.sandbox_expires_at IS NOT NULL AS sandbox FROM branches b \
+         LEFT JOIN story_entries e ON e.id = b.fork_entry_id \
+         WHERE b.story_id = ? ORDER BY b.created_at, b.id",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load branches: {}", e))?;
+    let branches: HashMap<&str, &BranchRow> = rows.iter().map(|r| (r.id.as_str(), r)).collect();
+
+    let mut entries = Entries::default();
+    let positions: Vec<(Option<String>, i64, i64)> = sqlx::query_as(
+        "SELECT branch_id, position, created_at FROM story_entries WHERE story_id = ? \
+         ORDER BY position",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load story entries: {}", e))?;
+    for (branch, position, created_at) in positions {
+        let last = entries.last_activity.entry(branch.clone()).or_default();
+        *last = (*last).max(created_at);
+        entries.positions.entry(branch).or_default().push(position);
+    }
+
+    let firsts: Vec<(String, String)> = sqlx::query_as(
+        "SELECT e.branch_id, e.content FROM story_entries e \
+         WHERE e.story_id = ? AND e.branch_id IS NOT NULL AND e.position = \
+         (SELECT MIN(position) FROM story_entries WHERE story_id = e.story_id AND branch_id = e.branch_id)",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load story entries: {}", e))?;
+    let divergences: HashMap<String, String> = firsts
+        .into_iter()
+        .map(|(branch, content)| (branch, summary(&content)))
+        .collect();
+
+    let current = current_branch(pool, story_id).await?;
+    let current_lineage: HashSet<String> = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    let shown = |row: &BranchRow| !row.sandbox || current_lineage.contains(&row.id);
+
+    let mut children: HashMap<Option<&str>, Vec<&BranchRow>> = HashMap::new();
+    for row in rows.iter().filter(|r| shown(r)) {
+        children
+            .entry(parent_of(&branches, row))
+            .or_default()
+            .push(row);
+    }
+    let child_ids = |id: Option<&str>| -> Vec<String> {
+        children
+            .get(&id)
+            .map(|rows| rows.iter().map(|r| r.id.clone()).collect())
+            .unwrap_or_default()
+    };
+
+    let own = |id: Option<&str>| {
+        entries
+            .positions
+            .get(&id.map(str::to_string))
+            .map_or(0, Vec::len)
+    };
+    let mut nodes = vec![BranchNode {
+        id: None,
+        name: "Main".to_string(),
+        parent_id: None,
+        depth: 0,
+        fork_entry_id: None,
+        fork_index: 0,
+        own_entries: own(None),
+        total_entries: own(None),
+        divergence: None,
+        child_ids: child_ids(None),
+        current: current.is_none(),
+        sandbox: false,
+        created_at: None,
+        last_activity: entries.last_activity.get(&None).copied(),
+    }];
+
+    // Depth-first so each branch's subtree follows it; a parent cycle can't
+    // be reached from main and is left out
+    let mut stack: Vec<(&BranchRow, usize)> = children
+        .get(&None)
+        .map(|rows| rows.iter().rev().map(|r| (*r, 1)).collect())
+        .unwrap_or_default();
+    let mut seen = HashSet::new();
+    while let Some((row, depth)) = stack.pop() {
+        if !seen.insert(row.id.as_str()) {
+            continue;
+        }
+        let id = Some(row.id.as_str());
+        let parent = parent_of(&branches, row);
+        let fork_index = entries.visible(&branches, parent, row.fork_position);
+        nodes.push(BranchNode {
+            id: Some(row.id.clone()),
+            name: row.name.clone(),
+            parent_id: parent.map(str::to_string),
+            depth,
+            fork_entry_id: Some(row.fork_entry_id.clone()),
+            fork_index,
+            own_entries: own(id),
+            total_entries: fork_index + own(id),
+            divergence: divergences.get(&row.id).cloned(),
+            child_ids: child_ids(id),
+            current: current.as_deref() == id,
+            sandbox: row.sandbox,
+            created_at: Some(row.created_at),
+            last_activity: entries.last_activity.get(&Some(row.id.clone())).copied(),
+        });
+        if let Some(rows) = children.get(&id) {
+            stack.extend(rows.iter().rev().map(|r| (*r, depth + 1)));
+        }
+    }
+
+    Ok(BranchTree {
+        story_id: story_id.to_string(),
+        current_branch_id: current,
+        nodes,
+    })
+}
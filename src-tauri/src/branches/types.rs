@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// One branch of the story graph. The main branch has no id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchNode {
+    pub id: Option<String>,
+    pub name: String,
+    pub parent_id: Option<String>,
+    /// Distance from the main branch
+    pub depth: usize,
+    pub fork_entry_id: Option<String>,
+    /// Entries the branch shares with its parent, up to and including the
+    /// fork entry
+    pub fork_index: usize,
+    /// Entries on the branch itself
+    pub own_entries: usize,
+    /// Entries readable on the branch, inherited ones included
+    pub total_entries: usize,
+    /// Start of the branch's first entry, showing where it went its own way
+    pub divergence: Option<String>,
+    pub child_ids: Vec<String>,
+    pub current: bool,
+    pub sandbox: bool,
+    pub created_at: Option<i64>,
+    /// When the branch's newest entry was written
+    pub last_activity: Option<i64>,
+}
+
+/// A story's branches in parent-before-child order, main first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchTree {
+    pub story_id: String,
+    pub current_branch_id: Option<String>,
+    pub nodes: Vec<BranchNode>,
+}
@@ -0,0 +1,144 @@
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::parser::parse_duration;
+use super::types::{CalendarDefinition, StoryDate, TimeTracker};
+use crate::db::{now_millis, DbState};
+
+/// Load a story's calendar, falling back to the default calendar
+pub async fn load_calendar(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<CalendarDefinition, String> {
+    let raw: Option<String> =
+        sqlx::query_scalar("SELECT definition FROM story_calendars WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load calendar: {}", e))?;
+    match raw {
+        Some(raw) => serde_json::from_str(&raw).map_err(|e| format!("Invalid calendar: {}", e)),
+        None => Ok(CalendarDefinition::default()),
+    }
+}
+
+/// Load a story's current tracker position (zero when unset)
+pub async fn load_time_tracker(pool: &SqlitePool, story_id: &str) -> Result<TimeTracker, String> {
+    let raw: Option<String> = sqlx::query_scalar("SELECT time_tracker FROM stories WHERE id = ?")
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load story time: {}", e))?
+        .flatten();
+    Ok(raw
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Move a story's tracker by `delta` minutes, saving and returning the new date
+pub async fn advance_time(
+    pool: &SqlitePool,
+    story_id: &str,
+    calendar: &CalendarDefinition,
+    delta: i64,
+) -> Result<StoryDate, String> {
+    let current = load_time_tracker(pool, story_id).await?;
+    let next = calendar.tracker_at(calendar.minutes_since_start(&current) + delta);
+    let raw = serde_json::to_string(&next)
+        .map_err(|e| format!("Failed to serialize story time: {}", e))?;
+    sqlx::query("UPDATE stories SET time_tracker = ? WHERE id = ?")
+        .bind(raw)
+        .bind(story_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save story time: {}", e))?;
+
+    Ok(calendar.date(&next))
+}
+
+/// Get the calendar a story uses
+#[tauri::command]
+pub async fn get_story_calendar(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<CalendarDefinition, String> {
+    load_calendar(db.pool(), &story_id).await
+}
+
+/// Set a story's calendar; `None` reverts to the default calendar
+#[tauri::command]
+pub async fn set_story_calendar(
+    db: State<'_, DbState>,
+    story_id: String,
+    calendar: Option<CalendarDefinition>,
+) -> Result<(), String> {
+    let Some(calendar) = calendar else {
+        sqlx::query("DELETE FROM story_calendars WHERE story_id = ?")
+            .bind(&story_id)
+            .execute(db.pool())
+            .await
+            .map_err(|e| format!("Failed to reset calendar: {}", e))?;
+        return Ok(());
+    };
+
+    calendar.validate()?;
+    let definition = serde_json::to_string(&calendar)
+        .map_err(|e| format!("Failed to serialize calendar: {}", e))?;
+    sqlx::query(
+        "INSERT INTO story_calendars (story_id, definition, updated_at) VALUES (?, ?, ?) \
+         ON CONFLICT(story_id) DO UPDATE SET definition = excluded.definition, updated_at = excluded.updated_at",
+    )
+    .bind(&story_id)
+    .bind(definition)
+    .bind(now_millis())
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save calendar: {}", e))?;
+    Ok(())
+}
+
+/// Get the story's current date; pass `time` to resolve another tracker value
+/// (e.g. a chapter's start time) against the story's calendar instead
+#[tauri::command]
+pub async fn get_story_date(
+    db: State<'_, DbState>,
+    story_id: String,
+    time: Option<TimeTracker>,
+) -> Result<StoryDate, String> {
+    let calendar = load_calendar(db.pool(), &story_id).await?;
+    let time = match time {
+        Some(time) => time,
+        None => load_time_tracker(db.pool(), &story_id).await?,
+    };
+    Ok(calendar.date(&time))
+}
+
+/// Parse a time expression ("three fortnights later") into minutes under the story's calendar
+#[tauri::command]
+pub async fn parse_time_expression(
+    db: State<'_, DbState>,
+    story_id: String,
+    expression: String,
+) -> Result<i64, String> {
+    let calendar = load_calendar(db.pool(), &story_id).await?;
+    parse_duration(&calendar, &expression)
+}
+
+/// Advance (or rewind) story time by an expression or a number of minutes,
+/// saving and returning the new date
+#[tauri::command]
+pub async fn advance_story_time(
+    db: State<'_, DbState>,
+    story_id: String,
+    expression: Option<String>,
+    minutes: Option<i64>,
+) -> Result<StoryDate, String> {
+    let calendar = load_calendar(db.pool(), &story_id).await?;
+    let delta = match (expression, minutes) {
+        (Some(expression), _) => parse_duration(&calendar, &expression)?,
+        (None, Some(minutes)) => minutes,
+        (None, None) => return Err("Either an expression or minutes is required".to_string()),
+    };
+
+    advance_time(db.pool(), &story_id, &calendar, delta).await
+}
@@ -0,0 +1,231 @@
+use super::types::{CalendarDefinition, MoonPhase, StoryDate, TimeTracker};
+
+const MOON_PHASES: [&str; 8] = [
+    "new moon",
+    "waxing crescent",
+    "first quarter",
+    "waxing gibbous",
+    "full moon",
+    "waning gibbous",
+    "last quarter",
+    "waning crescent",
+];
+
+impl CalendarDefinition {
+    /// Reject calendars the engine cannot do arithmetic with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.months.is_empty() {
+            return Err("Calendar needs at least one month".to_string());
+        }
+        if let Some(month) = self.months.iter().find(|m| m.days <= 0) {
+            return Err(format!("Month '{}' must have at least one day", month.name));
+        }
+        if self.hours_per_day <= 0 || self.minutes_per_hour <= 0 {
+            return Err("Hours per day and minutes per hour must be positive".to_string());
+        }
+        if let Some(moon) = self.moons.iter().find(|m| m.cycle_days <= 0.0) {
+            return Err(format!("Moon '{}' must have a positive cycle", moon.name));
+        }
+        if !self.weekdays.is_empty() && self.start_weekday >= self.weekdays.len() {
+            return Err("Starting weekday is out of range".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn days_per_year(&self) -> i64 {
+        self.months.iter().map(|m| m.days).sum()
+    }
+
+    pub fn minutes_per_day(&self) -> i64 {
+        self.hours_per_day * self.minutes_per_hour
+    }
+
+    /// Days in a week, falling back to seven when no weekday names are defined
+    pub fn days_per_week(&self) -> i64 {
+        match self.weekdays.len() {
+            0 => 7,
+            n => n as i64,
+        }
+    }
+
+    /// Average month length, used for "N months later"
+    pub fn average_month_days(&self) -> f64 {
+        self.days_per_year() as f64 / self.months.len() as f64
+    }
+
+    /// Minutes elapsed since the start of the story.
+    ///
+    /// Out-of-range components (e.g. day 364 of a 360-day year) carry over
+    /// naturally, so trackers normalized under another calendar stay valid.
+    pub fn minutes_since_start(&self, time: &TimeTracker) -> i64 {
+        let days = time.years * self.days_per_year() + time.days;
+        days * self.minutes_per_day() + time.hours * self.minutes_per_hour + time.minutes
+    }
+
+    /// Normalized tracker for a minute offset; negative offsets clamp to the start
+    pub fn tracker_at(&self, minutes: i64) -> TimeTracker {
+        let minutes = minutes.max(0);
+        let total_days = minutes / self.minutes_per_day();
+        let minute_of_day = minutes % self.minutes_per_day();
+        TimeTracker {
+            years: total_days / self.days_per_year(),
+            days: total_days % self.days_per_year(),
+            hours: minute_of_day / self.minutes_per_hour,
+            minutes: minute_of_day % self.minutes_per_hour,
+        }
+    }
+
+    pub fn normalize(&self, time: &TimeTracker) -> TimeTracker {
+        self.tracker_at(self.minutes_since_start(time))
+    }
+
+    /// A span of minutes in words, rounded down to its two largest units
+    /// ("2 days 3 hours")
+    pub fn format_duration(&self, minutes: i64) -> String {
+        let units = [
+            (self.minutes_per_day(), "day"),
+            (self.minutes_per_hour, "hour"),
+            (1, "minute"),
+        ];
+        let mut left = minutes.max(0);
+        let mut parts = Vec::new();
+        for (size, name) in units {
+            let count = left / size;
+            left %= size;
+            if count > 0 {
+                parts.push(format!(
+                    "{} {}{}",
+                    count,
+                    name,
+                    if count == 1 { "" } else { "s" }
+                ));
+            }
+        }
+        if parts.is_empty() {
+            return "0 minutes".to_string();
+        }
+        parts.truncate(2);
+        parts.join(" ")
+    }
+
+    /// Resolve a tracker position into calendar terms
+    pub fn date(&self, time: &TimeTracker) -> StoryDate {
+        let tracker = self.normalize(time);
+        let total_days = self.minutes_since_start(&tracker) / self.minutes_per_day();
+
+        let mut day_of_year = tracker.days;
+        let mut month_index = 0;
+        for (i, month) in self.months.iter().enumerate() {
+            if day_of_year < month.days {
+                month_index = i;
+                break;
+            }
+            day_of_year -= month.days;
+        }
+
+        let year = self.start_year + tracker.years;
+        let era = self
+            .eras
+            .iter()
+            .filter(|e| e.start_year <= year)
+            .max_by_key(|e| e.start_year);
+        let era_year = era.map_or(year, |e| year - e.start_year + 1);
+
+        let weekday = (!self.weekdays.is_empty()).then(|| {
+            let index = (self.start_weekday as i64 + total_days) % self.weekdays.len() as i64;
+            self.weekdays[index as usize].clone()
+        });
+
+        let elapsed_days = total_days as f64
+            + (tracker.hours * self.minutes_per_hour + tracker.minutes) as f64
+                / self.minutes_per_day() as f64;
+        let moons = self
+            .moons
+            .iter()
+            .map(|moon| {
+                let age = (elapsed_days + moon.phase_offset_days).rem_euclid(moon.cycle_days);
+                let fraction = age / moon.cycle_days;
+                MoonPhase {
+                    name: moon.name.clone(),
+                    phase: MOON_PHASES[((fraction * 8.0).round() as usize) % 8].to_string(),
+                    illumination: (1.0 - (fraction * std::f64::consts::TAU).cos()) / 2.0,
+                }
+            })
+            .collect();
+
+        let mut date = StoryDate {
+            tracker,
+            year,
+            era: era.map(|e| e.name.clone()),
+            era_year,
+            month: self.months[month_index].name.clone(),
+            month_index,
+            day_of_month: day_of_year + 1,
+            weekday,
+            hour: tracker.hours,
+            minute: tracker.minutes,
+            moons,
+            formatted: String::new(),
+        };
+        date.formatted = self.format(&date);
+        date
+    }
+
+    /// Render a date the same way everywhere it appears in prompts
+    fn format(&self, date: &StoryDate) -> String {
+        let mut text = String::new();
+        if let Some(weekday) = &date.weekday {
+            text.push_str(weekday);
+            text.push_str(", ");
+        }
+        text.push_str(&format!("{} {}, ", date.day_of_month, date.month));
+        match (
+            &date.era,
+            self.eras
+                .iter()
+                .find(|e| Some(&e.name) == date.era.as_ref()),
+        ) {
+            (Some(_), Some(era)) => match &era.abbreviation {
+                Some(abbr) if !abbr.is_empty() => {
+                    text.push_str(&format!("{} {}", date.era_year, abbr))
+                }
+                _ => text.push_str(&format!("year {} of the {}", date.era_year, era.name)),
+            },
+            _ => text.push_str(&format!("year {}", date.year)),
+        }
+
+        let hour_width = self
+            .hours_per_day
+            .saturating_sub(1)
+            .to_string()
+            .len()
+            .max(2);
+        let minute_width = self
+            .minutes_per_hour
+            .saturating_sub(1)
+            .to_string()
+            .len()
+            .max(2);
+        text.push_str(&format!(
+            ", {:0hw$}:{:0mw$}",
+            date.hour,
+            date.minute,
+            hw = hour_width,
+            mw = minute_width
+        ));
+
+        if !date.moons.is_empty() {
+            let moons = date
+                .moons
+                .iter()
+                .map(|m| match self.moons.len() {
+                    1 => m.phase.clone(),
+                    _ => format!("{}: {}", m.name, m.phase),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            text.push_str(&format!(" ({})", moons));
+        }
+        text
+    }
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod engine;
+pub mod parser;
+pub mod types;
+pub mod update;
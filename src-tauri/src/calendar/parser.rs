@@ -0,0 +1,140 @@
+use super::types::CalendarDefinition;
+
+/// Parse an in-story time expression ("three fortnights later", "2 hours and
+/// a half", "a day ago") into a signed number of minutes for `calendar`.
+///
+/// Weeks, months, and years use the calendar's own lengths; moon cycles
+/// ("two moons later") use the first moon.
+pub fn parse_duration(calendar: &CalendarDefinition, expression: &str) -> Result<i64, String> {
+    let normalized = expression.to_lowercase().replace('-', " ");
+    let words: Vec<&str> = normalized
+        .split(|c: char| !c.is_alphanumeric() && c != '.')
+        .map(|w| w.trim_matches('.'))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut total = 0.0;
+    let mut matched = false;
+    let mut sign = 1.0;
+    let mut pending: Option<f64> = None;
+    let mut last_unit: Option<f64> = None;
+    let mut i = 0;
+
+    while i < words.len() {
+        let word = words[i];
+
+        // "and a half" either completes a pending count ("two and a half
+        // hours") or adds to the previous unit ("two hours and a half")
+        if word == "and" && words.get(i + 1) == Some(&"a") && words.get(i + 2) == Some(&"half") {
+            match (pending, last_unit) {
+                (Some(n), _) => pending = Some(n + 0.5),
+                (None, Some(unit)) => total += unit * 0.5,
+                _ => {}
+            }
+            i += 3;
+            continue;
+        }
+
+        if let Some(unit) = unit_minutes(calendar, word) {
+            let amount = pending.take().unwrap_or(1.0);
+            total += amount * unit;
+            last_unit = Some(unit);
+            matched = true;
+            i += 1;
+            continue;
+        }
+
+        match word {
+            "tomorrow" | "overnight" => {
+                total += calendar.minutes_per_day() as f64;
+                matched = true;
+            }
+            "yesterday" => {
+                total -= calendar.minutes_per_day() as f64;
+                matched = true;
+            }
+            "ago" | "earlier" | "before" | "back" => sign = -1.0,
+            "half" => pending = Some(pending.unwrap_or(1.0) * 0.5),
+            "quarter" => pending = Some(pending.unwrap_or(1.0) * 0.25),
+            "a" | "an" if pending.is_none() => pending = Some(1.0),
+            "couple" => pending = Some(2.0),
+            "few" | "several" => pending = Some(3.0),
+            "dozen" => pending = Some(pending.unwrap_or(1.0) * 12.0),
+            "hundred" => pending = Some(pending.unwrap_or(1.0) * 100.0),
+            "thousand" => pending = Some(pending.unwrap_or(1.0) * 1000.0),
+            _ => {
+                if let Some(n) = number_word(word).or_else(|| word.parse::<f64>().ok()) {
+                    // "twenty one" and "a hundred twenty" combine; anything else starts a new count
+                    let place = if n < 10.0 { 10.0 } else { 100.0 };
+                    pending = Some(match pending {
+                        Some(p) if p > n && p % place == 0.0 => p + n,
+                        _ => n,
+                    });
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if !matched {
+        return Err(format!(
+            "Could not understand time expression: {}",
+            expression
+        ));
+    }
+    Ok((sign * total).round() as i64)
+}
+
+/// Length of a unit word in minutes under `calendar`
+fn unit_minutes(calendar: &CalendarDefinition, word: &str) -> Option<f64> {
+    let day = calendar.minutes_per_day() as f64;
+    let minutes = match word {
+        "minute" | "minutes" | "min" | "mins" => 1.0,
+        "hour" | "hours" | "hr" | "hrs" => calendar.minutes_per_hour as f64,
+        "day" | "days" | "night" | "nights" => day,
+        "week" | "weeks" | "sennight" | "sennights" => day * calendar.days_per_week() as f64,
+        "fortnight" | "fortnights" => day * calendar.days_per_week() as f64 * 2.0,
+        "month" | "months" => day * calendar.average_month_days(),
+        "year" | "years" => day * calendar.days_per_year() as f64,
+        "decade" | "decades" => day * calendar.days_per_year() as f64 * 10.0,
+        "century" | "centuries" => day * calendar.days_per_year() as f64 * 100.0,
+        "moon" | "moons" => day * calendar.moons.first()?.cycle_days,
+        _ => return None,
+    };
+    Some(minutes)
+}
+
+fn number_word(word: &str) -> Option<f64> {
+    let n = match word {
+        "zero" => 0,
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "thirteen" => 13,
+        "fourteen" => 14,
+        "fifteen" => 15,
+        "sixteen" => 16,
+        "seventeen" => 17,
+        "eighteen" => 18,
+        "nineteen" => 19,
+        "twenty" => 20,
+        "thirty" => 30,
+        "forty" => 40,
+        "fifty" => 50,
+        "sixty" => 60,
+        "seventy" => 70,
+        "eighty" => 80,
+        "ninety" => 90,
+        _ => return None,
+    };
+    Some(n as f64)
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+/// Mirrors the frontend's TimeTracker (`stories.time_tracker` JSON).
+///
+/// `years` and `days` are zero-based offsets from the start of the story.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeTracker {
+    pub years: i64,
+    pub days: i64,
+    pub hours: i64,
+    pub minutes: i64,
+}
+
+/// A story's calendar, stored as JSON in `story_calendars`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarDefinition {
+    pub name: String,
+    pub months: Vec<CalendarMonth>,
+    /// Day names in order; the week length is the number of names
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+    #[serde(default = "default_hours_per_day")]
+    pub hours_per_day: i64,
+    #[serde(default = "default_minutes_per_hour")]
+    pub minutes_per_hour: i64,
+    #[serde(default)]
+    pub moons: Vec<CalendarMoon>,
+    /// Named eras, each starting at a displayed year
+    #[serde(default)]
+    pub eras: Vec<CalendarEra>,
+    /// Displayed year at the start of the story (tracker year 0)
+    #[serde(default = "default_start_year")]
+    pub start_year: i64,
+    /// Index into `weekdays` of the story's first day
+    #[serde(default)]
+    pub start_weekday: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarMonth {
+    pub name: String,
+    pub days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarMoon {
+    pub name: String,
+    /// Days from new moon to new moon
+    pub cycle_days: f64,
+    /// Days into the cycle at the start of the story
+    #[serde(default)]
+    pub phase_offset_days: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarEra {
+    pub name: String,
+    #[serde(default)]
+    pub abbreviation: Option<String>,
+    /// First displayed year of the era; years within it count from 1
+    pub start_year: i64,
+}
+
+/// A tracker position resolved against a calendar
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryDate {
+    pub tracker: TimeTracker,
+    pub year: i64,
+    pub era: Option<String>,
+    pub era_year: i64,
+    pub month: String,
+    pub month_index: usize,
+    pub day_of_month: i64,
+    pub weekday: Option<String>,
+    pub hour: i64,
+    pub minute: i64,
+    pub moons: Vec<MoonPhase>,
+    /// Prompt-ready rendering, e.g. "Fireday, 3 Frostfall, year 412 of the Third Age, 14:05"
+    pub formatted: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoonPhase {
+    pub name: String,
+    pub phase: String,
+    /// 0.0 at new moon, 1.0 at full moon
+    pub illumination: f64,
+}
+
+fn default_hours_per_day() -> i64 {
+    24
+}
+
+fn default_minutes_per_hour() -> i64 {
+    60
+}
+
+fn default_start_year() -> i64 {
+    1
+}
+
+impl Default for CalendarDefinition {
+    /// Earth-like calendar without leap years, matching the tracker's historical 365-day years
+    fn default() -> Self {
+        let months = [
+            ("January", 31),
+            ("February", 28),
+            ("March", 31),
+            ("April", 30),
+            ("May", 31),
+            ("June", 30),
+            ("July", 31),
+            ("August", 31),
+            ("September", 30),
+            ("October", 31),
+            ("November", 30),
+            ("December", 31),
+        ];
+        let weekdays = [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ];
+        Self {
+            name: "Standard".to_string(),
+            months: months
+                .iter()
+                .map(|(name, days)| CalendarMonth {
+                    name: name.to_string(),
+                    days: *days,
+                })
+                .collect(),
+            weekdays: weekdays.iter().map(|d| d.to_string()).collect(),
+            hours_per_day: default_hours_per_day(),
+            minutes_per_hour: default_minutes_per_hour(),
+            moons: vec![CalendarMoon {
+                name: "Moon".to_string(),
+                cycle_days: 29.53,
+                phase_offset_days: 0.0,
+            }],
+            eras: Vec::new(),
+            start_year: default_start_year(),
+            start_weekday: 0,
+        }
+    }
+}
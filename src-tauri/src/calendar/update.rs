@@ -0,0 +1,104 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use super::commands::{advance_time, load_calendar, load_time_tracker};
+use super::parser::parse_duration;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You track the passage of time in an interactive story. \
+Given the current story date and the latest passage, estimate how much in-story time the passage covers. \
+Respond with JSON only, in this shape: {\"elapsed\": \"<duration such as '20 minutes' or '2 days', or empty>\", \
+\"reason\": \"<short explanation>\"}. Use an empty elapsed value when no meaningful time passes.";
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "time_update",
+        schema: json!({
+            "type": "object",
+            "required": ["elapsed"],
+            "properties": {
+                "elapsed": { "type": "string" },
+                "reason": { "type": "string" }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TimeReply {
+    elapsed: String,
+    reason: String,
+}
+
+/// Background job: ask the classification model how much time an entry
+/// covers and advance the story clock by it. Emits `story-time-updated`.
+pub async fn run_time_update_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Time update job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let calendar = load_calendar(pool, &story_id).await?;
+    let current = calendar.date(&load_time_tracker(pool, &story_id).await?);
+    let prompt = format!(
+        "Current date: {}\n\nPassage:\n{}",
+        current.formatted, content
+    );
+
+    let llm = config::resolve_service(pool, "timeUpdate", "classification").await?;
+    let reply: TimeReply = complete_structured(
+        pool,
+        "timeUpdate",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let elapsed = reply.elapsed.trim();
+    let minutes = if elapsed.is_empty() {
+        0
+    } else {
+        parse_duration(&calendar, elapsed)?
+    };
+    if minutes <= 0 {
+        return Ok(json!({ "minutes": 0, "date": current }));
+    }
+
+    let date = advance_time(pool, &story_id, &calendar, minutes).await?;
+    let payload = json!({
+        "storyId": story_id,
+        "entryId": entry_id,
+        "minutes": minutes,
+        "reason": reply.reason,
+        "date": date,
+    });
+    if let Err(e) = app.emit("story-time-updated", &payload) {
+        eprintln!("Failed to emit story time event: {}", e);
+    }
+    Ok(payload)
+}
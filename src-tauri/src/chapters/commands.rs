@@ -0,0 +1,23 @@
+use tauri::State;
+
+use super::store;
+use super::types::Chapter;
+use crate::db::DbState;
+
+#[tauri::command]
+pub async fn split_chapter(
+    db: State<'_, DbState>,
+    chapter_id: String,
+    at_entry_id: String,
+) -> Result<Vec<Chapter>, String> {
+    store::split(db.pool(), &chapter_id, &at_entry_id).await
+}
+
+#[tauri::command]
+pub async fn merge_chapters(
+    db: State<'_, DbState>,
+    a: String,
+    b: String,
+) -> Result<Chapter, String> {
+    store::merge(db.pool(), &a, &b).await
+}
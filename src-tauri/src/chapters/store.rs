@@ -0,0 +1,265 @@
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use super::types::Chapter;
+use crate::context::branch::{lineage, visible_entries};
+
+pub async fn load_chapter(pool: &SqlitePool, chapter_id: &str) -> Result<Chapter, String> {
+    sqlx::query_as("SELECT * FROM chapters WHERE id = ?")
+        .bind(chapter_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load chapter: {}", e))?
+        .ok_or_else(|| format!("Chapter not found: {}", chapter_id))
+}
+
+/// The current branch's entry ids, and where a chapter starts and ends in
+/// them. Chapters off the current branch can't be reorganized from here.
+async fn span(pool: &SqlitePool, chapter: &Chapter) -> Result<(Vec<String>, usize, usize), String> {
+    if let Some(branch) = &chapter.branch_id {
+        if !lineage(pool, &chapter.story_id)
+            .await?
+            .iter()
+            .any(|b| &b.id == branch)
+        {
+            return Err("Switch to the chapter's branch first".to_string());
+        }
+    }
+    let ids: Vec<String> = visible_entries(pool, &chapter.story_id)
+        .await?
+        .into_iter()
+        .map(|e| e.id)
+        .collect();
+    let find = |id: &str| {
+        ids.iter()
+            .position(|e| e == id)
+            .ok_or_else(|| format!("Chapter {} has lost its boundary entries", chapter.number))
+    };
+    let (start, end) = (find(&chapter.start_entry_id)?, find(&chapter.end_entry_id)?);
+    if end < start {
+        return Err(format!("Chapter {} ends before it starts", chapter.number));
+    }
+    Ok((ids, start, end))
+}
+
+/// `timeStart` or `timeEnd` from an entry's metadata, as chapters store it
+async fn entry_time(
+    pool: &SqlitePool,
+    entry_id: &str,
+    key: &str,
+) -> Result<Option<String>, String> {
+    let metadata: Option<Option<String>> =
+        sqlx::query_scalar("SELECT metadata FROM story_entries WHERE id = ?")
+            .bind(entry_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load entry: {}", e))?;
+    Ok(metadata
+        .flatten()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|meta| {
+            meta.get(key)
+                .filter(|t| !t.is_null())
+                .map(|t| t.to_string())
+        }))
+}
+
+/// Shift the numbers of chapters after `after` by `delta`: those on the
+/// chapter's own branch and on every branch that inherits it
+async fn renumber(
+    tx: &mut Transaction<'_, Sqlite>,
+    chapter: &Chapter,
+    after: i64,
+    delta: i64,
+) -> Result<(), String> {
+    let branches: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT id, parent_branch_id FROM branches WHERE story_id = ?")
+            .bind(&chapter.story_id)
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to load branches: {}", e))?;
+    let mut scope: Vec<Option<String>> = vec![chapter.branch_id.clone()];
+    if chapter.branch_id.is_none() {
+        scope.extend(branches.iter().map(|(id, _)| Some(id.clone())));
+    } else {
+        let mut i = 0;
+        while i < scope.len() {
+            let children: Vec<Option<String>> = branches
+                .iter()
+                .filter(|(id, parent)| parent == &scope[i] && !scope.contains(&Some(id.clone())))
+                .map(|(id, _)| Some(id.clone()))
+                .collect();
+            scope.extend(children);
+            i += 1;
+        }
+    }
+    for branch in scope {
+        sqlx::query(
+            "UPDATE chapters SET number = number + ? \
+             WHERE story_id = ? AND branch_id IS ? AND number > ?",
+        )
+        .bind(delta)
+        .bind(&chapter.story_id)
+        .bind(branch)
+        .bind(after)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to renumber chapters: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Union of two JSON string arrays, first one's order first
+fn merge_lists(a: &Option<String>, b: &Option<String>) -> Option<String> {
+    let parse = |raw: &Option<String>| -> Vec<String> {
+        raw.as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default()
+    };
+    let mut merged = parse(a);
+    for item in parse(b) {
+        if !merged.contains(&item) {
+            merged.push(item);
+        }
+    }
+    if a.is_none() && b.is_none() {
+        return None;
+    }
+    serde_json::to_string(&merged).ok()
+}
+
+/// Split a chapter so a new one starts at `at_entry_id`. Both halves keep
+/// the old summary, flagged stale until they're summarized again, and take
+/// their time spans from the entries at the cut.
+pub async fn split(
+    pool: &SqlitePool,
+    chapter_id: &str,
+    at_entry_id: &str,
+) -> Result<Vec<Chapter>, String> {
+    let chapter = load_chapter(pool, chapter_id).await?;
+    let (ids, start, end) = span(pool, &chapter).await?;
+    let at = ids[start..=end]
+        .iter()
+        .position(|id| id == at_entry_id)
+        .map(|i| start + i)
+        .ok_or("That entry isn't in this chapter")?;
+    if at == start {
+        return Err("A chapter can't be split at its first entry".to_string());
+    }
+
+    let last_of_first = &ids[at - 1];
+    let cut_start = entry_time(pool, at_entry_id, "timeStart").await?;
+    let first_end = entry_time(pool, last_of_first, "timeEnd")
+        .await?
+        .or_else(|| cut_start.clone());
+    let second_start = cut_start.or_else(|| first_end.clone());
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    renumber(&mut tx, &chapter, chapter.number, 1).await?;
+    sqlx::query(
+        "UPDATE chapters SET end_entry_id = ?, entry_count = ?, end_time = ?, summary_stale = 1 \
+         WHERE id = ?",
+    )
+    .bind(last_of_first)
+    .bind((at - start) as i64)
+    .bind(&first_end)
+    .bind(&chapter.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update chapter: {}", e))?;
+    let second_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO chapters (id, story_id, number, title, start_entry_id, end_entry_id, \
+         entry_count, summary, keywords, characters, locations, plot_threads, emotional_tone, \
+         arc_id, start_time, end_time, branch_id, summary_stale, created_at) \
+         SELECT ?, story_id, number + 1, title, ?, end_entry_id, ?, summary, keywords, characters, \
+         locations, plot_threads, emotional_tone, arc_id, ?, ?, branch_id, 1, created_at \
+         FROM chapters WHERE id = ?",
+    )
+    .bind(&second_id)
+    .bind(at_entry_id)
+    .bind((end - at + 1) as i64)
+    .bind(&second_start)
+    .bind(&chapter.end_time)
+    .bind(&chapter.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create chapter: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to split chapter: {}", e))?;
+
+    Ok(vec![
+        load_chapter(pool, &chapter.id).await?,
+        load_chapter(pool, &second_id).await?,
+    ])
+}
+
+/// Merge two adjacent chapters on the same branch into the earlier one.
+/// Summaries are joined and flagged stale; the later chapter is removed.
+pub async fn merge(pool: &SqlitePool, a_id: &str, b_id: &str) -> Result<Chapter, String> {
+    if a_id == b_id {
+        return Err("Pick two different chapters".to_string());
+    }
+    let (a, b) = (
+        load_chapter(pool, a_id).await?,
+        load_chapter(pool, b_id).await?,
+    );
+    if a.story_id != b.story_id || a.branch_id != b.branch_id {
+        return Err("Only chapters on the same branch can be merged".to_string());
+    }
+    let (_, a_start, a_end) = span(pool, &a).await?;
+    let (_, b_start, b_end) = span(pool, &b).await?;
+    let (first, second, first_span, second_span) = if a_start < b_start {
+        (a, b, (a_start, a_end), (b_start, b_end))
+    } else {
+        (b, a, (b_start, b_end), (a_start, a_end))
+    };
+    let (first_end, second_start) = (first_span.1, second_span.0);
+    if first_end + 1 != second_start {
+        return Err(format!(
+            "Chapters {} and {} aren't next to each other",
+            first.number, second.number
+        ));
+    }
+
+    let summary = [first.summary.trim(), second.summary.trim()]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    sqlx::query(
+        "UPDATE chapters SET end_entry_id = ?, entry_count = ?, end_time = ?, summary = ?, \
+         keywords = ?, characters = ?, locations = ?, plot_threads = ?, summary_stale = 1 \
+         WHERE id = ?",
+    )
+    .bind(&second.end_entry_id)
+    .bind((second_span.1 - first_span.0 + 1) as i64)
+    .bind(second.end_time.as_ref().or(first.end_time.as_ref()))
+    .bind(&summary)
+    .bind(merge_lists(&first.keywords, &second.keywords))
+    .bind(merge_lists(&first.characters, &second.characters))
+    .bind(merge_lists(&first.locations, &second.locations))
+    .bind(merge_lists(&first.plot_threads, &second.plot_threads))
+    .bind(&first.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update chapter: {}", e))?;
+    sqlx::query("DELETE FROM chapters WHERE id = ?")
+        .bind(&second.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete chapter: {}", e))?;
+    renumber(&mut tx, &second, second.number, -1).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to merge chapters: {}", e))?;
+    load_chapter(pool, &first.id).await
+}
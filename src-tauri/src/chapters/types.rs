@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+/// A chapter row. List and time fields hold the JSON the frontend stores.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub id: String,
+    pub story_id: String,
+    pub number: i64,
+    pub title: Option<String>,
+    pub start_entry_id: String,
+    pub end_entry_id: String,
+    pub entry_count: i64,
+    pub summary: String,
+    pub keywords: Option<String>,
+    pub characters: Option<String>,
+    pub locations: Option<String>,
+    pub plot_threads: Option<String>,
+    pub emotional_tone: Option<String>,
+    pub arc_id: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub branch_id: Option<String>,
+    pub summary_stale: bool,
+    pub created_at: i64,
+}
@@ -0,0 +1,28 @@
+use tauri::{AppHandle, State};
+
+use super::ClipboardWatcher;
+
+/// Turn the clipboard card watcher on or off for this session. The
+/// frontend keeps the choice in `clipboard_watch_settings`, which is read
+/// again at launch.
+#[tauri::command]
+pub fn set_clipboard_watch(
+    app: AppHandle,
+    watcher: State<'_, ClipboardWatcher>,
+    enabled: bool,
+) -> Result<(), String> {
+    if cfg!(mobile) {
+        return Err("Clipboard watching is only available on desktop".to_string());
+    }
+    if enabled {
+        watcher.start(app);
+    } else {
+        watcher.stop();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_clipboard_watch_status(watcher: State<'_, ClipboardWatcher>) -> bool {
+    watcher.is_running()
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod platform;
+pub mod watcher;
+
+pub use watcher::ClipboardWatcher;
@@ -0,0 +1,128 @@
+// Mobile has no clipboard watcher, only the stub `read`
+#![cfg_attr(mobile, allow(dead_code))]
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// A clipboard tool that hangs (e.g. waiting on a dead X selection owner)
+/// is killed after this long
+const READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What the clipboard holds that could be a character card
+pub enum ClipboardImage {
+    /// Raw PNG data, as browsers put it when copying an image
+    Png(Vec<u8>),
+    /// Files copied in a file manager
+    Files(Vec<PathBuf>),
+}
+
+async fn run(program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let output = tokio::time::timeout(READ_TIMEOUT, child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+/// Local paths from a `text/uri-list` or newline-separated path list
+fn parse_paths(list: &[u8]) -> Vec<PathBuf> {
+    String::from_utf8_lossy(list)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| match l.starts_with("file://") {
+            true => reqwest::Url::parse(l).ok()?.to_file_path().ok(),
+            false => Some(PathBuf::from(l)),
+        })
+        .filter(|p| p.is_absolute())
+        .collect()
+}
+
+#[cfg(all(desktop, unix, not(target_os = "macos")))]
+pub async fn read() -> Option<ClipboardImage> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let (png, files) = if wayland {
+        (
+            run("wl-paste", &["--no-newline", "--type", "image/png"]).await,
+            run("wl-paste", &["--no-newline", "--type", "text/uri-list"]).await,
+        )
+    } else {
+        (
+            run(
+                "xclip",
+                &["-selection", "clipboard", "-t", "image/png", "-o"],
+            )
+            .await,
+            run(
+                "xclip",
+                &["-selection", "clipboard", "-t", "text/uri-list", "-o"],
+            )
+            .await,
+        )
+    };
+    match (png, files) {
+        (Some(png), _) => Some(ClipboardImage::Png(png)),
+        (None, Some(list)) => Some(ClipboardImage::Files(parse_paths(&list))),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub async fn read() -> Option<ClipboardImage> {
+    // AppleScript prints PNG data as «data PNGf89504E47...»
+    if let Some(out) = run("osascript", &["-e", "the clipboard as «class PNGf»"]).await {
+        let text = String::from_utf8_lossy(&out);
+        let hex: String = text
+            .trim()
+            .trim_start_matches("«data PNGf")
+            .trim_end_matches('»')
+            .to_string();
+        if let Ok(png) = hex::decode(hex) {
+            return Some(ClipboardImage::Png(png));
+        }
+    }
+    let list = run(
+        "osascript",
+        &["-e", "POSIX path of (the clipboard as «class furl»)"],
+    )
+    .await?;
+    Some(ClipboardImage::Files(parse_paths(&list)))
+}
+
+#[cfg(windows)]
+pub async fn read() -> Option<ClipboardImage> {
+    use base64::Engine;
+
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+        $png = [Windows.Forms.Clipboard]::GetData('PNG'); \
+        if ($png) { 'png:' + [Convert]::ToBase64String($png.ToArray()) } \
+        else { [Windows.Forms.Clipboard]::GetFileDropList() }";
+    let out = run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-STA", "-Command", SCRIPT],
+    )
+    .await?;
+    let text = String::from_utf8_lossy(&out);
+    match text.trim().strip_prefix("png:") {
+        Some(data) => base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .ok()
+            .map(ClipboardImage::Png),
+        None => Some(ClipboardImage::Files(parse_paths(&out))),
+    }
+}
+
+#[cfg(mobile)]
+pub async fn read() -> Option<ClipboardImage> {
+    None
+}
@@ -0,0 +1,158 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::platform::{self, ClipboardImage};
+use crate::db::DbState;
+use crate::file_import::sniff;
+use crate::llm::config::get_setting;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Clipboard watcher options, stored in `clipboard_watch_settings`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardWatchSettings {
+    pub enabled: bool,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ClipboardWatchSettings, String> {
+    Ok(get_setting(pool, "clipboard_watch_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Payload of the `clipboard-card` event, ready for the vault's card import
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardCard {
+    pub name: String,
+    /// Card JSON
+    pub content: String,
+    /// The card PNG as a data URL, for the portrait
+    pub image: String,
+}
+
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ClipboardWatcher {
+    pub fn start(&self, app: AppHandle) {
+        let mut task = self.task.lock().unwrap();
+        if task.is_none() {
+            *task = Some(tauri::async_runtime::spawn(watch(app)));
+        }
+    }
+
+    pub fn stop(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task.lock().unwrap().is_some()
+    }
+}
+
+fn card_name(content: &str) -> String {
+    let card: Value = serde_json::from_str(content).unwrap_or_default();
+    card.pointer("/data/name")
+        .or_else(|| card.get("name"))
+        .and_then(Value::as_str)
+        .unwrap_or("Unnamed character")
+        .to_string()
+}
+
+/// The copied PNG, from image data or the first copied `.png` file
+async fn clipboard_png(image: ClipboardImage) -> Option<Vec<u8>> {
+    match image {
+        ClipboardImage::Png(bytes) => Some(bytes),
+        ClipboardImage::Files(paths) => {
+            let path = paths
+                .into_iter()
+                .find(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")))?;
+            tokio::fs::read(path).await.ok()
+        }
+    }
+}
+
+fn fingerprint(image: &ClipboardImage) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    match image {
+        ClipboardImage::Png(bytes) => hasher.update(bytes),
+        ClipboardImage::Files(paths) => {
+            for path in paths {
+                hasher.update(path.to_string_lossy().as_bytes());
+            }
+        }
+    }
+    hasher.finalize().into()
+}
+
+/// Poll the clipboard and emit `clipboard-card` once per newly copied card
+async fn watch(app: AppHandle) {
+    // Whatever was copied before the watcher started isn't offered
+    let mut last = platform::read().await.map(|image| fingerprint(&image));
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let Some(image) = platform::read().await else {
+            last = None;
+            continue;
+        };
+        // Only look at each clipboard content once, and skip re-reading
+        // copied files until the copied set changes
+        let seen = Some(fingerprint(&image));
+        if seen == last {
+            continue;
+        }
+        last = seen;
+
+        let Some(png) = clipboard_png(image).await.filter(|b| sniff::is_png(b)) else {
+            continue;
+        };
+        let Some(content) = sniff::card_from_png(&png) else {
+            continue;
+        };
+        let card = ClipboardCard {
+            name: card_name(&content),
+            content,
+            image: format!(
+                "data:image/png;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(&png)
+            ),
+        };
+        if let Err(e) = app.emit("clipboard-card", &card) {
+            eprintln!("Failed to emit clipboard-card: {}", e);
+        }
+    }
+}
+
+/// Start the watcher at launch if the user turned it on
+pub fn restore(app: AppHandle) {
+    if cfg!(mobile) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The settings table only exists once the frontend has run migrations
+        let settings = loop {
+            match load_settings(&pool).await {
+                Ok(settings) => break settings,
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        if settings.enabled {
+            app.state::<ClipboardWatcher>().start(app.clone());
+        }
+    });
+}
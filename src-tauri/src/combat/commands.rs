@@ -0,0 +1,63 @@
+use tauri::State;
+
+use super::engine::resolve_round;
+use super::store::{self, load_encounter, load_settings, save_encounter, sync_sheets};
+use super::types::{CombatAction, CombatantSetup, Encounter};
+use crate::db::{now_millis, DbState};
+
+/// Start a fight. Stats missing from a setup come from the character's sheet,
+/// then the combat settings. Pass `seed` to replay a fight exactly.
+#[tauri::command]
+pub async fn start_combat(
+    db: State<'_, DbState>,
+    story_id: String,
+    combatants: Vec<CombatantSetup>,
+    seed: Option<i64>,
+) -> Result<Encounter, String> {
+    store::start_encounter(db.pool(), &story_id, combatants, seed).await
+}
+
+/// Resolve the next round. Combatants without an action attack the weakest
+/// foe. The outcome is injected into the next prompt for narration.
+#[tauri::command]
+pub async fn resolve_combat_round(
+    db: State<'_, DbState>,
+    encounter_id: String,
+    actions: Vec<CombatAction>,
+) -> Result<Encounter, String> {
+    let pool = db.pool();
+    let settings = load_settings(pool).await?;
+    let mut encounter = load_encounter(pool, &encounter_id).await?;
+    resolve_round(&mut encounter, &actions, &settings)?;
+    encounter.updated_at = now_millis();
+    save_encounter(pool, &encounter).await?;
+    sync_sheets(pool, &encounter, &settings).await?;
+    Ok(encounter)
+}
+
+#[tauri::command]
+pub async fn get_combat(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Option<Encounter>, String> {
+    store::current_encounter(db.pool(), &story_id).await
+}
+
+/// Stop a fight without a result. Rounds not yet narrated are dropped.
+#[tauri::command]
+pub async fn end_combat(db: State<'_, DbState>, encounter_id: String) -> Result<(), String> {
+    let pool = db.pool();
+    let mut encounter = load_encounter(pool, &encounter_id).await?;
+    if encounter.status == "active" {
+        encounter.status = "ended".to_string();
+    }
+    encounter.narrated_round = encounter.round;
+    encounter.updated_at = now_millis();
+    save_encounter(pool, &encounter).await
+}
+
+/// Mark resolved rounds as told, e.g. after narrating them by hand
+#[tauri::command]
+pub async fn mark_combat_narrated(db: State<'_, DbState>, story_id: String) -> Result<(), String> {
+    store::mark_narrated(db.pool(), &story_id).await
+}
@@ -0,0 +1,56 @@
+//! Dice expressions such as "1d20", "2d6+3" or "d8-1"
+
+use rand::Rng;
+
+/// A parsed dice expression: `count` dice of `sides` sides plus `modifier`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub modifier: i64,
+}
+
+/// Most dice one expression may roll
+const MAX_DICE: u32 = 100;
+
+impl Dice {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let compact: String = expression
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        let invalid = || format!("Invalid dice expression: '{}'", expression);
+        let (count, rest) = compact.split_once('d').ok_or_else(invalid)?;
+        let count: u32 = if count.is_empty() {
+            1
+        } else {
+            count.parse().map_err(|_| invalid())?
+        };
+        let split = rest.find(['+', '-']).unwrap_or(rest.len());
+        let sides: u32 = rest[..split].parse().map_err(|_| invalid())?;
+        let modifier: i64 = match &rest[split..] {
+            "" => 0,
+            m => m.parse().map_err(|_| invalid())?,
+        };
+        if count == 0 || count > MAX_DICE || sides < 2 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            count,
+            sides,
+            modifier,
+        })
+    }
+
+    /// Sum of the dice without the modifier
+    pub fn roll_dice(&self, rng: &mut impl Rng) -> i64 {
+        (0..self.count)
+            .map(|_| rng.gen_range(1..=self.sides) as i64)
+            .sum()
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> i64 {
+        self.roll_dice(rng) + self.modifier
+    }
+}
@@ -0,0 +1,426 @@
+//! Deterministic combat resolution. Every roll of a round comes from an RNG
+//! seeded with the encounter's seed and the round number, so resolving the
+//! same round with the same actions always gives the same outcome.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::dice::Dice;
+use super::types::{
+    CombatAction, CombatEvent, CombatRound, CombatSettings, Combatant, Encounter, Side,
+    DEFEND_BONUS,
+};
+
+fn round_rng(seed: i64, round: i64) -> StdRng {
+    StdRng::seed_from_u64((seed as u64) ^ (round as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// "+3" or "-1", empty for no bonus
+fn signed(bonus: i64) -> String {
+    match bonus {
+        0 => String::new(),
+        b => format!("{:+}", b),
+    }
+}
+
+fn event(actor: &Combatant, target: Option<&Combatant>, text: String) -> CombatEvent {
+    CombatEvent {
+        actor: actor.id.clone(),
+        target: target.map(|t| t.id.clone()),
+        roll: None,
+        total: None,
+        against: None,
+        damage: None,
+        text,
+    }
+}
+
+/// Find a combatant by encounter id or name, ignoring case
+pub fn find(combatants: &[Combatant], key: &str) -> Option<usize> {
+    let key = key.trim();
+    combatants.iter().position(|c| c.id == key).or_else(|| {
+        combatants
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(key))
+    })
+}
+
+/// Roll initiative for everyone and sort them into turn order (round 0)
+pub fn roll_initiative(encounter: &mut Encounter, check: &Dice) {
+    let mut rng = round_rng(encounter.seed, 0);
+    for combatant in &mut encounter.combatants {
+        combatant.initiative = check.roll(&mut rng) + combatant.initiative_bonus;
+    }
+    encounter.combatants.sort_by(|a, b| {
+        b.initiative
+            .cmp(&a.initiative)
+            .then_with(|| b.initiative_bonus.cmp(&a.initiative_bonus))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Resolve one round: every active combatant takes a turn in initiative
+/// order, using their action from `actions` or attacking the weakest foe.
+/// Stops early once one side has no one left standing.
+pub fn resolve_round(
+    encounter: &mut Encounter,
+    actions: &[CombatAction],
+    settings: &CombatSettings,
+) -> Result<(), String> {
+    if encounter.status != "active" {
+        return Err("This combat is over".to_string());
+    }
+    let check = Dice::parse(&settings.check_die)?;
+    let mut planned: Vec<Option<&CombatAction>> = vec![None; encounter.combatants.len()];
+    for action in actions {
+        let actor = find(&encounter.combatants, action.actor())
+            .ok_or_else(|| format!("No combatant called '{}'", action.actor()))?;
+        if let CombatAction::Attack { target, .. } = action {
+            find(&encounter.combatants, target)
+                .ok_or_else(|| format!("No combatant called '{}'", target))?;
+        }
+        planned[actor] = Some(action);
+    }
+
+    encounter.round += 1;
+    let mut rng = round_rng(encounter.seed, encounter.round);
+    let mut events = Vec::new();
+    for (turn, action) in planned.into_iter().enumerate() {
+        if encounter.status != "active" {
+            break;
+        }
+        if !encounter.combatants[turn].is_active() {
+            continue;
+        }
+        take_turn(encounter, turn, action, &check, &mut rng, &mut events)?;
+        encounter.status = outcome(&encounter.combatants).to_string();
+    }
+    encounter.rounds.push(CombatRound {
+        round: encounter.round,
+        events,
+    });
+    Ok(())
+}
+
+fn take_turn(
+    encounter: &mut Encounter,
+    turn: usize,
+    action: Option<&CombatAction>,
+    check: &Dice,
+    rng: &mut StdRng,
+    events: &mut Vec<CombatEvent>,
+) -> Result<(), String> {
+    let combatants = &mut encounter.combatants;
+    combatants[turn].defending = false;
+
+    let ticking: i64 = combatants[turn]
+        .effects
+        .iter()
+        .map(|e| e.damage_per_round)
+        .sum();
+    if ticking > 0 {
+        let actor = &mut combatants[turn];
+        actor.hp = (actor.hp - ticking).max(0);
+        let causes: Vec<&str> = actor
+            .effects
+            .iter()
+            .filter(|e| e.damage_per_round > 0)
+            .map(|e| e.name.as_str())
+            .collect();
+        let mut tick = event(
+            actor,
+            None,
+            format!(
+                "{} takes {} damage from {} ({}/{} HP).",
+                actor.name,
+                ticking,
+                causes.join(" and "),
+                actor.hp,
+                actor.max_hp
+            ),
+        );
+        tick.damage = Some(ticking);
+        events.push(tick);
+        if actor.hp == 0 {
+            events.push(event(actor, None, format!("{} falls.", actor.name)));
+            return Ok(());
+        }
+    }
+
+    let stopped_by = combatants[turn]
+        .effects
+        .iter()
+        .find(|e| e.skip_turn)
+        .map(|e| e.name.clone());
+    match stopped_by {
+        Some(effect) => {
+            let actor = &combatants[turn];
+            events.push(event(
+                actor,
+                None,
+                format!("{} is {} and cannot act.", actor.name, effect),
+            ));
+        }
+        None => {
+            let auto;
+            let action = match action {
+                Some(action) => action,
+                None => {
+                    let Some(target) = weakest_foe(combatants, turn) else {
+                        return Ok(());
+                    };
+                    auto = CombatAction::Attack {
+                        actor: combatants[turn].id.clone(),
+                        target: combatants[target].id.clone(),
+                        damage: None,
+                        effect: None,
+                    };
+                    &auto
+                }
+            };
+            act(combatants, turn, action, check, rng, events)?;
+        }
+    }
+
+    let actor = &mut combatants[turn];
+    for effect in &mut actor.effects {
+        effect.rounds -= 1;
+    }
+    let (expired, lasting): (Vec<_>, Vec<_>) = actor.effects.drain(..).partition(|e| e.rounds <= 0);
+    actor.effects = lasting;
+    for effect in expired {
+        events.push(event(
+            actor,
+            None,
+            format!("{} wears off {}.", effect.name, actor.name),
+        ));
+    }
+    Ok(())
+}
+
+/// The active opponent with the least HP, earliest in turn order on ties
+fn weakest_foe(combatants: &[Combatant], turn: usize) -> Option<usize> {
+    let side = combatants[turn].side;
+    combatants
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.side != side && c.is_active())
+        .min_by_key(|(_, c)| c.hp)
+        .map(|(index, _)| index)
+}
+
+fn act(
+    combatants: &mut [Combatant],
+    turn: usize,
+    action: &CombatAction,
+    check: &Dice,
+    rng: &mut StdRng,
+    events: &mut Vec<CombatEvent>,
+) -> Result<(), String> {
+    match action {
+        CombatAction::Defend { .. } => {
+            let actor = &mut combatants[turn];
+            actor.defending = true;
+            events.push(event(
+                actor,
+                None,
+                format!(
+                    "{} takes a defensive stance (+{} defense).",
+                    actor.name, DEFEND_BONUS
+                ),
+            ));
+        }
+        CombatAction::Flee { .. } => {
+            let side = combatants[turn].side;
+            let against = 10
+                + combatants
+                    .iter()
+                    .filter(|c| c.side != side && c.is_active())
+                    .map(|c| c.initiative_bonus)
+                    .max()
+                    .unwrap_or(0);
+            let actor = &mut combatants[turn];
+            let roll = check.roll(rng);
+            let total = roll + actor.initiative_bonus;
+            actor.fled = total >= against;
+            let result = if actor.fled {
+                "and escapes"
+            } else {
+                "but is cut off"
+            };
+            let mut flee = event(
+                actor,
+                None,
+                format!(
+                    "{} tries to flee: rolls {}{} = {} against {}, {}.",
+                    actor.name,
+                    roll,
+                    signed(actor.initiative_bonus),
+                    total,
+                    against,
+                    result
+                ),
+            );
+            flee.roll = Some(roll);
+            flee.total = Some(total);
+            flee.against = Some(against);
+            events.push(flee);
+        }
+        CombatAction::Attack {
+            target,
+            damage,
+            effect,
+            ..
+        } => {
+            let target = find(combatants, target)
+                .ok_or_else(|| format!("No combatant called '{}'", target))?;
+            if !combatants[target].is_active() {
+                // Someone earlier in the round already dealt with them
+                let Some(other) = weakest_foe(combatants, turn) else {
+                    return Ok(());
+                };
+                let retarget = CombatAction::Attack {
+                    actor: combatants[turn].id.clone(),
+                    target: combatants[other].id.clone(),
+                    damage: damage.clone(),
+                    effect: effect.clone(),
+                };
+                return act(combatants, turn, &retarget, check, rng, events);
+            }
+            let dice = Dice::parse(damage.as_deref().unwrap_or(&combatants[turn].damage))?;
+            let roll = check.roll_dice(rng);
+            let bonus = combatants[turn].attack_bonus();
+            let total = roll + check.modifier + bonus;
+            let against = combatants[target].defense_total();
+            let critical = roll == (check.count * check.sides) as i64;
+            let fumble = roll == check.count as i64;
+            let hit = critical || (!fumble && total >= against);
+
+            let (actor, defender) = pick_two(combatants, turn, target);
+            let mut attack = event(actor, Some(defender), String::new());
+            attack.roll = Some(roll);
+            attack.total = Some(total);
+            attack.against = Some(against);
+            let rolled = format!(
+                "{} attacks {}: rolls {}{} = {} against {}",
+                actor.name,
+                defender.name,
+                roll,
+                signed(bonus + check.modifier),
+                total,
+                against
+            );
+            if !hit {
+                attack.text = if fumble {
+                    format!("{}, a fumble, and misses.", rolled)
+                } else {
+                    format!("{} and misses.", rolled)
+                };
+                events.push(attack);
+                return Ok(());
+            }
+
+            let mut dealt = dice.roll(rng);
+            if critical {
+                dealt += dice.roll_dice(rng);
+            }
+            let dealt = dealt.max(1);
+            defender.hp = (defender.hp - dealt).max(0);
+            attack.damage = Some(dealt);
+            attack.text = format!(
+                "{}, {} for {} damage ({} at {}/{} HP).",
+                rolled,
+                if critical {
+                    "a critical hit"
+                } else {
+                    "and hits"
+                },
+                dealt,
+                defender.name,
+                defender.hp,
+                defender.max_hp
+            );
+            events.push(attack);
+            if defender.hp == 0 {
+                events.push(event(defender, None, format!("{} falls.", defender.name)));
+                return Ok(());
+            }
+            if let Some(effect) = effect {
+                defender.effects.retain(|e| e.name != effect.name);
+                defender.effects.push(effect.clone());
+                events.push(event(
+                    actor,
+                    Some(defender),
+                    format!(
+                        "{} is {} for {} rounds.",
+                        defender.name, effect.name, effect.rounds
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Mutable access to two different combatants at once
+fn pick_two(
+    combatants: &mut [Combatant],
+    first: usize,
+    second: usize,
+) -> (&mut Combatant, &mut Combatant) {
+    if first < second {
+        let (left, right) = combatants.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = combatants.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
+}
+
+/// Status once a turn has been taken
+fn outcome(combatants: &[Combatant]) -> &'static str {
+    let standing = |side: Side| combatants.iter().any(|c| c.side == side && c.is_active());
+    match (standing(Side::Party), standing(Side::Enemy)) {
+        (true, true) => "active",
+        (true, false) => "victory",
+        (false, _) if combatants.iter().any(|c| c.side == Side::Party && c.fled) => "fled",
+        (false, _) => "defeat",
+    }
+}
+
+/// The latest rounds no response has narrated yet, for prompt injection
+pub fn format_unnarrated(encounter: &Encounter) -> String {
+    let rounds: Vec<&CombatRound> = encounter
+        .rounds
+        .iter()
+        .filter(|r| r.round > encounter.narrated_round)
+        .collect();
+    if rounds.is_empty() {
+        return String::new();
+    }
+    let mut lines = vec![
+        "[Combat, resolved by the game rules. Narrate exactly these outcomes; do not change who hits, who misses or who falls.]"
+            .to_string(),
+    ];
+    for round in rounds {
+        lines.push(format!("Round {}:", round.round));
+        lines.extend(round.events.iter().map(|e| format!("- {}", e.text)));
+    }
+    let status = match encounter.status.as_str() {
+        "victory" => "The party has won the fight.".to_string(),
+        "defeat" => "The party has been defeated.".to_string(),
+        "fled" => "The party has escaped the fight.".to_string(),
+        "ended" => "The fight has been called off.".to_string(),
+        _ => {
+            let standing: Vec<String> = encounter
+                .combatants
+                .iter()
+                .filter(|c| c.is_active())
+                .map(|c| format!("{} {}/{} HP", c.name, c.hp, c.max_hp))
+                .collect();
+            format!("Still fighting: {}", standing.join(", "))
+        }
+    };
+    lines.push(status);
+    lines.join("\n")
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod dice;
+pub mod engine;
+pub mod store;
+pub mod types;
@@ -0,0 +1,264 @@
+use rand::Rng;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use super::dice::Dice;
+use super::engine::{format_unnarrated, roll_initiative};
+use super::types::{CombatSettings, Combatant, CombatantSetup, Encounter};
+use crate::db::now_millis;
+use crate::llm::config::get_setting;
+use crate::stats::engine::{apply_deltas, load_sheets};
+use crate::stats::types::{CharacterSheet, StatDelta};
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<CombatSettings, String> {
+    Ok(get_setting(pool, "combat_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+fn encounter_from_row(row: &SqliteRow) -> Encounter {
+    Encounter {
+        id: row.get("id"),
+        story_id: row.get("story_id"),
+        status: row.get("status"),
+        seed: row.get("seed"),
+        round: row.get("round"),
+        narrated_round: row.get("narrated_round"),
+        combatants: serde_json::from_str(row.get::<&str, _>("combatants")).unwrap_or_default(),
+        rounds: serde_json::from_str(row.get::<&str, _>("rounds")).unwrap_or_default(),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+pub async fn load_encounter(pool: &SqlitePool, encounter_id: &str) -> Result<Encounter, String> {
+    sqlx::query("SELECT * FROM combat_encounters WHERE id = ?")
+        .bind(encounter_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load combat: {}", e))?
+        .map(|r| encounter_from_row(&r))
+        .ok_or_else(|| format!("Combat not found: {}", encounter_id))
+}
+
+/// The story's latest encounter that is still running or hasn't been
+/// narrated to the end
+pub async fn current_encounter(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Option<Encounter>, String> {
+    Ok(sqlx::query(
+        "SELECT * FROM combat_encounters WHERE story_id = ? \
+         AND (status = 'active' OR narrated_round < round) \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(story_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load combat: {}", e))?
+    .map(|r| encounter_from_row(&r)))
+}
+
+pub async fn save_encounter(pool: &SqlitePool, encounter: &Encounter) -> Result<(), String> {
+    let combatants = serde_json::to_string(&encounter.combatants)
+        .map_err(|e| format!("Failed to serialize combatants: {}", e))?;
+    let rounds = serde_json::to_string(&encounter.rounds)
+        .map_err(|e| format!("Failed to serialize combat rounds: {}", e))?;
+    sqlx::query(
+        "INSERT INTO combat_encounters \
+         (id, story_id, status, seed, round, narrated_round, combatants, rounds, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET status = excluded.status, round = excluded.round, \
+         narrated_round = excluded.narrated_round, combatants = excluded.combatants, \
+         rounds = excluded.rounds, updated_at = excluded.updated_at",
+    )
+    .bind(&encounter.id)
+    .bind(&encounter.story_id)
+    .bind(&encounter.status)
+    .bind(encounter.seed)
+    .bind(encounter.round)
+    .bind(encounter.narrated_round)
+    .bind(combatants)
+    .bind(rounds)
+    .bind(encounter.created_at)
+    .bind(encounter.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save combat: {}", e))?;
+    Ok(())
+}
+
+fn stat(sheet: Option<&CharacterSheet>, name: &str) -> Option<(i64, Option<i64>)> {
+    sheet?
+        .attributes
+        .iter()
+        .find(|a| a.name.eq_ignore_ascii_case(name))
+        .map(|a| {
+            (
+                a.value.round() as i64,
+                a.max_value.map(|m| m.round() as i64),
+            )
+        })
+}
+
+/// Start an encounter: fill each combatant in from its setup, then the
+/// character sheet, then the settings, and roll initiative. `seed` makes a
+/// fight reproducible; a random one is drawn otherwise.
+pub async fn start_encounter(
+    pool: &SqlitePool,
+    story_id: &str,
+    setups: Vec<CombatantSetup>,
+    seed: Option<i64>,
+) -> Result<Encounter, String> {
+    if setups.is_empty() {
+        return Err("A combat needs combatants".to_string());
+    }
+    let settings = load_settings(pool).await?;
+    let check = Dice::parse(&settings.check_die)?;
+    Dice::parse(&settings.default_damage)?;
+    let sheets = load_sheets(pool, story_id).await?;
+
+    let mut combatants = Vec::new();
+    for (index, setup) in setups.into_iter().enumerate() {
+        let sheet = setup
+            .character_id
+            .as_ref()
+            .and_then(|id| sheets.iter().find(|s| &s.character_id == id));
+        let name = match (&setup.name, &setup.character_id) {
+            (Some(name), _) if !name.trim().is_empty() => name.trim().to_string(),
+            (_, Some(id)) => match sheet {
+                Some(sheet) => sheet.name.clone(),
+                None => sqlx::query_scalar("SELECT name FROM characters WHERE id = ?")
+                    .bind(id)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(|e| format!("Failed to load character: {}", e))?
+                    .ok_or_else(|| format!("Character not found: {}", id))?,
+            },
+            _ => return Err("Every combatant needs a name or a character".to_string()),
+        };
+        if let Some(damage) = &setup.damage {
+            Dice::parse(damage)?;
+        }
+        let sheet_hp = stat(sheet, &settings.hp_stat);
+        let hp = setup
+            .hp
+            .or(sheet_hp.map(|(value, _)| value))
+            .unwrap_or(settings.default_hp)
+            .max(0);
+        let max_hp = sheet_hp
+            .and_then(|(_, max)| max)
+            .unwrap_or(hp)
+            .max(hp)
+            .max(1);
+        combatants.push(Combatant {
+            id: format!("c{}", index + 1),
+            character_id: setup.character_id,
+            name,
+            side: setup.side,
+            hp,
+            max_hp,
+            attack: setup
+                .attack
+                .or(stat(sheet, &settings.attack_stat).map(|(v, _)| v))
+                .unwrap_or(0),
+            defense: setup
+                .defense
+                .or(stat(sheet, &settings.defense_stat).map(|(v, _)| v))
+                .unwrap_or(settings.base_defense),
+            initiative_bonus: setup
+                .initiative
+                .or(stat(sheet, &settings.initiative_stat).map(|(v, _)| v))
+                .unwrap_or(0),
+            initiative: 0,
+            damage: setup
+                .damage
+                .unwrap_or_else(|| settings.default_damage.clone()),
+            effects: Vec::new(),
+            defending: false,
+            fled: false,
+        });
+    }
+
+    // A new fight replaces one still running
+    sqlx::query(
+        "UPDATE combat_encounters SET status = 'ended', narrated_round = round, updated_at = ? \
+         WHERE story_id = ? AND status = 'active'",
+    )
+    .bind(now_millis())
+    .bind(story_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to end combat: {}", e))?;
+
+    let now = now_millis();
+    let mut encounter = Encounter {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.to_string(),
+        status: "active".to_string(),
+        seed: seed.unwrap_or_else(|| rand::thread_rng().gen_range(0..i64::MAX)),
+        round: 0,
+        narrated_round: 0,
+        combatants,
+        rounds: Vec::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    roll_initiative(&mut encounter, &check);
+    save_encounter(pool, &encounter).await?;
+    Ok(encounter)
+}
+
+/// Write story characters' HP back to their sheets, with the down condition
+/// while they're at 0
+pub async fn sync_sheets(
+    pool: &SqlitePool,
+    encounter: &Encounter,
+    settings: &CombatSettings,
+) -> Result<(), String> {
+    let mut deltas = Vec::new();
+    for combatant in &encounter.combatants {
+        let Some(character_id) = &combatant.character_id else {
+            continue;
+        };
+        deltas.push(StatDelta::Attribute {
+            character: character_id.clone(),
+            stat: settings.hp_stat.clone(),
+            amount: combatant.hp as f64,
+            absolute: true,
+        });
+        deltas.push(StatDelta::Condition {
+            character: character_id.clone(),
+            name: settings.down_condition.clone(),
+            active: combatant.hp == 0,
+        });
+    }
+    if deltas.is_empty() {
+        return Ok(());
+    }
+    apply_deltas(pool, &encounter.story_id, &deltas, "combat").await?;
+    Ok(())
+}
+
+/// Mark every resolved round as narrated, once a response has told it
+pub async fn mark_narrated(pool: &SqlitePool, story_id: &str) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE combat_encounters SET narrated_round = round \
+         WHERE story_id = ? AND narrated_round < round",
+    )
+    .bind(story_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update combat: {}", e))?;
+    Ok(())
+}
+
+/// Rounds waiting to be narrated, for prompt injection
+pub async fn combat_block(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    Ok(current_encounter(pool, story_id)
+        .await?
+        .map(|e| format_unnarrated(&e))
+        .filter(|text| !text.is_empty()))
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+/// Which stats combat reads from character sheets, and the defaults for
+/// anything a sheet or setup leaves out (`combat_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CombatSettings {
+    pub hp_stat: String,
+    pub attack_stat: String,
+    pub defense_stat: String,
+    pub initiative_stat: String,
+    pub default_hp: i64,
+    /// Defense a combatant has without a defense stat
+    pub base_defense: i64,
+    pub default_damage: String,
+    /// Die rolled for attacks, initiative and fleeing
+    pub check_die: String,
+    /// Condition given to sheet characters who drop to 0 HP
+    pub down_condition: String,
+}
+
+impl Default for CombatSettings {
+    fn default() -> Self {
+        Self {
+            hp_stat: "HP".to_string(),
+            attack_stat: "Attack".to_string(),
+            defense_stat: "Defense".to_string(),
+            initiative_stat: "Agility".to_string(),
+            default_hp: 10,
+            base_defense: 10,
+            default_damage: "1d6".to_string(),
+            check_die: "1d20".to_string(),
+            down_condition: "Unconscious".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Party,
+    Enemy,
+}
+
+/// Who joins an encounter: a story character, whose sheet fills in anything
+/// left out, or a named foe described entirely here
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatantSetup {
+    pub character_id: Option<String>,
+    pub name: Option<String>,
+    pub side: Side,
+    pub hp: Option<i64>,
+    pub attack: Option<i64>,
+    pub defense: Option<i64>,
+    pub initiative: Option<i64>,
+    /// Damage dice, e.g. "1d8+2"
+    pub damage: Option<String>,
+}
+
+/// A lasting effect on a combatant, counted down at the end of their turns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEffect {
+    pub name: String,
+    pub rounds: i64,
+    #[serde(default)]
+    pub attack: i64,
+    #[serde(default)]
+    pub defense: i64,
+    /// Damage taken at the start of each of the combatant's turns
+    #[serde(default)]
+    pub damage_per_round: i64,
+    /// The combatant loses their turns while it lasts
+    #[serde(default)]
+    pub skip_turn: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Combatant {
+    /// Id within the encounter
+    pub id: String,
+    pub character_id: Option<String>,
+    pub name: String,
+    pub side: Side,
+    pub hp: i64,
+    pub max_hp: i64,
+    /// Added to attack rolls
+    pub attack: i64,
+    /// What an attack roll must reach to hit
+    pub defense: i64,
+    pub initiative_bonus: i64,
+    /// Rolled initiative; combatants act highest first
+    pub initiative: i64,
+    pub damage: String,
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub defending: bool,
+    #[serde(default)]
+    pub fled: bool,
+}
+
+impl Combatant {
+    /// Still fighting: conscious and on the field
+    pub fn is_active(&self) -> bool {
+        self.hp > 0 && !self.fled
+    }
+
+    pub fn attack_bonus(&self) -> i64 {
+        self.attack + self.effects.iter().map(|e| e.attack).sum::<i64>()
+    }
+
+    pub fn defense_total(&self) -> i64 {
+        let defending = if self.defending { DEFEND_BONUS } else { 0 };
+        self.defense + defending + self.effects.iter().map(|e| e.defense).sum::<i64>()
+    }
+}
+
+/// Defense added while a combatant defends
+pub const DEFEND_BONUS: i64 = 2;
+
+/// What a combatant does on their turn. Combatants given no action attack
+/// the weakest foe still standing.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum CombatAction {
+    /// Roll to hit `target`; `damage` overrides the combatant's damage dice
+    /// and `effect` lands on a hit
+    #[serde(rename_all = "camelCase")]
+    Attack {
+        actor: String,
+        target: String,
+        damage: Option<String>,
+        effect: Option<StatusEffect>,
+    },
+    /// Raise defense until the combatant's next turn
+    Defend { actor: String },
+    /// Try to leave the fight
+    Flee { actor: String },
+}
+
+impl CombatAction {
+    pub fn actor(&self) -> &str {
+        match self {
+            CombatAction::Attack { actor, .. }
+            | CombatAction::Defend { actor }
+            | CombatAction::Flee { actor } => actor,
+        }
+    }
+}
+
+/// One thing that happened in a round, with the numbers behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatEvent {
+    pub actor: String,
+    pub target: Option<String>,
+    pub roll: Option<i64>,
+    pub total: Option<i64>,
+    pub against: Option<i64>,
+    pub damage: Option<i64>,
+    /// The event in words, as injected into the prompt
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatRound {
+    pub round: i64,
+    pub events: Vec<CombatEvent>,
+}
+
+/// A fight and everything resolved in it so far
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Encounter {
+    pub id: String,
+    pub story_id: String,
+    /// 'active' | 'victory' | 'defeat' | 'fled' | 'ended'
+    pub status: String,
+    pub seed: i64,
+    pub round: i64,
+    pub narrated_round: i64,
+    /// In initiative order
+    pub combatants: Vec<Combatant>,
+    pub rounds: Vec<CombatRound>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
@@ -0,0 +1,166 @@
+use base64::Engine;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use super::types::{PackManifest, PACK_FORMAT, PACK_FORMAT_VERSION};
+
+pub const MANIFEST_PATH: &str = "manifest.json";
+
+/// Largest single file accepted from a pack
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Prefix of a vault value replaced by an image file in the archive
+const IMAGE_REF: &str = "pack-image:";
+
+/// Files of an opened pack, by archive path
+pub type PackFiles = HashMap<String, Vec<u8>>;
+
+pub fn write_pack(
+    path: &str,
+    manifest: &PackManifest,
+    files: &[(String, Vec<u8>)],
+) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create pack: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let manifest = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    for (name, bytes) in std::iter::once((MANIFEST_PATH, &manifest))
+        .chain(files.iter().map(|(name, bytes)| (name.as_str(), bytes)))
+    {
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("Failed to write {} to pack: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish pack: {}", e))?;
+    Ok(())
+}
+
+/// Open a pack and read its manifest and files, rejecting unsafe paths,
+/// oversized entries and manifests of an unknown format
+pub fn read_pack(path: &str) -> Result<(PackManifest, PackFiles), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open pack: {}", e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid pack: {}", e))?;
+    let mut files = PackFiles::new();
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| format!("Failed to read pack: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry
+            .enclosed_name()
+            .and_then(|p| p.to_str().map(str::to_string))
+        else {
+            return Err(format!("Pack contains an unsafe path: {}", entry.name()));
+        };
+        if entry.size() > MAX_ENTRY_BYTES {
+            return Err(format!("Pack file {} is too large", name));
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry
+            .by_ref()
+            .take(MAX_ENTRY_BYTES)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read {} from pack: {}", name, e))?;
+        files.insert(name.replace('\\', "/"), bytes);
+    }
+    let manifest: PackManifest = serde_json::from_slice(
+        files
+            .get(MANIFEST_PATH)
+            .ok_or("Pack has no manifest.json")?,
+    )
+    .map_err(|e| format!("Invalid pack manifest: {}", e))?;
+    if manifest.format != PACK_FORMAT {
+        return Err(format!(
+            "Not an Aventura content pack ({})",
+            manifest.format
+        ));
+    }
+    if manifest.format_version > PACK_FORMAT_VERSION {
+        return Err(format!(
+            "Pack format version {} is newer than this app supports ({}); please update Aventura",
+            manifest.format_version, PACK_FORMAT_VERSION
+        ));
+    }
+    for item in &manifest.items {
+        if !files.contains_key(&item.path) {
+            return Err(format!("Pack is missing {}", item.path));
+        }
+    }
+    Ok((manifest, files))
+}
+
+/// Move embedded data-URL images (portraits) out of an item into archive
+/// files, leaving `pack-image:<path>` references behind
+pub fn extract_images(
+    item: &mut Map<String, Value>,
+    prefix: &str,
+    images: &mut Vec<(String, Vec<u8>)>,
+) {
+    for (column, value) in item.iter_mut() {
+        let Some(url) = value.as_str() else {
+            continue;
+        };
+        let Some((mime, data)) = url
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+        else {
+            continue;
+        };
+        let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) else {
+            continue;
+        };
+        let extension = mime.strip_prefix("image/").unwrap_or("bin");
+        let path = format!("images/{}-{}.{}", prefix, column, extension);
+        *value = Value::String(format!("{}{}|{}", IMAGE_REF, mime, path));
+        images.push((path, bytes));
+    }
+}
+
+/// Turn `pack-image:` references back into data URLs, returning how many
+pub fn restore_images(item: &mut Map<String, Value>, files: &PackFiles) -> Result<usize, String> {
+    let mut restored = 0;
+    for value in item.values_mut() {
+        let Some(reference) = value.as_str().and_then(|v| v.strip_prefix(IMAGE_REF)) else {
+            continue;
+        };
+        let (mime, path) = reference
+            .split_once('|')
+            .ok_or_else(|| format!("Invalid image reference: {}", reference))?;
+        let bytes = files
+            .get(path)
+            .ok_or_else(|| format!("Pack is missing {}", path))?;
+        *value = Value::String(format!(
+            "data:{};base64,{}",
+            mime,
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ));
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// Compare dotted version numbers ('1.10.0' > '1.9'); missing parts are 0
+/// and non-numeric suffixes are ignored
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.trim()
+            .trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map_while(|p| p.parse().ok())
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let order = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if order.is_ne() {
+            return order;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
@@ -0,0 +1,368 @@
+use serde_json::{json, Map, Value};
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use super::archive::{
+    compare_versions, extract_images, read_pack, restore_images, write_pack, PackFiles,
+};
+use super::types::{
+    InstalledItem, InstalledPack, MissingDependency, PackBuildRequest, PackItem, PackItemKind,
+    PackManifest, PackPreview, PACK_FORMAT, PACK_FORMAT_VERSION,
+};
+use super::vault;
+use crate::db::{now_millis, DbState};
+use crate::file_import::progress::ImportTask;
+use crate::presets::commands::{build_preset, install_preset, parse_file};
+
+pub fn installed_from_row(r: &sqlx::sqlite::SqliteRow) -> InstalledPack {
+    InstalledPack {
+        id: r.get("id"),
+        name: r.get("name"),
+        version: r.get("version"),
+        author: r.get("author"),
+        items: serde_json::from_str(&r.get::<String, _>("items")).unwrap_or_default(),
+        installed_at: r.get("installed_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+async fn load_installed(pool: &SqlitePool, id: &str) -> Result<Option<InstalledPack>, String> {
+    let row = sqlx::query("SELECT * FROM installed_content_packs WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load installed packs: {}", e))?;
+    Ok(row.as_ref().map(installed_from_row))
+}
+
+/// Check a pack against the app version, the installed packs and the vault
+async fn inspect(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    manifest: PackManifest,
+    files: &PackFiles,
+) -> Result<PackPreview, String> {
+    let app_version = app.package_info().version.to_string();
+    let app_compatible = manifest
+        .min_app_version
+        .as_deref()
+        .is_none_or(|min| compare_versions(&app_version, min).is_ge());
+
+    let mut missing_dependencies = Vec::new();
+    for dependency in &manifest.dependencies {
+        let installed = load_installed(pool, &dependency.id).await?;
+        let satisfied = installed
+            .as_ref()
+            .is_some_and(|p| compare_versions(&p.version, &dependency.version).is_ge());
+        if !satisfied {
+            missing_dependencies.push(MissingDependency {
+                dependency: dependency.clone(),
+                installed_version: installed.map(|p| p.version),
+            });
+        }
+    }
+
+    let mut name_conflicts = Vec::new();
+    for item in &manifest.items {
+        if let Some(table) = item.kind.table() {
+            if vault::name_taken(pool, table, &item.name).await? {
+                name_conflicts.push(item.name.clone());
+            }
+        }
+    }
+
+    let installed_version = load_installed(pool, &manifest.id).await?.map(|p| p.version);
+    let mut blockers = Vec::new();
+    if !app_compatible {
+        blockers.push(format!(
+            "Requires Aventura {} or newer (this is {})",
+            manifest.min_app_version.as_deref().unwrap_or_default(),
+            app_version
+        ));
+    }
+    if let Some(installed) = &installed_version {
+        if compare_versions(installed, &manifest.version).is_ge() {
+            blockers.push(format!("Version {} is already installed", installed));
+        }
+    }
+    for item in manifest
+        .items
+        .iter()
+        .filter(|i| i.kind == PackItemKind::Preset)
+    {
+        let raw = String::from_utf8_lossy(&files[&item.path]).to_string();
+        if let Err(e) = parse_file(&raw) {
+            blockers.push(format!("{}: {}", item.name, e));
+        }
+    }
+
+    Ok(PackPreview {
+        manifest,
+        installed_version,
+        app_compatible,
+        missing_dependencies,
+        name_conflicts,
+        blockers,
+    })
+}
+
+/// Bundle vault scenarios, characters and lorebooks (with their embedded
+/// images) and presets into an `.aventurapack` archive at `path`. Returns
+/// the written manifest.
+#[tauri::command]
+pub async fn build_content_pack(
+    db: State<'_, DbState>,
+    request: PackBuildRequest,
+    path: String,
+) -> Result<PackManifest, String> {
+    if request.name.trim().is_empty() {
+        return Err("Pack name cannot be empty".to_string());
+    }
+    if compare_versions(&request.version, "0").is_eq() {
+        return Err(format!("Invalid pack version: {}", request.version));
+    }
+    let pool = db.pool();
+    let mut items = Vec::new();
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+
+    let groups = [
+        (PackItemKind::Scenario, &request.scenario_ids, "scenarios"),
+        (
+            PackItemKind::Character,
+            &request.character_ids,
+            "characters",
+        ),
+        (PackItemKind::Lorebook, &request.lorebook_ids, "lorebooks"),
+    ];
+    for (kind, ids, folder) in groups {
+        let table = kind.table().expect("vault kinds have a table");
+        for (n, id) in ids.iter().enumerate() {
+            let mut item = vault::load_item(pool, table, id).await?;
+            extract_images(&mut item, &format!("{}-{}", kind.as_str(), n), &mut files);
+            let path = format!("{}/{}.json", folder, n);
+            items.push(PackItem {
+                kind,
+                name: item
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                path: path.clone(),
+            });
+            files.push((path, Value::Object(item).to_string().into_bytes()));
+        }
+    }
+    for (n, preset) in request.presets.iter().enumerate() {
+        let file = build_preset(pool, preset.kind, preset.id.as_deref()).await?;
+        let path = format!("presets/{}.json", n);
+        items.push(PackItem {
+            kind: PackItemKind::Preset,
+            name: file.name.clone(),
+            path: path.clone(),
+        });
+        let bytes = serde_json::to_vec_pretty(&file)
+            .map_err(|e| format!("Failed to serialize preset: {}", e))?;
+        files.push((path, bytes));
+    }
+    if items.is_empty() {
+        return Err("A pack needs at least one item".to_string());
+    }
+
+    let manifest = PackManifest {
+        format: PACK_FORMAT.to_string(),
+        format_version: PACK_FORMAT_VERSION,
+        id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: request.name.trim().to_string(),
+        version: request.version.trim().to_string(),
+        author: request.author,
+        description: request.description,
+        min_app_version: request.min_app_version,
+        dependencies: request.dependencies,
+        items,
+        created_at: now_millis(),
+    };
+    let written = manifest.clone();
+    tokio::task::spawn_blocking(move || write_pack(&path, &written, &files))
+        .await
+        .map_err(|e| format!("Failed to write pack: {}", e))??;
+    Ok(manifest)
+}
+
+/// Read the pack at `path` and check it; see `preview_content_pack`
+pub async fn preview_pack(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    path: String,
+) -> Result<PackPreview, String> {
+    let (manifest, files) = tokio::task::spawn_blocking(move || read_pack(&path))
+        .await
+        .map_err(|e| format!("Failed to read pack: {}", e))??;
+    inspect(app, pool, manifest, &files).await
+}
+
+/// Read a pack's manifest and check it before installing: app version,
+/// dependencies, an installed copy, and vault name clashes
+#[tauri::command]
+pub async fn preview_content_pack(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    path: String,
+) -> Result<PackPreview, String> {
+    preview_pack(&app, db.pool(), path).await
+}
+
+/// Install a pack into the vault. Installing a newer version of an
+/// installed pack replaces the vault items the old version created.
+/// Missing dependencies block the install unless
+/// `allow_missing_dependencies` is set. Reports `import-progress` under
+/// `import_id` and stops on `cancel_import`, installing nothing.
+#[tauri::command]
+pub async fn install_content_pack(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    path: String,
+    allow_missing_dependencies: Option<bool>,
+    import_id: Option<String>,
+) -> Result<InstalledPack, String> {
+    let mut task = ImportTask::start(&app, import_id, "contentPack");
+    let result = install_pack(
+        &app,
+        db.pool(),
+        path,
+        allow_missing_dependencies.unwrap_or(false),
+        &mut task,
+    )
+    .await;
+    task.finish(result)
+}
+
+async fn install_pack(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    path: String,
+    allow_missing_dependencies: bool,
+    task: &mut ImportTask,
+) -> Result<InstalledPack, String> {
+    let (manifest, files) = tokio::task::spawn_blocking(move || read_pack(&path))
+        .await
+        .map_err(|e| format!("Failed to read pack: {}", e))??;
+    task.check()?;
+    let preview = inspect(app, pool, manifest, &files).await?;
+    if !preview.blockers.is_empty() {
+        return Err(preview.blockers.join("; "));
+    }
+    if !preview.missing_dependencies.is_empty() && !allow_missing_dependencies {
+        let names: Vec<String> = preview
+            .missing_dependencies
+            .iter()
+            .map(|m| format!("{} {}", m.dependency.name, m.dependency.version))
+            .collect();
+        return Err(format!("Missing dependencies: {}", names.join(", ")));
+    }
+    let manifest = preview.manifest;
+    let previous = load_installed(pool, &manifest.id).await?;
+    let provenance =
+        json!({ "id": manifest.id, "name": manifest.name, "version": manifest.version });
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    if let Some(previous) = &previous {
+        for item in &previous.items {
+            if let Some(table) = item.kind.table() {
+                vault::delete_item(&mut tx, table, &item.id).await?;
+            }
+        }
+    }
+    task.stage("writing", Some(manifest.items.len()))?;
+    let mut installed = Vec::new();
+    for item in &manifest.items {
+        let bytes = &files[&item.path];
+        match item.kind.table() {
+            Some(table) => {
+                let mut data: Map<String, Value> = serde_json::from_slice(bytes)
+                    .map_err(|e| format!("Invalid pack item {}: {}", item.path, e))?;
+                task.images(restore_images(&mut data, &files)?);
+                let id = vault::insert_item(&mut tx, table, data, provenance.clone()).await?;
+                installed.push(InstalledItem {
+                    kind: item.kind,
+                    id,
+                    name: item.name.clone(),
+                });
+            }
+            None => {
+                let preset = parse_file(&String::from_utf8_lossy(bytes))?;
+                let result = install_preset(&mut tx, preset, None).await?;
+                installed.push(InstalledItem {
+                    kind: item.kind,
+                    id: result.id.unwrap_or_default(),
+                    name: result.name,
+                });
+            }
+        }
+        task.rows(1)?;
+    }
+
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO installed_content_packs (id, name, version, author, manifest, items, installed_at, updated_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7) \
+         ON CONFLICT(id) DO UPDATE SET name = ?2, version = ?3, author = ?4, manifest = ?5, items = ?6, \
+         updated_at = ?7",
+    )
+    .bind(&manifest.id)
+    .bind(&manifest.name)
+    .bind(&manifest.version)
+    .bind(&manifest.author)
+    .bind(serde_json::to_string(&manifest).unwrap_or_default())
+    .bind(serde_json::to_string(&installed).unwrap_or_default())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record installed pack: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit pack install: {}", e))?;
+
+    load_installed(pool, &manifest.id)
+        .await?
+        .ok_or_else(|| "Installed pack disappeared".to_string())
+}
+
+#[tauri::command]
+pub async fn list_content_packs(db: State<'_, DbState>) -> Result<Vec<InstalledPack>, String> {
+    let rows = sqlx::query("SELECT * FROM installed_content_packs ORDER BY name COLLATE NOCASE")
+        .fetch_all(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load installed packs: {}", e))?;
+    Ok(rows.iter().map(installed_from_row).collect())
+}
+
+/// Remove an installed pack and the vault items it created. Presets it
+/// brought in stay, since they may have been edited or bound to stories.
+#[tauri::command]
+pub async fn uninstall_content_pack(db: State<'_, DbState>, pack_id: String) -> Result<(), String> {
+    let pool = db.pool();
+    let Some(pack) = load_installed(pool, &pack_id).await? else {
+        return Ok(());
+    };
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for item in &pack.items {
+        if let Some(table) = item.kind.table() {
+            vault::delete_item(&mut tx, table, &item.id).await?;
+        }
+    }
+    sqlx::query("DELETE FROM installed_content_packs WHERE id = ?")
+        .bind(&pack_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to remove installed pack: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit pack removal: {}", e))
+}
@@ -0,0 +1,4 @@
+pub mod archive;
+pub mod commands;
+pub mod types;
+pub mod vault;
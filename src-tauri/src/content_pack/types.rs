@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use crate::presets::types::PresetKind;
+
+/// Identifies a content pack manifest
+pub const PACK_FORMAT: &str = "aventurapack";
+
+/// Current archive layout version
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// Kind of vault item a pack carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PackItemKind {
+    Scenario,
+    Character,
+    Lorebook,
+    Preset,
+}
+
+impl PackItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackItemKind::Scenario => "scenario",
+            PackItemKind::Character => "character",
+            PackItemKind::Lorebook => "lorebook",
+            PackItemKind::Preset => "preset",
+        }
+    }
+
+    /// Vault table of the kind; presets go through the preset importer
+    pub fn table(&self) -> Option<&'static str> {
+        match self {
+            PackItemKind::Scenario => Some("scenario_vault"),
+            PackItemKind::Character => Some("character_vault"),
+            PackItemKind::Lorebook => Some("lorebook_vault"),
+            PackItemKind::Preset => None,
+        }
+    }
+}
+
+/// Another pack this one needs, at `version` or newer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackDependency {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub version: String,
+}
+
+/// One file in the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackItem {
+    pub kind: PackItemKind,
+    pub name: String,
+    /// Path inside the archive
+    pub path: String,
+}
+
+/// `manifest.json` at the root of an `.aventurapack`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// Stable across versions of the same pack
+    pub id: String,
+    pub name: String,
+    /// The pack's own version, dotted numbers (e.g. '1.2.0')
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Oldest app version the pack works with
+    #[serde(default)]
+    pub min_app_version: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<PackDependency>,
+    pub items: Vec<PackItem>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// A preset to bundle, as `export_preset` takes it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetRef {
+    pub kind: PresetKind,
+    pub id: Option<String>,
+}
+
+/// What to put in a new pack
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackBuildRequest {
+    /// Id of an earlier version of this pack; a new id when omitted
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub min_app_version: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<PackDependency>,
+    #[serde(default)]
+    pub scenario_ids: Vec<String>,
+    #[serde(default)]
+    pub character_ids: Vec<String>,
+    #[serde(default)]
+    pub lorebook_ids: Vec<String>,
+    #[serde(default)]
+    pub presets: Vec<PresetRef>,
+}
+
+/// A dependency the installed packs don't satisfy
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingDependency {
+    #[serde(flatten)]
+    pub dependency: PackDependency,
+    /// Version installed, when it is too old
+    pub installed_version: Option<String>,
+}
+
+/// Everything shown before installing a pack
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackPreview {
+    pub manifest: PackManifest,
+    /// Version of this pack already installed, if any
+    pub installed_version: Option<String>,
+    /// The running app is at least the pack's minimum version
+    pub app_compatible: bool,
+    pub missing_dependencies: Vec<MissingDependency>,
+    /// Items whose names already exist in the vault; they install alongside
+    pub name_conflicts: Vec<String>,
+    /// Problems that block installing
+    pub blockers: Vec<String>,
+}
+
+/// An item an install created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledItem {
+    pub kind: PackItemKind,
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledPack {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: Option<String>,
+    pub items: Vec<InstalledItem>,
+    pub installed_at: i64,
+    pub updated_at: i64,
+}
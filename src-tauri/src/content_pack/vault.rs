@@ -0,0 +1,141 @@
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row, Sqlite, SqlitePool, Transaction, TypeInfo, ValueRef};
+use uuid::Uuid;
+
+use crate::db::now_millis;
+
+/// Columns that belong to the installing device, not the shared item
+const LOCAL_COLUMNS: [&str; 4] = ["id", "favorite", "original_story_id", "created_at"];
+
+/// A vault row as a JSON object keyed by column, so packs survive columns
+/// being added to the vault tables
+fn row_to_json(row: &SqliteRow) -> Map<String, Value> {
+    let mut object = Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i).ok();
+        let value = match raw
+            .filter(|v| !v.is_null())
+            .map(|v| v.type_info().name().to_string())
+        {
+            None => Value::Null,
+            Some(kind) if kind == "INTEGER" => {
+                row.try_get::<i64, _>(i).map_or(Value::Null, Value::from)
+            }
+            Some(kind) if kind == "REAL" => {
+                row.try_get::<f64, _>(i).map_or(Value::Null, Value::from)
+            }
+            Some(_) => row.try_get::<String, _>(i).map_or(Value::Null, Value::from),
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    object
+}
+
+pub async fn load_item(
+    pool: &SqlitePool,
+    table: &str,
+    id: &str,
+) -> Result<Map<String, Value>, String> {
+    let row = sqlx::query(&format!("SELECT * FROM {} WHERE id = ?", table))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load vault item: {}", e))?
+        .ok_or_else(|| format!("Vault item not found: {}", id))?;
+    let mut item = row_to_json(&row);
+    for column in LOCAL_COLUMNS {
+        item.remove(column);
+    }
+    Ok(item)
+}
+
+async fn table_columns(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to read vault columns: {}", e))
+}
+
+/// Whether the vault table already has an item with this name
+pub async fn name_taken(pool: &SqlitePool, table: &str, name: &str) -> Result<bool, String> {
+    let count: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM {} WHERE name = ? COLLATE NOCASE",
+        table
+    ))
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to check vault names: {}", e))?;
+    Ok(count > 0)
+}
+
+/// Insert a packed item under a new id, keeping only columns this version
+/// of the table has. `provenance` is merged into the item's metadata.
+pub async fn insert_item(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    mut item: Map<String, Value>,
+    provenance: Value,
+) -> Result<String, String> {
+    let id = Uuid::new_v4().to_string();
+    let now = now_millis();
+    let mut metadata: Map<String, Value> = item
+        .get("metadata")
+        .and_then(|m| m.as_str())
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default();
+    metadata.insert("contentPack".to_string(), provenance);
+    item.insert(
+        "metadata".to_string(),
+        Value::String(Value::Object(metadata).to_string()),
+    );
+    item.insert("id".to_string(), Value::String(id.clone()));
+    item.insert("source".to_string(), Value::String("import".to_string()));
+    item.insert("created_at".to_string(), Value::from(now));
+    item.insert("updated_at".to_string(), Value::from(now));
+
+    let columns: Vec<String> = table_columns(tx, table)
+        .await?
+        .into_iter()
+        .filter(|c| item.contains_key(c))
+        .collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for column in &columns {
+        query = match &item[column] {
+            Value::Null => query.bind(None::<String>),
+            Value::Bool(b) => query.bind(*b),
+            Value::Number(n) if n.is_i64() => query.bind(n.as_i64()),
+            Value::Number(n) => query.bind(n.as_f64()),
+            Value::String(s) => query.bind(s.clone()),
+            other => query.bind(other.to_string()),
+        };
+    }
+    query
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to install vault item: {}", e))?;
+    Ok(id)
+}
+
+pub async fn delete_item(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    id: &str,
+) -> Result<(), String> {
+    sqlx::query(&format!("DELETE FROM {} WHERE id = ?", table))
+        .bind(id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to remove vault item: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+use crate::annotations::store::revision_block;
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::combat::store::combat_block;
+use crate::director::store::story_guidance;
+use crate::environment::simulate::describe;
+use crate::environment::store::{active_region, ensure_weather, story_day};
+use crate::factions::store::{format_faction_block, load_factions};
+use crate::inventory::extract::{format_inventory_summary, load_inventory};
+use crate::quests::store::{format_quest_block, load_quests};
+use crate::stats::engine::{format_stat_block, load_sheets};
+use crate::travel::store::travel_hints;
+
+/// Natively built context blocks for a story, keyed by template variable name.
+/// Blocks with nothing to say are omitted.
+pub async fn collect_blocks(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<BTreeMap<String, String>, String> {
+    let mut blocks = BTreeMap::new();
+
+    let calendar = load_calendar(pool, story_id).await?;
+    let time = load_time_tracker(pool, story_id).await?;
+    blocks.insert("storyDate".to_string(), calendar.date(&time).formatted);
+
+    let region = active_region(pool, story_id)
+        .await
+        .map_err(|e| format!("Failed to load regions: {}", e))?;
+    if let Some(region) = region {
+        let weather = ensure_weather(pool, &calendar, &region, story_day(&calendar, &time)).await?;
+        blocks.insert("environment".to_string(), describe(&region, &weather));
+    }
+
+    let sheets = load_sheets(pool, story_id).await?;
+    let stat_block = format_stat_block(&sheets);
+    if !stat_block.is_empty() {
+        blocks.insert("statBlock".to_string(), stat_block);
+    }
+
+    let include_inventory: Option<bool> = sqlx::query_scalar(
+        "SELECT json_extract(settings, '$.inventoryInContext') FROM stories WHERE id = ?",
+    )
+    .bind(story_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load story settings: {}", e))?
+    .flatten();
+    if include_inventory.unwrap_or(false) {
+        let items = load_inventory(pool, story_id, None)
+            .await
+            .map_err(|e| format!("Failed to load inventory: {}", e))?;
+        if !items.is_empty() {
+            blocks.insert(
+                "inventorySummary".to_string(),
+                format_inventory_summary(&items),
+            );
+        }
+    }
+
+    let quests = load_quests(pool, story_id, Some("active"))
+        .await
+        .map_err(|e| format!("Failed to load quests: {}", e))?;
+    let quest_block = format_quest_block(&quests);
+    if !quest_block.is_empty() {
+        blocks.insert("activeQuests".to_string(), quest_block);
+    }
+
+    let factions = load_factions(pool, story_id).await?;
+    let faction_block = format_faction_block(&factions);
+    if !faction_block.is_empty() {
+        blocks.insert("factionStanding".to_string(), faction_block);
+    }
+
+    if let Some(hints) = travel_hints(pool, story_id).await? {
+        blocks.insert("travelTimes".to_string(), hints);
+    }
+
+    if let Some(outcome) = combat_block(pool, story_id).await? {
+        blocks.insert("combatOutcome".to_string(), outcome);
+    }
+
+    if let Some(notes) = revision_block(pool, story_id).await? {
+        blocks.insert("revisionNotes".to_string(), notes);
+    }
+
+    if let Some(guidance) = story_guidance(pool, story_id).await? {
+        blocks.insert("directorNotes".to_string(), guidance);
+    }
+
+    Ok(blocks)
+}
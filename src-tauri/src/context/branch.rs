@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+
+use crate::llm::config::get_setting;
+
+/// A branch in the current branch's ancestry, with the position in its parent
+/// where it diverges
+#[derive(Debug, Clone)]
+pub struct LineageBranch {
+    pub id: String,
+    pub fork_position: Option<i64>,
+}
+
+/// A story entry visible on the current branch
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VisibleEntry {
+    pub id: String,
+    #[sqlx(rename = "type")]
+    pub kind: String,
+    pub content: String,
+    pub position: i64,
+}
+
+/// The story's current branch, if any
+pub async fn current_branch(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    Ok(
+        sqlx::query_scalar("SELECT current_branch_id FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load story: {}", e))?
+            .flatten(),
+    )
+}
+
+/// Ancestry of the story's current branch, root first; empty on the main branch
+pub async fn lineage(pool: &SqlitePool, story_id: &str) -> Result<Vec<LineageBranch>, String> {
+    let mut lineage = Vec::new();
+    let mut next = current_branch(pool, story_id).await?;
+    while let Some(id) = next {
+        if lineage.iter().any(|b: &LineageBranch| b.id == id) {
+            break;
+        }
+        let row: Option<(Option<String>, Option<i64>)> = sqlx::query_as(
+            "SELECT b.parent_branch_id, e.position FROM branches b \
+             LEFT JOIN story_entries e ON e.id = b.fork_entry_id WHERE b.id = ?",
+        )
+        .bind(&id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load branch: {}", e))?;
+        let Some((parent, fork_position)) = row else {
+            break;
+        };
+        lineage.insert(0, LineageBranch { id, fork_position });
+        next = parent;
+    }
+    Ok(lineage)
+}
+
+/// The stretches of branches readable on the current branch, in reading
+/// order: each ancestor up to the position the next branch forks at, then the
+/// whole current branch
+pub async fn segments(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<(Option<String>, Option<i64>)>, String> {
+    let mut segments = Vec::new();
+    let mut parent: Option<String> = None;
+    for branch in lineage(pool, story_id).await? {
+        segments.push((parent, branch.fork_position));
+        parent = Some(branch.id);
+    }
+    segments.push((parent, None));
+    Ok(segments)
+}
+
+/// Entries readable on the current branch, in reading order
+pub async fn visible_entries(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<VisibleEntry>, String> {
+    let mut entries = Vec::new();
+    for (branch_id, max_position) in segments(pool, story_id).await? {
+        let rows: Vec<VisibleEntry> = sqlx::query_as(
+            "SELECT id, type, content, position FROM story_entries \
+             WHERE story_id = ? AND branch_id IS ? AND (? IS NULL OR position <= ?) \
+             ORDER BY position ASC",
+        )
+        .bind(story_id)
+        .bind(branch_id)
+        .bind(max_position)
+        .bind(max_position)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story entries: {}", e))?;
+        entries.extend(rows);
+    }
+    Ok(entries)
+}
+
+/// Chapters on the current branch: untagged ones and those of its ancestry
+pub async fn chapter_count(pool: &SqlitePool, story_id: &str) -> Result<i64, String> {
+    let branches: Vec<String> = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    let chapter_branches: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT branch_id FROM chapters WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    Ok(chapter_branches
+        .iter()
+        .filter(|branch| branch.as_ref().is_none_or(|b| branches.contains(b)))
+        .count() as i64)
+}
+
+/// A character as it stands on the current branch
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BranchCharacter {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub relationship: Option<String>,
+    pub traits: Option<String>,
+    pub status: Option<String>,
+    pub visual_descriptors: Option<String>,
+    pub overrides_id: Option<String>,
+    pub deleted: i64,
+}
+
+/// The story's characters on the current branch, in creation order
+pub async fn characters(pool: &SqlitePool, story_id: &str) -> Result<Vec<BranchCharacter>, String> {
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        let rows: Vec<BranchCharacter> = sqlx::query_as(
+            "SELECT id, name, description, relationship, traits, status, visual_descriptors, \
+             overrides_id, deleted FROM characters WHERE story_id = ? AND branch_id IS ? ORDER BY rowid",
+        )
+        .bind(story_id)
+        .bind(&branch)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load characters: {}", e))?;
+        layers.push(rows);
+    }
+    Ok(view.resolve(
+        layers,
+        |c| c.overrides_id.clone().unwrap_or_else(|| c.id.clone()),
+        |c| c.deleted != 0,
+    ))
+}
+
+/// Which rows of the branch-aware world tables (entries, characters,
+/// story_beats, ...) make up the current branch's world state, mirroring how
+/// the story store loads them
+pub enum WorldView {
+    /// Exactly the rows tagged with this branch (main when `None`): full-copy
+    /// branches, or lightweight branches with a complete snapshot
+    Exact(Option<String>),
+    /// Lightweight copy-on-write branch: main rows overlaid by each branch in
+    /// lineage order, root first
+    Lineage(Vec<String>),
+}
+
+impl WorldView {
+    /// Branch ids whose rows are needed, in overlay order (`None` is main)
+    pub fn layers(&self) -> Vec<Option<String>> {
+        match self {
+            WorldView::Exact(branch) => vec![branch.clone()],
+            WorldView::Lineage(ids) => std::iter::once(None)
+                .chain(ids.iter().cloned().map(Some))
+                .collect(),
+        }
+    }
+
+    /// Merge the rows of each layer (in `layers` order) into the branch's
+    /// view. Copy-on-write rows override by canonical id, and a tombstone only
+    /// hides the row on the branch that made it.
+    pub fn resolve<T>(
+        &self,
+        layers: Vec<Vec<T>>,
+        canonical_id: impl Fn(&T) -> String,
+        deleted: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        let count = layers.len();
+        if let WorldView::Exact(_) = self {
+            return layers
+                .into_iter()
+                .flatten()
+                .filter(|r| !deleted(r))
+                .collect();
+        }
+        let mut slots: Vec<Option<T>> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (layer, rows) in layers.into_iter().enumerate() {
+            let is_current = layer > 0 && layer == count - 1;
+            for row in rows {
+                let id = canonical_id(&row);
+                let value = if deleted(&row) && is_current {
+                    None
+                } else {
+                    Some(row)
+                };
+                match index.get(&id) {
+                    Some(&slot) => slots[slot] = value,
+                    None => {
+                        index.insert(id, slots.len());
+                        slots.push(value);
+                    }
+                }
+            }
+        }
+        slots.into_iter().flatten().collect()
+    }
+}
+
+/// How the current branch's world state is stored
+pub async fn world_view(pool: &SqlitePool, story_id: &str) -> Result<WorldView, String> {
+    let Some(branch) = current_branch(pool, story_id).await? else {
+        return Ok(WorldView::Exact(None));
+    };
+    let lightweight = get_setting(pool, "experimental_features")
+        .await?
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|v| v.get("lightweightBranches")?.as_bool())
+        .unwrap_or(false);
+    let snapshot_complete: Option<i64> =
+        sqlx::query_scalar("SELECT snapshot_complete FROM branches WHERE id = ?")
+            .bind(&branch)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load branch: {}", e))?;
+    if !lightweight || snapshot_complete.unwrap_or(0) != 0 {
+        return Ok(WorldView::Exact(Some(branch)));
+    }
+    let ids = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    Ok(WorldView::Lineage(ids))
+}
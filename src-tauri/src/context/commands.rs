@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+use tauri::State;
+
+use super::blocks::collect_blocks;
+use super::preview::preview;
+use super::types::{ContextBudget, ContextPreview};
+use crate::db::DbState;
+
+/// Collect natively built context blocks for a story.
+///
+/// Returns a map of template variable name to text, merged into the
+/// frontend ContextBuilder so pack templates can reference them.
+/// Blocks with nothing to say are omitted.
+#[tauri::command]
+pub async fn get_context_blocks(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<BTreeMap<String, String>, String> {
+    collect_blocks(db.pool(), &story_id).await
+}
+
+/// Run context assembly against the given budgets without calling the model,
+/// reporting each section's token count and what was dropped to fit
+#[tauri::command]
+pub async fn preview_context(
+    db: State<'_, DbState>,
+    story_id: String,
+    settings: Option<ContextBudget>,
+) -> Result<ContextPreview, String> {
+    preview(db.pool(), &story_id, &settings.unwrap_or_default()).await
+}
@@ -0,0 +1,5 @@
+pub mod blocks;
+pub mod branch;
+pub mod commands;
+pub mod preview;
+pub mod types;
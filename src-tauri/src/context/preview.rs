@@ -0,0 +1,339 @@
+use sqlx::SqlitePool;
+
+use super::blocks::collect_blocks;
+use super::branch::{lineage, visible_entries, world_view};
+use super::types::{ContextBudget, ContextPreview, PreviewSection};
+use crate::llm::debug::{LoreActivation, TruncationDecision};
+use crate::llm::tokens::count_tokens;
+use crate::lore::activation::activate;
+use crate::lore::budget::apply_budget;
+use crate::lore::payload;
+use crate::lore::store::{current_turn, load_current_lore, load_settings, load_timers};
+use crate::npcs::store::annotate_lore;
+
+/// A candidate piece of context: a label for reports and the prompt text
+struct Item {
+    label: String,
+    text: String,
+}
+
+/// How a section gives way when it is over budget
+enum Fill {
+    /// Take items in order, skipping any that don't fit
+    Greedy,
+    /// Take items in order and stop at the first that doesn't fit, keeping
+    /// history contiguous; the kept items are then restored to story order
+    NewestFirst,
+}
+
+fn fill(
+    name: &str,
+    budget: usize,
+    items: Vec<Item>,
+    mode: Fill,
+    dropped: &mut Vec<TruncationDecision>,
+) -> PreviewSection {
+    let mut used = 0;
+    let mut kept: Vec<String> = Vec::new();
+    let mut over = false;
+    let mut dropped_here = 0;
+    for item in items {
+        let tokens = count_tokens(&item.text);
+        if !over && used + tokens <= budget {
+            used += tokens;
+            kept.push(item.text);
+            continue;
+        }
+        if matches!(mode, Fill::NewestFirst) {
+            over = true;
+        }
+        dropped_here += 1;
+        dropped.push(TruncationDecision {
+            item: item.label,
+            reason: format!("{} budget ({} tokens) exhausted", name, budget),
+            tokens,
+        });
+    }
+    if matches!(mode, Fill::NewestFirst) {
+        kept.reverse();
+    }
+    let text = kept.join("\n\n");
+    PreviewSection {
+        name: name.to_string(),
+        budget,
+        tokens: count_tokens(&text),
+        included: kept.len(),
+        dropped: dropped_here,
+        text,
+    }
+}
+
+/// The story header, which only changes when the story is edited
+async fn system_items(pool: &SqlitePool, story_id: &str) -> Result<Vec<Item>, String> {
+    let story: Option<(String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT title, genre, description FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load story: {}", e))?;
+    let Some((title, genre, description)) = story else {
+        return Err(format!("Story {} not found", story_id));
+    };
+    let mut header = format!("Story: {}", title);
+    if let Some(genre) = genre.filter(|g| !g.is_empty()) {
+        header.push_str(&format!("\nGenre: {}", genre));
+    }
+    if let Some(description) = description.filter(|d| !d.is_empty()) {
+        header.push_str(&format!("\n{}", description));
+    }
+
+    Ok(vec![Item {
+        label: "story".to_string(),
+        text: header,
+    }])
+}
+
+/// Native state blocks (date, weather, stats, quests), which change most turns
+async fn state_items(pool: &SqlitePool, story_id: &str) -> Result<Vec<Item>, String> {
+    Ok(collect_blocks(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|(label, text)| Item { label, text })
+        .collect())
+}
+
+async fn summary_items(pool: &SqlitePool, story_id: &str) -> Result<Vec<Item>, String> {
+    let branch_ids: Vec<String> = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    let chapters: Vec<(i64, Option<String>, String, Option<String>)> = sqlx::query_as(
+        "SELECT number, title, summary, branch_id FROM chapters WHERE story_id = ? \
+         ORDER BY number DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    Ok(chapters
+        .into_iter()
+        .filter(|(_, _, _, branch)| branch.as_ref().is_none_or(|b| branch_ids.contains(b)))
+        .map(|(number, title, summary, _)| {
+            let heading = match title.filter(|t| !t.is_empty()) {
+                Some(title) => format!("Chapter {}: {}", number, title),
+                None => format!("Chapter {}", number),
+            };
+            Item {
+                label: heading.clone(),
+                text: format!("{}\n{}", heading, summary),
+            }
+        })
+        .collect())
+}
+
+/// Unsummarized entries after the last chapter, newest first, capped at
+/// `limit`; entries beyond the cap are reported as dropped
+async fn recent_items(
+    pool: &SqlitePool,
+    story_id: &str,
+    limit: usize,
+    dropped: &mut Vec<TruncationDecision>,
+) -> Result<Vec<Item>, String> {
+    let entries = visible_entries(pool, story_id).await?;
+    let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    let chapter_ends: Vec<String> =
+        sqlx::query_scalar("SELECT end_entry_id FROM chapters WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    let start = chapter_ends
+        .iter()
+        .filter_map(|end| ids.iter().position(|id| id == end))
+        .max()
+        .map_or(0, |i| i + 1);
+
+    let mut items = Vec::new();
+    for (n, entry) in entries[start..].iter().rev().enumerate() {
+        let text = match entry.kind.as_str() {
+            "user_action" => format!("> {}", entry.content),
+            _ => entry.content.clone(),
+        };
+        let label = format!("{} #{}", entry.kind, entry.position);
+        if n >= limit {
+            dropped.push(TruncationDecision {
+                item: label,
+                reason: format!("beyond the {} most recent entries", limit),
+                tokens: count_tokens(&text),
+            });
+        } else {
+            items.push(Item { label, text });
+        }
+    }
+    Ok(items)
+}
+
+#[derive(sqlx::FromRow)]
+struct BeatRow {
+    id: String,
+    title: String,
+    description: Option<String>,
+    status: Option<String>,
+    overrides_id: Option<String>,
+    deleted: i64,
+}
+
+async fn beat_items(pool: &SqlitePool, story_id: &str) -> Result<Vec<Item>, String> {
+    let rows_for = |branch_id: Option<String>| async move {
+        sqlx::query_as::<_, BeatRow>(
+            "SELECT id, title, description, status, overrides_id, deleted FROM story_beats \
+             WHERE story_id = ? AND branch_id IS ?",
+        )
+        .bind(story_id)
+        .bind(branch_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story beats: {}", e))
+    };
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        layers.push(rows_for(branch).await?);
+    }
+    Ok(view
+        .resolve(
+            layers,
+            |r| r.overrides_id.clone().unwrap_or_else(|| r.id.clone()),
+            |r| r.deleted != 0,
+        )
+        .into_iter()
+        .filter(|b| matches!(b.status.as_deref(), Some("active") | Some("pending") | None))
+        .map(|b| Item {
+            text: match b.description.filter(|d| !d.is_empty()) {
+                Some(description) => format!("- {}: {}", b.title, description),
+                None => format!("- {}", b.title),
+            },
+            label: b.title,
+        })
+        .collect())
+}
+
+/// Assemble the narrative context exactly as budgets allow, without calling
+/// the model: every section, the lore that fired, and everything dropped.
+///
+/// Sections are ordered from least to most likely to change between turns
+/// (story, summaries, beats, lore, recent entries, state) so providers with
+/// prompt caching can reuse the longest possible prefix. The system budget
+/// covers both the story header and the state blocks.
+pub async fn preview(
+    pool: &SqlitePool,
+    story_id: &str,
+    budget: &ContextBudget,
+) -> Result<ContextPreview, String> {
+    let mut dropped = Vec::new();
+
+    let system = fill(
+        "system",
+        budget.system_tokens,
+        system_items(pool, story_id).await?,
+        Fill::Greedy,
+        &mut dropped,
+    );
+    let state = fill(
+        "state",
+        budget.system_tokens.saturating_sub(system.tokens),
+        state_items(pool, story_id).await?,
+        Fill::Greedy,
+        &mut dropped,
+    );
+
+    let recent = recent_items(pool, story_id, budget.recent_entries, &mut dropped).await?;
+    let mut scan_text: Vec<&str> = recent.iter().map(|i| i.text.as_str()).collect();
+    if let Some(input) = &budget.user_input {
+        scan_text.push(input);
+    }
+    let scan_text = scan_text.join("\n");
+
+    // Same activation and lore budget as a real turn, but timers are left untouched
+    let settings = load_settings(pool).await?;
+    let (mut entries, out_of_time) = load_current_lore(pool, story_id).await?;
+    annotate_lore(pool, story_id, &mut entries).await?;
+    dropped.extend(out_of_time.into_iter().map(|name| TruncationDecision {
+        item: format!("lore: {}", name),
+        reason: "outside its in-story validity window".to_string(),
+        tokens: 0,
+    }));
+    let turn = current_turn(pool, story_id).await?;
+    let fired = activate(
+        &entries,
+        &scan_text,
+        &settings,
+        &load_timers(pool, story_id).await?,
+        &payload::ripened(pool, story_id, &entries, turn, false).await?,
+        turn,
+        &mut rand::thread_rng(),
+    );
+    let lore_budget = budget.lore_tokens.unwrap_or(settings.token_budget);
+    let mut active = apply_budget(fired, &settings, Some(lore_budget));
+    // Activation order varies with scores; a fixed order keeps the same set
+    // of entries byte-identical across turns
+    active
+        .entries
+        .sort_by(|a, b| (&a.name, &a.entry_id).cmp(&(&b.name, &b.entry_id)));
+    let text = active
+        .entries
+        .iter()
+        .map(|f| f.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let lore = PreviewSection {
+        name: "lore".to_string(),
+        budget: lore_budget,
+        tokens: count_tokens(&text),
+        included: active.entries.len(),
+        dropped: active.cut.len(),
+        text,
+    };
+    dropped.extend(active.cut);
+    let lore_activations = active
+        .entries
+        .into_iter()
+        .map(|f| LoreActivation {
+            entry_id: Some(f.entry_id),
+            name: f.name,
+            reason: f.reason,
+        })
+        .collect();
+
+    let summaries = fill(
+        "summaries",
+        budget.summary_tokens,
+        summary_items(pool, story_id).await?,
+        Fill::NewestFirst,
+        &mut dropped,
+    );
+    let recent = fill(
+        "recent entries",
+        budget.recent_tokens,
+        recent,
+        Fill::NewestFirst,
+        &mut dropped,
+    );
+    let beats = fill(
+        "beats",
+        budget.beat_tokens,
+        beat_items(pool, story_id).await?,
+        Fill::Greedy,
+        &mut dropped,
+    );
+
+    let sections = vec![system, summaries, beats, lore, recent, state];
+    let total_tokens = sections.iter().map(|s| s.tokens).sum();
+    Ok(ContextPreview {
+        sections,
+        lore: lore_activations,
+        dropped,
+        total_tokens,
+    })
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::llm::debug::{LoreActivation, TruncationDecision};
+
+/// Token budgets for each part of the narrative context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContextBudget {
+    pub system_tokens: usize,
+    /// Defaults to the lore activation budget
+    pub lore_tokens: Option<usize>,
+    pub summary_tokens: usize,
+    pub recent_tokens: usize,
+    pub beat_tokens: usize,
+    /// Most recent entries considered before the token budget applies
+    pub recent_entries: usize,
+    /// The pending player input, scanned for lore keywords with recent entries
+    pub user_input: Option<String>,
+}
+
+impl Default for ContextBudget {
+    fn default() -> Self {
+        Self {
+            system_tokens: 2000,
+            lore_tokens: None,
+            summary_tokens: 2000,
+            recent_tokens: 6000,
+            beat_tokens: 500,
+            recent_entries: 20,
+            user_input: None,
+        }
+    }
+}
+
+/// One part of the assembled context and how it fared against its budget
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSection {
+    pub name: String,
+    pub budget: usize,
+    pub tokens: usize,
+    pub included: usize,
+    pub dropped: usize,
+    pub text: String,
+}
+
+/// What a narrative request would contain, without sending it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextPreview {
+    pub sections: Vec<PreviewSection>,
+    pub lore: Vec<LoreActivation>,
+    pub dropped: Vec<TruncationDecision>,
+    pub total_tokens: usize,
+}
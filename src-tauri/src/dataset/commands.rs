@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use futures_util::TryStreamExt;
+use tauri::State;
+use tokio::io::AsyncWriteExt;
+
+use super::entries::{header, line, DatasetRow};
+use super::finetune::{conversation, example, quality_flags, selected, system_prompt};
+use super::pii::PiiScrubber;
+use super::types::{
+    DatasetExport, DatasetField, DatasetFormat, EntryQuality, FineTuneExport, FineTuneOptions,
+    QualityFlag,
+};
+use crate::context::branch::segments;
+use crate::db::{now_millis, DbState};
+use crate::redaction::redactor::Redactor;
+
+/// Write the entries on the story's current branch, in reading order, to
+/// `path` as CSV or JSONL with the chosen fields (role, text, created time
+/// and token count and model when none are given). Rows are streamed from
+/// the database to the file, redacted by the story's rules when `redact`
+/// is set.
+#[tauri::command]
+pub async fn export_entries_dataset(
+    db: State<'_, DbState>,
+    story_id: String,
+    format: DatasetFormat,
+    fields: Vec<DatasetField>,
+    path: String,
+    redact: Option<bool>,
+) -> Result<DatasetExport, String> {
+    let fields = if fields.is_empty() {
+        DatasetField::DEFAULT.to_vec()
+    } else {
+        fields
+    };
+    let pool = db.pool();
+    let redactor = match redact {
+        Some(true) => Some(Redactor::load(pool, &story_id).await?),
+        _ => None,
+    };
+    let fail = |e: std::io::Error| format!("Failed to write dataset: {}", e);
+    let file = tokio::fs::File::create(&path).await.map_err(fail)?;
+    let mut out = tokio::io::BufWriter::new(file);
+    let mut bytes = 0u64;
+    if let Some(header) = header(format, &fields) {
+        out.write_all(header.as_bytes()).await.map_err(fail)?;
+        bytes += header.len() as u64;
+    }
+
+    let mut rows = 0;
+    for (branch_id, max_position) in segments(pool, &story_id).await? {
+        let mut stream = sqlx::query_as::<_, DatasetRow>(
+            "SELECT id, type, content, position, branch_id, created_at, metadata \
+             FROM story_entries \
+             WHERE story_id = ? AND branch_id IS ? AND (? IS NULL OR position <= ?) \
+             ORDER BY position ASC",
+        )
+        .bind(&story_id)
+        .bind(branch_id)
+        .bind(max_position)
+        .bind(max_position)
+        .fetch(pool);
+        while let Some(mut row) = stream
+            .try_next()
+            .await
+            .map_err(|e| format!("Failed to load story entries: {}", e))?
+        {
+            if let Some(redactor) = &redactor {
+                row.content = redactor.redact(&row.content);
+            }
+            let line = line(format, &fields, &row);
+            out.write_all(line.as_bytes()).await.map_err(fail)?;
+            bytes += line.len() as u64;
+            rows += 1;
+        }
+    }
+    out.flush().await.map_err(fail)?;
+    Ok(DatasetExport { path, rows, bytes })
+}
+
+/// Flag an entry as a good or bad turn for fine-tuning, or clear the flag
+#[tauri::command]
+pub async fn set_entry_quality(
+    db: State<'_, DbState>,
+    entry_id: String,
+    flag: Option<QualityFlag>,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let Some(flag) = flag else {
+        sqlx::query("DELETE FROM entry_quality WHERE entry_id = ?")
+            .bind(&entry_id)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to clear quality flag: {}", e))?;
+        return Ok(());
+    };
+    sqlx::query(
+        "INSERT INTO entry_quality (entry_id, story_id, flag, updated_at) \
+         SELECT id, story_id, ?, ? FROM story_entries WHERE id = ? \
+         ON CONFLICT(entry_id) DO UPDATE SET flag = excluded.flag, updated_at = excluded.updated_at",
+    )
+    .bind(flag.as_str())
+    .bind(now_millis())
+    .bind(&entry_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save quality flag: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_entry_quality(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<EntryQuality>, String> {
+    sqlx::query_as("SELECT entry_id, flag, updated_at FROM entry_quality WHERE story_id = ?")
+        .bind(&story_id)
+        .fetch_all(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load quality flags: {}", e))
+}
+
+/// Write chat-format JSONL (OpenAI or ShareGPT) for fine-tuning from the
+/// chosen stories' current branches. Each kept narration turn is one
+/// example, with the exchanges before it and an optional templated system
+/// prompt; personal details are masked as the options say.
+#[tauri::command]
+pub async fn export_finetune_dataset(
+    db: State<'_, DbState>,
+    options: FineTuneOptions,
+) -> Result<FineTuneExport, String> {
+    let pool = db.pool();
+    let wanted: Option<HashSet<&str>> = options
+        .entry_ids
+        .as_ref()
+        .map(|ids| ids.iter().map(String::as_str).collect());
+    let fail = |e: std::io::Error| format!("Failed to write dataset: {}", e);
+    let file = tokio::fs::File::create(&options.path).await.map_err(fail)?;
+    let mut out = tokio::io::BufWriter::new(file);
+    let mut report = FineTuneExport {
+        path: options.path.clone(),
+        examples: 0,
+        stories: 0,
+        skipped: 0,
+    };
+
+    for story_id in &options.story_ids {
+        let story: Option<(String, Option<String>, Option<String>)> =
+            sqlx::query_as("SELECT title, genre, description FROM stories WHERE id = ?")
+                .bind(story_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| format!("Failed to load story: {}", e))?;
+        let Some((title, genre, description)) = story else {
+            return Err(format!("Story not found: {}", story_id));
+        };
+        let player: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM characters WHERE story_id = ? AND relationship = 'self' LIMIT 1",
+        )
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load player character: {}", e))?;
+        let scrubber = PiiScrubber::new(&options.pii, player.as_deref())?;
+        let system = match &options.system_prompt {
+            Some(template) => {
+                let story = serde_json::json!({
+                    "title": title,
+                    "genre": genre,
+                    "description": description,
+                });
+                Some(system_prompt(template, &story, player.as_deref())?)
+            }
+            None => None,
+        };
+
+        let messages = conversation(pool, story_id).await?;
+        let flags = quality_flags(pool, story_id).await?;
+        let before = report.examples;
+        for (i, message) in messages.iter().enumerate() {
+            if message.role != "assistant" {
+                continue;
+            }
+            if !selected(message, &options, wanted.as_ref(), &flags) {
+                report.skipped += 1;
+                continue;
+            }
+            let start = i.saturating_sub(options.window * 2 + 1);
+            let line = example(
+                options.format,
+                system.as_deref(),
+                &messages[start..=i],
+                &scrubber,
+            );
+            out.write_all(line.as_bytes()).await.map_err(fail)?;
+            report.examples += 1;
+        }
+        if report.examples > before {
+            report.stories += 1;
+        }
+    }
+    out.flush().await.map_err(fail)?;
+    Ok(report)
+}
@@ -0,0 +1,92 @@
+//! Entry rows for datasets, written one at a time as they come out of the
+//! database so large stories don't have to fit in memory
+
+use serde_json::{Map, Value};
+
+use super::types::{DatasetField, DatasetFormat};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct DatasetRow {
+    pub id: String,
+    #[sqlx(rename = "type")]
+    pub kind: String,
+    pub content: String,
+    pub position: i64,
+    pub branch_id: Option<String>,
+    pub created_at: i64,
+    pub metadata: Option<String>,
+}
+
+/// Chat role of an entry type
+pub fn role(kind: &str) -> &'static str {
+    match kind {
+        "user_action" => "user",
+        "system" => "system",
+        _ => "assistant",
+    }
+}
+
+fn field_value(row: &DatasetRow, metadata: &Value, field: DatasetField) -> Value {
+    let meta = |key: &str| metadata.get(key).cloned().unwrap_or(Value::Null);
+    match field {
+        DatasetField::Id => Value::from(row.id.as_str()),
+        DatasetField::Role => Value::from(role(&row.kind)),
+        DatasetField::Type => Value::from(row.kind.as_str()),
+        DatasetField::Text => Value::from(row.content.as_str()),
+        DatasetField::Position => Value::from(row.position),
+        DatasetField::BranchId => row.branch_id.as_deref().map_or(Value::Null, Value::from),
+        DatasetField::CreatedAt => Value::from(row.created_at),
+        DatasetField::StoryTimeStart => meta("timeStart"),
+        DatasetField::StoryTimeEnd => meta("timeEnd"),
+        DatasetField::TokenCount => meta("tokenCount"),
+        DatasetField::Model => meta("model"),
+        DatasetField::GenerationTime => meta("generationTime"),
+    }
+}
+
+/// RFC 4180 quoting, only where needed
+fn csv_cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// The CSV header line; JSONL has none
+pub fn header(format: DatasetFormat, fields: &[DatasetField]) -> Option<String> {
+    (format == DatasetFormat::Csv).then(|| {
+        let keys: Vec<&str> = fields.iter().map(|f| f.key()).collect();
+        format!("{}\r\n", keys.join(","))
+    })
+}
+
+/// One output line for a row, terminator included
+pub fn line(format: DatasetFormat, fields: &[DatasetField], row: &DatasetRow) -> String {
+    let metadata: Value = row
+        .metadata
+        .as_deref()
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or(Value::Null);
+    match format {
+        DatasetFormat::Csv => {
+            let cells: Vec<String> = fields
+                .iter()
+                .map(|&f| csv_cell(&field_value(row, &metadata, f)))
+                .collect();
+            format!("{}\r\n", cells.join(","))
+        }
+        DatasetFormat::Jsonl => {
+            let object: Map<String, Value> = fields
+                .iter()
+                .map(|&f| (f.key().to_string(), field_value(row, &metadata, f)))
+                .collect();
+            format!("{}\n", Value::Object(object))
+        }
+    }
+}
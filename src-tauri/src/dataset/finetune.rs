@@ -0,0 +1,174 @@
+//! Chat-format fine-tuning examples from stories: each narration turn the
+//! selection keeps becomes one example, preceded by a window of the
+//! exchanges before it
+
+use std::collections::{HashMap, HashSet};
+
+use handlebars::{no_escape, Handlebars};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use super::entries::role;
+use super::pii::PiiScrubber;
+use super::types::{ChatFormat, FineTuneOptions, QualityFlag};
+use crate::context::branch::segments;
+
+pub struct Message {
+    pub role: &'static str,
+    pub text: String,
+    pub entry_ids: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TurnRow {
+    id: String,
+    #[sqlx(rename = "type")]
+    kind: String,
+    content: String,
+    original_input: Option<String>,
+}
+
+/// The story's current branch as alternating chat messages. System and
+/// retry entries are dropped; back-to-back entries of one role are joined.
+pub async fn conversation(pool: &SqlitePool, story_id: &str) -> Result<Vec<Message>, String> {
+    let mut messages: Vec<Message> = Vec::new();
+    for (branch_id, max_position) in segments(pool, story_id).await? {
+        let rows: Vec<TurnRow> = sqlx::query_as(
+            "SELECT id, type, content, original_input FROM story_entries \
+             WHERE story_id = ? AND branch_id IS ? AND (? IS NULL OR position <= ?) \
+             AND type IN ('user_action', 'narration') ORDER BY position ASC",
+        )
+        .bind(story_id)
+        .bind(branch_id)
+        .bind(max_position)
+        .bind(max_position)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story entries: {}", e))?;
+        for row in rows {
+            let role = role(&row.kind);
+            let text = match role {
+                "user" => row.original_input.unwrap_or(row.content),
+                _ => row.content,
+            };
+            match messages.last_mut() {
+                Some(last) if last.role == role => {
+                    last.text = format!("{}\n\n{}", last.text.trim_end(), text.trim_start());
+                    last.entry_ids.push(row.id);
+                }
+                _ => messages.push(Message {
+                    role,
+                    text,
+                    entry_ids: vec![row.id],
+                }),
+            }
+        }
+    }
+    Ok(messages)
+}
+
+pub async fn quality_flags(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<HashMap<String, QualityFlag>, String> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT entry_id, flag FROM entry_quality WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load quality flags: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, flag)| {
+            let flag = match flag.as_str() {
+                "good" => QualityFlag::Good,
+                "bad" => QualityFlag::Bad,
+                _ => return None,
+            };
+            Some((id, flag))
+        })
+        .collect())
+}
+
+/// The system message for a story, from the template
+pub fn system_prompt(
+    template: &str,
+    story: &Value,
+    player: Option<&str>,
+) -> Result<String, String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(no_escape);
+    handlebars
+        .render_template(template, &json!({ "story": story, "player": player }))
+        .map(|p| p.trim().to_string())
+        .map_err(|e| format!("Invalid system prompt template: {}", e))
+}
+
+/// Whether the turn ending in `message` makes an example
+pub fn selected(
+    message: &Message,
+    options: &FineTuneOptions,
+    wanted: Option<&HashSet<&str>>,
+    flags: &HashMap<String, QualityFlag>,
+) -> bool {
+    if message.role != "assistant" {
+        return false;
+    }
+    if let Some(wanted) = wanted {
+        if !message
+            .entry_ids
+            .iter()
+            .any(|id| wanted.contains(id.as_str()))
+        {
+            return false;
+        }
+    }
+    let flagged = |flag| {
+        message
+            .entry_ids
+            .iter()
+            .any(|id| flags.get(id) == Some(&flag))
+    };
+    if options.skip_bad && flagged(QualityFlag::Bad) {
+        return false;
+    }
+    !options.only_good || flagged(QualityFlag::Good)
+}
+
+/// One JSONL line: the system prompt and `messages`, scrubbed
+pub fn example(
+    format: ChatFormat,
+    system: Option<&str>,
+    messages: &[Message],
+    scrubber: &PiiScrubber,
+) -> String {
+    let turns = system
+        .filter(|s| !s.is_empty())
+        .map(|s| ("system", scrubber.scrub(s)))
+        .into_iter()
+        .chain(
+            messages
+                .iter()
+                .map(|m| (m.role, scrubber.scrub(m.text.trim()))),
+        );
+    let line = match format {
+        ChatFormat::OpenAi => json!({
+            "messages": turns
+                .map(|(role, content)| json!({ "role": role, "content": content }))
+                .collect::<Vec<_>>()
+        }),
+        ChatFormat::ShareGpt => json!({
+            "conversations": turns
+                .map(|(role, value)| {
+                    let from = match role {
+                        "user" => "human",
+                        "assistant" => "gpt",
+                        other => other,
+                    };
+                    json!({ "from": from, "value": value })
+                })
+                .collect::<Vec<_>>()
+        }),
+    };
+    format!("{}\n", line)
+}
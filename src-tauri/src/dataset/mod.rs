@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod entries;
+pub mod finetune;
+pub mod pii;
+pub mod types;
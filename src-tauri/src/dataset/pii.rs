@@ -0,0 +1,52 @@
+use regex::{Regex, RegexBuilder};
+
+use super::types::PiiOptions;
+
+/// Masks personal details in dataset text
+pub struct PiiScrubber {
+    rules: Vec<(Regex, &'static str)>,
+}
+
+fn word(term: &str) -> Result<Regex, String> {
+    RegexBuilder::new(&format!(r"\b{}\b", regex::escape(term.trim())))
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid term {}: {}", term, e))
+}
+
+impl PiiScrubber {
+    pub fn new(options: &PiiOptions, player: Option<&str>) -> Result<Self, String> {
+        let fixed = |pattern: &str| Regex::new(pattern).map_err(|e| e.to_string());
+        let mut rules = Vec::new();
+        if options.emails {
+            rules.push((fixed(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+")?, "[email]"));
+        }
+        if options.urls {
+            rules.push((fixed(r"\b(?:https?://|www\.)\S+")?, "[url]"));
+        }
+        if options.phone_numbers {
+            rules.push((fixed(r"\+?\d[\d \t().-]{7,}\d")?, "[phone]"));
+        }
+        if options.player_name {
+            if let Some(name) = player.filter(|n| !n.trim().is_empty()) {
+                rules.push((word(name)?, "{{user}}"));
+            }
+        }
+        for term in options.terms.iter().filter(|t| !t.trim().is_empty()) {
+            rules.push((word(term)?, "[redacted]"));
+        }
+        Ok(PiiScrubber { rules })
+    }
+
+    pub fn scrub(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (pattern, replacement) in &self.rules {
+            if let std::borrow::Cow::Owned(replaced) =
+                pattern.replace_all(&text, regex::NoExpand(replacement))
+            {
+                text = replaced;
+            }
+        }
+        text
+    }
+}
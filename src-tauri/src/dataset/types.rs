@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Csv,
+    Jsonl,
+}
+
+/// A column of `export_entries_dataset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DatasetField {
+    Id,
+    /// `user`, `assistant` or `system`, as in chat datasets
+    Role,
+    /// The entry type as stored: `user_action`, `narration`...
+    Type,
+    Text,
+    Position,
+    BranchId,
+    /// Unix milliseconds
+    CreatedAt,
+    /// In-story time tracker when the entry began and after it
+    StoryTimeStart,
+    StoryTimeEnd,
+    TokenCount,
+    Model,
+    /// Milliseconds the generation took
+    GenerationTime,
+}
+
+impl DatasetField {
+    /// Used when the caller picks no fields
+    pub const DEFAULT: &[DatasetField] = &[
+        DatasetField::Role,
+        DatasetField::Text,
+        DatasetField::CreatedAt,
+        DatasetField::TokenCount,
+        DatasetField::Model,
+    ];
+
+    /// Column header / JSON key
+    pub fn key(self) -> &'static str {
+        match self {
+            DatasetField::Id => "id",
+            DatasetField::Role => "role",
+            DatasetField::Type => "type",
+            DatasetField::Text => "text",
+            DatasetField::Position => "position",
+            DatasetField::BranchId => "branchId",
+            DatasetField::CreatedAt => "createdAt",
+            DatasetField::StoryTimeStart => "storyTimeStart",
+            DatasetField::StoryTimeEnd => "storyTimeEnd",
+            DatasetField::TokenCount => "tokenCount",
+            DatasetField::Model => "model",
+            DatasetField::GenerationTime => "generationTime",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetExport {
+    pub path: String,
+    pub rows: usize,
+    pub bytes: u64,
+}
+
+/// How good a turn is, for picking fine-tuning examples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityFlag {
+    Good,
+    Bad,
+}
+
+impl QualityFlag {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QualityFlag::Good => "good",
+            QualityFlag::Bad => "bad",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryQuality {
+    pub entry_id: String,
+    /// `good` or `bad`
+    pub flag: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChatFormat {
+    /// `{"messages": [{"role", "content"}]}`
+    OpenAi,
+    /// `{"conversations": [{"from", "value"}]}`
+    ShareGpt,
+}
+
+/// What personal details `export_finetune_dataset` masks
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PiiOptions {
+    pub emails: bool,
+    pub phone_numbers: bool,
+    pub urls: bool,
+    /// The player character's name becomes `{{user}}`
+    pub player_name: bool,
+    /// Further names or terms, replaced with `[redacted]`
+    pub terms: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_window() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FineTuneOptions {
+    pub story_ids: Vec<String>,
+    /// Only examples ending in these narration entries; all when omitted
+    #[serde(default)]
+    pub entry_ids: Option<Vec<String>>,
+    pub format: ChatFormat,
+    /// Handlebars template with `story` (title, genre, description) and
+    /// `player`; no system message when omitted
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Earlier user/assistant exchanges included before each turn
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Only turns flagged good
+    #[serde(default)]
+    pub only_good: bool,
+    /// Leave out turns flagged bad
+    #[serde(default = "default_true")]
+    pub skip_bad: bool,
+    #[serde(default)]
+    pub pii: PiiOptions,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FineTuneExport {
+    pub path: String,
+    pub examples: usize,
+    pub stories: usize,
+    /// Narration turns left out by the selection or quality flags
+    pub skipped: usize,
+}
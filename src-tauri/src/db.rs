@@ -0,0 +1,41 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Database access for Rust-side commands.
+///
+/// The frontend owns the schema through tauri-plugin-sql migrations; this pool
+/// opens the same `aventura.db` file so native commands can read and write it
+/// directly. Connections are opened lazily, after the plugin has migrated.
+pub struct DbState {
+    pool: SqlitePool,
+}
+
+impl DbState {
+    pub fn new(db_path: &Path) -> Self {
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .busy_timeout(Duration::from_secs(10));
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_lazy_with(options);
+
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+/// Current time in milliseconds, matching the `Date.now()` timestamps used by the frontend
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
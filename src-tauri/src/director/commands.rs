@@ -0,0 +1,106 @@
+use serde_json::json;
+use tauri::State;
+
+use super::store;
+use super::types::{ArcDrift, ArcPlan, ArcStatus};
+use crate::context::branch::{chapter_count, visible_entries};
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+#[tauri::command]
+pub async fn get_arc_plan(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Option<ArcPlan>, String> {
+    store::load_plan(db.pool(), &story_id).await
+}
+
+/// Save a story's arc plan, replacing any earlier one
+#[tauri::command]
+pub async fn set_arc_plan(
+    db: State<'_, DbState>,
+    story_id: String,
+    mut plan: ArcPlan,
+) -> Result<ArcPlan, String> {
+    store::validate(&mut plan)?;
+    store::save_plan(db.pool(), &story_id, &plan).await?;
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn delete_arc_plan(db: State<'_, DbState>, story_id: String) -> Result<(), String> {
+    store::delete_plan(db.pool(), &story_id).await
+}
+
+/// Pin the story to an act, or go back to following act lengths with `None`
+#[tauri::command]
+pub async fn set_current_act(
+    db: State<'_, DbState>,
+    story_id: String,
+    act: Option<usize>,
+) -> Result<ArcPlan, String> {
+    let pool = db.pool();
+    let mut plan = store::load_plan(pool, &story_id)
+        .await?
+        .ok_or("This story has no arc plan")?;
+    plan.current_act = act;
+    store::validate(&mut plan)?;
+    store::save_plan(pool, &story_id, &plan).await?;
+    Ok(plan)
+}
+
+/// The plan with the act the story is in, the guidance the context gets and
+/// the latest drift check
+#[tauri::command]
+pub async fn get_arc_status(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Option<ArcStatus>, String> {
+    let pool = db.pool();
+    let Some(plan) = store::load_plan(pool, &story_id).await? else {
+        return Ok(None);
+    };
+    let chapters = chapter_count(pool, &story_id).await?;
+    let act = store::current_act(&plan, chapters);
+    let last_check = store::load_drift(pool, &story_id, 1).await?.pop();
+    Ok(Some(ArcStatus {
+        guidance: store::guidance(&plan, act),
+        plan,
+        act,
+        chapters,
+        last_check,
+    }))
+}
+
+/// Queue a drift check against the latest entry now, rather than waiting
+/// for the pipeline
+#[tauri::command]
+pub async fn check_arc_drift(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+) -> Result<String, String> {
+    let pool = db.pool();
+    let entry = visible_entries(pool, &story_id)
+        .await?
+        .pop()
+        .ok_or("This story has no entries yet")?;
+    queue
+        .enqueue(
+            pool,
+            Some(&story_id),
+            JobKind::ArcDrift,
+            json!({ "entryId": entry.id }),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn get_arc_drift(
+    db: State<'_, DbState>,
+    story_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<ArcDrift>, String> {
+    store::load_drift(db.pool(), &story_id, limit.unwrap_or(20)).await
+}
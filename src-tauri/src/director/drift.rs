@@ -0,0 +1,127 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::store::{current_act, guidance, load_plan, save_drift};
+use super::types::ArcDrift;
+use crate::context::branch::{chapter_count, visible_entries};
+use crate::db::now_millis;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+/// Passages, up to and including the checked one, compared against the plan
+const RECENT_PASSAGES: usize = 6;
+
+const SYSTEM_PROMPT: &str = "You compare an interactive story against its author's outline. \
+Given the outline for the current act and the latest passages, judge how well the story is \
+still heading where the outline wants it to go. Detours are fine while they can still lead back; \
+only call it drift when the story is moving away from the act's goals or the planned ending. \
+Respond with JSON only, in this shape: \
+{\"alignment\": <0 to 1, where 1 is squarely on the outline>, \"notes\": \"<one or two sentences on \
+what has wandered, or what is on track>\"}.";
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "arc_drift",
+        schema: json!({
+            "type": "object",
+            "required": ["alignment", "notes"],
+            "properties": {
+                "alignment": { "type": "number", "minimum": 0, "maximum": 1 },
+                "notes": { "type": "string" }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DriftReply {
+    alignment: f64,
+    #[serde(default)]
+    notes: String,
+}
+
+/// Background job: ask the classification model how far the latest passages
+/// have wandered from the arc plan. Every check is stored; those below the
+/// plan's threshold are emitted on `arc-drift`.
+pub async fn run_drift_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Arc drift job is missing entryId")?;
+    let story_id = job
+        .story_id
+        .as_deref()
+        .ok_or("Arc drift job is missing its story")?;
+
+    let Some(plan) = load_plan(pool, story_id).await?.filter(|p| p.enabled) else {
+        return Ok(json!({ "skipped": "no arc plan" }));
+    };
+    let act = current_act(&plan, chapter_count(pool, story_id).await?);
+
+    let entries = visible_entries(pool, story_id).await?;
+    let end = entries
+        .iter()
+        .position(|e| e.id == entry_id)
+        .map(|i| i + 1)
+        .unwrap_or(entries.len());
+    let passages = entries[end.saturating_sub(RECENT_PASSAGES)..end]
+        .iter()
+        .map(|e| e.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if passages.trim().is_empty() {
+        return Ok(json!({ "skipped": "no passages" }));
+    }
+
+    let mut outline = guidance(&plan, act);
+    if let Some(ending) = plan.ending.as_ref().filter(|e| !e.trim().is_empty()) {
+        // The checker always judges against the ending, whatever the act shows
+        if !outline.contains("Planned ending:") {
+            outline.push_str(&format!("\nPlanned ending: {}", ending.trim()));
+        }
+    }
+    let prompt = format!("Outline:\n{}\n\nLatest passages:\n{}", outline, passages);
+
+    let llm = config::resolve_service(pool, "arcDrift", "classification").await?;
+    let reply: DriftReply = complete_structured(
+        pool,
+        "arcDrift",
+        Some(story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let alignment = reply.alignment.clamp(0.0, 1.0);
+    let drift = ArcDrift {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.to_string(),
+        entry_id: entry_id.to_string(),
+        act: act as i64,
+        alignment,
+        drifting: alignment < plan.drift_threshold,
+        notes: reply.notes.trim().to_string(),
+        created_at: now_millis(),
+    };
+    save_drift(pool, &drift).await?;
+    if drift.drifting {
+        if let Err(e) = app.emit("arc-drift", &drift) {
+            eprintln!("Failed to emit arc drift event: {}", e);
+        }
+    }
+    Ok(json!(drift))
+}
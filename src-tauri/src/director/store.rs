@@ -0,0 +1,205 @@
+use sqlx::SqlitePool;
+
+use super::types::{ArcDrift, ArcPlan};
+use crate::context::branch::{chapter_count, visible_entries};
+use crate::db::now_millis;
+
+pub async fn load_plan(pool: &SqlitePool, story_id: &str) -> Result<Option<ArcPlan>, String> {
+    let raw: Option<String> = sqlx::query_scalar("SELECT plan FROM story_arcs WHERE story_id = ?")
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load arc plan: {}", e))?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+/// Reject plans that point outside themselves and clamp the tuning values
+pub fn validate(plan: &mut ArcPlan) -> Result<(), String> {
+    if plan.acts.is_empty() {
+        return Err("An arc plan needs at least one act".to_string());
+    }
+    if plan.acts.iter().any(|a| a.title.trim().is_empty()) {
+        return Err("Every act needs a title".to_string());
+    }
+    if plan.acts.iter().any(|a| a.chapters.is_some_and(|n| n < 1)) {
+        return Err("An act must last at least one chapter".to_string());
+    }
+    if let Some(act) = plan.current_act.filter(|act| *act >= plan.acts.len()) {
+        return Err(format!("Act {} is not in the plan", act + 1));
+    }
+    if let Some(twist) = plan.twists.iter().find(|t| t.act >= plan.acts.len()) {
+        return Err(format!(
+            "Twist '{}' lands in an act that is not in the plan",
+            twist.description
+        ));
+    }
+    for act in &mut plan.acts {
+        act.weight = act.weight.clamp(0.0, 1.0);
+    }
+    plan.drift_threshold = plan.drift_threshold.clamp(0.0, 1.0);
+    Ok(())
+}
+
+pub async fn save_plan(pool: &SqlitePool, story_id: &str, plan: &ArcPlan) -> Result<(), String> {
+    let json =
+        serde_json::to_string(plan).map_err(|e| format!("Failed to serialize arc plan: {}", e))?;
+    sqlx::query(
+        "INSERT INTO story_arcs (story_id, plan, updated_at) VALUES (?, ?, ?) \
+         ON CONFLICT(story_id) DO UPDATE SET plan = excluded.plan, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(story_id)
+    .bind(json)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save arc plan: {}", e))?;
+    Ok(())
+}
+
+pub async fn delete_plan(pool: &SqlitePool, story_id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM story_arcs WHERE story_id = ?")
+        .bind(story_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete arc plan: {}", e))?;
+    Ok(())
+}
+
+/// The act the story is in: the one picked by hand, otherwise the first
+/// whose planned chapters haven't all passed
+pub fn current_act(plan: &ArcPlan, chapters: i64) -> usize {
+    let last = plan.acts.len().saturating_sub(1);
+    if let Some(act) = plan.current_act {
+        return act.min(last);
+    }
+    let mut end = 0;
+    for (index, act) in plan.acts.iter().enumerate() {
+        let Some(length) = act.chapters else {
+            return index;
+        };
+        end += length;
+        if chapters < end {
+            return index;
+        }
+    }
+    last
+}
+
+/// How hard the notes lean, by the act's weight
+fn steering(weight: f64) -> &'static str {
+    if weight >= 0.67 {
+        "Steer the story firmly toward these goals; bring it back if it strays."
+    } else if weight >= 0.34 {
+        "Move the story toward these goals when the player's choices allow it."
+    } else {
+        "Treat these as loose suggestions and follow the player's lead."
+    }
+}
+
+/// Hidden guidance for one act. The act's weight decides how far ahead the
+/// notes look and how firmly they are worded.
+pub fn guidance(plan: &ArcPlan, act: usize) -> String {
+    let Some(current) = plan.acts.get(act) else {
+        return String::new();
+    };
+    let mut lines = vec![
+        "[Director's notes: private guidance for the narrator. Never mention or reveal them.]"
+            .to_string(),
+        format!("Act {} of {}: {}", act + 1, plan.acts.len(), current.title),
+    ];
+    if !current.summary.trim().is_empty() {
+        lines.push(current.summary.trim().to_string());
+    }
+    if !current.goals.is_empty() {
+        lines.push(format!("Goals for this act: {}", current.goals.join("; ")));
+    }
+    let twists: Vec<&str> = plan
+        .twists
+        .iter()
+        .filter(|t| t.act == act && !t.revealed)
+        .map(|t| t.description.as_str())
+        .collect();
+    if !twists.is_empty() {
+        lines.push(format!(
+            "Foreshadow without revealing: {}",
+            twists.join("; ")
+        ));
+    }
+    if current.weight >= 0.34 {
+        if let Some(next) = plan.acts.get(act + 1) {
+            lines.push(format!("Building toward the next act: {}", next.title));
+        }
+    }
+    let final_act = act + 1 == plan.acts.len();
+    if let Some(ending) = plan.ending.as_ref().filter(|e| !e.trim().is_empty()) {
+        if final_act || current.weight >= 0.67 {
+            lines.push(format!("Planned ending: {}", ending.trim()));
+        }
+    }
+    lines.push(steering(current.weight).to_string());
+    lines.join("\n")
+}
+
+/// The story's guidance block, when it has an enabled plan
+pub async fn story_guidance(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    let Some(plan) = load_plan(pool, story_id).await?.filter(|p| p.enabled) else {
+        return Ok(None);
+    };
+    let act = current_act(&plan, chapter_count(pool, story_id).await?);
+    Ok(Some(guidance(&plan, act)).filter(|g| !g.is_empty()))
+}
+
+/// Whether the pipeline should queue a drift check after this response.
+/// Counts responses only, like scene illustrations.
+pub async fn check_due(pool: &SqlitePool, story_id: &str) -> Result<bool, String> {
+    let Some(plan) = load_plan(pool, story_id).await?.filter(|p| p.enabled) else {
+        return Ok(false);
+    };
+    if plan.check_every == 0 {
+        return Ok(false);
+    }
+    let responses = visible_entries(pool, story_id)
+        .await?
+        .iter()
+        .filter(|e| e.kind != "user_action")
+        .count() as u32;
+    Ok(responses > 0 && responses.is_multiple_of(plan.check_every))
+}
+
+pub async fn save_drift(pool: &SqlitePool, drift: &ArcDrift) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO arc_drift_checks \
+         (id, story_id, entry_id, act, alignment, drifting, notes, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&drift.id)
+    .bind(&drift.story_id)
+    .bind(&drift.entry_id)
+    .bind(drift.act)
+    .bind(drift.alignment)
+    .bind(drift.drifting)
+    .bind(&drift.notes)
+    .bind(drift.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save drift check: {}", e))?;
+    Ok(())
+}
+
+/// Latest drift checks, newest first
+pub async fn load_drift(
+    pool: &SqlitePool,
+    story_id: &str,
+    limit: i64,
+) -> Result<Vec<ArcDrift>, String> {
+    sqlx::query_as(
+        "SELECT id, story_id, entry_id, act, alignment, drifting, notes, created_at \
+         FROM arc_drift_checks WHERE story_id = ? ORDER BY created_at DESC LIMIT ?",
+    )
+    .bind(story_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load drift checks: {}", e))
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+/// One act of a story's outline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAct {
+    pub title: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub goals: Vec<String>,
+    /// Chapters the act should run for. An act without a length lasts
+    /// until the act is changed by hand.
+    #[serde(default)]
+    pub chapters: Option<i64>,
+    /// How firmly guidance steers while this act runs, 0 (loose) to 1 (firm)
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    0.5
+}
+
+/// A twist the story should build toward
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedTwist {
+    pub description: String,
+    /// Index of the act the twist lands in
+    pub act: usize,
+    /// Revealed twists are no longer foreshadowed
+    #[serde(default)]
+    pub revealed: bool,
+}
+
+/// A story's outline, injected into the context as hidden guidance.
+/// Stored in `story_arcs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcPlan {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub acts: Vec<PlannedAct>,
+    #[serde(default)]
+    pub twists: Vec<PlannedTwist>,
+    #[serde(default)]
+    pub ending: Option<String>,
+    /// Act picked by hand; otherwise worked out from act lengths
+    #[serde(default)]
+    pub current_act: Option<usize>,
+    /// Check for drift every this many responses, 0 to only check on demand
+    #[serde(default = "default_check_every")]
+    pub check_every: u32,
+    /// Alignment below which a check warns that the story has drifted
+    #[serde(default = "default_drift_threshold")]
+    pub drift_threshold: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_check_every() -> u32 {
+    5
+}
+
+fn default_drift_threshold() -> f64 {
+    0.4
+}
+
+/// Where a story stands against its plan
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcStatus {
+    pub plan: ArcPlan,
+    pub act: usize,
+    /// Chapters on the current branch
+    pub chapters: i64,
+    /// The guidance block as injected into the context
+    pub guidance: String,
+    pub last_check: Option<ArcDrift>,
+}
+
+/// Result of one drift check. Emitted on `arc-drift` when it drifted.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcDrift {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    pub act: i64,
+    pub alignment: f64,
+    pub drifting: bool,
+    pub notes: String,
+    pub created_at: i64,
+}
@@ -0,0 +1,187 @@
+use tauri::State;
+use uuid::Uuid;
+
+use super::store::{self, active_region, ensure_weather, load_regions, story_day};
+use super::types::{EnvironmentRegion, RegionInput, WeatherDay, CLIMATES};
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::db::{now_millis, DbState};
+
+/// Get a story's weather regions
+#[tauri::command]
+pub async fn get_environment_regions(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<EnvironmentRegion>, String> {
+    load_regions(db.pool(), &story_id)
+        .await
+        .map_err(|e| format!("Failed to load regions: {}", e))
+}
+
+/// Create or update a weather region, returning its id
+#[tauri::command]
+pub async fn save_environment_region(
+    db: State<'_, DbState>,
+    story_id: String,
+    region: RegionInput,
+) -> Result<String, String> {
+    let name = region.name.trim();
+    if name.is_empty() {
+        return Err("Region name cannot be empty".to_string());
+    }
+    if !CLIMATES.contains(&region.climate.as_str()) {
+        return Err(format!("Unknown climate: {}", region.climate));
+    }
+    let location_name = region
+        .location_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    if region.is_default {
+        sqlx::query("UPDATE environment_regions SET is_default = 0 WHERE story_id = ?")
+            .bind(&story_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update regions: {}", e))?;
+    }
+
+    let id = match region.id {
+        Some(id) => {
+            let (old_climate, old_seed): (String, i64) =
+                sqlx::query_as("SELECT climate, seed FROM environment_regions WHERE id = ?")
+                    .bind(&id)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to load region: {}", e))?
+                    .ok_or_else(|| format!("Region not found: {}", id))?;
+            sqlx::query(
+                "UPDATE environment_regions SET name = ?, climate = ?, location_name = ?, is_default = ?, \
+                 seed = COALESCE(?, seed) WHERE id = ? AND story_id = ?",
+            )
+            .bind(name)
+            .bind(&region.climate)
+            .bind(location_name)
+            .bind(region.is_default)
+            .bind(region.seed)
+            .bind(&id)
+            .bind(&story_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update region: {}", e))?;
+            // Climate or seed changes invalidate generated weather; manual entries stay
+            let reseeded = region.seed.is_some_and(|seed| seed != old_seed);
+            if reseeded || old_climate != region.climate {
+                sqlx::query(
+                    "DELETE FROM environment_weather WHERE region_id = ? AND source = 'generated'",
+                )
+                .bind(&id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to reset weather: {}", e))?;
+            }
+            id
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO environment_regions (id, story_id, name, climate, location_name, is_default, seed, created_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&id)
+            .bind(&story_id)
+            .bind(name)
+            .bind(&region.climate)
+            .bind(location_name)
+            .bind(region.is_default)
+            .bind(region.seed.unwrap_or_else(|| rand::random::<u32>() as i64))
+            .bind(now_millis())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to create region: {}", e))?;
+            id
+        }
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit region: {}", e))?;
+    Ok(id)
+}
+
+/// Delete a weather region and its history
+#[tauri::command]
+pub async fn delete_environment_region(
+    db: State<'_, DbState>,
+    region_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM environment_regions WHERE id = ?")
+        .bind(&region_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete region: {}", e))?;
+    Ok(())
+}
+
+/// Get the weather at the current story time.
+///
+/// Uses the given region, else the region tied to the current location, else
+/// the default region. Returns `None` when the story has no regions.
+#[tauri::command]
+pub async fn get_current_weather(
+    db: State<'_, DbState>,
+    story_id: String,
+    region_id: Option<String>,
+) -> Result<Option<WeatherDay>, String> {
+    let region = match region_id {
+        Some(id) => Some(store::load_region(db.pool(), &id).await?),
+        None => active_region(db.pool(), &story_id)
+            .await
+            .map_err(|e| format!("Failed to load regions: {}", e))?,
+    };
+    let Some(region) = region else {
+        return Ok(None);
+    };
+
+    let calendar = load_calendar(db.pool(), &story_id).await?;
+    let time = load_time_tracker(db.pool(), &story_id).await?;
+    let day = story_day(&calendar, &time);
+    ensure_weather(db.pool(), &calendar, &region, day)
+        .await
+        .map(Some)
+}
+
+/// Override a day's weather by hand; later generated days continue from it
+#[tauri::command]
+pub async fn set_weather(db: State<'_, DbState>, weather: WeatherDay) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO environment_weather (region_id, day, season, condition, temperature, wind, source) \
+         VALUES (?, ?, ?, ?, ?, ?, 'manual') \
+         ON CONFLICT(region_id, day) DO UPDATE SET season = excluded.season, condition = excluded.condition, \
+         temperature = excluded.temperature, wind = excluded.wind, source = 'manual'",
+    )
+    .bind(&weather.region_id)
+    .bind(weather.day)
+    .bind(&weather.season)
+    .bind(&weather.condition)
+    .bind(weather.temperature)
+    .bind(&weather.wind)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save weather: {}", e))?;
+
+    // Regenerate following days so they continue from the override
+    sqlx::query(
+        "DELETE FROM environment_weather WHERE region_id = ? AND day > ? AND source = 'generated'",
+    )
+    .bind(&weather.region_id)
+    .bind(weather.day)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to reset weather: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::types::{climate_profile, EnvironmentRegion, WeatherDay};
+
+const SEASONS: [&str; 4] = ["winter", "spring", "summer", "autumn"];
+
+/// Conditions that count as precipitation for day-to-day persistence
+const WET_CONDITIONS: &[&str] = &[
+    "drizzle",
+    "rain",
+    "heavy rain",
+    "thunderstorm",
+    "sleet",
+    "light snow",
+    "snow",
+    "blizzard",
+];
+
+/// Generate one day of weather.
+///
+/// Output depends only on the region's seed, the day, and the previous day,
+/// so regenerating a day always gives the same result. The previous day
+/// smooths temperature and makes wet spells persist, which keeps the weather
+/// from swinging wildly within a few days.
+///
+/// `year_fraction` is how far through the calendar year the day falls (0..1),
+/// with 0 at midwinter.
+pub fn generate_day(
+    region: &EnvironmentRegion,
+    day: i64,
+    year_fraction: f64,
+    previous: Option<&WeatherDay>,
+) -> WeatherDay {
+    let mut rng = StdRng::seed_from_u64(
+        (region.seed as u64) ^ (day as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    let profile = climate_profile(&region.climate);
+
+    // Interpolate between season midpoints so seasons blend instead of stepping
+    let position = year_fraction.rem_euclid(1.0) * 4.0;
+    let index = position.floor() as usize % 4;
+    let blend = position - position.floor();
+    let mean = lerp(
+        profile.mean_temperature[index],
+        profile.mean_temperature[(index + 1) % 4],
+        blend,
+    );
+    let precipitation = lerp(
+        profile.precipitation_chance[index],
+        profile.precipitation_chance[(index + 1) % 4],
+        blend,
+    );
+    let season = SEASONS[position.round() as usize % 4];
+
+    let noise = (rng.gen::<f64>() + rng.gen::<f64>() + rng.gen::<f64>() - 1.5) * 2.0;
+    let raw = mean + noise * profile.daily_spread;
+    let temperature = match previous {
+        Some(prev) => prev.temperature * 0.6 + raw * 0.4,
+        None => raw,
+    };
+    let temperature = (temperature * 10.0).round() / 10.0;
+
+    let was_wet = previous.is_some_and(|p| WET_CONDITIONS.contains(&p.condition.as_str()));
+    let wet_chance = if was_wet {
+        precipitation * 0.5 + 0.35
+    } else {
+        precipitation * 0.8
+    };
+    let wet = rng.gen::<f64>() < wet_chance;
+    let heavy = rng.gen::<f64>() < 0.25;
+    let gusty = rng.gen::<f64>();
+
+    let condition = if wet {
+        match (temperature, heavy) {
+            (t, true) if t <= 0.0 && gusty > 0.6 => "blizzard",
+            (t, true) if t <= 0.0 => "snow",
+            (t, false) if t <= 0.0 => "light snow",
+            (t, _) if t < 3.0 => "sleet",
+            (t, true) if t > 15.0 => "thunderstorm",
+            (_, true) => "heavy rain",
+            (_, false) if gusty < 0.4 => "drizzle",
+            _ => "rain",
+        }
+    } else {
+        let sky = rng.gen::<f64>();
+        match sky {
+            s if region.climate == "desert" && s > 0.95 => "dust storm",
+            s if s < 0.35 => "clear",
+            s if s < 0.65 => "partly cloudy",
+            s if s < 0.85 => "overcast",
+            _ if (-5.0..20.0).contains(&temperature) => "fog",
+            _ => "clear",
+        }
+    };
+
+    let wind_level = match condition {
+        "blizzard" | "thunderstorm" | "dust storm" => 3 + (gusty > 0.7) as usize,
+        "fog" => 0,
+        _ => (gusty * 3.5) as usize,
+    };
+    let wind = ["calm", "light breeze", "breezy", "strong winds", "gale"][wind_level.min(4)];
+
+    WeatherDay {
+        region_id: region.id.clone(),
+        day,
+        season: season.to_string(),
+        condition: condition.to_string(),
+        temperature,
+        wind: wind.to_string(),
+        source: "generated".to_string(),
+    }
+}
+
+/// Prompt-ready description, e.g. "Weather in Northreach: light snow, -3°C, strong winds (winter)"
+pub fn describe(region: &EnvironmentRegion, weather: &WeatherDay) -> String {
+    format!(
+        "Weather in {}: {}, {:.0}°C, {} ({})",
+        region.name, weather.condition, weather.temperature, weather.wind, weather.season
+    )
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
@@ -0,0 +1,137 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::simulate::generate_day;
+use super::types::{EnvironmentRegion, WeatherDay};
+use crate::calendar::types::{CalendarDefinition, TimeTracker};
+
+/// Days generated in one go when catching up after a long time skip
+const MAX_CATCH_UP_DAYS: i64 = 30;
+
+fn region_from_row(r: &SqliteRow) -> EnvironmentRegion {
+    EnvironmentRegion {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        name: r.get("name"),
+        climate: r.get("climate"),
+        location_name: r.get("location_name"),
+        is_default: r.get::<i64, _>("is_default") != 0,
+        seed: r.get("seed"),
+        created_at: r.get("created_at"),
+    }
+}
+
+fn weather_from_row(r: &SqliteRow) -> WeatherDay {
+    WeatherDay {
+        region_id: r.get("region_id"),
+        day: r.get("day"),
+        season: r.get("season"),
+        condition: r.get("condition"),
+        temperature: r.get("temperature"),
+        wind: r.get("wind"),
+        source: r.get("source"),
+    }
+}
+
+pub async fn load_regions(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<EnvironmentRegion>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM environment_regions WHERE story_id = ? ORDER BY is_default DESC, name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(region_from_row).collect())
+}
+
+pub async fn load_region(pool: &SqlitePool, region_id: &str) -> Result<EnvironmentRegion, String> {
+    sqlx::query("SELECT * FROM environment_regions WHERE id = ?")
+        .bind(region_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load region: {}", e))?
+        .map(|r| region_from_row(&r))
+        .ok_or_else(|| format!("Region not found: {}", region_id))
+}
+
+/// The region tied to the current location, else the default region
+pub async fn active_region(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Option<EnvironmentRegion>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT r.* FROM environment_regions r WHERE r.story_id = ?1 ORDER BY \
+         CASE WHEN r.location_name IN \
+         (SELECT name FROM locations WHERE story_id = ?1 AND current = 1 AND deleted = 0) THEN 0 \
+         WHEN r.is_default = 1 THEN 1 ELSE 2 END, r.created_at LIMIT 1",
+    )
+    .bind(story_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| region_from_row(&r)))
+}
+
+/// Story day (days since the start) for a tracker position
+pub fn story_day(calendar: &CalendarDefinition, time: &TimeTracker) -> i64 {
+    calendar.minutes_since_start(time) / calendar.minutes_per_day()
+}
+
+/// Get the weather for a region on a day, generating and persisting it (and
+/// any days since the last stored one) if needed
+pub async fn ensure_weather(
+    pool: &SqlitePool,
+    calendar: &CalendarDefinition,
+    region: &EnvironmentRegion,
+    day: i64,
+) -> Result<WeatherDay, String> {
+    let latest = sqlx::query(
+        "SELECT * FROM environment_weather WHERE region_id = ? AND day <= ? ORDER BY day DESC LIMIT 1",
+    )
+    .bind(&region.id)
+    .bind(day)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load weather: {}", e))?
+    .map(|r| weather_from_row(&r));
+
+    let mut previous = match latest {
+        Some(weather) if weather.day == day => return Ok(weather),
+        Some(weather) if day - weather.day <= MAX_CATCH_UP_DAYS => Some(weather),
+        _ => None,
+    };
+    let start = previous
+        .as_ref()
+        .map_or((day - MAX_CATCH_UP_DAYS).max(0), |p| p.day + 1);
+
+    let days_per_year = calendar.days_per_year();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    for d in start..=day {
+        let fraction = d.rem_euclid(days_per_year) as f64 / days_per_year as f64;
+        let weather = generate_day(region, d, fraction, previous.as_ref());
+        sqlx::query(
+            "INSERT OR IGNORE INTO environment_weather \
+             (region_id, day, season, condition, temperature, wind, source) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&weather.region_id)
+        .bind(weather.day)
+        .bind(&weather.season)
+        .bind(&weather.condition)
+        .bind(weather.temperature)
+        .bind(&weather.wind)
+        .bind(&weather.source)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save weather: {}", e))?;
+        previous = Some(weather);
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit weather: {}", e))?;
+
+    previous.ok_or_else(|| "No weather generated".to_string())
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// A weather region with its own climate and seed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentRegion {
+    pub id: String,
+    pub story_id: String,
+    pub name: String,
+    pub climate: String,
+    pub location_name: Option<String>,
+    pub is_default: bool,
+    pub seed: i64,
+    pub created_at: i64,
+}
+
+/// Input for creating or updating a region
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub climate: String,
+    pub location_name: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+    /// Random when omitted on creation; kept when omitted on update
+    pub seed: Option<i64>,
+}
+
+/// Weather for one region on one story day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherDay {
+    pub region_id: String,
+    pub day: i64,
+    pub season: String,
+    pub condition: String,
+    /// Degrees Celsius
+    pub temperature: f64,
+    pub wind: String,
+    /// 'generated' | 'manual'
+    pub source: String,
+}
+
+/// Seasonal behaviour of a climate; arrays run winter, spring, summer, autumn
+pub struct ClimateProfile {
+    pub mean_temperature: [f64; 4],
+    pub daily_spread: f64,
+    pub precipitation_chance: [f64; 4],
+}
+
+pub const CLIMATES: &[&str] = &["temperate", "arctic", "desert", "tropical", "mediterranean"];
+
+pub fn climate_profile(climate: &str) -> ClimateProfile {
+    match climate {
+        "arctic" => ClimateProfile {
+            mean_temperature: [-25.0, -8.0, 5.0, -10.0],
+            daily_spread: 5.0,
+            precipitation_chance: [0.35, 0.3, 0.35, 0.4],
+        },
+        "desert" => ClimateProfile {
+            mean_temperature: [14.0, 26.0, 38.0, 27.0],
+            daily_spread: 4.0,
+            precipitation_chance: [0.08, 0.05, 0.03, 0.06],
+        },
+        "tropical" => ClimateProfile {
+            mean_temperature: [26.0, 28.0, 29.0, 28.0],
+            daily_spread: 2.0,
+            precipitation_chance: [0.35, 0.55, 0.7, 0.55],
+        },
+        "mediterranean" => ClimateProfile {
+            mean_temperature: [10.0, 17.0, 28.0, 19.0],
+            daily_spread: 3.0,
+            precipitation_chance: [0.4, 0.25, 0.05, 0.3],
+        },
+        _ => ClimateProfile {
+            mean_temperature: [2.0, 12.0, 23.0, 13.0],
+            daily_spread: 4.0,
+            precipitation_chance: [0.45, 0.45, 0.3, 0.45],
+        },
+    }
+}
@@ -0,0 +1,128 @@
+//! Templates shipped with the app, following the layouts of the built-in
+//! Markdown and text exporters, plus the HTML page the web reader serves
+
+use super::types::ExportTemplate;
+
+const MARKDOWN: &str = r#"# {{story.title}}
+
+{{#if story.description}}
+*{{story.description}}*
+
+{{/if}}
+{{#if story.genre}}
+**Genre:** {{story.genre}}
+
+{{/if}}
+---
+
+{{#each entries}}
+{{#if chapterStart}}
+{{#with (chapter this)}}
+## Chapter {{number}}{{#if title}}: {{title}}{{/if}}
+
+{{#if startTime}}
+*{{time startTime}}*
+
+{{/if}}
+{{/with}}
+{{/if}}
+{{#if (eq kind "user_action")}}
+> **{{speaker this}}:** {{content}}
+
+{{else if (eq kind "system")}}
+*[System: {{content}}]*
+
+{{else}}
+{{content}}
+
+{{/if}}
+{{/each}}
+---
+
+*Exported from Aventura on {{exportedAt}}*
+"#;
+
+const TEXT: &str = r#"{{story.title}}
+
+{{#if story.description}}
+{{story.description}}
+
+{{/if}}
+---
+
+{{#each entries}}
+{{#if chapterStart}}
+{{#with (chapter this)}}
+Chapter {{number}}{{#if title}}: {{title}}{{/if}}
+
+{{/with}}
+{{/if}}
+{{#if (eq kind "user_action")}}
+> {{content}}
+
+{{else if (eq kind "narration")}}
+{{content}}
+
+{{/if}}
+{{/each}}
+"#;
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width,initial-scale=1">
+<title>{{story.title}}</title>
+<style>
+body{max-width:42rem;margin:0 auto;padding:1.5rem 1rem;font:1.1rem/1.6 Georgia,serif;color:#222;background:#fbf8f1}
+h1{margin-bottom:.2rem}.meta{color:#888;font-style:italic}
+blockquote{margin:1.2rem 0;padding-left:1rem;border-left:3px solid #b88}
+.system{color:#888;font-style:italic}
+</style>
+</head>
+<body>
+<h1>{{story.title}}</h1>
+{{#if story.description}}
+<p class="meta">{{story.description}}</p>
+{{/if}}
+{{#each entries}}
+{{#if chapterStart}}
+{{#with (chapter this)}}
+<h2>Chapter {{number}}{{#if title}}: {{title}}{{/if}}</h2>
+{{#if startTime}}
+<p class="meta">{{time startTime}}</p>
+{{/if}}
+{{/with}}
+{{/if}}
+{{#if (eq kind "user_action")}}
+<blockquote><strong>{{speaker this}}:</strong> {{{paragraphs content}}}</blockquote>
+{{else if (eq kind "system")}}
+<p class="system">[System: {{content}}]</p>
+{{else}}
+{{{paragraphs content}}}
+{{/if}}
+{{/each}}
+<p class="meta">Exported from Aventura on {{exportedAt}}</p>
+</body>
+</html>
+"#;
+
+fn builtin(id: &str, name: &str, format: &str, body: &str) -> ExportTemplate {
+    ExportTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        format: format.to_string(),
+        body: body.to_string(),
+        builtin: true,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+pub fn builtin_templates() -> Vec<ExportTemplate> {
+    vec![
+        builtin("builtin:markdown", "Markdown", "markdown", MARKDOWN),
+        builtin("builtin:text", "Plain text", "text", TEXT),
+        builtin("builtin:html", "Web page", "html", HTML),
+    ]
+}
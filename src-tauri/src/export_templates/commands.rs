@@ -0,0 +1,125 @@
+use tauri::{AppHandle, State};
+
+use super::render::render;
+use super::store;
+use super::types::{ExportData, ExportTemplate, ExportTemplateInput};
+use crate::attribution::footer::append;
+use crate::attribution::store::load_attribution;
+use crate::calendar::commands::load_calendar;
+use crate::db::DbState;
+use crate::redaction::redactor::Redactor;
+
+#[tauri::command]
+pub async fn get_export_templates(db: State<'_, DbState>) -> Result<Vec<ExportTemplate>, String> {
+    store::list_templates(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn save_export_template(
+    db: State<'_, DbState>,
+    input: ExportTemplateInput,
+) -> Result<ExportTemplate, String> {
+    store::save_template(db.pool(), input).await
+}
+
+#[tauri::command]
+pub async fn delete_export_template(
+    db: State<'_, DbState>,
+    template_id: String,
+) -> Result<(), String> {
+    store::delete_template(db.pool(), &template_id).await
+}
+
+/// Save a Handlebars file as a template. The format follows the extension
+/// (.html, .md, anything else is text).
+#[tauri::command]
+pub async fn import_export_template(
+    db: State<'_, DbState>,
+    path: String,
+    name: Option<String>,
+) -> Result<ExportTemplate, String> {
+    let body = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read template: {}", e))?;
+    let name = name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| {
+        std::path::Path::new(&path)
+            .file_stem()
+            .map_or("Imported template".to_string(), |s| {
+                s.to_string_lossy().to_string()
+            })
+    });
+    let input = ExportTemplateInput {
+        id: None,
+        name,
+        format: store::format_for_path(&path).to_string(),
+        body,
+    };
+    store::save_template(db.pool(), input).await
+}
+
+/// Mask what the story's redaction rules cover in the text a template sees
+fn redact_data(data: &mut ExportData, redactor: &Redactor) {
+    let mask = |text: &mut String| *text = redactor.redact(text);
+    mask(&mut data.story.title);
+    data.story.description.iter_mut().for_each(mask);
+    for entry in &mut data.entries {
+        mask(&mut entry.content);
+    }
+    for chapter in &mut data.chapters {
+        chapter.title.iter_mut().for_each(mask);
+        mask(&mut chapter.summary);
+        chapter.keywords.iter_mut().for_each(mask);
+        chapter.characters.iter_mut().for_each(mask);
+    }
+    for character in &mut data.characters {
+        mask(&mut character.name);
+        character.description.iter_mut().for_each(mask);
+    }
+    for location in &mut data.locations {
+        mask(&mut location.name);
+        location.description.iter_mut().for_each(mask);
+    }
+}
+
+/// Render the story's current branch through a template into `path`,
+/// redacted by the story's rules when `redact` is set and with an
+/// attribution footer when `attribution` is set
+#[tauri::command]
+pub async fn export_with_template(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    template_id: String,
+    path: String,
+    redact: Option<bool>,
+    attribution: Option<bool>,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let template = store::load_template(pool, &template_id).await?;
+    let mut data = store::load_export_data(pool, &story_id, &template.format).await?;
+    let calendar = load_calendar(pool, &story_id).await?;
+    let player: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM characters WHERE story_id = ? AND relationship = 'self' LIMIT 1",
+    )
+    .bind(&story_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load player character: {}", e))?;
+    let player = if redact.unwrap_or(false) {
+        let redactor = Redactor::load(pool, &story_id).await?;
+        redact_data(&mut data, &redactor);
+        player.map(|name| redactor.redact(&name))
+    } else {
+        player
+    };
+
+    let mut output = render(&template.body, &data, calendar, player)?;
+    if attribution.unwrap_or(false) {
+        let app_version = app.package_info().version.to_string();
+        let attribution = load_attribution(pool, &app_version, &story_id).await?;
+        append(&mut output, &attribution, &template.format);
+    }
+    tokio::fs::write(&path, output)
+        .await
+        .map_err(|e| format!("Failed to write export: {}", e))
+}
@@ -0,0 +1,5 @@
+pub mod builtin;
+pub mod commands;
+pub mod render;
+pub mod store;
+pub mod types;
@@ -0,0 +1,148 @@
+//! Handlebars rendering for export templates. Besides the data in
+//! `ExportData`, templates get helpers:
+//! - `entries` — all entries, or a chapter's: `{{#each (entries this)}}`
+//! - `chapter` — the chapter an entry belongs to: `{{#with (chapter this)}}`
+//! - `time` — a story time in the story's calendar: `{{time timeStart}}`
+//! - `speaker` — who an entry is from: the player, "Narrator" or "System"
+//! - `paragraphs` — text as `<p>` paragraphs in HTML (use `{{{ }}}`), as is
+//!   elsewhere
+
+use std::sync::Arc;
+
+use handlebars::{
+    no_escape, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson,
+};
+use serde_json::Value;
+
+use super::types::ExportData;
+use crate::calendar::types::{CalendarDefinition, TimeTracker};
+use crate::reader::html::paragraphs;
+
+/// A helper computing a value from its parameters, usable inline and as a
+/// subexpression
+struct ValueHelper<F>(F);
+
+impl<F> HelperDef for ValueHelper<F>
+where
+    F: Fn(&[&Value]) -> Value + Send + Sync,
+{
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let params: Vec<&Value> = h.params().iter().map(|p| p.value()).collect();
+        Ok(ScopedJson::Derived((self.0)(&params)))
+    }
+}
+
+fn helper<F>(f: F) -> Box<ValueHelper<F>>
+where
+    F: Fn(&[&Value]) -> Value + Send + Sync,
+{
+    Box::new(ValueHelper(f))
+}
+
+/// The chapter number a parameter stands for: a chapter, an entry or a number
+fn chapter_number(param: Option<&&Value>) -> Option<i64> {
+    let value = param?;
+    value
+        .as_i64()
+        .or_else(|| value.get("chapter").and_then(Value::as_i64))
+        .or_else(|| value.get("number").and_then(Value::as_i64))
+}
+
+/// Fail on a template that doesn't parse, so it isn't saved broken
+pub fn compile_check(body: &str) -> Result<(), String> {
+    Handlebars::new()
+        .register_template_string("template", body)
+        .map_err(|e| format!("Invalid template: {}", e))
+}
+
+pub fn render(
+    body: &str,
+    data: &ExportData,
+    calendar: CalendarDefinition,
+    player: Option<String>,
+) -> Result<String, String> {
+    let entries = Arc::new(serde_json::to_value(&data.entries).map_err(|e| e.to_string())?);
+    let chapters = Arc::new(serde_json::to_value(&data.chapters).map_err(|e| e.to_string())?);
+    let html = data.format == "html";
+
+    let mut handlebars = Handlebars::new();
+    if !html {
+        handlebars.register_escape_fn(no_escape);
+    }
+    handlebars.register_helper(
+        "entries",
+        helper(move |params| match chapter_number(params.first()) {
+            None => (*entries).clone(),
+            Some(number) => Value::Array(
+                entries
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|e| e.get("chapter").and_then(Value::as_i64) == Some(number))
+                    .cloned()
+                    .collect(),
+            ),
+        }),
+    );
+    handlebars.register_helper(
+        "chapter",
+        helper(move |params| {
+            let number = chapter_number(params.first());
+            chapters
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|c| number.is_some() && c.get("number").and_then(Value::as_i64) == number)
+                .cloned()
+                .unwrap_or(Value::Null)
+        }),
+    );
+    handlebars.register_helper(
+        "time",
+        helper(move |params| {
+            params
+                .first()
+                .filter(|t| t.is_object())
+                .and_then(|t| serde_json::from_value::<TimeTracker>((*t).clone()).ok())
+                .map_or(Value::Null, |t| Value::String(calendar.date(&t).formatted))
+        }),
+    );
+    handlebars.register_helper(
+        "speaker",
+        helper(move |params| {
+            let kind = params
+                .first()
+                .and_then(|e| e.get("kind"))
+                .and_then(Value::as_str);
+            Value::String(
+                match kind {
+                    Some("user_action") => player.as_deref().unwrap_or("You"),
+                    Some("system") => "System",
+                    _ => "Narrator",
+                }
+                .to_string(),
+            )
+        }),
+    );
+    handlebars.register_helper(
+        "paragraphs",
+        helper(move |params| {
+            let text = params.first().and_then(|t| t.as_str()).unwrap_or_default();
+            Value::String(if html {
+                paragraphs(text)
+            } else {
+                text.to_string()
+            })
+        }),
+    );
+
+    handlebars
+        .render_template(body, data)
+        .map_err(|e| format!("Failed to render template: {}", e))
+}
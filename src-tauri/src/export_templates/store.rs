@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::builtin::builtin_templates;
+use super::render::compile_check;
+use super::types::{
+    ExportChapter, ExportCharacter, ExportData, ExportEntry, ExportLocation, ExportStory,
+    ExportTemplate, ExportTemplateInput, FORMATS,
+};
+use crate::context::branch::{lineage, segments, world_view};
+use crate::db::now_millis;
+
+/// Built-in templates first, then the user's by name
+pub async fn list_templates(pool: &SqlitePool) -> Result<Vec<ExportTemplate>, String> {
+    let mut templates = builtin_templates();
+    let saved: Vec<ExportTemplate> = sqlx::query_as(
+        "SELECT id, name, format, body, created_at, updated_at FROM export_templates \
+         ORDER BY name COLLATE NOCASE",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load export templates: {}", e))?;
+    templates.extend(saved);
+    Ok(templates)
+}
+
+pub async fn load_template(pool: &SqlitePool, template_id: &str) -> Result<ExportTemplate, String> {
+    if let Some(builtin) = builtin_templates()
+        .into_iter()
+        .find(|t| t.id == template_id)
+    {
+        return Ok(builtin);
+    }
+    sqlx::query_as(
+        "SELECT id, name, format, body, created_at, updated_at FROM export_templates WHERE id = ?",
+    )
+    .bind(template_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load export template: {}", e))?
+    .ok_or_else(|| format!("Export template not found: {}", template_id))
+}
+
+/// Create or replace a template. It has to compile; built-ins can't be
+/// overwritten.
+pub async fn save_template(
+    pool: &SqlitePool,
+    input: ExportTemplateInput,
+) -> Result<ExportTemplate, String> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err("The template needs a name".to_string());
+    }
+    if !FORMATS.contains(&input.format.as_str()) {
+        return Err(format!("Unknown export format: {}", input.format));
+    }
+    compile_check(&input.body)?;
+
+    let now = now_millis();
+    let existing = match &input.id {
+        Some(id) => Some(load_template(pool, id).await?),
+        None => None,
+    };
+    if existing.as_ref().is_some_and(|t| t.builtin) {
+        return Err("Built-in templates can't be changed; save a copy instead".to_string());
+    }
+    let template = ExportTemplate {
+        id: input.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: name.to_string(),
+        format: input.format,
+        body: input.body,
+        builtin: false,
+        created_at: existing.map_or(now, |t| t.created_at),
+        updated_at: now,
+    };
+    sqlx::query(
+        "INSERT INTO export_templates (id, name, format, body, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, format = excluded.format, \
+         body = excluded.body, updated_at = excluded.updated_at",
+    )
+    .bind(&template.id)
+    .bind(&template.name)
+    .bind(&template.format)
+    .bind(&template.body)
+    .bind(template.created_at)
+    .bind(template.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save export template: {}", e))?;
+    Ok(template)
+}
+
+pub async fn delete_template(pool: &SqlitePool, template_id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM export_templates WHERE id = ?")
+        .bind(template_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete export template: {}", e))?;
+    Ok(())
+}
+
+/// Format of a template file, by its extension
+pub fn format_for_path(path: &str) -> &'static str {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".html") || lower.ends_with(".htm") {
+        "html"
+    } else if lower.ends_with(".md") || lower.ends_with(".markdown") {
+        "markdown"
+    } else {
+        "text"
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct EntryRow {
+    id: String,
+    #[sqlx(rename = "type")]
+    kind: String,
+    content: String,
+    translated_content: Option<String>,
+    original_input: Option<String>,
+    metadata: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChapterRow {
+    number: i64,
+    title: Option<String>,
+    summary: String,
+    start_entry_id: String,
+    end_entry_id: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    keywords: Option<String>,
+    characters: Option<String>,
+    branch_id: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct WorldRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    relationship: Option<String>,
+    current: Option<i64>,
+    visited: Option<i64>,
+    overrides_id: Option<String>,
+    deleted: i64,
+}
+
+fn parse_json(raw: Option<&str>) -> Option<Value> {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .filter(|v: &Value| !v.is_null())
+}
+
+fn parse_list(raw: Option<&str>) -> Vec<String> {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default()
+}
+
+/// Characters or locations on the current branch
+async fn world_rows(
+    pool: &SqlitePool,
+    story_id: &str,
+    table: &str,
+) -> Result<Vec<WorldRow>, String> {
+    let columns = if table == "characters" {
+        "relationship, NULL AS current, NULL AS visited"
+    } else {
+        "NULL AS relationship, current, visited"
+    };
+    let sql = format!(
+        "SELECT id, name, description, {}, overrides_id, deleted FROM {} \
+         WHERE story_id = ? AND branch_id IS ? ORDER BY rowid",
+        columns, table
+    );
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        let rows: Vec<WorldRow> = sqlx::query_as(&sql)
+            .bind(story_id)
+            .bind(&branch)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load {}: {}", table, e))?;
+        layers.push(rows);
+    }
+    Ok(view.resolve(
+        layers,
+        |r| r.overrides_id.clone().unwrap_or_else(|| r.id.clone()),
+        |r| r.deleted != 0,
+    ))
+}
+
+/// The story as it reads on the current branch, shaped for templates
+pub async fn load_export_data(
+    pool: &SqlitePool,
+    story_id: &str,
+    format: &str,
+) -> Result<ExportData, String> {
+    let story: ExportStory =
+        sqlx::query_as("SELECT id, title, description, genre FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load story: {}", e))?
+            .ok_or_else(|| format!("Story not found: {}", story_id))?;
+
+    let mut rows: Vec<EntryRow> = Vec::new();
+    for (branch_id, max_position) in segments(pool, story_id).await? {
+        let segment: Vec<EntryRow> = sqlx::query_as(
+            "SELECT id, type, content, translated_content, original_input, metadata \
+             FROM story_entries \
+             WHERE story_id = ? AND branch_id IS ? AND (? IS NULL OR position <= ?) \
+             ORDER BY position ASC",
+        )
+        .bind(story_id)
+        .bind(branch_id)
+        .bind(max_position)
+        .bind(max_position)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story entries: {}", e))?;
+        rows.extend(segment);
+    }
+    let mut entries: Vec<ExportEntry> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let metadata = parse_json(row.metadata.as_deref());
+            let time = |key: &str| {
+                metadata
+                    .as_ref()
+                    .and_then(|m| m.get(key))
+                    .filter(|t| !t.is_null())
+                    .cloned()
+            };
+            let content = match row.kind.as_str() {
+                "user_action" => row.original_input.unwrap_or(row.content),
+                "narration" => row.translated_content.unwrap_or(row.content),
+                _ => row.content,
+            };
+            ExportEntry {
+                time_start: time("timeStart"),
+                time_end: time("timeEnd"),
+                id: row.id,
+                kind: row.kind,
+                content,
+                index,
+                chapter: None,
+                chapter_start: false,
+            }
+        })
+        .collect();
+
+    let branches: Vec<String> = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    let chapter_rows: Vec<ChapterRow> = sqlx::query_as(
+        "SELECT number, title, summary, start_entry_id, end_entry_id, start_time, end_time, \
+         keywords, characters, branch_id FROM chapters WHERE story_id = ? ORDER BY number",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    let index: HashMap<String, usize> = entries.iter().map(|e| (e.id.clone(), e.index)).collect();
+    let mut chapters = Vec::new();
+    for row in chapter_rows {
+        if row
+            .branch_id
+            .as_ref()
+            .is_some_and(|b| !branches.contains(b))
+        {
+            continue;
+        }
+        let (Some(&start), Some(&end)) =
+            (index.get(&row.start_entry_id), index.get(&row.end_entry_id))
+        else {
+            continue;
+        };
+        for entry in entries.iter_mut().take(end + 1).skip(start) {
+            entry.chapter = Some(row.number);
+        }
+        entries[start].chapter_start = true;
+        chapters.push(ExportChapter {
+            number: row.number,
+            title: row.title,
+            summary: row.summary,
+            entry_count: end.saturating_sub(start) + 1,
+            start_time: parse_json(row.start_time.as_deref()),
+            end_time: parse_json(row.end_time.as_deref()),
+            keywords: parse_list(row.keywords.as_deref()),
+            characters: parse_list(row.characters.as_deref()),
+        });
+    }
+
+    let characters = world_rows(pool, story_id, "characters")
+        .await?
+        .into_iter()
+        .map(|r| ExportCharacter {
+            name: r.name,
+            relationship: r.relationship,
+            description: r.description,
+        })
+        .collect();
+    let locations = world_rows(pool, story_id, "locations")
+        .await?
+        .into_iter()
+        .map(|r| ExportLocation {
+            name: r.name,
+            description: r.description,
+            current: r.current.unwrap_or(0) != 0,
+            visited: r.visited.unwrap_or(0) != 0,
+        })
+        .collect();
+
+    Ok(ExportData {
+        story,
+        format: format.to_string(),
+        exported_at: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        entries,
+        chapters,
+        characters,
+        locations,
+    })
+}
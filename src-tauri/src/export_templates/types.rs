@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Formats a template can produce; HTML output escapes what it inserts
+pub const FORMATS: &[&str] = &["text", "markdown", "html"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplate {
+    pub id: String,
+    pub name: String,
+    pub format: String,
+    pub body: String,
+    /// Shipped with the app; can be copied but not edited
+    #[sqlx(default)]
+    pub builtin: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A template to create (no `id`) or replace
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplateInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub format: String,
+    pub body: String,
+}
+
+/// What a template renders: the current branch of a story
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportData {
+    pub story: ExportStory,
+    pub format: String,
+    /// Local date of the export, e.g. 2026-10-17
+    pub exported_at: String,
+    pub entries: Vec<ExportEntry>,
+    pub chapters: Vec<ExportChapter>,
+    pub characters: Vec<ExportCharacter>,
+    pub locations: Vec<ExportLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportStory {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub genre: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportEntry {
+    pub id: String,
+    /// `user_action`, `narration` or `system`
+    pub kind: String,
+    /// What the reader saw: the translation of narration, the original
+    /// wording of actions
+    pub content: String,
+    pub index: usize,
+    /// Number of the chapter holding the entry
+    pub chapter: Option<i64>,
+    /// The entry opens its chapter
+    pub chapter_start: bool,
+    pub time_start: Option<Value>,
+    pub time_end: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportChapter {
+    pub number: i64,
+    pub title: Option<String>,
+    pub summary: String,
+    pub entry_count: usize,
+    pub start_time: Option<Value>,
+    pub end_time: Option<Value>,
+    pub keywords: Vec<String>,
+    pub characters: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCharacter {
+    pub name: String,
+    pub relationship: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLocation {
+    pub name: String,
+    pub description: Option<String>,
+    pub current: bool,
+    pub visited: bool,
+}
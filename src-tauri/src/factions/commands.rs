@@ -0,0 +1,153 @@
+use serde_json::json;
+use tauri::State;
+
+use super::store::{self, load_factions, load_history};
+use super::types::{Faction, NewFaction, ReputationChange};
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+/// Get a story's factions with members and the player's standing
+#[tauri::command]
+pub async fn get_factions(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<Faction>, String> {
+    load_factions(db.pool(), &story_id).await
+}
+
+/// Create a faction, returning its id
+#[tauri::command]
+pub async fn create_faction(
+    db: State<'_, DbState>,
+    story_id: String,
+    faction: NewFaction,
+) -> Result<String, String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let id = store::create_faction(&mut tx, &story_id, &faction, "manual").await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit faction: {}", e))?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_faction(
+    db: State<'_, DbState>,
+    faction_id: String,
+    name: String,
+    description: Option<String>,
+) -> Result<(), String> {
+    store::update_faction(db.pool(), &faction_id, &name, description.as_deref()).await
+}
+
+/// Delete a faction with its members and reputation history
+#[tauri::command]
+pub async fn delete_faction(db: State<'_, DbState>, faction_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM factions WHERE id = ?")
+        .bind(&faction_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete faction: {}", e))?;
+    Ok(())
+}
+
+/// Add a character to a faction, or change their role in it
+#[tauri::command]
+pub async fn set_faction_member(
+    db: State<'_, DbState>,
+    faction_id: String,
+    character_id: String,
+    role: Option<String>,
+) -> Result<(), String> {
+    let mut conn = db
+        .pool()
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+    store::set_member(&mut conn, &faction_id, &character_id, role.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn remove_faction_member(
+    db: State<'_, DbState>,
+    faction_id: String,
+    character_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM faction_members WHERE faction_id = ? AND character_id = ?")
+        .bind(&faction_id)
+        .bind(&character_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to remove faction member: {}", e))?;
+    Ok(())
+}
+
+/// Change the player's reputation with a faction by hand. Returns `None`
+/// when the reputation is already at the limit.
+#[tauri::command]
+pub async fn adjust_reputation(
+    db: State<'_, DbState>,
+    faction_id: String,
+    delta: i64,
+    reason: Option<String>,
+) -> Result<Option<ReputationChange>, String> {
+    let mut conn = db
+        .pool()
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+    store::change_reputation(
+        &mut conn,
+        &faction_id,
+        None,
+        delta,
+        reason.as_deref(),
+        "manual",
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_reputation_history(
+    db: State<'_, DbState>,
+    faction_id: String,
+) -> Result<Vec<ReputationChange>, String> {
+    load_history(db.pool(), &faction_id).await
+}
+
+/// Undo one reputation change
+#[tauri::command]
+pub async fn delete_reputation_change(
+    db: State<'_, DbState>,
+    change_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM faction_reputation WHERE id = ?")
+        .bind(&change_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete reputation change: {}", e))?;
+    Ok(())
+}
+
+/// Queue background extraction of faction changes from an entry
+#[tauri::command]
+pub async fn queue_faction_update(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::FactionExtraction,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
@@ -0,0 +1,251 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+
+use super::store::{change_reputation, create_faction, find_faction, load_factions, set_member};
+use super::types::NewFaction;
+use crate::context::branch::characters;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You track factions in an interactive story: the groups the player \
+deals with and how each regards the player. Given the known factions, the known characters and \
+the latest passage, report how the passage changes the player's reputation with each faction \
+(from -25 for a grave offence to 25 for a great service), any new faction that clearly matters \
+to the story, and any character the passage shows belonging to a faction. Do not guess: only \
+report what the passage shows. Respond with JSON only, in this shape: \
+{\"changes\": [{\"faction\": \"<name>\", \"delta\": <integer>, \"reason\": \"<short explanation>\"}], \
+\"newFactions\": [{\"name\": \"<name>\", \"description\": \"<one sentence>\"}], \
+\"memberships\": [{\"character\": \"<name>\", \"faction\": \"<name>\", \"role\": \"<role or null>\"}]}. \
+Use empty lists when nothing changed.";
+
+/// Largest change one passage can make to a reputation
+const MAX_DELTA: i64 = 25;
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "faction_extraction",
+        schema: json!({
+            "type": "object",
+            "required": ["changes"],
+            "properties": {
+                "changes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["faction", "delta"],
+                        "properties": {
+                            "faction": { "type": "string" },
+                            "delta": { "type": "integer" },
+                            "reason": { "type": ["string", "null"] }
+                        }
+                    }
+                },
+                "newFactions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": { "type": "string" },
+                            "description": { "type": ["string", "null"] }
+                        }
+                    }
+                },
+                "memberships": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["character", "faction"],
+                        "properties": {
+                            "character": { "type": "string" },
+                            "faction": { "type": "string" },
+                            "role": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ExtractionReply {
+    changes: Vec<ExtractedChange>,
+    new_factions: Vec<ExtractedFaction>,
+    memberships: Vec<ExtractedMembership>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedChange {
+    faction: String,
+    delta: i64,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedFaction {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedMembership {
+    character: String,
+    faction: String,
+    #[serde(default)]
+    role: Option<String>,
+}
+
+/// Background job: ask the classification model how an entry changes the
+/// player's standing with factions, and apply it. New factions are created
+/// before reputation and membership changes, so both may refer to them.
+pub async fn run_extraction_job(
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Faction extraction job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let factions = load_factions(pool, &story_id).await?;
+    // The current branch's copies, so memberships attach to the characters it shows
+    let mut characters: Vec<(String, String)> = characters(pool, &story_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect();
+    characters.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let listing = if factions.is_empty() {
+        "(none yet)".to_string()
+    } else {
+        factions
+            .iter()
+            .map(|f| match &f.description {
+                Some(description) => format!(
+                    "- {} ({}, {:+}): {}",
+                    f.name, f.standing, f.reputation, description
+                ),
+                None => format!("- {} ({}, {:+})", f.name, f.standing, f.reputation),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let names = characters
+        .iter()
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let prompt = format!(
+        "Known factions:\n{}\n\nKnown characters: {}\n\nPassage:\n{}",
+        listing, names, content
+    );
+
+    let llm = config::resolve_service(pool, "factionExtraction", "classification").await?;
+    let reply: ExtractionReply = complete_structured(
+        pool,
+        "factionExtraction",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut created = Vec::new();
+    for faction in reply.new_factions {
+        if faction.name.trim().is_empty()
+            || find_faction(&mut tx, &story_id, &faction.name)
+                .await?
+                .is_some()
+        {
+            continue;
+        }
+        let new = NewFaction {
+            name: faction.name,
+            description: faction.description,
+            reputation: 0,
+        };
+        create_faction(&mut tx, &story_id, &new, "extraction").await?;
+        created.push(new.name.trim().to_string());
+    }
+
+    let mut changes = Vec::new();
+    for change in reply.changes {
+        // Drop factions the model invented without proposing them
+        let Some(faction_id) = find_faction(&mut tx, &story_id, &change.faction).await? else {
+            continue;
+        };
+        let delta = change.delta.clamp(-MAX_DELTA, MAX_DELTA);
+        if let Some(change) = change_reputation(
+            &mut tx,
+            &faction_id,
+            Some(entry_id),
+            delta,
+            change.reason.as_deref(),
+            "extraction",
+        )
+        .await?
+        {
+            changes.push(change);
+        }
+    }
+
+    let mut joined = 0;
+    for membership in reply.memberships {
+        let character = characters
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(membership.character.trim()));
+        let Some((character_id, _)) = character else {
+            continue;
+        };
+        let Some(faction_id) = find_faction(&mut tx, &story_id, &membership.faction).await? else {
+            continue;
+        };
+        let role = membership.role.filter(|r| r != "null");
+        // A mention without a role shouldn't wipe the one already recorded
+        let known = factions
+            .iter()
+            .filter(|f| f.id == faction_id)
+            .any(|f| f.members.iter().any(|m| &m.character_id == character_id));
+        if known && role.is_none() {
+            continue;
+        }
+        set_member(&mut tx, &faction_id, character_id, role.as_deref()).await?;
+        joined += 1;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit faction changes: {}", e))?;
+    Ok(json!({
+        "entryId": entry_id,
+        "created": created,
+        "changes": changes,
+        "memberships": joined,
+    }))
+}
@@ -0,0 +1,290 @@
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::types::{
+    standing, Faction, FactionMember, NewFaction, ReputationChange, REPUTATION_RANGE,
+};
+use crate::db::now_millis;
+
+/// Load a story's factions with their members and the player's reputation
+pub async fn load_factions(pool: &SqlitePool, story_id: &str) -> Result<Vec<Faction>, String> {
+    let rows = sqlx::query(
+        "SELECT id, story_id, name, description, created_at, updated_at FROM factions \
+         WHERE story_id = ? ORDER BY name COLLATE NOCASE",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load factions: {}", e))?;
+
+    // Newest first, so the first reason seen per faction is the latest one
+    let changes: Vec<(String, i64, Option<String>)> = sqlx::query_as(
+        "SELECT r.faction_id, r.delta, r.reason FROM faction_reputation r \
+         JOIN factions f ON f.id = r.faction_id WHERE f.story_id = ? \
+         ORDER BY r.created_at DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load reputation: {}", e))?;
+    let mut reputation: HashMap<String, (i64, Option<String>)> = HashMap::new();
+    for (faction_id, delta, reason) in changes {
+        let (total, last_reason) = reputation.entry(faction_id).or_default();
+        *total += delta;
+        if last_reason.is_none() {
+            *last_reason = reason.filter(|r| !r.trim().is_empty());
+        }
+    }
+
+    let member_rows = sqlx::query(
+        "SELECT m.faction_id, m.character_id, m.role, c.name, c.relationship \
+         FROM faction_members m JOIN factions f ON f.id = m.faction_id \
+         JOIN characters c ON c.id = m.character_id \
+         WHERE f.story_id = ? AND c.deleted = 0 ORDER BY c.name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load faction members: {}", e))?;
+
+    let mut factions: Vec<Faction> = rows
+        .iter()
+        .map(|r| {
+            let id: String = r.get("id");
+            let (total, last_reason) = reputation.remove(&id).unwrap_or_default();
+            Faction {
+                id,
+                story_id: r.get("story_id"),
+                name: r.get("name"),
+                description: r.get("description"),
+                reputation: total,
+                standing: standing(total).to_string(),
+                last_reason,
+                members: Vec::new(),
+                created_at: r.get("created_at"),
+                updated_at: r.get("updated_at"),
+            }
+        })
+        .collect();
+    for r in &member_rows {
+        let faction_id: String = r.get("faction_id");
+        if let Some(faction) = factions.iter_mut().find(|f| f.id == faction_id) {
+            let relationship: Option<String> = r.get("relationship");
+            faction.members.push(FactionMember {
+                character_id: r.get("character_id"),
+                name: r.get("name"),
+                role: r.get("role"),
+                is_player: relationship.as_deref() == Some("self"),
+            });
+        }
+    }
+    Ok(factions)
+}
+
+pub async fn has_factions(pool: &SqlitePool, story_id: &str) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM factions WHERE story_id = ?)")
+        .bind(story_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to load factions: {}", e))
+}
+
+/// Insert a faction, returning its id. Names are unique per story, ignoring case.
+pub async fn create_faction(
+    conn: &mut SqliteConnection,
+    story_id: &str,
+    faction: &NewFaction,
+    source: &str,
+) -> Result<String, String> {
+    let name = faction.name.trim();
+    if name.is_empty() {
+        return Err("Faction name cannot be empty".to_string());
+    }
+    if find_faction(conn, story_id, name).await?.is_some() {
+        return Err(format!("A faction named '{}' already exists", name));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO factions (id, story_id, name, description, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(story_id)
+    .bind(name)
+    .bind(faction.description.as_deref().map(str::trim))
+    .bind(now)
+    .bind(now)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to create faction: {}", e))?;
+    if faction.reputation != 0 {
+        change_reputation(conn, &id, None, faction.reputation, None, source).await?;
+    }
+    Ok(id)
+}
+
+/// A faction's id by name, ignoring case
+pub async fn find_faction(
+    conn: &mut SqliteConnection,
+    story_id: &str,
+    name: &str,
+) -> Result<Option<String>, String> {
+    sqlx::query_scalar("SELECT id FROM factions WHERE story_id = ? AND name = ? COLLATE NOCASE")
+        .bind(story_id)
+        .bind(name.trim())
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| format!("Failed to load faction: {}", e))
+}
+
+pub async fn update_faction(
+    pool: &SqlitePool,
+    faction_id: &str,
+    name: &str,
+    description: Option<&str>,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Faction name cannot be empty".to_string());
+    }
+    sqlx::query("UPDATE factions SET name = ?, description = ?, updated_at = ? WHERE id = ?")
+        .bind(name)
+        .bind(description.map(str::trim))
+        .bind(now_millis())
+        .bind(faction_id)
+        .execute(pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => {
+                format!("A faction named '{}' already exists", name)
+            }
+            _ => format!("Failed to update faction: {}", e),
+        })?;
+    Ok(())
+}
+
+/// Add a character to a faction, or change their role in it
+pub async fn set_member(
+    conn: &mut SqliteConnection,
+    faction_id: &str,
+    character_id: &str,
+    role: Option<&str>,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO faction_members (faction_id, character_id, role) VALUES (?, ?, ?) \
+         ON CONFLICT(faction_id, character_id) DO UPDATE SET role = excluded.role",
+    )
+    .bind(faction_id)
+    .bind(character_id)
+    .bind(role.map(str::trim).filter(|r| !r.is_empty()))
+    .execute(conn)
+    .await
+    .map_err(|e| format!("Failed to save faction member: {}", e))?;
+    Ok(())
+}
+
+/// Record a reputation change, trimmed so the total stays within range.
+/// Returns `None` when nothing is left of it.
+pub async fn change_reputation(
+    conn: &mut SqliteConnection,
+    faction_id: &str,
+    entry_id: Option<&str>,
+    delta: i64,
+    reason: Option<&str>,
+    source: &str,
+) -> Result<Option<ReputationChange>, String> {
+    let current: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(delta), 0) FROM faction_reputation WHERE faction_id = ?",
+    )
+    .bind(faction_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load reputation: {}", e))?;
+    let delta = (current + delta).clamp(-REPUTATION_RANGE, REPUTATION_RANGE) - current;
+    if delta == 0 {
+        return Ok(None);
+    }
+
+    let change = ReputationChange {
+        id: Uuid::new_v4().to_string(),
+        faction_id: faction_id.to_string(),
+        entry_id: entry_id.map(str::to_string),
+        delta,
+        reason: reason
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(str::to_string),
+        source: source.to_string(),
+        created_at: now_millis(),
+    };
+    sqlx::query(
+        "INSERT INTO faction_reputation \
+         (id, faction_id, entry_id, delta, reason, source, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&change.id)
+    .bind(&change.faction_id)
+    .bind(&change.entry_id)
+    .bind(change.delta)
+    .bind(&change.reason)
+    .bind(&change.source)
+    .bind(change.created_at)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to save reputation change: {}", e))?;
+    sqlx::query("UPDATE factions SET updated_at = ? WHERE id = ?")
+        .bind(change.created_at)
+        .bind(faction_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update faction: {}", e))?;
+    Ok(Some(change))
+}
+
+/// A faction's reputation changes, newest first
+pub async fn load_history(
+    pool: &SqlitePool,
+    faction_id: &str,
+) -> Result<Vec<ReputationChange>, String> {
+    sqlx::query_as(
+        "SELECT id, faction_id, entry_id, delta, reason, source, created_at \
+         FROM faction_reputation WHERE faction_id = ? ORDER BY created_at DESC",
+    )
+    .bind(faction_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load reputation history: {}", e))
+}
+
+/// Render the player's standing for prompt injection: their own memberships
+/// and every faction that isn't neutral towards them
+pub fn format_faction_block(factions: &[Faction]) -> String {
+    let mut lines = Vec::new();
+    let memberships: Vec<String> = factions
+        .iter()
+        .filter_map(|f| {
+            let member = f.members.iter().find(|m| m.is_player)?;
+            Some(match &member.role {
+                Some(role) => format!("{} ({})", f.name, role),
+                None => f.name.clone(),
+            })
+        })
+        .collect();
+    if !memberships.is_empty() {
+        lines.push(format!("Player belongs to: {}", memberships.join(", ")));
+    }
+    for faction in factions.iter().filter(|f| f.standing != "neutral") {
+        let mut line = format!(
+            "{}: {} ({:+})",
+            faction.name, faction.standing, faction.reputation
+        );
+        if let Some(reason) = &faction.last_reason {
+            line.push_str(&format!(", last: {}", reason));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// Reputation is kept within -100 (sworn enemy) to 100 (trusted ally)
+pub const REPUTATION_RANGE: i64 = 100;
+
+/// A group the player can stand well or badly with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Faction {
+    pub id: String,
+    pub story_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Sum of the faction's reputation changes
+    pub reputation: i64,
+    /// 'hostile' | 'unfriendly' | 'neutral' | 'friendly' | 'allied'
+    pub standing: String,
+    /// Why the reputation last changed
+    pub last_reason: Option<String>,
+    pub members: Vec<FactionMember>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FactionMember {
+    pub character_id: String,
+    pub name: String,
+    pub role: Option<String>,
+    /// Whether this is the player's character
+    pub is_player: bool,
+}
+
+/// Input for creating a faction
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewFaction {
+    pub name: String,
+    pub description: Option<String>,
+    /// Starting reputation
+    #[serde(default)]
+    pub reputation: i64,
+}
+
+/// One change to the player's reputation with a faction
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationChange {
+    pub id: String,
+    pub faction_id: String,
+    pub entry_id: Option<String>,
+    pub delta: i64,
+    pub reason: Option<String>,
+    /// 'extraction' | 'manual'
+    pub source: String,
+    pub created_at: i64,
+}
+
+/// Name the band a reputation falls in
+pub fn standing(reputation: i64) -> &'static str {
+    match reputation {
+        i64::MIN..=-60 => "hostile",
+        -59..=-20 => "unfriendly",
+        -19..=19 => "neutral",
+        20..=59 => "friendly",
+        _ => "allied",
+    }
+}
@@ -0,0 +1,255 @@
+use base64::Engine;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use super::fetch;
+use super::progress::{ImportRegistry, ImportTask};
+use super::sniff::{self, HEADER_LEN};
+use super::types::{FileOpenedEvent, ImportedFile, JsonKind};
+use crate::content_pack::commands::preview_pack;
+use crate::db::DbState;
+use crate::presets::commands::{install_preset, parse_file};
+
+/// Files the OS asked the app to open, waiting for the frontend to take them
+#[derive(Default)]
+pub struct OpenedFiles {
+    pending: Mutex<Vec<String>>,
+}
+
+/// Queue files handed over by the OS (launch arguments, "open with",
+/// drag-drop) and tell the frontend with a `file-opened` event. They stay
+/// queued so files opened before the window loads aren't lost.
+pub fn open_paths(app: &AppHandle, paths: impl IntoIterator<Item = PathBuf>) {
+    let state = app.state::<OpenedFiles>();
+    let pending = {
+        let mut pending = state.pending.lock().unwrap();
+        pending.extend(
+            paths
+                .into_iter()
+                .filter(|p| p.is_file())
+                .map(|p| p.to_string_lossy().to_string()),
+        );
+        pending.len()
+    };
+    if pending == 0 {
+        return;
+    }
+    if let Err(e) = app.emit("file-opened", &FileOpenedEvent { pending }) {
+        eprintln!("Failed to emit file-opened: {}", e);
+    }
+}
+
+/// Files passed on the command line, which is how Windows and Linux hand
+/// over associated files
+pub fn launch_paths() -> Vec<PathBuf> {
+    std::env::args_os().skip(1).map(PathBuf::from).collect()
+}
+
+/// macOS and iOS deliver associated files as an `Opened` run event
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn on_run_event(app: &AppHandle, event: RunEvent) {
+    if let RunEvent::Opened { urls } = event {
+        open_paths(app, urls.into_iter().filter_map(|u| u.to_file_path().ok()));
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+pub fn on_run_event(_app: &AppHandle, _event: RunEvent) {}
+
+/// Stop a running import; whatever it wrote so far is rolled back.
+/// Returns false when no import with that id is running.
+#[tauri::command]
+pub fn cancel_import(state: State<'_, ImportRegistry>, import_id: String) -> bool {
+    state.cancel(&import_id)
+}
+
+#[tauri::command]
+pub fn take_opened_files(state: State<'_, OpenedFiles>) -> Vec<String> {
+    std::mem::take(&mut *state.pending.lock().unwrap())
+}
+
+/// Import any supported file: `.avt`/`.aventura` stories, `.aventurapack`
+/// content packs, preset files, PNG or JSON character cards and Aventura or
+/// SillyTavern lorebooks. The type is sniffed from the contents, not the
+/// extension. Presets go into `story_id`'s rules (global when omitted).
+/// Reports `import-progress` under `import_id`.
+#[tauri::command]
+pub async fn import_file(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    path: String,
+    story_id: Option<String>,
+    import_id: Option<String>,
+) -> Result<ImportedFile, String> {
+    let mut task = ImportTask::start(&app, import_id, "file");
+    let result = import_path(&app, db.pool(), path, story_id.as_deref(), &mut task).await;
+    task.finish(result)
+}
+
+async fn import_path(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    path: String,
+    story_id: Option<&str>,
+    task: &mut ImportTask,
+) -> Result<ImportedFile, String> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    if sniff::is_zip(&header) {
+        let preview = preview_pack(app, pool, path.clone()).await?;
+        return Ok(ImportedFile::ContentPack {
+            path,
+            preview: Box::new(preview),
+        });
+    }
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    import_bytes(pool, bytes, story_id, task).await
+}
+
+/// Download a character card, lorebook, story export, preset or content
+/// pack over HTTPS and import it as `import_file` would. GitHub file page
+/// links are fetched from the raw file.
+#[tauri::command]
+pub async fn import_from_url(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    url: String,
+    story_id: Option<String>,
+    import_id: Option<String>,
+) -> Result<ImportedFile, String> {
+    let mut task = ImportTask::start(&app, import_id, "file");
+    let result = import_url(&app, db.pool(), &url, story_id.as_deref(), &mut task).await;
+    task.finish(result)
+}
+
+/// Where downloaded packs wait between preview and install
+fn downloads_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?
+        .join("downloads"))
+}
+
+/// Delete packs downloaded in earlier runs. Nothing reports whether a
+/// previewed pack was installed or dismissed, so they're swept at startup.
+pub fn clear_downloads(app: &AppHandle) {
+    if let Ok(dir) = downloads_dir(app) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+async fn import_url(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    url: &str,
+    story_id: Option<&str>,
+    task: &mut ImportTask,
+) -> Result<ImportedFile, String> {
+    let download = fetch::download(fetch::parse_url(url)?).await?;
+    task.check()?;
+
+    if sniff::is_zip(&download.bytes) {
+        // Packs are read from disk, and installed from the same file later
+        let dir = downloads_dir(app)?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("Failed to create download directory: {}", e))?;
+        let path = dir
+            .join(format!("{}.aventurapack", Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        tokio::fs::write(&path, &download.bytes)
+            .await
+            .map_err(|e| format!("Failed to save download: {}", e))?;
+        let preview = match preview_pack(app, pool, path.clone()).await {
+            Ok(preview) => preview,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e);
+            }
+        };
+        return Ok(ImportedFile::ContentPack {
+            path,
+            preview: Box::new(preview),
+        });
+    }
+
+    let is_html = download.is_html();
+    import_bytes(pool, download.bytes, story_id, task)
+        .await
+        .map_err(|e| {
+            if is_html {
+                "The link opens a web page, not a file; use the direct download link".to_string()
+            } else {
+                e
+            }
+        })
+}
+
+/// Route a PNG or JSON file to its importer
+async fn import_bytes(
+    pool: &SqlitePool,
+    bytes: Vec<u8>,
+    story_id: Option<&str>,
+    task: &mut ImportTask,
+) -> Result<ImportedFile, String> {
+    if sniff::is_png(&bytes) {
+        let content =
+            sniff::card_from_png(&bytes).ok_or("This image has no character card in it")?;
+        let image = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&bytes)
+        );
+        return Ok(ImportedFile::CharacterCard {
+            content,
+            image: Some(image),
+        });
+    }
+
+    let content = String::from_utf8(bytes)
+        .map_err(|_| "Unsupported file: not a pack, image or JSON file".to_string())?;
+    let content = content.trim_start_matches('\u{feff}').to_string();
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Unsupported file: not valid JSON ({})", e))?;
+    let kind = sniff::classify_json(&value)
+        .ok_or("Unsupported file: not a story, preset, character card or lorebook")?;
+
+    match kind {
+        JsonKind::Story => Ok(ImportedFile::Story { content }),
+        JsonKind::CharacterCard => Ok(ImportedFile::CharacterCard {
+            content,
+            image: None,
+        }),
+        JsonKind::Lorebook(format) => Ok(ImportedFile::Lorebook { format, content }),
+        JsonKind::Preset => {
+            let file = parse_file(&content)?;
+            task.stage("writing", Some(1))?;
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| format!("Failed to start transaction: {}", e))?;
+            let result = install_preset(&mut tx, file, story_id).await?;
+            task.rows(1)?;
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to commit preset import: {}", e))?;
+            Ok(ImportedFile::Preset { result })
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use std::time::Duration;
+
+/// Largest file `import_from_url` downloads
+const MAX_DOWNLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// reqwest's default redirect limit
+const MAX_REDIRECTS: usize = 10;
+
+/// A downloaded file and the content type the server gave it
+pub struct Download {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+}
+
+impl Download {
+    /// The server answered with a web page rather than a file
+    pub fn is_html(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|t| t.starts_with("text/html"))
+    }
+}
+
+/// Check the link is HTTPS, and point GitHub file pages at the raw file
+pub fn parse_url(raw: &str) -> Result<Url, String> {
+    let mut url = Url::parse(raw.trim()).map_err(|e| format!("Invalid link: {}", e))?;
+    if url.scheme() != "https" {
+        return Err("Only https:// links can be imported".to_string());
+    }
+    if url.host_str() == Some("github.com") {
+        let segments: Vec<String> = url
+            .path_segments()
+            .map(|s| s.map(str::to_string).collect())
+            .unwrap_or_default();
+        // /{owner}/{repo}/blob/{ref}/{path..} -> raw.githubusercontent.com/{owner}/{repo}/{ref}/{path..}
+        if segments.len() > 4 && segments[2] == "blob" {
+            let mut path = segments[..2].to_vec();
+            path.extend_from_slice(&segments[3..]);
+            url = Url::parse(&format!(
+                "https://raw.githubusercontent.com/{}",
+                path.join("/")
+            ))
+            .map_err(|e| format!("Invalid link: {}", e))?;
+        }
+    }
+    Ok(url)
+}
+
+pub async fn download(url: Url) -> Result<Download, String> {
+    download_capped(url, MAX_DOWNLOAD_BYTES).await
+}
+
+/// Download a file into memory, giving up once it passes `max_bytes`.
+/// Redirects must stay on https://, whatever the first link was.
+pub async fn download_capped(url: Url, max_bytes: u64) -> Result<Download, String> {
+    let redirects = Policy::custom(|attempt| {
+        if attempt.url().scheme() != "https" {
+            attempt.error("redirected to a non-https:// link")
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    });
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .redirect(redirects)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download file: {}", e))?;
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large(max_bytes));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_ascii_lowercase());
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download file: {}", e))?
+    {
+        // Servers can omit or understate Content-Length
+        if (bytes.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large(max_bytes));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Download {
+        bytes,
+        content_type,
+    })
+}
+
+fn too_large(max_bytes: u64) -> String {
+    format!(
+        "File is larger than the {} MB import limit",
+        max_bytes / (1024 * 1024)
+    )
+}
@@ -0,0 +1,8 @@
+pub mod commands;
+pub mod fetch;
+pub mod progress;
+pub mod sniff;
+pub mod types;
+
+pub use commands::OpenedFiles;
+pub use progress::ImportRegistry;
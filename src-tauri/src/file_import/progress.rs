@@ -0,0 +1,138 @@
+//! Progress and cancellation for imports that write through a Rust
+//! transaction. An importer holds an `ImportTask` while it runs, counting
+//! rows and images and checking for cancellation between them; returning
+//! the cancellation error drops the transaction, so nothing is kept.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use super::types::ImportProgressEvent;
+
+/// Error an import stops with once cancelled
+pub const CANCELLED: &str = "Import cancelled";
+
+/// Least time between progress events while running
+const EMIT_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Imports running in this session, by import id
+#[derive(Default)]
+pub struct ImportRegistry {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ImportRegistry {
+    /// Ask an import to stop. Returns false when it isn't running.
+    pub fn cancel(&self, import_id: &str) -> bool {
+        match self.running.lock().unwrap().get(import_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct ImportTask {
+    app: AppHandle,
+    cancelled: Arc<AtomicBool>,
+    event: ImportProgressEvent,
+    last_emit: Option<Instant>,
+}
+
+impl ImportTask {
+    /// Register an import under the id the frontend picked (a new one when
+    /// it didn't), so `cancel_import` can reach it
+    pub fn start(app: &AppHandle, import_id: Option<String>, kind: &str) -> Self {
+        let import_id = import_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        app.state::<ImportRegistry>()
+            .running
+            .lock()
+            .unwrap()
+            .insert(import_id.clone(), cancelled.clone());
+        let mut task = ImportTask {
+            app: app.clone(),
+            cancelled,
+            event: ImportProgressEvent {
+                import_id,
+                kind: kind.to_string(),
+                stage: "reading".to_string(),
+                rows_processed: 0,
+                rows_total: None,
+                images_decoded: 0,
+                status: "running".to_string(),
+                error: None,
+            },
+            last_emit: None,
+        };
+        task.emit(true);
+        task
+    }
+
+    fn emit(&mut self, force: bool) {
+        if !force && self.last_emit.is_some_and(|t| t.elapsed() < EMIT_INTERVAL) {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        if let Err(e) = self.app.emit("import-progress", &self.event) {
+            eprintln!("Failed to emit import progress: {}", e);
+        }
+    }
+
+    /// Move to the next stage, with the rows it will write when known
+    pub fn stage(&mut self, stage: &str, rows_total: Option<usize>) -> Result<(), String> {
+        self.event.stage = stage.to_string();
+        self.event.rows_processed = 0;
+        self.event.rows_total = rows_total;
+        self.emit(true);
+        self.check()
+    }
+
+    /// Count written rows, failing once the import is cancelled
+    pub fn rows(&mut self, count: usize) -> Result<(), String> {
+        self.event.rows_processed += count;
+        self.emit(false);
+        self.check()
+    }
+
+    pub fn images(&mut self, count: usize) {
+        self.event.images_decoded += count;
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(CANCELLED.to_string());
+        }
+        Ok(())
+    }
+
+    /// Report how the import ended, passing its result through
+    pub fn finish<T>(mut self, result: Result<T, String>) -> Result<T, String> {
+        self.event.status = match &result {
+            Ok(_) => "done",
+            Err(e) if e == CANCELLED => "cancelled",
+            Err(_) => "failed",
+        }
+        .to_string();
+        self.event.error = result.as_ref().err().cloned();
+        self.emit(true);
+        result
+    }
+}
+
+impl Drop for ImportTask {
+    fn drop(&mut self) {
+        self.app
+            .state::<ImportRegistry>()
+            .running
+            .lock()
+            .unwrap()
+            .remove(&self.event.import_id);
+    }
+}
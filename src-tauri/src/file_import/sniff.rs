@@ -0,0 +1,105 @@
+use base64::Engine;
+use serde_json::Value;
+
+use super::types::{JsonKind, LorebookFormat};
+use crate::presets::types::PRESET_FORMAT;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const ZIP_SIGNATURE: [u8; 4] = [b'P', b'K', 0x03, 0x04];
+
+/// Bytes needed to tell archives and images apart from JSON
+pub const HEADER_LEN: usize = 8;
+
+pub fn is_zip(header: &[u8]) -> bool {
+    header.starts_with(&ZIP_SIGNATURE)
+}
+
+pub fn is_png(header: &[u8]) -> bool {
+    header.starts_with(&PNG_SIGNATURE)
+}
+
+/// Character card JSON from a PNG's `tEXt` chunks. A V3 `ccv3` chunk wins
+/// over the V2 `chara` one when a card carries both.
+pub fn card_from_png(bytes: &[u8]) -> Option<String> {
+    let mut offset = PNG_SIGNATURE.len();
+    let mut chara = None;
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().ok()?) as usize;
+        let kind = &bytes[offset + 4..offset + 8];
+        let start = offset + 8;
+        let end = start.checked_add(length)?;
+        if end > bytes.len() {
+            break;
+        }
+        if kind == b"tEXt" {
+            let chunk = &bytes[start..end];
+            if let Some(split) = chunk.iter().position(|&b| b == 0) {
+                let keyword = String::from_utf8_lossy(&chunk[..split]).to_lowercase();
+                let text = &chunk[split + 1..];
+                if keyword == "ccv3" {
+                    return decode_card(text);
+                }
+                if keyword == "chara" {
+                    chara = decode_card(text);
+                }
+            }
+        }
+        if kind == b"IEND" {
+            break;
+        }
+        // Chunk data is followed by a 4-byte CRC
+        offset = end + 4;
+    }
+    chara
+}
+
+fn decode_card(text: &[u8]) -> Option<String> {
+    let trimmed: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(trimmed)
+        .ok()?;
+    String::from_utf8(decoded).ok()
+}
+
+/// Classify a JSON document by its shape, the same checks the frontend
+/// importers make
+pub fn classify_json(value: &Value) -> Option<JsonKind> {
+    if let Some(items) = value.as_array() {
+        let first = items.first()?;
+        let is_entry = ["name", "type", "description"]
+            .iter()
+            .all(|key| first.get(key).is_some())
+            && first.get("injection").is_some_and(Value::is_object);
+        return is_entry.then_some(JsonKind::Lorebook(LorebookFormat::Aventura));
+    }
+    let object = value.as_object()?;
+    let format = object.get("format").and_then(Value::as_str);
+    if format == Some(PRESET_FORMAT) || (format.is_none() && object.contains_key("templates")) {
+        return Some(JsonKind::Preset);
+    }
+    if ["version", "story", "entries"]
+        .iter()
+        .all(|key| object.contains_key(*key))
+    {
+        return Some(JsonKind::Story);
+    }
+    let is_card = object
+        .get("spec")
+        .and_then(Value::as_str)
+        .is_some_and(|spec| spec.starts_with("chara_card"))
+        || (object.contains_key("name") && object.contains_key("first_mes"));
+    if is_card {
+        return Some(JsonKind::CharacterCard);
+    }
+    if object
+        .get("entries")
+        .is_some_and(|e| e.is_object() || e.is_array())
+    {
+        return Some(JsonKind::Lorebook(LorebookFormat::SillyTavern));
+    }
+    None
+}
@@ -0,0 +1,76 @@
+use serde::Serialize;
+
+use crate::content_pack::types::PackPreview;
+use crate::presets::types::PresetImportResult;
+
+/// Lorebook JSON flavours the lorebook importer understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LorebookFormat {
+    Aventura,
+    SillyTavern,
+}
+
+/// What a JSON file turned out to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonKind {
+    Story,
+    Preset,
+    CharacterCard,
+    Lorebook(LorebookFormat),
+}
+
+/// Result of `import_file`. Presets are installed straight away; packs
+/// come back as a preview to confirm with `install_content_pack`; stories,
+/// cards and lorebooks carry the JSON for the frontend importers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ImportedFile {
+    #[serde(rename_all = "camelCase")]
+    Story { content: String },
+    #[serde(rename_all = "camelCase")]
+    ContentPack {
+        path: String,
+        preview: Box<PackPreview>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Preset { result: PresetImportResult },
+    /// `image` is the card PNG as a data URL, for the portrait
+    #[serde(rename_all = "camelCase")]
+    CharacterCard {
+        content: String,
+        image: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Lorebook {
+        format: LorebookFormat,
+        content: String,
+    },
+}
+
+/// Payload of the `file-opened` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOpenedEvent {
+    /// Paths waiting in `take_opened_files`
+    pub pending: usize,
+}
+
+/// Payload of the `import-progress` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgressEvent {
+    pub import_id: String,
+    /// What's being imported: `backup`, `contentPack` or `file`
+    pub kind: String,
+    /// e.g. `reading`, `writing`
+    pub stage: String,
+    pub rows_processed: usize,
+    /// Rows the current stage will write, when known
+    pub rows_total: Option<usize>,
+    pub images_decoded: usize,
+    /// `running`, then `done`, `failed` or `cancelled`. Nothing is kept of
+    /// an import that didn't finish.
+    pub status: String,
+    pub error: Option<String>,
+}
@@ -0,0 +1,138 @@
+use tauri::State;
+use uuid::Uuid;
+
+use super::engine::{compile, enforce, load_rules, log_hits, scan, statistics};
+use super::types::{
+    FilterHit, FilterOutcome, FilterRule, FilterRuleInput, FilterRuleStats, FILTER_ACTIONS,
+};
+use crate::db::{now_millis, DbState};
+
+#[tauri::command]
+pub async fn get_filter_rules(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<FilterRule>, String> {
+    load_rules(db.pool(), story_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load filter rules: {}", e))
+}
+
+/// Create or update a filter rule, returning its id
+#[tauri::command]
+pub async fn save_filter_rule(
+    db: State<'_, DbState>,
+    rule: FilterRuleInput,
+) -> Result<String, String> {
+    if rule.phrase.trim().is_empty() {
+        return Err("Filter phrase cannot be empty".to_string());
+    }
+    if !FILTER_ACTIONS.contains(&rule.action.as_str()) {
+        return Err(format!("Invalid filter action: {}", rule.action));
+    }
+    let id = rule
+        .id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let now = now_millis();
+    compile(&FilterRule {
+        id: id.clone(),
+        story_id: rule.story_id.clone(),
+        phrase: rule.phrase.clone(),
+        is_regex: rule.is_regex,
+        action: rule.action.clone(),
+        substitution: rule.substitution.clone(),
+        enabled: rule.enabled,
+        created_at: now,
+    })?;
+
+    sqlx::query(
+        "INSERT INTO filter_rules (id, story_id, phrase, is_regex, action, substitution, enabled, created_at, updated_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8) \
+         ON CONFLICT(id) DO UPDATE SET phrase = excluded.phrase, is_regex = excluded.is_regex, \
+         action = excluded.action, substitution = excluded.substitution, enabled = excluded.enabled, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(&id)
+    .bind(&rule.story_id)
+    .bind(&rule.phrase)
+    .bind(rule.is_regex)
+    .bind(&rule.action)
+    .bind(&rule.substitution)
+    .bind(rule.enabled)
+    .bind(now)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save filter rule: {}", e))?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_filter_rule(db: State<'_, DbState>, rule_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM filter_rules WHERE id = ?")
+        .bind(&rule_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete filter rule: {}", e))?;
+    Ok(())
+}
+
+/// Check streamed output as it arrives. `from_offset` is the byte length of
+/// text already checked, so only new hits are returned; nothing is logged.
+#[tauri::command]
+pub async fn check_filter_stream(
+    db: State<'_, DbState>,
+    story_id: String,
+    text: String,
+    from_offset: usize,
+) -> Result<Vec<FilterHit>, String> {
+    let rules = load_rules(db.pool(), Some(&story_id))
+        .await
+        .map_err(|e| format!("Failed to load filter rules: {}", e))?;
+    Ok(scan(&rules, &text)
+        .into_iter()
+        .filter(|h| h.offset + h.matched_text.len() > from_offset)
+        .collect())
+}
+
+/// Enforce the filter list on a finished response: applies substitutions,
+/// logs every hit and reports whether a regeneration is required
+#[tauri::command]
+pub async fn enforce_filters(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: Option<String>,
+    text: String,
+) -> Result<FilterOutcome, String> {
+    let rules = load_rules(db.pool(), Some(&story_id))
+        .await
+        .map_err(|e| format!("Failed to load filter rules: {}", e))?;
+    let outcome = enforce(&rules, &text);
+    log_hits(db.pool(), &story_id, entry_id.as_deref(), &outcome.hits)
+        .await
+        .map_err(|e| format!("Failed to log filter hits: {}", e))?;
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn get_filter_statistics(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<FilterRuleStats>, String> {
+    statistics(db.pool(), story_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load filter statistics: {}", e))
+}
+
+/// Clear logged hits, for one rule or all of them
+#[tauri::command]
+pub async fn reset_filter_statistics(
+    db: State<'_, DbState>,
+    rule_id: Option<String>,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM filter_hits WHERE ?1 IS NULL OR rule_id = ?1")
+        .bind(&rule_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to reset filter statistics: {}", e))?;
+    Ok(())
+}
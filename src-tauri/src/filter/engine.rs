@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use regex::{NoExpand, Regex, RegexBuilder};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::types::{FilterHit, FilterOutcome, FilterRule, FilterRuleStats};
+use crate::db::now_millis;
+
+const TOP_MATCHES: usize = 5;
+
+fn rule_from_row(r: &SqliteRow) -> FilterRule {
+    FilterRule {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        phrase: r.get("phrase"),
+        is_regex: r.get::<i64, _>("is_regex") != 0,
+        action: r.get("action"),
+        substitution: r.get("substitution"),
+        enabled: r.get::<i64, _>("enabled") != 0,
+        created_at: r.get("created_at"),
+    }
+}
+
+/// Global rules plus the story's own rules; only global rules without a story
+pub async fn load_rules(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<FilterRule>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM filter_rules WHERE story_id IS NULL OR story_id = ?1 \
+         ORDER BY story_id IS NOT NULL, created_at",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(rule_from_row).collect())
+}
+
+/// Plain phrases match case-insensitively on word boundaries ("shiver" does
+/// not hit "shivering"); regex rules are used as written
+pub fn compile(rule: &FilterRule) -> Result<Regex, String> {
+    let pattern = if rule.is_regex {
+        rule.phrase.clone()
+    } else {
+        let phrase = rule.phrase.trim();
+        let boundary = |c: Option<char>| {
+            if c.is_some_and(|c| c.is_alphanumeric()) {
+                r"\b"
+            } else {
+                ""
+            }
+        };
+        format!(
+            "{}{}{}",
+            boundary(phrase.chars().next()),
+            regex::escape(phrase),
+            boundary(phrase.chars().last())
+        )
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid filter pattern '{}': {}", rule.phrase, e))
+}
+
+fn compiled(rules: &[FilterRule]) -> Vec<(&FilterRule, Regex)> {
+    rules
+        .iter()
+        .filter(|r| r.enabled)
+        .filter_map(|r| compile(r).ok().map(|p| (r, p)))
+        .collect()
+}
+
+/// Find every banned phrase in `text`, ordered by position
+pub fn scan(rules: &[FilterRule], text: &str) -> Vec<FilterHit> {
+    let mut hits: Vec<FilterHit> = compiled(rules)
+        .iter()
+        .flat_map(|(rule, pattern)| {
+            pattern.find_iter(text).map(|m| FilterHit {
+                rule_id: rule.id.clone(),
+                action: rule.action.clone(),
+                matched_text: m.as_str().to_string(),
+                offset: m.start(),
+            })
+        })
+        .collect();
+    hits.sort_by_key(|h| h.offset);
+    hits
+}
+
+/// Apply substitutions and decide whether the response should be regenerated
+pub fn enforce(rules: &[FilterRule], text: &str) -> FilterOutcome {
+    let hits = scan(rules, text);
+
+    let mut result = text.to_string();
+    for (rule, pattern) in compiled(rules) {
+        if rule.action == "substitute" {
+            result = pattern
+                .replace_all(&result, NoExpand(&rule.substitution))
+                .into_owned();
+        }
+    }
+
+    let mut banned: Vec<&str> = hits
+        .iter()
+        .filter(|h| h.action == "regenerate")
+        .map(|h| h.matched_text.as_str())
+        .collect();
+    banned.sort_unstable_by_key(|s| s.to_lowercase());
+    banned.dedup_by_key(|s| s.to_lowercase());
+
+    let regeneration_instruction = (!banned.is_empty()).then(|| {
+        format!(
+            "Rewrite the response without using any of these phrases or close variations of them: {}.",
+            banned
+                .iter()
+                .map(|s| format!("\"{}\"", s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    });
+
+    FilterOutcome {
+        text: result,
+        regenerate: regeneration_instruction.is_some(),
+        regeneration_instruction,
+        hits,
+    }
+}
+
+/// Record hits from a finished response for the statistics view
+pub async fn log_hits(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: Option<&str>,
+    hits: &[FilterHit],
+) -> Result<(), sqlx::Error> {
+    if hits.is_empty() {
+        return Ok(());
+    }
+    let now = now_millis();
+    let mut tx = pool.begin().await?;
+    for hit in hits {
+        sqlx::query(
+            "INSERT INTO filter_hits (rule_id, story_id, entry_id, matched_text, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&hit.rule_id)
+        .bind(story_id)
+        .bind(entry_id)
+        .bind(&hit.matched_text)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Per-rule hit counts, most frequent first; scoped to one story when given
+pub async fn statistics(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<FilterRuleStats>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT r.id, r.phrase, r.action, h.matched_text, h.created_at \
+         FROM filter_rules r JOIN filter_hits h ON h.rule_id = r.id \
+         WHERE ?1 IS NULL OR h.story_id = ?1",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_rule: HashMap<String, (FilterRuleStats, HashMap<String, usize>)> = HashMap::new();
+    for r in &rows {
+        let rule_id: String = r.get("id");
+        let created_at: i64 = r.get("created_at");
+        let matched: String = r.get("matched_text");
+        let (stats, matches) = by_rule.entry(rule_id.clone()).or_insert_with(|| {
+            (
+                FilterRuleStats {
+                    rule_id,
+                    phrase: r.get("phrase"),
+                    action: r.get("action"),
+                    hit_count: 0,
+                    last_hit_at: None,
+                    top_matches: Vec::new(),
+                },
+                HashMap::new(),
+            )
+        });
+        stats.hit_count += 1;
+        stats.last_hit_at = stats.last_hit_at.max(Some(created_at));
+        *matches.entry(matched.to_lowercase()).or_default() += 1;
+    }
+
+    let mut result: Vec<FilterRuleStats> = by_rule
+        .into_values()
+        .map(|(mut stats, matches)| {
+            let mut matches: Vec<(String, usize)> = matches.into_iter().collect();
+            matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            stats.top_matches = matches
+                .into_iter()
+                .take(TOP_MATCHES)
+                .map(|(m, _)| m)
+                .collect();
+            stats
+        })
+        .collect();
+    result.sort_by_key(|s| std::cmp::Reverse(s.hit_count));
+    Ok(result)
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+pub const FILTER_ACTIONS: &[&str] = &["flag", "substitute", "regenerate"];
+
+/// A banned phrase (or pattern), global when `story_id` is `None`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRule {
+    pub id: String,
+    pub story_id: Option<String>,
+    pub phrase: String,
+    pub is_regex: bool,
+    /// 'flag' only logs, 'substitute' replaces inline, 'regenerate' asks for a new response
+    pub action: String,
+    pub substitution: String,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+/// Input for creating or updating a filter rule
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRuleInput {
+    pub id: Option<String>,
+    pub story_id: Option<String>,
+    pub phrase: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub action: String,
+    #[serde(default)]
+    pub substitution: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// A banned phrase found in generated text
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterHit {
+    pub rule_id: String,
+    pub action: String,
+    pub matched_text: String,
+    /// Byte offset in the scanned text
+    pub offset: usize,
+}
+
+/// Result of enforcing the filter list on a finished response
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterOutcome {
+    /// Response text with 'substitute' rules applied
+    pub text: String,
+    pub hits: Vec<FilterHit>,
+    /// Set when a 'regenerate' rule matched
+    pub regenerate: bool,
+    /// Instruction to append to the retry prompt so the model avoids the hits
+    pub regeneration_instruction: Option<String>,
+}
+
+/// Hit counts for one rule
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRuleStats {
+    pub rule_id: String,
+    pub phrase: String,
+    pub action: String,
+    pub hit_count: i64,
+    pub last_hit_at: Option<i64>,
+    /// Most frequent distinct matches, useful for tuning regex rules
+    pub top_matches: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
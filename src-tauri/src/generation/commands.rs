@@ -0,0 +1,279 @@
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+use super::engine::{self, GenerationState};
+use super::prefetch;
+use super::store;
+use super::types::{CandidateSet, GenerationParams, GenerationRequest, ResumeMode};
+use crate::db::{now_millis, DbState};
+use crate::llm::client::ChatMessage;
+
+/// Service used when a generation doesn't name one
+const DEFAULT_SERVICE: &str = "narrative";
+
+/// Upper bound on concurrent candidates for one turn
+const MAX_CANDIDATES: usize = 4;
+
+/// Stream a narrative response in the background, persisting the prompt and
+/// the text so far so it can be resumed after the app is killed. Deltas
+/// arrive on `generation-chunk`, the outcome on `generation-finished`.
+/// Returns the request id.
+#[tauri::command]
+pub async fn start_generation(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    messages: Vec<ChatMessage>,
+    service: Option<String>,
+    params: Option<GenerationParams>,
+) -> Result<String, String> {
+    engine::start(
+        &app,
+        db.pool(),
+        &story_id,
+        messages,
+        service.as_deref().unwrap_or(DEFAULT_SERVICE),
+        params.unwrap_or_default(),
+        None,
+    )
+    .await
+}
+
+/// Stream `n` responses to the same prompt at once, each under its own
+/// request id so the UI can show them side by side. With `profile_ids`,
+/// candidates cycle through those model profiles (e.g. to compare
+/// providers). Keep one with `choose_candidate`.
+#[tauri::command]
+pub async fn generate_candidates(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    messages: Vec<ChatMessage>,
+    n: usize,
+    service: Option<String>,
+    profile_ids: Option<Vec<String>>,
+) -> Result<CandidateSet, String> {
+    let group_id = Uuid::new_v4().to_string();
+    let profile_ids = profile_ids.unwrap_or_default();
+    let mut request_ids = Vec::new();
+    for i in 0..n.clamp(1, MAX_CANDIDATES) {
+        let params = GenerationParams {
+            profile_id: profile_ids.get(i % profile_ids.len().max(1)).cloned(),
+            ..GenerationParams::default()
+        };
+        let started = engine::start(
+            &app,
+            db.pool(),
+            &story_id,
+            messages.clone(),
+            service.as_deref().unwrap_or(DEFAULT_SERVICE),
+            params,
+            Some(group_id.clone()),
+        )
+        .await;
+        match started {
+            Ok(id) => request_ids.push(id),
+            Err(e) => {
+                let state = app.state::<GenerationState>();
+                for id in &request_ids {
+                    state.abort(id);
+                    store::delete(db.pool(), id).await?;
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(CandidateSet {
+        group_id,
+        request_ids,
+    })
+}
+
+/// Keep one candidate: the others are stopped and deleted. Returns the
+/// chosen request, which keeps streaming if it hasn't finished.
+#[tauri::command]
+pub async fn choose_candidate(
+    db: State<'_, DbState>,
+    state: State<'_, GenerationState>,
+    request_id: String,
+) -> Result<GenerationRequest, String> {
+    let mut chosen = store::load(db.pool(), &request_id)
+        .await?
+        .ok_or_else(|| format!("Generation request not found: {}", request_id))?;
+    let group = chosen
+        .candidate_group
+        .take()
+        .ok_or("Generation request is not a candidate")?;
+    for id in store::group_members(db.pool(), &group).await? {
+        if id != request_id {
+            state.abort(&id);
+            store::delete(db.pool(), &id).await?;
+        }
+    }
+    sqlx::query("UPDATE generation_requests SET candidate_group = NULL WHERE id = ?")
+        .bind(&request_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to keep candidate: {}", e))?;
+    Ok(chosen)
+}
+
+/// Store the service and sampling parameters (including the seed) of the
+/// request that produced an entry, for `regenerate_with_seed`
+#[tauri::command]
+pub async fn attach_generation(
+    db: State<'_, DbState>,
+    entry_id: String,
+    request_id: String,
+) -> Result<(), String> {
+    let request = store::load(db.pool(), &request_id)
+        .await?
+        .ok_or_else(|| format!("Generation request not found: {}", request_id))?;
+    let recorded = json!({ "service": request.service, "params": request.params });
+    sqlx::query("UPDATE story_entries SET generation_params = ? WHERE id = ?")
+        .bind(recorded.to_string())
+        .bind(&entry_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to save generation parameters: {}", e))?;
+    Ok(())
+}
+
+/// Generate a new response for an entry's turn with the seed and sampling
+/// parameters the entry was generated with, so the same response can be
+/// reproduced after the prompt is tweaked. Returns the request id.
+#[tauri::command]
+pub async fn regenerate_with_seed(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    entry_id: String,
+    messages: Vec<ChatMessage>,
+) -> Result<String, String> {
+    let row: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT story_id, generation_params FROM story_entries WHERE id = ?")
+            .bind(&entry_id)
+            .fetch_optional(db.pool())
+            .await
+            .map_err(|e| format!("Failed to load entry: {}", e))?;
+    let (story_id, recorded) = row.ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let recorded: serde_json::Value = recorded
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    let params: GenerationParams = recorded
+        .get("params")
+        .and_then(|p| serde_json::from_value(p.clone()).ok())
+        .unwrap_or_default();
+    if params.seed.is_none() {
+        return Err("This entry was generated without a seed".to_string());
+    }
+    let service = recorded
+        .get("service")
+        .and_then(|s| s.as_str())
+        .unwrap_or(DEFAULT_SERVICE);
+    engine::start(&app, db.pool(), &story_id, messages, service, params, None).await
+}
+
+/// Speculatively generate the response to the predicted next action (e.g.
+/// the first suggestion after a suggestion click) once the user has been
+/// idle for a while and the story's background jobs are done. Does nothing
+/// unless idle prefetch is enabled in settings; returns whether it was
+/// scheduled.
+#[tauri::command]
+pub async fn schedule_prefetch(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    action: String,
+    messages: Vec<ChatMessage>,
+    service: Option<String>,
+) -> Result<bool, String> {
+    let service = service.unwrap_or_else(|| DEFAULT_SERVICE.to_string());
+    prefetch::schedule(&app, db.pool(), &story_id, action, messages, service).await
+}
+
+/// Take the story's prefetched response for the action the user picked.
+/// Returns the request (streaming or completed) when the prediction and
+/// prompt match, otherwise discards the prefetch and returns nothing, in
+/// which case the caller generates normally.
+#[tauri::command]
+pub async fn claim_prefetch(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    action: String,
+    messages: Vec<ChatMessage>,
+) -> Result<Option<GenerationRequest>, String> {
+    prefetch::claim(&app, db.pool(), &story_id, &action, &messages).await
+}
+
+/// Drop the story's prefetch, e.g. when the user starts typing their own action
+#[tauri::command]
+pub async fn cancel_prefetch(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<(), String> {
+    prefetch::discard(&app, db.pool(), &story_id).await
+}
+
+/// Requests cut off by the app closing, newest first
+#[tauri::command]
+pub async fn get_interrupted_generations(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<GenerationRequest>, String> {
+    store::list_interrupted(db.pool(), story_id.as_deref()).await
+}
+
+/// Restart an interrupted (or failed) request under the same id. By default
+/// the saved partial text is continued; `retry` starts over from the prompt.
+#[tauri::command]
+pub async fn resume_generation(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    request_id: String,
+    mode: Option<ResumeMode>,
+) -> Result<String, String> {
+    let mut request = store::load(db.pool(), &request_id)
+        .await?
+        .ok_or_else(|| format!("Generation request not found: {}", request_id))?;
+    if !matches!(request.status.as_str(), "interrupted" | "failed") {
+        return Err(format!(
+            "Generation request is {}, not interrupted",
+            request.status
+        ));
+    }
+    if mode == Some(ResumeMode::Retry) {
+        request.partial.clear();
+    }
+    let config = engine::config_for(db.pool(), &request).await?;
+    engine::spawn(&app, db.pool(), request, config).await?;
+    Ok(request_id)
+}
+
+/// Stop a streaming request, keeping the text saved so far
+#[tauri::command]
+pub async fn cancel_generation(
+    db: State<'_, DbState>,
+    state: State<'_, GenerationState>,
+    request_id: String,
+) -> Result<(), String> {
+    state.abort(&request_id);
+    sqlx::query(
+        "UPDATE generation_requests SET status = 'cancelled', updated_at = ? \
+         WHERE id = ? AND status = 'streaming'",
+    )
+    .bind(now_millis())
+    .bind(&request_id)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to cancel generation: {}", e))?;
+    Ok(())
+}
+
+/// Drop an interrupted request the user doesn't want to resume
+#[tauri::command]
+pub async fn discard_generation(db: State<'_, DbState>, request_id: String) -> Result<(), String> {
+    store::delete(db.pool(), &request_id).await
+}
@@ -0,0 +1,300 @@
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use super::stops::{StopFilter, StopReason};
+use super::store;
+use super::types::{GenerationChunk, GenerationFinished, GenerationParams, GenerationRequest};
+use crate::analytics::Analytics;
+use crate::db::{now_millis, DbState};
+use crate::llm::cache;
+use crate::llm::client::{seed_field, ChatMessage};
+use crate::llm::config::{resolve_story_service, resolve_with_profile, LlmConfig};
+use crate::llm::profiles::load_profile;
+use crate::llm::stream::stream_with;
+
+/// How often streamed text is written to disk while a request runs
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Finished requests kept for inspection
+const FINISHED_HISTORY: i64 = 20;
+
+/// Streaming tasks of this session, by request id
+#[derive(Default)]
+pub struct GenerationState {
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl GenerationState {
+    /// Abort a running request. Returns false when it isn't running here.
+    pub fn abort(&self, request_id: &str) -> bool {
+        match self.tasks.lock().unwrap().remove(request_id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(&self, request_id: &str) {
+        self.tasks.lock().unwrap().remove(request_id);
+    }
+}
+
+/// The connection for a request's service with its saved sampling parameters
+pub async fn config_for(
+    pool: &SqlitePool,
+    request: &GenerationRequest,
+) -> Result<LlmConfig, String> {
+    let profile = match &request.params.profile_id {
+        Some(id) => Some(
+            load_profile(pool, id)
+                .await?
+                .ok_or_else(|| format!("Model profile not found: {}", id))?,
+        ),
+        None => None,
+    };
+    let mut config = match profile {
+        Some(profile) => {
+            resolve_with_profile(pool, &request.service, &request.service, &profile).await?
+        }
+        None => {
+            resolve_story_service(
+                pool,
+                Some(&request.story_id),
+                &request.service,
+                &request.service,
+            )
+            .await?
+        }
+    };
+    if let Some(model) = request.params.model.clone().filter(|m| !m.is_empty()) {
+        config.model = model;
+    }
+    config.temperature = request.params.temperature.or(config.temperature);
+    config.max_tokens = request.params.max_tokens.or(config.max_tokens);
+    if let (Some(field), Some(seed)) = (seed_field(&config.provider_type), request.params.seed) {
+        config.params.insert(field.to_string(), seed.into());
+    }
+    Ok(config)
+}
+
+/// Create a request for a prompt and start streaming it. Returns its id.
+pub async fn start(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    messages: Vec<ChatMessage>,
+    service: &str,
+    params: GenerationParams,
+    candidate_group: Option<String>,
+) -> Result<String, String> {
+    let now = now_millis();
+    let mut request = GenerationRequest {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.to_string(),
+        service: service.to_string(),
+        messages,
+        params,
+        partial: String::new(),
+        status: "streaming".to_string(),
+        error: None,
+        candidate_group,
+        prefetch_action: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let config = prepare(pool, &mut request).await?;
+    let id = request.id.clone();
+    spawn(app, pool, request, config).await?;
+    Ok(id)
+}
+
+/// Resolve the connection for a new request and pick its seed, recording
+/// the parameters actually sampled with on the request
+pub async fn prepare(
+    pool: &SqlitePool,
+    request: &mut GenerationRequest,
+) -> Result<LlmConfig, String> {
+    let mut config = config_for(pool, request).await?;
+    let seed = match (request.params.seed, seed_field(&config.provider_type)) {
+        (None, Some(field)) => {
+            let seed = i64::from(rand::random::<u32>());
+            config.params.insert(field.to_string(), seed.into());
+            Some(seed)
+        }
+        (seed, _) => seed,
+    };
+    // Record what was actually sampled with, so a resume or a seeded
+    // regeneration matches it
+    request.params = GenerationParams {
+        profile_id: request.params.profile_id.clone(),
+        model: Some(config.model.clone()),
+        temperature: config.temperature,
+        max_tokens: config.max_tokens,
+        seed,
+    };
+    Ok(config)
+}
+
+/// Persist the request as streaming and stream it in the background,
+/// continuing from `request.partial` when it isn't empty. Deltas arrive on
+/// `generation-chunk`, the outcome on `generation-finished`.
+pub async fn spawn(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    mut request: GenerationRequest,
+    config: LlmConfig,
+) -> Result<(), String> {
+    request.status = "streaming".to_string();
+    request.error = None;
+    store::save(pool, &mut request).await?;
+    store::prune(pool, FINISHED_HISTORY).await?;
+
+    let id = request.id.clone();
+    let (app_handle, pool) = (app.clone(), pool.clone());
+    let task = tauri::async_runtime::spawn(async move {
+        let id = request.id.clone();
+        run(&app_handle, &pool, request, &config).await;
+        app_handle.state::<GenerationState>().finish(&id);
+    });
+    app.state::<GenerationState>()
+        .tasks
+        .lock()
+        .unwrap()
+        .insert(id, task);
+    Ok(())
+}
+
+async fn run(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    mut request: GenerationRequest,
+    config: &LlmConfig,
+) {
+    let mut filter = match StopFilter::load(pool, &request.story_id).await {
+        Ok(filter) => filter,
+        Err(e) => return fail(app, pool, request, e).await,
+    };
+    let text = Arc::new(Mutex::new(request.partial.clone()));
+    // A non-empty partial is sent back as the start of the reply; backends
+    // that support assistant prefill continue it mid-sentence
+    let mut messages = request.messages.clone();
+    if !request.partial.is_empty() {
+        messages.push(ChatMessage::assistant(request.partial.clone()));
+    }
+
+    let streaming = stream_with(config, &messages, None, |delta| {
+        let safe = filter.push(delta);
+        if !safe.is_empty() {
+            text.lock().unwrap().push_str(&safe);
+            emit_chunk(app, &request.id, safe);
+        }
+        filter.stopped.is_none()
+    });
+    let persist = async {
+        loop {
+            tokio::time::sleep(PERSIST_INTERVAL).await;
+            let partial = text.lock().unwrap().clone();
+            if let Err(e) = store::save_partial(pool, &request.id, &partial).await {
+                eprintln!("{}", e);
+            }
+        }
+    };
+    let outcome = tokio::select! {
+        outcome = streaming => outcome,
+        _ = persist => unreachable!("persist loop never ends"),
+    };
+
+    let held = filter.flush();
+    if !held.is_empty() {
+        text.lock().unwrap().push_str(&held);
+        emit_chunk(app, &request.id, held);
+    }
+    request.partial = text.lock().unwrap().clone();
+    match outcome {
+        Ok(outcome) => {
+            if let Some(usage) = &outcome.usage {
+                let story_id = Some(request.story_id.as_str());
+                if let Err(e) = cache::record(pool, &request.service, story_id, config, usage).await
+                {
+                    eprintln!("{}", e);
+                }
+            }
+            request.partial = filter.finish(&request.partial, outcome.finish_reason.as_deref());
+            request.status = "completed".to_string();
+        }
+        Err(e) => {
+            request.status = "failed".to_string();
+            request.error = Some(e);
+        }
+    }
+    finish(app, pool, request, filter.stopped).await;
+}
+
+async fn fail(app: &AppHandle, pool: &SqlitePool, mut request: GenerationRequest, error: String) {
+    request.status = "failed".to_string();
+    request.error = Some(error);
+    finish(app, pool, request, None).await;
+}
+
+/// Save the request's final state and emit `generation-finished`
+async fn finish(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    mut request: GenerationRequest,
+    stop_reason: Option<StopReason>,
+) {
+    if let Err(e) = store::save(pool, &mut request).await {
+        eprintln!("{}", e);
+    }
+    let analytics = app.state::<Analytics>();
+    match &request.error {
+        Some(error) => analytics.record_error("generation", error),
+        None => analytics.record_feature("generation"),
+    }
+    let finished = GenerationFinished {
+        request_id: request.id.clone(),
+        story_id: request.story_id.clone(),
+        status: request.status.clone(),
+        text: request.partial.clone(),
+        error: request.error.clone(),
+        seed: request.params.seed,
+        stop_reason,
+    };
+    if let Err(e) = app.emit("generation-finished", &finished) {
+        eprintln!("Failed to emit generation result: {}", e);
+    }
+}
+
+fn emit_chunk(app: &AppHandle, request_id: &str, delta: String) {
+    let chunk = GenerationChunk {
+        request_id: request_id.to_string(),
+        delta,
+    };
+    if let Err(e) = app.emit("generation-chunk", &chunk) {
+        eprintln!("Failed to emit generation chunk: {}", e);
+    }
+}
+
+/// Mark requests a previous session left streaming as interrupted and drop
+/// its unserved prefetches
+pub fn recover(app: AppHandle) {
+    let started_at = now_millis();
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The table only exists once the frontend has run migrations
+        while store::mark_interrupted(&pool, started_at).await.is_err() {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        // Nobody is waiting for a speculative response from a past session
+        if let Err(e) = store::delete_prefetched(&pool).await {
+            eprintln!("{}", e);
+        }
+    });
+}
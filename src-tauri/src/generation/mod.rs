@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod engine;
+pub mod prefetch;
+pub mod stops;
+pub mod store;
+pub mod types;
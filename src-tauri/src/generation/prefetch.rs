@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use super::engine::{self, GenerationState};
+use super::store;
+use super::types::{GenerationParams, GenerationRequest};
+use crate::db::now_millis;
+use crate::llm::client::ChatMessage;
+use crate::llm::config::get_setting;
+
+/// How often a scheduled prefetch checks whether the story's jobs are done
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Idle prefetch options, stored in `idle_prefetch_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrefetchSettings {
+    pub enabled: bool,
+    /// Seconds without input before the predicted response is generated
+    pub idle_seconds: u64,
+}
+
+impl Default for PrefetchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_seconds: 15,
+        }
+    }
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<PrefetchSettings, String> {
+    Ok(get_setting(pool, "idle_prefetch_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// The one prefetch a story may have: waiting for idle, or streaming
+struct Prefetch {
+    token: String,
+    action: String,
+    waiting: JoinHandle<()>,
+    request_id: Option<String>,
+}
+
+/// Scheduled prefetches, by story id
+#[derive(Default)]
+pub struct PrefetchState {
+    pending: Mutex<HashMap<String, Prefetch>>,
+}
+
+/// Whether the story has background jobs queued or running. A prefetch
+/// waits for them so it doesn't compete with the last turn's updates.
+async fn story_busy(pool: &SqlitePool, story_id: &str) -> Result<bool, String> {
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM background_jobs \
+         WHERE story_id = ? AND status IN ('pending', 'running')",
+    )
+    .bind(story_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to check background jobs: {}", e))?;
+    Ok(active > 0)
+}
+
+fn same_action(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+fn same_prompt(a: &[ChatMessage], b: &[ChatMessage]) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// After the idle delay and once the story's jobs are done, generate the
+/// response to `action` in the background. Replaces the story's previous
+/// prefetch. Returns false when prefetching is turned off.
+pub async fn schedule(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    action: String,
+    messages: Vec<ChatMessage>,
+    service: String,
+) -> Result<bool, String> {
+    let settings = load_settings(pool).await?;
+    if !settings.enabled {
+        return Ok(false);
+    }
+    discard(app, pool, story_id).await?;
+
+    let (app_handle, pool_handle) = (app.clone(), pool.clone());
+    let token = Uuid::new_v4().to_string();
+    let (story, predicted, own) = (story_id.to_string(), action.clone(), token.clone());
+    let waiting = tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(settings.idle_seconds)).await;
+        loop {
+            match story_busy(&pool_handle, &story).await {
+                Ok(false) => break,
+                Ok(true) => tokio::time::sleep(JOB_POLL_INTERVAL).await,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            }
+        }
+        let started = start(
+            &app_handle,
+            &pool_handle,
+            &story,
+            predicted,
+            messages,
+            service,
+        );
+        match started.await {
+            Ok(request_id) => {
+                let state = app_handle.state::<PrefetchState>();
+                let kept = match state.pending.lock().unwrap().get_mut(&story) {
+                    Some(prefetch) if prefetch.token == own => {
+                        prefetch.request_id = Some(request_id.clone());
+                        true
+                    }
+                    _ => false,
+                };
+                // Claimed or replaced while the request was starting
+                if !kept {
+                    app_handle.state::<GenerationState>().abort(&request_id);
+                    if let Err(e) = store::delete(&pool_handle, &request_id).await {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Prefetch failed: {}", e),
+        }
+    });
+    app.state::<PrefetchState>().pending.lock().unwrap().insert(
+        story_id.to_string(),
+        Prefetch {
+            token,
+            action,
+            waiting,
+            request_id: None,
+        },
+    );
+    Ok(true)
+}
+
+async fn start(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    action: String,
+    messages: Vec<ChatMessage>,
+    service: String,
+) -> Result<String, String> {
+    let now = now_millis();
+    let mut request = GenerationRequest {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.to_string(),
+        service,
+        messages,
+        params: GenerationParams::default(),
+        partial: String::new(),
+        status: "streaming".to_string(),
+        error: None,
+        candidate_group: None,
+        prefetch_action: Some(action),
+        created_at: now,
+        updated_at: now,
+    };
+    let config = engine::prepare(pool, &mut request).await?;
+    let id = request.id.clone();
+    engine::spawn(app, pool, request, config).await?;
+    Ok(id)
+}
+
+/// Serve the story's prefetch if it was generated for `action` against the
+/// same prompt. The request is returned as if it had just been started and
+/// keeps streaming if it hasn't finished; anything else is discarded.
+pub async fn claim(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    action: &str,
+    messages: &[ChatMessage],
+) -> Result<Option<GenerationRequest>, String> {
+    let Some(prefetch) = app
+        .state::<PrefetchState>()
+        .pending
+        .lock()
+        .unwrap()
+        .remove(story_id)
+    else {
+        return Ok(None);
+    };
+    prefetch.waiting.abort();
+    let Some(request_id) = prefetch.request_id else {
+        return Ok(None);
+    };
+    let request = store::load(pool, &request_id).await?;
+    let hit = request.filter(|r| {
+        same_action(&prefetch.action, action)
+            && same_prompt(&r.messages, messages)
+            && matches!(r.status.as_str(), "streaming" | "completed")
+    });
+    let Some(mut request) = hit else {
+        app.state::<GenerationState>().abort(&request_id);
+        store::delete(pool, &request_id).await?;
+        return Ok(None);
+    };
+    sqlx::query("UPDATE generation_requests SET prefetch_action = NULL WHERE id = ?")
+        .bind(&request_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to serve prefetch: {}", e))?;
+    request.prefetch_action = None;
+    Ok(Some(request))
+}
+
+/// Stop and delete the story's prefetch, e.g. because the user started typing
+pub async fn discard(app: &AppHandle, pool: &SqlitePool, story_id: &str) -> Result<(), String> {
+    let prefetch = app
+        .state::<PrefetchState>()
+        .pending
+        .lock()
+        .unwrap()
+        .remove(story_id);
+    let Some(prefetch) = prefetch else {
+        return Ok(());
+    };
+    prefetch.waiting.abort();
+    if let Some(request_id) = prefetch.request_id {
+        app.state::<GenerationState>().abort(&request_id);
+        store::delete(pool, &request_id).await?;
+    }
+    Ok(())
+}
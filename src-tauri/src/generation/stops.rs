@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::llm::config::get_setting;
+
+/// Characters that end a sentence
+const SENTENCE_ENDS: [char; 4] = ['.', '!', '?', '…'];
+
+/// Characters that may close a sentence after its final punctuation
+const SENTENCE_CLOSERS: [char; 7] = ['"', '\'', '”', '’', ')', ']', '*'];
+
+/// How streamed narration is cut short, stored in `stop_sequence_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StopSettings {
+    /// User-defined strings that end the response where they appear
+    pub stop_sequences: Vec<String>,
+    /// Stop when the model starts a line speaking as the player
+    pub anti_impersonation: bool,
+    /// Cut a response truncated by the token limit back to its last full sentence
+    pub trim_incomplete: bool,
+}
+
+impl Default for StopSettings {
+    fn default() -> Self {
+        Self {
+            stop_sequences: Vec::new(),
+            anti_impersonation: true,
+            trim_incomplete: true,
+        }
+    }
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<StopSettings, String> {
+    Ok(get_setting(pool, "stop_sequence_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Why a response was cut, reported on `generation-finished`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    StopSequence,
+    Impersonation,
+    Truncated,
+}
+
+struct Pattern {
+    text: String,
+    reason: StopReason,
+}
+
+/// Applies stop patterns to a token stream. Text that could be the start of
+/// a pattern is held back until the next delta decides it, so nothing past
+/// a stop point ever reaches the frontend.
+pub struct StopFilter {
+    patterns: Vec<Pattern>,
+    trim_incomplete: bool,
+    held: String,
+    /// Set once a pattern matched; later deltas are dropped
+    pub stopped: Option<StopReason>,
+}
+
+impl StopFilter {
+    /// Build the filter for a story from the stop settings and, for
+    /// anti-impersonation, the story's player character
+    pub async fn load(pool: &SqlitePool, story_id: &str) -> Result<Self, String> {
+        let settings = load_settings(pool).await?;
+        let mut patterns: Vec<Pattern> = settings
+            .stop_sequences
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .map(|text| Pattern {
+                text,
+                reason: StopReason::StopSequence,
+            })
+            .collect();
+        if settings.anti_impersonation {
+            let player: Option<String> = sqlx::query(
+                "SELECT name FROM characters WHERE story_id = ? AND relationship = 'self' LIMIT 1",
+            )
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load player character: {}", e))?
+            .map(|row| row.get("name"));
+            let mut speakers = vec!["You".to_string(), "User".to_string()];
+            speakers.extend(player.filter(|n| !n.trim().is_empty()));
+            for speaker in speakers {
+                for prefix in ["\n", "\n**"] {
+                    patterns.push(Pattern {
+                        text: format!("{}{}:", prefix, speaker.trim()),
+                        reason: StopReason::Impersonation,
+                    });
+                }
+            }
+        }
+        Ok(Self {
+            patterns,
+            trim_incomplete: settings.trim_incomplete,
+            held: String::new(),
+            stopped: None,
+        })
+    }
+
+    /// Feed a delta and get back the text that is safe to pass on. After a
+    /// pattern matches, `stopped` is set and the stream should end.
+    pub fn push(&mut self, delta: &str) -> String {
+        if self.stopped.is_some() {
+            return String::new();
+        }
+        self.held.push_str(delta);
+
+        let found = self
+            .patterns
+            .iter()
+            .filter_map(|p| self.held.find(&p.text).map(|at| (at, p.reason)))
+            .min_by_key(|(at, _)| *at);
+        if let Some((at, reason)) = found {
+            self.stopped = Some(reason);
+            let mut safe = std::mem::take(&mut self.held);
+            safe.truncate(at);
+            return safe;
+        }
+
+        // Keep the longest tail that is still the start of some pattern
+        let keep_from = self
+            .held
+            .char_indices()
+            .map(|(i, _)| i)
+            .find(|&i| {
+                let tail = &self.held[i..];
+                self.patterns.iter().any(|p| p.text.starts_with(tail))
+            })
+            .unwrap_or(self.held.len());
+        let tail = self.held.split_off(keep_from);
+        std::mem::replace(&mut self.held, tail)
+    }
+
+    /// Release held-back text once the stream has ended
+    pub fn flush(&mut self) -> String {
+        std::mem::take(&mut self.held)
+    }
+
+    /// The final text of a response: trailing whitespace left by a stop is
+    /// dropped, and a response the provider cut off at the token limit ends
+    /// at its last complete sentence
+    pub fn finish(&mut self, text: &str, finish_reason: Option<&str>) -> String {
+        if self.stopped.is_some() {
+            return text.trim_end().to_string();
+        }
+        if self.trim_incomplete && finish_reason == Some("length") {
+            let trimmed = trim_to_sentence(text);
+            if trimmed.len() < text.trim_end().len() {
+                self.stopped = Some(StopReason::Truncated);
+            }
+            return trimmed.to_string();
+        }
+        text.to_string()
+    }
+}
+
+/// Cut text back to the end of its last complete sentence, keeping closing
+/// quotes and emphasis. Text without a sentence end is returned unchanged.
+pub fn trim_to_sentence(text: &str) -> &str {
+    let text = text.trim_end();
+    let Some((at, ch)) = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| SENTENCE_ENDS.contains(c))
+    else {
+        return text;
+    };
+    let mut end = at + ch.len_utf8();
+    for c in text[end..].chars() {
+        if !SENTENCE_CLOSERS.contains(&c) {
+            break;
+        }
+        end += c.len_utf8();
+    }
+    &text[..end]
+}
@@ -0,0 +1,144 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::types::GenerationRequest;
+use crate::db::now_millis;
+
+fn from_row(r: &SqliteRow) -> GenerationRequest {
+    let json = |column: &str| r.get::<String, _>(column);
+    GenerationRequest {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        service: r.get("service"),
+        messages: serde_json::from_str(&json("messages")).unwrap_or_default(),
+        params: serde_json::from_str(&json("params")).unwrap_or_default(),
+        partial: r.get("partial"),
+        status: r.get("status"),
+        error: r.get("error"),
+        candidate_group: r.get("candidate_group"),
+        prefetch_action: r.get("prefetch_action"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+/// Insert or update a request, stamping `updated_at`. The candidate group
+/// and prefetch marker are only written on insert, since they are cleared in
+/// place while the request may still be streaming.
+pub async fn save(pool: &SqlitePool, request: &mut GenerationRequest) -> Result<(), String> {
+    request.updated_at = now_millis();
+    sqlx::query(
+        "INSERT INTO generation_requests (id, story_id, service, messages, params, partial, \
+         status, error, candidate_group, prefetch_action, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET service = excluded.service, messages = excluded.messages, \
+         params = excluded.params, partial = excluded.partial, status = excluded.status, \
+         error = excluded.error, updated_at = excluded.updated_at",
+    )
+    .bind(&request.id)
+    .bind(&request.story_id)
+    .bind(&request.service)
+    .bind(serde_json::to_string(&request.messages).unwrap_or_else(|_| "[]".to_string()))
+    .bind(serde_json::to_string(&request.params).unwrap_or_else(|_| "{}".to_string()))
+    .bind(&request.partial)
+    .bind(&request.status)
+    .bind(&request.error)
+    .bind(&request.candidate_group)
+    .bind(&request.prefetch_action)
+    .bind(request.created_at)
+    .bind(request.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save generation request: {}", e))?;
+    Ok(())
+}
+
+/// Persist streamed text without rewriting the prompt
+pub async fn save_partial(pool: &SqlitePool, id: &str, partial: &str) -> Result<(), String> {
+    sqlx::query("UPDATE generation_requests SET partial = ?, updated_at = ? WHERE id = ?")
+        .bind(partial)
+        .bind(now_millis())
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save partial response: {}", e))?;
+    Ok(())
+}
+
+pub async fn load(pool: &SqlitePool, id: &str) -> Result<Option<GenerationRequest>, String> {
+    let row = sqlx::query("SELECT * FROM generation_requests WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load generation request: {}", e))?;
+    Ok(row.as_ref().map(from_row))
+}
+
+/// Ids of the requests in a candidate group
+pub async fn group_members(pool: &SqlitePool, group: &str) -> Result<Vec<String>, String> {
+    sqlx::query_scalar("SELECT id FROM generation_requests WHERE candidate_group = ?")
+        .bind(group)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load candidates: {}", e))
+}
+
+/// Interrupted requests, newest first, optionally for one story
+pub async fn list_interrupted(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<GenerationRequest>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM generation_requests WHERE status = 'interrupted' \
+         AND prefetch_action IS NULL AND (?1 IS NULL OR story_id = ?1) ORDER BY created_at DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load generation requests: {}", e))?;
+    Ok(rows.iter().map(from_row).collect())
+}
+
+/// Mark requests that were streaming before `started_at` as interrupted
+pub async fn mark_interrupted(pool: &SqlitePool, started_at: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE generation_requests SET status = 'interrupted' \
+         WHERE status = 'streaming' AND updated_at < ?",
+    )
+    .bind(started_at)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Delete prefetched requests that were never served
+pub async fn delete_prefetched(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query("DELETE FROM generation_requests WHERE prefetch_action IS NOT NULL")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete prefetched requests: {}", e))?;
+    Ok(())
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM generation_requests WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete generation request: {}", e))?;
+    Ok(())
+}
+
+/// Keep only the most recent `keep` finished requests
+pub async fn prune(pool: &SqlitePool, keep: i64) -> Result<(), String> {
+    sqlx::query(
+        "DELETE FROM generation_requests WHERE status IN ('completed', 'failed', 'cancelled') \
+         AND id NOT IN (SELECT id FROM generation_requests \
+         WHERE status IN ('completed', 'failed', 'cancelled') ORDER BY created_at DESC LIMIT ?)",
+    )
+    .bind(keep)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to prune generation requests: {}", e))?;
+    Ok(())
+}
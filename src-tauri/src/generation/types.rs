@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use super::stops::StopReason;
+use crate::llm::client::ChatMessage;
+
+/// Sampling parameters a generation was started with, kept so a resumed
+/// request samples the same way. The connection comes from the service.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GenerationParams {
+    /// Model profile supplying connection and sampler, e.g. for a candidate
+    /// on a different provider; `None` uses the service's resolution
+    pub profile_id: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+    /// Sampling seed; picked at random when the provider supports seeds
+    pub seed: Option<i64>,
+}
+
+/// A streamed narrative request, persisted while it runs so it survives the
+/// app being killed mid-response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationRequest {
+    pub id: String,
+    pub story_id: String,
+    /// Service whose preset supplies the connection, e.g. 'narrative'
+    pub service: String,
+    pub messages: Vec<ChatMessage>,
+    pub params: GenerationParams,
+    /// Text streamed so far
+    pub partial: String,
+    /// 'streaming' | 'completed' | 'failed' | 'cancelled' | 'interrupted'
+    pub status: String,
+    pub error: Option<String>,
+    /// Shared by the candidates of one `generate_candidates` call
+    pub candidate_group: Option<String>,
+    /// Predicted action an idle prefetch generated this for; cleared once
+    /// the user picks it
+    pub prefetch_action: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Requests started together by `generate_candidates`, one per pane
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateSet {
+    pub group_id: String,
+    pub request_ids: Vec<String>,
+}
+
+/// How `resume_generation` continues an interrupted request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResumeMode {
+    /// Send the partial text back as the start of the reply and keep going
+    Continue,
+    /// Discard the partial text and generate again
+    Retry,
+}
+
+/// Emitted on `generation-chunk` for every streamed delta
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationChunk {
+    pub request_id: String,
+    pub delta: String,
+}
+
+/// Emitted on `generation-finished` when a request stops streaming
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationFinished {
+    pub request_id: String,
+    pub story_id: String,
+    pub status: String,
+    pub text: String,
+    pub error: Option<String>,
+    /// Seed the response was sampled with, when the provider supports seeds
+    pub seed: Option<i64>,
+    /// Set when stop handling cut the response; `text` is then the final
+    /// text and replaces what was streamed
+    pub stop_reason: Option<StopReason>,
+}
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::types::{GrammarIssue, GrammarReport, GrammarSettings};
+use super::{harper, languagetool};
+use crate::db::DbState;
+use crate::llm::config::get_setting;
+use crate::prose::entities::{load_entities, name_words};
+
+async fn load_settings(pool: &SqlitePool) -> Result<GrammarSettings, String> {
+    Ok(get_setting(pool, "grammar_check_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Lowercased words from the editor checker's custom dictionary
+async fn custom_words(pool: &SqlitePool) -> Result<HashSet<String>, String> {
+    let words: Vec<String> = get_setting(pool, "harper_custom_dictionary_words")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    Ok(words.into_iter().map(|w| w.to_lowercase()).collect())
+}
+
+/// Check user-written text for grammar and spelling problems.
+///
+/// English is checked locally with Harper; other languages (or English when
+/// preferred) go to the configured LanguageTool-compatible server. With a
+/// `story_id`, spelling issues on the story's character and lorebook names are dropped,
+/// as are words in the custom dictionary.
+#[tauri::command]
+pub async fn check_text(
+    db: State<'_, DbState>,
+    text: String,
+    lang: String,
+    story_id: Option<String>,
+) -> Result<GrammarReport, String> {
+    let settings = load_settings(db.pool()).await?;
+
+    let (engine, mut issues) = match harper::dialect(&lang) {
+        Some(dialect) if !settings.prefer_language_tool => {
+            let issues = tokio::task::spawn_blocking(move || harper::check(&text, dialect))
+                .await
+                .map_err(|e| format!("Grammar check failed: {}", e))?;
+            ("harper", issues)
+        }
+        _ => (
+            "languagetool",
+            languagetool::check(&settings, &text, &lang).await?,
+        ),
+    };
+
+    let mut vocabulary = custom_words(db.pool()).await?;
+    if let Some(story_id) = story_id {
+        let names = name_words(&load_entities(db.pool(), &story_id).await?);
+        vocabulary.extend(names.into_keys());
+    }
+    issues.retain(|issue: &GrammarIssue| {
+        issue.kind != "spelling" || !vocabulary.contains(&issue.text.trim().to_lowercase())
+    });
+
+    Ok(GrammarReport {
+        engine: engine.to_string(),
+        issues,
+    })
+}
@@ -0,0 +1,65 @@
+use harper_core::linting::{LintGroup, Linter, Suggestion};
+use harper_core::spell::FstDictionary;
+use harper_core::{Dialect, Document};
+
+use super::types::GrammarIssue;
+
+/// Map a language tag to a Harper dialect; Harper only checks English
+pub fn dialect(lang: &str) -> Option<Dialect> {
+    let lang = lang.to_ascii_lowercase().replace('_', "-");
+    match lang.as_str() {
+        "en" | "en-us" => Some(Dialect::American),
+        "en-gb" | "en-uk" => Some(Dialect::British),
+        "en-ca" => Some(Dialect::Canadian),
+        "en-au" | "en-nz" => Some(Dialect::Australian),
+        _ if lang.starts_with("en-") => Some(Dialect::American),
+        _ => None,
+    }
+}
+
+/// Lint `text` with Harper's curated rule set.
+///
+/// Harper types are not `Send`, so callers run this on a blocking thread.
+pub fn check(text: &str, dialect: Dialect) -> Vec<GrammarIssue> {
+    let chars: Vec<char> = text.chars().collect();
+    let document = Document::new_plain_english_curated(text);
+    let mut linter = LintGroup::new_curated(FstDictionary::curated(), dialect);
+    // Same relaxations as the editor's checker: prose may be stylistically
+    // lowercase and long sentences are often deliberate
+    linter
+        .config
+        .set_rule_enabled("SentenceCapitalization", false);
+    linter.config.set_rule_enabled("LongSentences", false);
+
+    let mut lints = linter.lint(&document);
+    lints.sort_by_key(|l| l.span.start);
+
+    lints
+        .into_iter()
+        .map(|lint| {
+            let end = lint.span.end.min(chars.len());
+            let start = lint.span.start.min(end);
+            let flagged: String = chars[start..end].iter().collect();
+            let suggestions = lint
+                .suggestions
+                .iter()
+                .map(|s| match s {
+                    Suggestion::ReplaceWith(with) => with.iter().collect(),
+                    Suggestion::InsertAfter(after) => {
+                        format!("{}{}", flagged, after.iter().collect::<String>())
+                    }
+                    Suggestion::Remove => String::new(),
+                })
+                .collect();
+            GrammarIssue {
+                start,
+                end,
+                text: flagged,
+                message: lint.message,
+                kind: lint.lint_kind.to_string_key().to_lowercase(),
+                rule: None,
+                suggestions,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+
+use super::types::{GrammarIssue, GrammarSettings};
+
+/// Suggestions kept per issue; LanguageTool can return dozens for spelling
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct CheckResponse {
+    matches: Vec<Match>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Match {
+    message: String,
+    /// UTF-16 offsets, as LanguageTool is a Java service
+    offset: usize,
+    length: usize,
+    #[serde(default)]
+    replacements: Vec<Replacement>,
+    rule: Rule,
+}
+
+#[derive(Debug, Deserialize)]
+struct Replacement {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    #[serde(default)]
+    issue_type: Option<String>,
+}
+
+/// Convert a UTF-16 offset into `text` to a character index
+fn utf16_to_char(text: &str, offset: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in text.chars().enumerate() {
+        if units >= offset {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    text.chars().count()
+}
+
+/// Check `text` against a LanguageTool-compatible `/v2/check` endpoint
+pub async fn check(
+    settings: &GrammarSettings,
+    text: &str,
+    lang: &str,
+) -> Result<Vec<GrammarIssue>, String> {
+    let base = settings
+        .language_tool_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "No grammar checker available for '{}': configure a LanguageTool server",
+                lang
+            )
+        })?;
+
+    let mut form = vec![("text", text.to_string()), ("language", lang.to_string())];
+    if let (Some(user), Some(key)) = (
+        &settings.language_tool_username,
+        &settings.language_tool_api_key,
+    ) {
+        form.push(("username", user.clone()));
+        form.push(("apiKey", key.clone()));
+    }
+
+    let response: CheckResponse = reqwest::Client::new()
+        .post(format!("{}/v2/check", base.trim_end_matches('/')))
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Grammar check request failed: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Grammar check request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid grammar check response: {}", e))?;
+
+    Ok(response
+        .matches
+        .into_iter()
+        .map(|m| {
+            let start = utf16_to_char(text, m.offset);
+            let end = utf16_to_char(text, m.offset + m.length);
+            GrammarIssue {
+                start,
+                end,
+                text: text.chars().skip(start).take(end - start).collect(),
+                message: m.message,
+                kind: m
+                    .rule
+                    .issue_type
+                    .map(|t| match t.as_str() {
+                        "misspelling" => "spelling".to_string(),
+                        "typographical" => "punctuation".to_string(),
+                        _ => t,
+                    })
+                    .unwrap_or_else(|| "grammar".to_string()),
+                rule: Some(m.rule.id),
+                suggestions: m
+                    .replacements
+                    .into_iter()
+                    .take(MAX_SUGGESTIONS)
+                    .map(|r| r.value)
+                    .collect(),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod harper;
+pub mod languagetool;
+pub mod types;
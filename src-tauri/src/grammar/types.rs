@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// User settings for grammar checking (`grammar_check_settings`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GrammarSettings {
+    /// Base URL of a LanguageTool-compatible server, e.g. a self-hosted instance.
+    /// Required for languages other than English.
+    pub language_tool_url: Option<String>,
+    pub language_tool_username: Option<String>,
+    pub language_tool_api_key: Option<String>,
+    /// Send English text to LanguageTool instead of the built-in checker
+    pub prefer_language_tool: bool,
+}
+
+/// A grammar or spelling problem in checked text
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarIssue {
+    /// Character offsets into the checked text
+    pub start: usize,
+    pub end: usize,
+    /// The flagged text
+    pub text: String,
+    pub message: String,
+    /// 'spelling', 'grammar', 'punctuation', 'style', ...
+    pub kind: String,
+    /// Checker-specific rule id, for ignoring a rule
+    pub rule: Option<String>,
+    /// Replacements for the whole flagged span, best first
+    pub suggestions: Vec<String>,
+}
+
+/// Result of `check_text`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarReport {
+    /// 'harper' or 'languagetool'
+    pub engine: String,
+    pub issues: Vec<GrammarIssue>,
+}
@@ -0,0 +1,36 @@
+use tauri::State;
+use tokio::sync::Mutex;
+
+use super::detect::{detect, memory};
+use super::types::HardwareCapabilities;
+
+/// The last detection; GPUs and drivers don't change while the app runs
+#[derive(Default)]
+pub struct HardwareState {
+    cached: Mutex<Option<HardwareCapabilities>>,
+}
+
+impl HardwareState {
+    /// Capabilities from the cache, detecting them the first time or when
+    /// `refresh` is set. Free memory is re-read either way.
+    pub async fn capabilities(&self, refresh: bool) -> HardwareCapabilities {
+        let mut cached = self.cached.lock().await;
+        match cached.as_mut() {
+            Some(capabilities) if !refresh => {
+                capabilities.memory = memory();
+                capabilities.clone()
+            }
+            _ => cached.insert(detect().await).clone(),
+        }
+    }
+}
+
+/// CPU features, memory, GPUs and the backends this machine can run local
+/// models on, with recommended starting settings
+#[tauri::command]
+pub async fn get_hardware_capabilities(
+    state: State<'_, HardwareState>,
+    refresh: Option<bool>,
+) -> Result<HardwareCapabilities, String> {
+    Ok(state.capabilities(refresh.unwrap_or(false)).await)
+}
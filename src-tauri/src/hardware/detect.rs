@@ -0,0 +1,304 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use sysinfo::System;
+use tokio::process::Command;
+
+use super::types::{
+    BackendSupport, CpuInfo, GpuInfo, HardwareCapabilities, MemoryInfo, Recommendation,
+};
+use crate::db::now_millis;
+
+/// A driver tool that hangs is given up on after this long
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MIB: u64 = 1024 * 1024;
+
+/// Share of the free memory a model may take; the rest is left to the OS,
+/// the app and the webview
+const BUDGET_SHARE: f64 = 0.7;
+
+async fn run(program: &str, args: &[&str]) -> Option<String> {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let output = tokio::time::timeout(PROBE_TIMEOUT, child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// SIMD extensions the CPU reports at runtime
+fn cpu_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = Vec::new();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        macro_rules! probe {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_x86_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        probe!(
+            "sse4.2",
+            "avx",
+            "avx2",
+            "fma",
+            "f16c",
+            "avx512f",
+            "avx512vnni"
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        macro_rules! probe {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_aarch64_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        probe!("neon", "dotprod", "i8mm", "fp16", "sve");
+    }
+    features.into_iter().map(str::to_string).collect()
+}
+
+/// NVIDIA GPUs as `nvidia-smi` lists them
+async fn cuda_gpus() -> Vec<GpuInfo> {
+    let Some(out) = run(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,memory.total,memory.free,driver_version",
+            "--format=csv,noheader,nounits",
+        ],
+    )
+    .await
+    else {
+        return Vec::new();
+    };
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let mib = |i: usize| fields.get(i)?.parse::<u64>().ok().map(|m| m * MIB);
+            Some(GpuInfo {
+                name: fields.first().filter(|n| !n.is_empty())?.to_string(),
+                backend: "cuda".to_string(),
+                vram_bytes: mib(1),
+                free_vram_bytes: mib(2),
+                unified_memory: false,
+                driver: fields.get(3).map(|d| d.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Whether the Vulkan loader library is installed
+fn vulkan_loader() -> bool {
+    #[cfg(target_os = "windows")]
+    let candidates: Vec<std::path::PathBuf> = std::env::var_os("SystemRoot")
+        .map(|root| vec![std::path::Path::new(&root).join("System32/vulkan-1.dll")])
+        .unwrap_or_default();
+    #[cfg(target_os = "android")]
+    let candidates: Vec<std::path::PathBuf> = vec![
+        "/system/lib64/libvulkan.so".into(),
+        "/system/lib/libvulkan.so".into(),
+    ];
+    #[cfg(all(unix, not(target_os = "android"), not(target_vendor = "apple")))]
+    let candidates: Vec<std::path::PathBuf> = [
+        "/usr/lib",
+        "/usr/lib64",
+        "/usr/lib/x86_64-linux-gnu",
+        "/usr/lib/aarch64-linux-gnu",
+        "/usr/local/lib",
+    ]
+    .iter()
+    .map(|dir| std::path::Path::new(dir).join("libvulkan.so.1"))
+    .collect();
+    #[cfg(target_vendor = "apple")]
+    let candidates: Vec<std::path::PathBuf> = Vec::new();
+    candidates.iter().any(|path| path.exists())
+}
+
+/// Vulkan devices from `vulkaninfo --summary`, when the tool is installed
+async fn vulkan_gpus() -> Vec<GpuInfo> {
+    let Some(out) = run("vulkaninfo", &["--summary"]).await else {
+        return Vec::new();
+    };
+    let mut gpus: Vec<GpuInfo> = Vec::new();
+    for line in out.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "deviceName" => gpus.push(GpuInfo {
+                name: value.trim().to_string(),
+                backend: "vulkan".to_string(),
+                vram_bytes: None,
+                free_vram_bytes: None,
+                unified_memory: false,
+                driver: None,
+            }),
+            "driverInfo" => {
+                if let Some(gpu) = gpus.last_mut() {
+                    gpu.driver = Some(value.trim().to_string());
+                }
+            }
+            "deviceType" if value.contains("CPU") => {
+                // Software rasterizers (llvmpipe) are no help
+                gpus.pop();
+            }
+            _ => {}
+        }
+    }
+    gpus
+}
+
+/// Total and currently available system memory
+pub fn memory() -> MemoryInfo {
+    let mut system = System::new();
+    system.refresh_memory();
+    MemoryInfo {
+        total_bytes: system.total_memory(),
+        available_bytes: system.available_memory(),
+    }
+}
+
+/// Memory a model running on the CPU (or sharing system memory) may take
+pub fn ram_budget(memory: &MemoryInfo) -> u64 {
+    (memory.available_bytes as f64 * BUDGET_SHARE) as u64
+}
+
+fn backend(backend: &str, reason: Option<&str>) -> BackendSupport {
+    BackendSupport {
+        backend: backend.to_string(),
+        available: reason.is_none(),
+        reason: reason.map(str::to_string),
+    }
+}
+
+/// Backend, memory budget, threads and batch size to start from
+fn recommend(
+    cpu: &CpuInfo,
+    memory: &MemoryInfo,
+    gpus: &[GpuInfo],
+    backends: &[BackendSupport],
+) -> Recommendation {
+    let usable = |name: &str| backends.iter().any(|b| b.backend == name && b.available);
+    let ram_budget = ram_budget(memory);
+    let cuda = gpus
+        .iter()
+        .filter(|g| g.backend == "cuda")
+        .max_by_key(|g| g.free_vram_bytes.or(g.vram_bytes));
+    let (backend, memory_budget_bytes) = match cuda {
+        Some(gpu) if usable("cuda") => (
+            "cuda",
+            gpu.free_vram_bytes
+                .or(gpu.vram_bytes.map(|v| v / 10 * 9))
+                .unwrap_or(0),
+        ),
+        _ if usable("metal") => ("metal", ram_budget),
+        _ if usable("vulkan") => ("vulkan", ram_budget),
+        _ => ("cpu", ram_budget),
+    };
+    let threads = cpu
+        .physical_cores
+        .unwrap_or(cpu.logical_cores / 2)
+        .clamp(1, 16);
+    let batch_size = match backend {
+        "cpu" if memory_budget_bytes < 4096 * MIB => 128,
+        "cpu" => 256,
+        _ => 512,
+    };
+    Recommendation {
+        backend: backend.to_string(),
+        memory_budget_bytes,
+        threads,
+        batch_size,
+    }
+}
+
+pub async fn detect() -> HardwareCapabilities {
+    let mut system = System::new();
+    system.refresh_cpu_all();
+    let features = cpu_features();
+    let cpu = CpuInfo {
+        brand: system
+            .cpus()
+            .first()
+            .map(|c| c.brand().trim().to_string())
+            .unwrap_or_default(),
+        physical_cores: System::physical_core_count(),
+        logical_cores: system.cpus().len().max(1),
+        avx2: features.iter().any(|f| f == "avx2"),
+        neon: features.iter().any(|f| f == "neon"),
+        features,
+    };
+    let memory = memory();
+
+    let mut gpus = cuda_gpus().await;
+    let apple_silicon = cfg!(all(target_vendor = "apple", target_arch = "aarch64"));
+    if apple_silicon {
+        gpus.push(GpuInfo {
+            name: cpu.brand.clone(),
+            backend: "metal".to_string(),
+            vram_bytes: None,
+            free_vram_bytes: None,
+            unified_memory: true,
+            driver: None,
+        });
+    }
+    let loader = vulkan_loader();
+    if loader {
+        gpus.extend(vulkan_gpus().await);
+    }
+
+    let has = |name: &str| gpus.iter().any(|g| g.backend == name);
+    let backends = vec![
+        backend(
+            "cuda",
+            (!has("cuda")).then_some("No NVIDIA GPU with a working driver was found"),
+        ),
+        backend(
+            "metal",
+            (!apple_silicon).then_some("Metal is only used on Apple silicon Macs"),
+        ),
+        backend(
+            "vulkan",
+            if !loader {
+                Some("The Vulkan loader isn't installed")
+            } else if !has("vulkan") && !has("cuda") {
+                Some("No Vulkan GPU was found")
+            } else {
+                None
+            },
+        ),
+        backend(
+            "cpu",
+            (cpu.features.is_empty() && cfg!(target_arch = "x86_64"))
+                .then_some("The CPU lacks the SIMD extensions local models need"),
+        ),
+    ];
+    let recommended = recommend(&cpu, &memory, &gpus, &backends);
+
+    HardwareCapabilities {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpu,
+        memory,
+        gpus,
+        backends,
+        recommended,
+        detected_at: now_millis(),
+    }
+}
@@ -0,0 +1,10 @@
+//! What the machine can run locally: CPU features, memory and GPUs, and a
+//! backend, thread count and memory budget picked from them. Local-model
+//! subsystems size their work from this; the UI greys out what isn't
+//! supported.
+
+pub mod commands;
+pub mod detect;
+pub mod types;
+
+pub use commands::HardwareState;
@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareCapabilities {
+    pub os: String,
+    pub arch: String,
+    pub cpu: CpuInfo,
+    pub memory: MemoryInfo,
+    pub gpus: Vec<GpuInfo>,
+    /// Every inference backend with whether this machine can use it
+    pub backends: Vec<BackendSupport>,
+    pub recommended: Recommendation,
+    pub detected_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuInfo {
+    pub brand: String,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    /// SIMD extensions found at runtime, e.g. `avx2`, `fma`, `neon`
+    pub features: Vec<String>,
+    pub avx2: bool,
+    pub neon: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+    pub name: String,
+    /// `cuda`, `metal` or `vulkan`: the backend it was found through
+    pub backend: String,
+    pub vram_bytes: Option<u64>,
+    pub free_vram_bytes: Option<u64>,
+    /// Shares system memory (Apple silicon); `vram_bytes` is then unset
+    pub unified_memory: bool,
+    pub driver: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendSupport {
+    /// `cuda`, `metal`, `vulkan` or `cpu`
+    pub backend: String,
+    pub available: bool,
+    /// Why it isn't, for the UI to show
+    pub reason: Option<String>,
+}
+
+/// Settings a local model should start from on this machine
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    pub backend: String,
+    /// Memory a model and its context can take without starving the system
+    pub memory_budget_bytes: u64,
+    pub threads: usize,
+    pub batch_size: u32,
+}
@@ -0,0 +1,378 @@
+//! A CLIP vision encoder in plain Rust, enough to turn a portrait into an
+//! embedding for likeness checks. Weights are a Hugging Face CLIP model in
+//! safetensors format (`CLIPModel`, `CLIPVisionModelWithProjection` or a
+//! bare `CLIPVisionModel`); sizes are read from the weights, so base and
+//! large models both work.
+
+use image::imageops::{self, FilterType};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Normalization CLIP was trained with
+const MEAN: [f32; 3] = [0.481_454_66, 0.457_827_5, 0.408_210_73];
+const STD: [f32; 3] = [0.268_629_54, 0.261_302_6, 0.275_777_1];
+
+const HEAD_SIZE: usize = 64;
+const NORM_EPSILON: f32 = 1e-5;
+
+/// Model manager kind CLIP weights are installed under
+pub const MODEL_KIND: &str = "clip";
+
+/// The last model loaded, kept because loading takes seconds
+static LOADED: Mutex<Option<(PathBuf, Arc<ClipVision>)>> = Mutex::new(None);
+
+#[derive(Deserialize)]
+struct TensorInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: (usize, usize),
+}
+
+/// Tensors of a safetensors file, converted to f32
+struct Weights {
+    tensors: HashMap<String, (Vec<usize>, Vec<f32>)>,
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    let value = match (exponent, mantissa) {
+        (0, 0) => sign,
+        // Subnormal: mantissa × 2^-24
+        (0, _) => return f32::from_bits(sign | 1.0f32.to_bits()) * mantissa as f32 / 16_777_216.0,
+        (0x1f, _) => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(value)
+}
+
+impl Weights {
+    fn load(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read CLIP model: {}", e))?;
+        let invalid = || "The CLIP model isn't a safetensors file".to_string();
+        let header_len =
+            u64::from_le_bytes(bytes.get(..8).ok_or_else(invalid)?.try_into().unwrap()) as usize;
+        let header = bytes.get(8..8 + header_len).ok_or_else(invalid)?;
+        let data = &bytes[8 + header_len..];
+        let mut infos: HashMap<String, serde_json::Value> =
+            serde_json::from_slice(header).map_err(|_| invalid())?;
+        infos.remove("__metadata__");
+
+        let mut tensors = HashMap::new();
+        for (name, info) in infos {
+            let info: TensorInfo = serde_json::from_value(info).map_err(|_| invalid())?;
+            let raw = data
+                .get(info.data_offsets.0..info.data_offsets.1)
+                .ok_or_else(invalid)?;
+            let values = match info.dtype.as_str() {
+                "F32" => raw
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+                "F16" => raw
+                    .chunks_exact(2)
+                    .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+                    .collect(),
+                "BF16" => raw
+                    .chunks_exact(2)
+                    .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+                    .collect(),
+                // Integer tensors (position ids) aren't needed
+                _ => continue,
+            };
+            tensors.insert(name, (info.shape, values));
+        }
+        Ok(Self { tensors })
+    }
+
+    fn take(&mut self, name: &str) -> Result<(Vec<usize>, Vec<f32>), String> {
+        self.tensors
+            .remove(name)
+            .ok_or_else(|| format!("The CLIP model is missing {}", name))
+    }
+
+    fn linear(&mut self, prefix: &str) -> Result<Linear, String> {
+        let (shape, weight) = self.take(&format!("{}.weight", prefix))?;
+        let bias = self
+            .tensors
+            .remove(&format!("{}.bias", prefix))
+            .map(|(_, bias)| bias);
+        Ok(Linear {
+            outputs: shape[0],
+            inputs: shape[1..].iter().product(),
+            weight,
+            bias,
+        })
+    }
+
+    fn norm(&mut self, prefix: &str) -> Result<Norm, String> {
+        Ok(Norm {
+            weight: self.take(&format!("{}.weight", prefix))?.1,
+            bias: self.take(&format!("{}.bias", prefix))?.1,
+        })
+    }
+}
+
+struct Linear {
+    weight: Vec<f32>,
+    bias: Option<Vec<f32>>,
+    inputs: usize,
+    outputs: usize,
+}
+
+impl Linear {
+    /// `rows` input vectors in, `rows` output vectors out
+    fn forward(&self, x: &[f32], rows: usize) -> Vec<f32> {
+        let mut out = Vec::with_capacity(rows * self.outputs);
+        for row in x.chunks_exact(self.inputs).take(rows) {
+            for (o, weights) in self.weight.chunks_exact(self.inputs).enumerate() {
+                let dot: f32 = row.iter().zip(weights).map(|(a, b)| a * b).sum();
+                out.push(dot + self.bias.as_ref().map_or(0.0, |bias| bias[o]));
+            }
+        }
+        out
+    }
+}
+
+struct Norm {
+    weight: Vec<f32>,
+    bias: Vec<f32>,
+}
+
+impl Norm {
+    fn forward(&self, x: &[f32]) -> Vec<f32> {
+        let hidden = self.weight.len();
+        let mut out = Vec::with_capacity(x.len());
+        for row in x.chunks_exact(hidden) {
+            let mean = row.iter().sum::<f32>() / hidden as f32;
+            let variance = row.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / hidden as f32;
+            let scale = 1.0 / (variance + NORM_EPSILON).sqrt();
+            for (i, v) in row.iter().enumerate() {
+                out.push((v - mean) * scale * self.weight[i] + self.bias[i]);
+            }
+        }
+        out
+    }
+}
+
+struct Layer {
+    norm1: Norm,
+    query: Linear,
+    key: Linear,
+    value: Linear,
+    attention_out: Linear,
+    norm2: Norm,
+    fc1: Linear,
+    fc2: Linear,
+}
+
+impl Layer {
+    fn forward(&self, x: &mut [f32], tokens: usize, hidden: usize) {
+        let h = self.norm1.forward(x);
+        let scale = (HEAD_SIZE as f32).powf(-0.5);
+        let q = self.query.forward(&h, tokens);
+        let k = self.key.forward(&h, tokens);
+        let v = self.value.forward(&h, tokens);
+        let mut attended = vec![0.0; tokens * hidden];
+        for head in 0..hidden / HEAD_SIZE {
+            let at = |t: usize| t * hidden + head * HEAD_SIZE;
+            for t in 0..tokens {
+                let query = &q[at(t)..at(t) + HEAD_SIZE];
+                let mut scores: Vec<f32> = (0..tokens)
+                    .map(|s| {
+                        let key = &k[at(s)..at(s) + HEAD_SIZE];
+                        query.iter().zip(key).map(|(a, b)| a * b).sum::<f32>() * scale
+                    })
+                    .collect();
+                let max = scores.iter().copied().fold(f32::MIN, f32::max);
+                let mut total = 0.0;
+                for score in &mut scores {
+                    *score = (*score - max).exp();
+                    total += *score;
+                }
+                let out = &mut attended[at(t)..at(t) + HEAD_SIZE];
+                for (s, score) in scores.iter().enumerate() {
+                    let value = &v[at(s)..at(s) + HEAD_SIZE];
+                    for (o, v) in out.iter_mut().zip(value) {
+                        *o += score / total * v;
+                    }
+                }
+            }
+        }
+        for (x, a) in x
+            .iter_mut()
+            .zip(self.attention_out.forward(&attended, tokens))
+        {
+            *x += a;
+        }
+
+        let h = self.norm2.forward(x);
+        let mut inner = self.fc1.forward(&h, tokens);
+        // quick_gelu
+        for v in &mut inner {
+            *v *= 1.0 / (1.0 + (-1.702 * *v).exp());
+        }
+        for (x, m) in x.iter_mut().zip(self.fc2.forward(&inner, tokens)) {
+            *x += m;
+        }
+    }
+}
+
+pub struct ClipVision {
+    hidden: usize,
+    patch: usize,
+    image_size: usize,
+    class_embedding: Vec<f32>,
+    patch_embedding: Linear,
+    position_embedding: Vec<f32>,
+    pre_norm: Norm,
+    layers: Vec<Layer>,
+    post_norm: Norm,
+    projection: Option<Linear>,
+}
+
+impl ClipVision {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut weights = Weights::load(path)?;
+        let vision = "vision_model";
+        let (_, class_embedding) =
+            weights.take(&format!("{}.embeddings.class_embedding", vision))?;
+        let (patch_shape, _) = weights
+            .tensors
+            .get(&format!("{}.embeddings.patch_embedding.weight", vision))
+            .ok_or("The CLIP model has no vision encoder")?;
+        let patch = patch_shape[2];
+        let patch_embedding = weights.linear(&format!("{}.embeddings.patch_embedding", vision))?;
+        let (position_shape, position_embedding) =
+            weights.take(&format!("{}.embeddings.position_embedding.weight", vision))?;
+        let grid = ((position_shape[0] - 1) as f64).sqrt() as usize;
+
+        let mut layers = Vec::new();
+        let layer_prefix = |i: usize| format!("{}.encoder.layers.{}", vision, i);
+        while weights.tensors.contains_key(&format!(
+            "{}.layer_norm1.weight",
+            layer_prefix(layers.len())
+        )) {
+            let prefix = layer_prefix(layers.len());
+            layers.push(Layer {
+                norm1: weights.norm(&format!("{}.layer_norm1", prefix))?,
+                query: weights.linear(&format!("{}.self_attn.q_proj", prefix))?,
+                key: weights.linear(&format!("{}.self_attn.k_proj", prefix))?,
+                value: weights.linear(&format!("{}.self_attn.v_proj", prefix))?,
+                attention_out: weights.linear(&format!("{}.self_attn.out_proj", prefix))?,
+                norm2: weights.norm(&format!("{}.layer_norm2", prefix))?,
+                fc1: weights.linear(&format!("{}.mlp.fc1", prefix))?,
+                fc2: weights.linear(&format!("{}.mlp.fc2", prefix))?,
+            });
+        }
+        Ok(Self {
+            hidden: class_embedding.len(),
+            patch,
+            image_size: grid * patch,
+            class_embedding,
+            patch_embedding,
+            position_embedding,
+            // Spelled this way in the Hugging Face weights
+            pre_norm: weights.norm(&format!("{}.pre_layrnorm", vision))?,
+            layers,
+            post_norm: weights.norm(&format!("{}.post_layernorm", vision))?,
+            projection: weights.linear("visual_projection").ok(),
+        })
+    }
+
+    /// The last model loaded from `path`, loading it if needed
+    pub fn cached(path: &Path) -> Result<Arc<Self>, String> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((loaded_path, model)) = loaded.as_ref() {
+            if loaded_path == path {
+                return Ok(model.clone());
+            }
+        }
+        let model = Arc::new(Self::load(path)?);
+        *loaded = Some((path.to_path_buf(), model.clone()));
+        Ok(model)
+    }
+
+    /// Resize the short side to the model's input size, crop the middle
+    /// and normalize, channel by channel
+    fn pixels(&self, image: &[u8]) -> Result<Vec<f32>, String> {
+        let image = image::load_from_memory(image)
+            .map_err(|e| format!("Can't read the image for comparison: {}", e))?
+            .to_rgb8();
+        let size = self.image_size as u32;
+        let (width, height) = image.dimensions();
+        let scale = size as f32 / width.min(height).max(1) as f32;
+        let resized = imageops::resize(
+            &image,
+            ((width as f32 * scale).round() as u32).max(size),
+            ((height as f32 * scale).round() as u32).max(size),
+            FilterType::CatmullRom,
+        );
+        let left = (resized.width() - size) / 2;
+        let top = (resized.height() - size) / 2;
+        let cropped = imageops::crop_imm(&resized, left, top, size, size).to_image();
+
+        let mut out = vec![0.0; 3 * self.image_size * self.image_size];
+        for (x, y, pixel) in cropped.enumerate_pixels() {
+            for c in 0..3 {
+                let at = (c * self.image_size + y as usize) * self.image_size + x as usize;
+                out[at] = (pixel[c] as f32 / 255.0 - MEAN[c]) / STD[c];
+            }
+        }
+        Ok(out)
+    }
+
+    /// Unit-length embedding of an encoded image
+    pub fn embed(&self, image: &[u8]) -> Result<Vec<f32>, String> {
+        let pixels = self.pixels(image)?;
+        let grid = self.image_size / self.patch;
+        let tokens = grid * grid + 1;
+
+        // Patches flattened in the conv weight's channel, row, column order
+        let mut patches = Vec::with_capacity(grid * grid * self.patch_embedding.inputs);
+        for gy in 0..grid {
+            for gx in 0..grid {
+                for c in 0..3 {
+                    for py in 0..self.patch {
+                        let row = (c * self.image_size + gy * self.patch + py) * self.image_size;
+                        let start = row + gx * self.patch;
+                        patches.extend_from_slice(&pixels[start..start + self.patch]);
+                    }
+                }
+            }
+        }
+        let mut x = self.class_embedding.clone();
+        x.extend(self.patch_embedding.forward(&patches, grid * grid));
+        for (x, p) in x.iter_mut().zip(&self.position_embedding) {
+            *x += p;
+        }
+
+        let mut x = self.pre_norm.forward(&x);
+        for layer in &self.layers {
+            layer.forward(&mut x, tokens, self.hidden);
+        }
+        let pooled = self.post_norm.forward(&x[..self.hidden]);
+        let mut embedding = match &self.projection {
+            Some(projection) => projection.forward(&pooled, 1),
+            None => pooled,
+        };
+        let length = embedding
+            .iter()
+            .map(|v| v * v)
+            .sum::<f32>()
+            .sqrt()
+            .max(f32::EPSILON);
+        for v in &mut embedding {
+            *v /= length;
+        }
+        Ok(embedding)
+    }
+}
+
+/// Cosine similarity of two unit-length embeddings
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
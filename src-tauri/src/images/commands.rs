@@ -0,0 +1,265 @@
+use tauri::State;
+
+use super::consistency::{self, PortraitCandidate};
+use super::job::ImageJobPayload;
+use super::metadata;
+use super::providers;
+use super::scene;
+use super::sprites;
+use super::store;
+use super::types::{
+    CharacterSprite, GeneratedImage, IllustrationSettings, ImageMetadata, ImageProviderConfig,
+    ImageRequest, Mood,
+};
+use super::variation;
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+use crate::models::ModelManager;
+
+#[tauri::command]
+pub async fn list_image_providers(
+    db: State<'_, DbState>,
+) -> Result<Vec<ImageProviderConfig>, String> {
+    store::list(db.pool()).await
+}
+
+/// Create or update an image provider, returning its id
+#[tauri::command]
+pub async fn save_image_provider(
+    db: State<'_, DbState>,
+    provider: ImageProviderConfig,
+) -> Result<String, String> {
+    store::save(db.pool(), provider).await
+}
+
+#[tauri::command]
+pub async fn delete_image_provider(
+    db: State<'_, DbState>,
+    provider_id: String,
+) -> Result<(), String> {
+    store::delete(db.pool(), &provider_id).await
+}
+
+/// Send image requests that don't name a provider to `provider_id`
+#[tauri::command]
+pub async fn set_active_image_provider(
+    db: State<'_, DbState>,
+    provider_id: String,
+) -> Result<(), String> {
+    store::set_active(db.pool(), &provider_id).await
+}
+
+/// Generate one image with the requested provider, or the active one
+#[tauri::command]
+pub async fn generate_image(
+    db: State<'_, DbState>,
+    request: ImageRequest,
+) -> Result<GeneratedImage, String> {
+    let config = store::resolve(db.pool(), request.provider_id.as_deref()).await?;
+    let mut image = providers::generate(&config, &request)
+        .await
+        .map_err(|e| e.to_string())?;
+    metadata::record(db.pool(), None, &config, &request, &mut image).await;
+    Ok(image)
+}
+
+/// Generate an image on the background queue, filling in
+/// `embedded_image_id` when given. Progress is emitted on
+/// `image-job-progress`; returns the job id.
+#[tauri::command]
+pub async fn queue_image_generation(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: Option<String>,
+    request: ImageRequest,
+    embedded_image_id: Option<String>,
+) -> Result<String, String> {
+    // Fail now rather than in the background if nothing can take it
+    store::resolve(db.pool(), request.provider_id.as_deref()).await?;
+    let payload = ImageJobPayload {
+        request,
+        embedded_image_id,
+        sprite_id: None,
+    };
+    let payload = serde_json::to_value(&payload)
+        .map_err(|e| format!("Failed to serialize image job: {}", e))?;
+    queue
+        .enqueue(
+            db.pool(),
+            story_id.as_deref(),
+            JobKind::ImageGeneration,
+            payload,
+        )
+        .await
+}
+
+/// Evolve a character's look (aging, a new scar, an outfit change) from
+/// their current portrait, for the user to keep or discard. `portrait_id`
+/// is the id of the story or vault character the portrait belongs to.
+#[tauri::command]
+pub async fn generate_portrait_variation(
+    db: State<'_, DbState>,
+    portrait_id: String,
+    strength: f64,
+    prompt_delta: String,
+) -> Result<GeneratedImage, String> {
+    variation::generate_variation(db.pool(), &portrait_id, strength, &prompt_delta).await
+}
+
+#[tauri::command]
+pub async fn get_illustration_settings(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<IllustrationSettings, String> {
+    scene::load_settings(db.pool(), &story_id).await
+}
+
+/// Save a story's scene illustration settings; `None` turns them back off
+#[tauri::command]
+pub async fn set_illustration_settings(
+    db: State<'_, DbState>,
+    story_id: String,
+    settings: Option<IllustrationSettings>,
+) -> Result<IllustrationSettings, String> {
+    let raw = match &settings {
+        Some(settings) => Some(
+            serde_json::to_string(settings)
+                .map_err(|e| format!("Failed to serialize illustration settings: {}", e))?,
+        ),
+        None => None,
+    };
+    sqlx::query("UPDATE stories SET illustration_settings = ? WHERE id = ?")
+        .bind(raw)
+        .bind(&story_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to save illustration settings: {}", e))?;
+    scene::load_settings(db.pool(), &story_id).await
+}
+
+/// Illustrate the scene ending at an entry now, regardless of frequency
+/// (the chapter budget still applies). Returns the job id.
+#[tauri::command]
+pub async fn illustrate_scene(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::SceneIllustration,
+            serde_json::json!({ "entryId": entry_id }),
+        )
+        .await
+}
+
+/// How an image was generated. `id` is the id `generate_image` returned,
+/// or an embedded image's id.
+#[tauri::command]
+pub async fn get_image_metadata(
+    db: State<'_, DbState>,
+    id: String,
+) -> Result<Option<ImageMetadata>, String> {
+    metadata::get(db.pool(), &id).await
+}
+
+/// Save an image (base64 or a data URL) to `path`. PNGs get their
+/// generation parameters as text chunks, looked up by `metadata_id` or
+/// else by the image itself; other formats are written as they are.
+#[tauri::command]
+pub async fn export_image(
+    db: State<'_, DbState>,
+    image: String,
+    path: String,
+    metadata_id: Option<String>,
+) -> Result<(), String> {
+    let bytes = providers::decode_reference(&image)?;
+    let found = match metadata_id {
+        Some(id) => metadata::get(db.pool(), &id).await?,
+        None => metadata::find(db.pool(), &bytes).await?,
+    };
+    let bytes = match found {
+        Some(found) => {
+            let record = serde_json::to_string(&found)
+                .map_err(|e| format!("Failed to serialize image metadata: {}", e))?;
+            metadata::embed_text(
+                &bytes,
+                &[
+                    ("parameters", metadata::parameters_text(&found)),
+                    ("Software", "Aventuras".to_string()),
+                    ("aventuras", record),
+                ],
+            )
+        }
+        None => bytes,
+    };
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|e| format!("Failed to write image file: {}", e))
+}
+
+/// Generate a new portrait for a character. With likeness checks on,
+/// candidates unlike the current portrait are rejected and retried.
+#[tauri::command]
+pub async fn regenerate_portrait(
+    db: State<'_, DbState>,
+    models: State<'_, ModelManager>,
+    character_id: String,
+    request: ImageRequest,
+) -> Result<PortraitCandidate, String> {
+    consistency::regenerate_portrait(db.pool(), &models, &character_id, &request).await
+}
+
+/// Cosine similarity (0 to 1) between an image and a character's portrait
+#[tauri::command]
+pub async fn score_portrait(
+    db: State<'_, DbState>,
+    models: State<'_, ModelManager>,
+    character_id: String,
+    image: String,
+) -> Result<f32, String> {
+    consistency::score_portrait(db.pool(), &models, &character_id, &image).await
+}
+
+/// Queue expression sprites for a character, all moods when `moods` is
+/// empty. Each image arrives through its background job.
+#[tauri::command]
+pub async fn generate_character_sprites(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    character_id: String,
+    moods: Option<Vec<Mood>>,
+    provider_id: Option<String>,
+) -> Result<Vec<CharacterSprite>, String> {
+    sprites::generate_set(
+        db.pool(),
+        &queue,
+        &character_id,
+        &moods.unwrap_or_default(),
+        provider_id.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_character_sprites(
+    db: State<'_, DbState>,
+    character_id: String,
+) -> Result<Vec<CharacterSprite>, String> {
+    sprites::list(db.pool(), &character_id).await
+}
+
+/// The sprite to show for a character: for `mood`, or for the mood of their
+/// latest lines when it's `None`. Falls back to the neutral sprite.
+#[tauri::command]
+pub async fn get_character_sprite(
+    db: State<'_, DbState>,
+    character_id: String,
+    mood: Option<Mood>,
+) -> Result<Option<CharacterSprite>, String> {
+    sprites::get_sprite(db.pool(), &character_id, mood).await
+}
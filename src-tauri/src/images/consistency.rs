@@ -0,0 +1,206 @@
+//! Likeness checks for regenerated portraits. Each candidate is compared
+//! with the character's current portrait through CLIP image embeddings;
+//! candidates that look like someone else are rejected and the request is
+//! retried, leaning harder on the current portrait each time.
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::clip::{self, ClipVision};
+use super::metadata;
+use super::providers::{self, decode_reference};
+use super::store;
+use super::types::{GeneratedImage, ImageRequest};
+use super::variation::load_portrait;
+use crate::llm::config::get_setting;
+use crate::models::ModelManager;
+
+/// Strength of the first retry that starts from the current portrait
+const RETRY_STRENGTH: f64 = 0.75;
+
+/// Lowest strength retries go down to; below it barely anything changes
+const MIN_STRENGTH: f64 = 0.2;
+
+/// User settings for portrait likeness checks (`portrait_consistency_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConsistencySettings {
+    pub enabled: bool,
+    /// Installed CLIP model (model manager kind `clip`) with its weights in
+    /// safetensors format
+    pub model_id: Option<String>,
+    /// Cosine similarity a candidate needs to be kept, from 0 to 1
+    pub threshold: f32,
+    /// Candidates generated before giving up
+    pub max_attempts: u32,
+    /// How much each retry lowers the strength, keeping more of the
+    /// current portrait
+    pub strength_step: f64,
+}
+
+impl Default for ConsistencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model_id: None,
+            threshold: 0.8,
+            max_attempts: 3,
+            strength_step: 0.15,
+        }
+    }
+}
+
+/// A regenerated portrait and how closely it matches the old one
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortraitCandidate {
+    pub image: GeneratedImage,
+    /// `None` when likeness checks are off
+    pub similarity: Option<f32>,
+    pub attempts: u32,
+    /// False when no candidate reached the threshold; `image` is then the
+    /// closest one
+    pub accepted: bool,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ConsistencySettings, String> {
+    Ok(get_setting(pool, "portrait_consistency_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Embed an image off the async runtime; a forward pass takes a while
+async fn embed(model: Arc<ClipVision>, image: Vec<u8>) -> Result<Vec<f32>, String> {
+    tokio::task::spawn_blocking(move || model.embed(&image))
+        .await
+        .map_err(|e| format!("Image comparison failed: {}", e))?
+}
+
+async fn load_model(
+    models: &ModelManager,
+    settings: &ConsistencySettings,
+) -> Result<Option<Arc<ClipVision>>, String> {
+    let Some(id) = settings.model_id.as_deref().filter(|_| settings.enabled) else {
+        return Ok(None);
+    };
+    let installed = models
+        .installed(clip::MODEL_KIND, id)
+        .ok_or_else(|| format!("CLIP model not installed: {}", id))?;
+    let weights = installed
+        .files
+        .iter()
+        .find(|f| f.name.ends_with(".safetensors"))
+        .ok_or("The CLIP model has no .safetensors file")?;
+    let path = Path::new(&installed.path).join(&weights.name);
+    tokio::task::spawn_blocking(move || ClipVision::cached(&path))
+        .await
+        .map_err(|e| format!("Failed to load CLIP model: {}", e))?
+        .map(Some)
+}
+
+/// The request for attempt `attempt` (from 1). Retries get a new seed and,
+/// where the backend allows, start from the current portrait with less
+/// and less freedom to change it.
+fn adjust(
+    request: &ImageRequest,
+    portrait: &str,
+    supports_strength: bool,
+    attempt: u32,
+    step: f64,
+) -> ImageRequest {
+    let mut adjusted = request.clone();
+    if attempt == 1 {
+        return adjusted;
+    }
+    adjusted.seed = request.seed.map(|seed| seed.wrapping_add(attempt as u64));
+    if supports_strength {
+        if adjusted.reference_images.is_empty() {
+            adjusted.reference_images.push(portrait.to_string());
+        }
+        let start = request.strength.unwrap_or(RETRY_STRENGTH);
+        adjusted.strength = Some((start - step * (attempt - 1) as f64).max(MIN_STRENGTH));
+    }
+    adjusted
+}
+
+/// Generate a new portrait for a character, checking it against the
+/// current one when likeness checks are on
+pub async fn regenerate_portrait(
+    pool: &SqlitePool,
+    models: &ModelManager,
+    character_id: &str,
+    request: &ImageRequest,
+) -> Result<PortraitCandidate, String> {
+    let portrait = load_portrait(pool, character_id).await?;
+    let config = store::resolve(pool, request.provider_id.as_deref()).await?;
+    let settings = load_settings(pool).await?;
+    let Some(model) = load_model(models, &settings).await? else {
+        let mut image = providers::generate(&config, request)
+            .await
+            .map_err(|e| e.to_string())?;
+        metadata::record(pool, None, &config, request, &mut image).await;
+        return Ok(PortraitCandidate {
+            image,
+            similarity: None,
+            attempts: 1,
+            accepted: true,
+        });
+    };
+    let reference = embed(model.clone(), decode_reference(&portrait.image)?).await?;
+
+    let mut best: Option<(ImageRequest, GeneratedImage, f32)> = None;
+    let mut attempts = 0;
+    for attempt in 1..=settings.max_attempts.max(1) {
+        attempts = attempt;
+        let adjusted = adjust(
+            request,
+            &portrait.image,
+            config.provider_type.supports_strength(),
+            attempt,
+            settings.strength_step,
+        );
+        let image = providers::generate(&config, &adjusted)
+            .await
+            .map_err(|e| e.to_string())?;
+        let candidate = embed(model.clone(), decode_reference(&image.base64)?).await?;
+        let score = clip::similarity(&reference, &candidate);
+        let accepted = score >= settings.threshold;
+        if best.as_ref().is_none_or(|(_, _, best)| score > *best) {
+            best = Some((adjusted, image, score));
+        }
+        if accepted {
+            break;
+        }
+    }
+
+    let (adjusted, mut image, score) = best.ok_or("No portrait was generated")?;
+    metadata::record(pool, None, &config, &adjusted, &mut image).await;
+    Ok(PortraitCandidate {
+        image,
+        similarity: Some(score),
+        attempts,
+        accepted: score >= settings.threshold,
+    })
+}
+
+/// How closely an image matches a character's current portrait
+pub async fn score_portrait(
+    pool: &SqlitePool,
+    models: &ModelManager,
+    character_id: &str,
+    image: &str,
+) -> Result<f32, String> {
+    let portrait = load_portrait(pool, character_id).await?;
+    let mut settings = load_settings(pool).await?;
+    // Scoring on request works with checks off too
+    settings.enabled = true;
+    let model = load_model(models, &settings)
+        .await?
+        .ok_or("Choose a CLIP model in the portrait consistency settings first")?;
+    let reference = embed(model.clone(), decode_reference(&portrait.image)?).await?;
+    let candidate = embed(model, decode_reference(image)?).await?;
+    Ok(clip::similarity(&reference, &candidate))
+}
@@ -0,0 +1,184 @@
+//! Image generation on the background queue. Each provider gets its own
+//! concurrency cap, and a rate-limited job goes back in the queue until
+//! the provider's back-off has passed instead of failing.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter};
+
+use super::metadata;
+use super::providers::{self, ImageError};
+use super::store;
+use super::types::{GeneratedImage, ImageJobEvent, ImageRequest};
+use crate::jobs::queue::MAX_ATTEMPTS;
+use crate::jobs::types::{BackgroundJob, JobError};
+use crate::llm::limits;
+use crate::storage::space;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageJobPayload {
+    pub request: ImageRequest,
+    /// `embedded_images` row to fill in with the result; without one (or
+    /// a sprite) the image is only returned in the job result
+    #[serde(default)]
+    pub embedded_image_id: Option<String>,
+    /// `character_sprites` row to fill in with the result
+    #[serde(default)]
+    pub sprite_id: Option<String>,
+}
+
+impl ImageJobPayload {
+    /// Table, result key and id of the row the image is saved to
+    fn target(&self) -> Option<(&'static str, &'static str, &str)> {
+        match (&self.embedded_image_id, &self.sprite_id) {
+            (Some(id), _) => Some(("embedded_images", "embeddedImageId", id)),
+            (None, Some(id)) => Some(("character_sprites", "spriteId", id)),
+            (None, None) => None,
+        }
+    }
+}
+
+fn emit(
+    app: &AppHandle,
+    job: &BackgroundJob,
+    stage: &str,
+    fraction: Option<f64>,
+    preview: Option<String>,
+) {
+    let event = ImageJobEvent {
+        job_id: job.id.clone(),
+        story_id: job.story_id.clone(),
+        stage: stage.to_string(),
+        fraction,
+        preview,
+    };
+    if let Err(e) = app.emit("image-job-progress", &event) {
+        eprintln!("Failed to emit image job event: {}", e);
+    }
+}
+
+async fn set_status(
+    pool: &SqlitePool,
+    (table, _, id): (&str, &str, &str),
+    status: &str,
+    error: Option<&str>,
+) {
+    let updated = sqlx::query(&format!(
+        "UPDATE {} SET status = ?, error_message = ? WHERE id = ?",
+        table
+    ))
+    .bind(status)
+    .bind(error)
+    .bind(id)
+    .execute(pool)
+    .await;
+    if let Err(e) = updated {
+        eprintln!("Failed to update image status: {}", e);
+    }
+}
+
+async fn save_image(
+    pool: &SqlitePool,
+    (table, _, id): (&str, &str, &str),
+    request: &ImageRequest,
+    image: &GeneratedImage,
+) -> Result<(), String> {
+    // Written to the log and then the database
+    space::ensure_free_for_database(pool, image.base64.len() as u64 * 2).await?;
+    sqlx::query(&format!(
+        "UPDATE {} SET image_data = ?, model = ?, width = ?, height = ?, \
+         status = 'complete', error_message = NULL WHERE id = ?",
+        table
+    ))
+    .bind(&image.base64)
+    .bind(&image.model)
+    .bind(request.width)
+    .bind(request.height)
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save image: {}", e))?;
+    Ok(())
+}
+
+pub async fn run_image_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<Value, JobError> {
+    let payload: ImageJobPayload = serde_json::from_value(job.payload.clone())
+        .map_err(|e| JobError::Failed(format!("Invalid image job: {}", e)))?;
+    let config = store::resolve(pool, payload.request.provider_id.as_deref())
+        .await
+        .map_err(JobError::Failed)?;
+    let target = payload.target();
+
+    emit(app, job, "waiting", None, None);
+    let slot = limits::acquire_slot(config.base_url(), config.concurrency()).await;
+    if let Some(target) = target {
+        set_status(pool, target, "generating", None).await;
+    }
+    emit(app, job, "generating", Some(0.0), None);
+    let generated = providers::generate_with_progress(&config, &payload.request, &|progress| {
+        emit(
+            app,
+            job,
+            "generating",
+            Some(progress.fraction),
+            progress.preview,
+        )
+    })
+    .await;
+    drop(slot);
+
+    let mut image = match generated {
+        Ok(image) => image,
+        Err(ImageError::RateLimited {
+            retry_after,
+            message,
+        }) => {
+            // Hold back the provider's other jobs too
+            limits::back_off(config.base_url(), retry_after);
+            if let Some(target) = target {
+                set_status(pool, target, "pending", None).await;
+            }
+            return Err(JobError::Deferred {
+                delay: retry_after,
+                reason: message,
+            });
+        }
+        Err(ImageError::Failed(message)) => {
+            if let Some(target) = target {
+                let status = if job.attempts >= MAX_ATTEMPTS {
+                    "failed"
+                } else {
+                    "pending"
+                };
+                set_status(pool, target, status, Some(&message)).await;
+            }
+            return Err(JobError::Failed(message));
+        }
+    };
+
+    emit(app, job, "saving", Some(1.0), None);
+    let target_id = target.map(|(_, _, id)| id);
+    metadata::record(pool, target_id, &config, &payload.request, &mut image).await;
+    match target {
+        Some(target @ (_, key, id)) => {
+            save_image(pool, target, &payload.request, &image)
+                .await
+                .map_err(JobError::Failed)?;
+            // The image is in the database; keep the job row small
+            Ok(serde_json::json!({
+                key: id,
+                "providerId": image.provider_id,
+                "model": image.model,
+                "seed": image.seed,
+            }))
+        }
+        None => serde_json::to_value(&image)
+            .map_err(|e| JobError::Failed(format!("Failed to serialize image: {}", e))),
+    }
+}
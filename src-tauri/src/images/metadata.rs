@@ -0,0 +1,185 @@
+//! Generation parameters kept with every generated image, and written into
+//! exported PNGs as text chunks in the AUTOMATIC1111 `parameters` format
+//! that most image tools read.
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use super::providers::decode_reference;
+use super::types::{GeneratedImage, ImageMetadata, ImageProviderConfig, ImageRequest};
+use crate::db::now_millis;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Hex SHA-256 of an image's bytes
+pub fn image_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn metadata_from_row(r: &SqliteRow) -> ImageMetadata {
+    ImageMetadata {
+        id: r.get("id"),
+        image_hash: r.get("image_hash"),
+        provider_id: r.get("provider_id"),
+        provider_type: r.get("provider_type"),
+        model: r.get("model"),
+        prompt: r.get("prompt"),
+        negative_prompt: r.get("negative_prompt"),
+        revised_prompt: r.get("revised_prompt"),
+        seed: r.get::<Option<i64>, _>("seed").map(|seed| seed as u64),
+        sampler: r.get("sampler"),
+        width: r.get::<i64, _>("width") as u32,
+        height: r.get::<i64, _>("height") as u32,
+        strength: r.get("strength"),
+        parameters: serde_json::from_str(&r.get::<String, _>("parameters")).unwrap_or_default(),
+        created_at: r.get("created_at"),
+    }
+}
+
+/// Record how `image` was made under `id` (a new id when `None`) and set
+/// `image.id`. A regenerated embedded image replaces its old record.
+/// Failures are logged: the image itself is still good.
+pub async fn record(
+    pool: &SqlitePool,
+    id: Option<&str>,
+    config: &ImageProviderConfig,
+    request: &ImageRequest,
+    image: &mut GeneratedImage,
+) {
+    let id = id.map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let hash = match decode_reference(&image.base64) {
+        Ok(bytes) => image_hash(&bytes),
+        Err(e) => {
+            eprintln!("Failed to record image metadata: {}", e);
+            return;
+        }
+    };
+    let parameters = Value::Object(config.options.clone()).to_string();
+    let saved = sqlx::query(
+        "INSERT OR REPLACE INTO image_metadata (id, image_hash, provider_id, provider_type, model, \
+         prompt, negative_prompt, revised_prompt, seed, sampler, width, height, strength, \
+         parameters, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(hash)
+    .bind(&config.id)
+    .bind(config.provider_type.as_str())
+    .bind(&image.model)
+    .bind(&request.prompt)
+    .bind(request.negative_prompt.as_deref().filter(|n| !n.is_empty()))
+    .bind(&image.revised_prompt)
+    .bind(image.seed.map(|seed| seed as i64))
+    .bind(&image.sampler)
+    .bind(request.width)
+    .bind(request.height)
+    // Strength only means something when starting from an image
+    .bind(request.strength.filter(|_| !request.reference_images.is_empty()))
+    .bind(parameters)
+    .bind(now_millis())
+    .execute(pool)
+    .await;
+    match saved {
+        Ok(_) => image.id = id,
+        Err(e) => eprintln!("Failed to record image metadata: {}", e),
+    }
+}
+
+pub async fn get(pool: &SqlitePool, id: &str) -> Result<Option<ImageMetadata>, String> {
+    let row = sqlx::query("SELECT * FROM image_metadata WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load image metadata: {}", e))?;
+    Ok(row.as_ref().map(metadata_from_row))
+}
+
+/// The newest record of an image with these bytes
+pub async fn find(pool: &SqlitePool, bytes: &[u8]) -> Result<Option<ImageMetadata>, String> {
+    let row = sqlx::query(
+        "SELECT * FROM image_metadata WHERE image_hash = ? ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(image_hash(bytes))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load image metadata: {}", e))?;
+    Ok(row.as_ref().map(metadata_from_row))
+}
+
+/// The metadata as AUTOMATIC1111 writes it: the prompt, the negative
+/// prompt, then one line of `Key: value` pairs
+pub fn parameters_text(metadata: &ImageMetadata) -> String {
+    let mut text = metadata.prompt.clone();
+    if let Some(negative) = &metadata.negative_prompt {
+        text.push_str(&format!("\nNegative prompt: {}", negative));
+    }
+    let mut fields = Vec::new();
+    if let Some(steps) = metadata.parameters.get("steps") {
+        fields.push(format!("Steps: {}", steps));
+    }
+    if let Some(sampler) = &metadata.sampler {
+        fields.push(format!("Sampler: {}", sampler));
+    }
+    if let Some(seed) = metadata.seed {
+        fields.push(format!("Seed: {}", seed));
+    }
+    fields.push(format!("Size: {}x{}", metadata.width, metadata.height));
+    if !metadata.model.is_empty() {
+        fields.push(format!("Model: {}", metadata.model));
+    }
+    if let Some(strength) = metadata.strength {
+        fields.push(format!("Denoising strength: {}", strength));
+    }
+    text.push('\n');
+    text.push_str(&fields.join(", "));
+    text
+}
+
+fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    let mut out = Vec::with_capacity(data.len() + 12);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+    out
+}
+
+/// A tEXt chunk, or iTXt when the text doesn't fit Latin-1
+fn text_chunk(key: &str, text: &str) -> Vec<u8> {
+    let latin1: Option<Vec<u8>> = text.chars().map(|c| u8::try_from(c as u32).ok()).collect();
+    let mut data = key.as_bytes().to_vec();
+    data.push(0);
+    match latin1 {
+        Some(bytes) => {
+            data.extend_from_slice(&bytes);
+            chunk(b"tEXt", &data)
+        }
+        None => {
+            // Uncompressed, with no language tag or translated keyword
+            data.extend_from_slice(&[0, 0, 0, 0]);
+            data.extend_from_slice(text.as_bytes());
+            chunk(b"iTXt", &data)
+        }
+    }
+}
+
+/// A copy of a PNG with text chunks added after the header. Anything that
+/// isn't a PNG comes back unchanged.
+pub fn embed_text(png: &[u8], entries: &[(&str, String)]) -> Vec<u8> {
+    // Signature, then the IHDR chunk: length, type, 13 bytes, CRC
+    let header_end = PNG_SIGNATURE.len() + 25;
+    if !png.starts_with(PNG_SIGNATURE) || png.len() < header_end {
+        return png.to_vec();
+    }
+    let mut out = png[..header_end].to_vec();
+    for (key, text) in entries {
+        out.extend(text_chunk(key, text));
+    }
+    out.extend_from_slice(&png[header_end..]);
+    out
+}
@@ -0,0 +1,11 @@
+pub mod clip;
+pub mod commands;
+pub mod consistency;
+pub mod job;
+pub mod metadata;
+pub mod providers;
+pub mod scene;
+pub mod sprites;
+pub mod store;
+pub mod types;
+pub mod variation;
@@ -0,0 +1,256 @@
+//! Image generation backends behind one interface. Each backend turns an
+//! `ImageRequest` into a call to its own API; `generate` picks the backend
+//! a provider is configured with.
+
+pub mod novelai;
+pub mod openai;
+pub mod stability;
+pub mod webui;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::StatusCode;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use super::types::{
+    GeneratedImage, ImageBackend, ImageProgress, ImageProviderConfig, ImageRequest,
+};
+use crate::llm::limits;
+
+/// Local backends on slow hardware can take minutes per image
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often backends that report progress are asked for it
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum ImageError {
+    /// The backend asked to be left alone for a while
+    RateLimited {
+        retry_after: Duration,
+        message: String,
+    },
+    Failed(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::RateLimited { message, .. } | ImageError::Failed(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl From<String> for ImageError {
+    fn from(message: String) -> Self {
+        ImageError::Failed(message)
+    }
+}
+
+impl From<&str> for ImageError {
+    fn from(message: &str) -> Self {
+        ImageError::Failed(message.to_string())
+    }
+}
+
+pub trait ImageProvider: Send + Sync {
+    /// Generate one image for `request`
+    fn generate(
+        &self,
+        request: &ImageRequest,
+    ) -> impl Future<Output = Result<GeneratedImage, ImageError>> + Send;
+
+    /// How far the image in flight has come, for backends that can tell
+    fn progress(&self) -> impl Future<Output = Option<ImageProgress>> + Send {
+        async { None }
+    }
+}
+
+/// Generate an image with the backend `config` is set up for
+pub async fn generate(
+    config: &ImageProviderConfig,
+    request: &ImageRequest,
+) -> Result<GeneratedImage, ImageError> {
+    generate_with_progress(config, request, &|_| {}).await
+}
+
+/// `generate`, passing progress reports to `on_progress` as they come
+pub async fn generate_with_progress(
+    config: &ImageProviderConfig,
+    request: &ImageRequest,
+    on_progress: &(dyn Fn(ImageProgress) + Sync),
+) -> Result<GeneratedImage, ImageError> {
+    if request.prompt.trim().is_empty() {
+        return Err("The image prompt is empty".into());
+    }
+    let image = match config.provider_type {
+        ImageBackend::OpenAi => {
+            watch(openai::OpenAiImages::new(config), request, on_progress).await
+        }
+        ImageBackend::NovelAi => {
+            watch(novelai::NovelAiImages::new(config), request, on_progress).await
+        }
+        ImageBackend::Stability => {
+            watch(
+                stability::StabilityImages::new(config),
+                request,
+                on_progress,
+            )
+            .await
+        }
+        ImageBackend::WebUi => watch(webui::WebUiImages::new(config), request, on_progress).await,
+    }?;
+    Ok(GeneratedImage {
+        provider_id: config.id.clone(),
+        ..image
+    })
+}
+
+async fn watch<P: ImageProvider>(
+    provider: P,
+    request: &ImageRequest,
+    on_progress: &(dyn Fn(ImageProgress) + Sync),
+) -> Result<GeneratedImage, ImageError> {
+    let generation = provider.generate(request);
+    tokio::pin!(generation);
+    let mut ticks = tokio::time::interval(PROGRESS_INTERVAL);
+    loop {
+        tokio::select! {
+            result = &mut generation => return result,
+            _ = ticks.tick() => {
+                if let Some(progress) = provider.progress().await {
+                    on_progress(progress);
+                }
+            }
+        }
+    }
+}
+
+/// The request's model, else the provider's, else the backend default
+pub fn model_for(config: &ImageProviderConfig, request: &ImageRequest) -> String {
+    [request.model.as_deref(), Some(config.model.as_str())]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|model| !model.is_empty())
+        .unwrap_or(config.provider_type.default_model())
+        .to_string()
+}
+
+/// Base64 without any `data:image/...;base64,` prefix
+pub fn strip_data_url(image: &str) -> &str {
+    match image.split_once(";base64,") {
+        Some((prefix, data)) if prefix.starts_with("data:") => data,
+        _ => image,
+    }
+}
+
+pub fn decode_reference(image: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(strip_data_url(image))
+        .map_err(|e| format!("Invalid reference image: {}", e))
+}
+
+/// Image type of base64 data, from its first bytes
+pub fn mime_type(base64: &str) -> &'static str {
+    if base64.starts_with("/9j/") {
+        "image/jpeg"
+    } else if base64.starts_with("UklGR") {
+        "image/webp"
+    } else if base64.starts_with("R0lGOD") {
+        "image/gif"
+    } else {
+        "image/png"
+    }
+}
+
+/// Width and height of a PNG or JPEG
+pub fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| Some(u16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]) as u32);
+    let be32 = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    if bytes.starts_with(b"\x89PNG") {
+        // The IHDR chunk always comes first
+        return Some((be32(16)?, be32(20)?));
+    }
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    // Walk the JPEG segments to the frame header
+    let mut at = 2;
+    while *bytes.get(at)? == 0xFF {
+        let marker = *bytes.get(at + 1)?;
+        let is_frame = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+        if is_frame {
+            return Some((be16(at + 7)?, be16(at + 5)?));
+        }
+        at += 2 + be16(at + 2)? as usize;
+    }
+    None
+}
+
+/// A generated image; the provider id is filled in by `generate`
+pub fn image(base64: String, model: String) -> GeneratedImage {
+    GeneratedImage {
+        id: String::new(),
+        mime_type: mime_type(&base64).to_string(),
+        base64,
+        provider_id: String::new(),
+        model,
+        seed: None,
+        sampler: None,
+        revised_prompt: None,
+    }
+}
+
+/// Add a provider's extra fields to a request body
+pub fn merge_options(body: &mut Value, options: &Map<String, Value>) {
+    for (key, value) in options {
+        body[key] = value.clone();
+    }
+}
+
+/// Option values as multipart text fields
+pub fn option_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The error of a failed response, whatever shape the API uses
+pub async fn error_for(response: reqwest::Response) -> ImageError {
+    let status = response.status();
+    let retry_after = limits::retry_after(response.headers());
+    let text = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&text)
+        .ok()
+        .and_then(|payload| {
+            [
+                "/error/message",
+                "/error",
+                "/message",
+                "/errors/0",
+                "/detail",
+            ]
+            .iter()
+            .find_map(|pointer| payload.pointer(pointer)?.as_str().map(str::to_string))
+        })
+        .unwrap_or(text);
+    let message = format!("Image request failed ({}): {}", status, message.trim());
+    match (status, retry_after) {
+        (StatusCode::TOO_MANY_REQUESTS, delay) => ImageError::RateLimited {
+            retry_after: delay.unwrap_or(limits::DEFAULT_BACKOFF),
+            message,
+        },
+        // An overloaded server that says when to come back
+        (StatusCode::SERVICE_UNAVAILABLE, Some(delay)) => ImageError::RateLimited {
+            retry_after: delay,
+            message,
+        },
+        _ => ImageError::Failed(message),
+    }
+}
@@ -0,0 +1,116 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::multipart::{Form, Part};
+use serde_json::{json, Value};
+
+use super::{
+    decode_reference, error_for, image, merge_options, mime_type, model_for, option_text,
+    strip_data_url, ImageError, ImageProvider, REQUEST_TIMEOUT,
+};
+use crate::images::types::{GeneratedImage, ImageProviderConfig, ImageRequest};
+
+/// OpenAI's Images API. Reference images go through the edits endpoint.
+pub struct OpenAiImages<'a> {
+    config: &'a ImageProviderConfig,
+    client: reqwest::Client,
+}
+
+impl<'a> OpenAiImages<'a> {
+    pub fn new(config: &'a ImageProviderConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn edit_form(&self, request: &ImageRequest, model: &str) -> Result<Form, String> {
+        let mut form = Form::new()
+            .text("model", model.to_string())
+            .text("prompt", request.prompt.clone())
+            .text("size", format!("{}x{}", request.width, request.height))
+            .text("n", "1");
+        for (i, reference) in request.reference_images.iter().enumerate() {
+            let mime = mime_type(strip_data_url(reference));
+            let part = Part::bytes(decode_reference(reference)?)
+                .file_name(format!("reference-{}.{}", i, &mime[6..]))
+                .mime_str(mime)
+                .map_err(|e| format!("Invalid reference image: {}", e))?;
+            form = form.part("image[]", part);
+        }
+        for (key, value) in &self.config.options {
+            form = form.text(key.clone(), option_text(value));
+        }
+        Ok(form)
+    }
+
+    async fn download(&self, url: &str) -> Result<String, String> {
+        let bytes = self
+            .client
+            .get(url)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to download image: {}", e))?
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download image: {}", e))?;
+        Ok(STANDARD.encode(bytes))
+    }
+}
+
+impl ImageProvider for OpenAiImages<'_> {
+    async fn generate(&self, request: &ImageRequest) -> Result<GeneratedImage, ImageError> {
+        let model = model_for(self.config, request);
+        let base_url = self.config.base_url();
+        let mut call = if request.reference_images.is_empty() {
+            let mut body = json!({
+                "model": model,
+                "prompt": request.prompt,
+                "size": format!("{}x{}", request.width, request.height),
+                "n": 1,
+            });
+            // gpt-image models always answer with base64 and reject the field
+            if !model.starts_with("gpt-image") {
+                body["response_format"] = json!("b64_json");
+            }
+            merge_options(&mut body, &self.config.options);
+            self.client
+                .post(format!("{}/images/generations", base_url))
+                .json(&body)
+        } else {
+            self.client
+                .post(format!("{}/images/edits", base_url))
+                .multipart(self.edit_form(request, &model)?)
+        };
+        if !self.config.api_key.is_empty() {
+            call = call.bearer_auth(&self.config.api_key);
+        }
+        let response = call
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Image request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(error_for(response).await);
+        }
+        let payload: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid image response: {}", e))?;
+
+        let data = payload
+            .pointer("/data/0")
+            .ok_or("The image response has no image")?;
+        let base64 = match (data.get("b64_json"), data.get("url")) {
+            (Some(Value::String(b64)), _) => b64.clone(),
+            (_, Some(Value::String(url))) => self.download(url).await?,
+            _ => return Err("The image response has no image".into()),
+        };
+        let mut generated = image(base64, model);
+        generated.revised_prompt = data
+            .get("revised_prompt")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Ok(generated)
+    }
+}
@@ -0,0 +1,135 @@
+use reqwest::multipart::{Form, Part};
+use serde_json::Value;
+
+use super::{
+    decode_reference, error_for, image, model_for, option_text, ImageError, ImageProvider,
+    REQUEST_TIMEOUT,
+};
+use crate::images::types::{GeneratedImage, ImageProviderConfig, ImageRequest};
+
+/// Aspect ratios Stability accepts instead of a size
+const ASPECT_RATIOS: &[(u32, u32)] = &[
+    (21, 9),
+    (16, 9),
+    (3, 2),
+    (5, 4),
+    (1, 1),
+    (4, 5),
+    (2, 3),
+    (9, 16),
+    (9, 21),
+];
+
+/// How far the result strays from a reference image unless asked otherwise
+const REFERENCE_STRENGTH: &str = "0.7";
+
+/// Stability AI's Stable Image API. The model picks the endpoint: `core`,
+/// `ultra`, or an `sd3*` model.
+pub struct StabilityImages<'a> {
+    config: &'a ImageProviderConfig,
+}
+
+impl<'a> StabilityImages<'a> {
+    pub fn new(config: &'a ImageProviderConfig) -> Self {
+        Self { config }
+    }
+}
+
+fn aspect_ratio(width: u32, height: u32) -> String {
+    let target = width.max(1) as f64 / height.max(1) as f64;
+    let (w, h) = ASPECT_RATIOS
+        .iter()
+        .min_by(|a, b| {
+            let distance = |(w, h): &&(u32, u32)| (*w as f64 / *h as f64 - target).abs();
+            distance(a).total_cmp(&distance(b))
+        })
+        .copied()
+        .unwrap_or((1, 1));
+    format!("{}:{}", w, h)
+}
+
+impl ImageProvider for StabilityImages<'_> {
+    async fn generate(&self, request: &ImageRequest) -> Result<GeneratedImage, ImageError> {
+        let model = model_for(self.config, request);
+        let endpoint = match model.as_str() {
+            "core" | "ultra" => model.as_str(),
+            sd3 if sd3.starts_with("sd3") => "sd3",
+            other => return Err(format!("Unknown Stability model: {}", other).into()),
+        };
+
+        let mut form = Form::new()
+            .text("prompt", request.prompt.clone())
+            .text("output_format", "png");
+        if endpoint == "sd3" {
+            form = form.text("model", model.clone());
+        }
+        if let Some(negative) = request.negative_prompt.as_ref().filter(|n| !n.is_empty()) {
+            form = form.text("negative_prompt", negative.clone());
+        }
+        if let Some(seed) = request.seed {
+            form = form.text("seed", seed.to_string());
+        }
+        match request.reference_images.first() {
+            Some(_) if endpoint == "core" => {
+                return Err(
+                    "Stability's core model can't start from a reference image; choose ultra or an sd3 model"
+                        .into(),
+                )
+            }
+            Some(reference) => {
+                let part = Part::bytes(decode_reference(reference)?).file_name("reference.png");
+                form = form.part("image", part);
+                match request.strength {
+                    Some(strength) => form = form.text("strength", strength.to_string()),
+                    None if !self.config.options.contains_key("strength") => {
+                        form = form.text("strength", REFERENCE_STRENGTH)
+                    }
+                    None => {}
+                }
+                if endpoint == "sd3" {
+                    form = form.text("mode", "image-to-image");
+                }
+            }
+            // The size follows the reference image when there is one
+            None => form = form.text("aspect_ratio", aspect_ratio(request.width, request.height)),
+        }
+        for (key, value) in &self.config.options {
+            if key == "strength" && request.strength.is_some() {
+                continue;
+            }
+            form = form.text(key.clone(), option_text(value));
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/v2beta/stable-image/generate/{}",
+                self.config.base_url(),
+                endpoint
+            ))
+            .bearer_auth(&self.config.api_key)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .multipart(form)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Image request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(error_for(response).await);
+        }
+        let payload: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid image response: {}", e))?;
+        if payload.get("finish_reason").and_then(Value::as_str) == Some("CONTENT_FILTERED") {
+            return Err("Stability withheld this image for content; try a different prompt".into());
+        }
+
+        let base64 = payload
+            .get("image")
+            .and_then(Value::as_str)
+            .ok_or("The image response has no image")?;
+        let mut generated = image(base64.to_string(), model);
+        generated.seed = payload.get("seed").and_then(Value::as_u64);
+        Ok(generated)
+    }
+}
@@ -0,0 +1,110 @@
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::{
+    error_for, image, merge_options, model_for, strip_data_url, ImageError, ImageProvider,
+    REQUEST_TIMEOUT,
+};
+use crate::images::types::{GeneratedImage, ImageProgress, ImageProviderConfig, ImageRequest};
+
+/// How far img2img strays from the reference image unless asked otherwise
+const DENOISING_STRENGTH: f64 = 0.6;
+
+/// The API of a local AUTOMATIC1111 or Forge WebUI started with `--api`.
+/// An API key of the form `user:password` is sent as `--api-auth`
+/// credentials.
+pub struct WebUiImages<'a> {
+    config: &'a ImageProviderConfig,
+}
+
+impl<'a> WebUiImages<'a> {
+    pub fn new(config: &'a ImageProviderConfig) -> Self {
+        Self { config }
+    }
+
+    fn with_auth(&self, call: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.config.api_key.split_once(':') {
+            Some((user, password)) => call.basic_auth(user, Some(password)),
+            None => call,
+        }
+    }
+}
+
+impl ImageProvider for WebUiImages<'_> {
+    async fn generate(&self, request: &ImageRequest) -> Result<GeneratedImage, ImageError> {
+        let model = model_for(self.config, request);
+        let mut body = json!({
+            "prompt": request.prompt,
+            "negative_prompt": request.negative_prompt.clone().unwrap_or_default(),
+            "width": request.width,
+            "height": request.height,
+            "seed": request.seed.map_or(-1, |seed| seed as i64),
+            "batch_size": 1,
+            "n_iter": 1,
+        });
+        // Empty keeps whichever checkpoint the WebUI has loaded
+        if !model.is_empty() {
+            body["override_settings"] = json!({ "sd_model_checkpoint": model });
+        }
+        let endpoint = match request.reference_images.first() {
+            Some(reference) => {
+                body["init_images"] = json!([strip_data_url(reference)]);
+                body["denoising_strength"] = json!(request.strength.unwrap_or(DENOISING_STRENGTH));
+                "img2img"
+            }
+            None => "txt2img",
+        };
+        merge_options(&mut body, &self.config.options);
+
+        let call = reqwest::Client::new()
+            .post(format!("{}/sdapi/v1/{}", self.config.base_url(), endpoint))
+            .json(&body)
+            .timeout(REQUEST_TIMEOUT);
+        let response = self.with_auth(call).send().await.map_err(|e| {
+            format!(
+                "Couldn't reach the WebUI at {}. Is it running with --api? ({})",
+                self.config.base_url(),
+                e
+            )
+        })?;
+        if !response.status().is_success() {
+            return Err(error_for(response).await);
+        }
+        let payload: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid image response: {}", e))?;
+
+        let base64 = payload
+            .pointer("/images/0")
+            .and_then(Value::as_str)
+            .ok_or("The image response has no image")?;
+        let mut generated = image(base64.to_string(), model);
+        // `info` is itself JSON, encoded as a string
+        let info = payload
+            .get("info")
+            .and_then(Value::as_str)
+            .and_then(|info| serde_json::from_str::<Value>(info).ok())
+            .unwrap_or(Value::Null);
+        generated.seed = info.get("seed").and_then(Value::as_u64);
+        generated.sampler = info
+            .get("sampler_name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Ok(generated)
+    }
+
+    async fn progress(&self) -> Option<ImageProgress> {
+        let call = reqwest::Client::new()
+            .get(format!("{}/sdapi/v1/progress", self.config.base_url()))
+            .timeout(Duration::from_secs(5));
+        let payload: Value = self.with_auth(call).send().await.ok()?.json().await.ok()?;
+        Some(ImageProgress {
+            fraction: payload.get("progress")?.as_f64()?,
+            preview: payload
+                .get("current_image")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        })
+    }
+}
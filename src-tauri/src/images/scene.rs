@@ -0,0 +1,281 @@
+//! Automatic scene illustration. After a response the turn pipeline asks
+//! `should_illustrate`; when the entry is due, a background job has a model
+//! write an image prompt from the latest passages and queues the image,
+//! attached to the entry as an embedded image.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use super::job::ImageJobPayload;
+use super::store;
+use super::types::{IllustrationSettings, ImageRequest};
+use crate::context::branch::{characters, visible_entries, VisibleEntry};
+use crate::db::now_millis;
+use crate::jobs::types::{BackgroundJob, JobKind};
+use crate::jobs::JobQueue;
+use crate::llm::client::{self, ChatMessage};
+use crate::llm::config;
+use crate::scenario::template::{self, RenderContext};
+
+const SYSTEM_PROMPT: &str = "You write prompts for an image generator. \
+Describe the single most striking moment of the scene you are given as one illustration: \
+who is in it and what they look like, what they are doing, the setting, lighting and mood. \
+Use short comma-separated phrases, under 80 words. Name no text, captions or speech bubbles. \
+Reply with the prompt only.";
+
+/// Request the model gets unless the story sets its own template. Values:
+/// `passages`, `characters` (appearance of characters in the passages) and
+/// `style`.
+pub const DEFAULT_PROMPT_TEMPLATE: &str = "{{#if characters}}Characters in the scene:
+{{characters}}
+
+{{/if}}{{#if style}}Art style: {{style}}
+
+{{/if}}Latest passages:
+{{passages}}";
+
+pub async fn load_settings(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<IllustrationSettings, String> {
+    let raw: Option<String> =
+        sqlx::query_scalar("SELECT illustration_settings FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load illustration settings: {}", e))?
+            .flatten();
+    Ok(raw
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Visible entries from the start of the entry's chapter through the entry.
+/// The chapter starts after the last closed chapter that ends before it.
+async fn chapter_so_far(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: &str,
+) -> Result<Vec<VisibleEntry>, String> {
+    let mut entries = visible_entries(pool, story_id).await?;
+    let index = entries
+        .iter()
+        .position(|e| e.id == entry_id)
+        .ok_or_else(|| format!("Entry not found on the current branch: {}", entry_id))?;
+    entries.truncate(index + 1);
+    let chapter_ends: Vec<String> =
+        sqlx::query_scalar("SELECT end_entry_id FROM chapters WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    let start = entries[..index]
+        .iter()
+        .rposition(|e| chapter_ends.contains(&e.id))
+        .map_or(0, |end| end + 1);
+    Ok(entries.split_off(start))
+}
+
+/// Scene illustrations already made for the chapter
+async fn illustrations_in(pool: &SqlitePool, chapter: &[VisibleEntry]) -> Result<u32, String> {
+    let ids: Vec<&str> = chapter.iter().map(|e| e.id.as_str()).collect();
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM embedded_images WHERE origin = 'scene' \
+         AND entry_id IN (SELECT value FROM json_each(?))",
+    )
+    .bind(json!(ids).to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count scene illustrations: {}", e))?;
+    Ok(count as u32)
+}
+
+fn within_budget(settings: &IllustrationSettings, used: u32) -> bool {
+    settings.max_per_chapter.is_none_or(|max| used < max)
+}
+
+/// Whether a new response entry should get a scene illustration: the
+/// story has them on, the entry is due by the frequency setting and the
+/// chapter's budget isn't spent
+pub async fn should_illustrate(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: &str,
+) -> Result<bool, String> {
+    let settings = load_settings(pool, story_id).await?;
+    if !settings.enabled {
+        return Ok(false);
+    }
+    let chapter = chapter_so_far(pool, story_id, entry_id).await?;
+    // Count responses only, so the frequency doesn't depend on how many
+    // actions the player took in between
+    let nth = chapter.iter().filter(|e| e.kind != "user_action").count() as u32;
+    let due = (settings.on_chapter_start && nth == 1)
+        || (settings.every_entries > 0 && nth.is_multiple_of(settings.every_entries));
+    Ok(due && within_budget(&settings, illustrations_in(pool, &chapter).await?))
+}
+
+/// Appearance of the characters the passages mention, one per line
+async fn characters_in(
+    pool: &SqlitePool,
+    story_id: &str,
+    passages: &str,
+) -> Result<String, String> {
+    let mut rows: Vec<(String, Option<String>)> = characters(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.name, c.visual_descriptors))
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    let lower = passages.to_lowercase();
+    let mut lines: Vec<String> = Vec::new();
+    for (name, descriptors) in rows {
+        let line_start = format!("- {}", name);
+        if !lower.contains(&name.to_lowercase()) || lines.iter().any(|l| l.starts_with(&line_start))
+        {
+            continue;
+        }
+        let descriptors = descriptors
+            .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+            .unwrap_or(Value::Null);
+        let appearance = super::variation::describe(&descriptors).join(", ");
+        lines.push(if appearance.is_empty() {
+            line_start
+        } else {
+            format!("{}: {}", line_start, appearance)
+        });
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Background job: write an image prompt for the scene ending at an entry
+/// and queue its image. The embedded image row is created here so the
+/// entry shows the picture as pending straight away.
+pub async fn run_scene_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Scene illustration job is missing entryId")?;
+    let story_id: String = sqlx::query_scalar("SELECT story_id FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let settings = load_settings(pool, &story_id).await?;
+
+    // Jobs queued close together could overrun the budget
+    let chapter = chapter_so_far(pool, &story_id, entry_id).await?;
+    if !within_budget(&settings, illustrations_in(pool, &chapter).await?) {
+        return Ok(json!({ "skipped": "The chapter's illustration budget is spent" }));
+    }
+    let provider = store::resolve(pool, settings.provider_id.as_deref()).await?;
+
+    let entries = visible_entries(pool, &story_id).await?;
+    let end = entries
+        .iter()
+        .position(|e| e.id == entry_id)
+        .map_or(entries.len(), |index| index + 1);
+    let passages = entries[end.saturating_sub(settings.recent_entries.max(1))..end]
+        .iter()
+        .map(|e| match e.kind.as_str() {
+            "user_action" => format!("> {}", e.content),
+            _ => e.content.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let values = HashMap::from([
+        (
+            "characters".to_string(),
+            characters_in(pool, &story_id, &passages).await?,
+        ),
+        ("style".to_string(), settings.style.trim().to_string()),
+        ("passages".to_string(), passages.clone()),
+    ]);
+    let nodes = template::parse(
+        settings
+            .prompt_template
+            .as_deref()
+            .unwrap_or(DEFAULT_PROMPT_TEMPLATE),
+    )
+    .map_err(|e| format!("Template error in the illustration prompt: {}", e))?;
+    let mut rng = StdRng::from_entropy();
+    let request = template::render(
+        &nodes,
+        &mut RenderContext {
+            values: &values,
+            tables: &HashMap::new(),
+            rng: &mut rng,
+        },
+    )?;
+
+    let llm = config::resolve_service(pool, "sceneIllustration", "classification").await?;
+    let reply = client::complete_logged(
+        pool,
+        "sceneIllustration",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(request),
+        ],
+    )
+    .await?;
+    let mut prompt = reply.trim().trim_matches('"').trim().to_string();
+    if prompt.is_empty() {
+        return Err("The model wrote an empty image prompt".to_string());
+    }
+    if !settings.style.trim().is_empty() {
+        prompt = format!("{}, {}", prompt, settings.style.trim());
+    }
+
+    let image_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO embedded_images (id, story_id, entry_id, source_text, prompt, style_id, \
+         model, status, origin, created_at) VALUES (?, ?, ?, ?, ?, 'scene', ?, 'pending', 'scene', ?)",
+    )
+    .bind(&image_id)
+    .bind(&story_id)
+    .bind(entry_id)
+    .bind(&passages)
+    .bind(&prompt)
+    .bind(super::providers::model_for(&provider, &ImageRequest::default()))
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save scene illustration: {}", e))?;
+
+    let payload = ImageJobPayload {
+        request: ImageRequest {
+            provider_id: Some(provider.id.clone()),
+            prompt: prompt.clone(),
+            width: settings.width,
+            height: settings.height,
+            ..Default::default()
+        },
+        embedded_image_id: Some(image_id.clone()),
+        sprite_id: None,
+    };
+    let payload = serde_json::to_value(&payload)
+        .map_err(|e| format!("Failed to serialize image job: {}", e))?;
+    let image_job = app
+        .state::<JobQueue>()
+        .enqueue(pool, Some(&story_id), JobKind::ImageGeneration, payload)
+        .await?;
+    Ok(json!({
+        "embeddedImageId": image_id,
+        "imageJobId": image_job,
+        "prompt": prompt,
+    }))
+}
@@ -0,0 +1,312 @@
+//! Expression sprites, VN style: a set of images of one character with
+//! different expressions, generated from the character's portrait, and a
+//! heuristic that picks the one matching their latest lines.
+
+use regex::Regex;
+use serde_json::Value;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+use super::job::ImageJobPayload;
+use super::store;
+use super::types::{CharacterSprite, ImageRequest, Mood};
+use super::variation::describe;
+use crate::context::branch::visible_entries;
+use crate::db::now_millis;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+static QUOTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"["“]([^"“”]+)["”]"#).unwrap());
+static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\p{L}']+").unwrap());
+
+/// How far a sprite may stray from the portrait; low enough to keep the
+/// face, high enough for the expression to change
+const SPRITE_STRENGTH: f64 = 0.5;
+
+/// Entries searched for the character's latest lines, newest first
+const RECENT_ENTRIES: usize = 4;
+
+/// Words that suggest each mood; a trailing `*` matches any ending
+/// ("laugh*" matches "laughed" and "laughing")
+const LEXICON: &[(Mood, &str)] = &[
+    (
+        Mood::Happy,
+        "happy glad laugh* smil* grin* wonderful great love* delight* thank* haha yay joy* \
+         excit* cheer* perfect lovely",
+    ),
+    (
+        Mood::Sad,
+        "sad* sorry sigh* tear* cry cries cried weep* miss* lonely alone grief griev* mourn* \
+         regret* hurts lost unfortunately forgive",
+    ),
+    (
+        Mood::Angry,
+        "angry anger furious damn* hate* idiot* fool* enough glar* snarl* growl* shout* \
+         yell* rage* bastard* curse*",
+    ),
+    (
+        Mood::Surprised,
+        "what really wow oh whoa impossible unbelievable gasp* surpris* wait seriously huh \
+         shock*",
+    ),
+    (
+        Mood::Afraid,
+        "afraid scared fear* terrif* help please run tremb* shak* panic* danger* flee* hide \
+         hiding",
+    ),
+];
+
+/// Phrases that count a little more than single words
+const PHRASES: &[(Mood, &str)] = &[
+    (Mood::Angry, "how dare"),
+    (Mood::Angry, "shut up"),
+    (Mood::Afraid, "don't hurt"),
+    (Mood::Afraid, "stay away"),
+    (Mood::Sad, "i'm sorry"),
+];
+
+const NEGATIONS: &[&str] = &[
+    "not", "never", "no", "don't", "isn't", "wasn't", "can't", "won't",
+];
+
+fn sprite_from_row(r: &SqliteRow) -> CharacterSprite {
+    CharacterSprite {
+        id: r.get("id"),
+        character_id: r.get("character_id"),
+        story_id: r.get("story_id"),
+        mood: Mood::parse(&r.get::<String, _>("mood")).unwrap_or(Mood::Neutral),
+        prompt: r.get("prompt"),
+        model: r.get("model"),
+        image_data: r.get("image_data"),
+        width: r.get::<Option<i64>, _>("width").map(|w| w as u32),
+        height: r.get::<Option<i64>, _>("height").map(|h| h as u32),
+        status: r.get("status"),
+        error_message: r.get("error_message"),
+        created_at: r.get("created_at"),
+    }
+}
+
+pub async fn list(pool: &SqlitePool, character_id: &str) -> Result<Vec<CharacterSprite>, String> {
+    let rows = sqlx::query("SELECT * FROM character_sprites WHERE character_id = ?")
+        .bind(character_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load sprites: {}", e))?;
+    let mut sprites: Vec<CharacterSprite> = rows.iter().map(sprite_from_row).collect();
+    sprites.sort_by_key(|s| Mood::ALL.iter().position(|m| *m == s.mood));
+    Ok(sprites)
+}
+
+struct Character {
+    name: String,
+    story_id: String,
+    descriptors: Value,
+    portrait: Option<String>,
+}
+
+async fn load_character(pool: &SqlitePool, character_id: &str) -> Result<Character, String> {
+    let row: (String, String, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT name, story_id, visual_descriptors, portrait FROM characters WHERE id = ?",
+    )
+    .bind(character_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load character: {}", e))?
+    .ok_or_else(|| format!("Character not found: {}", character_id))?;
+    let (name, story_id, descriptors, portrait) = row;
+    Ok(Character {
+        name,
+        story_id,
+        descriptors: descriptors
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or(Value::Null),
+        portrait: portrait.filter(|p| !p.is_empty()),
+    })
+}
+
+/// Queue a sprite for each mood (all of them when `moods` is empty).
+/// Existing sprites are regenerated; their old image stays until the new
+/// one is saved.
+pub async fn generate_set(
+    pool: &SqlitePool,
+    queue: &JobQueue,
+    character_id: &str,
+    moods: &[Mood],
+    provider_id: Option<&str>,
+) -> Result<Vec<CharacterSprite>, String> {
+    let character = load_character(pool, character_id).await?;
+    let config = store::resolve(pool, provider_id).await?;
+    let appearance = describe(&character.descriptors);
+    let moods = if moods.is_empty() {
+        &Mood::ALL[..]
+    } else {
+        moods
+    };
+
+    for mood in moods {
+        let mut prompt = vec![format!("portrait of {}", character.name)];
+        prompt.extend(appearance.iter().cloned());
+        prompt.push(mood.expression().to_string());
+        prompt.push("upper body, simple background".to_string());
+        let prompt = prompt.join(", ");
+
+        sqlx::query(
+            "INSERT INTO character_sprites (id, character_id, story_id, mood, prompt, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?) ON CONFLICT(character_id, mood) DO UPDATE SET \
+             prompt = excluded.prompt, status = 'pending', error_message = NULL",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(character_id)
+        .bind(&character.story_id)
+        .bind(mood.as_str())
+        .bind(&prompt)
+        .bind(now_millis())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save sprite: {}", e))?;
+        let sprite_id: String = sqlx::query_scalar(
+            "SELECT id FROM character_sprites WHERE character_id = ? AND mood = ?",
+        )
+        .bind(character_id)
+        .bind(mood.as_str())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to save sprite: {}", e))?;
+
+        let payload = ImageJobPayload {
+            request: ImageRequest {
+                provider_id: Some(config.id.clone()),
+                prompt,
+                width: 832,
+                height: 1216,
+                // Start from the portrait so every sprite is the same person
+                reference_images: character.portrait.iter().cloned().collect(),
+                strength: character.portrait.as_ref().map(|_| SPRITE_STRENGTH),
+                ..Default::default()
+            },
+            embedded_image_id: None,
+            sprite_id: Some(sprite_id),
+        };
+        let payload = serde_json::to_value(&payload)
+            .map_err(|e| format!("Failed to serialize image job: {}", e))?;
+        queue
+            .enqueue(
+                pool,
+                Some(&character.story_id),
+                JobKind::ImageGeneration,
+                payload,
+            )
+            .await?;
+    }
+    list(pool, character_id).await
+}
+
+/// Mood of a piece of text by keyword counts. A negated happy word counts
+/// as sad; other negated words don't count.
+pub fn score_mood(text: &str) -> Mood {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = WORD.find_iter(&lower).map(|m| m.as_str()).collect();
+    let mut scores: HashMap<Mood, f32> = HashMap::new();
+    for (i, word) in words.iter().enumerate() {
+        let negated = words[i.saturating_sub(2)..i]
+            .iter()
+            .any(|w| NEGATIONS.contains(w));
+        for (mood, lexicon) in LEXICON {
+            let matched = lexicon
+                .split_whitespace()
+                .any(|entry| match entry.strip_suffix('*') {
+                    Some(stem) => word.starts_with(stem),
+                    None => word == &entry,
+                });
+            if !matched {
+                continue;
+            }
+            match (mood, negated) {
+                (Mood::Happy, true) => *scores.entry(Mood::Sad).or_default() += 1.0,
+                (_, true) => {}
+                _ => *scores.entry(*mood).or_default() += 1.0,
+            }
+        }
+    }
+    for (mood, phrase) in PHRASES {
+        *scores.entry(*mood).or_default() += lower.matches(phrase).count() as f32 * 1.5;
+    }
+    if text.contains("?!") || text.contains("!?") {
+        *scores.entry(Mood::Surprised).or_default() += 1.0;
+    }
+    if text.contains('!') {
+        // Exclamation strengthens whatever feeling is already there
+        for score in scores.values_mut() {
+            *score *= 1.25;
+        }
+    }
+    Mood::ALL
+        .into_iter()
+        .filter_map(|mood| scores.get(&mood).map(|score| (mood, *score)))
+        .filter(|(_, score)| *score >= 1.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(Mood::Neutral, |(mood, _)| mood)
+}
+
+/// The character's mood from their latest lines: dialogue in the newest
+/// paragraph that names them, or that paragraph's narration when they
+/// don't speak
+pub async fn detect_mood(pool: &SqlitePool, character_id: &str) -> Result<Mood, String> {
+    let character = load_character(pool, character_id).await?;
+    let names: Vec<String> = std::iter::once(character.name.to_lowercase())
+        .chain(
+            character
+                .name
+                .split_whitespace()
+                .next()
+                .map(str::to_lowercase),
+        )
+        .collect();
+    let entries = visible_entries(pool, &character.story_id).await?;
+    for entry in entries.iter().rev().take(RECENT_ENTRIES) {
+        let paragraph = entry.content.rsplit("\n\n").find(|p| {
+            let lower = p.to_lowercase();
+            names.iter().any(|name| lower.contains(name))
+        });
+        let Some(paragraph) = paragraph else {
+            continue;
+        };
+        let dialogue: Vec<&str> = QUOTE
+            .captures_iter(paragraph)
+            .filter_map(|c| c.get(1))
+            .map(|m| m.as_str())
+            .collect();
+        return Ok(if dialogue.is_empty() {
+            score_mood(paragraph)
+        } else {
+            score_mood(&dialogue.join(" "))
+        });
+    }
+    Ok(Mood::Neutral)
+}
+
+/// The finished sprite for `mood` (detected from the story when `None`),
+/// falling back to the neutral one
+pub async fn get_sprite(
+    pool: &SqlitePool,
+    character_id: &str,
+    mood: Option<Mood>,
+) -> Result<Option<CharacterSprite>, String> {
+    let mood = match mood {
+        Some(mood) => mood,
+        None => detect_mood(pool, character_id).await?,
+    };
+    let sprites: Vec<CharacterSprite> = list(pool, character_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.status == "complete" && !s.image_data.is_empty())
+        .collect();
+    let found = sprites
+        .iter()
+        .find(|s| s.mood == mood)
+        .or_else(|| sprites.iter().find(|s| s.mood == Mood::Neutral));
+    Ok(found.cloned())
+}
@@ -0,0 +1,123 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use super::types::{ImageBackend, ImageProviderConfig};
+use crate::db::now_millis;
+
+fn provider_from_row(r: &SqliteRow) -> Result<ImageProviderConfig, String> {
+    let provider_type: String = r.get("provider_type");
+    Ok(ImageProviderConfig {
+        id: r.get("id"),
+        name: r.get("name"),
+        provider_type: ImageBackend::parse(&provider_type)
+            .ok_or_else(|| format!("Unknown image provider type: {}", provider_type))?,
+        base_url: r.get("base_url"),
+        api_key: r.get("api_key"),
+        model: r.get("model"),
+        options: r
+            .get::<Option<String>, _>("options")
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default(),
+        max_concurrent: r
+            .get::<Option<i64>, _>("max_concurrent")
+            .map(|limit| limit as u32),
+        is_active: r.get::<i64, _>("is_active") != 0,
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    })
+}
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<ImageProviderConfig>, String> {
+    sqlx::query("SELECT * FROM image_providers ORDER BY name COLLATE NOCASE")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load image providers: {}", e))?
+        .iter()
+        .map(provider_from_row)
+        .collect()
+}
+
+/// The provider to send a request to: `provider_id` if given, otherwise
+/// the active one
+pub async fn resolve(
+    pool: &SqlitePool,
+    provider_id: Option<&str>,
+) -> Result<ImageProviderConfig, String> {
+    let row = match provider_id {
+        Some(id) => {
+            sqlx::query("SELECT * FROM image_providers WHERE id = ?")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        }
+        None => {
+            sqlx::query("SELECT * FROM image_providers WHERE is_active = 1 LIMIT 1")
+                .fetch_optional(pool)
+                .await
+        }
+    }
+    .map_err(|e| format!("Failed to load image provider: {}", e))?;
+    match (row, provider_id) {
+        (Some(row), _) => provider_from_row(&row),
+        (None, Some(id)) => Err(format!("Image provider not found: {}", id)),
+        (None, None) => Err(
+            "No image provider is active. Add one in the image settings and make it active."
+                .to_string(),
+        ),
+    }
+}
+
+/// Create or update a provider, returning its id. The first provider
+/// saved becomes the active one.
+pub async fn save(pool: &SqlitePool, provider: ImageProviderConfig) -> Result<String, String> {
+    if provider.name.trim().is_empty() {
+        return Err("Image provider name cannot be empty".to_string());
+    }
+    let id = if provider.id.is_empty() {
+        Uuid::new_v4().to_string()
+    } else {
+        provider.id.clone()
+    };
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO image_providers (id, name, provider_type, base_url, api_key, model, options, \
+         max_concurrent, is_active, created_at, updated_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?9, NOT EXISTS (SELECT 1 FROM image_providers), ?8, ?8) \
+         ON CONFLICT(id) DO UPDATE SET name = ?2, provider_type = ?3, base_url = ?4, api_key = ?5, \
+         model = ?6, options = ?7, max_concurrent = ?9, updated_at = ?8",
+    )
+    .bind(&id)
+    .bind(provider.name.trim())
+    .bind(provider.provider_type.as_str())
+    .bind(provider.base_url.trim())
+    .bind(provider.api_key.trim())
+    .bind(provider.model.trim())
+    .bind(serde_json::Value::Object(provider.options).to_string())
+    .bind(now)
+    .bind(provider.max_concurrent.map(i64::from))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save image provider: {}", e))?;
+    Ok(id)
+}
+
+pub async fn delete(pool: &SqlitePool, provider_id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM image_providers WHERE id = ?")
+        .bind(provider_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete image provider: {}", e))?;
+    Ok(())
+}
+
+/// Make `provider_id` the provider requests go to by default
+pub async fn set_active(pool: &SqlitePool, provider_id: &str) -> Result<(), String> {
+    resolve(pool, Some(provider_id)).await?;
+    sqlx::query("UPDATE image_providers SET is_active = (id = ?)")
+        .bind(provider_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to switch image provider: {}", e))?;
+    Ok(())
+}
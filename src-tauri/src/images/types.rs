@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The API an image provider speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageBackend {
+    /// OpenAI's Images API, or any service that copies it
+    OpenAi,
+    NovelAi,
+    Stability,
+    /// A local AUTOMATIC1111 or Forge Stable Diffusion WebUI
+    WebUi,
+}
+
+impl ImageBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageBackend::OpenAi => "openai",
+            ImageBackend::NovelAi => "novelai",
+            ImageBackend::Stability => "stability",
+            ImageBackend::WebUi => "webui",
+        }
+    }
+
+    pub fn parse(provider_type: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(provider_type.to_string())).ok()
+    }
+
+    /// Endpoint used when a provider leaves the base URL empty
+    pub fn default_base_url(&self) -> &'static str {
+        match self {
+            ImageBackend::OpenAi => "https://api.openai.com/v1",
+            ImageBackend::NovelAi => "https://image.novelai.net",
+            ImageBackend::Stability => "https://api.stability.ai",
+            ImageBackend::WebUi => "http://127.0.0.1:7860",
+        }
+    }
+
+    /// Requests a provider takes at once unless configured otherwise
+    pub fn default_concurrency(&self) -> usize {
+        match self {
+            // Local GPUs and NovelAI's API both work one image at a time
+            ImageBackend::WebUi | ImageBackend::NovelAi => 1,
+            ImageBackend::OpenAi | ImageBackend::Stability => 2,
+        }
+    }
+
+    /// Whether the backend can start from an image with a chosen strength
+    pub fn supports_strength(&self) -> bool {
+        !matches!(self, ImageBackend::OpenAi)
+    }
+
+    /// Model used when neither the provider nor the request names one
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            ImageBackend::OpenAi => "gpt-image-1",
+            ImageBackend::NovelAi => "nai-diffusion-4-5-full",
+            ImageBackend::Stability => "core",
+            ImageBackend::WebUi => "",
+        }
+    }
+}
+
+/// A configured image backend (a row of `image_providers`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageProviderConfig {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub provider_type: ImageBackend,
+    /// Empty uses the backend's usual endpoint
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// Empty uses the backend's default model
+    #[serde(default)]
+    pub model: String,
+    /// Extra request fields sent as-is (e.g. `steps`, `quality`)
+    #[serde(default)]
+    pub options: Map<String, Value>,
+    /// Requests allowed in flight at once; `None` uses the backend's default
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl ImageProviderConfig {
+    pub fn base_url(&self) -> &str {
+        let url = self.base_url.trim().trim_end_matches('/');
+        if url.is_empty() {
+            self.provider_type.default_base_url()
+        } else {
+            url
+        }
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.max_concurrent
+            .map_or(self.provider_type.default_concurrency(), |limit| {
+                limit as usize
+            })
+            .max(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageRequest {
+    /// Provider to use; `None` uses the active one
+    pub provider_id: Option<String>,
+    pub prompt: String,
+    pub negative_prompt: Option<String>,
+    /// Overrides the provider's model
+    pub model: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub seed: Option<u64>,
+    /// Base64 images to start from or keep consistent with, where the
+    /// backend supports it
+    pub reference_images: Vec<String>,
+    /// How far the result may stray from the first reference image, from
+    /// 0 (unchanged) to 1; `None` uses the backend's default
+    pub strength: Option<f64>,
+}
+
+impl Default for ImageRequest {
+    fn default() -> Self {
+        Self {
+            provider_id: None,
+            prompt: String::new(),
+            negative_prompt: None,
+            model: None,
+            width: 1024,
+            height: 1024,
+            seed: None,
+            reference_images: Vec::new(),
+            strength: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedImage {
+    /// Id of the image's `image_metadata` record, once it's recorded
+    pub id: String,
+    /// Raw base64, without a `data:` prefix
+    pub base64: String,
+    pub mime_type: String,
+    pub provider_id: String,
+    pub model: String,
+    /// The seed used, when the backend reports it
+    pub seed: Option<u64>,
+    /// The sampler used, when known
+    pub sampler: Option<String>,
+    /// The prompt as the backend rewrote it, if it did
+    pub revised_prompt: Option<String>,
+}
+
+/// Everything an image was generated with, kept so it can be reproduced
+/// or refined later (a row of `image_metadata`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    pub id: String,
+    /// SHA-256 of the image bytes
+    pub image_hash: String,
+    pub provider_id: Option<String>,
+    pub provider_type: Option<String>,
+    pub model: String,
+    pub prompt: String,
+    pub negative_prompt: Option<String>,
+    pub revised_prompt: Option<String>,
+    pub seed: Option<u64>,
+    pub sampler: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub strength: Option<f64>,
+    /// The provider's extra request fields (steps, guidance, ...)
+    pub parameters: Map<String, Value>,
+    pub created_at: i64,
+}
+
+/// A progress report for an image being generated
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageProgress {
+    /// From 0 to 1
+    pub fraction: f64,
+    /// Base64 of the image so far, for backends that show it
+    pub preview: Option<String>,
+}
+
+/// Emitted on `image-job-progress` while an image job runs
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageJobEvent {
+    pub job_id: String,
+    pub story_id: Option<String>,
+    /// 'waiting' (for a free slot or a rate limit) | 'generating' | 'saving'
+    pub stage: String,
+    pub fraction: Option<f64>,
+    pub preview: Option<String>,
+}
+
+/// A story's automatic scene illustration: after a response, the latest
+/// passages are turned into an image prompt and the picture is attached
+/// to the entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IllustrationSettings {
+    pub enabled: bool,
+    /// Illustrate every Nth entry of a chapter; 0 only illustrates chapter
+    /// starts
+    pub every_entries: u32,
+    /// Illustrate the first entry of each chapter
+    pub on_chapter_start: bool,
+    /// Scene illustrations allowed per chapter; `None` is unlimited
+    pub max_per_chapter: Option<u32>,
+    /// Passages the image prompt is written from
+    pub recent_entries: usize,
+    /// Art style appended to every prompt, e.g. "watercolor, muted colors"
+    pub style: String,
+    /// Template (in the scenario template syntax) for the request that
+    /// writes the image prompt; `None` uses the built-in one
+    pub prompt_template: Option<String>,
+    /// Image provider to use; `None` uses the active one
+    pub provider_id: Option<String>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for IllustrationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_entries: 5,
+            on_chapter_start: true,
+            max_per_chapter: Some(3),
+            recent_entries: 3,
+            style: String::new(),
+            prompt_template: None,
+            provider_id: None,
+            width: 1216,
+            height: 832,
+        }
+    }
+}
+
+/// Expressions a character sprite set covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mood {
+    Neutral,
+    Happy,
+    Sad,
+    Angry,
+    Surprised,
+    Afraid,
+}
+
+impl Mood {
+    pub const ALL: [Mood; 6] = [
+        Mood::Neutral,
+        Mood::Happy,
+        Mood::Sad,
+        Mood::Angry,
+        Mood::Surprised,
+        Mood::Afraid,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mood::Neutral => "neutral",
+            Mood::Happy => "happy",
+            Mood::Sad => "sad",
+            Mood::Angry => "angry",
+            Mood::Surprised => "surprised",
+            Mood::Afraid => "afraid",
+        }
+    }
+
+    pub fn parse(mood: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(mood.to_string())).ok()
+    }
+
+    /// How the expression is described in an image prompt
+    pub fn expression(&self) -> &'static str {
+        match self {
+            Mood::Neutral => "neutral expression, calm face",
+            Mood::Happy => "happy expression, warm smile",
+            Mood::Sad => "sad expression, downcast eyes",
+            Mood::Angry => "angry expression, furrowed brows, clenched jaw",
+            Mood::Surprised => "surprised expression, wide eyes, open mouth",
+            Mood::Afraid => "frightened expression, wide eyes, tense shoulders",
+        }
+    }
+}
+
+/// One expression of a character (a row of `character_sprites`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterSprite {
+    pub id: String,
+    pub character_id: String,
+    pub story_id: String,
+    pub mood: Mood,
+    pub prompt: String,
+    pub model: String,
+    /// Base64; empty until generated
+    pub image_data: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// 'pending' | 'generating' | 'complete' | 'failed'
+    pub status: String,
+    pub error_message: Option<String>,
+    pub created_at: i64,
+}
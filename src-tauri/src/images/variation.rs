@@ -0,0 +1,127 @@
+//! Variations of an existing character portrait: the portrait is sent to
+//! the backend's img2img mode with a prompt built from the character's
+//! appearance plus the requested change, so the face carries over.
+
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use super::metadata;
+use super::providers::{self, decode_reference};
+use super::store;
+use super::types::{GeneratedImage, ImageRequest};
+
+/// Appearance fields in the order they're described
+const DESCRIPTOR_FIELDS: &[&str] = &[
+    "face",
+    "hair",
+    "eyes",
+    "build",
+    "clothing",
+    "accessories",
+    "distinguishing",
+];
+
+/// NovelAI and most Stable Diffusion models want sides in multiples of 64
+const SIZE_STEP: u32 = 64;
+
+pub(super) struct Portrait {
+    pub name: String,
+    pub descriptors: Value,
+    pub image: String,
+}
+
+/// A story character's portrait, or else a vault character's
+pub(super) async fn load_portrait(
+    pool: &SqlitePool,
+    portrait_id: &str,
+) -> Result<Portrait, String> {
+    for table in ["characters", "character_vault"] {
+        let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(&format!(
+            "SELECT name, visual_descriptors, portrait FROM {} WHERE id = ?",
+            table
+        ))
+        .bind(portrait_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load character: {}", e))?;
+        if let Some((name, descriptors, image)) = row {
+            let image = image
+                .filter(|image| !image.is_empty())
+                .ok_or_else(|| format!("{} has no portrait yet", name))?;
+            return Ok(Portrait {
+                name,
+                descriptors: descriptors
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or(Value::Null),
+                image,
+            });
+        }
+    }
+    Err(format!("Character not found: {}", portrait_id))
+}
+
+/// Visual descriptors as prompt text. Older characters keep them as a
+/// plain list instead of named fields.
+pub fn describe(descriptors: &Value) -> Vec<String> {
+    let parts: Vec<&str> = match descriptors {
+        Value::Object(fields) => DESCRIPTOR_FIELDS
+            .iter()
+            .filter_map(|field| fields.get(*field)?.as_str())
+            .collect(),
+        Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn round_size(side: u32) -> u32 {
+    (side.div_ceil(SIZE_STEP) * SIZE_STEP).clamp(SIZE_STEP, 2048)
+}
+
+/// Generate a variation of a character's portrait with the active
+/// provider. `strength` (0 to 1) is how far it may move from the original;
+/// `prompt_delta` describes the change, e.g. "ten years older, scar across
+/// the left cheek". The current portrait is left as it is.
+pub async fn generate_variation(
+    pool: &SqlitePool,
+    portrait_id: &str,
+    strength: f64,
+    prompt_delta: &str,
+) -> Result<GeneratedImage, String> {
+    let config = store::resolve(pool, None).await?;
+    if !config.provider_type.supports_strength() {
+        return Err(format!(
+            "{} can't vary an existing image. Make a WebUI, NovelAI or Stability provider active.",
+            config.name
+        ));
+    }
+    let portrait = load_portrait(pool, portrait_id).await?;
+    let (width, height) = providers::image_size(&decode_reference(&portrait.image)?)
+        .map(|(w, h)| (round_size(w), round_size(h)))
+        .unwrap_or((1024, 1024));
+
+    let mut prompt = vec![format!("portrait of {}", portrait.name)];
+    prompt.extend(describe(&portrait.descriptors));
+    let delta = prompt_delta.trim();
+    if !delta.is_empty() {
+        prompt.push(delta.to_string());
+    }
+    let request = ImageRequest {
+        prompt: prompt.join(", "),
+        width,
+        height,
+        reference_images: vec![portrait.image],
+        strength: Some(strength.clamp(0.0, 1.0)),
+        ..Default::default()
+    };
+    let mut image = providers::generate(&config, &request)
+        .await
+        .map_err(|e| e.to_string())?;
+    metadata::record(pool, None, &config, &request, &mut image).await;
+    Ok(image)
+}
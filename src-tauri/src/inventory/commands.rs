@@ -0,0 +1,167 @@
+use serde_json::json;
+use tauri::State;
+use uuid::Uuid;
+
+use super::extract::load_inventory;
+use super::types::InventoryItem;
+use crate::db::{now_millis, DbState};
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+/// Get the items held in a story, optionally for a single character
+#[tauri::command]
+pub async fn get_inventory(
+    db: State<'_, DbState>,
+    story_id: String,
+    character_id: Option<String>,
+) -> Result<Vec<InventoryItem>, String> {
+    load_inventory(db.pool(), &story_id, character_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load inventory: {}", e))
+}
+
+/// Queue a model-assisted job that extracts item changes from a story entry
+#[tauri::command]
+pub async fn queue_inventory_extraction(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::InventoryExtraction,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
+
+/// Manually correct an item's quantity; zero or less removes it
+#[tauri::command]
+pub async fn set_inventory_item(
+    db: State<'_, DbState>,
+    story_id: String,
+    character_id: String,
+    name: String,
+    quantity: i64,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Item name cannot be empty".to_string());
+    }
+
+    check_character(db.pool(), &story_id, &character_id).await?;
+
+    if quantity <= 0 {
+        sqlx::query(
+            "DELETE FROM inventory_items WHERE story_id = ? AND character_id = ? AND name = ?",
+        )
+        .bind(&story_id)
+        .bind(&character_id)
+        .bind(name)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to remove item: {}", e))?;
+        return Ok(());
+    }
+
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO inventory_items (id, story_id, character_id, name, quantity, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(character_id, name) DO UPDATE SET quantity = excluded.quantity, updated_at = excluded.updated_at",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&story_id)
+    .bind(&character_id)
+    .bind(name)
+    .bind(quantity)
+    .bind(now)
+    .bind(now)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save item: {}", e))?;
+    Ok(())
+}
+
+/// Move an item (or part of a stack) to another character
+#[tauri::command]
+pub async fn transfer_inventory_item(
+    db: State<'_, DbState>,
+    item_id: String,
+    to_character_id: String,
+    quantity: Option<i64>,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let (story_id, name, held): (String, String, i64) =
+        sqlx::query_as("SELECT story_id, name, quantity FROM inventory_items WHERE id = ?")
+            .bind(&item_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load item: {}", e))?
+            .ok_or_else(|| format!("Item not found: {}", item_id))?;
+    if held < 1 {
+        return Err(format!("Item has nothing left to transfer: {}", name));
+    }
+    let moved = quantity.unwrap_or(held).clamp(1, held);
+
+    // Items only move between characters of the same story
+    check_character(&mut *tx, &story_id, &to_character_id).await?;
+    let now = now_millis();
+
+    sqlx::query("UPDATE inventory_items SET quantity = quantity - ?, updated_at = ? WHERE id = ?")
+        .bind(moved)
+        .bind(now)
+        .bind(&item_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update item: {}", e))?;
+    sqlx::query("DELETE FROM inventory_items WHERE id = ? AND quantity <= 0")
+        .bind(&item_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update item: {}", e))?;
+    sqlx::query(
+        "INSERT INTO inventory_items (id, story_id, character_id, name, quantity, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(character_id, name) DO UPDATE SET \
+         quantity = quantity + excluded.quantity, updated_at = excluded.updated_at",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&story_id)
+    .bind(&to_character_id)
+    .bind(&name)
+    .bind(moved)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to transfer item: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transfer: {}", e))
+}
+
+/// Refuse a character that isn't part of the story
+async fn check_character(
+    conn: impl sqlx::SqliteExecutor<'_>,
+    story_id: &str,
+    character_id: &str,
+) -> Result<(), String> {
+    sqlx::query("SELECT 1 FROM characters WHERE id = ? AND story_id = ?")
+        .bind(character_id)
+        .bind(story_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| format!("Failed to load character: {}", e))?
+        .ok_or_else(|| format!("Character not found in this story: {}", character_id))?;
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+
+use super::types::{ExtractionReply, InventoryItem};
+use crate::context::branch::characters;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+use crate::stats::engine::apply_deltas;
+use crate::stats::types::StatDelta;
+
+const SYSTEM_PROMPT: &str = "You track the items characters carry in an interactive story. \
+Given the characters, their current inventories, and the latest passage, list every item a character \
+picked up, received, used up, lost, or handed over in that passage. Only report changes that clearly happen \
+in the passage. Respond with JSON only, in this shape: \
+{\"changes\": [{\"character\": \"<character name>\", \"item\": \"<item name>\", \"quantity\": <integer, positive when gained, negative when lost>}]}. \
+Use existing item names when an item is already in an inventory. Respond with {\"changes\": []} if nothing changed.";
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "inventory_changes",
+        schema: json!({
+            "type": "object",
+            "required": ["changes"],
+            "properties": {
+                "changes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["character", "item", "quantity"],
+                        "properties": {
+                            "character": { "type": "string" },
+                            "item": { "type": "string" },
+                            "quantity": { "type": "integer" }
+                        }
+                    }
+                }
+            }
+        }),
+    }
+}
+
+/// Load inventory rows for a story, optionally limited to one character
+pub async fn load_inventory(
+    pool: &SqlitePool,
+    story_id: &str,
+    character_id: Option<&str>,
+) -> Result<Vec<InventoryItem>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT i.id, i.character_id, c.name AS character_name, i.name, i.quantity, i.updated_at \
+         FROM inventory_items i JOIN characters c ON c.id = i.character_id \
+         WHERE i.story_id = ?1 AND (?2 IS NULL OR i.character_id = ?2) \
+         ORDER BY CASE WHEN c.relationship = 'self' THEN 0 ELSE 1 END, c.name, i.name",
+    )
+    .bind(story_id)
+    .bind(character_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| InventoryItem {
+            id: r.get("id"),
+            character_id: r.get("character_id"),
+            character_name: r.get("character_name"),
+            name: r.get("name"),
+            quantity: r.get("quantity"),
+            updated_at: r.get("updated_at"),
+        })
+        .collect())
+}
+
+/// Render inventories as one line per character for prompt injection
+pub fn format_inventory_summary(items: &[InventoryItem]) -> String {
+    let mut lines: Vec<(String, Vec<String>)> = Vec::new();
+    for item in items {
+        let label = match item.quantity {
+            1 => item.name.clone(),
+            n => format!("{} x{}", item.name, n),
+        };
+        match lines.last_mut() {
+            Some((name, labels)) if *name == item.character_name => labels.push(label),
+            _ => lines.push((item.character_name.clone(), vec![label])),
+        }
+    }
+
+    lines
+        .iter()
+        .map(|(name, labels)| format!("{} carries: {}", name, labels.join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Background job: ask the classification model which items changed hands in an entry
+pub async fn run_extraction_job(
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Inventory extraction job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    // Only the current branch's characters, so items land on the copies it shows
+    let mut roster = characters(pool, &story_id).await?;
+    roster.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut inventory = load_inventory(pool, &story_id, None)
+        .await
+        .map_err(|e| format!("Failed to load inventory: {}", e))?;
+    inventory.retain(|item| roster.iter().any(|c| c.id == item.character_id));
+    let names: Vec<&str> = roster.iter().map(|c| c.name.as_str()).collect();
+
+    let current = if inventory.is_empty() {
+        "(empty)".to_string()
+    } else {
+        format_inventory_summary(&inventory)
+    };
+    let prompt = format!(
+        "Characters: {}\n\nCurrent inventories:\n{}\n\nPassage:\n{}",
+        names.join(", "),
+        current,
+        content
+    );
+
+    let llm = config::resolve_service(pool, "inventoryExtraction", "classification").await?;
+    let parsed: ExtractionReply = complete_structured(
+        pool,
+        "inventoryExtraction",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let deltas: Vec<StatDelta> = parsed
+        .changes
+        .into_iter()
+        .filter(|c| c.quantity != 0 && !c.item.trim().is_empty())
+        .map(|c| StatDelta::Item {
+            character: c.character,
+            name: c.item,
+            quantity: c.quantity,
+        })
+        .collect();
+
+    let report = apply_deltas(pool, &story_id, &deltas, "extraction").await?;
+    Ok(json!({
+        "entryId": entry_id,
+        "changes": deltas,
+        "unknownCharacters": report.unknown_characters,
+    }))
+}
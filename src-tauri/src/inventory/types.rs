@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// An item held by a character
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    pub id: String,
+    pub character_id: String,
+    pub character_name: String,
+    pub name: String,
+    pub quantity: i64,
+    pub updated_at: i64,
+}
+
+/// Item change reported by the extraction model
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractedItemChange {
+    pub character: String,
+    pub item: String,
+    pub quantity: i64,
+}
+
+/// Shape of the extraction model's JSON reply
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExtractionReply {
+    pub changes: Vec<ExtractedItemChange>,
+}
@@ -0,0 +1,79 @@
+use tauri::{AppHandle, State};
+
+use super::queue::job_from_row;
+use super::types::BackgroundJob;
+use crate::db::{now_millis, DbState};
+
+/// List background jobs, newest first, optionally filtered by story and status
+#[tauri::command]
+pub async fn get_background_jobs(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<BackgroundJob>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM background_jobs \
+         WHERE (?1 IS NULL OR story_id = ?1) AND (?2 IS NULL OR status = ?2) \
+         ORDER BY created_at DESC LIMIT ?3",
+    )
+    .bind(story_id)
+    .bind(status)
+    .bind(limit.unwrap_or(100))
+    .fetch_all(db.pool())
+    .await
+    .map_err(|e| format!("Failed to load jobs: {}", e))?;
+
+    Ok(rows.iter().map(job_from_row).collect())
+}
+
+/// Cancel a job that has not started yet
+#[tauri::command]
+pub async fn cancel_background_job(db: State<'_, DbState>, job_id: String) -> Result<bool, String> {
+    let result = sqlx::query("DELETE FROM background_jobs WHERE id = ? AND status = 'pending'")
+        .bind(&job_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to cancel job: {}", e))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Re-queue a failed job with a fresh attempt budget
+#[tauri::command]
+pub async fn retry_background_job(
+    db: State<'_, DbState>,
+    queue: State<'_, super::JobQueue>,
+    job_id: String,
+) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE background_jobs SET status = 'pending', attempts = 0, not_before = NULL, error = NULL, updated_at = ? \
+         WHERE id = ? AND status = 'failed'",
+    )
+    .bind(now_millis())
+    .bind(&job_id)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to retry job: {}", e))?;
+
+    if result.rows_affected() > 0 {
+        queue.wake();
+    }
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether heavy jobs are currently held back, and why
+#[tauri::command]
+pub async fn get_job_throttle_state(
+    throttle: State<'_, super::JobThrottle>,
+) -> Result<super::throttle::ThrottleState, String> {
+    Ok(throttle.state())
+}
+
+/// Re-evaluate the throttle now, e.g. after the settings changed
+#[tauri::command]
+pub async fn refresh_job_throttle(
+    app: AppHandle,
+) -> Result<super::throttle::ThrottleState, String> {
+    super::throttle::refresh(&app).await
+}
@@ -0,0 +1,7 @@
+pub mod commands;
+pub mod queue;
+pub mod throttle;
+pub mod types;
+
+pub use queue::JobQueue;
+pub use throttle::JobThrottle;
@@ -0,0 +1,320 @@
+use sqlx::{Row, SqlitePool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use super::types::{BackgroundJob, JobError, JobEvent, JobKind};
+use super::JobThrottle;
+use crate::analytics::Analytics;
+use crate::db::{now_millis, DbState};
+use crate::llm::limits;
+
+/// Attempts before a failing job is marked failed instead of retried
+pub const MAX_ATTEMPTS: i64 = 3;
+
+/// Wait before retrying a failed job, doubled with each attempt
+const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// How long the worker sleeps when idle before polling again
+const IDLE_POLL: Duration = Duration::from_secs(30);
+
+/// State managed by Tauri for the background job queue
+pub struct JobQueue {
+    notify: Arc<Notify>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            notify: Arc::new(Notify::new()),
+        }
+    }
+}
+
+impl JobQueue {
+    /// Persist a new job and wake the worker
+    pub async fn enqueue(
+        &self,
+        pool: &SqlitePool,
+        story_id: Option<&str>,
+        kind: JobKind,
+        payload: serde_json::Value,
+    ) -> Result<String, String> {
+        let id = Uuid::new_v4().to_string();
+        let now = now_millis();
+        sqlx::query(
+            "INSERT INTO background_jobs (id, story_id, kind, payload, status, attempts, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, 'pending', 0, ?, ?)",
+        )
+        .bind(&id)
+        .bind(story_id)
+        .bind(kind.as_str())
+        .bind(payload.to_string())
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to queue job: {}", e))?;
+
+        self.notify.notify_one();
+        Ok(id)
+    }
+
+    /// Wake the worker after jobs were re-queued outside of `enqueue`
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Start the worker task. Jobs left 'running' by a previous session are re-queued.
+    pub fn start(&self, app: AppHandle) {
+        let notify = self.notify.clone();
+        tauri::async_runtime::spawn(async move {
+            run_worker(app, notify).await;
+        });
+    }
+}
+
+async fn run_worker(app: AppHandle, notify: Arc<Notify>) {
+    let pool = app.state::<DbState>().pool().clone();
+    let running = Arc::new(AtomicUsize::new(0));
+    let mut recovered = false;
+
+    loop {
+        if !recovered {
+            // The table only exists once the frontend has run migrations
+            recovered = sqlx::query(
+                "UPDATE background_jobs SET status = 'pending', updated_at = ? WHERE status = 'running'",
+            )
+            .bind(now_millis())
+            .execute(&pool)
+            .await
+            .is_ok();
+            if !recovered {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        }
+
+        let max_jobs = match limits::load_settings(&pool).await {
+            Ok(settings) => settings.max_parallel_jobs.max(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        };
+        let claimed = if running.load(Ordering::SeqCst) >= max_jobs {
+            Ok(None)
+        } else {
+            claim_next(&pool, app.state::<JobThrottle>().paused()).await
+        };
+
+        let job = match claimed {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                // Finishing jobs notify too, freeing a slot or unblocking their kind
+                tokio::select! {
+                    _ = notify.notified() => {}
+                    _ = tokio::time::sleep(IDLE_POLL) => {}
+                }
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Background queue error: {}", e);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+
+        running.fetch_add(1, Ordering::SeqCst);
+        let (app, pool) = (app.clone(), pool.clone());
+        let (notify, running) = (notify.clone(), running.clone());
+        tauri::async_runtime::spawn(async move {
+            let retry_in = run_job(&app, &pool, job).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            notify.notify_one();
+            // Look again once the job may run, rather than at the next idle poll
+            if let Some(delay) = retry_in {
+                tokio::time::sleep(delay).await;
+                notify.notify_one();
+            }
+        });
+    }
+}
+
+/// Run a job and record the outcome. Returns how long until it may be
+/// retried, if it's going to be.
+async fn run_job(app: &AppHandle, pool: &SqlitePool, job: BackgroundJob) -> Option<Duration> {
+    emit_status(app, &job, "running", None, None);
+
+    let outcome = match JobKind::parse(&job.kind) {
+        Some(kind) => dispatch(app, pool, kind, &job).await,
+        None => Err(JobError::Failed(format!("Unknown job kind: {}", job.kind))),
+    };
+
+    let analytics = app.state::<Analytics>();
+    let source = format!("job.{}", job.kind);
+    match &outcome {
+        Ok(_) => analytics.record_feature(&source),
+        Err(JobError::Failed(e)) => analytics.record_error(&source, e),
+        Err(JobError::Deferred { .. }) => {}
+    }
+
+    let (status, error, result, retry_in, refund) = match outcome {
+        Ok(result) => ("completed", None, Some(result), None, false),
+        Err(JobError::Deferred { delay, reason }) => {
+            ("pending", Some(reason), None, Some(delay), true)
+        }
+        Err(JobError::Failed(e)) if job.attempts < MAX_ATTEMPTS => {
+            let delay = RETRY_BACKOFF * 2u32.pow((job.attempts - 1).max(0) as u32);
+            ("pending", Some(e), None, Some(delay), false)
+        }
+        Err(JobError::Failed(e)) => ("failed", Some(e), None, None, false),
+    };
+
+    let not_before = retry_in.map(|delay| now_millis() + delay.as_millis() as i64);
+    let outcome = JobOutcome {
+        status,
+        error: error.as_deref(),
+        result: result.as_ref(),
+        not_before,
+        refund,
+    };
+    if let Err(e) = finish(pool, &job.id, outcome).await {
+        eprintln!("Failed to record job result: {}", e);
+    }
+    emit_status(app, &job, status, error, result);
+    retry_in
+}
+
+/// Route a job to the subsystem that handles it
+async fn dispatch(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    kind: JobKind,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, JobError> {
+    let result = match kind {
+        JobKind::StatExtraction => crate::stats::engine::run_extraction_job(pool, job).await,
+        JobKind::InventoryExtraction => crate::inventory::extract::run_extraction_job(pool, job).await,
+        JobKind::QuestAnalysis => crate::quests::analysis::run_analysis_job(pool, job).await,
+        JobKind::Translation => crate::translation::job::run_translation_job(app, pool, job).await,
+        JobKind::RepetitionCheck => crate::prose::repetition::run_repetition_job(app, pool, job).await,
+        JobKind::WorldUpdate => crate::lore::world_update::run_world_update_job(app, pool, job).await,
+        JobKind::TimeUpdate => crate::calendar::update::run_time_update_job(app, pool, job).await,
+        JobKind::BeatCheck => crate::beats::check::run_beat_check_job(app, pool, job).await,
+        JobKind::ImageGeneration => return crate::images::job::run_image_job(app, pool, job).await,
+        JobKind::SceneIllustration => crate::images::scene::run_scene_job(app, pool, job).await,
+        JobKind::ChapterTheme => crate::audio::music::run_theme_job(app, pool, job).await,
+        JobKind::ArcDrift => crate::director::drift::run_drift_job(app, pool, job).await,
+        JobKind::NpcAgenda => crate::npcs::simulate::run_agenda_job(app, pool, job).await,
+        JobKind::FactionExtraction => crate::factions::extraction::run_extraction_job(pool, job).await,
+        JobKind::LocationUpdate => return crate::travel::extract::run_location_job(app, pool, job).await,
+    };
+    result.map_err(JobError::Failed)
+}
+
+fn emit_status(
+    app: &AppHandle,
+    job: &BackgroundJob,
+    status: &str,
+    error: Option<String>,
+    result: Option<serde_json::Value>,
+) {
+    let event = JobEvent {
+        job_id: job.id.clone(),
+        story_id: job.story_id.clone(),
+        kind: job.kind.clone(),
+        status: status.to_string(),
+        error,
+        result,
+    };
+    if let Err(e) = app.emit("background-job", &event) {
+        eprintln!("Failed to emit job event: {}", e);
+    }
+}
+
+/// Claim the oldest pending job that's due and whose story has no job of
+/// the same kind running, so jobs of one kind still apply to a story in
+/// order. While `throttled`, heavy kinds are left pending.
+async fn claim_next(pool: &SqlitePool, throttled: bool) -> Result<Option<BackgroundJob>, sqlx::Error> {
+    let held_back = if throttled {
+        let kinds: Vec<String> = JobKind::all()
+            .iter()
+            .filter(|k| k.is_heavy())
+            .map(|k| format!("'{}'", k.as_str()))
+            .collect();
+        format!("AND j.kind NOT IN ({}) ", kinds.join(", "))
+    } else {
+        String::new()
+    };
+    let unordered: Vec<String> = JobKind::all()
+        .iter()
+        .filter(|k| !k.runs_in_order())
+        .map(|k| format!("'{}'", k.as_str()))
+        .collect();
+    let now = now_millis();
+    let row = sqlx::query(&format!(
+        "UPDATE background_jobs SET status = 'running', attempts = attempts + 1, updated_at = ?1 \
+         WHERE id = (SELECT id FROM background_jobs j WHERE status = 'pending' {}\
+           AND (j.not_before IS NULL OR j.not_before <= ?1) \
+           AND (j.kind IN ({}) OR NOT EXISTS \
+           (SELECT 1 FROM background_jobs r WHERE r.status = 'running' AND r.kind = j.kind \
+            AND r.story_id IS j.story_id)) \
+         ORDER BY created_at LIMIT 1) \
+         RETURNING *",
+        held_back,
+        unordered.join(", ")
+    ))
+    .bind(now)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| job_from_row(&r)))
+}
+
+/// What `finish` records for a job
+struct JobOutcome<'a> {
+    status: &'a str,
+    error: Option<&'a str>,
+    result: Option<&'a serde_json::Value>,
+    not_before: Option<i64>,
+    /// Give back the attempt, for jobs deferred through no fault of their own
+    refund: bool,
+}
+
+async fn finish(pool: &SqlitePool, job_id: &str, outcome: JobOutcome<'_>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE background_jobs SET status = ?, error = ?, result = ?, not_before = ?, \
+         attempts = attempts - ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(outcome.status)
+    .bind(outcome.error)
+    .bind(outcome.result.map(|r| r.to_string()))
+    .bind(outcome.not_before)
+    .bind(outcome.refund as i64)
+    .bind(now_millis())
+    .bind(job_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub fn job_from_row(row: &sqlx::sqlite::SqliteRow) -> BackgroundJob {
+    let parse = |raw: Option<String>| raw.and_then(|r| serde_json::from_str(&r).ok());
+    BackgroundJob {
+        id: row.get("id"),
+        story_id: row.get("story_id"),
+        kind: row.get("kind"),
+        payload: parse(row.get("payload")).unwrap_or_default(),
+        status: row.get("status"),
+        attempts: row.get("attempts"),
+        not_before: row.get("not_before"),
+        error: row.get("error"),
+        result: parse(row.get("result")),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
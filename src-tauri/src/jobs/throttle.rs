@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::plugin::{Builder, PluginApi, TauriPlugin};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use super::JobQueue;
+use crate::db::DbState;
+use crate::llm::config::get_setting;
+
+/// How often the device's power and network state is read
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When to hold back heavy jobs, stored in `job_throttle_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThrottleSettings {
+    pub enabled: bool,
+    /// Pause below this battery level unless charging
+    pub min_battery_percent: u8,
+    /// Pause while the OS battery saver is on, unless charging
+    pub pause_in_power_save: bool,
+    /// Pause on metered (usually mobile data) connections
+    pub pause_on_metered: bool,
+}
+
+impl Default for ThrottleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_battery_percent: 20,
+            pause_in_power_save: true,
+            pause_on_metered: true,
+        }
+    }
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ThrottleSettings, String> {
+    Ok(get_setting(pool, "job_throttle_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Power and network state as the platform reports it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceState {
+    pub battery_percent: Option<u8>,
+    pub charging: bool,
+    pub metered: bool,
+    pub power_save: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThrottleReason {
+    LowBattery,
+    PowerSave,
+    Metered,
+}
+
+/// Emitted on `job-throttle` when it changes, and returned by
+/// `get_job_throttle_state`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleState {
+    /// Whether this platform reports its power state
+    pub supported: bool,
+    /// Heavy jobs stay queued while set
+    pub paused: bool,
+    pub reasons: Vec<ThrottleReason>,
+    pub device: Option<DeviceState>,
+}
+
+impl ThrottleState {
+    fn evaluate(settings: &ThrottleSettings, device: Option<DeviceState>) -> Self {
+        let mut reasons = Vec::new();
+        if let Some(d) = device.as_ref().filter(|_| settings.enabled) {
+            if !d.charging {
+                if d.battery_percent
+                    .is_some_and(|p| p < settings.min_battery_percent)
+                {
+                    reasons.push(ThrottleReason::LowBattery);
+                }
+                if d.power_save && settings.pause_in_power_save {
+                    reasons.push(ThrottleReason::PowerSave);
+                }
+            }
+            if d.metered && settings.pause_on_metered {
+                reasons.push(ThrottleReason::Metered);
+            }
+        }
+        Self {
+            supported: device.is_some(),
+            paused: !reasons.is_empty(),
+            reasons,
+            device,
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+struct DeviceStatePlugin(tauri::plugin::PluginHandle<Wry>);
+
+/// Throttle state shared with the queue worker
+pub struct JobThrottle {
+    state: Mutex<ThrottleState>,
+}
+
+impl Default for JobThrottle {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(ThrottleState::evaluate(&ThrottleSettings::default(), None)),
+        }
+    }
+}
+
+impl JobThrottle {
+    pub fn paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    pub fn state(&self) -> ThrottleState {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+#[cfg(target_os = "android")]
+fn register(app: &AppHandle, api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = api.register_android_plugin("com.karelian.aventura", "DeviceStatePlugin")?;
+    app.manage(DeviceStatePlugin(handle));
+    Ok(())
+}
+
+#[cfg(not(target_os = "android"))]
+fn register(_app: &AppHandle, _api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Registers the Android power state reader; other platforms never throttle
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("device-state").setup(register).build()
+}
+
+#[cfg(target_os = "android")]
+fn read_device(app: &AppHandle) -> Option<DeviceState> {
+    match app
+        .state::<DeviceStatePlugin>()
+        .0
+        .run_mobile_plugin("powerState", ())
+    {
+        Ok(device) => Some(device),
+        Err(e) => {
+            eprintln!("Failed to read device state: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn read_device(_app: &AppHandle) -> Option<DeviceState> {
+    None
+}
+
+/// Re-read the settings and device state, tell the UI if the throttle
+/// changed and wake the queue when heavy jobs may run again
+pub async fn refresh(app: &AppHandle) -> Result<ThrottleState, String> {
+    let pool = app.state::<DbState>().pool().clone();
+    let settings = load_settings(&pool).await?;
+    let next = ThrottleState::evaluate(&settings, read_device(app));
+
+    let throttle = app.state::<JobThrottle>();
+    let previous = std::mem::replace(&mut *throttle.state.lock().unwrap(), next.clone());
+    if previous != next {
+        if let Err(e) = app.emit("job-throttle", &next) {
+            eprintln!("Failed to emit job-throttle: {}", e);
+        }
+        if previous.paused && !next.paused {
+            app.state::<JobQueue>().wake();
+        }
+    }
+    Ok(next)
+}
+
+/// Poll the device state on platforms that report it
+pub fn start(app: AppHandle) {
+    if cfg!(not(target_os = "android")) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Fails until the frontend has created the settings table
+            let _ = refresh(&app).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
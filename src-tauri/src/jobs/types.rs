@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Kinds of work the background queue knows how to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Parse stat, condition, and item changes out of a story entry
+    StatExtraction,
+    /// Model-assisted detection of items gained or lost in a story entry
+    InventoryExtraction,
+    /// Model-assisted detection of quest progress in a story entry
+    QuestAnalysis,
+    /// Translate a batch of narration entries
+    Translation,
+    /// Score a narration entry for repeated phrasing
+    RepetitionCheck,
+    /// Model-assisted lorebook update from a snapshot of the story
+    WorldUpdate,
+    /// Model-assisted estimate of the story time an entry covers
+    TimeUpdate,
+    /// Model-assisted detection of story beats an entry resolves
+    BeatCheck,
+    /// Generate an image with an image provider
+    ImageGeneration,
+    /// Write an image prompt for the latest scene and queue its image
+    SceneIllustration,
+    /// Pick or generate a music theme for a chapter
+    ChapterTheme,
+    /// Model-assisted check of how far the story has drifted from its arc plan
+    ArcDrift,
+    /// Simulate what major NPCs do off-screen over a story day
+    NpcAgenda,
+    /// Model-assisted detection of faction and reputation changes in a story entry
+    FactionExtraction,
+    /// Model-assisted tracking of the party's location, checked against travel times
+    LocationUpdate,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::StatExtraction => "stat_extraction",
+            JobKind::InventoryExtraction => "inventory_extraction",
+            JobKind::QuestAnalysis => "quest_analysis",
+            JobKind::Translation => "translation",
+            JobKind::RepetitionCheck => "repetition_check",
+            JobKind::WorldUpdate => "world_update",
+            JobKind::TimeUpdate => "time_update",
+            JobKind::BeatCheck => "beat_check",
+            JobKind::ImageGeneration => "image_generation",
+            JobKind::SceneIllustration => "scene_illustration",
+            JobKind::ChapterTheme => "chapter_theme",
+            JobKind::ArcDrift => "arc_drift",
+            JobKind::NpcAgenda => "npc_agenda",
+            JobKind::FactionExtraction => "faction_extraction",
+            JobKind::LocationUpdate => "location_update",
+        }
+    }
+
+    /// Jobs that call a model or run a local engine, held back while the
+    /// queue is throttled on battery or a metered connection
+    pub fn is_heavy(&self) -> bool {
+        !matches!(self, JobKind::StatExtraction | JobKind::RepetitionCheck)
+    }
+
+    /// Jobs of this kind for one story run one at a time, oldest first.
+    /// Images don't build on each other, so only their provider limits them.
+    pub fn runs_in_order(&self) -> bool {
+        !matches!(self, JobKind::ImageGeneration)
+    }
+
+    pub fn all() -> [JobKind; 15] {
+        [
+            JobKind::StatExtraction,
+            JobKind::InventoryExtraction,
+            JobKind::QuestAnalysis,
+            JobKind::Translation,
+            JobKind::RepetitionCheck,
+            JobKind::WorldUpdate,
+            JobKind::TimeUpdate,
+            JobKind::BeatCheck,
+            JobKind::ImageGeneration,
+            JobKind::SceneIllustration,
+            JobKind::ChapterTheme,
+            JobKind::ArcDrift,
+            JobKind::NpcAgenda,
+            JobKind::FactionExtraction,
+            JobKind::LocationUpdate,
+        ]
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(kind.to_string())).ok()
+    }
+}
+
+/// A job persisted in the `background_jobs` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundJob {
+    pub id: String,
+    pub story_id: Option<String>,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// 'pending' | 'running' | 'completed' | 'failed'
+    pub status: String,
+    pub attempts: i64,
+    /// A pending job isn't started before this time
+    pub not_before: Option<i64>,
+    pub error: Option<String>,
+    pub result: Option<serde_json::Value>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Emitted on `background-job` whenever a job changes status
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEvent {
+    pub job_id: String,
+    pub story_id: Option<String>,
+    pub kind: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub result: Option<serde_json::Value>,
+}
+
+/// Why a job didn't complete
+#[derive(Debug)]
+pub enum JobError {
+    Failed(String),
+    /// Try again after `delay` without using up an attempt, e.g. when a
+    /// provider rate-limits the job
+    Deferred {
+        delay: Duration,
+        reason: String,
+    },
+}
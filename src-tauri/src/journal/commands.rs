@@ -0,0 +1,409 @@
+use serde_json::{Map, Value};
+use tauri::State;
+
+use super::order::{sequence, OrderEntry};
+use super::replace::{build_pattern, replace_in};
+use super::store::{self, capture_delete, capture_rows, record};
+use super::types::{JournalOperation, OrderReport, OrderStrategy, ReplaceOptions, ReplaceReport};
+use crate::backup::snapshot::{bind_value, quote, table_columns};
+use crate::context::branch::visible_entries;
+use crate::db::{now_millis, DbState};
+
+/// Tables holding a branch's own rows, deleted along with it
+pub const BRANCH_TABLES: &[&str] = &[
+    "story_entries",
+    "chapters",
+    "characters",
+    "locations",
+    "items",
+    "story_beats",
+    "entries",
+];
+
+/// Lore fields a bulk edit may not touch
+const PROTECTED_LORE_FIELDS: &[&str] = &["id", "story_id", "created_at", "updated_at"];
+
+fn counted(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+/// Delete story entries, keeping them (and their images, alternatives and
+/// world state changes) in the journal so the delete can be undone
+#[tauri::command]
+pub async fn delete_story_entries(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_ids: Vec<String>,
+) -> Result<JournalOperation, String> {
+    let ids = serde_json::to_string(&entry_ids).map_err(|e| e.to_string())?;
+    let condition = "story_id = ?1 AND id IN (SELECT value FROM json_each(?2))";
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let images = capture_delete(&mut tx, "story_entries", condition, &[&story_id, &ids]).await?;
+    let deleted = images.iter().filter(|i| i.table == "story_entries").count();
+    sqlx::query(&format!("DELETE FROM story_entries WHERE {}", condition))
+        .bind(&story_id)
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete entries: {}", e))?;
+
+    let description = format!("Delete {}", counted(deleted, "entry", "entries"));
+    let operation = record(
+        &mut tx,
+        Some(&story_id),
+        store::KIND_DELETE_ENTRIES,
+        description,
+        &images,
+    )
+    .await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete entries: {}", e))?;
+    Ok(operation)
+}
+
+/// Apply the same field changes to many lore entries at once. Object and
+/// array values are stored as JSON, like the frontend stores them.
+#[tauri::command]
+pub async fn bulk_edit_lore(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_ids: Vec<String>,
+    changes: Map<String, Value>,
+) -> Result<JournalOperation, String> {
+    let columns = table_columns(db.pool(), "entries").await?;
+    if let Some(field) = changes
+        .keys()
+        .find(|field| !columns.contains(field) || PROTECTED_LORE_FIELDS.contains(&field.as_str()))
+    {
+        return Err(format!("Lore entries have no editable field '{}'", field));
+    }
+    if changes.is_empty() {
+        return Err("Nothing to change".to_string());
+    }
+
+    let ids = serde_json::to_string(&entry_ids).map_err(|e| e.to_string())?;
+    let condition = "story_id = ?1 AND id IN (SELECT value FROM json_each(?2))";
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let images = capture_rows(&mut tx, "entries", condition, &[&story_id, &ids]).await?;
+
+    let sql = format!(
+        "UPDATE entries SET {}, updated_at = ?3 WHERE {}",
+        changes
+            .keys()
+            .enumerate()
+            .map(|(i, field)| format!("{} = ?{}", quote(field), i + 4))
+            .collect::<Vec<_>>()
+            .join(", "),
+        condition
+    );
+    let mut query = sqlx::query(&sql)
+        .bind(&story_id)
+        .bind(&ids)
+        .bind(now_millis());
+    for value in changes.values() {
+        query = bind_value(query, value);
+    }
+    query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update lore: {}", e))?;
+
+    let description = format!(
+        "Edit {}",
+        counted(images.len(), "lore entry", "lore entries")
+    );
+    let operation = record(
+        &mut tx,
+        Some(&story_id),
+        store::KIND_BULK_LORE_EDIT,
+        description,
+        &images,
+    )
+    .await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update lore: {}", e))?;
+    Ok(operation)
+}
+
+/// Delete a branch with everything on it, the way the story view does,
+/// keeping it all in the journal so the prune can be undone
+#[tauri::command]
+pub async fn prune_branch(
+    db: State<'_, DbState>,
+    story_id: String,
+    branch_id: String,
+) -> Result<JournalOperation, String> {
+    let name: Option<String> =
+        sqlx::query_scalar("SELECT name FROM branches WHERE id = ? AND story_id = ?")
+            .bind(&branch_id)
+            .bind(&story_id)
+            .fetch_optional(db.pool())
+            .await
+            .map_err(|e| format!("Failed to load branch: {}", e))?;
+    let name = name.ok_or_else(|| format!("Branch not found: {}", branch_id))?;
+
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    // Entries and chapters point at each other; check once everything's gone
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let mut images = Vec::new();
+    for table in BRANCH_TABLES {
+        let condition = "branch_id = ?1";
+        images.extend(capture_delete(&mut tx, table, condition, &[&branch_id]).await?);
+        sqlx::query(&format!("DELETE FROM {} WHERE {}", quote(table), condition))
+            .bind(&branch_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete from {}: {}", table, e))?;
+    }
+    images.extend(capture_delete(&mut tx, "branches", "id = ?1", &[&branch_id]).await?);
+    sqlx::query("DELETE FROM branches WHERE id = ?")
+        .bind(&branch_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete branch: {}", e))?;
+
+    let operation = record(
+        &mut tx,
+        Some(&story_id),
+        store::KIND_PRUNE_BRANCH,
+        format!("Delete branch \"{}\"", name),
+        &images,
+    )
+    .await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete branch: {}", e))?;
+    Ok(operation)
+}
+
+/// Most changed entries previewed by a bulk replace
+const MAX_REPLACE_SAMPLES: usize = 20;
+
+/// Find and replace across a story's entries in one transaction, journaled
+/// so it can be undone. With `dry_run` set, only counts and previews.
+#[tauri::command]
+pub async fn bulk_replace_text(
+    db: State<'_, DbState>,
+    story_id: String,
+    find: String,
+    replace: String,
+    options: ReplaceOptions,
+) -> Result<ReplaceReport, String> {
+    let pattern = build_pattern(&find, &options)?;
+    let pool = db.pool();
+    let entries: Vec<(String, String)> = if options.current_branch_only {
+        visible_entries(pool, &story_id)
+            .await?
+            .into_iter()
+            .map(|e| (e.id, e.content))
+            .collect()
+    } else {
+        sqlx::query_as("SELECT id, content FROM story_entries WHERE story_id = ? ORDER BY position")
+            .bind(&story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load story entries: {}", e))?
+    };
+
+    let mut changed = Vec::new();
+    let mut report = ReplaceReport {
+        matches: 0,
+        entries: 0,
+        samples: Vec::new(),
+        operation: None,
+    };
+    for (id, content) in &entries {
+        let Some((updated, sample)) = replace_in(&pattern, content, &replace, &options, id) else {
+            continue;
+        };
+        report.matches += sample.matches;
+        report.entries += 1;
+        if report.samples.len() < MAX_REPLACE_SAMPLES {
+            report.samples.push(sample);
+        }
+        if updated != *content {
+            changed.push((id, updated));
+        }
+    }
+    if options.dry_run || changed.is_empty() {
+        return Ok(report);
+    }
+
+    let ids = serde_json::to_string(&changed.iter().map(|(id, _)| id).collect::<Vec<_>>())
+        .map_err(|e| e.to_string())?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut images = capture_rows(
+        &mut tx,
+        "story_entries",
+        "story_id = ?1 AND id IN (SELECT value FROM json_each(?2))",
+        &[&story_id, &ids],
+    )
+    .await?;
+    // The active variant mirrors the entry's text
+    let alternatives = "entry_id IN (SELECT value FROM json_each(?1)) AND active = 1";
+    images.extend(capture_rows(&mut tx, "entry_alternatives", alternatives, &[&ids]).await?);
+    for (id, content) in &changed {
+        sqlx::query("UPDATE story_entries SET content = ? WHERE id = ?")
+            .bind(content)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update entry: {}", e))?;
+        sqlx::query("UPDATE entry_alternatives SET content = ? WHERE entry_id = ? AND active = 1")
+            .bind(content)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update alternative: {}", e))?;
+    }
+
+    let description = format!(
+        "Replace \"{}\" in {}",
+        find,
+        counted(changed.len(), "entry", "entries")
+    );
+    report.operation = Some(
+        record(
+            &mut tx,
+            Some(&story_id),
+            store::KIND_BULK_REPLACE,
+            description,
+            &images,
+        )
+        .await?,
+    );
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update entries: {}", e))?;
+    Ok(report)
+}
+
+/// Re-sequence a story's entries, branch by branch, after an import or sync
+/// merge left them out of order. Each branch keeps the positions it starts
+/// at; cycles and ambiguities are reported rather than guessed at.
+#[tauri::command]
+pub async fn repair_entry_order(
+    db: State<'_, DbState>,
+    story_id: String,
+    strategy: Option<OrderStrategy>,
+) -> Result<OrderReport, String> {
+    let strategy = strategy.unwrap_or_default();
+    let pool = db.pool();
+    let branches: Vec<Option<String>> = sqlx::query_scalar(
+        "SELECT DISTINCT branch_id FROM story_entries WHERE story_id = ? ORDER BY branch_id",
+    )
+    .bind(&story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load story entries: {}", e))?;
+
+    let mut report = OrderReport {
+        moved: 0,
+        issues: Vec::new(),
+        operation: None,
+    };
+    let mut moves: Vec<(String, i64)> = Vec::new();
+    for branch_id in branches {
+        let entries: Vec<OrderEntry> = sqlx::query_as(
+            "SELECT id, parent_id, position, created_at FROM story_entries \
+             WHERE story_id = ? AND branch_id IS ? ORDER BY position, id",
+        )
+        .bind(&story_id)
+        .bind(&branch_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load story entries: {}", e))?;
+        let Some(order) = sequence(&entries, strategy, &branch_id, &mut report.issues) else {
+            continue;
+        };
+        let first = entries.first().map_or(0, |e| e.position);
+        for (offset, i) in order.into_iter().enumerate() {
+            let position = first + offset as i64;
+            if entries[i].position != position {
+                moves.push((entries[i].id.clone(), position));
+            }
+        }
+    }
+    report.moved = moves.len();
+    if moves.is_empty() {
+        return Ok(report);
+    }
+
+    let ids = serde_json::to_string(&moves.iter().map(|(id, _)| id).collect::<Vec<_>>())
+        .map_err(|e| e.to_string())?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let images = capture_rows(
+        &mut tx,
+        "story_entries",
+        "story_id = ?1 AND id IN (SELECT value FROM json_each(?2))",
+        &[&story_id, &ids],
+    )
+    .await?;
+    for (id, position) in &moves {
+        sqlx::query("UPDATE story_entries SET position = ? WHERE id = ?")
+            .bind(position)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to move entry: {}", e))?;
+    }
+    let description = format!("Reorder {}", counted(moves.len(), "entry", "entries"));
+    report.operation = Some(
+        record(
+            &mut tx,
+            Some(&story_id),
+            store::KIND_REPAIR_ORDER,
+            description,
+            &images,
+        )
+        .await?,
+    );
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to reorder entries: {}", e))?;
+    Ok(report)
+}
+
+/// Undo operations, newest first, of one story or of the whole app
+#[tauri::command]
+pub async fn list_journal_operations(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<JournalOperation>, String> {
+    store::list(db.pool(), story_id.as_deref()).await
+}
+
+/// Undo the newest journaled operation, of one story if given. Returns the
+/// operation undone, or `None` when there was nothing left to undo.
+#[tauri::command]
+pub async fn undo_last_operation(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Option<JournalOperation>, String> {
+    store::undo_last(db.pool(), story_id.as_deref()).await
+}
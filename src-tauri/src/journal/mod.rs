@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod order;
+pub mod replace;
+pub mod store;
+pub mod types;
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use super::types::{OrderIssue, OrderStrategy};
+
+/// A story entry as `repair_entry_order` sees it
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderEntry {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub position: i64,
+    pub created_at: i64,
+}
+
+fn issue(
+    kind: &str,
+    branch_id: &Option<String>,
+    entries: &[OrderEntry],
+    of: &[usize],
+) -> OrderIssue {
+    OrderIssue {
+        kind: kind.to_string(),
+        branch_id: branch_id.clone(),
+        entry_ids: of.iter().map(|&i| entries[i].id.clone()).collect(),
+    }
+}
+
+/// The order one branch's entries should be in, as indices into `entries`
+/// (which come in their current order). `None` when a cycle of parent links
+/// leaves no safe order; what couldn't be settled goes into `issues`.
+pub fn sequence(
+    entries: &[OrderEntry],
+    strategy: OrderStrategy,
+    branch_id: &Option<String>,
+    issues: &mut Vec<OrderIssue>,
+) -> Option<Vec<usize>> {
+    let by_time = |order: &mut Vec<usize>| {
+        order.sort_by_key(|&i| (entries[i].created_at, entries[i].position));
+    };
+    match strategy {
+        OrderStrategy::Timestamp => {
+            let mut order: Vec<usize> = (0..entries.len()).collect();
+            by_time(&mut order);
+            // Nothing tells these apart; they stay in id order
+            for tied in order.chunk_by(|&a, &b| {
+                (entries[a].created_at, entries[a].position)
+                    == (entries[b].created_at, entries[b].position)
+            }) {
+                if tied.len() > 1 {
+                    issues.push(issue("tie", branch_id, entries, tied));
+                }
+            }
+            Some(order)
+        }
+        OrderStrategy::ParentLinks => {
+            let index: HashMap<&str, usize> = entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (e.id.as_str(), i))
+                .collect();
+            let mut roots = Vec::new();
+            let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (i, entry) in entries.iter().enumerate() {
+                // A parent on another branch (the fork) counts as no parent
+                match entry.parent_id.as_deref().and_then(|p| index.get(p)) {
+                    Some(&parent) => children.entry(parent).or_default().push(i),
+                    None => roots.push(i),
+                }
+            }
+            for (&parent, siblings) in children.iter_mut() {
+                by_time(siblings);
+                if siblings.len() > 1 {
+                    let mut family = vec![parent];
+                    family.extend(siblings.iter());
+                    issues.push(issue("siblings", branch_id, entries, &family));
+                }
+            }
+            by_time(&mut roots);
+
+            let mut order = Vec::with_capacity(entries.len());
+            let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+            while let Some(i) = stack.pop() {
+                order.push(i);
+                if let Some(next) = children.get(&i) {
+                    stack.extend(next.iter().rev());
+                }
+            }
+            if order.len() < entries.len() {
+                // Whatever wasn't reached hangs off a loop of parent links
+                let mut reached = vec![false; entries.len()];
+                for &i in &order {
+                    reached[i] = true;
+                }
+                let stuck: Vec<usize> = (0..entries.len()).filter(|&i| !reached[i]).collect();
+                issues.push(issue("cycle", branch_id, entries, &stuck));
+                return None;
+            }
+            Some(order)
+        }
+    }
+}
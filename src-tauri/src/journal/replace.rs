@@ -0,0 +1,83 @@
+use regex::{Captures, Regex, RegexBuilder};
+
+use super::types::{ReplaceOptions, ReplaceSample};
+
+/// Bytes of text kept on each side of a previewed match
+const CONTEXT: usize = 60;
+
+pub fn build_pattern(find: &str, options: &ReplaceOptions) -> Result<Regex, String> {
+    if find.is_empty() {
+        return Err("Nothing to find".to_string());
+    }
+    let mut pattern = if options.regex {
+        find.to_string()
+    } else {
+        regex::escape(find)
+    };
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// `content` with every match replaced, how many there were and a preview
+/// of the first; `None` when nothing matches
+pub fn replace_in(
+    pattern: &Regex,
+    content: &str,
+    replace: &str,
+    options: &ReplaceOptions,
+    entry_id: &str,
+) -> Option<(String, ReplaceSample)> {
+    let expand = |caps: &Captures| {
+        if options.regex {
+            let mut out = String::new();
+            caps.expand(replace, &mut out);
+            out
+        } else {
+            replace.to_string()
+        }
+    };
+    let first = pattern.captures(content)?;
+    let whole = first.get(0)?;
+    let matches = pattern.find_iter(content).count();
+    let updated = pattern.replace_all(content, expand).into_owned();
+
+    let start = floor_boundary(content, whole.start().saturating_sub(CONTEXT));
+    let end = ceil_boundary(content, (whole.end() + CONTEXT).min(content.len()));
+    let (lead, trail) = (
+        if start > 0 { "…" } else { "" },
+        if end < content.len() { "…" } else { "" },
+    );
+    let sample = ReplaceSample {
+        entry_id: entry_id.to_string(),
+        matches,
+        before: format!("{}{}{}", lead, &content[start..end], trail),
+        after: format!(
+            "{}{}{}{}{}",
+            lead,
+            &content[start..whole.start()],
+            expand(&first),
+            &content[whole.end()..end],
+            trail
+        ),
+    };
+    Some((updated, sample))
+}
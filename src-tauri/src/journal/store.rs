@@ -0,0 +1,265 @@
+//! The operation journal keeps, for each destructive operation, every row
+//! it deleted or changed as it was before. Undoing writes those rows back,
+//! which reverses deletes (including what they cascaded to) and edits alike.
+
+use serde_json::Value;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use super::types::{JournalOperation, RowImage};
+use crate::backup::snapshot::{bind_value, quote, row_to_json, table_columns};
+use crate::db::now_millis;
+
+pub const KIND_DELETE_ENTRIES: &str = "deleteEntries";
+pub const KIND_BULK_LORE_EDIT: &str = "bulkLoreEdit";
+pub const KIND_PRUNE_BRANCH: &str = "pruneBranch";
+pub const KIND_BULK_REPLACE: &str = "bulkReplace";
+pub const KIND_REPAIR_ORDER: &str = "repairOrder";
+
+/// Operations kept; older ones can no longer be undone
+const MAX_OPERATIONS: i64 = 50;
+
+/// How deep to follow cascading deletes (entry -> image is one level)
+const MAX_CASCADE_DEPTH: usize = 4;
+
+/// Rows of `table` matching `condition`. Parameters are numbered (`?1`,
+/// `?2`...) since conditions get nested into each other.
+pub async fn capture_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    condition: &str,
+    params: &[&str],
+) -> Result<Vec<RowImage>, String> {
+    let sql = format!(
+        "SELECT rowid AS \"$rowid\", * FROM {} WHERE {} ORDER BY rowid",
+        quote(table),
+        condition
+    );
+    let mut query = sqlx::query(&sql);
+    for param in params {
+        query = query.bind(*param);
+    }
+    query
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", table, e))?
+        .iter()
+        .map(|row| {
+            Ok(RowImage {
+                table: table.to_string(),
+                row: row_to_json(row)?,
+            })
+        })
+        .collect()
+}
+
+/// Rows a delete of `table` rows matching `condition` would remove, plus
+/// the rows foreign keys would cascade to or null out, parents first
+pub async fn capture_delete(
+    conn: &mut SqliteConnection,
+    table: &str,
+    condition: &str,
+    params: &[&str],
+) -> Result<Vec<RowImage>, String> {
+    let mut images = Vec::new();
+    let mut pending = vec![(table.to_string(), condition.to_string(), 0)];
+    while let Some((table, condition, depth)) = pending.pop() {
+        images.extend(capture_rows(conn, &table, &condition, params).await?);
+        if depth >= MAX_CASCADE_DEPTH {
+            continue;
+        }
+        let dependents: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT m.name, f.\"from\", f.\"to\", f.on_delete FROM sqlite_master m \
+             JOIN pragma_foreign_key_list(m.name) f WHERE m.type = 'table' AND f.\"table\" = ?",
+        )
+        .bind(&table)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+
+        for (child, from, to, on_delete) in dependents {
+            let child_condition = format!(
+                "{} IN (SELECT {} FROM {} WHERE {})",
+                quote(&from),
+                quote(&to),
+                quote(&table),
+                condition
+            );
+            match on_delete.as_str() {
+                "CASCADE" if child != table => {
+                    pending.push((child, child_condition, depth + 1));
+                }
+                // Only the referencing column changes; nothing further is lost
+                "CASCADE" | "SET NULL" | "SET DEFAULT" => {
+                    images.extend(capture_rows(conn, &child, &child_condition, params).await?);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    images.retain(|image| {
+        let rowid = image.row.get("$rowid").and_then(Value::as_i64);
+        seen.insert((image.table.clone(), rowid))
+    });
+    Ok(images)
+}
+
+/// Journal an operation run in `tx`, trimming the history to its limit
+pub async fn record(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: Option<&str>,
+    kind: &str,
+    description: String,
+    images: &[RowImage],
+) -> Result<JournalOperation, String> {
+    let changes =
+        serde_json::to_string(images).map_err(|e| format!("Failed to serialize rows: {}", e))?;
+    let operation = JournalOperation {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.map(str::to_string),
+        kind: kind.to_string(),
+        description,
+        row_count: images.len() as i64,
+        created_at: now_millis(),
+    };
+    sqlx::query(
+        "INSERT INTO operation_journal (id, story_id, kind, description, changes, row_count, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&operation.id)
+    .bind(&operation.story_id)
+    .bind(&operation.kind)
+    .bind(&operation.description)
+    .bind(changes)
+    .bind(operation.row_count)
+    .bind(operation.created_at)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to journal operation: {}", e))?;
+
+    sqlx::query(
+        "DELETE FROM operation_journal WHERE id NOT IN \
+         (SELECT id FROM operation_journal ORDER BY created_at DESC, rowid DESC LIMIT ?)",
+    )
+    .bind(MAX_OPERATIONS)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to trim journal: {}", e))?;
+    Ok(operation)
+}
+
+pub async fn list(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<JournalOperation>, String> {
+    sqlx::query_as(
+        "SELECT id, story_id, kind, description, row_count, created_at FROM operation_journal \
+         WHERE ?1 IS NULL OR story_id = ?1 ORDER BY created_at DESC, rowid DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load journal: {}", e))
+}
+
+/// Put one row back as it was: updated in place when it still exists,
+/// inserted again otherwise
+async fn restore_row(conn: &mut SqliteConnection, image: &RowImage) -> Result<(), String> {
+    let columns = table_columns(&mut *conn, &image.table).await?;
+    let keys: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk")
+            .bind(&image.table)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read schema of {}: {}", image.table, e))?;
+    let present: Vec<(&String, &Value)> = image
+        .row
+        .iter()
+        .filter(|(name, _)| columns.contains(name))
+        .collect();
+    if present.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<String> = present.iter().map(|(name, _)| quote(name)).collect();
+    let mut sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote(&image.table),
+        names.join(", "),
+        vec!["?"; names.len()].join(", ")
+    );
+    // An upsert rather than INSERT OR REPLACE, which would delete the row
+    // first and cascade to everything referencing it
+    if !keys.is_empty() {
+        sql.push_str(&format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            keys.iter().map(|k| quote(k)).collect::<Vec<_>>().join(", "),
+            names
+                .iter()
+                .map(|name| format!("{name} = excluded.{name}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let mut query = sqlx::query(&sql);
+    for (_, value) in &present {
+        query = bind_value(query, value);
+    }
+    query
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to restore {}: {}", image.table, e))?;
+    Ok(())
+}
+
+/// Undo the newest journaled operation (of one story, if given) and drop
+/// it from the journal. Returns `None` when there's nothing to undo.
+pub async fn undo_last(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Option<JournalOperation>, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let operation: Option<JournalOperation> = sqlx::query_as(
+        "SELECT id, story_id, kind, description, row_count, created_at FROM operation_journal \
+         WHERE ?1 IS NULL OR story_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT 1",
+    )
+    .bind(story_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load journal: {}", e))?;
+    let Some(operation) = operation else {
+        return Ok(None);
+    };
+
+    let changes: String = sqlx::query_scalar("SELECT changes FROM operation_journal WHERE id = ?")
+        .bind(&operation.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load journal: {}", e))?;
+    let images: Vec<RowImage> =
+        serde_json::from_str(&changes).map_err(|e| format!("Invalid journal entry: {}", e))?;
+
+    // Rows reference each other (entries and branches), so check foreign
+    // keys once all of them are back
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to begin undo: {}", e))?;
+    for image in &images {
+        restore_row(&mut tx, image).await?;
+    }
+    sqlx::query("DELETE FROM operation_journal WHERE id = ?")
+        .bind(&operation.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update journal: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to undo: {}", e))?;
+    Ok(Some(operation))
+}
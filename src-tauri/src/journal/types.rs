@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A row as it was before an operation changed or deleted it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowImage {
+    pub table: String,
+    pub row: Map<String, Value>,
+}
+
+/// A journaled destructive operation that can still be undone
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalOperation {
+    pub id: String,
+    pub story_id: Option<String>,
+    /// `deleteEntries`, `bulkLoreEdit`, `pruneBranch`, `bulkReplace` or
+    /// `repairOrder`
+    pub kind: String,
+    /// Shown to the user, e.g. in an "Undo ..." button
+    pub description: String,
+    /// Rows the undo puts back
+    pub row_count: i64,
+    pub created_at: i64,
+}
+
+/// How `bulk_replace_text` matches
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplaceOptions {
+    /// `find` is a regular expression and `replace` may use `$1`, `${name}`
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Only entries readable on the current branch
+    pub current_branch_only: bool,
+    /// Count and preview the matches without changing anything
+    pub dry_run: bool,
+}
+
+/// One changed entry, shown around its first match
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceSample {
+    pub entry_id: String,
+    pub matches: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceReport {
+    pub matches: usize,
+    pub entries: usize,
+    pub samples: Vec<ReplaceSample>,
+    /// The journaled change, once applied
+    pub operation: Option<JournalOperation>,
+}
+
+/// How `repair_entry_order` decides the order of a branch's entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderStrategy {
+    /// By creation time, keeping the current order among equal times
+    #[default]
+    Timestamp,
+    /// Each entry right after its `parent_id`; entries without a parent on
+    /// the branch, and siblings, by creation time
+    ParentLinks,
+}
+
+/// Something `repair_entry_order` couldn't settle by itself
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderIssue {
+    /// `cycle` (the branch was left as it was), `siblings` (entries sharing a
+    /// parent, placed by creation time) or `tie` (same position and time,
+    /// placed by id)
+    pub kind: String,
+    pub branch_id: Option<String>,
+    pub entry_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderReport {
+    /// Entries whose position changed
+    pub moved: usize,
+    pub issues: Vec<OrderIssue>,
+    /// The journaled change, if anything moved
+    pub operation: Option<JournalOperation>,
+}
@@ -1,13 +1,242 @@
 use tauri::Manager;
 use tauri_plugin_sql::{Migration, MigrationKind};
 
+mod alternatives;
+mod analytics;
+mod annotations;
+mod attribution;
+mod audio;
+mod backup;
+mod beats;
+mod bookmarks;
+mod branches;
+mod calendar;
+mod chapters;
+mod clipboard;
+mod combat;
+mod content_pack;
+mod context;
+mod dataset;
+mod db;
+mod director;
+mod environment;
+mod export_templates;
+mod factions;
+mod file_import;
+mod filter;
+mod generation;
+mod grammar;
+mod hardware;
+mod images;
+mod inventory;
+mod jobs;
+mod journal;
+mod lint;
+mod llm;
+mod local_llm;
+mod lore;
 mod migration_patch;
+mod migration_preflight;
+mod models;
+mod npcs;
+mod paths;
+mod pipeline;
+mod postprocess;
+mod presets;
+mod profiles;
+mod pronunciation;
+mod prose;
+mod quests;
+mod reader;
+mod redaction;
+mod remote;
+mod sandbox;
+mod scenario;
+mod sessions;
+mod share;
+mod stats;
+mod storage;
+mod story_format;
 mod sync;
+mod translation;
+mod travel;
+mod tray;
 
+use analytics::commands::{
+    clear_analytics, export_analytics_report, get_analytics_report, record_feature_usage,
+    set_analytics_enabled,
+};
+use alternatives::commands::{
+    add_entry_alternative, delete_entry_alternative, get_story_alternatives,
+    import_entry_alternatives, list_entry_alternatives, prune_entry_alternatives,
+    set_active_alternative,
+};
+use annotations::commands::{
+    add_entry_annotation, delete_entry_annotation, get_entry_annotations, get_shared_annotations,
+    import_entry_annotations, update_entry_annotation,
+};
+use attribution::commands::get_export_attribution;
+use audio::commands::{
+    download_chapter_theme, get_ambience, get_chapter_theme, install_sound_pack, list_sound_packs,
+    queue_chapter_theme, remove_sound_pack, set_ambience, set_ambience_crossfade,
+    set_ambience_volume, update_ambience,
+};
+use backup::commands::{
+    clear_s3_backup_credentials, get_s3_backup, has_s3_backup_credentials, list_incremental_backups,
+    list_s3_backups, reconstruct_incremental_backup, restore_s3_backup_story,
+    restore_story_from_backup, run_incremental_backup, run_s3_backup, set_s3_backup_credentials,
+    verify_backup_chain,
+};
+use beats::commands::{get_beat_suggestions, resolve_beat_suggestion};
+use bookmarks::commands::{
+    add_bookmark, delete_bookmark, get_bookmarks, resolve_bookmark, update_bookmark,
+};
+use branches::commands::get_branch_tree;
+use calendar::commands::{
+    advance_story_time, get_story_calendar, get_story_date, parse_time_expression,
+    set_story_calendar,
+};
+use chapters::commands::{merge_chapters, split_chapter};
+use clipboard::commands::{get_clipboard_watch_status, set_clipboard_watch};
+use combat::commands::{
+    end_combat, get_combat, mark_combat_narrated, resolve_combat_round, start_combat,
+};
+use content_pack::commands::{
+    build_content_pack, install_content_pack, list_content_packs, preview_content_pack,
+    uninstall_content_pack,
+};
+use context::commands::{get_context_blocks, preview_context};
+use dataset::commands::{
+    export_entries_dataset, export_finetune_dataset, get_entry_quality, set_entry_quality,
+};
+use director::commands::{
+    check_arc_drift, delete_arc_plan, get_arc_drift, get_arc_plan, get_arc_status, set_arc_plan,
+    set_current_act,
+};
+use environment::commands::{
+    delete_environment_region, get_current_weather, get_environment_regions,
+    save_environment_region, set_weather,
+};
+use export_templates::commands::{
+    delete_export_template, export_with_template, get_export_templates, import_export_template,
+    save_export_template,
+};
+use factions::commands::{
+    adjust_reputation, create_faction, delete_faction, delete_reputation_change, get_factions,
+    get_reputation_history, queue_faction_update, remove_faction_member, set_faction_member,
+    update_faction,
+};
+use file_import::commands::{cancel_import, import_file, import_from_url, take_opened_files};
+use filter::commands::{
+    check_filter_stream, delete_filter_rule, enforce_filters, get_filter_rules,
+    get_filter_statistics, reset_filter_statistics, save_filter_rule,
+};
+use generation::commands::{
+    attach_generation, cancel_generation, cancel_prefetch, choose_candidate, claim_prefetch,
+    discard_generation, generate_candidates, get_interrupted_generations, regenerate_with_seed,
+    resume_generation, schedule_prefetch, start_generation,
+};
+use grammar::commands::check_text;
+use hardware::commands::get_hardware_capabilities;
+use images::commands::{
+    delete_image_provider, export_image, generate_character_sprites, generate_image,
+    generate_portrait_variation, get_character_sprite, get_illustration_settings,
+    get_image_metadata, illustrate_scene, list_character_sprites, list_image_providers,
+    queue_image_generation, regenerate_portrait, save_image_provider, score_portrait,
+    set_active_image_provider, set_illustration_settings,
+};
+use inventory::commands::{
+    get_inventory, queue_inventory_extraction, set_inventory_item, transfer_inventory_item,
+};
+use jobs::commands::{
+    cancel_background_job, get_background_jobs, get_job_throttle_state, refresh_job_throttle,
+    retry_background_job,
+};
+use journal::commands::{
+    bulk_edit_lore, bulk_replace_text, delete_story_entries, list_journal_operations,
+    prune_branch, repair_entry_order, undo_last_operation,
+};
+use lint::commands::{apply_lint_fix, lint_story};
+use llm::commands::{
+    clear_request_debug, count_text_tokens, delete_model_profile, finish_request_debug,
+    get_last_request_debug, get_prompt_cache_stats, get_story_model_bindings, list_model_profiles,
+    list_request_debug, record_request_debug, save_model_profile, set_story_model_binding,
+};
+use local_llm::commands::{get_local_llm_status, load_local_llm, unload_local_llm};
+use lore::commands::{
+    get_active_lore, queue_world_update, reset_lore_timers, schedule_lore_payload,
+    set_lore_validity,
+};
+use migration_preflight::get_migration_preflight;
+use models::commands::{
+    cancel_model_download, download_model, get_installed_model, list_installed_models,
+    remove_model,
+};
+use npcs::commands::{delete_npc_agenda, get_npc_agendas, set_npc_agenda, simulate_npcs};
+use pipeline::commands::{
+    get_turn_pipeline, get_turn_pipeline_run, retry_turn_pipeline, run_turn_pipeline,
+    set_turn_pipeline,
+};
+use postprocess::commands::{
+    apply_postprocess, delete_postprocess_rule, extract_suggestions, get_postprocess_rules,
+    reorder_postprocess_rules, save_postprocess_rule, test_postprocess_rule,
+};
+use presets::commands::{export_preset, import_preset};
+use profiles::commands::{
+    create_profile, delete_profile, get_data_paths, list_profiles, rename_profile, switch_profile,
+};
+use pronunciation::commands::{
+    apply_pronunciations, delete_pronunciation, get_pronunciations, save_pronunciation,
+    test_pronunciation,
+};
+use prose::commands::{
+    analyze_repetition, check_entity_names, get_story_entities, get_style_metrics, measure_style,
+    queue_repetition_check,
+};
+use quests::commands::{
+    create_quest, delete_quest, get_active_quests, get_quest_suggestions, get_quests,
+    queue_quest_analysis, resolve_quest_suggestion, set_objective_status, set_quest_status,
+};
+use reader::commands::{get_web_reader_status, start_web_reader, stop_web_reader};
+use redaction::commands::{
+    delete_redaction_rule, get_redaction_rules, preview_redaction, redact_story_export,
+    save_redaction_rule,
+};
+use sandbox::commands::{
+    create_sandbox_branch, discard_sandbox_branch, get_sandbox_branches, promote_sandbox_branch,
+};
+use scenario::commands::instantiate_scenario;
+use sessions::commands::{
+    end_activity, get_achievements, get_activity_heatmap, get_writing_activity, get_writing_streak,
+    record_activity, set_story_finished, set_story_word_goal,
+};
+use share::commands::{export_to_encrypted_share, import_from_share_link};
+use stats::commands::{
+    apply_stat_deltas, get_character_sheets, get_stat_rules, queue_stat_extraction,
+    set_character_stat, set_stat_rules,
+};
+use storage::commands::{get_storage_report, preview_image_gc, run_image_gc, set_data_directory};
+use story_format::commands::validate_export_file;
 use sync::commands::{
-    clear_received_stories, get_received_stories, start_sync_server, stop_sync_server,
-    sync_connect, sync_pull_story, sync_push_story,
+    ble_pair, ble_scan, ble_sync_connect, ble_sync_pull_story, ble_sync_push_story,
+    clear_received_stories, get_ble_status, get_direct_link, get_local_network_access,
+    get_received_stories, get_sync_bandwidth_limit, get_sync_service_status,
+    request_local_network_access, send_story_to_device, set_sync_bandwidth_limit,
+    start_ble_advertising, start_direct_sync_server, start_sync_server, start_sync_service,
+    stop_ble_advertising, stop_sync_server, stop_sync_service, sync_connect, sync_handshake,
+    sync_list_stories_if_changed, sync_pull_story, sync_push_story,
 };
+use translation::commands::{
+    clear_translation_cache, delete_glossary_entry, delete_translation_model,
+    download_translation_model, get_local_translation_status, get_translation_glossary,
+    list_translation_models, queue_translation, save_glossary_entry, suggest_glossary_terms,
+};
+use travel::commands::{
+    delete_location_route, dismiss_travel_warning, get_location_routes, get_party_location,
+    get_travel_time, get_travel_warnings, queue_location_update, set_location_route,
+    set_party_location,
+};
+use tray::commands::set_close_to_tray;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -209,9 +438,304 @@ pub fn run() {
             description: "vault_assistant_conversations",
             sql: include_str!("../migrations/033_vault_assistant_conversations.sql"),
             kind: MigrationKind::Up,
-        }
+        },
+        Migration {
+            version: 34,
+            description: "scenario_templates",
+            sql: include_str!("../migrations/034_scenario_templates.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 35,
+            description: "background_jobs",
+            sql: include_str!("../migrations/035_background_jobs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 36,
+            description: "character_stats",
+            sql: include_str!("../migrations/036_character_stats.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 37,
+            description: "quests",
+            sql: include_str!("../migrations/037_quests.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 38,
+            description: "story_calendars",
+            sql: include_str!("../migrations/038_story_calendars.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 39,
+            description: "environment",
+            sql: include_str!("../migrations/039_environment.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 40,
+            description: "translation_cache",
+            sql: include_str!("../migrations/040_translation_cache.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 41,
+            description: "translation_glossary",
+            sql: include_str!("../migrations/041_translation_glossary.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 42,
+            description: "postprocess_rules",
+            sql: include_str!("../migrations/042_postprocess_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 43,
+            description: "filter_rules",
+            sql: include_str!("../migrations/043_filter_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 44,
+            description: "llm_request_log",
+            sql: include_str!("../migrations/044_llm_request_log.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 45,
+            description: "lore_activation",
+            sql: include_str!("../migrations/045_lore_activation.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 46,
+            description: "turn_pipeline",
+            sql: include_str!("../migrations/046_turn_pipeline.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 47,
+            description: "generation_requests",
+            sql: include_str!("../migrations/047_generation_requests.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 48,
+            description: "model_profiles",
+            sql: include_str!("../migrations/048_model_profiles.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 49,
+            description: "generation_candidates",
+            sql: include_str!("../migrations/049_generation_candidates.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 50,
+            description: "entry_generation_params",
+            sql: include_str!("../migrations/050_entry_generation_params.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 51,
+            description: "entry_alternatives",
+            sql: include_str!("../migrations/051_entry_alternatives.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 52,
+            description: "generation_prefetch",
+            sql: include_str!("../migrations/052_generation_prefetch.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 53,
+            description: "prompt_cache_stats",
+            sql: include_str!("../migrations/053_prompt_cache_stats.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 54,
+            description: "installed_content_packs",
+            sql: include_str!("../migrations/054_installed_content_packs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 55,
+            description: "backup_change_log",
+            sql: include_str!("../migrations/055_backup_change_log.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 56,
+            description: "operation_journal",
+            sql: include_str!("../migrations/056_operation_journal.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 57,
+            description: "image_providers",
+            sql: include_str!("../migrations/057_image_providers.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 58,
+            description: "image_job_retry",
+            sql: include_str!("../migrations/058_image_job_retry.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 59,
+            description: "scene_illustration",
+            sql: include_str!("../migrations/059_scene_illustration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 60,
+            description: "image_metadata",
+            sql: include_str!("../migrations/060_image_metadata.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 61,
+            description: "character_sprites",
+            sql: include_str!("../migrations/061_character_sprites.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 62,
+            description: "chapter_themes",
+            sql: include_str!("../migrations/062_chapter_themes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 63,
+            description: "writing_sessions",
+            sql: include_str!("../migrations/063_writing_sessions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 64,
+            description: "achievements",
+            sql: include_str!("../migrations/064_achievements.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 65,
+            description: "analytics",
+            sql: include_str!("../migrations/065_analytics.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 66,
+            description: "beat_suggestions",
+            sql: include_str!("../migrations/066_beat_suggestions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 67,
+            description: "lore_payloads",
+            sql: include_str!("../migrations/067_lore_payloads.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 68,
+            description: "story_arcs",
+            sql: include_str!("../migrations/068_story_arcs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 69,
+            description: "npc_agendas",
+            sql: include_str!("../migrations/069_npc_agendas.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 70,
+            description: "factions",
+            sql: include_str!("../migrations/070_factions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 71,
+            description: "travel",
+            sql: include_str!("../migrations/071_travel.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 72,
+            description: "combat",
+            sql: include_str!("../migrations/072_combat.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 73,
+            description: "sandbox_branches",
+            sql: include_str!("../migrations/073_sandbox_branches.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 74,
+            description: "bookmarks",
+            sql: include_str!("../migrations/074_bookmarks.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 75,
+            description: "entry_annotations",
+            sql: include_str!("../migrations/075_entry_annotations.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 76,
+            description: "chapter_summary_stale",
+            sql: include_str!("../migrations/076_chapter_summary_stale.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 77,
+            description: "export_templates",
+            sql: include_str!("../migrations/077_export_templates.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 78,
+            description: "entry_quality",
+            sql: include_str!("../migrations/078_entry_quality.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 79,
+            description: "redaction_rules",
+            sql: include_str!("../migrations/079_redaction_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 80,
+            description: "story_provenance",
+            sql: include_str!("../migrations/080_story_provenance.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 81,
+            description: "pronunciations",
+            sql: include_str!("../migrations/081_pronunciations.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 82,
+            description: "share_uploads",
+            sql: include_str!("../migrations/082_share_uploads.sql"),
+            kind: MigrationKind::Up,
+        },
     ];
 
+    #[allow(unused_mut)] // only mutated when the devtools feature is enabled
     let mut builder = tauri::Builder::default();
 
     #[cfg(all(debug_assertions, feature = "devtools"))]
@@ -222,22 +746,71 @@ pub fn run() {
 
     builder
         .manage(sync::SyncState::default())
+        .manage(jobs::JobQueue::default())
+        .manage(jobs::JobThrottle::default())
+        .manage(generation::engine::GenerationState::default())
+        .manage(generation::prefetch::PrefetchState::default())
+        .manage(file_import::OpenedFiles::default())
+        .manage(file_import::ImportRegistry::default())
+        .manage(clipboard::ClipboardWatcher::default())
+        .manage(tray::TrayState::default())
+        .manage(reader::ReaderState::default())
+        .manage(hardware::HardwareState::default())
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:aventura.db", migrations)
-                .build(),
-        )
-        .setup(|app| {
-            let db_path = app
-                .path()
-                .app_data_dir()
-                .expect("failed to get app data dir")
-                .join("aventura.db");
+        .setup(move |app| {
+            let paths = profiles::store::resolve(app.handle())?;
+            let db_path = paths.database.clone();
 
-            if db_path.try_exists().expect("failed to check db path") {
+            let preflight = if db_path.try_exists().expect("failed to check db path") {
                 tauri::async_runtime::block_on(migration_patch::apply_checksum_patch(&db_path));
+                tauri::async_runtime::block_on(migration_preflight::rehearse(&db_path, &migrations))
+            } else {
+                migration_preflight::MigrationPreflight::default()
+            };
+            if let Some(error) = &preflight.error {
+                eprintln!("Migration rehearsal failed, keeping the current schema: {}", error);
             }
+            // Registered here rather than on the builder so it only gets
+            // migrations that survived the rehearsal
+            app.handle().plugin(
+                tauri_plugin_sql::Builder::default()
+                    .add_migrations(
+                        &paths.database_url,
+                        migration_preflight::allowed(migrations, &preflight),
+                    )
+                    .build(),
+            )?;
+            app.manage(preflight);
+
+            app.manage(db::DbState::new(&db_path));
+            app.manage(translation::local::LocalTranslator::new(
+                paths
+                    .local
+                    .join("models")
+                    .join(translation::local::MODEL_KIND),
+            ));
+            app.manage(audio::Ambience::new(paths.local.join("sound-packs")));
+            app.manage(models::ModelManager::new(paths.local.join("models")));
+            local_llm::watch(app.handle().clone(), paths.local.join("models"));
+            app.manage(sessions::SessionTracker::default());
+            app.manage(analytics::Analytics::default());
+            app.manage(paths);
+            app.state::<jobs::JobQueue>().start(app.handle().clone());
+            jobs::throttle::start(app.handle().clone());
+            pipeline::runner::resume(app.handle().clone());
+            generation::engine::recover(app.handle().clone());
+            clipboard::watcher::restore(app.handle().clone());
+            audio::player::restore(app.handle().clone());
+            sessions::achievements::start(app.handle().clone());
+            sandbox::cleanup::start(app.handle().clone());
+            analytics::collector::start(app.handle().clone());
+            tray::commands::restore(app.handle().clone());
+            sync::bandwidth::restore(app.handle().clone());
+            share::cleanup::start(app.handle().clone());
+            #[cfg(desktop)]
+            tray::icon::create(app)?;
+            file_import::commands::clear_downloads(app.handle());
+            file_import::commands::open_paths(app.handle(), file_import::commands::launch_paths());
 
             Ok(())
         })
@@ -247,6 +820,10 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(sync::service::init())
+        .plugin(jobs::throttle::init())
+        .plugin(sync::ble::init())
+        .plugin(sync::direct::init())
         .invoke_handler(tauri::generate_handler![
             start_sync_server,
             stop_sync_server,
@@ -255,7 +832,327 @@ pub fn run() {
             sync_connect,
             sync_pull_story,
             sync_push_story,
+            instantiate_scenario,
+            get_background_jobs,
+            cancel_background_job,
+            retry_background_job,
+            get_character_sheets,
+            apply_stat_deltas,
+            queue_stat_extraction,
+            set_character_stat,
+            get_stat_rules,
+            set_stat_rules,
+            get_context_blocks,
+            preview_context,
+            get_inventory,
+            queue_inventory_extraction,
+            set_inventory_item,
+            transfer_inventory_item,
+            get_quests,
+            get_active_quests,
+            create_quest,
+            delete_quest,
+            set_quest_status,
+            set_objective_status,
+            queue_quest_analysis,
+            get_quest_suggestions,
+            resolve_quest_suggestion,
+            get_story_calendar,
+            set_story_calendar,
+            get_story_date,
+            parse_time_expression,
+            advance_story_time,
+            get_environment_regions,
+            save_environment_region,
+            delete_environment_region,
+            get_current_weather,
+            set_weather,
+            queue_translation,
+            clear_translation_cache,
+            get_local_translation_status,
+            list_translation_models,
+            download_translation_model,
+            delete_translation_model,
+            get_translation_glossary,
+            save_glossary_entry,
+            delete_glossary_entry,
+            suggest_glossary_terms,
+            get_postprocess_rules,
+            save_postprocess_rule,
+            delete_postprocess_rule,
+            reorder_postprocess_rules,
+            test_postprocess_rule,
+            apply_postprocess,
+            get_filter_rules,
+            save_filter_rule,
+            delete_filter_rule,
+            check_filter_stream,
+            enforce_filters,
+            get_filter_statistics,
+            reset_filter_statistics,
+            queue_repetition_check,
+            analyze_repetition,
+            get_style_metrics,
+            measure_style,
+            get_story_entities,
+            check_entity_names,
+            get_last_request_debug,
+            list_request_debug,
+            record_request_debug,
+            finish_request_debug,
+            clear_request_debug,
+            count_text_tokens,
+            check_text,
+            get_active_lore,
+            reset_lore_timers,
+            set_lore_validity,
+            queue_world_update,
+            extract_suggestions,
+            get_turn_pipeline,
+            set_turn_pipeline,
+            run_turn_pipeline,
+            get_turn_pipeline_run,
+            retry_turn_pipeline,
+            start_generation,
+            get_interrupted_generations,
+            resume_generation,
+            cancel_generation,
+            discard_generation,
+            list_model_profiles,
+            save_model_profile,
+            delete_model_profile,
+            get_story_model_bindings,
+            set_story_model_binding,
+            generate_candidates,
+            choose_candidate,
+            attach_generation,
+            regenerate_with_seed,
+            add_entry_alternative,
+            list_entry_alternatives,
+            set_active_alternative,
+            delete_entry_alternative,
+            prune_entry_alternatives,
+            get_story_alternatives,
+            import_entry_alternatives,
+            schedule_prefetch,
+            claim_prefetch,
+            cancel_prefetch,
+            get_prompt_cache_stats,
+            export_preset,
+            import_preset,
+            build_content_pack,
+            preview_content_pack,
+            install_content_pack,
+            list_content_packs,
+            uninstall_content_pack,
+            import_file,
+            import_from_url,
+            take_opened_files,
+            set_clipboard_watch,
+            get_clipboard_watch_status,
+            set_close_to_tray,
+            start_sync_service,
+            stop_sync_service,
+            get_sync_service_status,
+            get_job_throttle_state,
+            refresh_job_throttle,
+            set_sync_bandwidth_limit,
+            get_sync_bandwidth_limit,
+            sync_list_stories_if_changed,
+            sync_handshake,
+            start_ble_advertising,
+            stop_ble_advertising,
+            get_ble_status,
+            ble_scan,
+            ble_pair,
+            ble_sync_connect,
+            ble_sync_pull_story,
+            ble_sync_push_story,
+            start_direct_sync_server,
+            get_direct_link,
+            send_story_to_device,
+            get_local_network_access,
+            request_local_network_access,
+            export_to_encrypted_share,
+            import_from_share_link,
+            start_web_reader,
+            stop_web_reader,
+            get_web_reader_status,
+            set_s3_backup_credentials,
+            clear_s3_backup_credentials,
+            has_s3_backup_credentials,
+            run_s3_backup,
+            list_s3_backups,
+            get_s3_backup,
+            restore_s3_backup_story,
+            run_incremental_backup,
+            list_incremental_backups,
+            reconstruct_incremental_backup,
+            verify_backup_chain,
+            restore_story_from_backup,
+            delete_story_entries,
+            bulk_edit_lore,
+            prune_branch,
+            list_journal_operations,
+            undo_last_operation,
+            get_migration_preflight,
+            get_data_paths,
+            list_profiles,
+            create_profile,
+            rename_profile,
+            delete_profile,
+            switch_profile,
+            set_data_directory,
+            get_storage_report,
+            preview_image_gc,
+            run_image_gc,
+            list_image_providers,
+            save_image_provider,
+            delete_image_provider,
+            set_active_image_provider,
+            generate_image,
+            queue_image_generation,
+            generate_portrait_variation,
+            get_illustration_settings,
+            set_illustration_settings,
+            illustrate_scene,
+            get_image_metadata,
+            export_image,
+            regenerate_portrait,
+            score_portrait,
+            generate_character_sprites,
+            list_character_sprites,
+            get_character_sprite,
+            list_sound_packs,
+            install_sound_pack,
+            remove_sound_pack,
+            get_ambience,
+            set_ambience,
+            set_ambience_volume,
+            set_ambience_crossfade,
+            update_ambience,
+            queue_chapter_theme,
+            get_chapter_theme,
+            download_chapter_theme,
+            record_activity,
+            end_activity,
+            get_writing_activity,
+            get_writing_streak,
+            set_story_word_goal,
+            get_achievements,
+            set_story_finished,
+            get_activity_heatmap,
+            record_feature_usage,
+            set_analytics_enabled,
+            get_analytics_report,
+            export_analytics_report,
+            clear_analytics,
+            lint_story,
+            apply_lint_fix,
+            get_beat_suggestions,
+            resolve_beat_suggestion,
+            schedule_lore_payload,
+            get_arc_plan,
+            set_arc_plan,
+            delete_arc_plan,
+            set_current_act,
+            get_arc_status,
+            check_arc_drift,
+            get_arc_drift,
+            get_npc_agendas,
+            set_npc_agenda,
+            delete_npc_agenda,
+            simulate_npcs,
+            get_factions,
+            create_faction,
+            update_faction,
+            delete_faction,
+            set_faction_member,
+            remove_faction_member,
+            adjust_reputation,
+            get_reputation_history,
+            delete_reputation_change,
+            queue_faction_update,
+            get_location_routes,
+            set_location_route,
+            delete_location_route,
+            get_travel_time,
+            get_party_location,
+            set_party_location,
+            get_travel_warnings,
+            dismiss_travel_warning,
+            queue_location_update,
+            start_combat,
+            resolve_combat_round,
+            get_combat,
+            end_combat,
+            mark_combat_narrated,
+            create_sandbox_branch,
+            get_sandbox_branches,
+            promote_sandbox_branch,
+            discard_sandbox_branch,
+            get_branch_tree,
+            add_bookmark,
+            update_bookmark,
+            delete_bookmark,
+            get_bookmarks,
+            resolve_bookmark,
+            get_entry_annotations,
+            add_entry_annotation,
+            update_entry_annotation,
+            delete_entry_annotation,
+            get_shared_annotations,
+            import_entry_annotations,
+            bulk_replace_text,
+            split_chapter,
+            merge_chapters,
+            repair_entry_order,
+            get_export_templates,
+            save_export_template,
+            delete_export_template,
+            import_export_template,
+            export_with_template,
+            cancel_import,
+            validate_export_file,
+            export_entries_dataset,
+            set_entry_quality,
+            get_entry_quality,
+            export_finetune_dataset,
+            get_redaction_rules,
+            save_redaction_rule,
+            delete_redaction_rule,
+            preview_redaction,
+            redact_story_export,
+            get_export_attribution,
+            get_pronunciations,
+            save_pronunciation,
+            delete_pronunciation,
+            apply_pronunciations,
+            test_pronunciation,
+            download_model,
+            cancel_model_download,
+            list_installed_models,
+            get_installed_model,
+            remove_model,
+            get_hardware_capabilities,
+            load_local_llm,
+            unload_local_llm,
+            get_local_llm_status,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                file_import::commands::open_paths(window.app_handle(), paths.clone());
+            }
+            // Hide instead of quitting so sync and queued jobs keep running
+            tauri::WindowEvent::CloseRequested { api, .. }
+                if cfg!(desktop) && window.state::<tray::TrayState>().close_to_tray() =>
+            {
+                api.prevent_close();
+                let _ = window.hide();
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(file_import::commands::on_run_event);
 }
@@ -0,0 +1,464 @@
+//! Consistency checks over a story's beats, lorebook, clock and branches.
+//! Each check adds its issues to the report; none of them change anything.
+
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
+use super::types::{LintFix, LintIssue, LintReport, LintRule, Severity};
+use crate::beats::store::load_open_beats;
+use crate::calendar::commands::{advance_time, load_calendar, load_time_tracker};
+use crate::calendar::types::TimeTracker;
+use crate::context::branch::{characters, current_branch, lineage, visible_entries};
+use crate::db::now_millis;
+use crate::lore::activation::Matcher;
+use crate::lore::store::{self as lore_store, load_lore};
+use crate::lore::types::LoreEntry;
+
+/// Chapters an active beat may stay open before it's reported
+pub const STALE_AFTER_CHAPTERS: u32 = 3;
+
+/// Entries a story needs before lore that never fired is worth reporting
+const MIN_ENTRIES_FOR_LORE: usize = 10;
+
+fn issue(
+    rule: LintRule,
+    severity: Severity,
+    message: String,
+    target_id: Option<&str>,
+    fixes: Vec<LintFix>,
+) -> LintIssue {
+    LintIssue {
+        rule,
+        severity,
+        message,
+        target_id: target_id.map(str::to_string),
+        fixes,
+    }
+}
+
+pub async fn lint(
+    pool: &SqlitePool,
+    story_id: &str,
+    stale_after: u32,
+) -> Result<LintReport, String> {
+    let mut issues = Vec::new();
+    let lore = load_lore(pool, story_id).await?;
+    stale_beats(pool, story_id, stale_after.max(1), &mut issues).await?;
+    lore_usage(pool, story_id, &lore, &mut issues).await?;
+    characters_without_lore(pool, story_id, &lore, &mut issues).await?;
+    time(pool, story_id, &lore, &mut issues).await?;
+    branches(pool, story_id, &mut issues).await?;
+    Ok(LintReport {
+        story_id: story_id.to_string(),
+        issues,
+        checked_at: now_millis(),
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct Chapter {
+    id: String,
+    number: i64,
+    created_at: i64,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    branch_id: Option<String>,
+}
+
+/// Chapters on the current branch, in order
+async fn chapters(pool: &SqlitePool, story_id: &str) -> Result<Vec<Chapter>, String> {
+    let branches: HashSet<String> = lineage(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    let rows: Vec<Chapter> = sqlx::query_as(
+        "SELECT id, number, created_at, start_time, end_time, branch_id FROM chapters \
+         WHERE story_id = ? ORDER BY number",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load chapters: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .filter(|c| {
+            c.branch_id
+                .as_ref()
+                .is_none_or(|branch| branches.contains(branch))
+        })
+        .collect())
+}
+
+async fn stale_beats(
+    pool: &SqlitePool,
+    story_id: &str,
+    stale_after: u32,
+    issues: &mut Vec<LintIssue>,
+) -> Result<(), String> {
+    let triggered: HashMap<String, i64> = sqlx::query_as(
+        "SELECT id, triggered_at FROM story_beats WHERE story_id = ? AND triggered_at IS NOT NULL",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load story beats: {}", e))?
+    .into_iter()
+    .collect();
+    let chapters = chapters(pool, story_id).await?;
+    for beat in load_open_beats(pool, story_id).await? {
+        let Some(&since) = triggered.get(&beat.id) else {
+            continue;
+        };
+        let open_for = chapters.iter().filter(|c| c.created_at > since).count() as u32;
+        if open_for < stale_after {
+            continue;
+        }
+        let fixes = ["completed", "failed"]
+            .into_iter()
+            .map(|status| LintFix::ResolveBeat {
+                beat_id: beat.id.clone(),
+                status: status.to_string(),
+            })
+            .collect();
+        issues.push(issue(
+            LintRule::StaleBeat,
+            Severity::Warning,
+            format!(
+                "Beat '{}' has been open for {} chapters",
+                beat.title, open_for
+            ),
+            Some(&beat.id),
+            fixes,
+        ));
+    }
+    Ok(())
+}
+
+async fn lore_usage(
+    pool: &SqlitePool,
+    story_id: &str,
+    lore: &[LoreEntry],
+    issues: &mut Vec<LintIssue>,
+) -> Result<(), String> {
+    let settings = lore_store::load_settings(pool).await?;
+    let entries = visible_entries(pool, story_id).await?;
+    let text = entries
+        .iter()
+        .map(|e| e.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let hits = Matcher::build(lore, &settings).scan(&text);
+
+    for (i, entry) in lore.iter().enumerate() {
+        // Payloads are held back on purpose
+        if entry.injection.mode != "keyword" || entry.injection.payload.is_some() {
+            continue;
+        }
+        let edit = vec![LintFix::EditLoreEntry {
+            entry_id: entry.id.clone(),
+        }];
+        let has_keys = entry
+            .injection
+            .keywords
+            .iter()
+            .any(|k| !k.trim().is_empty())
+            || (settings.match_names && !entry.name.trim().is_empty());
+        if !has_keys {
+            issues.push(issue(
+                LintRule::LoreWithoutKeywords,
+                Severity::Warning,
+                format!("'{}' has no keywords, so it can never activate", entry.name),
+                Some(&entry.id),
+                edit,
+            ));
+        } else if entries.len() >= MIN_ENTRIES_FOR_LORE && !hits.contains_key(&i) {
+            issues.push(issue(
+                LintRule::LoreNeverActivated,
+                Severity::Info,
+                format!(
+                    "None of the keywords of '{}' appear in the story",
+                    entry.name
+                ),
+                Some(&entry.id),
+                edit,
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn characters_without_lore(
+    pool: &SqlitePool,
+    story_id: &str,
+    lore: &[LoreEntry],
+    issues: &mut Vec<LintIssue>,
+) -> Result<(), String> {
+    let names: HashSet<String> = lore
+        .iter()
+        .flat_map(|e| {
+            std::iter::once(&e.name)
+                .chain(&e.aliases)
+                .chain(&e.injection.keywords)
+        })
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    let mut characters: Vec<(String, String)> = characters(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect();
+    characters.sort_by(|a, b| a.1.cmp(&b.1));
+    for (id, name) in characters {
+        let full = name.trim().to_lowercase();
+        let first = full
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if full.is_empty() || names.contains(&full) || names.contains(&first) {
+            continue;
+        }
+        issues.push(issue(
+            LintRule::CharacterWithoutLore,
+            Severity::Info,
+            format!("No lorebook entry names {}", name.trim()),
+            Some(&id),
+            vec![LintFix::CreateCharacterEntry {
+                character_id: id.clone(),
+                name: name.trim().to_string(),
+            }],
+        ));
+    }
+    Ok(())
+}
+
+async fn time(
+    pool: &SqlitePool,
+    story_id: &str,
+    lore: &[LoreEntry],
+    issues: &mut Vec<LintIssue>,
+) -> Result<(), String> {
+    let calendar = match load_calendar(pool, story_id).await {
+        Ok(calendar) => calendar.validate().map(|_| calendar),
+        Err(e) => Err(e),
+    };
+    let calendar = match calendar {
+        Ok(calendar) => calendar,
+        Err(e) => {
+            // Nothing else about time can be checked without a calendar
+            issues.push(issue(
+                LintRule::InvalidCalendar,
+                Severity::Error,
+                e,
+                None,
+                vec![LintFix::EditCalendar],
+            ));
+            return Ok(());
+        }
+    };
+
+    let tracker = load_time_tracker(pool, story_id).await?;
+    if calendar.normalize(&tracker) != tracker {
+        issues.push(issue(
+            LintRule::TimeNotNormalized,
+            Severity::Warning,
+            "The story clock has fields outside the calendar's range".to_string(),
+            None,
+            vec![LintFix::NormalizeTime],
+        ));
+    }
+
+    for entry in lore {
+        let (Some(from), Some(until)) = (entry.injection.valid_from, entry.injection.valid_until)
+        else {
+            continue;
+        };
+        if calendar.minutes_since_start(&until) <= calendar.minutes_since_start(&from) {
+            issues.push(issue(
+                LintRule::LoreValidityReversed,
+                Severity::Warning,
+                format!("'{}' stops being true before it starts", entry.name),
+                Some(&entry.id),
+                vec![LintFix::EditLoreEntry {
+                    entry_id: entry.id.clone(),
+                }],
+            ));
+        }
+    }
+
+    let parse = |raw: &Option<String>| {
+        raw.as_deref()
+            .and_then(|raw| serde_json::from_str::<TimeTracker>(raw).ok())
+            .map(|time| calendar.minutes_since_start(&time))
+    };
+    let mut previous_end: Option<(i64, i64)> = None;
+    for chapter in chapters(pool, story_id).await? {
+        let (id, number) = (&chapter.id, chapter.number);
+        let (start, end) = (parse(&chapter.start_time), parse(&chapter.end_time));
+        if let (Some(start), Some(end)) = (start, end) {
+            if end < start {
+                issues.push(issue(
+                    LintRule::ChapterTimeBackwards,
+                    Severity::Warning,
+                    format!("Chapter {} ends before it starts", number),
+                    Some(id),
+                    Vec::new(),
+                ));
+            }
+        }
+        if let (Some(start), Some((previous, previous_end))) = (start, previous_end) {
+            if start < previous_end {
+                issues.push(issue(
+                    LintRule::ChapterTimeBackwards,
+                    Severity::Warning,
+                    format!("Chapter {} starts before chapter {} ends", number, previous),
+                    Some(id),
+                    Vec::new(),
+                ));
+            }
+        }
+        if let Some(end) = end.or(start) {
+            previous_end = Some((number, end));
+        }
+    }
+    Ok(())
+}
+
+async fn branches(
+    pool: &SqlitePool,
+    story_id: &str,
+    issues: &mut Vec<LintIssue>,
+) -> Result<(), String> {
+    let rows: Vec<(String, String, Option<String>, String)> = sqlx::query_as(
+        "SELECT id, name, parent_branch_id, fork_entry_id FROM branches WHERE story_id = ?",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load branches: {}", e))?;
+    let parents: HashMap<&str, Option<&str>> = rows
+        .iter()
+        .map(|(id, _, parent, _)| (id.as_str(), parent.as_deref()))
+        .collect();
+
+    if let Some(current) = current_branch(pool, story_id).await? {
+        if !parents.contains_key(current.as_str()) {
+            issues.push(issue(
+                LintRule::CurrentBranchMissing,
+                Severity::Error,
+                "The story is set to a branch that no longer exists".to_string(),
+                Some(&current),
+                vec![LintFix::ResetCurrentBranch],
+            ));
+        }
+    }
+
+    for (id, name, parent, fork_entry_id) in &rows {
+        let detach = vec![LintFix::DetachBranch {
+            branch_id: id.clone(),
+        }];
+        if let Some(parent) = parent {
+            if !parents.contains_key(parent.as_str()) {
+                issues.push(issue(
+                    LintRule::BranchParentMissing,
+                    Severity::Error,
+                    format!(
+                        "Branch '{}' forks from a branch that no longer exists",
+                        name
+                    ),
+                    Some(id),
+                    detach,
+                ));
+                continue;
+            }
+        }
+        // Follow the parents; coming back to this branch means a cycle
+        let mut seen = HashSet::new();
+        let mut next = parent.as_deref();
+        while let Some(ancestor) = next {
+            if ancestor == id || !seen.insert(ancestor) {
+                break;
+            }
+            next = parents.get(ancestor).copied().flatten();
+        }
+        if next == Some(id.as_str()) {
+            issues.push(issue(
+                LintRule::BranchCycle,
+                Severity::Error,
+                format!("Branch '{}' is its own ancestor", name),
+                Some(id),
+                detach,
+            ));
+            continue;
+        }
+        let fork_exists: Option<i64> =
+            sqlx::query_scalar("SELECT 1 FROM story_entries WHERE id = ? AND story_id = ?")
+                .bind(fork_entry_id)
+                .bind(story_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| format!("Failed to load entry: {}", e))?;
+        if fork_exists.is_none() {
+            issues.push(issue(
+                LintRule::BranchForkMissing,
+                Severity::Warning,
+                format!("The entry branch '{}' forks from has been deleted", name),
+                Some(id),
+                Vec::new(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Carry out a fix that needs no decisions from the user
+pub async fn apply_fix(pool: &SqlitePool, story_id: &str, fix: &LintFix) -> Result<(), String> {
+    let fail = |e: sqlx::Error| format!("Failed to apply fix: {}", e);
+    match fix {
+        LintFix::ResolveBeat { beat_id, status } => {
+            if !matches!(status.as_str(), "completed" | "failed") {
+                return Err(format!("Invalid beat status: {}", status));
+            }
+            sqlx::query(
+                "UPDATE story_beats SET status = ?, resolved_at = ? WHERE id = ? AND story_id = ?",
+            )
+            .bind(status)
+            .bind(now_millis())
+            .bind(beat_id)
+            .bind(story_id)
+            .execute(pool)
+            .await
+            .map_err(fail)?;
+        }
+        LintFix::NormalizeTime => {
+            let calendar = load_calendar(pool, story_id).await?;
+            calendar.validate()?;
+            advance_time(pool, story_id, &calendar, 0).await?;
+        }
+        LintFix::ResetCurrentBranch => {
+            sqlx::query("UPDATE stories SET current_branch_id = NULL WHERE id = ?")
+                .bind(story_id)
+                .execute(pool)
+                .await
+                .map_err(fail)?;
+        }
+        LintFix::DetachBranch { branch_id } => {
+            sqlx::query(
+                "UPDATE branches SET parent_branch_id = NULL WHERE id = ? AND story_id = ?",
+            )
+            .bind(branch_id)
+            .bind(story_id)
+            .execute(pool)
+            .await
+            .map_err(fail)?;
+        }
+        LintFix::EditLoreEntry { .. }
+        | LintFix::CreateCharacterEntry { .. }
+        | LintFix::EditCalendar => {
+            return Err("This fix is made in the editor".to_string());
+        }
+    }
+    Ok(())
+}
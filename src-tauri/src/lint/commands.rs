@@ -0,0 +1,31 @@
+use tauri::State;
+
+use super::checks::{self, STALE_AFTER_CHAPTERS};
+use super::types::{LintFix, LintReport};
+use crate::db::DbState;
+
+/// Check a story for open beats, unused lore, characters missing from the
+/// lorebook, clock problems and broken branches
+#[tauri::command]
+pub async fn lint_story(
+    db: State<'_, DbState>,
+    story_id: String,
+    stale_after_chapters: Option<u32>,
+) -> Result<LintReport, String> {
+    checks::lint(
+        db.pool(),
+        &story_id,
+        stale_after_chapters.unwrap_or(STALE_AFTER_CHAPTERS),
+    )
+    .await
+}
+
+/// Apply one of a lint issue's fixes
+#[tauri::command]
+pub async fn apply_lint_fix(
+    db: State<'_, DbState>,
+    story_id: String,
+    fix: LintFix,
+) -> Result<(), String> {
+    checks::apply_fix(db.pool(), &story_id, &fix).await
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// An active beat left open for several chapters
+    StaleBeat,
+    /// A keyword entry none of whose keys appear in the story
+    LoreNeverActivated,
+    /// A keyword entry with nothing to match on
+    LoreWithoutKeywords,
+    /// A character no lorebook entry names
+    CharacterWithoutLore,
+    /// An entry that stops being true before it starts
+    LoreValidityReversed,
+    /// The calendar can't be used for time arithmetic
+    InvalidCalendar,
+    /// The story clock has out-of-range or negative fields
+    TimeNotNormalized,
+    /// A chapter that starts before the previous one ends, or ends before
+    /// it starts
+    ChapterTimeBackwards,
+    /// The story points at a branch that doesn't exist
+    CurrentBranchMissing,
+    /// A branch whose parent doesn't exist or belongs to another story
+    BranchParentMissing,
+    /// A branch that is its own ancestor
+    BranchCycle,
+    /// A branch whose fork entry is gone
+    BranchForkMissing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A fix offered for an issue. `apply_lint_fix` carries out the ones that
+/// are plain repairs; the `Edit*` and `Create*` ones open the editor for
+/// the user to decide.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LintFix {
+    /// Mark a beat completed or failed
+    #[serde(rename_all = "camelCase")]
+    ResolveBeat {
+        beat_id: String,
+        status: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    EditLoreEntry {
+        entry_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    CreateCharacterEntry {
+        character_id: String,
+        name: String,
+    },
+    EditCalendar,
+    /// Rewrite the story clock in range, keeping the moment it stands for
+    NormalizeTime,
+    /// Go back to the main branch
+    ResetCurrentBranch,
+    /// Attach a branch to the main branch instead of its missing or cyclic
+    /// parent
+    #[serde(rename_all = "camelCase")]
+    DetachBranch {
+        branch_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub message: String,
+    /// The beat, entry, character, chapter or branch the issue is about
+    pub target_id: Option<String>,
+    pub fixes: Vec<LintFix>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    pub story_id: String,
+    pub issues: Vec<LintIssue>,
+    pub checked_at: i64,
+}
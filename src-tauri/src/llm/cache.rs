@@ -0,0 +1,191 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::client::ChatMessage;
+use super::config::LlmConfig;
+use crate::db::now_millis;
+
+/// Days of cache statistics kept
+const STATS_RETENTION_DAYS: i64 = 30;
+
+/// Prompt-cache accounting reported by the provider for one request
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub prompt_tokens: u64,
+    /// Prompt tokens served from the provider's cache
+    pub cached_tokens: u64,
+    /// Prompt tokens written to the cache (providers with explicit markers)
+    pub cache_write_tokens: u64,
+}
+
+impl CacheUsage {
+    /// Read usage from a response (or final stream event), whichever of the
+    /// OpenAI, Anthropic or llama.cpp shapes it uses
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        let number = |pointer: &str| payload.pointer(pointer).and_then(Value::as_u64);
+        if let Some(prompt_tokens) = number("/usage/prompt_tokens") {
+            let cached_tokens = number("/usage/prompt_tokens_details/cached_tokens")
+                .or_else(|| number("/usage/cache_read_input_tokens"))
+                .unwrap_or(0);
+            return Some(Self {
+                prompt_tokens,
+                cached_tokens,
+                cache_write_tokens: number("/usage/cache_creation_input_tokens").unwrap_or(0),
+            });
+        }
+        // llama.cpp reports reused prompt tokens in its timings
+        let evaluated = number("/timings/prompt_n")?;
+        let cached_tokens = number("/timings/cache_n").unwrap_or(0);
+        Some(Self {
+            prompt_tokens: evaluated + cached_tokens,
+            cached_tokens,
+            cache_write_tokens: 0,
+        })
+    }
+}
+
+/// Whether the provider honours Anthropic-style `cache_control` markers
+fn supports_markers(config: &LlmConfig) -> bool {
+    match config.provider_type.as_str() {
+        "anthropic" => true,
+        "openrouter" => ["anthropic/", "google/gemini"]
+            .iter()
+            .any(|prefix| config.model.starts_with(prefix)),
+        _ => false,
+    }
+}
+
+/// Messages as sent to the provider. Where markers are supported, messages
+/// flagged `cache` (or, when none are, the last system message, which ends
+/// the stable part of a prompt) carry a cache breakpoint.
+pub fn wire_messages(config: &LlmConfig, messages: &[ChatMessage]) -> Value {
+    if !supports_markers(config) {
+        return json!(messages
+            .iter()
+            .map(|m| json!({ "role": m.role, "content": m.content }))
+            .collect::<Vec<_>>());
+    }
+    let breakpoints: Vec<usize> = if messages.iter().any(|m| m.cache) {
+        (0..messages.len()).filter(|&i| messages[i].cache).collect()
+    } else {
+        messages
+            .iter()
+            .rposition(|m| m.role == "system")
+            .into_iter()
+            .collect()
+    };
+    json!(messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            if breakpoints.contains(&i) {
+                json!({
+                    "role": m.role,
+                    "content": [{
+                        "type": "text",
+                        "text": m.content,
+                        "cache_control": { "type": "ephemeral" },
+                    }],
+                })
+            } else {
+                json!({ "role": m.role, "content": m.content })
+            }
+        })
+        .collect::<Vec<_>>())
+}
+
+/// Request fields that turn on prompt reuse or usage reporting
+pub fn request_fields(config: &LlmConfig, stream: bool) -> Option<Value> {
+    match config.provider_type.as_str() {
+        // Keep the evaluated prompt in the slot so the next turn reuses it
+        "llamacpp" => Some(json!({ "cache_prompt": true })),
+        "openai" | "openrouter" | "deepseek" | "groq" | "xai" if stream => {
+            Some(json!({ "stream_options": { "include_usage": true } }))
+        }
+        _ => None,
+    }
+}
+
+/// Store one request's cache usage and drop statistics past retention
+pub async fn record(
+    pool: &SqlitePool,
+    source: &str,
+    story_id: Option<&str>,
+    config: &LlmConfig,
+    usage: &CacheUsage,
+) -> Result<(), String> {
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO prompt_cache_stats (id, story_id, source, provider_type, model, \
+         prompt_tokens, cached_tokens, cache_write_tokens, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(story_id)
+    .bind(source)
+    .bind(&config.provider_type)
+    .bind(&config.model)
+    .bind(usage.prompt_tokens as i64)
+    .bind(usage.cached_tokens as i64)
+    .bind(usage.cache_write_tokens as i64)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record cache usage: {}", e))?;
+    sqlx::query("DELETE FROM prompt_cache_stats WHERE created_at < ?")
+        .bind(now - STATS_RETENTION_DAYS * 24 * 60 * 60 * 1000)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to prune cache usage: {}", e))?;
+    Ok(())
+}
+
+/// Cache hits of one source (e.g. 'narrative') or of everything
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub source: String,
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub cached_tokens: i64,
+    pub cache_write_tokens: i64,
+    /// Share of prompt tokens served from cache, 0 to 1
+    pub hit_rate: f64,
+}
+
+/// Cache statistics per source, optionally for one story, with the total
+/// over all sources (source 'all') first
+pub async fn stats(pool: &SqlitePool, story_id: Option<&str>) -> Result<Vec<CacheStats>, String> {
+    let mut rows: Vec<CacheStats> = sqlx::query_as(
+        "SELECT source, COUNT(*) AS requests, SUM(prompt_tokens) AS prompt_tokens, \
+         SUM(cached_tokens) AS cached_tokens, SUM(cache_write_tokens) AS cache_write_tokens, \
+         0.0 AS hit_rate FROM prompt_cache_stats WHERE ?1 IS NULL OR story_id = ?1 \
+         GROUP BY source ORDER BY source",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load cache statistics: {}", e))?;
+    let mut total = CacheStats {
+        source: "all".to_string(),
+        requests: 0,
+        prompt_tokens: 0,
+        cached_tokens: 0,
+        cache_write_tokens: 0,
+        hit_rate: 0.0,
+    };
+    for row in &rows {
+        total.requests += row.requests;
+        total.prompt_tokens += row.prompt_tokens;
+        total.cached_tokens += row.cached_tokens;
+        total.cache_write_tokens += row.cache_write_tokens;
+    }
+    rows.insert(0, total);
+    for row in &mut rows {
+        row.hit_rate = row.cached_tokens as f64 / row.prompt_tokens.max(1) as f64;
+    }
+    Ok(rows)
+}
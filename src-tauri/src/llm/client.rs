@@ -0,0 +1,224 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+use super::cache::{self, CacheUsage};
+use super::config::LlmConfig;
+use super::debug::{self, RequestDebug};
+use super::limits;
+use crate::local_llm::{LOCAL_LLM, PROVIDER as EMBEDDED};
+
+/// Times a rate-limited (429) request is retried after backing off
+const RATE_LIMIT_RETRIES: u32 = 2;
+
+/// A chat message in OpenAI format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    /// Ends a stable prompt prefix; providers with explicit prompt caching
+    /// get a cache breakpoint here
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+            cache: false,
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+            cache: false,
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.into(),
+            cache: false,
+        }
+    }
+}
+
+/// Request field carrying a sampling seed, for providers that honour one
+pub fn seed_field(provider_type: &str) -> Option<&'static str> {
+    match provider_type {
+        "openai" | "openrouter" | "llamacpp" | "ollama" | "lmstudio" | "groq" | "xai"
+        | "nvidia-nim" | "nanogpt" | "chutes" | "embedded" => Some("seed"),
+        "mistral" => Some("random_seed"),
+        _ => None,
+    }
+}
+
+/// Chat completion request body. `extra` holds additional top-level request
+/// fields (e.g. `response_format`).
+pub fn request_body(
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    extra: Option<&serde_json::Value>,
+    stream: bool,
+) -> serde_json::Value {
+    let mut body = json!({
+        "model": config.model,
+        "messages": cache::wire_messages(config, messages),
+        "stream": stream,
+    });
+    if let Some(serde_json::Value::Object(fields)) = cache::request_fields(config, stream) {
+        for (key, value) in fields {
+            body[key] = value;
+        }
+    }
+    if let Some(temperature) = config.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(max_tokens) = config.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+    for (key, value) in &config.params {
+        body[key] = value.clone();
+    }
+    if let Some(serde_json::Value::Object(fields)) = extra {
+        for (key, value) in fields {
+            body[key] = value.clone();
+        }
+    }
+    body
+}
+
+/// Run a non-streaming chat completion and return the message text with the
+/// provider's prompt-cache usage. `extra` holds additional top-level request
+/// fields (e.g. `response_format`), which the in-process model of embedded
+/// profiles ignores.
+pub async fn complete_with(
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    extra: Option<&serde_json::Value>,
+) -> Result<(String, Option<CacheUsage>), String> {
+    if config.provider_type == EMBEDDED {
+        let outcome = LOCAL_LLM.generate(config, messages, |_| true).await?;
+        return Ok((outcome.text, None));
+    }
+    let body = request_body(config, messages, extra, false);
+    let client = reqwest::Client::new();
+    let mut attempt = 0;
+    let response = loop {
+        let mut request = client
+            .post(format!("{}/chat/completions", config.base_url))
+            .json(&body)
+            .timeout(Duration::from_secs(120));
+        if !config.api_key.is_empty() {
+            request = request.bearer_auth(&config.api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Model request failed: {}", e))?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS || attempt == RATE_LIMIT_RETRIES {
+            break response;
+        }
+        // Pause every request to this endpoint, not just this one
+        let delay = limits::retry_after(response.headers()).unwrap_or(limits::DEFAULT_BACKOFF);
+        limits::back_off(&config.base_url, delay);
+        limits::wait_for_backoff(&config.base_url).await;
+        attempt += 1;
+    };
+    let status = response.status();
+    let payload: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid model response: {}", e))?;
+
+    if !status.is_success() {
+        let message = payload
+            .pointer("/error/message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Model request failed ({}): {}", status, message));
+    }
+
+    let text = payload
+        .pointer("/choices/0/message/content")
+        .and_then(|c| c.as_str())
+        .map(String::from)
+        .ok_or_else(|| "Model response has no content".to_string())?;
+    Ok((text, CacheUsage::from_payload(&payload)))
+}
+
+/// `complete_with` without extra fields, recorded in the request debug log
+pub async fn complete_logged(
+    pool: &SqlitePool,
+    source: &str,
+    story_id: Option<&str>,
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+) -> Result<String, String> {
+    complete_logged_with(pool, source, story_id, config, messages, None).await
+}
+
+/// `complete_with`, recorded in the request debug log and cache statistics
+/// and held to the endpoint's concurrency cap
+pub async fn complete_logged_with(
+    pool: &SqlitePool,
+    source: &str,
+    story_id: Option<&str>,
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    extra: Option<&serde_json::Value>,
+) -> Result<String, String> {
+    let record = RequestDebug::for_messages(source, story_id, &config.model, messages);
+    let id = debug::record(pool, record).await;
+    let permit = limits::acquire(pool, config).await;
+    let result = complete_with(config, messages, extra).await;
+    drop(permit);
+    let result = match result {
+        Ok((reply, usage)) => {
+            if let Some(usage) = usage {
+                if let Err(e) = cache::record(pool, source, story_id, config, &usage).await {
+                    eprintln!("{}", e);
+                }
+            }
+            Ok(reply)
+        }
+        Err(e) => Err(e),
+    };
+    match &id {
+        Ok(id) => {
+            let (response, error) = match &result {
+                Ok(reply) => (Some(reply.as_str()), None),
+                Err(e) => (None, Some(e.as_str())),
+            };
+            if let Err(e) = debug::finish(pool, id, response, error).await {
+                eprintln!("{}", e);
+            }
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+    result
+}
+
+/// Pull the JSON payload out of a model reply, tolerating code fences and surrounding prose
+pub fn extract_json(text: &str) -> Option<&str> {
+    let text = text.trim();
+    if let Some(start) = text.find("```") {
+        let after = &text[start + 3..];
+        let body_start = after.find('\n').map(|i| i + 1).unwrap_or(0);
+        if let Some(end) = after[body_start..].find("```") {
+            return Some(after[body_start..body_start + end].trim());
+        }
+    }
+
+    let start = text.find(['{', '['])?;
+    let end = text.rfind(['}', ']'])?;
+    (end > start).then(|| &text[start..=end])
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use tauri::State;
+use uuid::Uuid;
+
+use super::cache::{self, CacheStats};
+use super::debug::{self, RequestDebug};
+use super::profiles::{profile_from_row, ModelPhase, ModelProfile};
+use super::tokens::count_tokens;
+use crate::db::{now_millis, DbState};
+
+/// Requests returned by `list_request_debug` when no limit is given
+const DEFAULT_LIST_LIMIT: i64 = 20;
+
+/// The exact messages, per-section token counts, fired lore and truncation
+/// decisions of a recorded request; the most recent one when no id is given
+#[tauri::command]
+pub async fn get_last_request_debug(
+    db: State<'_, DbState>,
+    request_id: Option<String>,
+) -> Result<Option<RequestDebug>, String> {
+    debug::load(db.pool(), request_id.as_deref()).await
+}
+
+/// Recent recorded requests, newest first
+#[tauri::command]
+pub async fn list_request_debug(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<RequestDebug>, String> {
+    debug::list(
+        db.pool(),
+        story_id.as_deref(),
+        limit.unwrap_or(DEFAULT_LIST_LIMIT),
+    )
+    .await
+}
+
+/// Record a request assembled by the frontend (e.g. narrative generation).
+/// Sections without a token count are counted here. Returns the request id.
+#[tauri::command]
+pub async fn record_request_debug(
+    db: State<'_, DbState>,
+    mut request: RequestDebug,
+) -> Result<String, String> {
+    for section in request.sections.iter_mut().filter(|s| s.tokens == 0) {
+        if let Some(message) = request.messages.iter().find(|m| m.role == section.name) {
+            section.tokens = count_tokens(&message.content);
+        }
+    }
+    debug::record(db.pool(), request).await
+}
+
+/// Attach the response (or error) to a request recorded earlier
+#[tauri::command]
+pub async fn finish_request_debug(
+    db: State<'_, DbState>,
+    request_id: String,
+    response: Option<String>,
+    error: Option<String>,
+) -> Result<(), String> {
+    debug::finish(
+        db.pool(),
+        &request_id,
+        response.as_deref(),
+        error.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn clear_request_debug(db: State<'_, DbState>) -> Result<(), String> {
+    sqlx::query("DELETE FROM llm_request_log")
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to clear request log: {}", e))?;
+    Ok(())
+}
+
+/// Count tokens with the same encoding used for request debugging
+#[tauri::command]
+pub fn count_text_tokens(text: String) -> usize {
+    count_tokens(&text)
+}
+
+/// Prompt-cache hits reported by providers over the last 30 days, per
+/// source with the overall total first, optionally for one story
+#[tauri::command]
+pub async fn get_prompt_cache_stats(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<CacheStats>, String> {
+    cache::stats(db.pool(), story_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn list_model_profiles(db: State<'_, DbState>) -> Result<Vec<ModelProfile>, String> {
+    let rows = sqlx::query("SELECT * FROM model_profiles ORDER BY name COLLATE NOCASE")
+        .fetch_all(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load model profiles: {}", e))?;
+    Ok(rows.iter().map(profile_from_row).collect())
+}
+
+/// Create or update a model profile, returning its id
+#[tauri::command]
+pub async fn save_model_profile(
+    db: State<'_, DbState>,
+    profile: ModelProfile,
+) -> Result<String, String> {
+    if profile.name.trim().is_empty() {
+        return Err("Model profile name cannot be empty".to_string());
+    }
+    let id = if profile.id.is_empty() {
+        Uuid::new_v4().to_string()
+    } else {
+        profile.id.clone()
+    };
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO model_profiles (id, name, api_profile_id, model, temperature, max_tokens, params, \
+         created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8) \
+         ON CONFLICT(id) DO UPDATE SET name = ?2, api_profile_id = ?3, model = ?4, temperature = ?5, \
+         max_tokens = ?6, params = ?7, updated_at = ?8",
+    )
+    .bind(&id)
+    .bind(profile.name.trim())
+    .bind(&profile.api_profile_id)
+    .bind(profile.model.trim())
+    .bind(profile.temperature)
+    .bind(profile.max_tokens.map(i64::from))
+    .bind(serde_json::Value::Object(profile.params).to_string())
+    .bind(now)
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save model profile: {}", e))?;
+    Ok(id)
+}
+
+/// Delete a model profile; stories bound to it fall back to global settings
+#[tauri::command]
+pub async fn delete_model_profile(
+    db: State<'_, DbState>,
+    profile_id: String,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    sqlx::query("DELETE FROM story_model_bindings WHERE profile_id = ?")
+        .bind(&profile_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to unbind model profile: {}", e))?;
+    sqlx::query("DELETE FROM model_profiles WHERE id = ?")
+        .bind(&profile_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete model profile: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit model profile deletion: {}", e))
+}
+
+/// Profile id bound to each phase of a story; unbound phases are absent
+#[tauri::command]
+pub async fn get_story_model_bindings(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<HashMap<ModelPhase, String>, String> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT phase, profile_id FROM story_model_bindings WHERE story_id = ?")
+            .bind(&story_id)
+            .fetch_all(db.pool())
+            .await
+            .map_err(|e| format!("Failed to load model profile bindings: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(phase, profile)| Some((ModelPhase::parse(&phase)?, profile)))
+        .collect())
+}
+
+/// Bind a story phase to a model profile, or unbind it with `None`
+#[tauri::command]
+pub async fn set_story_model_binding(
+    db: State<'_, DbState>,
+    story_id: String,
+    phase: ModelPhase,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    let query = match &profile_id {
+        Some(_) => sqlx::query(
+            "INSERT INTO story_model_bindings (story_id, phase, profile_id) VALUES (?, ?, ?) \
+             ON CONFLICT(story_id, phase) DO UPDATE SET profile_id = excluded.profile_id",
+        ),
+        None => sqlx::query("DELETE FROM story_model_bindings WHERE story_id = ? AND phase = ?"),
+    };
+    query
+        .bind(&story_id)
+        .bind(phase.as_str())
+        .bind(&profile_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to bind model profile: {}", e))?;
+    Ok(())
+}
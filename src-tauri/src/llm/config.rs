@@ -0,0 +1,198 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use super::profiles::{bound_profile, ModelPhase, ModelProfile};
+
+/// Connection and sampling settings for one model call
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    /// The API profile's provider, e.g. 'openai' or 'llamacpp'
+    pub provider_type: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+    /// Further sampler fields sent as-is (e.g. `top_p`, `min_p`)
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Subset of the frontend's APIProfile (`api_profiles` setting)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiProfile {
+    id: String,
+    provider_type: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    api_key: String,
+}
+
+/// Subset of the frontend's GenerationPreset (`generation_presets` setting)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationPreset {
+    id: String,
+    #[serde(default)]
+    profile_id: Option<String>,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    temperature: Option<f64>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+}
+
+/// OpenAI-compatible endpoint for providers whose profiles leave the base URL empty
+fn default_base_url(provider_type: &str) -> &'static str {
+    match provider_type {
+        "openrouter" => "https://openrouter.ai/api/v1",
+        "nanogpt" => "https://nano-gpt.com/api/v1",
+        "chutes" => "https://llm.chutes.ai/v1",
+        "pollinations" => "https://gen.pollinations.ai/v1",
+        "ollama" => "http://localhost:11434/v1",
+        "lmstudio" => "http://localhost:1234/v1",
+        "llamacpp" => "http://localhost:8080/v1",
+        "nvidia-nim" => "https://integrate.api.nvidia.com/v1",
+        "anthropic" => "https://api.anthropic.com/v1",
+        "google" => "https://generativelanguage.googleapis.com/v1beta/openai",
+        "xai" => "https://api.x.ai/v1",
+        "groq" => "https://api.groq.com/openai/v1",
+        "zhipu" => "https://open.bigmodel.cn/api/paas/v4",
+        "deepseek" => "https://api.deepseek.com/v1",
+        "mistral" => "https://api.mistral.ai/v1",
+        _ => "https://api.openai.com/v1",
+    }
+}
+
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to read setting '{}': {}", key, e))
+}
+
+async fn get_json_setting<T: serde::de::DeserializeOwned + Default>(
+    pool: &SqlitePool,
+    key: &str,
+) -> Result<T, String> {
+    Ok(get_setting(pool, key)
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Resolve the model configuration for a service.
+///
+/// Mirrors the frontend: the service's assigned generation preset (or
+/// `fallback_preset`) picks the model and API profile; a preset without a
+/// profile uses the main narrative profile.
+pub async fn resolve_service(
+    pool: &SqlitePool,
+    service: &str,
+    fallback_preset: &str,
+) -> Result<LlmConfig, String> {
+    let assignments: HashMap<String, String> =
+        get_json_setting(pool, "service_preset_assignments").await?;
+    let presets: Vec<GenerationPreset> = get_json_setting(pool, "generation_presets").await?;
+    let profiles: Vec<ApiProfile> = get_json_setting(pool, "api_profiles").await?;
+
+    let preset_id = assignments
+        .get(service)
+        .map(String::as_str)
+        .unwrap_or(fallback_preset);
+    let preset = presets.iter().find(|p| p.id == preset_id);
+
+    let main_profile_id = get_setting(pool, "main_narrative_profile_id").await?;
+    let profile_id = preset
+        .and_then(|p| p.profile_id.clone())
+        .or(main_profile_id);
+    let profile = profile_id
+        .and_then(|id| profiles.iter().find(|p| p.id == id))
+        .or_else(|| profiles.first())
+        .ok_or("No API profile configured")?;
+
+    let model = match preset.map(|p| p.model.clone()).filter(|m| !m.is_empty()) {
+        Some(model) => model,
+        None => get_setting(pool, "default_model")
+            .await?
+            .ok_or_else(|| format!("No model configured for service '{}'", service))?,
+    };
+
+    Ok(LlmConfig {
+        provider_type: profile.provider_type.clone(),
+        base_url: profile_base_url(profile),
+        api_key: profile.api_key.clone(),
+        model,
+        temperature: preset.and_then(|p| p.temperature),
+        max_tokens: preset.and_then(|p| p.max_tokens),
+        params: serde_json::Map::new(),
+    })
+}
+
+fn profile_base_url(profile: &ApiProfile) -> String {
+    profile
+        .base_url
+        .clone()
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| default_base_url(&profile.provider_type).to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Resolve the model configuration for a service within a story. A model
+/// profile bound to the service's phase for the story overrides the global
+/// preset resolution of `resolve_service`.
+pub async fn resolve_story_service(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+    service: &str,
+    fallback_preset: &str,
+) -> Result<LlmConfig, String> {
+    let bound = match (story_id, ModelPhase::for_service(service)) {
+        (Some(story_id), Some(phase)) => bound_profile(pool, story_id, phase).await?,
+        _ => None,
+    };
+    match bound {
+        Some(profile) => resolve_with_profile(pool, service, fallback_preset, &profile).await,
+        None => resolve_service(pool, service, fallback_preset).await,
+    }
+}
+
+/// `resolve_service` overridden by a model profile's connection and sampler
+pub async fn resolve_with_profile(
+    pool: &SqlitePool,
+    service: &str,
+    fallback_preset: &str,
+    bound: &ModelProfile,
+) -> Result<LlmConfig, String> {
+    let mut config = match resolve_service(pool, service, fallback_preset).await {
+        Ok(config) => config,
+        // The profile may supply everything the global settings lack
+        Err(_) if bound.api_profile_id.is_some() && !bound.model.is_empty() => LlmConfig {
+            provider_type: String::new(),
+            base_url: String::new(),
+            api_key: String::new(),
+            model: String::new(),
+            temperature: None,
+            max_tokens: None,
+            params: serde_json::Map::new(),
+        },
+        Err(e) => return Err(e),
+    };
+    if let Some(profile_id) = &bound.api_profile_id {
+        let profiles: Vec<ApiProfile> = get_json_setting(pool, "api_profiles").await?;
+        let profile = profiles
+            .iter()
+            .find(|p| &p.id == profile_id)
+            .ok_or_else(|| format!("API profile not found for model profile '{}'", bound.name))?;
+        config.provider_type = profile.provider_type.clone();
+        config.base_url = profile_base_url(profile);
+        config.api_key = profile.api_key.clone();
+    }
+    bound.apply(&mut config);
+    Ok(config)
+}
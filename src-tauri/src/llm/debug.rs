@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use super::client::ChatMessage;
+use super::config::get_setting;
+use super::tokens::count_tokens;
+use crate::db::now_millis;
+
+/// Requests kept when `request_debug_history` is unset
+const DEFAULT_HISTORY: i64 = 20;
+
+/// Token count of one part of an assembled prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionTokens {
+    pub name: String,
+    pub tokens: usize,
+}
+
+/// A lore entry included in a prompt and what triggered it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoreActivation {
+    #[serde(default)]
+    pub entry_id: Option<String>,
+    pub name: String,
+    /// e.g. 'keyword: sword', 'always active', 'retrieved by model'
+    pub reason: String,
+}
+
+/// Something dropped or shortened to fit the context budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TruncationDecision {
+    pub item: String,
+    pub reason: String,
+    #[serde(default)]
+    pub tokens: usize,
+}
+
+/// Everything about one model request needed to explain its output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDebug {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub story_id: Option<String>,
+    pub source: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub sections: Vec<SectionTokens>,
+    #[serde(default)]
+    pub lore: Vec<LoreActivation>,
+    #[serde(default)]
+    pub truncation: Vec<TruncationDecision>,
+    #[serde(default)]
+    pub total_tokens: usize,
+    #[serde(default)]
+    pub response: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+impl RequestDebug {
+    /// A record for a plain message list, one section per message
+    pub fn for_messages(
+        source: &str,
+        story_id: Option<&str>,
+        model: &str,
+        messages: &[ChatMessage],
+    ) -> Self {
+        Self {
+            id: String::new(),
+            story_id: story_id.map(str::to_string),
+            source: source.to_string(),
+            model: Some(model.to_string()),
+            messages: messages.to_vec(),
+            sections: message_sections(messages),
+            lore: Vec::new(),
+            truncation: Vec::new(),
+            total_tokens: 0,
+            response: None,
+            error: None,
+            created_at: 0,
+        }
+    }
+}
+
+/// One section per message, named by its role
+fn message_sections(messages: &[ChatMessage]) -> Vec<SectionTokens> {
+    messages
+        .iter()
+        .map(|m| SectionTokens {
+            name: m.role.clone(),
+            tokens: count_tokens(&m.content),
+        })
+        .collect()
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn from_row(r: &SqliteRow) -> RequestDebug {
+    let json = |column: &str| r.get::<String, _>(column);
+    RequestDebug {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        source: r.get("source"),
+        model: r.get("model"),
+        messages: serde_json::from_str(&json("messages")).unwrap_or_default(),
+        sections: serde_json::from_str(&json("sections")).unwrap_or_default(),
+        lore: serde_json::from_str(&json("lore")).unwrap_or_default(),
+        truncation: serde_json::from_str(&json("truncation")).unwrap_or_default(),
+        total_tokens: r.get::<i64, _>("total_tokens") as usize,
+        response: r.get("response"),
+        error: r.get("error"),
+        created_at: r.get("created_at"),
+    }
+}
+
+/// Persist a request and prune the log to the configured history length.
+/// Returns the request id.
+pub async fn record(pool: &SqlitePool, mut request: RequestDebug) -> Result<String, String> {
+    if request.id.is_empty() {
+        request.id = Uuid::new_v4().to_string();
+    }
+    if request.sections.is_empty() {
+        request.sections = message_sections(&request.messages);
+    }
+    if request.total_tokens == 0 {
+        request.total_tokens = request
+            .messages
+            .iter()
+            .map(|m| count_tokens(&m.content))
+            .sum();
+    }
+    sqlx::query(
+        "INSERT OR REPLACE INTO llm_request_log (id, story_id, source, model, messages, sections, lore, \
+         truncation, total_tokens, response, error, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&request.id)
+    .bind(&request.story_id)
+    .bind(&request.source)
+    .bind(&request.model)
+    .bind(to_json(&request.messages))
+    .bind(to_json(&request.sections))
+    .bind(to_json(&request.lore))
+    .bind(to_json(&request.truncation))
+    .bind(request.total_tokens as i64)
+    .bind(&request.response)
+    .bind(&request.error)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record request: {}", e))?;
+
+    let history = get_setting(pool, "request_debug_history")
+        .await?
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_HISTORY);
+    sqlx::query(
+        "DELETE FROM llm_request_log WHERE id NOT IN \
+         (SELECT id FROM llm_request_log ORDER BY created_at DESC LIMIT ?)",
+    )
+    .bind(history.max(1))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to prune request log: {}", e))?;
+
+    Ok(request.id)
+}
+
+/// Attach the outcome to a recorded request
+pub async fn finish(
+    pool: &SqlitePool,
+    id: &str,
+    response: Option<&str>,
+    error: Option<&str>,
+) -> Result<(), String> {
+    sqlx::query("UPDATE llm_request_log SET response = ?, error = ? WHERE id = ?")
+        .bind(response)
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update request log: {}", e))?;
+    Ok(())
+}
+
+/// One request by id, or the most recent one
+pub async fn load(pool: &SqlitePool, id: Option<&str>) -> Result<Option<RequestDebug>, String> {
+    let row = sqlx::query(
+        "SELECT * FROM llm_request_log WHERE ?1 IS NULL OR id = ?1 ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load request: {}", e))?;
+    Ok(row.as_ref().map(from_row))
+}
+
+/// Recent requests, newest first, optionally for one story
+pub async fn list(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<RequestDebug>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM llm_request_log WHERE ?1 IS NULL OR story_id = ?1 \
+         ORDER BY created_at DESC LIMIT ?2",
+    )
+    .bind(story_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load requests: {}", e))?;
+    Ok(rows.iter().map(from_row).collect())
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::config::{get_setting, LlmConfig};
+
+/// Providers that run on the user's machine and serve one request at a time
+const LOCAL_PROVIDERS: [&str; 3] = ["llamacpp", "ollama", "lmstudio"];
+
+/// Back-off applied to a 429 response without a usable Retry-After header
+pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// How many background jobs and provider requests may run at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConcurrencySettings {
+    /// Background jobs run concurrently by the queue
+    pub max_parallel_jobs: usize,
+    /// In-flight auxiliary requests per remote endpoint
+    pub default_provider_limit: usize,
+    /// In-flight auxiliary requests per local endpoint
+    pub local_provider_limit: usize,
+    /// Per-provider overrides, keyed by provider type (e.g. 'openrouter')
+    pub provider_limits: HashMap<String, usize>,
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_parallel_jobs: 4,
+            default_provider_limit: 2,
+            local_provider_limit: 1,
+            provider_limits: HashMap::new(),
+        }
+    }
+}
+
+impl ConcurrencySettings {
+    fn limit_for(&self, provider_type: &str) -> usize {
+        let limit = match self.provider_limits.get(provider_type) {
+            Some(limit) => *limit,
+            None if LOCAL_PROVIDERS.contains(&provider_type) => self.local_provider_limit,
+            None => self.default_provider_limit,
+        };
+        limit.max(1)
+    }
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ConcurrencySettings, String> {
+    Ok(get_setting(pool, "aux_concurrency_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Request slots and rate-limit state of one endpoint
+struct Endpoint {
+    limit: usize,
+    slots: Arc<Semaphore>,
+    blocked_until: Option<Instant>,
+}
+
+/// Endpoints keyed by base URL, so two profiles on one server share a cap
+static ENDPOINTS: LazyLock<Mutex<HashMap<String, Endpoint>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Wait for a request slot on the config's endpoint, and for any rate-limit
+/// back-off on it to pass. The slot is held until the permit is dropped.
+pub async fn acquire(pool: &SqlitePool, config: &LlmConfig) -> OwnedSemaphorePermit {
+    let limit = match load_settings(pool).await {
+        Ok(settings) => settings.limit_for(&config.provider_type),
+        Err(e) => {
+            eprintln!("{}", e);
+            ConcurrencySettings::default().limit_for(&config.provider_type)
+        }
+    };
+    acquire_slot(&config.base_url, limit).await
+}
+
+/// Wait for one of `limit` request slots on `base_url`, and for any
+/// rate-limit back-off on it to pass
+pub async fn acquire_slot(base_url: &str, limit: usize) -> OwnedSemaphorePermit {
+    let slots = {
+        let mut endpoints = ENDPOINTS.lock().unwrap();
+        let endpoint = endpoints
+            .entry(base_url.to_string())
+            .or_insert_with(|| Endpoint {
+                limit,
+                slots: Arc::new(Semaphore::new(limit)),
+                blocked_until: None,
+            });
+        // Requests holding the old semaphore finish under the old limit
+        if endpoint.limit != limit {
+            endpoint.limit = limit;
+            endpoint.slots = Arc::new(Semaphore::new(limit));
+        }
+        endpoint.slots.clone()
+    };
+    let permit = slots
+        .acquire_owned()
+        .await
+        .expect("endpoint semaphore is never closed");
+    wait_for_backoff(base_url).await;
+    permit
+}
+
+/// Wait until the endpoint's rate-limit back-off, if any, has passed
+pub async fn wait_for_backoff(base_url: &str) {
+    let until = ENDPOINTS
+        .lock()
+        .unwrap()
+        .get(base_url)
+        .and_then(|e| e.blocked_until);
+    if let Some(until) = until {
+        tokio::time::sleep_until(until.into()).await;
+    }
+}
+
+/// Hold back every request to the endpoint for `delay` after a 429
+pub fn back_off(base_url: &str, delay: Duration) {
+    let until = Instant::now() + delay;
+    let mut endpoints = ENDPOINTS.lock().unwrap();
+    if let Some(endpoint) = endpoints.get_mut(base_url) {
+        if endpoint.blocked_until.is_none_or(|current| current < until) {
+            endpoint.blocked_until = Some(until);
+        }
+    }
+}
+
+/// Delay requested by a Retry-After header given in seconds
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
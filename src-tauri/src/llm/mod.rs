@@ -0,0 +1,10 @@
+pub mod cache;
+pub mod client;
+pub mod commands;
+pub mod config;
+pub mod debug;
+pub mod limits;
+pub mod profiles;
+pub mod stream;
+pub mod structured;
+pub mod tokens;
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::config::LlmConfig;
+
+/// The parts of a story that can be bound to their own model profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelPhase {
+    Narration,
+    WorldUpdate,
+    Summaries,
+    Translate,
+    Images,
+}
+
+impl ModelPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelPhase::Narration => "narration",
+            ModelPhase::WorldUpdate => "worldUpdate",
+            ModelPhase::Summaries => "summaries",
+            ModelPhase::Translate => "translate",
+            ModelPhase::Images => "images",
+        }
+    }
+
+    pub fn parse(phase: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(phase.to_string())).ok()
+    }
+
+    /// The phase a service (as named in `service_preset_assignments`) runs
+    /// in; auxiliary classifiers have none and always use global settings
+    pub fn for_service(service: &str) -> Option<Self> {
+        match service {
+            "narrative" | "narration" => Some(ModelPhase::Narration),
+            "loreManagement" | "worldUpdate" => Some(ModelPhase::WorldUpdate),
+            s if s.starts_with("translation") => Some(ModelPhase::Translate),
+            s if s.starts_with("image") => Some(ModelPhase::Images),
+            s if s.contains("ummar") || s.starts_with("memory") => Some(ModelPhase::Summaries),
+            _ => None,
+        }
+    }
+}
+
+/// A named provider/model/sampler combination a story phase can be bound to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelProfile {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// API profile (from the `api_profiles` setting) to connect with; `None`
+    /// keeps the connection the service resolves to globally
+    #[serde(default)]
+    pub api_profile_id: Option<String>,
+    /// Empty keeps the globally resolved model
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Further sampler fields sent as-is (e.g. `top_p`, `min_p`)
+    #[serde(default)]
+    pub params: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl ModelProfile {
+    /// Override a resolved config with the profile's model and sampler settings
+    pub fn apply(&self, config: &mut LlmConfig) {
+        if !self.model.is_empty() {
+            config.model = self.model.clone();
+        }
+        config.temperature = self.temperature.or(config.temperature);
+        config.max_tokens = self.max_tokens.or(config.max_tokens);
+        config
+            .params
+            .extend(self.params.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+pub fn profile_from_row(r: &SqliteRow) -> ModelProfile {
+    ModelProfile {
+        id: r.get("id"),
+        name: r.get("name"),
+        api_profile_id: r.get("api_profile_id"),
+        model: r.get("model"),
+        temperature: r.get("temperature"),
+        max_tokens: r.get::<Option<i64>, _>("max_tokens").map(|t| t as u32),
+        params: r
+            .get::<Option<String>, _>("params")
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default(),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+/// The profile bound to a story phase, if any
+pub async fn bound_profile(
+    pool: &SqlitePool,
+    story_id: &str,
+    phase: ModelPhase,
+) -> Result<Option<ModelProfile>, String> {
+    let row = sqlx::query(
+        "SELECT p.* FROM story_model_bindings b JOIN model_profiles p ON p.id = b.profile_id \
+         WHERE b.story_id = ? AND b.phase = ?",
+    )
+    .bind(story_id)
+    .bind(phase.as_str())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load model profile binding: {}", e))?;
+    Ok(row.as_ref().map(profile_from_row))
+}
+
+pub async fn load_profile(
+    pool: &SqlitePool,
+    profile_id: &str,
+) -> Result<Option<ModelProfile>, String> {
+    let row = sqlx::query("SELECT * FROM model_profiles WHERE id = ?")
+        .bind(profile_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load model profile: {}", e))?;
+    Ok(row.as_ref().map(profile_from_row))
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use super::cache::CacheUsage;
+use super::client::{request_body, ChatMessage};
+use super::config::LlmConfig;
+use crate::local_llm::{LOCAL_LLM, PROVIDER as EMBEDDED};
+
+/// A stream that sends nothing for this long is treated as dead
+const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How a streamed completion ended
+#[derive(Debug, Clone)]
+pub struct StreamOutcome {
+    pub text: String,
+    /// The provider's finish reason ('stop', 'length', ...), when it sent one
+    pub finish_reason: Option<String>,
+    /// The caller stopped the stream before the provider finished
+    pub stopped: bool,
+    /// Prompt-cache usage, when the provider reported it
+    pub usage: Option<CacheUsage>,
+}
+
+/// What one SSE data payload carried
+struct StreamEvent {
+    delta: Option<String>,
+    finish: Option<String>,
+    usage: Option<CacheUsage>,
+}
+
+/// Pull the content delta, finish reason and usage out of one SSE data payload
+fn parse_event(data: &str) -> Result<StreamEvent, String> {
+    let event: serde_json::Value =
+        serde_json::from_str(data).map_err(|e| format!("Invalid stream event: {}", e))?;
+    if let Some(message) = event.pointer("/error/message").and_then(|m| m.as_str()) {
+        return Err(format!("Model stream failed: {}", message));
+    }
+    let choice = event.pointer("/choices/0");
+    let delta = choice
+        .and_then(|c| c.pointer("/delta/content"))
+        .and_then(|c| c.as_str())
+        .filter(|c| !c.is_empty())
+        .map(String::from);
+    let finish = choice
+        .and_then(|c| c.get("finish_reason"))
+        .and_then(|f| f.as_str())
+        .map(String::from);
+    Ok(StreamEvent {
+        delta,
+        finish,
+        usage: CacheUsage::from_payload(&event),
+    })
+}
+
+/// Run a streaming chat completion, passing each content delta to
+/// `on_delta`. Returning `false` from `on_delta` stops the stream early.
+/// Embedded profiles are served by the in-process model.
+pub async fn stream_with(
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    extra: Option<&serde_json::Value>,
+    mut on_delta: impl FnMut(&str) -> bool,
+) -> Result<StreamOutcome, String> {
+    if config.provider_type == EMBEDDED {
+        return LOCAL_LLM.generate(config, messages, on_delta).await;
+    }
+    let body = request_body(config, messages, extra, true);
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/chat/completions", config.base_url))
+        .json(&body);
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Model request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let payload: serde_json::Value = response.json().await.unwrap_or_default();
+        let message = payload
+            .pointer("/error/message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Model request failed ({}): {}", status, message));
+    }
+
+    let mut outcome = StreamOutcome {
+        text: String::new(),
+        finish_reason: None,
+        stopped: false,
+        usage: None,
+    };
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(IDLE_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| "Model stream timed out".to_string())?
+            .map_err(|e| format!("Model stream failed: {}", e))?;
+        let Some(chunk) = chunk else {
+            return Ok(outcome);
+        };
+        pending.extend_from_slice(&chunk);
+
+        // Only complete lines are parsed; a multi-byte character may span chunks
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                return Ok(outcome);
+            }
+            let event = parse_event(data)?;
+            if let Some(finish) = event.finish {
+                outcome.finish_reason = Some(finish);
+            }
+            if let Some(usage) = event.usage {
+                outcome.usage = Some(usage);
+            }
+            if let Some(delta) = event.delta {
+                outcome.text.push_str(&delta);
+                if !on_delta(&delta) {
+                    outcome.stopped = true;
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use super::client::{complete_logged_with, extract_json, ChatMessage};
+use super::config::LlmConfig;
+
+/// A JSON schema an auxiliary model reply has to satisfy
+pub struct OutputSchema {
+    /// Short identifier sent to providers that name their schemas
+    pub name: &'static str,
+    pub schema: Value,
+}
+
+/// Request fields that make the provider constrain its output, when it can:
+/// a JSON schema where supported, plain JSON mode where only that is, and
+/// nothing for providers without either
+fn constraint(provider_type: &str, schema: &OutputSchema) -> Option<Value> {
+    match provider_type {
+        "openai" | "openrouter" | "lmstudio" | "ollama" | "xai" | "groq" | "nvidia-nim" => {
+            Some(json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": schema.name, "schema": schema.schema, "strict": false },
+                }
+            }))
+        }
+        // llama.cpp turns the schema into a sampling grammar
+        "llamacpp" => Some(json!({
+            "response_format": { "type": "json_object", "schema": schema.schema }
+        })),
+        "deepseek" | "mistral" | "zhipu" | "google" | "chutes" => {
+            Some(json!({ "response_format": { "type": "json_object" } }))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a reply and validate it against the schema, describing every problem
+fn check(validator: &jsonschema::Validator, reply: &str) -> Result<Value, Vec<String>> {
+    let json = extract_json(reply).ok_or_else(|| vec!["the reply contains no JSON".to_string()])?;
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| vec![format!("the JSON does not parse: {}", e)])?;
+    let problems: Vec<String> = validator
+        .iter_errors(&value)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(value)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Run an auxiliary completion whose reply must match `schema`.
+///
+/// The provider is asked to enforce the schema when it supports that (and
+/// asked again without the constraint if it rejects the request). The reply
+/// is always validated locally; an invalid reply gets one repair round where
+/// the model sees its output and the validation errors.
+pub async fn complete_structured<T: DeserializeOwned>(
+    pool: &SqlitePool,
+    source: &str,
+    story_id: Option<&str>,
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    schema: &OutputSchema,
+) -> Result<T, String> {
+    let validator = jsonschema::validator_for(&schema.schema)
+        .map_err(|e| format!("Invalid {} schema: {}", schema.name, e))?;
+    let constraint = constraint(&config.provider_type, schema);
+
+    let mut reply = complete_logged_with(
+        pool,
+        source,
+        story_id,
+        config,
+        messages,
+        constraint.as_ref(),
+    )
+    .await;
+    if reply.is_err() && constraint.is_some() {
+        reply = complete_logged_with(pool, source, story_id, config, messages, None).await;
+    }
+    let reply = reply?;
+
+    let problems = match check(&validator, &reply) {
+        Ok(value) => return parse(value, schema),
+        Err(problems) => problems,
+    };
+
+    let mut repair = messages.to_vec();
+    repair.push(ChatMessage::assistant(reply));
+    repair.push(ChatMessage::user(format!(
+        "Your reply does not match the required JSON schema:\n- {}\n\nSchema:\n{}\n\n\
+         Reply again with only the corrected JSON.",
+        problems.join("\n- "),
+        schema.schema
+    )));
+    let reply =
+        complete_logged_with(pool, source, story_id, config, &repair, constraint.as_ref()).await?;
+    match check(&validator, &reply) {
+        Ok(value) => parse(value, schema),
+        Err(problems) => Err(format!(
+            "Model reply does not match the {} schema: {}",
+            schema.name,
+            problems.join("; ")
+        )),
+    }
+}
+
+fn parse<T: DeserializeOwned>(value: Value, schema: &OutputSchema) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("Invalid {} reply: {}", schema.name, e))
+}
@@ -0,0 +1,15 @@
+use std::sync::LazyLock;
+
+use tiktoken_rs::CoreBPE;
+
+/// o200k_base, the same encoding the frontend tokenizer uses
+static ENCODING: LazyLock<CoreBPE> =
+    LazyLock::new(|| tiktoken_rs::o200k_base().expect("o200k_base encoding is bundled"));
+
+/// Count tokens in a text string
+pub fn count_tokens(text: &str) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    ENCODING.encode_ordinary(text).len()
+}
@@ -0,0 +1,126 @@
+use tokenizers::Tokenizer;
+
+use crate::llm::client::ChatMessage;
+
+/// How a model expects a conversation to be laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatTemplate {
+    /// Qwen and most fine-tunes
+    ChatMl,
+    Llama3,
+    /// No system role; system text goes into the first user turn
+    Gemma,
+    /// `[INST]` turns, also used for Llama 2; no system role either
+    Mistral,
+}
+
+impl ChatTemplate {
+    /// The template matching the special tokens the tokenizer knows
+    pub fn detect(tokenizer: &Tokenizer) -> Self {
+        let has = |token: &str| tokenizer.token_to_id(token).is_some();
+        if has("<|im_start|>") {
+            Self::ChatMl
+        } else if has("<|start_header_id|>") {
+            Self::Llama3
+        } else if has("<start_of_turn>") {
+            Self::Gemma
+        } else {
+            Self::Mistral
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ChatMl => "chatml",
+            Self::Llama3 => "llama3",
+            Self::Gemma => "gemma",
+            Self::Mistral => "mistral",
+        }
+    }
+
+    /// Tokens that end the model's turn
+    pub fn stop_tokens(self) -> &'static [&'static str] {
+        match self {
+            Self::ChatMl => &["<|im_end|>", "<|endoftext|>"],
+            Self::Llama3 => &["<|eot_id|>", "<|end_of_text|>"],
+            Self::Gemma => &["<end_of_turn>", "<eos>"],
+            Self::Mistral => &["</s>"],
+        }
+    }
+
+    /// The prompt for `messages`, ending where the model's reply starts. A
+    /// trailing assistant message is left open, so the reply continues it.
+    pub fn render(self, messages: &[ChatMessage]) -> String {
+        let (turns, prefill) = match messages.split_last() {
+            Some((last, rest)) if last.role == "assistant" => (rest, last.content.as_str()),
+            _ => (messages, ""),
+        };
+        let mut prompt = String::new();
+        match self {
+            Self::ChatMl => {
+                for m in turns {
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        m.role, m.content
+                    ));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+            }
+            Self::Llama3 => {
+                prompt.push_str("<|begin_of_text|>");
+                for m in turns {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        m.role, m.content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+            }
+            Self::Gemma => {
+                prompt.push_str("<bos>");
+                for (role, content) in fold_system(turns) {
+                    let role = if role == "assistant" { "model" } else { "user" };
+                    prompt.push_str(&format!(
+                        "<start_of_turn>{}\n{}<end_of_turn>\n",
+                        role, content
+                    ));
+                }
+                prompt.push_str("<start_of_turn>model\n");
+            }
+            Self::Mistral => {
+                prompt.push_str("<s>");
+                for (role, content) in fold_system(turns) {
+                    if role == "assistant" {
+                        prompt.push_str(&format!("{}</s>", content));
+                    } else {
+                        prompt.push_str(&format!("[INST] {} [/INST]", content));
+                    }
+                }
+            }
+        }
+        prompt.push_str(prefill);
+        prompt
+    }
+}
+
+/// Turns for templates without a system role: system text is put at the
+/// start of the next user turn
+fn fold_system(turns: &[ChatMessage]) -> Vec<(&str, String)> {
+    let mut folded = Vec::new();
+    let mut system: Vec<&str> = Vec::new();
+    for m in turns {
+        match m.role.as_str() {
+            "system" => system.push(&m.content),
+            "assistant" => folded.push(("assistant", m.content.clone())),
+            _ => {
+                system.push(&m.content);
+                folded.push(("user", system.join("\n\n")));
+                system.clear();
+            }
+        }
+    }
+    if !system.is_empty() {
+        folded.push(("user", system.join("\n\n")));
+    }
+    folded
+}
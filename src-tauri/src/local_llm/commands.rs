@@ -0,0 +1,31 @@
+use tauri::State;
+
+use super::types::{LoadedLlm, LocalLlmStatus};
+use super::{LOCAL_LLM, MODEL_KIND};
+use crate::models::ModelManager;
+
+/// Load an installed local model (kind `llm`), replacing the one loaded.
+/// A model that wouldn't fit in free memory is refused unless `force` is set.
+/// Requests load their model themselves; this is for loading ahead of time.
+#[tauri::command]
+pub async fn load_local_llm(
+    models: State<'_, ModelManager>,
+    model_id: String,
+    force: Option<bool>,
+) -> Result<LoadedLlm, String> {
+    let installed = models
+        .installed(MODEL_KIND, &model_id)
+        .ok_or_else(|| format!("Local model not installed: {}", model_id))?;
+    LOCAL_LLM.load(installed, !force.unwrap_or(false)).await
+}
+
+/// Free the loaded model's memory. Returns false when none was loaded.
+#[tauri::command]
+pub fn unload_local_llm() -> bool {
+    LOCAL_LLM.unload().is_some()
+}
+
+#[tauri::command]
+pub fn get_local_llm_status() -> LocalLlmStatus {
+    LOCAL_LLM.status()
+}
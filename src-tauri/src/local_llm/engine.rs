@@ -0,0 +1,505 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::{LogitsProcessor, Sampling};
+use candle_transformers::models::{
+    quantized_gemma3, quantized_llama, quantized_qwen2, quantized_qwen3,
+};
+use candle_transformers::utils::apply_repeat_penalty;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokenizers::Tokenizer;
+use tokio::sync::mpsc;
+
+use super::chat::ChatTemplate;
+use super::types::{LoadedLlm, LocalLlmSettings, LocalLlmStatus, LocalLlmUnloaded};
+use crate::db::{now_millis, DbState};
+use crate::hardware::detect::{memory, ram_budget};
+use crate::llm::client::ChatMessage;
+use crate::llm::config::{get_setting, LlmConfig};
+use crate::llm::stream::StreamOutcome;
+use crate::models::manager::installed_in;
+use crate::models::types::InstalledModel;
+
+/// Model manager kind local language models are installed under
+pub const MODEL_KIND: &str = "llm";
+
+/// API profile provider type served by the local model
+pub const PROVIDER: &str = "embedded";
+
+const TOKENIZER_FILE: &str = "tokenizer.json";
+
+const MIB: u64 = 1024 * 1024;
+
+/// Longer contexts need more KV cache than small devices have to spare
+const MAX_CONTEXT: usize = 8192;
+
+const DEFAULT_MAX_TOKENS: usize = 512;
+const DEFAULT_TEMPERATURE: f64 = 0.8;
+
+/// Small models loop without a repeat penalty; llama.cpp's classic default
+const DEFAULT_REPEAT_PENALTY: f64 = 1.1;
+const REPEAT_WINDOW: usize = 64;
+
+/// Free memory is checked every this many generated tokens
+const MEMORY_CHECK_TOKENS: usize = 32;
+
+/// How often the watcher looks for an idle model or low memory
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The engine; one model is loaded at a time
+pub static LOCAL_LLM: LazyLock<LocalLlm> = LazyLock::new(LocalLlm::default);
+
+enum Weights {
+    /// Also Mistral, whose GGUF files use the llama architecture
+    Llama(quantized_llama::ModelWeights),
+    Qwen2(quantized_qwen2::ModelWeights),
+    Qwen3(quantized_qwen3::ModelWeights),
+    Gemma3(quantized_gemma3::ModelWeights),
+}
+
+impl Weights {
+    /// Logits for the token after `input`, which starts at position `pos`
+    fn forward(&mut self, input: &Tensor, pos: usize) -> candle_core::Result<Tensor> {
+        let logits = match self {
+            Self::Llama(m) => m.forward(input, pos)?,
+            Self::Qwen2(m) => m.forward(input, pos)?,
+            Self::Qwen3(m) => m.forward(input, pos)?,
+            Self::Gemma3(m) => m.forward(input, pos)?,
+        };
+        logits.squeeze(0)
+    }
+
+    /// Forget the previous prompt. The others start over when fed from
+    /// position 0.
+    fn reset(&mut self) {
+        if let Self::Qwen3(m) = self {
+            m.clear_kv_cache();
+        }
+    }
+}
+
+struct Model {
+    info: LoadedLlm,
+    weights: Mutex<Weights>,
+    tokenizer: Tokenizer,
+    template: ChatTemplate,
+    stop_tokens: Vec<u32>,
+}
+
+/// Sampling settings of one request
+struct SamplingParams {
+    seed: u64,
+    temperature: f64,
+    top_p: Option<f64>,
+    top_k: Option<usize>,
+    repeat_penalty: f32,
+    max_tokens: usize,
+}
+
+impl SamplingParams {
+    fn from_config(config: &LlmConfig) -> Self {
+        let param = |key: &str| config.params.get(key).and_then(Value::as_f64);
+        Self {
+            seed: config
+                .params
+                .get("seed")
+                .and_then(Value::as_u64)
+                .unwrap_or_else(rand::random),
+            temperature: config.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            top_p: param("top_p").filter(|p| *p > 0.0 && *p < 1.0),
+            top_k: param("top_k").map(|k| k as usize).filter(|k| *k > 0),
+            repeat_penalty: param("repeat_penalty")
+                .or(param("repetition_penalty"))
+                .unwrap_or(DEFAULT_REPEAT_PENALTY) as f32,
+            max_tokens: config.max_tokens.map_or(DEFAULT_MAX_TOKENS, |m| m as usize),
+        }
+    }
+
+    fn processor(&self) -> LogitsProcessor {
+        let temperature = self.temperature;
+        let sampling = match (self.top_k, self.top_p) {
+            _ if temperature < 1e-7 => Sampling::ArgMax,
+            (None, None) => Sampling::All { temperature },
+            (Some(k), None) => Sampling::TopK { k, temperature },
+            (None, Some(p)) => Sampling::TopP { p, temperature },
+            (Some(k), Some(p)) => Sampling::TopKThenTopP { k, p, temperature },
+        };
+        LogitsProcessor::from_sampling(self.seed, sampling)
+    }
+}
+
+fn model_error(e: impl std::fmt::Display) -> String {
+    format!("Local model failed: {}", e)
+}
+
+fn low_memory(floor: u64) -> bool {
+    floor > 0 && memory().available_bytes < floor
+}
+
+impl Model {
+    fn open(installed: &InstalledModel) -> Result<Self, String> {
+        let dir = Path::new(&installed.path);
+        let gguf = installed
+            .files
+            .iter()
+            .find(|f| f.name.to_lowercase().ends_with(".gguf"))
+            .ok_or("The model has no .gguf file")?;
+        let tokenizer = Tokenizer::from_file(dir.join(TOKENIZER_FILE))
+            .map_err(|e| format!("Failed to load the model's tokenizer: {}", e))?;
+        let mut file = std::fs::File::open(dir.join(&gguf.name))
+            .map_err(|e| format!("Failed to open model: {}", e))?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| format!("Invalid GGUF model: {}", e))?;
+
+        let metadata = |key: &str| content.metadata.get(key);
+        let architecture = metadata("general.architecture")
+            .and_then(|v| v.to_string().ok())
+            .cloned()
+            .unwrap_or_default();
+        let trained_context = metadata(&format!("{}.context_length", architecture))
+            .and_then(|v| v.to_u32().ok())
+            .map_or(MAX_CONTEXT, |c| c as usize);
+        let eos = metadata("tokenizer.ggml.eos_token_id").and_then(|v| v.to_u32().ok());
+
+        let device = Device::Cpu;
+        let failed = |e: candle_core::Error| format!("Failed to load model: {}", e);
+        let (weights, context_length) = match architecture.as_str() {
+            "llama" => (
+                Weights::Llama(
+                    quantized_llama::ModelWeights::from_gguf(content, &mut file, &device)
+                        .map_err(failed)?,
+                ),
+                trained_context.min(quantized_llama::MAX_SEQ_LEN),
+            ),
+            "qwen2" => (
+                Weights::Qwen2(
+                    quantized_qwen2::ModelWeights::from_gguf(content, &mut file, &device)
+                        .map_err(failed)?,
+                ),
+                trained_context,
+            ),
+            "qwen3" => (
+                Weights::Qwen3(
+                    quantized_qwen3::ModelWeights::from_gguf(content, &mut file, &device)
+                        .map_err(failed)?,
+                ),
+                trained_context,
+            ),
+            "gemma3" => (
+                Weights::Gemma3(
+                    quantized_gemma3::ModelWeights::from_gguf(content, &mut file, &device)
+                        .map_err(failed)?,
+                ),
+                trained_context,
+            ),
+            // Earlier Gemma generations have a different layout from Gemma 3
+            "gemma" | "gemma2" => {
+                return Err(format!(
+                    "Unsupported model architecture: {} (only Gemma 3 models are supported)",
+                    architecture
+                ))
+            }
+            "" => return Err("The GGUF file doesn't name its architecture".to_string()),
+            other => return Err(format!("Unsupported model architecture: {}", other)),
+        };
+
+        let template = ChatTemplate::detect(&tokenizer);
+        let mut stop_tokens: Vec<u32> = template
+            .stop_tokens()
+            .iter()
+            .filter_map(|t| tokenizer.token_to_id(t))
+            .collect();
+        stop_tokens.extend(eos);
+        Ok(Self {
+            info: LoadedLlm {
+                model_id: installed.id.clone(),
+                name: installed.name.clone(),
+                architecture,
+                context_length: context_length.min(MAX_CONTEXT),
+                template: template.name().to_string(),
+                size_bytes: installed.size,
+                loaded_at: now_millis(),
+            },
+            weights: Mutex::new(weights),
+            tokenizer,
+            template,
+            stop_tokens,
+        })
+    }
+
+    /// Generate a reply to `prompt`, passing text to `on_text` as it
+    /// decodes. Returns the finish reason, or `None` when `cancelled` was
+    /// set. Stops with an error when free memory drops below `memory_floor`.
+    fn run(
+        &self,
+        prompt: &str,
+        params: &SamplingParams,
+        cancelled: &AtomicBool,
+        memory_floor: u64,
+        mut on_text: impl FnMut(String),
+    ) -> Result<Option<&'static str>, String> {
+        let prompt: Vec<u32> = self
+            .tokenizer
+            .encode(prompt, false)
+            .map_err(model_error)?
+            .get_ids()
+            .to_vec();
+        let room = self.info.context_length.saturating_sub(prompt.len());
+        if room == 0 {
+            return Err(format!(
+                "The prompt ({} tokens) doesn't fit the local model's context of {} tokens",
+                prompt.len(),
+                self.info.context_length
+            ));
+        }
+
+        let mut weights = self.weights.lock().unwrap();
+        weights.reset();
+        let mut processor = params.processor();
+        let mut decoder = self.tokenizer.decode_stream(true);
+        let mut history = prompt.clone();
+        let mut input = prompt;
+        let mut pos = 0;
+        for index in 0..params.max_tokens.min(room) {
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            if index % MEMORY_CHECK_TOKENS == 0 && low_memory(memory_floor) {
+                return Err("Stopped: the system is running low on memory".to_string());
+            }
+            let tensor = Tensor::new(input.as_slice(), &Device::Cpu)
+                .and_then(|t| t.unsqueeze(0))
+                .map_err(model_error)?;
+            let mut logits = weights.forward(&tensor, pos).map_err(model_error)?;
+            pos += input.len();
+            if params.repeat_penalty != 1.0 {
+                let recent = &history[history.len().saturating_sub(REPEAT_WINDOW)..];
+                logits = apply_repeat_penalty(&logits, params.repeat_penalty, recent)
+                    .map_err(model_error)?;
+            }
+            let next = processor.sample(&logits).map_err(model_error)?;
+            if self.stop_tokens.contains(&next) {
+                return Ok(Some("stop"));
+            }
+            history.push(next);
+            input = vec![next];
+            if let Some(text) = decoder.step(next).map_err(model_error)? {
+                on_text(text);
+            }
+        }
+        Ok(Some("length"))
+    }
+}
+
+/// Sets its flag when dropped, so an aborted request stops the model
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Counts a request as generating until it ends
+struct Busy<'a>(&'a LocalLlm);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.generating.fetch_sub(1, Ordering::Relaxed);
+        self.0.touch();
+    }
+}
+
+#[derive(Default)]
+pub struct LocalLlm {
+    /// Model manager root, set once at startup
+    models_dir: OnceLock<PathBuf>,
+    model: Mutex<Option<Arc<Model>>>,
+    /// Held while a model loads, so two requests don't load it twice
+    loading: tokio::sync::Mutex<()>,
+    generating: AtomicUsize,
+    last_used: AtomicI64,
+    /// Free memory, in bytes, generation stops below; from the settings
+    memory_floor: AtomicU64,
+}
+
+impl LocalLlm {
+    fn current(&self) -> Option<Arc<Model>> {
+        self.model.lock().unwrap().clone()
+    }
+
+    fn touch(&self) {
+        self.last_used.store(now_millis(), Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> LocalLlmStatus {
+        let last_used = self.last_used.load(Ordering::Relaxed);
+        LocalLlmStatus {
+            loaded: self.current().map(|m| m.info.clone()),
+            generating: self.generating.load(Ordering::Relaxed) > 0,
+            last_used_at: (last_used > 0).then_some(last_used),
+        }
+    }
+
+    /// Drop the loaded model. Its memory is freed once running requests
+    /// finish with it.
+    pub fn unload(&self) -> Option<LoadedLlm> {
+        self.model.lock().unwrap().take().map(|m| m.info.clone())
+    }
+
+    /// Load an installed model in place of the current one. With
+    /// `check_memory`, a model that wouldn't fit in free memory is refused.
+    pub async fn load(
+        &self,
+        installed: InstalledModel,
+        check_memory: bool,
+    ) -> Result<LoadedLlm, String> {
+        let _loading = self.loading.lock().await;
+        if let Some(model) = self.current().filter(|m| m.info.model_id == installed.id) {
+            return Ok(model.info.clone());
+        }
+        // Free the old weights before reading the new ones
+        self.unload();
+        if check_memory {
+            // Weights plus roughly what the KV cache and activations take
+            let needed = installed.size + installed.size / 8;
+            let budget = ram_budget(&memory());
+            if needed > budget {
+                return Err(format!(
+                    "{} needs about {} MB of memory but only {} MB can be spared",
+                    installed.name,
+                    needed / MIB,
+                    budget / MIB
+                ));
+            }
+        }
+        let model = tauri::async_runtime::spawn_blocking(move || Model::open(&installed))
+            .await
+            .map_err(|e| format!("Failed to load model: {}", e))??;
+        let info = model.info.clone();
+        *self.model.lock().unwrap() = Some(Arc::new(model));
+        self.touch();
+        Ok(info)
+    }
+
+    /// The model a request names, loading it when another (or none) is
+    async fn model_for(&self, model_id: &str) -> Result<Arc<Model>, String> {
+        if let Some(model) = self.current().filter(|m| m.info.model_id == model_id) {
+            return Ok(model);
+        }
+        let installed = self
+            .models_dir
+            .get()
+            .and_then(|dir| installed_in(dir, MODEL_KIND, model_id))
+            .ok_or_else(|| format!("Local model not installed: {}", model_id))?;
+        self.load(installed, true).await?;
+        self.current()
+            .ok_or_else(|| "The local model was unloaded".to_string())
+    }
+
+    /// Run a chat completion on the model named by `config.model`, passing
+    /// each piece of text to `on_delta` like `stream_with` does. Returning
+    /// `false` from `on_delta` stops generation.
+    pub async fn generate(
+        &self,
+        config: &LlmConfig,
+        messages: &[ChatMessage],
+        mut on_delta: impl FnMut(&str) -> bool,
+    ) -> Result<StreamOutcome, String> {
+        let model = self.model_for(&config.model).await?;
+        let prompt = model.template.render(messages);
+        let params = SamplingParams::from_config(config);
+        let memory_floor = self.memory_floor.load(Ordering::Relaxed);
+
+        self.generating.fetch_add(1, Ordering::Relaxed);
+        let _busy = Busy(self);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _cancel = CancelOnDrop(cancelled.clone());
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let task = {
+            let cancelled = cancelled.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                model.run(&prompt, &params, &cancelled, memory_floor, |text| {
+                    let _ = sender.send(text);
+                })
+            })
+        };
+
+        let mut outcome = StreamOutcome {
+            text: String::new(),
+            finish_reason: None,
+            stopped: false,
+            usage: None,
+        };
+        while let Some(delta) = receiver.recv().await {
+            outcome.text.push_str(&delta);
+            if !on_delta(&delta) {
+                outcome.stopped = true;
+                cancelled.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+        drop(receiver);
+        outcome.finish_reason = task.await.map_err(model_error)??.map(String::from);
+        Ok(outcome)
+    }
+
+    /// Why the model should be unloaded now, if it should
+    fn unload_reason(&self, settings: &LocalLlmSettings) -> Option<&'static str> {
+        if self.current().is_none() || self.generating.load(Ordering::Relaxed) > 0 {
+            return None;
+        }
+        if low_memory(settings.min_free_memory_mb * MIB) {
+            return Some("memory");
+        }
+        let idle = now_millis() - self.last_used.load(Ordering::Relaxed);
+        (settings.idle_unload_minutes > 0
+            && idle > i64::from(settings.idle_unload_minutes) * 60_000)
+            .then_some("idle")
+    }
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<LocalLlmSettings, String> {
+    Ok(get_setting(pool, "local_llm_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Point the engine at the model manager's folder and watch the loaded
+/// model, unloading it when it idles too long or the system runs low on
+/// memory. Emits `local-llm-unloaded` when it does.
+pub fn watch(app: AppHandle, models_dir: PathBuf) {
+    let _ = LOCAL_LLM.models_dir.set(models_dir);
+    let floor = LocalLlmSettings::default().min_free_memory_mb * MIB;
+    LOCAL_LLM.memory_floor.store(floor, Ordering::Relaxed);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            let pool = app.state::<DbState>().pool().clone();
+            // The settings table only exists once the frontend has run migrations
+            let settings = load_settings(&pool).await.unwrap_or_default();
+            LOCAL_LLM
+                .memory_floor
+                .store(settings.min_free_memory_mb * MIB, Ordering::Relaxed);
+            let Some(reason) = LOCAL_LLM.unload_reason(&settings) else {
+                continue;
+            };
+            if let Some(model) = LOCAL_LLM.unload() {
+                let event = LocalLlmUnloaded {
+                    model_id: model.model_id,
+                    reason: reason.to_string(),
+                };
+                if let Err(e) = app.emit("local-llm-unloaded", &event) {
+                    eprintln!("Failed to emit local model unload: {}", e);
+                }
+            }
+        }
+    });
+}
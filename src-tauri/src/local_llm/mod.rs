@@ -0,0 +1,17 @@
+//! A small model run in-process, for narration or utility calls without
+//! Ollama or another server. Models are GGUF files (Llama, Mistral, Qwen 2/3
+//! or Gemma 3) installed through the model manager as kind `llm`, with the
+//! `tokenizer.json` that goes with them. API profiles with provider type
+//! `embedded` are routed here by the LLM client, so streamed narration goes
+//! out on the usual `generation-chunk` events.
+//!
+//! Inference runs on the CPU. A loaded model is unloaded again when it has
+//! been idle a while or the system runs low on memory, which matters most on
+//! phones.
+
+pub mod chat;
+pub mod commands;
+pub mod engine;
+pub mod types;
+
+pub use engine::{watch, LOCAL_LLM, MODEL_KIND, PROVIDER};
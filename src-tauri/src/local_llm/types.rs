@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// Stored under the `local_llm_settings` key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalLlmSettings {
+    /// Unload the model after this many minutes without a request; 0 keeps
+    /// it loaded
+    pub idle_unload_minutes: u32,
+    /// Unload (or stop generating) when free system memory drops below this
+    pub min_free_memory_mb: u64,
+}
+
+impl Default for LocalLlmSettings {
+    fn default() -> Self {
+        if cfg!(mobile) {
+            Self {
+                idle_unload_minutes: 3,
+                min_free_memory_mb: 400,
+            }
+        } else {
+            Self {
+                idle_unload_minutes: 15,
+                min_free_memory_mb: 256,
+            }
+        }
+    }
+}
+
+/// The model currently in memory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedLlm {
+    pub model_id: String,
+    pub name: String,
+    /// GGUF architecture, e.g. `llama` or `qwen2`
+    pub architecture: String,
+    /// Prompt plus reply, in tokens
+    pub context_length: usize,
+    /// Chat format prompts are rendered in, e.g. `chatml`
+    pub template: String,
+    pub size_bytes: u64,
+    pub loaded_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalLlmStatus {
+    pub loaded: Option<LoadedLlm>,
+    pub generating: bool,
+    pub last_used_at: Option<i64>,
+}
+
+/// Emitted on `local-llm-unloaded` when the model is unloaded without being
+/// asked to
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalLlmUnloaded {
+    pub model_id: String,
+    /// `idle` or `memory`
+    pub reason: String,
+}
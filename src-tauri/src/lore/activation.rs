@@ -0,0 +1,281 @@
+use std::collections::{HashMap, HashSet};
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use rand::Rng;
+use regex::{Regex, RegexBuilder};
+
+use super::types::{ActivationSettings, ActivationTimer, FiredEntry, LoreEntry};
+use crate::llm::tokens::count_tokens;
+
+/// Parse a `/pattern/flags` key; flags are any of `imsx`
+fn parse_regex_key(key: &str, case_sensitive: bool) -> Option<Regex> {
+    let rest = key.strip_prefix('/')?;
+    let end = rest.rfind('/')?;
+    let (pattern, flags) = (&rest[..end], &rest[end + 1..]);
+    if pattern.is_empty() || !flags.chars().all(|c| "imsx".contains(c)) {
+        return None;
+    }
+    RegexBuilder::new(pattern)
+        .case_insensitive(flags.contains('i') || !case_sensitive)
+        .multi_line(flags.contains('m'))
+        .dot_matches_new_line(flags.contains('s'))
+        .ignore_whitespace(flags.contains('x'))
+        .size_limit(1 << 20)
+        .build()
+        .ok()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `text[start..end]` is not glued to surrounding word characters
+fn on_word_boundary(text: &str, start: usize, end: usize) -> bool {
+    !text[..start].chars().next_back().is_some_and(is_word_char)
+        && !text[end..].chars().next().is_some_and(is_word_char)
+}
+
+/// Compiled keys of every keyword-mode entry. Plain keys share one
+/// Aho-Corasick automaton so a scan costs one pass over the text however
+/// many entries there are.
+pub struct Matcher {
+    automaton: Option<AhoCorasick>,
+    /// Entries (index, original key) behind each automaton pattern
+    pattern_entries: Vec<Vec<(usize, String)>>,
+    regexes: Vec<(usize, String, Regex)>,
+    case_sensitive: bool,
+    whole_words: bool,
+}
+
+impl Matcher {
+    pub fn build(entries: &[LoreEntry], settings: &ActivationSettings) -> Self {
+        let mut patterns: Vec<String> = Vec::new();
+        let mut pattern_index: HashMap<String, usize> = HashMap::new();
+        let mut pattern_entries: Vec<Vec<(usize, String)>> = Vec::new();
+        let mut regexes = Vec::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.injection.mode != "keyword" {
+                continue;
+            }
+            let names = settings
+                .match_names
+                .then(|| std::iter::once(&entry.name).chain(&entry.aliases))
+                .into_iter()
+                .flatten();
+            for key in entry.injection.keywords.iter().chain(names) {
+                let key = key.trim();
+                if key.is_empty() {
+                    continue;
+                }
+                if let Some(regex) = parse_regex_key(key, settings.case_sensitive) {
+                    regexes.push((i, key.to_string(), regex));
+                    continue;
+                }
+                let pattern = if settings.case_sensitive {
+                    key.to_string()
+                } else {
+                    key.to_lowercase()
+                };
+                let slot = *pattern_index.entry(pattern.clone()).or_insert_with(|| {
+                    patterns.push(pattern);
+                    pattern_entries.push(Vec::new());
+                    pattern_entries.len() - 1
+                });
+                if !pattern_entries[slot].iter().any(|(e, _)| *e == i) {
+                    pattern_entries[slot].push((i, key.to_string()));
+                }
+            }
+        }
+
+        let automaton = (!patterns.is_empty())
+            .then(|| {
+                AhoCorasickBuilder::new()
+                    .match_kind(MatchKind::Standard)
+                    .build(&patterns)
+                    .ok()
+            })
+            .flatten();
+        Self {
+            automaton,
+            pattern_entries,
+            regexes,
+            case_sensitive: settings.case_sensitive,
+            whole_words: settings.whole_words,
+        }
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        if self.case_sensitive {
+            text.to_string()
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    /// Entries with a key in `text`, each with the first key that matched
+    pub fn scan(&self, text: &str) -> HashMap<usize, String> {
+        let mut hits: HashMap<usize, String> = HashMap::new();
+        if let Some(automaton) = &self.automaton {
+            let haystack = self.normalize(text);
+            for m in automaton.find_overlapping_iter(&haystack) {
+                if self.whole_words && !on_word_boundary(&haystack, m.start(), m.end()) {
+                    continue;
+                }
+                for (entry, key) in &self.pattern_entries[m.pattern().as_usize()] {
+                    hits.entry(*entry).or_insert_with(|| key.clone());
+                }
+            }
+        }
+        for (entry, key, regex) in &self.regexes {
+            if !hits.contains_key(entry) && regex.is_match(text) {
+                hits.insert(*entry, key.clone());
+            }
+        }
+        hits
+    }
+
+    /// A single key (used for secondary keys) against `text`
+    fn key_matches(&self, key: &str, text: &str) -> bool {
+        if let Some(regex) = parse_regex_key(key, self.case_sensitive) {
+            return regex.is_match(text);
+        }
+        let (key, haystack) = (self.normalize(key.trim()), self.normalize(text));
+        if key.is_empty() {
+            return false;
+        }
+        haystack.match_indices(&key).any(|(start, k)| {
+            !self.whole_words || on_word_boundary(&haystack, start, start + k.len())
+        })
+    }
+
+    /// Whether an entry's secondary keys allow it to fire
+    fn secondary_allows(&self, entry: &LoreEntry, text: &str) -> bool {
+        let keys = &entry.injection.secondary_keys;
+        if keys.is_empty() {
+            return true;
+        }
+        let mut found = keys.iter().map(|k| self.key_matches(k, text));
+        match entry.injection.secondary_logic.as_str() {
+            "andAll" => found.all(|f| f),
+            "notAny" => !found.any(|f| f),
+            "notAll" => !found.all(|f| f),
+            _ => found.any(|f| f),
+        }
+    }
+}
+
+fn fired(entry: &LoreEntry, reason: String, depth: usize, triggered: bool) -> FiredEntry {
+    let text = entry.render();
+    FiredEntry {
+        entry_id: entry.id.clone(),
+        name: entry.name.clone(),
+        kind: entry.kind.clone(),
+        tokens: count_tokens(&text),
+        text,
+        priority: entry.injection.priority,
+        class: entry.injection.class().to_string(),
+        reason,
+        depth,
+        triggered,
+    }
+}
+
+/// Work out which entries fire for `scan_text` on `turn`.
+///
+/// Payload entries stay silent until they've ripened (`ripened` holds the
+/// turn each did), then fire for their reveal turns. Always-on entries and
+/// entries still inside their sticky period fire unconditionally. Keyword entries fire when a key matches, their secondary
+/// keys agree, they are not cooling down and the probability roll passes.
+/// Fired entries' text is then scanned again, up to the recursion depth.
+/// Results are ordered by priority, highest first.
+pub fn activate(
+    entries: &[LoreEntry],
+    scan_text: &str,
+    settings: &ActivationSettings,
+    timers: &HashMap<String, ActivationTimer>,
+    ripened: &HashMap<String, i64>,
+    turn: i64,
+    rng: &mut impl Rng,
+) -> Vec<FiredEntry> {
+    let matcher = Matcher::build(entries, settings);
+    let mut result: Vec<FiredEntry> = Vec::new();
+    let mut done: HashSet<usize> = HashSet::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let payload = entry.injection.payload.as_ref();
+        if payload.is_some() && !ripened.contains_key(&entry.id) {
+            done.insert(i);
+            continue;
+        }
+        let revealing = payload
+            .zip(ripened.get(&entry.id))
+            .map(|(schedule, ripe_at)| ripe_at + schedule.reveal_turns - turn)
+            .filter(|left| *left > 0);
+        let reason = match (entry.injection.mode.as_str(), timers.get(&entry.id)) {
+            _ if revealing.is_some() => {
+                revealing.map(|left| format!("payload revealed ({} turns left)", left))
+            }
+            ("never", _) => None,
+            ("always", _) => Some("always active".to_string()),
+            (_, Some(t)) if t.sticky_until >= turn && t.last_activated_turn < turn => {
+                Some(format!("sticky ({} turns left)", t.sticky_until - turn))
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            done.insert(i);
+            result.push(fired(entry, reason, 0, false));
+        }
+    }
+
+    let mut text = scan_text.to_string();
+    let mut via: HashMap<usize, String> = HashMap::new();
+    for depth in 0..=settings.max_recursion_depth {
+        let mut hits: Vec<(usize, String)> = matcher
+            .scan(&text)
+            .into_iter()
+            .filter(|(i, _)| !done.contains(i))
+            .collect();
+        hits.sort_by_key(|(i, _)| *i);
+
+        let mut next_text: Vec<String> = Vec::new();
+        let mut next_via: HashMap<usize, String> = HashMap::new();
+        for (i, key) in hits {
+            let entry = &entries[i];
+            let injection = &entry.injection;
+            if depth > 0 && injection.exclude_recursion {
+                continue;
+            }
+            let cooling = timers
+                .get(&entry.id)
+                .is_some_and(|t| t.sticky_until < turn && t.cooldown_until >= turn);
+            if cooling || !matcher.secondary_allows(entry, &text) {
+                continue;
+            }
+            if injection.probability < 100 && rng.gen_range(0..100) >= injection.probability {
+                continue;
+            }
+            done.insert(i);
+            let reason = match via.get(&i) {
+                Some(source) => format!("keyword: {} (via {})", key, source),
+                None => format!("keyword: {}", key),
+            };
+            if !injection.prevent_recursion {
+                next_text.push(entry.render());
+                for j in matcher.scan(&entry.render()).into_keys() {
+                    next_via.entry(j).or_insert_with(|| entry.name.clone());
+                }
+            }
+            result.push(fired(entry, reason, depth, true));
+        }
+        if next_text.is_empty() {
+            break;
+        }
+        text = next_text.join("\n");
+        via = next_via;
+    }
+
+    result.sort_by_key(|f| std::cmp::Reverse(f.priority));
+    result
+}
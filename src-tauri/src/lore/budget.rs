@@ -0,0 +1,86 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::types::{ActivationSettings, ActiveLore, FiredEntry, BUDGET_CLASSES};
+use crate::llm::debug::TruncationDecision;
+
+fn class_rank(class: &str) -> usize {
+    BUDGET_CLASSES
+        .iter()
+        .position(|c| *c == class)
+        .unwrap_or(BUDGET_CLASSES.len())
+}
+
+/// Fit fired entries into the lore budget.
+///
+/// Entries claim tokens class by class (always, high, normal, low), by
+/// priority within a class, with ties broken by size and then name so the
+/// same input always cuts the same entries. An entry is cut when it would
+/// overrun the total budget, its class cap or its type cap, so low classes
+/// lose out first when the budget is tight. `total` overrides the configured
+/// total budget. Kept entries come back in claim order.
+pub fn apply_budget(
+    mut fired: Vec<FiredEntry>,
+    settings: &ActivationSettings,
+    total: Option<usize>,
+) -> ActiveLore {
+    let total = total.unwrap_or(settings.token_budget);
+    fired.sort_by(|a, b| {
+        (class_rank(&a.class), Reverse(a.priority), a.tokens, &a.name).cmp(&(
+            class_rank(&b.class),
+            Reverse(b.priority),
+            b.tokens,
+            &b.name,
+        ))
+    });
+
+    let mut used = 0;
+    let mut by_class: HashMap<String, usize> = HashMap::new();
+    let mut by_type: HashMap<String, usize> = HashMap::new();
+    let mut kept = Vec::new();
+    let mut cut = Vec::new();
+    for entry in fired {
+        let class_used = by_class.get(&entry.class).copied().unwrap_or(0);
+        let type_used = by_type.get(&entry.kind).copied().unwrap_or(0);
+        let reason = if used + entry.tokens > total {
+            Some(format!("lore budget ({} tokens) exhausted", total))
+        } else if let Some(cap) = settings
+            .class_budgets
+            .get(&entry.class)
+            .filter(|cap| class_used + entry.tokens > **cap)
+        {
+            Some(format!(
+                "{} class budget ({} tokens) exhausted",
+                entry.class, cap
+            ))
+        } else {
+            settings
+                .type_budgets
+                .get(&entry.kind)
+                .filter(|cap| type_used + entry.tokens > **cap)
+                .map(|cap| format!("{} budget ({} tokens) exhausted", entry.kind, cap))
+        };
+        match reason {
+            Some(reason) => cut.push(TruncationDecision {
+                item: format!("lore: {}", entry.name),
+                reason,
+                tokens: entry.tokens,
+            }),
+            None => {
+                used += entry.tokens;
+                *by_class.entry(entry.class.clone()).or_default() += entry.tokens;
+                *by_type.entry(entry.kind.clone()).or_default() += entry.tokens;
+                kept.push(entry);
+            }
+        }
+    }
+    // Report the lowest classes first: they are the ones the budget gave up
+    cut.reverse();
+
+    ActiveLore {
+        entries: kept,
+        cut,
+        tokens: used,
+        out_of_time: Vec::new(),
+    }
+}
@@ -0,0 +1,198 @@
+use serde_json::json;
+use tauri::State;
+
+use super::activation::activate;
+use super::budget::apply_budget;
+use super::payload;
+use super::store::{
+    current_turn, load_current_lore, load_settings, load_timers, record_activations,
+};
+use super::types::{ActiveLore, PayloadSchedule};
+use super::world_update::{take_snapshot, DEFAULT_UPDATE_ENTRIES};
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::calendar::parser::parse_duration;
+use crate::calendar::types::TimeTracker;
+use crate::db::{now_millis, DbState};
+use crate::jobs::queue::JobQueue;
+use crate::jobs::types::JobKind;
+use crate::npcs::store::annotate_lore;
+
+/// Lorebook entries that fire for this turn, with the reason each fired,
+/// fitted to the lore budget (`token_budget` overrides the configured total).
+/// Entries outside their in-story validity window are skipped. Starts
+/// sticky/cooldown timers for keyword hits that made the cut, and marks
+/// foreshadowing payloads whose conditions are now met as ripe.
+#[tauri::command]
+pub async fn get_active_lore(
+    db: State<'_, DbState>,
+    story_id: String,
+    scan_text: String,
+    token_budget: Option<usize>,
+) -> Result<ActiveLore, String> {
+    let pool = db.pool();
+    let (mut entries, out_of_time) = load_current_lore(pool, &story_id).await?;
+    annotate_lore(pool, &story_id, &mut entries).await?;
+    let settings = load_settings(pool).await?;
+    let timers = load_timers(pool, &story_id).await?;
+    let turn = current_turn(pool, &story_id).await?;
+    let ripened = payload::ripened(pool, &story_id, &entries, turn, true).await?;
+
+    let fired = activate(
+        &entries,
+        &scan_text,
+        &settings,
+        &timers,
+        &ripened,
+        turn,
+        &mut rand::thread_rng(),
+    );
+    let mut active = apply_budget(fired, &settings, token_budget);
+    active.out_of_time = out_of_time;
+    record_activations(pool, &story_id, &entries, &active.entries, turn).await?;
+    Ok(active)
+}
+
+#[tauri::command]
+pub async fn reset_lore_timers(db: State<'_, DbState>, story_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM lore_activation_state WHERE story_id = ?")
+        .bind(&story_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to reset lore timers: {}", e))?;
+    Ok(())
+}
+
+/// Set when a lorebook entry holds in story time. `duration` ("3 days",
+/// "until the next moon") is measured from `valid_from`, or from the current
+/// story time when no start is given, and overrides `valid_until`.
+/// Passing nothing clears the window.
+#[tauri::command]
+pub async fn set_lore_validity(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: String,
+    valid_from: Option<TimeTracker>,
+    valid_until: Option<TimeTracker>,
+    duration: Option<String>,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let calendar = load_calendar(pool, &story_id).await?;
+    let (valid_from, valid_until) = match duration {
+        Some(expression) => {
+            let from = match valid_from {
+                Some(from) => from,
+                None => load_time_tracker(pool, &story_id).await?,
+            };
+            let minutes = parse_duration(&calendar, &expression)?;
+            let until = calendar.tracker_at(calendar.minutes_since_start(&from) + minutes);
+            (Some(from), Some(until))
+        }
+        None => (valid_from, valid_until),
+    };
+
+    let raw: Option<Option<String>> =
+        sqlx::query_scalar("SELECT injection FROM entries WHERE id = ? AND story_id = ?")
+            .bind(&entry_id)
+            .bind(&story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load lorebook entry: {}", e))?;
+    let Some(raw) = raw else {
+        return Err(format!("Lorebook entry {} not found", entry_id));
+    };
+    // Edit the stored JSON in place so fields this side doesn't model survive
+    let mut injection: serde_json::Map<String, serde_json::Value> = raw
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    for (key, value) in [("validFrom", valid_from), ("validUntil", valid_until)] {
+        match value {
+            Some(time) => injection.insert(key.to_string(), serde_json::json!(time)),
+            None => injection.remove(key),
+        };
+    }
+
+    sqlx::query("UPDATE entries SET injection = ?, updated_at = ? WHERE id = ?")
+        .bind(serde_json::Value::Object(injection).to_string())
+        .bind(now_millis())
+        .bind(&entry_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save lorebook entry: {}", e))?;
+    Ok(())
+}
+
+/// Make a lorebook entry a foreshadowing payload that stays out of the
+/// context until `schedule`'s conditions are met, or pass nothing to make
+/// it an ordinary entry again. Chapters are counted from now.
+#[tauri::command]
+pub async fn schedule_lore_payload(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: String,
+    schedule: Option<PayloadSchedule>,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let schedule = match schedule {
+        Some(schedule) => Some(PayloadSchedule {
+            from_chapter: payload::StoryProgress::load(pool, &story_id)
+                .await?
+                .chapters,
+            ..schedule
+        }),
+        None => None,
+    };
+
+    let raw: Option<Option<String>> =
+        sqlx::query_scalar("SELECT injection FROM entries WHERE id = ? AND story_id = ?")
+            .bind(&entry_id)
+            .bind(&story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load lorebook entry: {}", e))?;
+    let Some(raw) = raw else {
+        return Err(format!("Lorebook entry {} not found", entry_id));
+    };
+    let mut injection: serde_json::Map<String, serde_json::Value> = raw
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    match schedule {
+        Some(schedule) => injection.insert("payload".to_string(), json!(schedule)),
+        None => injection.remove("payload"),
+    };
+
+    sqlx::query("UPDATE entries SET injection = ?, updated_at = ? WHERE id = ?")
+        .bind(serde_json::Value::Object(injection).to_string())
+        .bind(now_millis())
+        .bind(&entry_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save lorebook entry: {}", e))?;
+    payload::reset(pool, &story_id, &entry_id).await
+}
+
+/// Snapshot the lorebook and latest passages now and update the lorebook
+/// from them in the background, so the next turn doesn't wait on it.
+/// Results (including conflicts with manual edits) arrive on
+/// `world-update-applied`. Returns the job id.
+#[tauri::command]
+pub async fn queue_world_update(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    recent_entries: Option<usize>,
+) -> Result<String, String> {
+    let snapshot = take_snapshot(
+        db.pool(),
+        &story_id,
+        recent_entries.unwrap_or(DEFAULT_UPDATE_ENTRIES),
+    )
+    .await?;
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::WorldUpdate,
+            json!({ "snapshot": snapshot }),
+        )
+        .await
+}
@@ -0,0 +1,8 @@
+pub mod activation;
+pub mod budget;
+pub mod commands;
+pub mod payload;
+pub mod store;
+pub mod types;
+pub mod validity;
+pub mod world_update;
@@ -0,0 +1,125 @@
+//! Foreshadowing payloads: entries held back until chapters pass, a story
+//! date arrives or a beat resolves. Ripeness is checked each turn and the
+//! turn it happened on is kept, so the reveal survives later edits.
+
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use super::types::{LoreEntry, PayloadSchedule};
+use crate::beats::store::load_beats;
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::calendar::types::CalendarDefinition;
+use crate::context::branch::chapter_count;
+use crate::db::now_millis;
+
+/// What payload conditions are checked against
+pub struct StoryProgress {
+    pub chapters: i64,
+    /// Minutes since the story started
+    pub now: i64,
+    pub calendar: CalendarDefinition,
+    /// Status of every beat on the current branch
+    pub beats: HashMap<String, String>,
+}
+
+impl StoryProgress {
+    pub async fn load(pool: &SqlitePool, story_id: &str) -> Result<Self, String> {
+        let chapters = chapter_count(pool, story_id).await?;
+        let calendar = load_calendar(pool, story_id).await?;
+        let now = calendar.minutes_since_start(&load_time_tracker(pool, story_id).await?);
+        let beats = load_beats(pool, story_id)
+            .await?
+            .into_iter()
+            .map(|b| (b.id, b.status.unwrap_or_default()))
+            .collect();
+        Ok(Self {
+            chapters,
+            now,
+            calendar,
+            beats,
+        })
+    }
+
+    pub fn is_ripe(&self, schedule: &PayloadSchedule) -> bool {
+        let chapters = schedule
+            .after_chapters
+            .map(|n| self.chapters - schedule.from_chapter >= n);
+        let time = schedule
+            .after_time
+            .map(|time| self.now >= self.calendar.minutes_since_start(&time));
+        let beat = schedule.after_beat.as_ref().map(|beat| {
+            let status = self.beats.get(beat).map(String::as_str);
+            match schedule.beat_status.as_deref() {
+                Some(wanted) => status == Some(wanted),
+                None => matches!(status, Some("completed" | "failed")),
+            }
+        });
+        let conditions: Vec<bool> = [chapters, time, beat].into_iter().flatten().collect();
+        match (conditions.is_empty(), schedule.any) {
+            (true, _) => false,
+            (false, true) => conditions.contains(&true),
+            (false, false) => conditions.iter().all(|met| *met),
+        }
+    }
+}
+
+/// Payload entries that have ripened, with the turn each ripened on.
+/// With `record`, entries that ripen now are saved as ripening on `turn`;
+/// without it they're only reported.
+pub async fn ripened(
+    pool: &SqlitePool,
+    story_id: &str,
+    entries: &[LoreEntry],
+    turn: i64,
+    record: bool,
+) -> Result<HashMap<String, i64>, String> {
+    let mut ripened: HashMap<String, i64> =
+        sqlx::query_as("SELECT entry_id, ripened_turn FROM lore_payload_state WHERE story_id = ?")
+            .bind(story_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load lore payloads: {}", e))?
+            .into_iter()
+            .collect();
+    let waiting: Vec<(&LoreEntry, &PayloadSchedule)> = entries
+        .iter()
+        .filter(|e| !ripened.contains_key(&e.id))
+        .filter_map(|e| Some((e, e.injection.payload.as_ref()?)))
+        .collect();
+    if waiting.is_empty() {
+        return Ok(ripened);
+    }
+
+    let progress = StoryProgress::load(pool, story_id).await?;
+    for (entry, schedule) in waiting {
+        if !progress.is_ripe(schedule) {
+            continue;
+        }
+        if record {
+            sqlx::query(
+                "INSERT OR IGNORE INTO lore_payload_state \
+                 (story_id, entry_id, ripened_turn, ripened_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(story_id)
+            .bind(&entry.id)
+            .bind(turn)
+            .bind(now_millis())
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to save lore payload: {}", e))?;
+        }
+        ripened.insert(entry.id.clone(), turn);
+    }
+    Ok(ripened)
+}
+
+/// Forget that an entry ripened, so a new schedule starts over
+pub async fn reset(pool: &SqlitePool, story_id: &str, entry_id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM lore_payload_state WHERE story_id = ? AND entry_id = ?")
+        .bind(story_id)
+        .bind(entry_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to reset lore payload: {}", e))?;
+    Ok(())
+}
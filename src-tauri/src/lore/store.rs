@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+
+use super::types::{ActivationSettings, ActivationTimer, FiredEntry, LoreEntry};
+use super::validity::partition_by_time;
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::context::branch::{visible_entries, world_view};
+use crate::db::now_millis;
+use crate::llm::config::get_setting;
+
+#[derive(sqlx::FromRow)]
+struct EntryRow {
+    id: String,
+    name: String,
+    #[sqlx(rename = "type")]
+    kind: String,
+    description: Option<String>,
+    aliases: Option<String>,
+    injection: Option<String>,
+    overrides_id: Option<String>,
+    deleted: i64,
+}
+
+async fn rows_for_branch(
+    pool: &SqlitePool,
+    story_id: &str,
+    branch_id: Option<&str>,
+) -> Result<Vec<EntryRow>, String> {
+    sqlx::query_as(
+        "SELECT id, name, type, description, aliases, injection, overrides_id, deleted \
+         FROM entries WHERE story_id = ? AND branch_id IS ? ORDER BY created_at ASC",
+    )
+    .bind(story_id)
+    .bind(branch_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load lorebook: {}", e))
+}
+
+/// Lorebook entries visible on the story's current branch, loaded the same
+/// way the story store does for the branch's storage mode
+pub async fn load_lore(pool: &SqlitePool, story_id: &str) -> Result<Vec<LoreEntry>, String> {
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        layers.push(rows_for_branch(pool, story_id, branch.as_deref()).await?);
+    }
+    let rows = view.resolve(
+        layers,
+        |r| r.overrides_id.clone().unwrap_or_else(|| r.id.clone()),
+        |r| r.deleted != 0,
+    );
+    Ok(rows
+        .into_iter()
+        .map(|r| LoreEntry {
+            id: r.id,
+            name: r.name,
+            kind: r.kind,
+            description: r.description.unwrap_or_default(),
+            aliases: r
+                .aliases
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            injection: r
+                .injection
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Lorebook entries in effect at the story's current in-story time, plus the
+/// names of entries whose validity window excludes it
+pub async fn load_current_lore(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<(Vec<LoreEntry>, Vec<String>), String> {
+    let entries = load_lore(pool, story_id).await?;
+    let calendar = load_calendar(pool, story_id).await?;
+    let time = load_time_tracker(pool, story_id).await?;
+    Ok(partition_by_time(entries, &calendar, &time))
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ActivationSettings, String> {
+    Ok(get_setting(pool, "lore_activation_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// The story's current turn: player actions visible on the current branch
+pub async fn current_turn(pool: &SqlitePool, story_id: &str) -> Result<i64, String> {
+    Ok(visible_entries(pool, story_id)
+        .await?
+        .iter()
+        .filter(|e| e.kind == "user_action")
+        .count() as i64)
+}
+
+pub async fn load_timers(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<HashMap<String, ActivationTimer>, String> {
+    let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT entry_id, last_activated_turn, sticky_until, cooldown_until \
+         FROM lore_activation_state WHERE story_id = ?",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load lore timers: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, last, sticky, cooldown)| {
+            (
+                id,
+                ActivationTimer {
+                    last_activated_turn: last,
+                    sticky_until: sticky,
+                    cooldown_until: cooldown,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Start sticky and cooldown timers for entries whose keys fired this turn
+pub async fn record_activations(
+    pool: &SqlitePool,
+    story_id: &str,
+    entries: &[LoreEntry],
+    fired: &[FiredEntry],
+    turn: i64,
+) -> Result<(), String> {
+    for f in fired.iter().filter(|f| f.triggered) {
+        let Some(entry) = entries.iter().find(|e| e.id == f.entry_id) else {
+            continue;
+        };
+        let injection = &entry.injection;
+        if injection.sticky <= 0 && injection.cooldown <= 0 {
+            continue;
+        }
+        let sticky_until = turn + injection.sticky.max(0);
+        sqlx::query(
+            "INSERT OR REPLACE INTO lore_activation_state \
+             (story_id, entry_id, last_activated_turn, sticky_until, cooldown_until, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(story_id)
+        .bind(&entry.id)
+        .bind(turn)
+        .bind(sticky_until)
+        .bind(sticky_until + injection.cooldown.max(0))
+        .bind(now_millis())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save lore timers: {}", e))?;
+    }
+    Ok(())
+}
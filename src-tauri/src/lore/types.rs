@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::types::TimeTracker;
+use crate::llm::debug::TruncationDecision;
+
+/// Injection rules of a lorebook entry (the `entries.injection` JSON).
+/// Mirrors the frontend `EntryInjection`; fields it doesn't know default off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Injection {
+    /// 'always' | 'keyword' | 'never'
+    pub mode: String,
+    /// Plain keys, or `/pattern/flags` for a regex key
+    pub keywords: Vec<String>,
+    pub priority: i64,
+    /// Extra keys checked once a primary key has matched
+    pub secondary_keys: Vec<String>,
+    /// 'andAny' | 'andAll' | 'notAny' | 'notAll'
+    pub secondary_logic: String,
+    /// Chance (0-100) that a keyword match actually fires the entry
+    pub probability: u8,
+    /// Turns the entry stays active after firing
+    pub sticky: i64,
+    /// Turns after the sticky period during which keywords are ignored
+    pub cooldown: i64,
+    /// Only the story text can fire this entry, not other entries' text
+    pub exclude_recursion: bool,
+    /// This entry's text never fires other entries
+    pub prevent_recursion: bool,
+    /// 'always' | 'high' | 'normal' | 'low'; defaults from the mode
+    pub budget_class: Option<String>,
+    /// In-story time the entry becomes true; always true before it if unset
+    pub valid_from: Option<TimeTracker>,
+    /// In-story time the entry stops being true and is no longer injected
+    pub valid_until: Option<TimeTracker>,
+    /// Foreshadowing payload: the entry is held back until its conditions
+    /// are met, then injected for a few turns
+    pub payload: Option<PayloadSchedule>,
+}
+
+/// When a payload entry ripens. Unset conditions are ignored; with none
+/// set the payload never ripens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PayloadSchedule {
+    /// Chapters that must close after `from_chapter`
+    pub after_chapters: Option<i64>,
+    /// Chapters the story had when the payload was planned
+    pub from_chapter: i64,
+    /// In-story time the payload waits for
+    pub after_time: Option<TimeTracker>,
+    /// Beat that must be resolved first
+    pub after_beat: Option<String>,
+    /// 'completed' | 'failed'; either resolution counts when unset
+    pub beat_status: Option<String>,
+    /// Ripen on any one condition instead of all of them
+    pub any: bool,
+    /// Turns the entry is injected for once ripe, keywords or not; after
+    /// that it activates like any other entry
+    pub reveal_turns: i64,
+}
+
+impl Default for PayloadSchedule {
+    fn default() -> Self {
+        Self {
+            after_chapters: None,
+            from_chapter: 0,
+            after_time: None,
+            after_beat: None,
+            beat_status: None,
+            any: false,
+            reveal_turns: 3,
+        }
+    }
+}
+
+/// Budget classes in the order they claim tokens
+pub const BUDGET_CLASSES: [&str; 4] = ["always", "high", "normal", "low"];
+
+impl Injection {
+    /// The entry's budget class: explicit, else 'always' for always-on
+    /// entries and 'normal' for the rest
+    pub fn class(&self) -> &str {
+        match self.budget_class.as_deref() {
+            Some(class) if BUDGET_CLASSES.contains(&class) => class,
+            _ if self.mode == "always" => "always",
+            _ => "normal",
+        }
+    }
+}
+
+impl Default for Injection {
+    fn default() -> Self {
+        Self {
+            mode: "keyword".to_string(),
+            keywords: Vec::new(),
+            priority: 0,
+            secondary_keys: Vec::new(),
+            secondary_logic: "andAny".to_string(),
+            probability: 100,
+            sticky: 0,
+            cooldown: 0,
+            exclude_recursion: false,
+            prevent_recursion: false,
+            budget_class: None,
+            valid_from: None,
+            valid_until: None,
+            payload: None,
+        }
+    }
+}
+
+/// A lorebook entry as resolved for the story's current branch
+#[derive(Debug, Clone)]
+pub struct LoreEntry {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub description: String,
+    pub aliases: Vec<String>,
+    pub injection: Injection,
+}
+
+impl LoreEntry {
+    /// The entry as it appears in a prompt
+    pub fn render(&self) -> String {
+        format!("[{}] {}: {}", self.kind, self.name, self.description)
+    }
+}
+
+/// User settings for lore activation (`lore_activation_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActivationSettings {
+    /// How many rounds fired entries' text may fire further entries
+    pub max_recursion_depth: usize,
+    pub case_sensitive: bool,
+    /// Plain keys only match whole words
+    pub whole_words: bool,
+    /// Entry names and aliases act as keys alongside explicit keywords
+    pub match_names: bool,
+    /// Tokens all fired lore may use together
+    pub token_budget: usize,
+    /// Optional cap per budget class ('always', 'high', 'normal', 'low')
+    pub class_budgets: HashMap<String, usize>,
+    /// Optional cap per entry type ('character', 'location', ...)
+    pub type_budgets: HashMap<String, usize>,
+}
+
+impl Default for ActivationSettings {
+    fn default() -> Self {
+        Self {
+            max_recursion_depth: 2,
+            case_sensitive: false,
+            whole_words: true,
+            match_names: true,
+            token_budget: 3000,
+            class_budgets: HashMap::new(),
+            type_budgets: HashMap::new(),
+        }
+    }
+}
+
+/// Sticky and cooldown timers of one entry, in turns
+#[derive(Debug, Clone, Default)]
+pub struct ActivationTimer {
+    pub last_activated_turn: i64,
+    pub sticky_until: i64,
+    pub cooldown_until: i64,
+}
+
+/// An entry that fired for the current turn
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FiredEntry {
+    pub entry_id: String,
+    pub name: String,
+    pub kind: String,
+    pub text: String,
+    pub priority: i64,
+    /// e.g. 'always active', 'keyword: sword', 'sticky (2 turns left)'
+    pub reason: String,
+    pub class: String,
+    pub tokens: usize,
+    /// 0 when fired by the story text, n when fired through n entries' text
+    pub depth: usize,
+    /// Fired by a key match this turn (as opposed to always-on or sticky)
+    #[serde(skip)]
+    pub triggered: bool,
+}
+
+/// Fired entries after the lore budget is applied
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveLore {
+    pub entries: Vec<FiredEntry>,
+    /// Entries that fired but were cut for budget, lowest class first
+    pub cut: Vec<TruncationDecision>,
+    pub tokens: usize,
+    /// Names of entries skipped because their in-story validity has lapsed
+    /// (or not yet begun)
+    pub out_of_time: Vec<String>,
+}
+
+/// A lorebook entry as captured for a world update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotEntry {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub description: String,
+    pub keywords: Vec<String>,
+    pub aliases: Vec<String>,
+    pub branch_id: Option<String>,
+    pub overrides_id: Option<String>,
+    /// Compared at apply time to detect edits made while the job ran
+    pub updated_at: i64,
+}
+
+/// Everything a world update job reads, frozen when the job is queued so the
+/// story can move on while it runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldSnapshot {
+    /// Branch that new and overriding rows are written to
+    pub branch_id: Option<String>,
+    /// Whether the branch stores only its changes (copy-on-write)
+    pub copy_on_write: bool,
+    pub passage: String,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// A proposed change that was not applied because the lorebook moved on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldUpdateConflict {
+    pub entry_id: Option<String>,
+    pub name: String,
+    pub reason: String,
+    /// The change as proposed, for the user to apply by hand
+    pub proposed: serde_json::Value,
+}
+
+/// Outcome of a world update job, also emitted on `world-update-applied`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldUpdateResult {
+    pub story_id: String,
+    pub updated: Vec<String>,
+    pub created: Vec<String>,
+    pub conflicts: Vec<WorldUpdateConflict>,
+}
@@ -0,0 +1,27 @@
+use super::types::{Injection, LoreEntry};
+use crate::calendar::types::{CalendarDefinition, TimeTracker};
+
+/// Whether an entry's facts hold at `now` (minutes since the story started)
+pub fn in_effect(injection: &Injection, calendar: &CalendarDefinition, now: i64) -> bool {
+    let started = injection
+        .valid_from
+        .is_none_or(|from| calendar.minutes_since_start(&from) <= now);
+    let lapsed = injection
+        .valid_until
+        .is_some_and(|until| calendar.minutes_since_start(&until) <= now);
+    started && !lapsed
+}
+
+/// Split entries into those in effect at story time `time` and the names of
+/// those that are not
+pub fn partition_by_time(
+    entries: Vec<LoreEntry>,
+    calendar: &CalendarDefinition,
+    time: &TimeTracker,
+) -> (Vec<LoreEntry>, Vec<String>) {
+    let now = calendar.minutes_since_start(time);
+    let (current, stale): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| in_effect(&e.injection, calendar, now));
+    (current, stale.into_iter().map(|e| e.name).collect())
+}
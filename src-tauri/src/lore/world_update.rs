@@ -0,0 +1,460 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::types::{
+    Injection, SnapshotEntry, WorldSnapshot, WorldUpdateConflict, WorldUpdateResult,
+};
+use crate::context::branch::{visible_entries, world_view, WorldView};
+use crate::db::now_millis;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You maintain the lorebook of an interactive story. \
+Given the existing entries and the latest passages, update entries whose facts the passages changed \
+and create entries for important new characters, locations, items, factions, concepts or events. \
+Keep descriptions concise and factual; never remove facts the passages don't contradict. \
+Respond with JSON only, in this shape: \
+{\"updates\": [{\"id\": \"<existing id>\", \"description\": \"<full new description>\", \
+\"keywords\": [\"<optional full keyword list>\"], \"aliases\": [\"<optional full alias list>\"]}], \
+\"creations\": [{\"name\": \"<name>\", \"type\": \"character\" | \"location\" | \"item\" | \"faction\" | \"concept\" | \"event\", \
+\"description\": \"<description>\", \"keywords\": [\"<keyword>\"]}]}. \
+Respond with {\"updates\": [], \"creations\": []} if nothing changed.";
+
+/// Story entries included in a world update snapshot when not specified
+pub const DEFAULT_UPDATE_ENTRIES: usize = 10;
+
+const ENTRY_TYPES: [&str; 6] = [
+    "character",
+    "location",
+    "item",
+    "faction",
+    "concept",
+    "event",
+];
+
+fn reply_schema() -> OutputSchema {
+    let list = json!({ "type": "array", "items": { "type": "string" } });
+    OutputSchema {
+        name: "world_update",
+        schema: json!({
+            "type": "object",
+            "required": ["updates", "creations"],
+            "properties": {
+                "updates": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["id"],
+                        "properties": {
+                            "id": { "type": "string" },
+                            "description": { "type": "string" },
+                            "keywords": list,
+                            "aliases": list
+                        }
+                    }
+                },
+                "creations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "type", "description"],
+                        "properties": {
+                            "name": { "type": "string", "minLength": 1 },
+                            "type": { "enum": ENTRY_TYPES },
+                            "description": { "type": "string" },
+                            "keywords": list
+                        }
+                    }
+                }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct UpdateReply {
+    updates: Vec<ProposedUpdate>,
+    creations: Vec<ProposedEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+struct ProposedUpdate {
+    id: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    keywords: Option<Vec<String>>,
+    #[serde(default)]
+    aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+struct ProposedEntry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Row {
+    id: String,
+    name: String,
+    #[sqlx(rename = "type")]
+    kind: String,
+    description: Option<String>,
+    aliases: Option<String>,
+    injection: Option<String>,
+    branch_id: Option<String>,
+    overrides_id: Option<String>,
+    deleted: i64,
+    updated_at: i64,
+}
+
+/// Freeze the current branch's lorebook and latest passages for a world update
+pub async fn take_snapshot(
+    pool: &SqlitePool,
+    story_id: &str,
+    recent_entries: usize,
+) -> Result<WorldSnapshot, String> {
+    let view = world_view(pool, story_id).await?;
+    let mut layers = Vec::new();
+    for branch in view.layers() {
+        let rows: Vec<Row> = sqlx::query_as(
+            "SELECT id, name, type, description, aliases, injection, branch_id, overrides_id, \
+             deleted, updated_at FROM entries WHERE story_id = ? AND branch_id IS ? \
+             AND COALESCE(lore_management_blacklisted, 0) = 0 ORDER BY created_at ASC",
+        )
+        .bind(story_id)
+        .bind(&branch)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load lorebook: {}", e))?;
+        layers.push(rows);
+    }
+    let (branch_id, copy_on_write) = match &view {
+        WorldView::Exact(branch) => (branch.clone(), false),
+        WorldView::Lineage(ids) => (ids.last().cloned(), true),
+    };
+    let entries = view
+        .resolve(
+            layers,
+            |r| r.overrides_id.clone().unwrap_or_else(|| r.id.clone()),
+            |r| r.deleted != 0,
+        )
+        .into_iter()
+        .map(|r| SnapshotEntry {
+            keywords: r
+                .injection
+                .and_then(|raw| serde_json::from_str::<Injection>(&raw).ok())
+                .unwrap_or_default()
+                .keywords,
+            aliases: r
+                .aliases
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            id: r.id,
+            name: r.name,
+            kind: r.kind,
+            description: r.description.unwrap_or_default(),
+            branch_id: r.branch_id,
+            overrides_id: r.overrides_id,
+            updated_at: r.updated_at,
+        })
+        .collect();
+
+    let visible = visible_entries(pool, story_id).await?;
+    let passage = visible[visible.len().saturating_sub(recent_entries)..]
+        .iter()
+        .map(|e| match e.kind.as_str() {
+            "user_action" => format!("> {}", e.content),
+            _ => e.content.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(WorldSnapshot {
+        branch_id,
+        copy_on_write,
+        passage,
+        entries,
+    })
+}
+
+fn conflict(
+    entry_id: Option<&str>,
+    name: &str,
+    reason: &str,
+    proposed: impl serde::Serialize,
+) -> WorldUpdateConflict {
+    WorldUpdateConflict {
+        entry_id: entry_id.map(str::to_string),
+        name: name.to_string(),
+        reason: reason.to_string(),
+        proposed: json!(proposed),
+    }
+}
+
+/// Apply one update unless the entry changed since the snapshot. On a
+/// copy-on-write branch, inherited rows get an override row instead of being
+/// edited in place. Returns a conflict when the update was not applied.
+async fn apply_update(
+    tx: &mut Transaction<'_, Sqlite>,
+    snapshot: &WorldSnapshot,
+    entry: &SnapshotEntry,
+    update: &ProposedUpdate,
+) -> Result<Option<WorldUpdateConflict>, String> {
+    let current: Option<(i64, i64)> =
+        sqlx::query_as("SELECT updated_at, deleted FROM entries WHERE id = ?")
+            .bind(&entry.id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to load lorebook entry: {}", e))?;
+    match current {
+        None | Some((_, 1)) => {
+            return Ok(Some(conflict(
+                Some(&entry.id),
+                &entry.name,
+                "deleted since the snapshot",
+                update,
+            )));
+        }
+        Some((updated_at, _)) if updated_at != entry.updated_at => {
+            return Ok(Some(conflict(
+                Some(&entry.id),
+                &entry.name,
+                "edited since the snapshot",
+                update,
+            )));
+        }
+        _ => {}
+    }
+
+    let mut target = entry.id.clone();
+    if snapshot.copy_on_write && entry.branch_id != snapshot.branch_id {
+        let canonical = entry
+            .overrides_id
+            .clone()
+            .unwrap_or_else(|| entry.id.clone());
+        let existing: Option<String> =
+            sqlx::query_scalar("SELECT id FROM entries WHERE overrides_id = ? AND branch_id IS ?")
+                .bind(&canonical)
+                .bind(&snapshot.branch_id)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(|e| format!("Failed to load lorebook entry: {}", e))?;
+        if existing.is_some() {
+            return Ok(Some(conflict(
+                Some(&entry.id),
+                &entry.name,
+                "edited on this branch since the snapshot",
+                update,
+            )));
+        }
+        target = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO entries (id, story_id, name, type, description, hidden_info, aliases, state, \
+             adventure_state, creative_state, injection, first_mentioned, last_mentioned, mention_count, \
+             created_by, created_at, updated_at, lore_management_blacklisted, branch_id, overrides_id, deleted) \
+             SELECT ?, story_id, name, type, description, hidden_info, aliases, state, adventure_state, \
+             creative_state, injection, first_mentioned, last_mentioned, mention_count, created_by, \
+             created_at, updated_at, lore_management_blacklisted, ?, ?, 0 FROM entries WHERE id = ?",
+        )
+        .bind(&target)
+        .bind(&snapshot.branch_id)
+        .bind(&canonical)
+        .bind(&entry.id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to copy lorebook entry to branch: {}", e))?;
+    }
+
+    let keywords = update.keywords.as_ref().map(|k| json!(k).to_string());
+    sqlx::query(
+        "UPDATE entries SET description = COALESCE(?, description), \
+         aliases = COALESCE(?, aliases), \
+         injection = CASE WHEN ? IS NULL THEN injection \
+             ELSE json_set(COALESCE(injection, '{}'), '$.keywords', json(?)) END, \
+         updated_at = ? WHERE id = ?",
+    )
+    .bind(&update.description)
+    .bind(update.aliases.as_ref().map(|a| json!(a).to_string()))
+    .bind(&keywords)
+    .bind(&keywords)
+    .bind(now_millis())
+    .bind(&target)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to update lorebook entry: {}", e))?;
+    Ok(None)
+}
+
+async fn apply_creation(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: &str,
+    snapshot: &WorldSnapshot,
+    entry: &ProposedEntry,
+) -> Result<Option<WorldUpdateConflict>, String> {
+    let exists: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM entries WHERE story_id = ? AND branch_id IS ? AND deleted = 0 \
+         AND lower(name) = lower(?)",
+    )
+    .bind(story_id)
+    .bind(&snapshot.branch_id)
+    .bind(&entry.name)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to load lorebook: {}", e))?;
+    if let Some(id) = exists {
+        return Ok(Some(conflict(
+            Some(&id),
+            &entry.name,
+            "an entry with this name was added since the snapshot",
+            entry,
+        )));
+    }
+
+    let now = now_millis();
+    let injection = json!({ "mode": "keyword", "keywords": entry.keywords, "priority": 0 });
+    sqlx::query(
+        "INSERT INTO entries (id, story_id, name, type, description, aliases, state, injection, \
+         mention_count, created_by, created_at, updated_at, branch_id) \
+         VALUES (?, ?, ?, ?, ?, '[]', ?, ?, 0, 'ai', ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(story_id)
+    .bind(&entry.name)
+    .bind(&entry.kind)
+    .bind(&entry.description)
+    .bind(json!({ "type": entry.kind }).to_string())
+    .bind(injection.to_string())
+    .bind(now)
+    .bind(now)
+    .bind(&snapshot.branch_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to create lorebook entry: {}", e))?;
+    Ok(None)
+}
+
+/// Background job: ask the model for lorebook changes based on a snapshot
+/// taken when the job was queued, then apply them in one transaction.
+/// Changes to entries edited (or names added) since the snapshot are not
+/// applied but reported as conflicts. The outcome is emitted on
+/// `world-update-applied`.
+pub async fn run_world_update_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let story_id = job
+        .story_id
+        .clone()
+        .ok_or("World update job is missing its story")?;
+    let snapshot: WorldSnapshot = serde_json::from_value(
+        job.payload
+            .get("snapshot")
+            .cloned()
+            .ok_or("World update job is missing its snapshot")?,
+    )
+    .map_err(|e| format!("Invalid world snapshot: {}", e))?;
+
+    let listing = snapshot
+        .entries
+        .iter()
+        .map(|e| {
+            format!(
+                "[{}] ({}) {}: {}\n  keywords: {}",
+                e.id,
+                e.kind,
+                e.name,
+                e.description,
+                e.keywords.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = format!(
+        "Existing entries:\n{}\n\nLatest passages:\n{}",
+        if listing.is_empty() {
+            "(none)"
+        } else {
+            &listing
+        },
+        snapshot.passage
+    );
+
+    let llm = config::resolve_story_service(
+        pool,
+        Some(&story_id),
+        "loreManagement",
+        "agentic",
+    )
+    .await?;
+    let parsed: UpdateReply = complete_structured(
+        pool,
+        "loreManagement",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let mut result = WorldUpdateResult {
+        story_id: story_id.clone(),
+        updated: Vec::new(),
+        created: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for update in &parsed.updates {
+        // Drop ids the model invented
+        let Some(entry) = snapshot.entries.iter().find(|e| e.id == update.id) else {
+            continue;
+        };
+        match apply_update(&mut tx, &snapshot, entry, update).await? {
+            Some(c) => result.conflicts.push(c),
+            None => result.updated.push(entry.name.clone()),
+        }
+    }
+    for entry in &parsed.creations {
+        let name = entry.name.trim();
+        if name.is_empty()
+            || !ENTRY_TYPES.contains(&entry.kind.as_str())
+            || snapshot
+                .entries
+                .iter()
+                .any(|e| e.name.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        match apply_creation(&mut tx, &story_id, &snapshot, entry).await? {
+            Some(c) => result.conflicts.push(c),
+            None => result.created.push(name.to_string()),
+        }
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to apply world update: {}", e))?;
+
+    if let Err(e) = app.emit("world-update-applied", &result) {
+        eprintln!("Failed to emit world update event: {}", e);
+    }
+    serde_json::to_value(&result).map_err(|e| format!("Failed to serialize world update: {}", e))
+}
@@ -0,0 +1,183 @@
+//! Pending migrations are rehearsed on a copy of the database before the
+//! SQL plugin runs them for real. If one fails, the plugin only gets the
+//! migrations before it, so the app keeps working on the old schema and
+//! the failure can be reported instead of leaving a half-migrated database.
+
+use serde::Serialize;
+use sqlx::migrate::{MigrateError, Migration as SqlxMigration, MigrationType, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::borrow::Cow;
+use std::path::Path;
+use tauri::State;
+use tauri_plugin_sql::Migration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightStatus {
+    /// New database or nothing pending
+    #[default]
+    Skipped,
+    Passed,
+    Failed,
+}
+
+/// Outcome of rehearsing this launch's migrations
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationPreflight {
+    pub status: PreflightStatus,
+    /// Versions not yet applied to the database
+    pub pending: Vec<i64>,
+    /// First migration held back; it and everything after stay unapplied
+    pub failed_version: Option<i64>,
+    pub failed_description: Option<String>,
+    pub error: Option<String>,
+}
+
+async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+    sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+        .fetch_all(pool)
+        .await
+}
+
+/// Same conversion the SQL plugin does, so checksums match
+fn migrator(migrations: &[Migration]) -> Migrator {
+    let migrations = migrations
+        .iter()
+        .filter(|m| matches!(m.kind, tauri_plugin_sql::MigrationKind::Up))
+        .map(|m| {
+            SqlxMigration::new(
+                m.version,
+                Cow::Borrowed(m.description),
+                MigrationType::ReversibleUp,
+                Cow::Borrowed(m.sql),
+                false,
+            )
+        })
+        .collect::<Vec<_>>();
+    Migrator {
+        migrations: Cow::Owned(migrations),
+        ..Migrator::DEFAULT
+    }
+}
+
+/// Apply pending migrations to a copy of the database at `db_path`
+pub async fn rehearse(db_path: &Path, migrations: &[Migration]) -> MigrationPreflight {
+    let live = match SqlitePool::connect_with(SqliteConnectOptions::new().filename(db_path)).await {
+        Ok(pool) => pool,
+        Err(e) => return failed_at_start(migrations, Vec::new(), e.to_string()),
+    };
+    let applied = applied_versions(&live).await.unwrap_or_default();
+    let pending: Vec<i64> = migrations
+        .iter()
+        .map(|m| m.version)
+        .filter(|version| !applied.contains(version))
+        .collect();
+    if pending.is_empty() {
+        live.close().await;
+        return MigrationPreflight::default();
+    }
+
+    // VACUUM INTO takes a consistent copy even with the WAL in use
+    let copy = db_path.with_extension("preflight.db");
+    let _ = std::fs::remove_file(&copy);
+    let copied = sqlx::query("VACUUM INTO ?")
+        .bind(copy.to_string_lossy().to_string())
+        .execute(&live)
+        .await;
+    live.close().await;
+    if let Err(e) = copied {
+        return failed_at_start(
+            migrations,
+            pending,
+            format!("Couldn't copy the database: {}", e),
+        );
+    }
+
+    let result = run_on_copy(&copy, migrations).await;
+    let _ = std::fs::remove_file(&copy);
+    match result {
+        Ok(()) => MigrationPreflight {
+            status: PreflightStatus::Passed,
+            pending,
+            ..Default::default()
+        },
+        Err(e) => {
+            let failed_version = match &e {
+                MigrateError::ExecuteMigration(_, version) | MigrateError::Dirty(version) => {
+                    Some(*version)
+                }
+                _ => pending.first().copied(),
+            };
+            MigrationPreflight {
+                status: PreflightStatus::Failed,
+                failed_description: description_of(migrations, failed_version),
+                failed_version,
+                pending,
+                error: Some(e.to_string()),
+            }
+        }
+    }
+}
+
+async fn run_on_copy(copy: &Path, migrations: &[Migration]) -> Result<(), MigrateError> {
+    let options = SqliteConnectOptions::new()
+        .filename(copy)
+        .journal_mode(SqliteJournalMode::Memory);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    let result = migrator(migrations).run(&pool).await;
+    pool.close().await;
+    result
+}
+
+fn description_of(migrations: &[Migration], version: Option<i64>) -> Option<String> {
+    migrations
+        .iter()
+        .find(|m| Some(m.version) == version)
+        .map(|m| m.description.to_string())
+}
+
+/// Without a rehearsal nothing pending is trusted
+fn failed_at_start(
+    migrations: &[Migration],
+    pending: Vec<i64>,
+    error: String,
+) -> MigrationPreflight {
+    let failed_version = pending.first().copied();
+    MigrationPreflight {
+        status: PreflightStatus::Failed,
+        failed_description: description_of(migrations, failed_version),
+        failed_version,
+        pending,
+        error: Some(error),
+    }
+}
+
+/// The migrations the SQL plugin may run after `preflight`
+pub fn allowed(migrations: Vec<Migration>, preflight: &MigrationPreflight) -> Vec<Migration> {
+    match preflight.failed_version {
+        Some(failed) => migrations
+            .into_iter()
+            .filter(|m| m.version < failed)
+            .collect(),
+        None => migrations,
+    }
+}
+
+/// How this launch's migration rehearsal went, so a failure can be shown
+#[tauri::command]
+pub fn get_migration_preflight(preflight: State<'_, MigrationPreflight>) -> MigrationPreflight {
+    preflight.inner().clone()
+}
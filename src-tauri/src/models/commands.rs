@@ -0,0 +1,50 @@
+use tauri::{AppHandle, State};
+
+use super::types::{InstalledModel, ModelSpec};
+use super::ModelManager;
+
+/// Download and install a model. An interrupted or cancelled download of
+/// the same model picks up where it stopped.
+#[tauri::command]
+pub async fn download_model(
+    app: AppHandle,
+    models: State<'_, ModelManager>,
+    spec: ModelSpec,
+) -> Result<InstalledModel, String> {
+    models.download(&app, spec).await
+}
+
+/// Stop a model download, keeping what it fetched. Returns false when it
+/// isn't running.
+#[tauri::command]
+pub fn cancel_model_download(models: State<'_, ModelManager>, kind: String, id: String) -> bool {
+    models.cancel(&kind, &id)
+}
+
+/// Installed models of one kind, or all of them
+#[tauri::command]
+pub fn list_installed_models(
+    models: State<'_, ModelManager>,
+    kind: Option<String>,
+) -> Vec<InstalledModel> {
+    models.list(kind.as_deref())
+}
+
+/// One installed model, for a subsystem checking it has what it needs
+#[tauri::command]
+pub fn get_installed_model(
+    models: State<'_, ModelManager>,
+    kind: String,
+    id: String,
+) -> Option<InstalledModel> {
+    models.installed(&kind, &id)
+}
+
+#[tauri::command]
+pub fn remove_model(
+    models: State<'_, ModelManager>,
+    kind: String,
+    id: String,
+) -> Result<(), String> {
+    models.remove(&kind, &id)
+}
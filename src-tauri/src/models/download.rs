@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::types::ModelFileSpec;
+
+/// Error a download stops with once cancelled
+pub const CANCELLED: &str = "Download cancelled";
+
+/// Feed what an earlier attempt left in `path` to the hasher
+async fn hash_existing(path: &Path, hasher: &mut Sha256) -> Result<u64, String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to read partial download: {}", e))?;
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut read = 0u64;
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read partial download: {}", e))?;
+        if n == 0 {
+            return Ok(read);
+        }
+        hasher.update(&buffer[..n]);
+        read += n as u64;
+    }
+}
+
+/// Download `file` into `part`, carrying on from whatever is already there
+/// when the server supports ranges. Reports `(downloaded, total)` roughly
+/// every megabyte and returns the verified hex SHA-256. A file failing its
+/// checksum is deleted so the next attempt starts over.
+pub async fn fetch(
+    client: &reqwest::Client,
+    file: &ModelFileSpec,
+    part: &Path,
+    cancelled: &AtomicBool,
+    mut progress: impl FnMut(u64, Option<u64>),
+) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let mut start = match tokio::fs::metadata(part).await {
+        Ok(meta) if meta.len() > 0 => hash_existing(part, &mut hasher).await?,
+        _ => 0,
+    };
+
+    let mut request = client.get(&file.url);
+    if start > 0 {
+        request = request.header(RANGE, format!("bytes={}-", start));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", file.name, e))?;
+
+    // The earlier attempt already had everything
+    let complete = start > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE;
+    if !complete {
+        response = response
+            .error_for_status()
+            .map_err(|e| format!("Failed to download {}: {}", file.name, e))?;
+        if start > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            // No range support; start over
+            start = 0;
+            hasher = Sha256::new();
+        }
+        let total = response
+            .content_length()
+            .map(|len| len + start)
+            .or(file.size);
+        let mut out = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(start > 0)
+            .truncate(start == 0)
+            .open(part)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", file.name, e))?;
+
+        let mut downloaded = start;
+        let mut last_reported = 0u64;
+        progress(downloaded, total);
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", file.name, e))?
+        {
+            if cancelled.load(Ordering::Relaxed) {
+                // Keep what arrived so far for resuming
+                let _ = out.flush().await;
+                return Err(CANCELLED.to_string());
+            }
+            hasher.update(&chunk);
+            out.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", file.name, e))?;
+            downloaded += chunk.len() as u64;
+            if downloaded - last_reported >= 1 << 20 {
+                last_reported = downloaded;
+                progress(downloaded, total);
+            }
+        }
+        out.flush()
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", file.name, e))?;
+        progress(downloaded, total);
+    }
+
+    let actual = hex::encode(hasher.finalize());
+    if let Some(expected) = &file.sha256 {
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            let _ = tokio::fs::remove_file(part).await;
+            return Err(format!(
+                "Checksum mismatch for {}; the download was discarded, try again",
+                file.name
+            ));
+        }
+    }
+    Ok(actual)
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::{AppHandle, Emitter};
+
+use super::download::{fetch, CANCELLED};
+use super::types::{InstalledFile, InstalledModel, ModelDownloadProgress, ModelSpec};
+use crate::db::now_millis;
+use crate::storage::space::ensure_free;
+
+const MANIFEST: &str = "model.json";
+const PARTIAL_DIR: &str = ".partial";
+
+/// Downloads and installed models, shared by every local-model subsystem
+pub struct ModelManager {
+    root: PathBuf,
+    /// Downloads in progress by `kind/id`, with their cancel flags
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// A kind, id or file name that is safe to use as one path component
+fn check_name(what: &str, name: &str) -> Result<(), String> {
+    if name.trim().is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\', ':'])
+        || name.ends_with(".part")
+    {
+        return Err(format!("Invalid model {}: {}", what, name));
+    }
+    Ok(())
+}
+
+fn key(kind: &str, id: &str) -> String {
+    format!("{}/{}", kind, id)
+}
+
+/// Unregisters a download however it ends
+struct Running<'a> {
+    manager: &'a ModelManager,
+    key: String,
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.manager.running.lock().unwrap().remove(&self.key);
+    }
+}
+
+struct Reporter<'a> {
+    app: &'a AppHandle,
+    event: ModelDownloadProgress,
+}
+
+impl Reporter<'_> {
+    fn emit(&self) {
+        if let Err(e) = self.app.emit("model-download-progress", &self.event) {
+            eprintln!("Failed to emit model download progress: {}", e);
+        }
+    }
+
+    fn stage(&mut self, stage: &str) {
+        self.event.stage = stage.to_string();
+        self.emit();
+    }
+}
+
+/// An installed model under `root`, for code that runs without the managed
+/// `ModelManager` at hand
+pub fn installed_in(root: &Path, kind: &str, id: &str) -> Option<InstalledModel> {
+    if check_name("kind", kind).is_err() || check_name("id", id).is_err() {
+        return None;
+    }
+    ModelManager::read_manifest(&root.join(kind).join(id))
+}
+
+impl ModelManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn model_dir(&self, kind: &str, id: &str) -> PathBuf {
+        self.root.join(kind).join(id)
+    }
+
+    fn partial_dir(&self, kind: &str, id: &str) -> PathBuf {
+        self.root.join(PARTIAL_DIR).join(kind).join(id)
+    }
+
+    fn read_manifest(dir: &Path) -> Option<InstalledModel> {
+        let raw = std::fs::read_to_string(dir.join(MANIFEST)).ok()?;
+        let mut model: InstalledModel = serde_json::from_str(&raw).ok()?;
+        model.path = dir.to_string_lossy().into_owned();
+        Some(model)
+    }
+
+    /// An installed model, for the subsystem about to load it
+    pub fn installed(&self, kind: &str, id: &str) -> Option<InstalledModel> {
+        installed_in(&self.root, kind, id)
+    }
+
+    /// Installed models of one kind, or of every kind, by kind and name
+    pub fn list(&self, kind: Option<&str>) -> Vec<InstalledModel> {
+        let kinds: Vec<PathBuf> = match kind {
+            Some(kind) if check_name("kind", kind).is_ok() => vec![self.root.join(kind)],
+            Some(_) => Vec::new(),
+            None => std::fs::read_dir(&self.root)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .map(|e| e.path())
+                .collect(),
+        };
+        let mut models: Vec<InstalledModel> = kinds
+            .iter()
+            .flat_map(|dir| std::fs::read_dir(dir).into_iter().flatten().flatten())
+            .filter_map(|e| Self::read_manifest(&e.path()))
+            .collect();
+        models.sort_by(|a, b| {
+            (a.kind.as_str(), a.name.to_lowercase()).cmp(&(b.kind.as_str(), b.name.to_lowercase()))
+        });
+        models
+    }
+
+    /// Ask a running download to stop. What it fetched so far is kept for
+    /// the next attempt. Returns false when it isn't running.
+    pub fn cancel(&self, kind: &str, id: &str) -> bool {
+        match self.running.lock().unwrap().get(&key(kind, id)) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Delete an installed model along with any unfinished download of it
+    pub fn remove(&self, kind: &str, id: &str) -> Result<(), String> {
+        check_name("kind", kind)?;
+        check_name("id", id)?;
+        if self.running.lock().unwrap().contains_key(&key(kind, id)) {
+            return Err("Cancel the download before removing the model".to_string());
+        }
+        for dir in [self.model_dir(kind, id), self.partial_dir(kind, id)] {
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)
+                    .map_err(|e| format!("Failed to remove model: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Download a model's files, resuming an earlier attempt, check them
+    /// against their checksums and install the model, replacing any
+    /// installed copy. Emits `model-download-progress` along the way.
+    pub async fn download(
+        &self,
+        app: &AppHandle,
+        spec: ModelSpec,
+    ) -> Result<InstalledModel, String> {
+        check_name("kind", &spec.kind)?;
+        check_name("id", &spec.id)?;
+        if spec.files.is_empty() {
+            return Err("The model has no files".to_string());
+        }
+        for file in &spec.files {
+            check_name("file name", &file.name)?;
+        }
+
+        let key = key(&spec.kind, &spec.id);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        {
+            let mut running = self.running.lock().unwrap();
+            if running.contains_key(&key) {
+                return Err(format!("{} is already downloading", spec.id));
+            }
+            running.insert(key.clone(), cancelled.clone());
+        }
+        let _running = Running { manager: self, key };
+
+        let mut reporter = Reporter {
+            app,
+            event: ModelDownloadProgress {
+                kind: spec.kind.clone(),
+                id: spec.id.clone(),
+                file: String::new(),
+                file_index: 0,
+                files: spec.files.len(),
+                downloaded: 0,
+                total: None,
+                stage: "downloading".to_string(),
+                error: None,
+            },
+        };
+        let result = self
+            .fetch_and_install(&spec, &cancelled, &mut reporter)
+            .await;
+        match &result {
+            Ok(_) => reporter.stage("done"),
+            Err(e) => {
+                reporter.event.error = Some(e.clone());
+                reporter.stage(if e == CANCELLED {
+                    "cancelled"
+                } else {
+                    "failed"
+                });
+            }
+        }
+        result
+    }
+
+    async fn fetch_and_install(
+        &self,
+        spec: &ModelSpec,
+        cancelled: &AtomicBool,
+        reporter: &mut Reporter<'_>,
+    ) -> Result<InstalledModel, String> {
+        let staging = self.partial_dir(&spec.kind, &spec.id);
+        std::fs::create_dir_all(&staging)
+            .map_err(|e| format!("Failed to create model folder: {}", e))?;
+        let part = |name: &str| staging.join(format!("{}.part", name));
+
+        // Room for what's left to fetch, where the sizes are known
+        let needed: u64 = spec
+            .files
+            .iter()
+            .map(|f| {
+                let have = std::fs::metadata(part(&f.name)).map_or(0, |m| m.len());
+                f.size.unwrap_or(0).saturating_sub(have)
+            })
+            .sum();
+        ensure_free(&self.root, needed)?;
+
+        let client = reqwest::Client::new();
+        let mut files = Vec::new();
+        for (index, file) in spec.files.iter().enumerate() {
+            reporter.event.file = file.name.clone();
+            reporter.event.file_index = index;
+            reporter.event.stage = "downloading".to_string();
+            let sha256 = fetch(
+                &client,
+                file,
+                &part(&file.name),
+                cancelled,
+                |downloaded, total| {
+                    reporter.event.downloaded = downloaded;
+                    reporter.event.total = total;
+                    reporter.emit();
+                },
+            )
+            .await?;
+            files.push((file, sha256));
+        }
+
+        reporter.stage("installing");
+        let mut installed_files = Vec::new();
+        for (file, sha256) in files {
+            let path = staging.join(&file.name);
+            std::fs::rename(part(&file.name), &path)
+                .map_err(|e| format!("Failed to install {}: {}", file.name, e))?;
+            installed_files.push(InstalledFile {
+                name: file.name.clone(),
+                size: std::fs::metadata(&path).map_or(0, |m| m.len()),
+                sha256,
+                url: file.url.clone(),
+            });
+        }
+        let mut model = InstalledModel {
+            kind: spec.kind.clone(),
+            id: spec.id.clone(),
+            name: spec
+                .name
+                .clone()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| spec.id.clone()),
+            size: installed_files.iter().map(|f| f.size).sum(),
+            files: installed_files,
+            installed_at: now_millis(),
+            path: String::new(),
+        };
+        let manifest = serde_json::to_string_pretty(&model)
+            .map_err(|e| format!("Failed to write model manifest: {}", e))?;
+        std::fs::write(staging.join(MANIFEST), manifest)
+            .map_err(|e| format!("Failed to write model manifest: {}", e))?;
+
+        let target = self.model_dir(&spec.kind, &spec.id);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)
+                .map_err(|e| format!("Failed to replace installed model: {}", e))?;
+        }
+        std::fs::create_dir_all(self.root.join(&spec.kind))
+            .map_err(|e| format!("Failed to create model folder: {}", e))?;
+        std::fs::rename(&staging, &target)
+            .map_err(|e| format!("Failed to install model: {}", e))?;
+        model.path = target.to_string_lossy().into_owned();
+        Ok(model)
+    }
+}
@@ -0,0 +1,12 @@
+//! Downloaded model files for the local features (speech recognition,
+//! voices, embeddings and so on). Each model lives in
+//! `<local>/models/<kind>/<id>/` with a `model.json` describing it;
+//! downloads are staged under `.partial/` and resumed from there, and only
+//! move into place once every file passed its checksum.
+
+pub mod commands;
+pub mod download;
+pub mod manager;
+pub mod types;
+
+pub use manager::ModelManager;
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// A model to download, as the subsystem using it describes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSpec {
+    /// Subsystem the model belongs to, e.g. `whisper`, `piper`, `embeddings`
+    pub kind: String,
+    /// Stable id within the kind, e.g. `ggml-base.en`
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub files: Vec<ModelFileSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFileSpec {
+    pub name: String,
+    pub url: String,
+    /// Hex SHA-256 the download must match
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Expected size, for the disk space check before anything downloads
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// `model.json` of an installed model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledModel {
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub files: Vec<InstalledFile>,
+    /// Bytes on disk
+    pub size: u64,
+    pub installed_at: i64,
+    /// Folder holding the files; filled in when listed
+    #[serde(default, skip_deserializing)]
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+    pub url: String,
+}
+
+/// Emitted on `model-download-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDownloadProgress {
+    pub kind: String,
+    pub id: String,
+    /// File being fetched
+    pub file: String,
+    /// 0-based index of the file, out of `files`
+    pub file_index: usize,
+    pub files: usize,
+    /// Bytes of this file on disk so far, including what an earlier attempt
+    /// left
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    /// `downloading`, `verifying`, `installing`, `done`, `cancelled` or
+    /// `failed`
+    pub stage: String,
+    pub error: Option<String>,
+}
@@ -0,0 +1,64 @@
+use serde_json::json;
+use tauri::State;
+
+use super::store;
+use super::types::NpcAgenda;
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+#[tauri::command]
+pub async fn get_npc_agendas(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<NpcAgenda>, String> {
+    store::load_agendas(db.pool(), &story_id).await
+}
+
+/// Set an NPC's agenda by hand. It holds until the next simulated day.
+#[tauri::command]
+pub async fn set_npc_agenda(
+    db: State<'_, DbState>,
+    story_id: String,
+    character_id: String,
+    goal: String,
+    location: String,
+    activity: String,
+) -> Result<NpcAgenda, String> {
+    let pool = db.pool();
+    let npc = store::load_npc(pool, &character_id).await?;
+    let day = store::current_day(pool, &story_id).await?;
+    let agenda = store::agenda_for(&story_id, &npc, goal, location, activity, day);
+    store::save_agenda(pool, &agenda).await?;
+    Ok(agenda)
+}
+
+#[tauri::command]
+pub async fn delete_npc_agenda(
+    db: State<'_, DbState>,
+    story_id: String,
+    character_id: String,
+) -> Result<(), String> {
+    store::delete_agenda(db.pool(), &story_id, &character_id).await
+}
+
+/// Queue the off-screen simulation for the current story day now, even if
+/// it already ran today or is switched off
+#[tauri::command]
+pub async fn simulate_npcs(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+) -> Result<String, String> {
+    let pool = db.pool();
+    let day = store::current_day(pool, &story_id).await?;
+    store::claim_day(pool, &story_id, day).await?;
+    queue
+        .enqueue(
+            pool,
+            Some(&story_id),
+            JobKind::NpcAgenda,
+            json!({ "day": day }),
+        )
+        .await
+}
@@ -0,0 +1,158 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter};
+
+use super::store::{agenda_for, load_agendas, load_settings, major_npcs, save_agenda};
+use super::types::{Npc, NpcAgenda};
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::context::branch::visible_entries;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config::{self, LlmConfig};
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str =
+    "You simulate what a non-player character in an interactive story does \
+off-screen. Given who they are, what they were up to before and the latest passages, decide what \
+they want now, where they are and what they are doing as the new story day begins. Stay true to \
+the character and the story so far: take small, plausible steps rather than sudden reversals, and \
+never decide anything for the player's character. Respond with JSON only, in this shape: \
+{\"goal\": \"<what they want>\", \"location\": \"<where they are>\", \
+\"activity\": \"<what they are doing, one sentence>\"}.";
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "npc_agenda",
+        schema: json!({
+            "type": "object",
+            "required": ["goal", "location", "activity"],
+            "properties": {
+                "goal": { "type": "string" },
+                "location": { "type": "string" },
+                "activity": { "type": "string" }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AgendaReply {
+    goal: String,
+    location: String,
+    activity: String,
+}
+
+/// Background job: run one cheap model call per major NPC to move their
+/// private agenda on to a new story day. NPCs whose call fails keep their
+/// old agenda. The updated agendas are emitted on `npc-agendas`.
+pub async fn run_agenda_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let story_id = job
+        .story_id
+        .as_deref()
+        .ok_or("NPC agenda job is missing its story")?;
+    let day = job
+        .payload
+        .get("day")
+        .and_then(|v| v.as_i64())
+        .ok_or("NPC agenda job is missing day")?;
+
+    let settings = load_settings(pool).await?;
+    let entries = visible_entries(pool, story_id).await?;
+    let story_text = entries
+        .iter()
+        .map(|e| e.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let npcs = major_npcs(pool, story_id, &story_text, settings.max_npcs).await?;
+    if npcs.is_empty() {
+        return Ok(json!({ "updated": [] }));
+    }
+    let passages = entries[entries.len().saturating_sub(settings.recent_entries)..]
+        .iter()
+        .map(|e| e.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let calendar = load_calendar(pool, story_id).await?;
+    let date = calendar
+        .date(&load_time_tracker(pool, story_id).await?)
+        .formatted;
+    let previous = load_agendas(pool, story_id).await?;
+
+    let llm = config::resolve_service(pool, "npcAgenda", "classification").await?;
+    let mut updated = Vec::new();
+    for npc in &npcs {
+        let before = previous.iter().find(|a| a.character_id == npc.id);
+        let prompt = prompt_for(npc, before, &date, &passages);
+        match simulate_one(pool, story_id, &llm, prompt).await {
+            Ok(reply) if !reply.goal.trim().is_empty() => {
+                let agenda = agenda_for(
+                    story_id,
+                    npc,
+                    reply.goal.trim().to_string(),
+                    reply.location.trim().to_string(),
+                    reply.activity.trim().to_string(),
+                    day,
+                );
+                save_agenda(pool, &agenda).await?;
+                updated.push(agenda);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to simulate {}: {}", npc.name, e),
+        }
+    }
+    if let Err(e) = app.emit("npc-agendas", &updated) {
+        eprintln!("Failed to emit NPC agenda event: {}", e);
+    }
+    Ok(json!({ "updated": updated }))
+}
+
+fn prompt_for(npc: &Npc, before: Option<&NpcAgenda>, date: &str, passages: &str) -> String {
+    let mut about = vec![format!("Character: {}", npc.name)];
+    for (label, value) in [
+        ("Description", &npc.description),
+        ("Relationship to the player", &npc.relationship),
+        ("Traits", &npc.traits),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            about.push(format!("{}: {}", label, value.trim()));
+        }
+    }
+    if let Some(before) = before {
+        about.push(format!(
+            "Previously: wanted {}; at {}; {}",
+            before.goal, before.location, before.activity
+        ));
+    }
+    format!(
+        "{}\n\nStory date: {}\n\nLatest passages:\n{}",
+        about.join("\n"),
+        date,
+        passages
+    )
+}
+
+async fn simulate_one(
+    pool: &SqlitePool,
+    story_id: &str,
+    llm: &LlmConfig,
+    prompt: String,
+) -> Result<AgendaReply, String> {
+    complete_structured(
+        pool,
+        "npcAgenda",
+        Some(story_id),
+        llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await
+}
@@ -0,0 +1,187 @@
+use sqlx::SqlitePool;
+
+use super::types::{Npc, NpcAgenda, NpcAgendaSettings};
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::context::branch::characters;
+use crate::db::now_millis;
+use crate::environment::store::story_day;
+use crate::llm::config::get_setting;
+use crate::lore::types::LoreEntry;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<NpcAgendaSettings, String> {
+    Ok(get_setting(pool, "npc_agenda_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+pub async fn load_agendas(pool: &SqlitePool, story_id: &str) -> Result<Vec<NpcAgenda>, String> {
+    sqlx::query_as(
+        "SELECT story_id, character_id, name, goal, location, activity, day, updated_at \
+         FROM npc_agendas WHERE story_id = ? ORDER BY name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load NPC agendas: {}", e))
+}
+
+pub async fn save_agenda(pool: &SqlitePool, agenda: &NpcAgenda) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO npc_agendas \
+         (story_id, character_id, name, goal, location, activity, day, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(story_id, character_id) DO UPDATE SET name = excluded.name, \
+         goal = excluded.goal, location = excluded.location, activity = excluded.activity, \
+         day = excluded.day, updated_at = excluded.updated_at",
+    )
+    .bind(&agenda.story_id)
+    .bind(&agenda.character_id)
+    .bind(&agenda.name)
+    .bind(&agenda.goal)
+    .bind(&agenda.location)
+    .bind(&agenda.activity)
+    .bind(agenda.day)
+    .bind(agenda.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save NPC agenda: {}", e))?;
+    Ok(())
+}
+
+pub async fn delete_agenda(
+    pool: &SqlitePool,
+    story_id: &str,
+    character_id: &str,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM npc_agendas WHERE story_id = ? AND character_id = ?")
+        .bind(story_id)
+        .bind(character_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete NPC agenda: {}", e))?;
+    Ok(())
+}
+
+pub async fn load_npc(pool: &SqlitePool, character_id: &str) -> Result<Npc, String> {
+    sqlx::query_as(
+        "SELECT id, name, description, relationship, traits FROM characters WHERE id = ?",
+    )
+    .bind(character_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load character: {}", e))?
+    .ok_or_else(|| format!("Character not found: {}", character_id))
+}
+
+/// Active characters other than the protagonist, most mentioned in
+/// `story_text` first. Characters the story never names are left out.
+pub async fn major_npcs(
+    pool: &SqlitePool,
+    story_id: &str,
+    story_text: &str,
+    max: usize,
+) -> Result<Vec<Npc>, String> {
+    let npcs = characters(pool, story_id)
+        .await?
+        .into_iter()
+        .filter(|c| {
+            c.status.as_deref().unwrap_or("active") == "active"
+                && c.relationship.as_deref() != Some("self")
+        })
+        .map(|c| Npc {
+            id: c.id,
+            name: c.name,
+            description: c.description,
+            relationship: c.relationship,
+            traits: c.traits,
+        });
+    let text = story_text.to_lowercase();
+    let mut ranked: Vec<(usize, Npc)> = npcs
+        .into_iter()
+        .map(|npc| (text.matches(&npc.name.to_lowercase()).count(), npc))
+        .filter(|(mentions, npc)| *mentions > 0 && !npc.name.trim().is_empty())
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    Ok(ranked.into_iter().take(max).map(|(_, npc)| npc).collect())
+}
+
+/// The story day the clock is on
+pub async fn current_day(pool: &SqlitePool, story_id: &str) -> Result<i64, String> {
+    let calendar = load_calendar(pool, story_id).await?;
+    let time = load_time_tracker(pool, story_id).await?;
+    Ok(story_day(&calendar, &time))
+}
+
+/// Mark `day` as simulated unless it (or a later day) already was. Returns
+/// whether this call claimed it, so a day is only queued once.
+pub async fn claim_day(pool: &SqlitePool, story_id: &str, day: i64) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE stories SET npc_simulated_day = ? \
+         WHERE id = ? AND (npc_simulated_day IS NULL OR npc_simulated_day < ?)",
+    )
+    .bind(day)
+    .bind(story_id)
+    .bind(day)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update story: {}", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The day the pipeline should simulate, when the simulation is on and the
+/// clock has reached a day not simulated yet
+pub async fn due_day(pool: &SqlitePool, story_id: &str) -> Result<Option<i64>, String> {
+    if !load_settings(pool).await?.enabled {
+        return Ok(None);
+    }
+    let day = current_day(pool, story_id).await?;
+    Ok(claim_day(pool, story_id, day).await?.then_some(day))
+}
+
+/// Append each NPC's off-screen agenda to their character entry, so it
+/// surfaces whenever the entry fires as they re-enter the scene
+pub async fn annotate_lore(
+    pool: &SqlitePool,
+    story_id: &str,
+    entries: &mut [LoreEntry],
+) -> Result<(), String> {
+    let agendas = load_agendas(pool, story_id).await?;
+    if agendas.is_empty() {
+        return Ok(());
+    }
+    for entry in entries.iter_mut().filter(|e| e.kind == "character") {
+        let agenda = agendas.iter().find(|a| {
+            a.name.eq_ignore_ascii_case(&entry.name)
+                || entry
+                    .aliases
+                    .iter()
+                    .any(|alias| a.name.eq_ignore_ascii_case(alias))
+        });
+        if let Some(agenda) = agenda {
+            entry.description = format!("{}\n{}", entry.description, agenda.render());
+        }
+    }
+    Ok(())
+}
+
+/// Build an agenda row for `npc` stamped now
+pub fn agenda_for(
+    story_id: &str,
+    npc: &Npc,
+    goal: String,
+    location: String,
+    activity: String,
+    day: i64,
+) -> NpcAgenda {
+    NpcAgenda {
+        story_id: story_id.to_string(),
+        character_id: npc.id.clone(),
+        name: npc.name.clone(),
+        goal,
+        location,
+        activity,
+        day,
+        updated_at: now_millis(),
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// What an NPC is up to off-screen. Stored in `npc_agendas` and emitted on
+/// `npc-agendas` after each simulated day.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct NpcAgenda {
+    pub story_id: String,
+    pub character_id: String,
+    pub name: String,
+    pub goal: String,
+    pub location: String,
+    pub activity: String,
+    /// Story day the agenda is for
+    pub day: i64,
+    pub updated_at: i64,
+}
+
+impl NpcAgenda {
+    /// The agenda as appended to the NPC's lorebook entry
+    pub fn render(&self) -> String {
+        format!(
+            "Off-screen (day {}): wants {}; at {}; {}",
+            self.day + 1,
+            self.goal,
+            self.location,
+            self.activity
+        )
+    }
+}
+
+/// User settings for the off-screen simulation (`npc_agenda_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NpcAgendaSettings {
+    pub enabled: bool,
+    /// Most-mentioned NPCs simulated each day
+    pub max_npcs: usize,
+    /// Latest passages each NPC's call sees
+    pub recent_entries: usize,
+}
+
+impl Default for NpcAgendaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_npcs: 4,
+            recent_entries: 12,
+        }
+    }
+}
+
+/// A character the simulation can run
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Npc {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub relationship: Option<String>,
+    pub traits: Option<String>,
+}
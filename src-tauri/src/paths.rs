@@ -0,0 +1,163 @@
+//! Where user data lives. Each profile has a folder for its database and
+//! backups; commands take these paths from the managed `DataPaths` rather
+//! than building them from the app data folder themselves.
+//!
+//! In portable mode (desktop only) everything goes into a `data/` folder
+//! beside the executable instead, so the app can run from a USB stick.
+//! Starting with `--portable` turns it on for good by leaving a `portable`
+//! marker file next to the executable.
+//!
+//! Users can also move their data to a folder of their choosing. The choice
+//! is kept in `location.json` in the usual (or portable) folder, which stays
+//! put along with machine-local things like downloaded models.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+pub const DATABASE_FILE: &str = "aventura.db";
+const LOCATION_FILE: &str = "location.json";
+
+#[cfg(desktop)]
+const PORTABLE_DIR: &str = "data";
+#[cfg(desktop)]
+const PORTABLE_MARKER: &str = "portable";
+#[cfg(desktop)]
+const PORTABLE_FLAG: &str = "--portable";
+
+/// Profile used for this run; switching profiles restarts the app
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataPaths {
+    /// Holds the profile list and every profile's folder
+    pub root: PathBuf,
+    pub profile_id: String,
+    pub profile_dir: PathBuf,
+    pub database: PathBuf,
+    /// Connection string for the SQL plugin, the one migrations are
+    /// registered under. It's absolute, so the plugin opens this file
+    /// instead of one in its own folder.
+    pub database_url: String,
+    /// Machine-local data that never moves with the rest (models)
+    pub local: PathBuf,
+    pub portable: bool,
+    /// Whether the data was moved to a folder the user chose
+    pub custom: bool,
+}
+
+impl DataPaths {
+    pub fn new(base: AppDirs, profile_id: String, profile_dir: PathBuf, database: PathBuf) -> Self {
+        let _ = ACTIVE_PROFILE.set(profile_id.clone());
+        Self {
+            root: base.data,
+            profile_id,
+            profile_dir,
+            database_url: format!("sqlite:{}", database.display()),
+            database,
+            local: base.local,
+            portable: base.portable,
+            custom: base.custom,
+        }
+    }
+
+    pub fn backups(&self) -> PathBuf {
+        self.profile_dir.join("backups")
+    }
+}
+
+/// The profile this run uses, for state kept outside its folder (keychain)
+pub fn active_profile() -> &'static str {
+    ACTIVE_PROFILE
+        .get()
+        .map(String::as_str)
+        .unwrap_or("default")
+}
+
+/// The folders everything else is placed under
+pub struct AppDirs {
+    pub data: PathBuf,
+    /// Where the default profile's database is. Without portable mode or a
+    /// custom folder this is the SQL plugin's own folder, where earlier
+    /// versions created it.
+    pub config: PathBuf,
+    pub local: PathBuf,
+    pub portable: bool,
+    pub custom: bool,
+}
+
+/// Contents of `location.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DataLocation {
+    data_dir: Option<PathBuf>,
+}
+
+/// Point the app at `data_dir` from the next launch on
+pub fn save_location(local: &Path, data_dir: &Path) -> Result<(), String> {
+    let location = DataLocation {
+        data_dir: Some(data_dir.to_path_buf()),
+    };
+    let bytes = serde_json::to_vec_pretty(&location)
+        .map_err(|e| format!("Failed to serialize data location: {}", e))?;
+    std::fs::create_dir_all(local)
+        .and_then(|_| std::fs::write(local.join(LOCATION_FILE), bytes))
+        .map_err(|e| format!("Failed to save data location: {}", e))
+}
+
+/// The `data/` folder beside the executable, when running portable
+#[cfg(desktop)]
+fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let marker = exe_dir.join(PORTABLE_MARKER);
+    let flagged = std::env::args().any(|arg| arg == PORTABLE_FLAG);
+    if flagged && !marker.exists() {
+        if let Err(e) = std::fs::write(&marker, b"") {
+            eprintln!("Failed to save portable mode: {}", e);
+        }
+    }
+    let portable = flagged || marker.exists();
+    portable.then(|| exe_dir.join(PORTABLE_DIR))
+}
+
+#[cfg(mobile)]
+fn portable_dir() -> Option<PathBuf> {
+    None
+}
+
+pub fn app_dirs(app: &AppHandle) -> Result<AppDirs, String> {
+    let (local, config, portable) = match portable_dir() {
+        Some(dir) => (dir.clone(), dir, true),
+        None => (
+            app.path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to find app data folder: {}", e))?,
+            app.path()
+                .app_config_dir()
+                .map_err(|e| format!("Failed to find app config folder: {}", e))?,
+            false,
+        ),
+    };
+    let custom = std::fs::read(local.join(LOCATION_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<DataLocation>(&bytes).ok())
+        .and_then(|location| location.data_dir);
+    Ok(match custom {
+        Some(dir) => AppDirs {
+            data: dir.clone(),
+            config: dir,
+            local,
+            portable,
+            custom: true,
+        },
+        None => AppDirs {
+            data: local.clone(),
+            config,
+            local,
+            portable,
+            custom: false,
+        },
+    })
+}
@@ -0,0 +1,88 @@
+use tauri::{AppHandle, State};
+
+use super::runner;
+use super::store::{load_pipeline, load_run};
+use super::types::{PipelineRun, PipelineStepConfig};
+use crate::db::DbState;
+
+/// A story's post-response pipeline, in order
+#[tauri::command]
+pub async fn get_turn_pipeline(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<PipelineStepConfig>, String> {
+    load_pipeline(db.pool(), &story_id).await
+}
+
+/// Reorder or toggle a story's pipeline steps; `None` restores the default
+#[tauri::command]
+pub async fn set_turn_pipeline(
+    db: State<'_, DbState>,
+    story_id: String,
+    steps: Option<Vec<PipelineStepConfig>>,
+) -> Result<Vec<PipelineStepConfig>, String> {
+    let raw = match &steps {
+        Some(steps) => Some(
+            serde_json::to_string(steps)
+                .map_err(|e| format!("Failed to serialize turn pipeline: {}", e))?,
+        ),
+        None => None,
+    };
+    sqlx::query("UPDATE stories SET turn_pipeline = ? WHERE id = ?")
+        .bind(raw)
+        .bind(&story_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to save turn pipeline: {}", e))?;
+    load_pipeline(db.pool(), &story_id).await
+}
+
+/// Run the story's pipeline over a saved response entry. Post-processing and
+/// option extraction rewrite the entry; the other steps queue background
+/// jobs. Progress arrives on `turn-pipeline`; returns the finished run.
+#[tauri::command]
+pub async fn run_turn_pipeline(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    entry_id: String,
+    text: String,
+    allowed_types: Option<Vec<String>>,
+) -> Result<PipelineRun, String> {
+    runner::start(
+        &app,
+        db.pool(),
+        &story_id,
+        &entry_id,
+        text,
+        allowed_types.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_turn_pipeline_run(
+    db: State<'_, DbState>,
+    run_id: String,
+) -> Result<Option<PipelineRun>, String> {
+    load_run(db.pool(), &run_id).await
+}
+
+/// Continue a failed run from the step that failed
+#[tauri::command]
+pub async fn retry_turn_pipeline(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    run_id: String,
+) -> Result<PipelineRun, String> {
+    let mut run = load_run(db.pool(), &run_id)
+        .await?
+        .ok_or_else(|| format!("Pipeline run not found: {}", run_id))?;
+    if run.status != "failed" {
+        return Err("Only failed pipeline runs can be retried".to_string());
+    }
+    run.status = "running".to_string();
+    run.error = None;
+    runner::drive(&app, db.pool(), &mut run).await;
+    Ok(run)
+}
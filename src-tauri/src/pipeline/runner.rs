@@ -0,0 +1,247 @@
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use super::store::{load_pipeline, save_run, unfinished_runs};
+use super::types::{PipelineRun, PipelineStep};
+use crate::db::{now_millis, DbState};
+use crate::images::scene::should_illustrate;
+use crate::jobs::queue::JobQueue;
+use crate::jobs::types::JobKind;
+use crate::lore::world_update::{take_snapshot, DEFAULT_UPDATE_ENTRIES};
+use crate::postprocess::commands::process_output;
+use crate::postprocess::options;
+
+/// Start a pipeline run over a freshly saved entry and drive it to the end
+pub async fn start(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: &str,
+    text: String,
+    allowed_types: Vec<String>,
+) -> Result<PipelineRun, String> {
+    let steps = load_pipeline(pool, story_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.enabled)
+        .map(|s| s.step)
+        .collect();
+    let now = now_millis();
+    let mut run = PipelineRun {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.to_string(),
+        entry_id: entry_id.to_string(),
+        steps,
+        next_step: 0,
+        status: "running".to_string(),
+        text,
+        allowed_types,
+        options: Vec::new(),
+        jobs: Vec::new(),
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+    save_run(pool, &mut run).await?;
+    drive(app, pool, &mut run).await;
+    Ok(run)
+}
+
+/// Run the remaining steps, saving and emitting the run after each one.
+/// A step interrupted by a restart runs again when the run resumes.
+pub async fn drive(app: &AppHandle, pool: &SqlitePool, run: &mut PipelineRun) {
+    while let Some(&step) = run.steps.get(run.next_step) {
+        if let Err(e) = run_step(app, pool, run, step).await {
+            run.status = "failed".to_string();
+            run.error = Some(e);
+            finish(app, pool, run).await;
+            return;
+        }
+        run.next_step += 1;
+        if let Err(e) = save_run(pool, run).await {
+            eprintln!("{}", e);
+        }
+        emit(app, run);
+    }
+    run.status = "completed".to_string();
+    finish(app, pool, run).await;
+}
+
+async fn finish(app: &AppHandle, pool: &SqlitePool, run: &mut PipelineRun) {
+    if let Err(e) = save_run(pool, run).await {
+        eprintln!("{}", e);
+    }
+    emit(app, run);
+}
+
+fn emit(app: &AppHandle, run: &PipelineRun) {
+    if let Err(e) = app.emit("turn-pipeline", run) {
+        eprintln!("Failed to emit pipeline event: {}", e);
+    }
+}
+
+async fn run_step(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    run: &mut PipelineRun,
+    step: PipelineStep,
+) -> Result<(), String> {
+    let queue = app.state::<JobQueue>();
+    let job = match step {
+        PipelineStep::Postprocess => {
+            let text = process_output(app, pool, &run.story_id, &run.text).await?;
+            return set_text(pool, run, text).await;
+        }
+        PipelineStep::ExtractSuggestions => {
+            let extracted = options::extract(&run.text, &run.allowed_types);
+            run.options = extracted.options;
+            return set_text(pool, run, extracted.text).await;
+        }
+        PipelineStep::LoreUpdate => {
+            let snapshot = take_snapshot(pool, &run.story_id, DEFAULT_UPDATE_ENTRIES).await?;
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::WorldUpdate,
+                    json!({ "snapshot": snapshot }),
+                )
+                .await?
+        }
+        PipelineStep::TimeUpdate => {
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::TimeUpdate,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::BeatCheck => {
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::BeatCheck,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::Ambience => {
+            // Missing audio is no reason to fail the turn
+            if let Err(e) = crate::audio::select::update(app, pool, &run.story_id).await {
+                eprintln!("Failed to update ambience: {}", e);
+            }
+            return Ok(());
+        }
+        PipelineStep::IllustrateScene => {
+            if !should_illustrate(pool, &run.story_id, &run.entry_id).await? {
+                return Ok(());
+            }
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::SceneIllustration,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::ArcCheck => {
+            if !crate::director::store::check_due(pool, &run.story_id).await? {
+                return Ok(());
+            }
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::ArcDrift,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::NpcAgendas => {
+            let Some(day) = crate::npcs::store::due_day(pool, &run.story_id).await? else {
+                return Ok(());
+            };
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::NpcAgenda,
+                    json!({ "day": day }),
+                )
+                .await?
+        }
+        PipelineStep::FactionUpdate => {
+            // Stories start tracking factions once one is created by hand or
+            // by an explicit faction update
+            if !crate::factions::store::has_factions(pool, &run.story_id).await? {
+                return Ok(());
+            }
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::FactionExtraction,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::LocationUpdate => {
+            if !crate::travel::store::has_locations(pool, &run.story_id).await? {
+                return Ok(());
+            }
+            queue
+                .enqueue(
+                    pool,
+                    Some(&run.story_id),
+                    JobKind::LocationUpdate,
+                    json!({ "entryId": run.entry_id }),
+                )
+                .await?
+        }
+        PipelineStep::CombatNarrated => {
+            crate::combat::store::mark_narrated(pool, &run.story_id).await?;
+            return Ok(());
+        }
+    };
+    run.jobs.push(job);
+    Ok(())
+}
+
+/// Store changed text on the run and the entry, so queued jobs read it
+async fn set_text(pool: &SqlitePool, run: &mut PipelineRun, text: String) -> Result<(), String> {
+    if text == run.text {
+        return Ok(());
+    }
+    sqlx::query("UPDATE story_entries SET content = ? WHERE id = ?")
+        .bind(&text)
+        .bind(&run.entry_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update entry: {}", e))?;
+    run.text = text;
+    Ok(())
+}
+
+/// Resume runs a previous session left unfinished
+pub fn resume(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The table only exists once the frontend has run migrations
+        let runs = loop {
+            match unfinished_runs(&pool).await {
+                Ok(runs) => break runs,
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        for mut run in runs {
+            drive(&app, &pool, &mut run).await;
+        }
+    });
+}
@@ -0,0 +1,113 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::types::{PipelineRun, PipelineStep, PipelineStepConfig};
+use crate::db::now_millis;
+
+/// Every step, enabled, in the order the frontend used to chain them
+pub fn default_pipeline() -> Vec<PipelineStepConfig> {
+    PipelineStep::ALL
+        .into_iter()
+        .map(|step| PipelineStepConfig {
+            step,
+            enabled: true,
+        })
+        .collect()
+}
+
+/// A story's pipeline. Steps missing from a saved pipeline (e.g. added in a
+/// later version) are appended enabled.
+pub async fn load_pipeline(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<PipelineStepConfig>, String> {
+    let raw: Option<String> = sqlx::query_scalar("SELECT turn_pipeline FROM stories WHERE id = ?")
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load turn pipeline: {}", e))?
+        .flatten();
+    let Some(mut steps) =
+        raw.and_then(|raw| serde_json::from_str::<Vec<PipelineStepConfig>>(&raw).ok())
+    else {
+        return Ok(default_pipeline());
+    };
+    for step in PipelineStep::ALL {
+        if !steps.iter().any(|s| s.step == step) {
+            steps.push(PipelineStepConfig {
+                step,
+                enabled: true,
+            });
+        }
+    }
+    Ok(steps)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn run_from_row(r: &SqliteRow) -> PipelineRun {
+    let json = |column: &str| r.get::<String, _>(column);
+    PipelineRun {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        entry_id: r.get("entry_id"),
+        steps: serde_json::from_str(&json("steps")).unwrap_or_default(),
+        next_step: r.get::<i64, _>("next_step") as usize,
+        status: r.get("status"),
+        text: r.get("text"),
+        allowed_types: serde_json::from_str(&json("allowed_types")).unwrap_or_default(),
+        options: serde_json::from_str(&json("options")).unwrap_or_default(),
+        jobs: serde_json::from_str(&json("jobs")).unwrap_or_default(),
+        error: r.get("error"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+/// Insert or update a run, stamping `updated_at`
+pub async fn save_run(pool: &SqlitePool, run: &mut PipelineRun) -> Result<(), String> {
+    run.updated_at = now_millis();
+    sqlx::query(
+        "INSERT OR REPLACE INTO turn_pipeline_runs (id, story_id, entry_id, steps, next_step, status, \
+         text, allowed_types, options, jobs, error, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run.id)
+    .bind(&run.story_id)
+    .bind(&run.entry_id)
+    .bind(to_json(&run.steps))
+    .bind(run.next_step as i64)
+    .bind(&run.status)
+    .bind(&run.text)
+    .bind(to_json(&run.allowed_types))
+    .bind(to_json(&run.options))
+    .bind(to_json(&run.jobs))
+    .bind(&run.error)
+    .bind(run.created_at)
+    .bind(run.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save pipeline run: {}", e))?;
+    Ok(())
+}
+
+pub async fn load_run(pool: &SqlitePool, run_id: &str) -> Result<Option<PipelineRun>, String> {
+    let row = sqlx::query("SELECT * FROM turn_pipeline_runs WHERE id = ?")
+        .bind(run_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load pipeline run: {}", e))?;
+    Ok(row.as_ref().map(run_from_row))
+}
+
+/// Runs left unfinished, oldest first
+pub async fn unfinished_runs(pool: &SqlitePool) -> Result<Vec<PipelineRun>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM turn_pipeline_runs WHERE status = 'running' ORDER BY created_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(run_from_row).collect())
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::postprocess::types::SuggestedOption;
+
+/// One step of the post-response pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStep {
+    /// Output rules and entity name check over the response
+    Postprocess,
+    /// Split a trailing options block off the response
+    ExtractSuggestions,
+    /// Queue a lorebook update from the latest passages
+    LoreUpdate,
+    /// Queue an estimate of the story time the entry covers
+    TimeUpdate,
+    /// Queue a check for story beats the entry resolves
+    BeatCheck,
+    /// Queue a scene illustration when the story's settings say one is due
+    IllustrateScene,
+    /// Switch the ambience to suit the latest passages
+    Ambience,
+    /// Queue a check for drift from the arc plan when one is due
+    ArcCheck,
+    /// Queue the off-screen NPC simulation when a new story day has begun
+    NpcAgendas,
+    /// Queue a check for reputation changes when the story tracks factions
+    FactionUpdate,
+    /// Queue an update of the party's location, checked against travel times
+    LocationUpdate,
+    /// Mark resolved combat rounds as narrated by the response
+    CombatNarrated,
+}
+
+impl PipelineStep {
+    pub const ALL: [PipelineStep; 12] = [
+        PipelineStep::Postprocess,
+        PipelineStep::ExtractSuggestions,
+        PipelineStep::LoreUpdate,
+        PipelineStep::TimeUpdate,
+        PipelineStep::BeatCheck,
+        PipelineStep::IllustrateScene,
+        PipelineStep::Ambience,
+        PipelineStep::ArcCheck,
+        PipelineStep::NpcAgendas,
+        PipelineStep::FactionUpdate,
+        PipelineStep::LocationUpdate,
+        PipelineStep::CombatNarrated,
+    ];
+}
+
+/// A step's place and toggle in a story's pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineStepConfig {
+    pub step: PipelineStep,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One pass of the pipeline over a story entry, saved after every step.
+/// Emitted on `turn-pipeline` whenever a step finishes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineRun {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    /// Enabled steps in order, fixed when the run starts
+    pub steps: Vec<PipelineStep>,
+    /// Index into `steps` of the next step to run
+    pub next_step: usize,
+    /// 'running' | 'completed' | 'failed'
+    pub status: String,
+    /// The entry text as of the last finished step
+    pub text: String,
+    /// Option types the story mode understands, for `extract_suggestions`
+    pub allowed_types: Vec<String>,
+    pub options: Vec<SuggestedOption>,
+    /// Ids of the background jobs the run queued
+    pub jobs: Vec<String>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
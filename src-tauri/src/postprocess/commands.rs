@@ -0,0 +1,182 @@
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, State};
+use uuid::Uuid;
+
+use super::engine::{apply_rules, compile, load_rules, test_rule};
+use super::options;
+use super::types::{ExtractedOptions, PostprocessRule, RuleTarget, RuleTestResult};
+use crate::db::{now_millis, DbState};
+use crate::prose::entities;
+
+/// Get the rules that apply to a story (global rules first), or only the
+/// global rules when no story is given
+#[tauri::command]
+pub async fn get_postprocess_rules(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<Vec<PostprocessRule>, String> {
+    load_rules(db.pool(), story_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load post-processing rules: {}", e))
+}
+
+/// Create or update a rule, returning its id. New rules go to the end of their scope.
+#[tauri::command]
+pub async fn save_postprocess_rule(
+    db: State<'_, DbState>,
+    rule: PostprocessRule,
+) -> Result<String, String> {
+    if rule.name.trim().is_empty() {
+        return Err("Rule name cannot be empty".to_string());
+    }
+    compile(&rule)?;
+
+    let now = now_millis();
+    let id = match &rule.id {
+        Some(id) => {
+            sqlx::query(
+                "UPDATE postprocess_rules SET name = ?, pattern = ?, replacement = ?, case_insensitive = ?, \
+                 multiline = ?, apply_to_output = ?, apply_to_prompt = ?, enabled = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(rule.name.trim())
+            .bind(&rule.pattern)
+            .bind(&rule.replacement)
+            .bind(rule.case_insensitive)
+            .bind(rule.multiline)
+            .bind(rule.apply_to_output)
+            .bind(rule.apply_to_prompt)
+            .bind(rule.enabled)
+            .bind(now)
+            .bind(id)
+            .execute(db.pool())
+            .await
+            .map_err(|e| format!("Failed to update rule: {}", e))?;
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO postprocess_rules (id, story_id, name, pattern, replacement, case_insensitive, \
+                 multiline, apply_to_output, apply_to_prompt, enabled, position, created_at, updated_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, \
+                 (SELECT COALESCE(MAX(position) + 1, 0) FROM postprocess_rules WHERE story_id IS ?2), ?11, ?11)",
+            )
+            .bind(&id)
+            .bind(&rule.story_id)
+            .bind(rule.name.trim())
+            .bind(&rule.pattern)
+            .bind(&rule.replacement)
+            .bind(rule.case_insensitive)
+            .bind(rule.multiline)
+            .bind(rule.apply_to_output)
+            .bind(rule.apply_to_prompt)
+            .bind(rule.enabled)
+            .bind(now)
+            .execute(db.pool())
+            .await
+            .map_err(|e| format!("Failed to create rule: {}", e))?;
+            id
+        }
+    };
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_postprocess_rule(
+    db: State<'_, DbState>,
+    rule_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM postprocess_rules WHERE id = ?")
+        .bind(&rule_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete rule: {}", e))?;
+    Ok(())
+}
+
+/// Set rule order within a scope; `rule_ids` lists the rules in their new order
+#[tauri::command]
+pub async fn reorder_postprocess_rules(
+    db: State<'_, DbState>,
+    rule_ids: Vec<String>,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    for (position, id) in rule_ids.iter().enumerate() {
+        sqlx::query("UPDATE postprocess_rules SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to reorder rules: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit rule order: {}", e))
+}
+
+/// Try a rule (saved or not) against sample text
+#[tauri::command]
+pub fn test_postprocess_rule(
+    rule: PostprocessRule,
+    sample: String,
+) -> Result<RuleTestResult, String> {
+    test_rule(&rule, &sample)
+}
+
+/// Run the enabled global and story rules over model output or a prompt.
+///
+/// Story output is also checked for misspelled entity names; findings are
+/// emitted on `entity-consistency` and corrected when auto-correct is on.
+#[tauri::command]
+pub async fn apply_postprocess(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+    text: String,
+    target: RuleTarget,
+) -> Result<String, String> {
+    if let (Some(story_id), RuleTarget::Output) = (&story_id, target) {
+        return process_output(&app, db.pool(), story_id, &text).await;
+    }
+    let rules = load_rules(db.pool(), story_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load post-processing rules: {}", e))?;
+    Ok(apply_rules(&rules, &text, target))
+}
+
+/// Run a story's output rules and entity check over model output
+pub async fn process_output(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    text: &str,
+) -> Result<String, String> {
+    let rules = load_rules(pool, Some(story_id))
+        .await
+        .map_err(|e| format!("Failed to load post-processing rules: {}", e))?;
+    let text = apply_rules(&rules, text, RuleTarget::Output);
+
+    let settings = entities::load_settings(pool).await?;
+    let known = entities::load_entities(pool, story_id).await?;
+    let check = entities::check(&text, &known, &settings);
+    if !check.warnings.is_empty() {
+        let payload = json!({ "storyId": story_id, "warnings": check.warnings });
+        if let Err(e) = app.emit("entity-consistency", payload) {
+            eprintln!("Failed to emit entity consistency event: {}", e);
+        }
+    }
+    Ok(check.text)
+}
+
+/// Split a trailing options block off a story response so the options can be
+/// shown as suggestions without a separate model call. `allowed_types` lists
+/// the option types the caller understands; others fall back to the first.
+#[tauri::command]
+pub fn extract_suggestions(text: String, allowed_types: Option<Vec<String>>) -> ExtractedOptions {
+    options::extract(&text, &allowed_types.unwrap_or_default())
+}
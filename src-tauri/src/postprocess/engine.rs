@@ -0,0 +1,95 @@
+use regex::{Regex, RegexBuilder};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use super::types::{PostprocessRule, RuleMatch, RuleTarget, RuleTestResult};
+
+/// Patterns larger than this (compiled) are rejected to keep rules cheap
+const MAX_PATTERN_SIZE: usize = 1 << 20;
+
+fn rule_from_row(r: &SqliteRow) -> PostprocessRule {
+    PostprocessRule {
+        id: r.get("id"),
+        story_id: r.get("story_id"),
+        name: r.get("name"),
+        pattern: r.get("pattern"),
+        replacement: r.get("replacement"),
+        case_insensitive: r.get::<i64, _>("case_insensitive") != 0,
+        multiline: r.get::<i64, _>("multiline") != 0,
+        apply_to_output: r.get::<i64, _>("apply_to_output") != 0,
+        apply_to_prompt: r.get::<i64, _>("apply_to_prompt") != 0,
+        enabled: r.get::<i64, _>("enabled") != 0,
+        position: r.get("position"),
+    }
+}
+
+/// Global rules followed by the story's own rules, each in position order
+pub async fn load_rules(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<PostprocessRule>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM postprocess_rules WHERE story_id IS NULL OR story_id = ?1 \
+         ORDER BY story_id IS NOT NULL, position, created_at",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(rule_from_row).collect())
+}
+
+/// Compile a rule's pattern with its flags
+pub fn compile(rule: &PostprocessRule) -> Result<Regex, String> {
+    RegexBuilder::new(&rule.pattern)
+        .case_insensitive(rule.case_insensitive)
+        .multi_line(rule.multiline)
+        .dot_matches_new_line(rule.multiline)
+        .size_limit(MAX_PATTERN_SIZE)
+        .build()
+        .map_err(|e| format!("Invalid pattern in rule '{}': {}", rule.name, e))
+}
+
+fn replacement(rule: &PostprocessRule) -> String {
+    rule.replacement.replace("{{match}}", "${0}")
+}
+
+/// Apply the enabled rules for `target` in order.
+///
+/// Rules with invalid patterns are skipped rather than failing the whole
+/// pipeline; they are reported when saved or tested.
+pub fn apply_rules(rules: &[PostprocessRule], text: &str, target: RuleTarget) -> String {
+    let mut result = text.to_string();
+    for rule in rules {
+        let applies = match target {
+            RuleTarget::Output => rule.apply_to_output,
+            RuleTarget::Prompt => rule.apply_to_prompt,
+        };
+        if !rule.enabled || !applies {
+            continue;
+        }
+        if let Ok(pattern) = compile(rule) {
+            result = pattern
+                .replace_all(&result, replacement(rule).as_str())
+                .into_owned();
+        }
+    }
+    result
+}
+
+/// Run a single rule against a sample, listing what it matched
+pub fn test_rule(rule: &PostprocessRule, sample: &str) -> Result<RuleTestResult, String> {
+    let pattern = compile(rule)?;
+    let char_offset = |byte: usize| sample[..byte].chars().count();
+    let matches = pattern
+        .find_iter(sample)
+        .map(|m| RuleMatch {
+            start: char_offset(m.start()),
+            end: char_offset(m.end()),
+            text: m.as_str().to_string(),
+        })
+        .collect();
+    let output = pattern
+        .replace_all(sample, replacement(rule).as_str())
+        .into_owned();
+    Ok(RuleTestResult { output, matches })
+}
@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod engine;
+pub mod options;
+pub mod types;
@@ -0,0 +1,165 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+use super::types::{ExtractedOptions, SuggestedOption};
+
+/// Options beyond this many are ignored
+const MAX_OPTIONS: usize = 6;
+
+/// A heading line that starts a trailing options list ("---OPTIONS---",
+/// "## Choices", "[Suggestions]", "What do you do?")
+static OPTIONS_HEADING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^\s*(?:[-=*]{3,}|#{1,4})?\s*[\[(*_]*\s*(?:options|choices|suggestions|suggested actions|what do you do\??)\s*[\])*_]*:?\s*[-=*]{0,}\s*$",
+    )
+    .unwrap()
+});
+
+/// "1. text", "2) text", "- text", "* text"
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:\d+[.)]|[-*•])\s+(.+?)\s*$").unwrap());
+
+/// A leading "[dialogue]" / "(move)" / "Examine:" type marker
+static TYPE_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:\[(\w+)\]|\((\w+)\)|(\w+):)\s*(.+)$").unwrap());
+
+static OPTIONS_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<options>(.*?)</options>\s*$").unwrap());
+
+fn option(text: &str, kind: Option<&str>, allowed: &[String]) -> Option<SuggestedOption> {
+    let text = text
+        .trim()
+        .trim_matches(|c| c == '*' || c == '_' || c == '"')
+        .trim();
+    if text.is_empty() {
+        return None;
+    }
+    let kind = kind
+        .map(str::to_lowercase)
+        .filter(|k| allowed.is_empty() || allowed.contains(k))
+        .or_else(|| allowed.first().cloned())
+        .unwrap_or_else(|| "action".to_string());
+    Some(SuggestedOption {
+        text: text.to_string(),
+        kind,
+    })
+}
+
+fn from_json(value: &Value, allowed: &[String]) -> Vec<SuggestedOption> {
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(map) => match ["choices", "suggestions", "options", "actions"]
+            .iter()
+            .find_map(|k| map.get(*k)?.as_array())
+        {
+            Some(items) => items,
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Value::String(text) => option(text, None, allowed),
+            Value::Object(o) => option(
+                o.get("text").or_else(|| o.get("action"))?.as_str()?,
+                o.get("type").and_then(Value::as_str),
+                allowed,
+            ),
+            _ => None,
+        })
+        .take(MAX_OPTIONS)
+        .collect()
+}
+
+fn from_lines<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    allowed: &[String],
+) -> Option<Vec<SuggestedOption>> {
+    let mut options = Vec::new();
+    for line in lines.into_iter().filter(|l| !l.trim().is_empty()) {
+        let item = LIST_ITEM.captures(line)?.get(1)?.as_str();
+        let parsed = match TYPE_PREFIX.captures(item) {
+            Some(c) => {
+                let kind = c.get(1).or(c.get(2)).or(c.get(3)).map(|m| m.as_str());
+                // "Ask: where is he?" is a type only if it names one we know
+                match kind.filter(|k| allowed.is_empty() || allowed.contains(&k.to_lowercase())) {
+                    Some(kind) => option(&c[4], Some(kind), allowed),
+                    None => option(item, None, allowed),
+                }
+            }
+            None => option(item, None, allowed),
+        };
+        options.extend(parsed);
+    }
+    (!options.is_empty()).then(|| options.into_iter().take(MAX_OPTIONS).collect())
+}
+
+/// A trailing fenced block holding JSON options
+fn fenced(text: &str, allowed: &[String]) -> Option<(usize, Vec<SuggestedOption>)> {
+    let body_end = text.strip_suffix("```")?.len();
+    let open = text[..body_end].rfind("```")?;
+    let inner = &text[open + 3..body_end];
+    // Skip a language tag on the opening fence
+    let inner = match inner.find('\n') {
+        Some(i) if !inner[..i].trim_start().starts_with(['{', '[']) => &inner[i + 1..],
+        _ => inner,
+    };
+    let value: Value = serde_json::from_str(inner.trim()).ok()?;
+    let options = from_json(&value, allowed);
+    (!options.is_empty()).then_some((open, options))
+}
+
+/// Split trailing player options off a story response.
+///
+/// Recognizes, at the very end of the response: a fenced JSON block (an
+/// array of strings or `{text, type}` objects, or an object holding one under
+/// `choices`/`suggestions`/`options`), an `<options>` tag, or a heading such
+/// as `---OPTIONS---` followed by a numbered or bulleted list. Option types
+/// outside `allowed` fall back to its first entry. A response without such a
+/// block comes back unchanged with no options.
+pub fn extract(text: &str, allowed: &[String]) -> ExtractedOptions {
+    let trimmed = text.trim_end();
+    let unchanged = || ExtractedOptions {
+        text: text.to_string(),
+        options: Vec::new(),
+    };
+
+    let found = fenced(trimmed, allowed)
+        .or_else(|| {
+            let caps = OPTIONS_TAG.captures(trimmed)?;
+            let (start, inner) = (caps.get(0)?.start(), caps.get(1)?.as_str());
+            let options = match serde_json::from_str::<Value>(inner.trim()) {
+                Ok(value) => from_json(&value, allowed),
+                Err(_) => from_lines(inner.lines(), allowed)?,
+            };
+            (!options.is_empty()).then_some((start, options))
+        })
+        .or_else(|| {
+            let lines: Vec<&str> = trimmed.lines().collect();
+            let heading = lines.iter().rposition(|l| OPTIONS_HEADING.is_match(l))?;
+            let options = from_lines(lines[heading + 1..].iter().copied(), allowed)?;
+            // `lines` borrow from `trimmed`, so the heading's offset is its address difference
+            let start = lines[heading].as_ptr() as usize - trimmed.as_ptr() as usize;
+            Some((start, options))
+        });
+
+    match found {
+        Some((start, options)) => {
+            let body = trimmed[..start].trim_end();
+            // Drop a horizontal rule left between the story and the block
+            let body = body
+                .strip_suffix("---")
+                .or_else(|| body.strip_suffix("***"))
+                .unwrap_or(body)
+                .trim_end();
+            ExtractedOptions {
+                text: body.to_string(),
+                options,
+            }
+        }
+        None => unchanged(),
+    }
+}
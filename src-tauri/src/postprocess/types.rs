@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// A regex find/replace rule, global when `story_id` is `None`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessRule {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub story_id: Option<String>,
+    pub name: String,
+    pub pattern: String,
+    /// Supports `$1` / `${name}` group references and SillyTavern's `{{match}}`
+    #[serde(default)]
+    pub replacement: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub multiline: bool,
+    #[serde(default = "default_true")]
+    pub apply_to_output: bool,
+    #[serde(default)]
+    pub apply_to_prompt: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub position: i64,
+}
+
+/// Which text a set of rules is being applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleTarget {
+    Output,
+    Prompt,
+}
+
+/// One match found while testing a rule
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleMatch {
+    /// Character offsets into the sample, for highlighting in the UI
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Result of `test_postprocess_rule`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTestResult {
+    pub output: String,
+    pub matches: Vec<RuleMatch>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A player option the model appended to its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedOption {
+    pub text: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// A response split into its displayable text and trailing options
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedOptions {
+    pub text: String,
+    pub options: Vec<SuggestedOption>,
+}
@@ -0,0 +1,293 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tauri::State;
+
+use super::store;
+use super::types::{
+    FilterEntryData, ModelProfileData, PostprocessRulesData, PresetFile, PresetImportResult,
+    PresetKind, PromptPackData, PRESET_FORMAT, PRESET_VERSION,
+};
+use crate::db::{now_millis, DbState};
+use crate::filter::types::{FilterRule, FILTER_ACTIONS};
+use crate::{filter, postprocess};
+
+const VARIABLE_TYPES: [&str; 5] = ["text", "textarea", "enum", "number", "boolean"];
+
+fn valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn parse_data<T: DeserializeOwned>(data: Value) -> Result<T, String> {
+    serde_json::from_value(data).map_err(|e| format!("Invalid preset data: {}", e))
+}
+
+fn check_pack(pack: &PromptPackData) -> Vec<String> {
+    let mut problems = Vec::new();
+    if pack.name.trim().is_empty() {
+        problems.push("name: cannot be empty".to_string());
+    }
+    for (i, template) in pack.templates.iter().enumerate() {
+        if template.template_id.trim().is_empty() {
+            problems.push(format!("templates.{}.templateId: cannot be empty", i));
+        }
+    }
+    for (i, variable) in pack.variables.iter().enumerate() {
+        if !valid_variable_name(&variable.variable_name) {
+            problems.push(format!(
+                "variables.{}.variableName: must use lowercase letters, numbers, and underscores only",
+                i
+            ));
+        }
+        if variable.display_name.trim().is_empty() {
+            problems.push(format!("variables.{}.displayName: cannot be empty", i));
+        }
+        if !VARIABLE_TYPES.contains(&variable.variable_type.as_str()) {
+            problems.push(format!(
+                "variables.{}.variableType: unknown type '{}'",
+                i, variable.variable_type
+            ));
+        }
+        if variable.variable_type == "enum"
+            && variable.enum_options.as_ref().is_none_or(|o| o.is_empty())
+        {
+            problems.push(format!(
+                "variables.{}.enumOptions: enum variables must have at least one option",
+                i
+            ));
+        }
+    }
+    problems
+}
+
+fn check_rules(rules: &PostprocessRulesData) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.name.trim().is_empty() {
+            problems.push(format!("{}.name: cannot be empty", i));
+        }
+        if let Err(e) = postprocess::engine::compile(rule) {
+            problems.push(format!("{}.pattern: {}", i, e));
+        }
+    }
+    problems
+}
+
+fn check_filters(entries: &[FilterEntryData]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.phrase.trim().is_empty() {
+            problems.push(format!("{}.phrase: cannot be empty", i));
+        }
+        if !FILTER_ACTIONS.contains(&entry.action.as_str()) {
+            problems.push(format!("{}.action: unknown action '{}'", i, entry.action));
+        }
+        let rule = FilterRule {
+            id: String::new(),
+            story_id: None,
+            phrase: entry.phrase.clone(),
+            is_regex: entry.is_regex,
+            action: entry.action.clone(),
+            substitution: entry.substitution.clone(),
+            enabled: entry.enabled,
+            created_at: 0,
+        };
+        if let Err(e) = filter::engine::compile(&rule) {
+            problems.push(format!("{}.phrase: {}", i, e));
+        }
+    }
+    problems
+}
+
+fn reject(problems: Vec<String>) -> Result<(), String> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid preset: {}", problems.join("; ")))
+    }
+}
+
+/// Read a preset file, accepting the older bare `.prompt.json` pack export
+pub fn parse_file(raw: &str) -> Result<PresetFile, String> {
+    let value: Value =
+        serde_json::from_str(raw).map_err(|e| format!("Invalid preset file: {}", e))?;
+    if value.get("format").is_none() && value.get("templates").is_some() {
+        let name = value
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or_default()
+            .to_string();
+        return Ok(PresetFile {
+            format: PRESET_FORMAT.to_string(),
+            version: PRESET_VERSION,
+            kind: PresetKind::PromptPack,
+            name,
+            exported_at: 0,
+            data: value,
+        });
+    }
+    let file: PresetFile =
+        serde_json::from_value(value).map_err(|e| format!("Invalid preset file: {}", e))?;
+    if file.format != PRESET_FORMAT {
+        return Err(format!("Not an Aventura preset file ({})", file.format));
+    }
+    if file.version > PRESET_VERSION {
+        return Err(format!(
+            "Preset file version {} is newer than this app supports ({}); please update Aventura",
+            file.version, PRESET_VERSION
+        ));
+    }
+    Ok(file)
+}
+
+/// Assemble a preset. `id` is the pack or model profile id; for
+/// post-processing rules and filter lists it is the story whose own rules
+/// to take, or `None` for the global ones. Model profiles leave out their
+/// API profile.
+pub async fn build_preset(
+    pool: &SqlitePool,
+    kind: PresetKind,
+    id: Option<&str>,
+) -> Result<PresetFile, String> {
+    let (name, data) = match kind {
+        PresetKind::PromptPack => {
+            let pack = store::load_pack(pool, id.ok_or("A pack id is required")?).await?;
+            (pack.name.clone(), serde_json::to_value(pack))
+        }
+        PresetKind::ModelProfile => {
+            let profile_id = id.ok_or("A model profile id is required")?;
+            let profile = store::load_model_profile(pool, profile_id).await?;
+            (profile.name.clone(), serde_json::to_value(profile))
+        }
+        PresetKind::PostprocessRules => {
+            let rules = store::load_postprocess_rules(pool, id).await?;
+            (
+                "Post-processing rules".to_string(),
+                serde_json::to_value(rules),
+            )
+        }
+        PresetKind::FilterList => {
+            let entries = store::load_filter_list(pool, id).await?;
+            ("Filter list".to_string(), serde_json::to_value(entries))
+        }
+    };
+    Ok(PresetFile {
+        format: PRESET_FORMAT.to_string(),
+        version: PRESET_VERSION,
+        kind,
+        name,
+        exported_at: now_millis(),
+        data: data.map_err(|e| format!("Failed to serialize preset: {}", e))?,
+    })
+}
+
+/// Write a preset to a shareable file; see `build_preset` for what `id` names
+#[tauri::command]
+pub async fn export_preset(
+    db: State<'_, DbState>,
+    kind: PresetKind,
+    id: Option<String>,
+    path: String,
+) -> Result<(), String> {
+    let file = build_preset(db.pool(), kind, id.as_deref()).await?;
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("Failed to serialize preset: {}", e))?;
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write preset file: {}", e))
+}
+
+/// Validate a preset and add its contents within `tx`.
+///
+/// Packs and model profiles are created alongside existing ones, renamed
+/// with an "(Imported)" suffix when the name is taken. Rules and filter
+/// phrases are added to `story_id`'s rules (global when `None`), skipping
+/// ones that scope already has.
+pub async fn install_preset(
+    tx: &mut Transaction<'_, Sqlite>,
+    file: PresetFile,
+    story_id: Option<&str>,
+) -> Result<PresetImportResult, String> {
+    let result = match file.kind {
+        PresetKind::PromptPack => {
+            let pack: PromptPackData = parse_data(file.data)?;
+            reject(check_pack(&pack))?;
+            let (id, name) = store::insert_pack(tx, &pack).await?;
+            PresetImportResult {
+                kind: file.kind,
+                id: Some(id),
+                name,
+                imported: pack.templates.len(),
+                skipped: 0,
+            }
+        }
+        PresetKind::ModelProfile => {
+            let profile: ModelProfileData = parse_data(file.data)?;
+            if profile.name.trim().is_empty() {
+                return Err("Invalid preset: name: cannot be empty".to_string());
+            }
+            let (id, name) = store::insert_model_profile(tx, &profile).await?;
+            PresetImportResult {
+                kind: file.kind,
+                id: Some(id),
+                name,
+                imported: 1,
+                skipped: 0,
+            }
+        }
+        PresetKind::PostprocessRules => {
+            let rules: PostprocessRulesData = parse_data(file.data)?;
+            reject(check_rules(&rules))?;
+            let (imported, skipped) = store::insert_postprocess_rules(tx, story_id, &rules).await?;
+            PresetImportResult {
+                kind: file.kind,
+                id: None,
+                name: file.name,
+                imported,
+                skipped,
+            }
+        }
+        PresetKind::FilterList => {
+            let entries: Vec<FilterEntryData> = parse_data(file.data)?;
+            reject(check_filters(&entries))?;
+            let (imported, skipped) = store::insert_filter_list(tx, story_id, &entries).await?;
+            PresetImportResult {
+                kind: file.kind,
+                id: None,
+                name: file.name,
+                imported,
+                skipped,
+            }
+        }
+    };
+    Ok(result)
+}
+
+/// Validate a preset file and add its contents (see `install_preset`).
+/// Nothing is written if any part is invalid.
+#[tauri::command]
+pub async fn import_preset(
+    db: State<'_, DbState>,
+    path: String,
+    story_id: Option<String>,
+) -> Result<PresetImportResult, String> {
+    let raw = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read preset file: {}", e))?;
+    let file = parse_file(&raw)?;
+
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let result = install_preset(&mut tx, file, story_id.as_deref()).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit preset import: {}", e))?;
+    Ok(result)
+}
@@ -0,0 +1,332 @@
+use sha2::{Digest, Sha256};
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use super::types::{
+    EnumOption, FilterEntryData, ModelProfileData, PackTemplateData, PackVariableData,
+    PostprocessRulesData, PromptPackData,
+};
+use crate::db::now_millis;
+use crate::filter;
+use crate::llm::profiles::load_profile;
+use crate::postprocess;
+
+/// Same normalization and digest as the frontend's pack template hash
+fn content_hash(content: &str) -> String {
+    let normalized = content.trim().replace("\r\n", "\n");
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+pub async fn load_pack(pool: &SqlitePool, pack_id: &str) -> Result<PromptPackData, String> {
+    let pack = sqlx::query("SELECT name, description, author FROM preset_packs WHERE id = ?")
+        .bind(pack_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load pack: {}", e))?
+        .ok_or_else(|| format!("Pack not found: {}", pack_id))?;
+    let templates = sqlx::query(
+        "SELECT template_id, content FROM pack_templates WHERE pack_id = ? ORDER BY template_id",
+    )
+    .bind(pack_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load pack templates: {}", e))?
+    .iter()
+    .map(|r| PackTemplateData {
+        template_id: r.get("template_id"),
+        content: r.get("content"),
+    })
+    .collect();
+    let variables = sqlx::query(
+        "SELECT * FROM pack_variables WHERE pack_id = ? ORDER BY sort_order, variable_name",
+    )
+    .bind(pack_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load pack variables: {}", e))?
+    .iter()
+    .map(|r| PackVariableData {
+        variable_name: r.get("variable_name"),
+        display_name: r.get("display_name"),
+        description: r.get("description"),
+        variable_type: r.get("variable_type"),
+        is_required: r.get::<i64, _>("is_required") != 0,
+        sort_order: r.get("sort_order"),
+        default_value: r.get("default_value"),
+        enum_options: r
+            .get::<Option<String>, _>("enum_options")
+            .and_then(|raw| serde_json::from_str::<Vec<EnumOption>>(&raw).ok()),
+    })
+    .collect();
+    Ok(PromptPackData {
+        name: pack.get("name"),
+        description: pack.get("description"),
+        author: pack.get("author"),
+        templates,
+        variables,
+    })
+}
+
+pub async fn load_model_profile(
+    pool: &SqlitePool,
+    profile_id: &str,
+) -> Result<ModelProfileData, String> {
+    let mut profile = load_profile(pool, profile_id)
+        .await?
+        .ok_or_else(|| format!("Model profile not found: {}", profile_id))?;
+    profile.id = String::new();
+    profile.api_profile_id = None;
+    profile.created_at = 0;
+    profile.updated_at = 0;
+    Ok(profile)
+}
+
+/// Rules of exactly one scope: a story's own rules, or the global ones
+pub async fn load_postprocess_rules(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<PostprocessRulesData, String> {
+    Ok(postprocess::engine::load_rules(pool, story_id)
+        .await
+        .map_err(|e| format!("Failed to load post-processing rules: {}", e))?
+        .into_iter()
+        .filter(|r| r.story_id.as_deref() == story_id)
+        .map(|mut r| {
+            r.id = None;
+            r.story_id = None;
+            r
+        })
+        .collect())
+}
+
+pub async fn load_filter_list(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+) -> Result<Vec<FilterEntryData>, String> {
+    Ok(filter::engine::load_rules(pool, story_id)
+        .await
+        .map_err(|e| format!("Failed to load filter rules: {}", e))?
+        .into_iter()
+        .filter(|r| r.story_id.as_deref() == story_id)
+        .map(|r| FilterEntryData {
+            phrase: r.phrase,
+            is_regex: r.is_regex,
+            action: r.action,
+            substitution: r.substitution,
+            enabled: r.enabled,
+        })
+        .collect())
+}
+
+/// `name`, or the first of "name (Imported)", "name (Imported 2)", ... not
+/// already used in `table` (compared case-insensitively)
+async fn free_name(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    name: &str,
+) -> Result<String, String> {
+    let mut candidate = name.to_string();
+    let mut attempt = 0;
+    loop {
+        let taken: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE name = ? COLLATE NOCASE",
+            table
+        ))
+        .bind(&candidate)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to check names: {}", e))?;
+        if taken == 0 {
+            return Ok(candidate);
+        }
+        attempt += 1;
+        candidate = match attempt {
+            1 => format!("{} (Imported)", name),
+            n => format!("{} (Imported {})", name, n),
+        };
+    }
+}
+
+/// Create the pack under a free name. Returns its id and final name.
+pub async fn insert_pack(
+    tx: &mut Transaction<'_, Sqlite>,
+    pack: &PromptPackData,
+) -> Result<(String, String), String> {
+    let id = Uuid::new_v4().to_string();
+    let name = free_name(tx, "preset_packs", pack.name.trim()).await?;
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO preset_packs (id, name, description, author, is_default, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, 0, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&name)
+    .bind(&pack.description)
+    .bind(&pack.author)
+    .bind(now)
+    .bind(now)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to create pack: {}", e))?;
+    for template in &pack.templates {
+        sqlx::query(
+            "INSERT INTO pack_templates (id, pack_id, template_id, content, content_hash, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&id)
+        .bind(&template.template_id)
+        .bind(&template.content)
+        .bind(content_hash(&template.content))
+        .bind(now)
+        .bind(now)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to create pack template: {}", e))?;
+    }
+    for variable in &pack.variables {
+        let enum_options = variable
+            .enum_options
+            .as_ref()
+            .map(|options| serde_json::to_string(options).unwrap_or_default());
+        sqlx::query(
+            "INSERT INTO pack_variables (id, pack_id, variable_name, display_name, description, \
+             variable_type, is_required, sort_order, default_value, enum_options, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&id)
+        .bind(&variable.variable_name)
+        .bind(&variable.display_name)
+        .bind(&variable.description)
+        .bind(&variable.variable_type)
+        .bind(variable.is_required)
+        .bind(variable.sort_order)
+        .bind(&variable.default_value)
+        .bind(enum_options)
+        .bind(now)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to create pack variable: {}", e))?;
+    }
+    Ok((id, name))
+}
+
+/// Create the profile under a free name. Returns its id and final name.
+pub async fn insert_model_profile(
+    tx: &mut Transaction<'_, Sqlite>,
+    profile: &ModelProfileData,
+) -> Result<(String, String), String> {
+    let id = Uuid::new_v4().to_string();
+    let name = free_name(tx, "model_profiles", profile.name.trim()).await?;
+    let now = now_millis();
+    sqlx::query(
+        "INSERT INTO model_profiles (id, name, api_profile_id, model, temperature, max_tokens, params, \
+         created_at, updated_at) VALUES (?, ?, NULL, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&name)
+    .bind(profile.model.trim())
+    .bind(profile.temperature)
+    .bind(profile.max_tokens.map(i64::from))
+    .bind(serde_json::Value::Object(profile.params.clone()).to_string())
+    .bind(now)
+    .bind(now)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to create model profile: {}", e))?;
+    Ok((id, name))
+}
+
+/// Append rules to a scope after its existing ones, skipping rules whose
+/// pattern and replacement the scope already has. Returns (added, skipped).
+pub async fn insert_postprocess_rules(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: Option<&str>,
+    rules: &PostprocessRulesData,
+) -> Result<(usize, usize), String> {
+    let (mut added, mut skipped) = (0, 0);
+    let now = now_millis();
+    for rule in rules {
+        let exists: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM postprocess_rules WHERE story_id IS ? AND pattern = ? AND replacement = ?",
+        )
+        .bind(story_id)
+        .bind(&rule.pattern)
+        .bind(&rule.replacement)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to check rules: {}", e))?;
+        if exists > 0 {
+            skipped += 1;
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO postprocess_rules (id, story_id, name, pattern, replacement, case_insensitive, \
+             multiline, apply_to_output, apply_to_prompt, enabled, position, created_at, updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, \
+             (SELECT COALESCE(MAX(position) + 1, 0) FROM postprocess_rules WHERE story_id IS ?2), ?11, ?11)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(story_id)
+        .bind(rule.name.trim())
+        .bind(&rule.pattern)
+        .bind(&rule.replacement)
+        .bind(rule.case_insensitive)
+        .bind(rule.multiline)
+        .bind(rule.apply_to_output)
+        .bind(rule.apply_to_prompt)
+        .bind(rule.enabled)
+        .bind(now)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to create rule: {}", e))?;
+        added += 1;
+    }
+    Ok((added, skipped))
+}
+
+/// Add phrases to a scope, skipping phrases it already has. Returns
+/// (added, skipped).
+pub async fn insert_filter_list(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: Option<&str>,
+    entries: &[FilterEntryData],
+) -> Result<(usize, usize), String> {
+    let (mut added, mut skipped) = (0, 0);
+    let now = now_millis();
+    for entry in entries {
+        let exists: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM filter_rules WHERE story_id IS ? AND phrase = ? COLLATE NOCASE \
+             AND is_regex = ?",
+        )
+        .bind(story_id)
+        .bind(&entry.phrase)
+        .bind(entry.is_regex)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to check filter rules: {}", e))?;
+        if exists > 0 {
+            skipped += 1;
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO filter_rules (id, story_id, phrase, is_regex, action, substitution, enabled, \
+             created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(story_id)
+        .bind(&entry.phrase)
+        .bind(entry.is_regex)
+        .bind(&entry.action)
+        .bind(&entry.substitution)
+        .bind(entry.enabled)
+        .bind(now)
+        .bind(now)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to create filter rule: {}", e))?;
+        added += 1;
+    }
+    Ok((added, skipped))
+}
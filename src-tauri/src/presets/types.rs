@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::llm::profiles::ModelProfile;
+use crate::postprocess::types::PostprocessRule;
+
+/// Identifies a shareable preset file
+pub const PRESET_FORMAT: &str = "aventura-preset";
+
+/// Current preset file version; older versions are upgraded on import
+pub const PRESET_VERSION: u32 = 1;
+
+/// What a preset file carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PresetKind {
+    /// A prompt template pack with its custom variables
+    PromptPack,
+    /// A model profile's model and sampler settings
+    ModelProfile,
+    /// A set of post-processing rules
+    PostprocessRules,
+    /// A banned-phrase list
+    FilterList,
+}
+
+/// A preset as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetFile {
+    pub format: String,
+    pub version: u32,
+    pub kind: PresetKind,
+    pub name: String,
+    #[serde(default)]
+    pub exported_at: i64,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackTemplateData {
+    pub template_id: String,
+    pub content: String,
+}
+
+/// Mirrors the frontend's pack variable export shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackVariableData {
+    pub variable_name: String,
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub variable_type: String,
+    #[serde(default)]
+    pub is_required: bool,
+    #[serde(default)]
+    pub sort_order: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enum_options: Option<Vec<EnumOption>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumOption {
+    pub label: String,
+    pub value: String,
+}
+
+/// `data` of a prompt pack preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptPackData {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub templates: Vec<PackTemplateData>,
+    #[serde(default)]
+    pub variables: Vec<PackVariableData>,
+}
+
+/// `data` of a model profile preset. The API profile is left out: it names
+/// a connection (and key) that only exists on the exporting device.
+pub type ModelProfileData = ModelProfile;
+
+/// `data` of a post-processing rules preset, in rule order
+pub type PostprocessRulesData = Vec<PostprocessRule>;
+
+/// One phrase of a filter list preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterEntryData {
+    pub phrase: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub action: String,
+    #[serde(default)]
+    pub substitution: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// What `import_preset` added
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetImportResult {
+    pub kind: PresetKind,
+    /// Id of the created pack or profile; `None` for rule lists
+    pub id: Option<String>,
+    /// Final name, with an "(Imported)" suffix when the original was taken
+    pub name: String,
+    pub imported: usize,
+    /// Rules already present in the target scope
+    pub skipped: usize,
+}
@@ -0,0 +1,102 @@
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use super::store::{self, DEFAULT_PROFILE};
+use super::types::{Profile, ProfileList};
+use crate::db::now_millis;
+use crate::paths::DataPaths;
+
+/// Where the active profile's data is, including the database URL the
+/// frontend opens
+#[tauri::command]
+pub fn get_data_paths(paths: State<'_, DataPaths>) -> DataPaths {
+    paths.inner().clone()
+}
+
+#[tauri::command]
+pub fn list_profiles(paths: State<'_, DataPaths>) -> ProfileList {
+    let mut list = store::load(&paths.root);
+    // Report the profile actually in use, even if the file says otherwise
+    list.active = paths.profile_id.clone();
+    list
+}
+
+fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+#[tauri::command]
+pub fn create_profile(paths: State<'_, DataPaths>, name: String) -> Result<Profile, String> {
+    let mut list = store::load(&paths.root);
+    let profile = Profile {
+        id: Uuid::new_v4().to_string(),
+        name: clean_name(&name)?,
+        created_at: now_millis(),
+    };
+    std::fs::create_dir_all(store::profile_dir(&paths.root, &profile.id))
+        .map_err(|e| format!("Failed to create profile folder: {}", e))?;
+    list.profiles.push(profile.clone());
+    store::save(&paths.root, &list)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn rename_profile(paths: State<'_, DataPaths>, id: String, name: String) -> Result<(), String> {
+    let mut list = store::load(&paths.root);
+    let profile = list
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Profile not found: {}", id))?;
+    profile.name = clean_name(&name)?;
+    store::save(&paths.root, &list)
+}
+
+/// Delete a profile and all of its data. The default profile and the one
+/// in use can't be deleted.
+#[tauri::command]
+pub fn delete_profile(paths: State<'_, DataPaths>, id: String) -> Result<(), String> {
+    if id == DEFAULT_PROFILE {
+        return Err("The default profile can't be deleted".to_string());
+    }
+    if id == paths.profile_id {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    let mut list = store::load(&paths.root);
+    let before = list.profiles.len();
+    list.profiles.retain(|p| p.id != id);
+    if list.profiles.len() == before {
+        return Err(format!("Profile not found: {}", id));
+    }
+    store::save(&paths.root, &list)?;
+    let dir = store::profile_dir(&paths.root, &id);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to delete profile data: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Make `id` the active profile and restart, so the SQL plugin, the Rust
+/// database pool and background work all reopen on its database
+#[tauri::command]
+pub fn switch_profile(
+    app: AppHandle,
+    paths: State<'_, DataPaths>,
+    id: String,
+) -> Result<(), String> {
+    let mut list = store::load(&paths.root);
+    if !list.profiles.iter().any(|p| p.id == id) {
+        return Err(format!("Profile not found: {}", id));
+    }
+    if id == paths.profile_id {
+        return Ok(());
+    }
+    list.active = id;
+    store::save(&paths.root, &list)?;
+    app.restart()
+}
@@ -0,0 +1,62 @@
+//! Profiles are listed in `profiles.json` in the app data folder. The
+//! default profile keeps the database where it has always been; every
+//! other profile gets a folder under `profiles/`.
+
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::types::ProfileList;
+use crate::paths::{self, DataPaths, DATABASE_FILE};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_FILE: &str = "profiles.json";
+
+pub fn load(root: &Path) -> ProfileList {
+    let mut list: ProfileList = std::fs::read(root.join(PROFILES_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    if !list.profiles.iter().any(|p| p.id == DEFAULT_PROFILE) {
+        list.profiles
+            .insert(0, ProfileList::default().profiles.remove(0));
+    }
+    if !list.profiles.iter().any(|p| p.id == list.active) {
+        list.active = DEFAULT_PROFILE.to_string();
+    }
+    list
+}
+
+pub fn save(root: &Path, list: &ProfileList) -> Result<(), String> {
+    std::fs::create_dir_all(root).map_err(|e| format!("Failed to create data folder: {}", e))?;
+    let bytes = serde_json::to_vec_pretty(list)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    let partial = root.join(format!("{}.partial", PROFILES_FILE));
+    std::fs::write(&partial, bytes)
+        .and_then(|_| std::fs::rename(&partial, root.join(PROFILES_FILE)))
+        .map_err(|e| format!("Failed to save profiles: {}", e))
+}
+
+pub fn profile_dir(root: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join("profiles").join(id)
+    }
+}
+
+/// Paths of the active profile. The default profile's database stays in
+/// the SQL plugin's own folder, where earlier versions created it.
+pub fn resolve(app: &AppHandle) -> Result<DataPaths, String> {
+    let base = paths::app_dirs(app)?;
+    let active = load(&base.data).active;
+    let profile_dir = profile_dir(&base.data, &active);
+    let database = if active == DEFAULT_PROFILE {
+        base.config.join(DATABASE_FILE)
+    } else {
+        profile_dir.join(DATABASE_FILE)
+    };
+    std::fs::create_dir_all(&profile_dir)
+        .and_then(|_| std::fs::create_dir_all(&base.config))
+        .map_err(|e| format!("Failed to create profile folder: {}", e))?;
+    Ok(DataPaths::new(base, active, profile_dir, database))
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use super::store::DEFAULT_PROFILE;
+
+/// A separate set of stories, settings and saved keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+/// Contents of `profiles.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for ProfileList {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE.to_string(),
+                name: "Default".to_string(),
+                created_at: 0,
+            }],
+        }
+    }
+}
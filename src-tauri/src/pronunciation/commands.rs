@@ -0,0 +1,66 @@
+use tauri::State;
+
+use super::store;
+use super::types::{Pronunciation, PronunciationInput, PronunciationTest, SpeechFormat};
+use crate::db::DbState;
+
+#[tauri::command]
+pub async fn get_pronunciations(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<Pronunciation>, String> {
+    store::load_pronunciations(db.pool(), &story_id).await
+}
+
+#[tauri::command]
+pub async fn save_pronunciation(
+    db: State<'_, DbState>,
+    story_id: String,
+    input: PronunciationInput,
+) -> Result<Pronunciation, String> {
+    store::save_pronunciation(db.pool(), &story_id, input).await
+}
+
+#[tauri::command]
+pub async fn delete_pronunciation(
+    db: State<'_, DbState>,
+    pronunciation_id: String,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM pronunciations WHERE id = ?")
+        .bind(&pronunciation_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete pronunciation: {}", e))?;
+    Ok(())
+}
+
+/// Rewrite text with the story's pronunciations right before it goes to the
+/// TTS engine
+#[tauri::command]
+pub async fn apply_pronunciations(
+    db: State<'_, DbState>,
+    story_id: String,
+    text: String,
+    format: Option<SpeechFormat>,
+) -> Result<String, String> {
+    let lexicon = store::load_lexicon(db.pool(), &story_id).await?;
+    Ok(lexicon.apply(&text, format.unwrap_or_default()))
+}
+
+/// How the engine would be asked to say `word`, for trying out an entry
+/// before reading a whole passage
+#[tauri::command]
+pub async fn test_pronunciation(
+    db: State<'_, DbState>,
+    story_id: String,
+    word: String,
+    format: Option<SpeechFormat>,
+) -> Result<PronunciationTest, String> {
+    let lexicon = store::load_lexicon(db.pool(), &story_id).await?;
+    let word = word.trim().to_string();
+    Ok(PronunciationTest {
+        spoken: lexicon.apply(&word, format.unwrap_or_default()),
+        entry: lexicon.lookup(&word).cloned(),
+        word,
+    })
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use regex::{Captures, Regex, RegexBuilder};
+
+use super::types::{Pronunciation, SpeechFormat};
+use crate::reader::html::escape;
+
+/// A story's pronunciation dictionary, compiled for rewriting text before
+/// it is synthesized
+pub struct Lexicon {
+    pattern: Option<Regex>,
+    /// Case-sensitive entries by grapheme
+    exact: HashMap<String, Pronunciation>,
+    /// The rest by lowercased grapheme
+    folded: HashMap<String, Pronunciation>,
+}
+
+/// `grapheme` as a whole word, where its ends are word characters
+fn word(grapheme: &str) -> String {
+    let edge = |c: Option<char>| {
+        if c.is_some_and(char::is_alphanumeric) {
+            r"\b"
+        } else {
+            ""
+        }
+    };
+    format!(
+        "{}{}{}",
+        edge(grapheme.chars().next()),
+        regex::escape(grapheme),
+        edge(grapheme.chars().last())
+    )
+}
+
+impl Lexicon {
+    pub fn new(entries: Vec<Pronunciation>) -> Result<Self, String> {
+        let mut graphemes: Vec<&str> = entries.iter().map(|e| e.grapheme.as_str()).collect();
+        // Longest first, so "Ser Arthur" wins over "Arthur"
+        graphemes.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let pattern = if graphemes.is_empty() {
+            None
+        } else {
+            let alternatives: Vec<String> = graphemes.iter().map(|g| word(g)).collect();
+            let regex = RegexBuilder::new(&alternatives.join("|"))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Failed to build pronunciation pattern: {}", e))?;
+            Some(regex)
+        };
+
+        let mut exact = HashMap::new();
+        let mut folded = HashMap::new();
+        for entry in entries {
+            if entry.case_sensitive {
+                exact.insert(entry.grapheme.clone(), entry);
+            } else {
+                folded.insert(entry.grapheme.to_lowercase(), entry);
+            }
+        }
+        Ok(Lexicon {
+            pattern,
+            exact,
+            folded,
+        })
+    }
+
+    /// The entry for a matched word, if its case fits
+    pub fn lookup(&self, word: &str) -> Option<&Pronunciation> {
+        self.exact
+            .get(word)
+            .or_else(|| self.folded.get(&word.to_lowercase()))
+    }
+
+    /// Rewrite `text` for the engine. Plain text gets respellings in place
+    /// of the words (IPA entries need SSML and are left as written); SSML
+    /// gets a `<speak>` document with `<sub>` and `<phoneme>` tags.
+    pub fn apply(&self, text: &str, format: SpeechFormat) -> String {
+        let Some(pattern) = &self.pattern else {
+            return match format {
+                SpeechFormat::Text => text.to_string(),
+                SpeechFormat::Ssml => format!("<speak>{}</speak>", escape(text)),
+            };
+        };
+        match format {
+            SpeechFormat::Text => pattern
+                .replace_all(text, |caps: &Captures| {
+                    let word = &caps[0];
+                    match self.lookup(word) {
+                        Some(entry) if entry.alphabet == "respelling" => entry.phoneme.clone(),
+                        _ => word.to_string(),
+                    }
+                })
+                .into_owned(),
+            SpeechFormat::Ssml => {
+                let mut out = String::from("<speak>");
+                let mut last = 0;
+                for found in pattern.find_iter(text) {
+                    out.push_str(&escape(&text[last..found.start()]));
+                    let word = found.as_str();
+                    match self.lookup(word) {
+                        Some(entry) if entry.alphabet == "ipa" => out.push_str(&format!(
+                            "<phoneme alphabet=\"ipa\" ph=\"{}\">{}</phoneme>",
+                            escape(&entry.phoneme),
+                            escape(word)
+                        )),
+                        Some(entry) => out.push_str(&format!(
+                            "<sub alias=\"{}\">{}</sub>",
+                            escape(&entry.phoneme),
+                            escape(word)
+                        )),
+                        None => out.push_str(&escape(word)),
+                    }
+                    last = found.end();
+                }
+                out.push_str(&escape(&text[last..]));
+                out.push_str("</speak>");
+                out
+            }
+        }
+    }
+}
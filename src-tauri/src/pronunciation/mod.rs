@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod lexicon;
+pub mod store;
+pub mod types;
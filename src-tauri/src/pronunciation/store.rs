@@ -0,0 +1,83 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::lexicon::Lexicon;
+use super::types::{Pronunciation, PronunciationInput, ALPHABETS};
+use crate::db::now_millis;
+
+const SELECT_PRONUNCIATION: &str = "SELECT id, story_id, grapheme, phoneme, alphabet, \
+     case_sensitive, created_at, updated_at FROM pronunciations";
+
+pub async fn load_pronunciations(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<Pronunciation>, String> {
+    sqlx::query_as(&format!(
+        "{} WHERE story_id = ? ORDER BY grapheme COLLATE NOCASE",
+        SELECT_PRONUNCIATION
+    ))
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load pronunciations: {}", e))
+}
+
+pub async fn load_lexicon(pool: &SqlitePool, story_id: &str) -> Result<Lexicon, String> {
+    Lexicon::new(load_pronunciations(pool, story_id).await?)
+}
+
+/// Create or replace an entry. Saving a grapheme the story already has
+/// replaces that entry.
+pub async fn save_pronunciation(
+    pool: &SqlitePool,
+    story_id: &str,
+    input: PronunciationInput,
+) -> Result<Pronunciation, String> {
+    let grapheme = input.grapheme.trim();
+    let phoneme = input.phoneme.trim();
+    if grapheme.is_empty() {
+        return Err("The entry needs a word".to_string());
+    }
+    if phoneme.is_empty() {
+        return Err("The entry needs a pronunciation".to_string());
+    }
+    let alphabet = input.alphabet.unwrap_or_else(|| "respelling".to_string());
+    if !ALPHABETS.contains(&alphabet.as_str()) {
+        return Err(format!("Unknown phonetic alphabet: {}", alphabet));
+    }
+
+    let now = now_millis();
+    let id = input.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    // Drop the entry being renamed onto an existing grapheme's slot
+    sqlx::query("DELETE FROM pronunciations WHERE story_id = ? AND grapheme = ? AND id != ?")
+        .bind(story_id)
+        .bind(grapheme)
+        .bind(&id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save pronunciation: {}", e))?;
+    sqlx::query(
+        "INSERT INTO pronunciations (id, story_id, grapheme, phoneme, alphabet, case_sensitive, \
+         created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET grapheme = excluded.grapheme, \
+         phoneme = excluded.phoneme, alphabet = excluded.alphabet, \
+         case_sensitive = excluded.case_sensitive, updated_at = excluded.updated_at",
+    )
+    .bind(&id)
+    .bind(story_id)
+    .bind(grapheme)
+    .bind(phoneme)
+    .bind(&alphabet)
+    .bind(input.case_sensitive)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save pronunciation: {}", e))?;
+
+    sqlx::query_as(&format!("{} WHERE id = ?", SELECT_PRONUNCIATION))
+        .bind(&id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to load pronunciation: {}", e))
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// How a phoneme is written: a respelling any engine can read, or IPA,
+/// which only SSML output carries
+pub const ALPHABETS: &[&str] = &["respelling", "ipa"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Pronunciation {
+    pub id: String,
+    pub story_id: String,
+    /// The word or name as written
+    pub grapheme: String,
+    pub phoneme: String,
+    pub alphabet: String,
+    pub case_sensitive: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// An entry to create (no `id`) or replace
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PronunciationInput {
+    pub id: Option<String>,
+    pub grapheme: String,
+    pub phoneme: String,
+    /// `respelling` when omitted
+    pub alphabet: Option<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+/// What the TTS engine is given
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechFormat {
+    /// Plain text with respellings swapped in
+    #[default]
+    Text,
+    /// An SSML `<speak>` document with `<phoneme>` tags
+    Ssml,
+}
+
+/// How a word would be read
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PronunciationTest {
+    pub word: String,
+    /// The text handed to the engine
+    pub spoken: String,
+    /// The dictionary entry used, if any
+    pub entry: Option<Pronunciation>,
+}
@@ -0,0 +1,116 @@
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+
+use super::entities;
+use super::repetition::{analyze, load_settings, recent_narration};
+use super::style;
+use super::types::{CanonicalEntity, EntityCheck, RepetitionReport, StyleMetrics};
+use crate::db::DbState;
+use crate::jobs::queue::JobQueue;
+use crate::jobs::types::JobKind;
+
+/// Queue a repetition check of a saved entry; flagged results arrive on `repetition-detected`
+#[tauri::command]
+pub async fn queue_repetition_check(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::RepetitionCheck,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
+
+/// Score unsaved text (e.g. a finished stream) against the story's latest narration
+#[tauri::command]
+pub async fn analyze_repetition(
+    db: State<'_, DbState>,
+    story_id: String,
+    text: String,
+) -> Result<RepetitionReport, String> {
+    let settings = load_settings(db.pool()).await?;
+    let history = recent_narration(db.pool(), &story_id, None, settings.window).await?;
+    Ok(analyze(&text, &history, &settings))
+}
+
+/// The story's configured POV from its settings JSON
+async fn story_pov(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    let settings: Option<String> = sqlx::query_scalar("SELECT settings FROM stories WHERE id = ?")
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load story: {}", e))?
+        .flatten();
+    Ok(settings
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|v| v.get("pov").and_then(|p| p.as_str()).map(str::to_string)))
+}
+
+/// Readability and style metrics for a chapter's narration
+#[tauri::command]
+pub async fn get_style_metrics(
+    db: State<'_, DbState>,
+    chapter_id: String,
+) -> Result<StyleMetrics, String> {
+    let chapter = sqlx::query(
+        "SELECT c.story_id, s.position AS start_position, e.position AS end_position \
+         FROM chapters c \
+         JOIN story_entries s ON s.id = c.start_entry_id \
+         JOIN story_entries e ON e.id = c.end_entry_id \
+         WHERE c.id = ?",
+    )
+    .bind(&chapter_id)
+    .fetch_optional(db.pool())
+    .await
+    .map_err(|e| format!("Failed to load chapter: {}", e))?
+    .ok_or_else(|| format!("Chapter not found: {}", chapter_id))?;
+    let story_id: String = chapter.get("story_id");
+
+    let texts: Vec<String> = sqlx::query_scalar(
+        "SELECT content FROM story_entries \
+         WHERE story_id = ? AND type = 'narration' AND position BETWEEN ? AND ? ORDER BY position",
+    )
+    .bind(&story_id)
+    .bind(chapter.get::<i64, _>("start_position"))
+    .bind(chapter.get::<i64, _>("end_position"))
+    .fetch_all(db.pool())
+    .await
+    .map_err(|e| format!("Failed to load chapter entries: {}", e))?;
+
+    let pov = story_pov(db.pool(), &story_id).await?;
+    Ok(style::compute(&texts, pov.as_deref()))
+}
+
+/// Style metrics for arbitrary passages, e.g. the entries under style review
+#[tauri::command]
+pub fn measure_style(texts: Vec<String>, pov: Option<String>) -> StyleMetrics {
+    style::compute(&texts, pov.as_deref())
+}
+
+/// The canonical entity names generated text is checked against
+#[tauri::command]
+pub async fn get_story_entities(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<CanonicalEntity>, String> {
+    entities::load_entities(db.pool(), &story_id).await
+}
+
+/// Check text for misspelled character and lorebook names
+#[tauri::command]
+pub async fn check_entity_names(
+    db: State<'_, DbState>,
+    story_id: String,
+    text: String,
+) -> Result<EntityCheck, String> {
+    let settings = entities::load_settings(db.pool()).await?;
+    let known = entities::load_entities(db.pool(), &story_id).await?;
+    Ok(entities::check(&text, &known, &settings))
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use harper_core::spell::{Dictionary, FstDictionary};
+use regex::Regex;
+use sqlx::SqlitePool;
+
+use super::types::{CanonicalEntity, EntityCheck, EntitySettings, EntityWarning};
+use crate::context::branch::characters;
+use crate::llm::config::get_setting;
+use crate::lore::store::load_lore;
+
+static NAME_WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\p{L}+").unwrap());
+
+/// Name words shorter than this are too ambiguous to fuzzy-match
+const MIN_WORD_LEN: usize = 4;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<EntitySettings, String> {
+    Ok(get_setting(pool, "entity_consistency_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Characters plus lorebook entries on the current branch, with their aliases
+pub async fn load_entities(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<CanonicalEntity>, String> {
+    let mut entities: Vec<CanonicalEntity> = characters(pool, story_id)
+        .await?
+        .into_iter()
+        .map(|c| CanonicalEntity {
+            name: c.name,
+            kind: "character".to_string(),
+            aliases: Vec::new(),
+        })
+        .collect();
+    for entry in load_lore(pool, story_id).await? {
+        match entities
+            .iter_mut()
+            .find(|e| e.name.eq_ignore_ascii_case(&entry.name))
+        {
+            Some(existing) => existing.aliases.extend(entry.aliases),
+            None => entities.push(CanonicalEntity {
+                name: entry.name,
+                kind: entry.kind,
+                aliases: entry.aliases,
+            }),
+        }
+    }
+    Ok(entities)
+}
+
+/// Lowercased individual words of every entity name and alias
+pub fn name_words(entities: &[CanonicalEntity]) -> HashMap<String, (String, String)> {
+    let mut words = HashMap::new();
+    for entity in entities {
+        for name in std::iter::once(&entity.name).chain(&entity.aliases) {
+            for word in NAME_WORD.find_iter(name) {
+                words
+                    .entry(word.as_str().to_lowercase())
+                    .or_insert_with(|| (word.as_str().to_string(), entity.name.clone()));
+            }
+        }
+    }
+    words
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent transpositions)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Match the capitalization style of `found` ("ELERA" → "ELARA")
+fn match_case(found: &str, expected: &str) -> String {
+    if found.len() > 1 && found.chars().all(|c| !c.is_lowercase()) {
+        expected.to_uppercase()
+    } else {
+        expected.to_string()
+    }
+}
+
+/// Find capitalized words that are a small edit away from an entity name word
+/// but are neither a known name nor an ordinary dictionary word
+pub fn check(text: &str, entities: &[CanonicalEntity], settings: &EntitySettings) -> EntityCheck {
+    let words = name_words(entities);
+    let candidates: Vec<(Vec<char>, &String, &String)> = words
+        .iter()
+        .filter(|(lower, _)| lower.chars().count() >= MIN_WORD_LEN)
+        .map(|(lower, (word, entity))| (lower.chars().collect(), word, entity))
+        .collect();
+    if !settings.enabled || candidates.is_empty() {
+        return EntityCheck {
+            text: text.to_string(),
+            warnings: Vec::new(),
+        };
+    }
+    let dictionary = FstDictionary::curated();
+
+    let mut found: Vec<(std::ops::Range<usize>, EntityWarning)> = Vec::new();
+    for m in NAME_WORD.find_iter(text) {
+        let word = m.as_str();
+        let lower = word.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        if chars.len() < MIN_WORD_LEN
+            || !word.starts_with(char::is_uppercase)
+            || words.contains_key(&lower)
+            || dictionary.contains_word_str(word)
+        {
+            continue;
+        }
+        // Longer names tolerate more typos; short ones only a single edit
+        let allowed = if chars.len() <= 5 {
+            1
+        } else {
+            settings.max_distance.max(1)
+        };
+        let best = candidates
+            .iter()
+            .filter(|(name, _, _)| {
+                name[0] == chars[0] && name.len().abs_diff(chars.len()) <= allowed
+            })
+            .map(|(name, expected, entity)| (edit_distance(&chars, name), *expected, *entity))
+            .filter(|(distance, _, _)| *distance > 0 && *distance <= allowed)
+            .min_by_key(|(distance, _, _)| *distance);
+
+        if let Some((distance, expected, entity)) = best {
+            found.push((
+                m.range(),
+                EntityWarning {
+                    found: word.to_string(),
+                    expected: match_case(word, expected),
+                    entity: entity.clone(),
+                    start: text[..m.start()].chars().count(),
+                    end: text[..m.end()].chars().count(),
+                    distance,
+                    corrected: settings.auto_correct,
+                },
+            ));
+        }
+    }
+
+    let mut corrected = text.to_string();
+    if settings.auto_correct {
+        for (range, warning) in found.iter().rev() {
+            corrected.replace_range(range.clone(), &warning.expected);
+        }
+    }
+
+    EntityCheck {
+        text: corrected,
+        warnings: found.into_iter().map(|(_, w)| w).collect(),
+    }
+}
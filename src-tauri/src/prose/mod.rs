@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod entities;
+pub mod repetition;
+pub mod style;
+pub mod types;
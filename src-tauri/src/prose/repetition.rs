@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use super::types::{RepeatedPhrase, RepetitionReport, RepetitionSettings};
+use crate::jobs::types::BackgroundJob;
+use crate::llm::config::get_setting;
+
+static WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{L}\p{N}][\p{L}\p{N}'’]*").unwrap());
+
+/// Phrases reported per response
+const MAX_PHRASES: usize = 10;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<RepetitionSettings, String> {
+    Ok(get_setting(pool, "repetition_detection_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+fn words(text: &str) -> Vec<&str> {
+    WORD.find_iter(text).map(|m| m.as_str()).collect()
+}
+
+fn ngram_key(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|w| w.to_lowercase().replace('’', "'"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// N-grams made only of short function words ("and in the end") are too
+/// common in prose to count as repetition
+fn is_meaningful(words: &[&str]) -> bool {
+    words.iter().any(|w| w.chars().count() > 3)
+}
+
+/// Score `text` for n-gram overlap with `history` and with itself
+pub fn analyze(text: &str, history: &[String], settings: &RepetitionSettings) -> RepetitionReport {
+    let n = settings.ngram_size.max(2);
+    let tokens = words(text);
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for earlier in history {
+        let earlier_words = words(earlier);
+        for gram in earlier_words.windows(n) {
+            *seen.entry(ngram_key(gram)).or_default() += 1;
+        }
+    }
+    let mut own: HashMap<String, usize> = HashMap::new();
+    for gram in tokens.windows(n) {
+        *own.entry(ngram_key(gram)).or_default() += 1;
+    }
+
+    let mut covered = vec![false; tokens.len()];
+    let mut repeated = 0usize;
+    let mut total = 0usize;
+    for (i, gram) in tokens.windows(n).enumerate() {
+        if !is_meaningful(gram) {
+            continue;
+        }
+        total += 1;
+        let key = ngram_key(gram);
+        if seen.contains_key(&key) || own.get(&key).copied().unwrap_or(0) > 1 {
+            repeated += 1;
+            covered[i..i + n].iter_mut().for_each(|c| *c = true);
+        }
+    }
+    let score = if total == 0 {
+        0.0
+    } else {
+        repeated as f64 / total as f64
+    };
+
+    // Join overlapping repeated n-grams into maximal phrases
+    let mut phrases: Vec<RepeatedPhrase> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !covered[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < tokens.len() && covered[i] {
+            i += 1;
+        }
+        let run = &tokens[start..i];
+        let key = ngram_key(&run[..n.min(run.len())]);
+        let phrase = run.join(" ");
+        if phrases
+            .iter()
+            .any(|p| p.phrase.eq_ignore_ascii_case(&phrase))
+        {
+            continue;
+        }
+        phrases.push(RepeatedPhrase {
+            phrase,
+            occurrences: seen.get(&key).copied().unwrap_or(0) + own.get(&key).copied().unwrap_or(1),
+        });
+    }
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.phrase.len()));
+    phrases.truncate(MAX_PHRASES);
+
+    let flagged = settings.enabled && score >= settings.threshold && !phrases.is_empty();
+    let regenerate = flagged && settings.auto_regenerate;
+    let regeneration_instruction = flagged.then(|| {
+        format!(
+            "Your previous attempt repeated wording from earlier in the story. Do not reuse these phrases: {}. \
+             Vary sentence structure, imagery, and word choice.",
+            phrases
+                .iter()
+                .map(|p| format!("\"{}\"", p.phrase))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    });
+
+    RepetitionReport {
+        entry_id: None,
+        score,
+        flagged,
+        phrases,
+        regenerate,
+        regeneration_instruction,
+    }
+}
+
+/// The `window` narration entries before `position` (or the latest ones)
+pub async fn recent_narration(
+    pool: &SqlitePool,
+    story_id: &str,
+    before_position: Option<i64>,
+    window: i64,
+) -> Result<Vec<String>, String> {
+    sqlx::query_scalar(
+        "SELECT content FROM story_entries \
+         WHERE story_id = ?1 AND type = 'narration' AND (?2 IS NULL OR position < ?2) \
+         ORDER BY position DESC LIMIT ?3",
+    )
+    .bind(story_id)
+    .bind(before_position)
+    .bind(window)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load recent entries: {}", e))
+}
+
+/// Background job: score an entry for repetition and emit `repetition-detected`
+/// when it crosses the threshold
+pub async fn run_repetition_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Repetition check job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content, position FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+    let position: i64 = row.get("position");
+
+    let settings = load_settings(pool).await?;
+    let history = recent_narration(pool, &story_id, Some(position), settings.window).await?;
+
+    let mut report = analyze(&content, &history, &settings);
+    report.entry_id = Some(entry_id.to_string());
+
+    if report.flagged {
+        if let Err(e) = app.emit("repetition-detected", &report) {
+            eprintln!("Failed to emit repetition event: {}", e);
+        }
+    }
+
+    serde_json::to_value(&report).map_err(|e| format!("Failed to serialize report: {}", e))
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::types::{PovPronouns, SentenceLengthBucket, StyleMetrics, WordCount};
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]+>").unwrap());
+static WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\p{L}\p{N}][\p{L}\p{N}'’-]*").unwrap());
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[.!?…]+["'”’)\]]*(\s+|$)"#).unwrap());
+static DIALOGUE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""[^"]*"|“[^”]*”"#).unwrap());
+
+/// Upper bounds (inclusive) of the sentence length histogram buckets
+const BUCKETS: &[usize] = &[5, 10, 20, 30, 40];
+
+const TOP_ADVERBS: usize = 10;
+
+/// Common '-ly' words that are not adverbs
+const NOT_ADVERBS: &[&str] = &[
+    "only", "family", "early", "ugly", "holy", "belly", "lily", "jelly", "rally", "ally", "reply",
+    "supply", "apply", "fly", "july", "italy", "silly", "lonely", "lovely", "friendly", "likely",
+    "deadly", "elderly", "costly", "curly", "daily", "weekly", "monthly", "yearly", "chilly",
+    "hilly", "oily", "woolly", "bully", "rely", "comply", "multiply", "imply", "assembly",
+];
+
+const FIRST_PERSON: &[&str] = &[
+    "i",
+    "me",
+    "my",
+    "mine",
+    "myself",
+    "we",
+    "us",
+    "our",
+    "ours",
+    "ourselves",
+];
+const SECOND_PERSON: &[&str] = &["you", "your", "yours", "yourself", "yourselves"];
+const THIRD_PERSON: &[&str] = &[
+    "he",
+    "him",
+    "his",
+    "himself",
+    "she",
+    "her",
+    "hers",
+    "herself",
+    "they",
+    "them",
+    "their",
+    "theirs",
+    "themselves",
+];
+
+/// Rough English syllable count: vowel groups, minus a silent final 'e'
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let chars: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    if chars.is_empty() {
+        return 0;
+    }
+    let is_vowel = |c: char| "aeiouy".contains(c);
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &c in &chars {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    let n = chars.len();
+    if n > 2 && chars[n - 1] == 'e' && chars[n - 2] != 'l' && !is_vowel(chars[n - 2]) {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+fn is_adverb(word: &str) -> bool {
+    word.chars().count() > 4 && word.ends_with("ly") && !NOT_ADVERBS.contains(&word)
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Compute style metrics over narration texts. `expected_pov` is the story's
+/// configured POV, used to judge pronoun consistency.
+pub fn compute(texts: &[String], expected_pov: Option<&str>) -> StyleMetrics {
+    let mut sentence_lengths: Vec<usize> = Vec::new();
+    let mut word_count = 0;
+    let mut syllable_count = 0;
+    let mut total_chars = 0;
+    let mut dialogue_chars = 0;
+    let mut adverbs: HashMap<String, usize> = HashMap::new();
+    let mut pov = PovPronouns {
+        expected: expected_pov.map(str::to_string),
+        ..Default::default()
+    };
+
+    for text in texts {
+        let text = TAG.replace_all(text, " ");
+
+        total_chars += text.chars().filter(|c| !c.is_whitespace()).count();
+        dialogue_chars += DIALOGUE
+            .find_iter(&text)
+            .map(|m| m.as_str().chars().filter(|c| !c.is_whitespace()).count())
+            .sum::<usize>();
+
+        for sentence in SENTENCE_END.split(&text) {
+            let words = WORD.find_iter(sentence).count();
+            if words > 0 {
+                sentence_lengths.push(words);
+            }
+        }
+
+        for word in WORD.find_iter(&text) {
+            let lower = word.as_str().to_lowercase();
+            word_count += 1;
+            syllable_count += syllables(&lower);
+            if is_adverb(&lower) {
+                *adverbs.entry(lower).or_default() += 1;
+            }
+        }
+
+        // Pronouns in dialogue belong to the speaker, not the narration
+        let narration = DIALOGUE.replace_all(&text, " ");
+        for word in WORD.find_iter(&narration) {
+            let lower = word.as_str().to_lowercase().replace('’', "'");
+            let lower = lower.split('\'').next().unwrap_or_default();
+            if FIRST_PERSON.contains(&lower) {
+                pov.first += 1;
+            } else if SECOND_PERSON.contains(&lower) {
+                pov.second += 1;
+            } else if THIRD_PERSON.contains(&lower) {
+                pov.third += 1;
+            }
+        }
+    }
+
+    let pronouns = pov.first + pov.second + pov.third;
+    let matching = match pov.expected.as_deref() {
+        Some("first") => pov.first,
+        Some("second") => pov.second,
+        Some("third") => pov.third,
+        _ => pov.first.max(pov.second).max(pov.third),
+    };
+    pov.consistency = if pronouns == 0 {
+        1.0
+    } else {
+        ratio(matching, pronouns)
+    };
+
+    let sentence_count = sentence_lengths.len();
+    let words_per_sentence = ratio(word_count, sentence_count);
+    let syllables_per_word = ratio(syllable_count, word_count);
+    let (flesch_reading_ease, flesch_kincaid_grade) = if word_count == 0 {
+        (0.0, 0.0)
+    } else {
+        (
+            206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+        )
+    };
+
+    let mut sorted = sentence_lengths.clone();
+    sorted.sort_unstable();
+    let median_sentence_length = match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0,
+        n => sorted[n / 2] as f64,
+    };
+    let variance = if sentence_count == 0 {
+        0.0
+    } else {
+        sentence_lengths
+            .iter()
+            .map(|&l| (l as f64 - words_per_sentence).powi(2))
+            .sum::<f64>()
+            / sentence_count as f64
+    };
+
+    let mut buckets: Vec<SentenceLengthBucket> = Vec::new();
+    let mut min = 1;
+    for &max in BUCKETS {
+        buckets.push(SentenceLengthBucket {
+            min,
+            max: Some(max),
+            count: sentence_lengths
+                .iter()
+                .filter(|&&l| l >= min && l <= max)
+                .count(),
+        });
+        min = max + 1;
+    }
+    buckets.push(SentenceLengthBucket {
+        min,
+        max: None,
+        count: sentence_lengths.iter().filter(|&&l| l >= min).count(),
+    });
+
+    let adverb_count: usize = adverbs.values().sum();
+    let mut top_adverbs: Vec<WordCount> = adverbs
+        .into_iter()
+        .map(|(word, count)| WordCount { word, count })
+        .collect();
+    top_adverbs.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    top_adverbs.truncate(TOP_ADVERBS);
+
+    let mut metrics = StyleMetrics {
+        entry_count: texts.len(),
+        word_count,
+        sentence_count,
+        flesch_reading_ease,
+        flesch_kincaid_grade,
+        mean_sentence_length: words_per_sentence,
+        median_sentence_length,
+        sentence_length_std_dev: variance.sqrt(),
+        sentence_lengths: buckets,
+        dialogue_ratio: ratio(dialogue_chars, total_chars),
+        adverb_density: ratio(adverb_count, word_count) * 100.0,
+        top_adverbs,
+        pov,
+        summary: String::new(),
+    };
+    metrics.summary = summarize(&metrics);
+    metrics
+}
+
+fn summarize(m: &StyleMetrics) -> String {
+    let mut lines = vec![
+        format!(
+            "- Flesch reading ease {:.0}, grade level {:.1}",
+            m.flesch_reading_ease, m.flesch_kincaid_grade
+        ),
+        format!(
+            "- Sentence length: mean {:.1} words, median {:.1}, spread {:.1}",
+            m.mean_sentence_length, m.median_sentence_length, m.sentence_length_std_dev
+        ),
+        format!("- Dialogue: {:.0}% of text", m.dialogue_ratio * 100.0),
+        format!("- '-ly' adverbs: {:.1} per 100 words", m.adverb_density),
+    ];
+    if !m.top_adverbs.is_empty() {
+        lines.push(format!(
+            "- Most used adverbs: {}",
+            m.top_adverbs
+                .iter()
+                .map(|a| format!("{} ({})", a.word, a.count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if let Some(expected) = &m.pov.expected {
+        lines.push(format!(
+            "- {:.0}% of narration pronouns match the {}-person POV",
+            m.pov.consistency * 100.0,
+            expected
+        ));
+    }
+    lines.join("\n")
+}
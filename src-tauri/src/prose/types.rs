@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+/// User settings for the repetition detector (`repetition_detection_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RepetitionSettings {
+    pub enabled: bool,
+    /// Word n-gram length compared against earlier entries
+    pub ngram_size: usize,
+    /// How many recent narration entries to compare against
+    pub window: i64,
+    /// Share of the response's n-grams seen before (0-1) that flags it
+    pub threshold: f64,
+    /// Ask the frontend to regenerate flagged responses automatically
+    pub auto_regenerate: bool,
+}
+
+impl Default for RepetitionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ngram_size: 4,
+            window: 10,
+            threshold: 0.2,
+            auto_regenerate: false,
+        }
+    }
+}
+
+/// A run of words the response shares with recent entries or repeats itself
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeatedPhrase {
+    pub phrase: String,
+    /// Times the phrase appears in the response and the compared entries
+    pub occurrences: usize,
+}
+
+/// Repetition analysis of one response
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepetitionReport {
+    pub entry_id: Option<String>,
+    /// Share of the response's n-grams that already appeared (0-1)
+    pub score: f64,
+    pub flagged: bool,
+    pub phrases: Vec<RepeatedPhrase>,
+    /// Set on flagged reports when auto-regeneration is enabled
+    pub regenerate: bool,
+    /// Instruction to add to the regeneration prompt
+    pub regeneration_instruction: Option<String>,
+}
+
+/// Counts of sentences by word length
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentenceLengthBucket {
+    /// Inclusive word range, `max` is `None` for the open-ended last bucket
+    pub min: usize,
+    pub max: Option<usize>,
+    pub count: usize,
+}
+
+/// A word and how often it was used
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordCount {
+    pub word: String,
+    pub count: usize,
+}
+
+/// Narration pronoun counts outside dialogue, by point of view
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PovPronouns {
+    pub first: usize,
+    pub second: usize,
+    pub third: usize,
+    /// The story's configured POV ('first' | 'second' | 'third'), if set
+    pub expected: Option<String>,
+    /// Share of POV pronouns matching the expected POV (or the dominant one)
+    pub consistency: f64,
+}
+
+/// Readability and style numbers for a chapter or set of entries
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleMetrics {
+    pub entry_count: usize,
+    pub word_count: usize,
+    pub sentence_count: usize,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub mean_sentence_length: f64,
+    pub median_sentence_length: f64,
+    pub sentence_length_std_dev: f64,
+    pub sentence_lengths: Vec<SentenceLengthBucket>,
+    /// Share of characters inside quotation marks (0-1)
+    pub dialogue_ratio: f64,
+    /// '-ly' adverbs per 100 words
+    pub adverb_density: f64,
+    pub top_adverbs: Vec<WordCount>,
+    pub pov: PovPronouns,
+    /// Plain-text digest for the style reviewer prompt
+    pub summary: String,
+}
+
+/// User settings for entity name checking (`entity_consistency_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EntitySettings {
+    pub enabled: bool,
+    /// Replace likely misspellings with the canonical name instead of only warning
+    pub auto_correct: bool,
+    /// Largest edit distance treated as a misspelling for longer names
+    pub max_distance: usize,
+}
+
+impl Default for EntitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auto_correct: false,
+            max_distance: 2,
+        }
+    }
+}
+
+/// A character or lorebook entry whose name the story should spell consistently
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanonicalEntity {
+    pub name: String,
+    /// 'character' or the lorebook entry type
+    pub kind: String,
+    pub aliases: Vec<String>,
+}
+
+/// A word in generated text that looks like a misspelled entity name
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityWarning {
+    pub found: String,
+    /// The name word it most likely should be
+    pub expected: String,
+    /// Full name of the entity the word belongs to
+    pub entity: String,
+    /// Character offsets in the checked text
+    pub start: usize,
+    pub end: usize,
+    pub distance: usize,
+    pub corrected: bool,
+}
+
+/// Result of checking text against the story's entities
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityCheck {
+    /// The text, with corrections applied when auto-correct is on
+    pub text: String,
+    pub warnings: Vec<EntityWarning>,
+}
@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use super::store::load_quests;
+use crate::db::now_millis;
+use crate::jobs::types::BackgroundJob;
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You track quest progress in an interactive story. \
+Given the active quests with their objectives and the latest passage, report any objective the passage \
+clearly completes or fails, and any quest that is resolved outright. Do not guess: only report what the passage shows. \
+Respond with JSON only, in this shape: \
+{\"suggestions\": [{\"questId\": \"<id>\", \"objectiveId\": \"<id, or null for the whole quest>\", \
+\"status\": \"completed\" | \"failed\", \"confidence\": <0 to 1>, \"reason\": \"<short explanation>\"}]}. \
+Respond with {\"suggestions\": []} if nothing changed.";
+
+/// Suggestions below this confidence are discarded
+const MIN_CONFIDENCE: f64 = 0.5;
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "quest_analysis",
+        schema: json!({
+            "type": "object",
+            "required": ["suggestions"],
+            "properties": {
+                "suggestions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["questId", "status"],
+                        "properties": {
+                            "questId": { "type": "string" },
+                            "objectiveId": { "type": ["string", "null"] },
+                            "status": { "enum": ["completed", "failed"] },
+                            "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                            "reason": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnalysisReply {
+    suggestions: Vec<SuggestedChange>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SuggestedChange {
+    quest_id: String,
+    #[serde(default)]
+    objective_id: Option<String>,
+    status: String,
+    #[serde(default)]
+    confidence: f64,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Background job: ask the classification model whether an entry advances any active quest.
+///
+/// Results are stored as pending suggestions for the user to accept or dismiss.
+pub async fn run_analysis_job(
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Quest analysis job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let quests = load_quests(pool, &story_id, Some("active"))
+        .await
+        .map_err(|e| format!("Failed to load quests: {}", e))?;
+    if quests.is_empty() {
+        return Ok(json!({ "entryId": entry_id, "suggestions": [] }));
+    }
+
+    let listing = quests
+        .iter()
+        .map(|q| {
+            let objectives = q
+                .objectives
+                .iter()
+                .filter(|o| o.status == "active" || o.status == "pending")
+                .map(|o| format!("  - [{}] ({}) {}", o.id, o.status, o.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("Quest [{}]: {}\n{}", q.id, q.title, objectives)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let prompt = format!("Active quests:\n{}\n\nPassage:\n{}", listing, content);
+
+    let llm = config::resolve_service(pool, "questAnalysis", "classification").await?;
+    let parsed: AnalysisReply = complete_structured(
+        pool,
+        "questAnalysis",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    let mut stored = Vec::new();
+    for change in parsed.suggestions {
+        if change.confidence < MIN_CONFIDENCE
+            || !matches!(change.status.as_str(), "completed" | "failed")
+        {
+            continue;
+        }
+        // Drop ids the model invented
+        let Some(quest) = quests.iter().find(|q| q.id == change.quest_id) else {
+            continue;
+        };
+        let objective_id = change
+            .objective_id
+            .filter(|id| !id.is_empty() && id != "null");
+        if let Some(id) = &objective_id {
+            if !quest.objectives.iter().any(|o| &o.id == id) {
+                continue;
+            }
+        }
+
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO quest_suggestions \
+             (id, story_id, quest_id, objective_id, entry_id, suggested_status, confidence, reason, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&story_id)
+        .bind(&quest.id)
+        .bind(&objective_id)
+        .bind(entry_id)
+        .bind(&change.status)
+        .bind(change.confidence.min(1.0))
+        .bind(&change.reason)
+        .bind(now_millis())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save quest suggestion: {}", e))?;
+        stored.push(json!({
+            "id": id,
+            "questId": quest.id,
+            "objectiveId": objective_id,
+            "status": change.status,
+        }));
+    }
+
+    Ok(json!({ "entryId": entry_id, "suggestions": stored }))
+}
@@ -0,0 +1,160 @@
+use serde_json::json;
+use tauri::State;
+
+use super::store::{self, load_quests, load_suggestions};
+use super::types::{NewQuest, Quest, QuestSuggestion};
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+/// Get a story's quests, optionally filtered by status
+#[tauri::command]
+pub async fn get_quests(
+    db: State<'_, DbState>,
+    story_id: String,
+    status: Option<String>,
+) -> Result<Vec<Quest>, String> {
+    load_quests(db.pool(), &story_id, status.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load quests: {}", e))
+}
+
+/// Get the quests currently in progress
+#[tauri::command]
+pub async fn get_active_quests(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<Quest>, String> {
+    load_quests(db.pool(), &story_id, Some("active"))
+        .await
+        .map_err(|e| format!("Failed to load quests: {}", e))
+}
+
+/// Create a quest with ordered objectives, returning its id
+#[tauri::command]
+pub async fn create_quest(
+    db: State<'_, DbState>,
+    story_id: String,
+    quest: NewQuest,
+) -> Result<String, String> {
+    store::create_quest(db.pool(), &story_id, quest).await
+}
+
+/// Delete a quest and its objectives
+#[tauri::command]
+pub async fn delete_quest(db: State<'_, DbState>, quest_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM quests WHERE id = ?")
+        .bind(&quest_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete quest: {}", e))?;
+    Ok(())
+}
+
+/// Change a quest's status
+#[tauri::command]
+pub async fn set_quest_status(
+    db: State<'_, DbState>,
+    quest_id: String,
+    status: String,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    store::set_quest_status(&mut tx, &quest_id, &status).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit quest status: {}", e))
+}
+
+/// Change an objective's status
+#[tauri::command]
+pub async fn set_objective_status(
+    db: State<'_, DbState>,
+    objective_id: String,
+    status: String,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    store::set_objective_status(&mut tx, &objective_id, &status).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit objective status: {}", e))
+}
+
+/// Queue background analysis of an entry for quest progress
+#[tauri::command]
+pub async fn queue_quest_analysis(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::QuestAnalysis,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
+
+/// Get pending quest suggestions produced by background analysis
+#[tauri::command]
+pub async fn get_quest_suggestions(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<QuestSuggestion>, String> {
+    load_suggestions(db.pool(), &story_id)
+        .await
+        .map_err(|e| format!("Failed to load quest suggestions: {}", e))
+}
+
+/// Accept (applying the suggested status) or dismiss a quest suggestion
+#[tauri::command]
+pub async fn resolve_quest_suggestion(
+    db: State<'_, DbState>,
+    suggestion_id: String,
+    accept: bool,
+) -> Result<(), String> {
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let (quest_id, objective_id, suggested): (String, Option<String>, String) = sqlx::query_as(
+        "SELECT quest_id, objective_id, suggested_status FROM quest_suggestions WHERE id = ? AND status = 'pending'",
+    )
+    .bind(&suggestion_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load suggestion: {}", e))?
+    .ok_or_else(|| format!("Suggestion not found: {}", suggestion_id))?;
+
+    if accept {
+        match &objective_id {
+            Some(objective_id) => {
+                store::set_objective_status(&mut tx, objective_id, &suggested).await?
+            }
+            None => store::set_quest_status(&mut tx, &quest_id, &suggested).await?,
+        }
+    }
+
+    sqlx::query("UPDATE quest_suggestions SET status = ? WHERE id = ?")
+        .bind(if accept { "accepted" } else { "dismissed" })
+        .bind(&suggestion_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update suggestion: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit suggestion: {}", e))
+}
@@ -0,0 +1,4 @@
+pub mod analysis;
+pub mod commands;
+pub mod store;
+pub mod types;
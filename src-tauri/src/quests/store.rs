@@ -0,0 +1,322 @@
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use super::types::{
+    objective_transition_allowed, quest_transition_allowed, NewQuest, Quest, QuestObjective,
+    QuestSuggestion, OBJECTIVE_STATUSES, QUEST_STATUSES,
+};
+use crate::db::now_millis;
+
+/// Load a story's quests with their objectives, optionally filtered by status
+pub async fn load_quests(
+    pool: &SqlitePool,
+    story_id: &str,
+    status: Option<&str>,
+) -> Result<Vec<Quest>, sqlx::Error> {
+    let quest_rows = sqlx::query(
+        "SELECT id, story_id, beat_id, title, description, status, created_at, updated_at, resolved_at \
+         FROM quests WHERE story_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY created_at",
+    )
+    .bind(story_id)
+    .bind(status)
+    .fetch_all(pool)
+    .await?;
+
+    let objective_rows = sqlx::query(
+        "SELECT o.id, o.quest_id, o.position, o.description, o.status, o.completed_at \
+         FROM quest_objectives o JOIN quests q ON q.id = o.quest_id \
+         WHERE q.story_id = ? ORDER BY o.quest_id, o.position",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut quests: Vec<Quest> = quest_rows
+        .iter()
+        .map(|r| Quest {
+            id: r.get("id"),
+            story_id: r.get("story_id"),
+            beat_id: r.get("beat_id"),
+            title: r.get("title"),
+            description: r.get("description"),
+            status: r.get("status"),
+            objectives: Vec::new(),
+            created_at: r.get("created_at"),
+            updated_at: r.get("updated_at"),
+            resolved_at: r.get("resolved_at"),
+        })
+        .collect();
+
+    for r in &objective_rows {
+        let quest_id: String = r.get("quest_id");
+        if let Some(quest) = quests.iter_mut().find(|q| q.id == quest_id) {
+            quest.objectives.push(QuestObjective {
+                id: r.get("id"),
+                position: r.get("position"),
+                description: r.get("description"),
+                status: r.get("status"),
+                completed_at: r.get("completed_at"),
+            });
+        }
+    }
+
+    Ok(quests)
+}
+
+/// Insert a quest; its first objective starts active, the rest pending
+pub async fn create_quest(
+    pool: &SqlitePool,
+    story_id: &str,
+    quest: NewQuest,
+) -> Result<String, String> {
+    let title = quest.title.trim();
+    if title.is_empty() {
+        return Err("Quest title cannot be empty".to_string());
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let id = Uuid::new_v4().to_string();
+    let now = now_millis();
+
+    sqlx::query(
+        "INSERT INTO quests (id, story_id, beat_id, title, description, status, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, 'active', ?, ?)",
+    )
+    .bind(&id)
+    .bind(story_id)
+    .bind(&quest.beat_id)
+    .bind(title)
+    .bind(&quest.description)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create quest: {}", e))?;
+
+    let objectives = quest
+        .objectives
+        .iter()
+        .map(|o| o.trim())
+        .filter(|o| !o.is_empty());
+    for (position, description) in objectives.enumerate() {
+        sqlx::query(
+            "INSERT INTO quest_objectives (id, quest_id, position, description, status) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&id)
+        .bind(position as i64)
+        .bind(description)
+        .bind(if position == 0 { "active" } else { "pending" })
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create objective: {}", e))?;
+    }
+
+    if let Some(beat_id) = &quest.beat_id {
+        sqlx::query("UPDATE story_beats SET status = 'active' WHERE id = ? AND status = 'pending'")
+            .bind(beat_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to activate story beat: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit quest: {}", e))?;
+    Ok(id)
+}
+
+/// Move a quest to a new status, mirroring completion or failure onto its story beat
+pub async fn set_quest_status(
+    conn: &mut SqliteConnection,
+    quest_id: &str,
+    status: &str,
+) -> Result<(), String> {
+    if !QUEST_STATUSES.contains(&status) {
+        return Err(format!("Unknown quest status: {}", status));
+    }
+
+    let (current, beat_id): (String, Option<String>) =
+        sqlx::query_as("SELECT status, beat_id FROM quests WHERE id = ?")
+            .bind(quest_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to load quest: {}", e))?
+            .ok_or_else(|| format!("Quest not found: {}", quest_id))?;
+    if current == status {
+        return Ok(());
+    }
+    if !quest_transition_allowed(&current, status) {
+        return Err(format!(
+            "Cannot move quest from '{}' to '{}'",
+            current, status
+        ));
+    }
+
+    let now = now_millis();
+    let resolved_at = (status != "active").then_some(now);
+    sqlx::query("UPDATE quests SET status = ?, updated_at = ?, resolved_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(now)
+        .bind(resolved_at)
+        .bind(quest_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update quest: {}", e))?;
+
+    if let Some(beat_id) = beat_id {
+        let beat_status = match status {
+            "completed" => "completed",
+            "failed" => "failed",
+            _ => "active",
+        };
+        sqlx::query("UPDATE story_beats SET status = ?, resolved_at = ? WHERE id = ?")
+            .bind(beat_status)
+            .bind(resolved_at.filter(|_| beat_status != "active"))
+            .bind(beat_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to update story beat: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Move an objective to a new status.
+///
+/// Completing an objective activates the next pending one; completing the
+/// last one completes the quest.
+pub async fn set_objective_status(
+    conn: &mut SqliteConnection,
+    objective_id: &str,
+    status: &str,
+) -> Result<(), String> {
+    if !OBJECTIVE_STATUSES.contains(&status) {
+        return Err(format!("Unknown objective status: {}", status));
+    }
+
+    let (quest_id, position, current): (String, i64, String) =
+        sqlx::query_as("SELECT quest_id, position, status FROM quest_objectives WHERE id = ?")
+            .bind(objective_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to load objective: {}", e))?
+            .ok_or_else(|| format!("Objective not found: {}", objective_id))?;
+    if current == status {
+        return Ok(());
+    }
+    if !objective_transition_allowed(&current, status) {
+        return Err(format!(
+            "Cannot move objective from '{}' to '{}'",
+            current, status
+        ));
+    }
+
+    let completed_at = (status == "completed").then(now_millis);
+    sqlx::query("UPDATE quest_objectives SET status = ?, completed_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(completed_at)
+        .bind(objective_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update objective: {}", e))?;
+    sqlx::query("UPDATE quests SET updated_at = ? WHERE id = ?")
+        .bind(now_millis())
+        .bind(&quest_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update quest: {}", e))?;
+
+    if status != "completed" {
+        return Ok(());
+    }
+
+    let next: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM quest_objectives WHERE quest_id = ? AND position > ? AND status = 'pending' \
+         ORDER BY position LIMIT 1",
+    )
+    .bind(&quest_id)
+    .bind(position)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load objectives: {}", e))?;
+    if let Some(next) = next {
+        sqlx::query("UPDATE quest_objectives SET status = 'active' WHERE id = ?")
+            .bind(next)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to activate objective: {}", e))?;
+    }
+
+    let unfinished: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM quest_objectives WHERE quest_id = ? AND status != 'completed'",
+    )
+    .bind(&quest_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load objectives: {}", e))?;
+    let quest_status: String = sqlx::query_scalar("SELECT status FROM quests WHERE id = ?")
+        .bind(&quest_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to load quest: {}", e))?;
+    if unfinished == 0 && quest_status == "active" {
+        set_quest_status(conn, &quest_id, "completed").await?;
+    }
+    Ok(())
+}
+
+/// Pending suggestions for a story, newest first
+pub async fn load_suggestions(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<QuestSuggestion>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, quest_id, objective_id, entry_id, suggested_status, confidence, reason, created_at \
+         FROM quest_suggestions WHERE story_id = ? AND status = 'pending' ORDER BY created_at DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| QuestSuggestion {
+            id: r.get("id"),
+            quest_id: r.get("quest_id"),
+            objective_id: r.get("objective_id"),
+            entry_id: r.get("entry_id"),
+            suggested_status: r.get("suggested_status"),
+            confidence: r.get("confidence"),
+            reason: r.get("reason"),
+            created_at: r.get("created_at"),
+        })
+        .collect())
+}
+
+/// Render active quests and their current objectives for prompt injection
+pub fn format_quest_block(quests: &[Quest]) -> String {
+    quests
+        .iter()
+        .filter(|q| q.status == "active")
+        .map(|q| {
+            let mut text = format!("Quest: {}", q.title);
+            if let Some(description) = q.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                text.push_str(&format!(" — {}", description.trim()));
+            }
+            for objective in &q.objectives {
+                let mark = match objective.status.as_str() {
+                    "completed" => "[x]",
+                    "failed" => "[-]",
+                    "active" => "[>]",
+                    _ => "[ ]",
+                };
+                text.push_str(&format!("\n  {} {}", mark, objective.description));
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// A multi-step quest, optionally tied to a story beat
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quest {
+    pub id: String,
+    pub story_id: String,
+    pub beat_id: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    /// 'active' | 'completed' | 'failed' | 'abandoned'
+    pub status: String,
+    pub objectives: Vec<QuestObjective>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub resolved_at: Option<i64>,
+}
+
+/// One ordered step of a quest
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestObjective {
+    pub id: String,
+    pub position: i64,
+    pub description: String,
+    /// 'pending' | 'active' | 'completed' | 'failed'
+    pub status: String,
+    pub completed_at: Option<i64>,
+}
+
+/// Input for creating a quest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewQuest {
+    pub title: String,
+    pub description: Option<String>,
+    pub beat_id: Option<String>,
+    pub objectives: Vec<String>,
+}
+
+/// A status change proposed by background analysis, awaiting user confirmation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestSuggestion {
+    pub id: String,
+    pub quest_id: String,
+    pub objective_id: Option<String>,
+    pub entry_id: String,
+    pub suggested_status: String,
+    pub confidence: f64,
+    pub reason: Option<String>,
+    pub created_at: i64,
+}
+
+pub const QUEST_STATUSES: &[&str] = &["active", "completed", "failed", "abandoned"];
+pub const OBJECTIVE_STATUSES: &[&str] = &["pending", "active", "completed", "failed"];
+
+/// Whether a quest may move from one status to another
+pub fn quest_transition_allowed(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("active", "completed" | "failed" | "abandoned") | ("abandoned" | "failed", "active")
+    )
+}
+
+/// Whether an objective may move from one status to another
+pub fn objective_transition_allowed(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("pending", "active" | "completed" | "failed")
+            | ("active", "completed" | "failed")
+            | ("completed" | "failed", "active")
+    )
+}
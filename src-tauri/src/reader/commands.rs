@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use super::server::{build_router, ReaderServerState};
+use super::types::WebReaderInfo;
+use crate::db::DbState;
+use crate::sync::commands::{generate_qr_code, get_local_ip};
+use crate::sync::server::bind_listener;
+
+/// State managed by Tauri for the web reader
+#[derive(Default)]
+pub struct ReaderState {
+    server_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    info: Arc<Mutex<Option<WebReaderInfo>>>,
+}
+
+/// Serve a story read-only to browsers on the local network, for devices
+/// without the app. The link carries a fresh viewing token; stopping the
+/// reader or starting another invalidates it.
+#[tauri::command]
+pub async fn start_web_reader(
+    db: State<'_, DbState>,
+    state: State<'_, ReaderState>,
+    story_id: String,
+) -> Result<WebReaderInfo, String> {
+    stop_web_reader(state.clone()).await?;
+
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM stories WHERE id = ?")
+        .bind(&story_id)
+        .fetch_optional(db.pool())
+        .await
+        .map_err(|e| format!("Failed to load story: {}", e))?;
+    if exists.is_none() {
+        return Err(format!("Story not found: {}", story_id));
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    let listener = bind_listener().await?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get local address: {}", e))?
+        .port();
+    let url = format!("http://{}:{}/read?token={}", get_local_ip()?, port, token);
+    let info = WebReaderInfo {
+        story_id: story_id.clone(),
+        qr_code_base64: generate_qr_code(&url)?,
+        url,
+    };
+
+    let router = build_router(ReaderServerState {
+        pool: db.pool().clone(),
+        story_id,
+        token,
+    });
+    let handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            eprintln!("Web reader error: {}", e);
+        }
+    });
+
+    *state.server_handle.lock().await = Some(handle);
+    *state.info.lock().await = Some(info.clone());
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn stop_web_reader(state: State<'_, ReaderState>) -> Result<(), String> {
+    if let Some(handle) = state.server_handle.lock().await.take() {
+        handle.abort();
+    }
+    *state.info.lock().await = None;
+    Ok(())
+}
+
+/// The running web reader, if any
+#[tauri::command]
+pub async fn get_web_reader_status(
+    state: State<'_, ReaderState>,
+) -> Result<Option<WebReaderInfo>, String> {
+    Ok(state.info.lock().await.clone())
+}
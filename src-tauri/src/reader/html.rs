@@ -0,0 +1,93 @@
+//! HTML helpers for the web reader. Story pages come from the HTML export
+//! template; this adds the reader's page navigation to them.
+
+/// Entries per page; small enough to load quickly on a phone
+pub const PAGE_SIZE: usize = 40;
+
+/// How often the last page reloads, so readers follow a story in progress
+const REFRESH_SECS: u32 = 30;
+
+const STYLE: &str = "body{max-width:42rem;margin:0 auto;padding:1.5rem 1rem;\
+font:1.1rem/1.6 Georgia,serif;color:#222;background:#fbf8f1}\
+@media (prefers-color-scheme:dark){body{color:#ddd;background:#1b1a17}}";
+
+/// Added to exported pages for the navigation and dark mode
+const NAV_STYLE: &str = "@media (prefers-color-scheme:dark){body{color:#ddd;background:#1b1a17}\
+a{color:#9bd}}\
+nav{display:flex;justify-content:space-between;margin:2rem 0;font-family:sans-serif}";
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Blank lines split paragraphs, single newlines become line breaks
+pub fn paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>", escape(p).replace('\n', "<br>")))
+        .collect()
+}
+
+fn page_link(token: &str, page: usize, label: &str) -> String {
+    format!(
+        "<a href=\"/read?token={}&amp;page={}\">{}</a>",
+        escape(token),
+        page,
+        label
+    )
+}
+
+/// Turn a page rendered by the HTML exporter into one page of the reader:
+/// links to the pages either side, and the last page reloading itself
+pub fn paginate(html: &mut String, page: usize, pages: usize, token: &str) {
+    let mut head = format!("<style>{}</style>", NAV_STYLE);
+    if page == pages {
+        head.push_str(&format!(
+            "<meta http-equiv=\"refresh\" content=\"{}\">",
+            REFRESH_SECS
+        ));
+    }
+    if let Some(at) = html.find("</head>") {
+        html.insert_str(at, &head);
+    }
+
+    let mut nav = String::from("<nav><span>");
+    if page > 1 {
+        nav.push_str(&page_link(token, page - 1, "&larr; Previous"));
+    }
+    nav.push_str(&format!(
+        "</span><span>Page {} of {}</span><span>",
+        page, pages
+    ));
+    if page < pages {
+        nav.push_str(&page_link(token, page + 1, "Next &rarr;"));
+    }
+    nav.push_str("</span></nav>");
+    match html.rfind("</body>") {
+        Some(at) => html.insert_str(at, &nav),
+        None => html.push_str(&nav),
+    }
+}
+
+/// Shown for a missing or wrong token, and when the story is gone
+pub fn render_message(message: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+         <title>Aventura</title><style>{}</style></head><body><p>{}</p></body></html>",
+        STYLE,
+        escape(message)
+    )
+}
@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod html;
+pub mod server;
+pub mod types;
+
+pub use commands::ReaderState;
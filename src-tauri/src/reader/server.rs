@@ -0,0 +1,90 @@
+//! Read-only HTTP server that shows one story to browsers on the LAN.
+//! Pages are rendered from the database on every request, through the HTML
+//! exporter, so readers see new entries as they're written.
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use subtle::ConstantTimeEq;
+
+use super::html::{paginate, render_message, PAGE_SIZE};
+use crate::calendar::commands::load_calendar;
+use crate::export_templates::render::render;
+use crate::export_templates::store::{load_export_data, load_template};
+
+/// The exporter's web page template, which pages are rendered with
+const HTML_TEMPLATE: &str = "builtin:html";
+
+#[derive(Clone)]
+pub struct ReaderServerState {
+    pub pool: SqlitePool,
+    pub story_id: String,
+    /// Viewing token every request must carry
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+struct ReadQuery {
+    token: Option<String>,
+    /// Defaults to the latest page, for following along
+    page: Option<usize>,
+}
+
+pub fn build_router(state: ReaderServerState) -> Router {
+    Router::new()
+        .route("/", get(read))
+        .route("/read", get(read))
+        .with_state(state)
+}
+
+async fn read(
+    State(state): State<ReaderServerState>,
+    Query(query): Query<ReadQuery>,
+) -> (StatusCode, Html<String>) {
+    // Compared in constant time, so response timing doesn't leak the token
+    let valid: bool = query
+        .token
+        .as_deref()
+        .is_some_and(|token| token.as_bytes().ct_eq(state.token.as_bytes()).into());
+    if !valid {
+        return (
+            StatusCode::FORBIDDEN,
+            Html(render_message(
+                "This link isn't valid any more. Ask for a new one from the Aventura app.",
+            )),
+        );
+    }
+    match load_page(&state, query.page).await {
+        Ok(html) => (StatusCode::OK, Html(html)),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Html(render_message(&e))),
+    }
+}
+
+async fn load_page(state: &ReaderServerState, page: Option<usize>) -> Result<String, String> {
+    let pool = &state.pool;
+    let template = load_template(pool, HTML_TEMPLATE).await?;
+    let mut data = load_export_data(pool, &state.story_id, &template.format).await?;
+    let calendar = load_calendar(pool, &state.story_id).await?;
+    let player = data
+        .characters
+        .iter()
+        .find(|c| c.relationship.as_deref() == Some("self"))
+        .map(|c| c.name.clone());
+
+    let pages = data.entries.len().div_ceil(PAGE_SIZE).max(1);
+    let page = page.unwrap_or(pages).clamp(1, pages);
+    let start = (page - 1) * PAGE_SIZE;
+    let end = (start + PAGE_SIZE).min(data.entries.len());
+    data.entries = data.entries.drain(start..end).collect();
+    if page > 1 {
+        data.story.description = None;
+    }
+
+    let mut html = render(&template.body, &data, calendar, player)?;
+    paginate(&mut html, page, pages, &state.token);
+    Ok(html)
+}
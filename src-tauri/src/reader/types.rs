@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+/// Returned when the web reader starts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebReaderInfo {
+    pub story_id: String,
+    /// Full link including the viewing token, for typing or sharing
+    pub url: String,
+    pub qr_code_base64: String,
+}
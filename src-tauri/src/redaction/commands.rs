@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use tauri::State;
+
+use super::redactor::{redact_story_json, Redactor};
+use super::store;
+use super::types::{RedactionHit, RedactionPreview, RedactionRule, RedactionRuleInput};
+use crate::context::branch::visible_entries;
+use crate::db::DbState;
+
+/// Hits listed by a preview; the rest are only counted
+const PREVIEW_HITS: usize = 200;
+
+#[tauri::command]
+pub async fn get_redaction_rules(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<RedactionRule>, String> {
+    store::load_rules(db.pool(), &story_id).await
+}
+
+#[tauri::command]
+pub async fn save_redaction_rule(
+    db: State<'_, DbState>,
+    story_id: String,
+    input: RedactionRuleInput,
+) -> Result<RedactionRule, String> {
+    store::save_rule(db.pool(), &story_id, input).await
+}
+
+#[tauri::command]
+pub async fn delete_redaction_rule(db: State<'_, DbState>, rule_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM redaction_rules WHERE id = ?")
+        .bind(&rule_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete redaction rule: {}", e))?;
+    Ok(())
+}
+
+/// Show what the story's enabled rules would mask in `text`, or in the
+/// entries on its current branch when no text is given
+#[tauri::command]
+pub async fn preview_redaction(
+    db: State<'_, DbState>,
+    story_id: String,
+    text: Option<String>,
+) -> Result<RedactionPreview, String> {
+    let pool = db.pool();
+    let redactor = Redactor::load(pool, &story_id).await?;
+    let texts: Vec<(Option<String>, String)> = match text {
+        Some(text) => vec![(None, text)],
+        None => visible_entries(pool, &story_id)
+            .await?
+            .into_iter()
+            .map(|e| (Some(e.id), e.content))
+            .collect(),
+    };
+
+    let mut preview = RedactionPreview {
+        hits: Vec::new(),
+        total: 0,
+        by_rule: HashMap::new(),
+    };
+    for (entry_id, text) in &texts {
+        redactor.hits(text, |rule_id, matched, replacement, context| {
+            preview.total += 1;
+            *preview.by_rule.entry(rule_id.to_string()).or_default() += 1;
+            if preview.hits.len() < PREVIEW_HITS {
+                preview.hits.push(RedactionHit {
+                    rule_id: rule_id.to_string(),
+                    entry_id: entry_id.clone(),
+                    matched: matched.to_string(),
+                    replacement: replacement.to_string(),
+                    context,
+                });
+            }
+        });
+    }
+    Ok(preview)
+}
+
+/// Apply the story's redaction rules to an Aventura export before it is
+/// saved or shared
+#[tauri::command]
+pub async fn redact_story_export(
+    db: State<'_, DbState>,
+    story_json: String,
+) -> Result<String, String> {
+    redact_story_json(db.pool(), &story_json).await
+}
@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod redactor;
+pub mod store;
+pub mod types;
@@ -0,0 +1,232 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use super::store::load_rules;
+
+/// Fields of an Aventura export holding ids, enums or images rather than
+/// text anyone wrote
+const SKIP_KEYS: &[&str] = &[
+    "id",
+    "type",
+    "kind",
+    "status",
+    "relationship",
+    "mode",
+    "role",
+    "version",
+    "createdBy",
+    "portrait",
+    "currentBgImage",
+    "embeddedImages",
+];
+
+/// Characters of text kept on each side of a previewed match
+const CONTEXT_CHARS: usize = 40;
+
+struct Rule {
+    id: String,
+    pattern: Regex,
+    replacement: String,
+}
+
+/// A story's enabled redaction rules, compiled, applied in the order they
+/// were created
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    let regex = RegexBuilder::new(pattern)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))?;
+    if regex.is_match("") {
+        return Err("The pattern must not match empty text".to_string());
+    }
+    Ok(regex)
+}
+
+/// Every name of a world entity on any branch: characters, locations and
+/// items by name, lore entries by name and aliases
+pub async fn entity_names(
+    pool: &SqlitePool,
+    story_id: &str,
+    entity_id: &str,
+) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for table in ["characters", "locations", "items"] {
+        let rows: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT name FROM {} WHERE story_id = ? AND (id = ? OR overrides_id = ?)",
+            table
+        ))
+        .bind(story_id)
+        .bind(entity_id)
+        .bind(entity_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load {}: {}", table, e))?;
+        names.extend(rows);
+    }
+    let lore: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT name, aliases FROM entries WHERE story_id = ? AND (id = ? OR overrides_id = ?)",
+    )
+    .bind(story_id)
+    .bind(entity_id)
+    .bind(entity_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load lore entries: {}", e))?;
+    for (name, aliases) in lore {
+        names.push(name);
+        names.extend(
+            aliases
+                .and_then(|raw| serde_json::from_str::<Vec<String>>(&raw).ok())
+                .unwrap_or_default(),
+        );
+    }
+
+    let mut names: Vec<String> = names
+        .iter()
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect();
+    // Longest first, so "Anna Maria" is masked whole before "Anna"
+    names.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(names)
+}
+
+/// Whole-word, case-insensitive match of any of `names`
+fn names_pattern(names: &[String]) -> Result<Option<Regex>, String> {
+    if names.is_empty() {
+        return Ok(None);
+    }
+    let alternatives: Vec<String> = names.iter().map(|n| regex::escape(n)).collect();
+    RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+        .case_insensitive(true)
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Failed to build name pattern: {}", e))
+}
+
+/// `text` around `start..end` with the match swapped for `replacement`
+fn context(text: &str, start: usize, end: usize, replacement: &str) -> String {
+    let before: Vec<char> = text[..start]
+        .chars()
+        .rev()
+        .take(CONTEXT_CHARS + 1)
+        .collect();
+    let after: Vec<char> = text[end..].chars().take(CONTEXT_CHARS + 1).collect();
+    let mut out = String::new();
+    if before.len() > CONTEXT_CHARS {
+        out.push('…');
+    }
+    out.extend(before.iter().take(CONTEXT_CHARS).rev());
+    out.push_str(replacement);
+    out.extend(after.iter().take(CONTEXT_CHARS));
+    if after.len() > CONTEXT_CHARS {
+        out.push('…');
+    }
+    out
+}
+
+impl Redactor {
+    pub async fn load(pool: &SqlitePool, story_id: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for rule in load_rules(pool, story_id).await? {
+            if !rule.enabled {
+                continue;
+            }
+            let pattern = match rule.kind.as_str() {
+                "entity" => {
+                    let entity_id = rule.entity_id.as_deref().unwrap_or_default();
+                    match names_pattern(&entity_names(pool, story_id, entity_id).await?)? {
+                        Some(pattern) => pattern,
+                        // The entity is gone
+                        None => continue,
+                    }
+                }
+                _ => compile(rule.pattern.as_deref().unwrap_or_default())?,
+            };
+            rules.push(Rule {
+                id: rule.id,
+                pattern,
+                replacement: rule.replacement,
+            });
+        }
+        Ok(Redactor { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for rule in &self.rules {
+            if let std::borrow::Cow::Owned(replaced) =
+                rule.pattern.replace_all(&text, NoExpand(&rule.replacement))
+            {
+                text = replaced;
+            }
+        }
+        text
+    }
+
+    /// Redact every string in a JSON document except ids, enum-like fields
+    /// and images
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    let skipped = SKIP_KEYS.contains(&key.as_str())
+                        || key.ends_with("Id")
+                        || key.ends_with("Ids")
+                        || key.ends_with("_id");
+                    if !skipped {
+                        self.redact_json(field);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Report what `redact` would mask in `text` as
+    /// `(rule id, matched text, replacement, context)`. Each rule sees the
+    /// text as the rules before it left it, as when redacting.
+    pub fn hits(&self, text: &str, mut hit: impl FnMut(&str, &str, &str, String)) {
+        let mut text = text.to_string();
+        for rule in &self.rules {
+            for found in rule.pattern.find_iter(&text) {
+                let context = context(&text, found.start(), found.end(), &rule.replacement);
+                hit(&rule.id, found.as_str(), &rule.replacement, context);
+            }
+            if let std::borrow::Cow::Owned(replaced) =
+                rule.pattern.replace_all(&text, NoExpand(&rule.replacement))
+            {
+                text = replaced;
+            }
+        }
+    }
+}
+
+/// Apply a story's redaction rules to its Aventura export JSON
+pub async fn redact_story_json(pool: &SqlitePool, story_json: &str) -> Result<String, String> {
+    let mut export: Value =
+        serde_json::from_str(story_json).map_err(|e| format!("Invalid story data: {}", e))?;
+    let story_id = export
+        .pointer("/story/id")
+        .and_then(Value::as_str)
+        .ok_or("The story data has no story id")?
+        .to_string();
+    let redactor = Redactor::load(pool, &story_id).await?;
+    if redactor.is_empty() {
+        return Ok(story_json.to_string());
+    }
+    redactor.redact_json(&mut export);
+    serde_json::to_string(&export).map_err(|e| format!("Failed to serialize story: {}", e))
+}
@@ -0,0 +1,93 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use super::redactor::{compile, entity_names};
+use super::types::{RedactionRule, RedactionRuleInput, DEFAULT_REPLACEMENT, KINDS};
+use crate::db::now_millis;
+
+const SELECT_RULE: &str = "SELECT id, story_id, kind, pattern, entity_id, replacement, enabled, \
+     created_at FROM redaction_rules";
+
+/// A story's rules in the order they are applied
+pub async fn load_rules(pool: &SqlitePool, story_id: &str) -> Result<Vec<RedactionRule>, String> {
+    sqlx::query_as(&format!(
+        "{} WHERE story_id = ? ORDER BY created_at, id",
+        SELECT_RULE
+    ))
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load redaction rules: {}", e))
+}
+
+/// Create or replace a rule. Patterns have to compile and entities have to
+/// exist in the story.
+pub async fn save_rule(
+    pool: &SqlitePool,
+    story_id: &str,
+    input: RedactionRuleInput,
+) -> Result<RedactionRule, String> {
+    if !KINDS.contains(&input.kind.as_str()) {
+        return Err(format!("Unknown redaction rule kind: {}", input.kind));
+    }
+    let pattern = input
+        .pattern
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    let entity_id = input.entity_id.filter(|id| !id.is_empty());
+    let (pattern, entity_id) = if input.kind == "pattern" {
+        compile(pattern.as_deref().ok_or("The rule needs a pattern")?)?;
+        (pattern, None)
+    } else {
+        let entity_id = entity_id.ok_or("The rule needs an entity")?;
+        if entity_names(pool, story_id, &entity_id).await?.is_empty() {
+            return Err(format!("Entity not found: {}", entity_id));
+        }
+        (None, Some(entity_id))
+    };
+
+    let created_at = match &input.id {
+        Some(id) => sqlx::query_scalar::<_, i64>(
+            "SELECT created_at FROM redaction_rules WHERE id = ? AND story_id = ?",
+        )
+        .bind(id)
+        .bind(story_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load redaction rule: {}", e))?
+        .ok_or_else(|| format!("Redaction rule not found: {}", id))?,
+        None => now_millis(),
+    };
+    let rule = RedactionRule {
+        id: input.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        story_id: story_id.to_string(),
+        kind: input.kind,
+        pattern,
+        entity_id,
+        replacement: input
+            .replacement
+            .filter(|r| !r.is_empty())
+            .unwrap_or_else(|| DEFAULT_REPLACEMENT.to_string()),
+        enabled: input.enabled,
+        created_at,
+    };
+    sqlx::query(
+        "INSERT INTO redaction_rules (id, story_id, kind, pattern, entity_id, replacement, \
+         enabled, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET kind = excluded.kind, pattern = excluded.pattern, \
+         entity_id = excluded.entity_id, replacement = excluded.replacement, \
+         enabled = excluded.enabled",
+    )
+    .bind(&rule.id)
+    .bind(&rule.story_id)
+    .bind(&rule.kind)
+    .bind(&rule.pattern)
+    .bind(&rule.entity_id)
+    .bind(&rule.replacement)
+    .bind(rule.enabled)
+    .bind(rule.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save redaction rule: {}", e))?;
+    Ok(rule)
+}
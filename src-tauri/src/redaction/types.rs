@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// Rule kinds: a regex, or the names of a world entity
+pub const KINDS: &[&str] = &["pattern", "entity"];
+
+pub const DEFAULT_REPLACEMENT: &str = "[redacted]";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    pub id: String,
+    pub story_id: String,
+    pub kind: String,
+    pub pattern: Option<String>,
+    /// Character, location, item or lore entry whose names are masked
+    pub entity_id: Option<String>,
+    pub replacement: String,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+/// A rule to create (no `id`) or replace
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRuleInput {
+    pub id: Option<String>,
+    pub kind: String,
+    pub pattern: Option<String>,
+    pub entity_id: Option<String>,
+    /// `[redacted]` when empty
+    pub replacement: Option<String>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// One piece of text a rule would mask
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionHit {
+    pub rule_id: String,
+    /// Entry holding the text; `None` when previewing given text
+    pub entry_id: Option<String>,
+    pub matched: String,
+    pub replacement: String,
+    /// The surrounding text with the match masked
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionPreview {
+    /// The first hits, in reading order
+    pub hits: Vec<RedactionHit>,
+    /// Every hit, including those not listed
+    pub total: usize,
+    /// Hits per rule id
+    pub by_rule: std::collections::HashMap<String, usize>,
+}
@@ -0,0 +1,3 @@
+pub mod paste;
+pub mod s3;
+pub mod webdav;
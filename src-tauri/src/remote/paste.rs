@@ -0,0 +1,83 @@
+//! Anonymous file hosts in the style of 0x0.st: POST a `file` form field,
+//! get the download URL back as the response body
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteConfig {
+    /// e.g. `https://0x0.st`
+    pub url: String,
+}
+
+/// Where an upload landed
+pub struct PasteUpload {
+    pub url: String,
+    /// Management token (0x0.st's `X-Token`) that lets whoever holds it
+    /// delete the file; not every host hands one out
+    pub token: Option<String>,
+}
+
+/// Upload `body` as `name`, asking the host to delete it after
+/// `expires_hours`. Hosts that don't support expiry ignore the field.
+pub async fn upload(
+    config: &PasteConfig,
+    name: &str,
+    body: Vec<u8>,
+    expires_hours: u32,
+) -> Result<PasteUpload, String> {
+    let file = reqwest::multipart::Part::bytes(body)
+        .file_name(name.to_string())
+        .mime_str("application/octet-stream")
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+    let form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("expires", expires_hours.to_string());
+    let response = reqwest::Client::new()
+        .post(&config.url)
+        // Some hosts refuse requests without a user agent
+        .header(reqwest::header::USER_AGENT, "Aventura")
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?;
+    let status = response.status();
+    let token = response
+        .headers()
+        .get("x-token")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("File host returned {}: {}", status, body.trim()));
+    }
+    let url = body.trim();
+    if !url.starts_with("http") {
+        return Err(format!("File host returned no link: {}", url));
+    }
+    Ok(PasteUpload {
+        url: url.to_string(),
+        token,
+    })
+}
+
+/// Delete an upload with the token the host gave for it
+pub async fn delete(url: &str, token: &str) -> Result<(), String> {
+    let form = reqwest::multipart::Form::new()
+        .text("token", token.to_string())
+        .text("delete", "");
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::USER_AGENT, "Aventura")
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Delete failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("File host returned {}", response.status()));
+    }
+    Ok(())
+}
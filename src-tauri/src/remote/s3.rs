@@ -0,0 +1,271 @@
+//! Minimal S3 client (AWS Signature V4, path-style URLs), enough for
+//! MinIO, Backblaze B2, R2 and AWS itself
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Longest a presigned URL may stay valid
+pub const MAX_PRESIGN_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Config {
+    /// e.g. `https://s3.us-west-004.backblazeb2.com` or `http://nas:9000`
+    pub endpoint: String,
+    /// `us-east-1` for services that ignore regions, like MinIO
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+pub struct S3Client {
+    config: S3Config,
+    http: reqwest::Client,
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// URI-encode as SigV4 wants: everything but unreserved characters, and
+/// `/` too unless it separates path segments
+fn encode(value: &str, keep_slash: bool) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Text of every `<tag>` element. S3 listings are flat enough that this
+/// beats pulling in an XML parser.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+impl S3Client {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn host(&self) -> Result<String, String> {
+        let url = reqwest::Url::parse(&self.config.endpoint)
+            .map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+        let host = url.host_str().ok_or("Invalid S3 endpoint: no host")?;
+        Ok(match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        })
+    }
+
+    fn path(&self, key: &str) -> String {
+        format!(
+            "/{}/{}",
+            encode(&self.config.bucket, false),
+            encode(key, true)
+        )
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.endpoint.trim_end_matches('/'), path)
+    }
+
+    fn scope(&self, now: &DateTime<Utc>) -> String {
+        format!(
+            "{}/{}/s3/aws4_request",
+            now.format("%Y%m%d"),
+            self.config.region
+        )
+    }
+
+    fn signature(&self, now: &DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            self.scope(now),
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let date = now.format("%Y%m%d").to_string();
+        let key = hmac(
+            format!("AWS4{}", self.config.secret_access_key).as_bytes(),
+            &date,
+        );
+        let key = hmac(&key, &self.config.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        hex::encode(hmac(&key, &string_to_sign))
+    }
+
+    /// Send a request signed in the `Authorization` header. An empty `key`
+    /// addresses the bucket itself.
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, String> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let host = self.host()?;
+        let path = self.path(key);
+        let mut query: Vec<String> = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", encode(name, false), encode(value, false)))
+            .collect();
+        query.sort();
+        let query = query.join("&");
+        let payload_hash = sha256_hex(&body);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, query, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id,
+            self.scope(&now),
+            signed_headers,
+            self.signature(&now, &canonical_request)
+        );
+
+        let mut url = self.url(&path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        let response = self
+            .http
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("S3 request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(format!("S3 returned {}: {}", status, detail.trim()));
+        }
+        Ok(response)
+    }
+
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<(), String> {
+        self.send(reqwest::Method::PUT, key, &[], body)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>, String> {
+        let response = self
+            .send(reqwest::Method::GET, key, &[], Vec::new())
+            .await?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("S3 download failed: {}", e))?;
+        Ok(bytes.to_vec())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), String> {
+        self.send(reqwest::Method::DELETE, key, &[], Vec::new())
+            .await
+            .map(|_| ())
+    }
+
+    /// Keys of every object under `prefix`, following pagination
+    pub async fn list_keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = token.as_deref() {
+                query.push(("continuation-token", token));
+            }
+            let response = self
+                .send(reqwest::Method::GET, "", &query, Vec::new())
+                .await?;
+            let xml = response
+                .text()
+                .await
+                .map_err(|e| format!("S3 listing failed: {}", e))?;
+            keys.extend(xml_values(&xml, "Key"));
+            token = xml_values(&xml, "NextContinuationToken").into_iter().next();
+            if token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    /// A URL anyone can GET `key` from for `expires_secs`, without credentials
+    pub fn presigned_get_url(&self, key: &str, expires_secs: u64) -> Result<String, String> {
+        self.presign("GET", key, expires_secs)
+    }
+
+    /// A URL anyone can DELETE `key` with for `expires_secs`, without credentials
+    pub fn presigned_delete_url(&self, key: &str, expires_secs: u64) -> Result<String, String> {
+        self.presign("DELETE", key, expires_secs)
+    }
+
+    fn presign(&self, method: &str, key: &str, expires_secs: u64) -> Result<String, String> {
+        let now = Utc::now();
+        let host = self.host()?;
+        let path = self.path(key);
+        let credential = format!("{}/{}", self.config.access_key_id, self.scope(&now));
+        // Already in sorted order, as the canonical query string needs
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            encode(&credential, false),
+            now.format("%Y%m%dT%H%M%SZ"),
+            expires_secs.min(MAX_PRESIGN_SECS)
+        );
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            method, path, query, host
+        );
+        Ok(format!(
+            "{}?{}&X-Amz-Signature={}",
+            self.url(&path),
+            query,
+            self.signature(&now, &canonical_request)
+        ))
+    }
+}
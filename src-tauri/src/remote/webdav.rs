@@ -0,0 +1,58 @@
+//! Uploads to a WebDAV folder (Nextcloud, ownCloud, a NAS...)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavConfig {
+    /// Folder to upload into, e.g. `https://cloud.example.com/remote.php/dav/files/me/aventura`
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Where uploaded files can be downloaded without logging in, if that
+    /// differs from `url` (a public share of the same folder)
+    pub public_url: Option<String>,
+}
+
+fn join(base: &str, name: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), name)
+}
+
+/// Upload `body` as `name` and return the URL to download it from
+pub async fn put(config: &WebDavConfig, name: &str, body: Vec<u8>) -> Result<String, String> {
+    let mut request = reqwest::Client::new()
+        .put(join(&config.url, name))
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(body);
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, config.password.as_deref());
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("WebDAV upload failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("WebDAV server returned {}", response.status()));
+    }
+    Ok(join(
+        config.public_url.as_deref().unwrap_or(&config.url),
+        name,
+    ))
+}
+
+/// Delete `name` from the folder; one that's already gone counts as deleted
+pub async fn delete(config: &WebDavConfig, name: &str) -> Result<(), String> {
+    let mut request = reqwest::Client::new().delete(join(&config.url, name));
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, config.password.as_deref());
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("WebDAV delete failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("WebDAV server returned {}", status));
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+
+use super::store::{blocked_reason, delete_sandbox, load_sandbox};
+use crate::db::{now_millis, DbState};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Delete expired sandboxes. One that's still open or that other branches
+/// fork from is kept until that's no longer so.
+pub async fn remove_expired(pool: &SqlitePool) -> Result<usize, String> {
+    let expired: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM branches WHERE sandbox_expires_at IS NOT NULL AND sandbox_expires_at <= ?",
+    )
+    .bind(now_millis())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load sandbox branches: {}", e))?;
+
+    let mut removed = 0;
+    for id in expired {
+        let sandbox = load_sandbox(pool, &id).await?;
+        if blocked_reason(pool, &sandbox).await?.is_none() {
+            delete_sandbox(pool, &id).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Clear out expired sandboxes now and every so often
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        loop {
+            // Fails until the frontend has run migrations
+            let _ = remove_expired(&pool).await;
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,48 @@
+use tauri::State;
+
+use super::store::{self, blocked_reason, load_sandbox};
+use super::types::SandboxBranch;
+use crate::db::DbState;
+
+/// Fork a throwaway branch at `entry_id` to try something out. It stays out
+/// of branch lists and sync, and is deleted after a few days unless promoted.
+#[tauri::command]
+pub async fn create_sandbox_branch(
+    db: State<'_, DbState>,
+    entry_id: String,
+    name: Option<String>,
+) -> Result<SandboxBranch, String> {
+    store::create_sandbox(db.pool(), &entry_id, name.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_sandbox_branches(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<SandboxBranch>, String> {
+    store::load_sandboxes(db.pool(), &story_id).await
+}
+
+/// Keep a sandbox as a regular branch
+#[tauri::command]
+pub async fn promote_sandbox_branch(
+    db: State<'_, DbState>,
+    branch_id: String,
+    name: Option<String>,
+) -> Result<(), String> {
+    store::promote_sandbox(db.pool(), &branch_id, name.as_deref()).await
+}
+
+/// Delete a sandbox now rather than waiting for it to expire
+#[tauri::command]
+pub async fn discard_sandbox_branch(
+    db: State<'_, DbState>,
+    branch_id: String,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let sandbox = load_sandbox(pool, &branch_id).await?;
+    if let Some(reason) = blocked_reason(pool, &sandbox).await? {
+        return Err(reason.to_string());
+    }
+    store::delete_sandbox(pool, &branch_id).await
+}
@@ -0,0 +1,4 @@
+pub mod cleanup;
+pub mod commands;
+pub mod store;
+pub mod types;
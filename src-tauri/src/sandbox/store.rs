@@ -0,0 +1,307 @@
+use serde_json::{Map, Value};
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use super::types::{SandboxBranch, SandboxSettings};
+use crate::backup::snapshot::{bind_value, quote, table_columns};
+use crate::context::branch::{current_branch, visible_entries, world_view};
+use crate::db::now_millis;
+use crate::journal::commands::BRANCH_TABLES;
+use crate::journal::store::capture_rows;
+use crate::llm::config::get_setting;
+
+/// World state a sandbox gets its own copy of. Locations come before items,
+/// which refer to them.
+const WORLD_TABLES: &[&str] = &["characters", "locations", "items", "story_beats", "entries"];
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<SandboxSettings, String> {
+    Ok(get_setting(pool, "sandbox_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+const SELECT_SANDBOX: &str = "SELECT id, story_id, name, parent_branch_id, fork_entry_id, \
+     sandbox_expires_at AS expires_at, created_at FROM branches";
+
+pub async fn load_sandboxes(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<SandboxBranch>, String> {
+    sqlx::query_as(&format!(
+        "{} WHERE story_id = ? AND sandbox_expires_at IS NOT NULL ORDER BY created_at",
+        SELECT_SANDBOX
+    ))
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load sandbox branches: {}", e))
+}
+
+pub async fn load_sandbox(pool: &SqlitePool, branch_id: &str) -> Result<SandboxBranch, String> {
+    sqlx::query_as(&format!(
+        "{} WHERE id = ? AND sandbox_expires_at IS NOT NULL",
+        SELECT_SANDBOX
+    ))
+    .bind(branch_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load sandbox branch: {}", e))?
+    .ok_or_else(|| format!("Sandbox branch not found: {}", branch_id))
+}
+
+fn text<'a>(row: &'a Map<String, Value>, column: &str) -> Option<&'a str> {
+    row.get(column).and_then(Value::as_str)
+}
+
+async fn insert_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    row: &Map<String, Value>,
+) -> Result<(), String> {
+    let columns = table_columns(&mut *conn, table).await?;
+    let present: Vec<(&String, &Value)> = row
+        .iter()
+        .filter(|(name, _)| columns.contains(name))
+        .collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote(table),
+        present
+            .iter()
+            .map(|(name, _)| quote(name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        vec!["?"; present.len()].join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for (_, value) in &present {
+        query = bind_value(query, value);
+    }
+    query
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to copy {}: {}", table, e))?;
+    Ok(())
+}
+
+/// Fork a sandbox off the branch holding `entry_id`, which must be visible on
+/// the story's current branch. The sandbox gets its own copy of the current
+/// branch's world state, so nothing done in it reaches the real branches.
+pub async fn create_sandbox(
+    pool: &SqlitePool,
+    entry_id: &str,
+    name: Option<&str>,
+) -> Result<SandboxBranch, String> {
+    let (story_id, parent_branch_id): (String, Option<String>) =
+        sqlx::query_as("SELECT story_id, branch_id FROM story_entries WHERE id = ?")
+            .bind(entry_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load entry: {}", e))?
+            .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    if !visible_entries(pool, &story_id)
+        .await?
+        .iter()
+        .any(|e| e.id == entry_id)
+    {
+        return Err("That entry isn't on the current branch".to_string());
+    }
+    let view = world_view(pool, &story_id).await?;
+    let settings = load_settings(pool).await?;
+
+    let now = now_millis();
+    let sandbox = SandboxBranch {
+        id: Uuid::new_v4().to_string(),
+        story_id: story_id.clone(),
+        name: name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or("Sandbox")
+            .to_string(),
+        parent_branch_id,
+        fork_entry_id: entry_id.to_string(),
+        expires_at: now + settings.keep_days.max(1) * DAY_MILLIS,
+        created_at: now,
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    sqlx::query(
+        "INSERT INTO branches (id, story_id, name, parent_branch_id, fork_entry_id, \
+         checkpoint_id, created_at, snapshot_complete, sandbox_expires_at) \
+         VALUES (?, ?, ?, ?, ?, NULL, ?, 1, ?)",
+    )
+    .bind(&sandbox.id)
+    .bind(&sandbox.story_id)
+    .bind(&sandbox.name)
+    .bind(&sandbox.parent_branch_id)
+    .bind(&sandbox.fork_entry_id)
+    .bind(sandbox.created_at)
+    .bind(sandbox.expires_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create sandbox branch: {}", e))?;
+
+    // New ids of the copied locations, by old id and by the id they override
+    let mut location_ids: std::collections::HashMap<String, String> = Default::default();
+    for table in WORLD_TABLES {
+        let mut layers = Vec::new();
+        for layer in view.layers() {
+            let images = match &layer {
+                None => {
+                    capture_rows(
+                        &mut tx,
+                        table,
+                        "story_id = ?1 AND branch_id IS NULL",
+                        &[&story_id],
+                    )
+                    .await?
+                }
+                Some(branch) => {
+                    capture_rows(
+                        &mut tx,
+                        table,
+                        "story_id = ?1 AND branch_id = ?2",
+                        &[&story_id, branch],
+                    )
+                    .await?
+                }
+            };
+            layers.push(images.into_iter().map(|i| i.row).collect::<Vec<_>>());
+        }
+        let rows = view.resolve(
+            layers,
+            |row| {
+                text(row, "overrides_id")
+                    .or(text(row, "id"))
+                    .unwrap_or_default()
+                    .to_string()
+            },
+            |row| row.get("deleted").and_then(Value::as_i64).unwrap_or(0) != 0,
+        );
+
+        let mut copies = Vec::new();
+        for mut row in rows {
+            let id = Uuid::new_v4().to_string();
+            if *table == "locations" {
+                for old in [text(&row, "id"), text(&row, "overrides_id")]
+                    .into_iter()
+                    .flatten()
+                {
+                    location_ids.insert(old.to_string(), id.clone());
+                }
+            }
+            row.remove("$rowid");
+            row.insert("id".to_string(), Value::from(id));
+            row.insert("branch_id".to_string(), Value::from(sandbox.id.clone()));
+            row.insert("overrides_id".to_string(), Value::Null);
+            copies.push(row);
+        }
+        for row in &mut copies {
+            match *table {
+                "locations" => {
+                    let connections = text(row, "connections")
+                        .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok());
+                    if let Some(connections) = connections {
+                        let remapped: Vec<&String> = connections
+                            .iter()
+                            .map(|c| location_ids.get(c).unwrap_or(c))
+                            .collect();
+                        let raw = serde_json::to_string(&remapped).map_err(|e| e.to_string())?;
+                        row.insert("connections".to_string(), Value::from(raw));
+                    }
+                }
+                "items" => {
+                    let location = text(row, "location").and_then(|l| location_ids.get(l));
+                    if let Some(location) = location.cloned() {
+                        row.insert("location".to_string(), Value::from(location));
+                    }
+                }
+                _ => {}
+            }
+            insert_row(&mut tx, table, row).await?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to create sandbox branch: {}", e))?;
+    Ok(sandbox)
+}
+
+/// Make a sandbox a regular branch that is listed, synced and kept
+pub async fn promote_sandbox(
+    pool: &SqlitePool,
+    branch_id: &str,
+    name: Option<&str>,
+) -> Result<(), String> {
+    let name = name.map(str::trim).filter(|n| !n.is_empty());
+    let result = sqlx::query(
+        "UPDATE branches SET sandbox_expires_at = NULL, name = COALESCE(?, name) \
+         WHERE id = ? AND sandbox_expires_at IS NOT NULL",
+    )
+    .bind(name)
+    .bind(branch_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to promote sandbox branch: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Sandbox branch not found: {}", branch_id));
+    }
+    Ok(())
+}
+
+/// Why a sandbox can't be deleted right now, if it can't
+pub async fn blocked_reason(
+    pool: &SqlitePool,
+    sandbox: &SandboxBranch,
+) -> Result<Option<&'static str>, String> {
+    if current_branch(pool, &sandbox.story_id).await?.as_deref() == Some(sandbox.id.as_str()) {
+        return Ok(Some(
+            "Switch to another branch before discarding this sandbox",
+        ));
+    }
+    let children: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM branches WHERE parent_branch_id = ?")
+            .bind(&sandbox.id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to load branches: {}", e))?;
+    if children > 0 {
+        return Ok(Some("Other branches fork from this sandbox"));
+    }
+    Ok(None)
+}
+
+/// Delete a sandbox with everything on it
+pub async fn delete_sandbox(pool: &SqlitePool, branch_id: &str) -> Result<(), String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    // Entries and chapters point at each other; check once everything's gone
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    for table in BRANCH_TABLES {
+        sqlx::query(&format!("DELETE FROM {} WHERE branch_id = ?", quote(table)))
+            .bind(branch_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete from {}: {}", table, e))?;
+    }
+    sqlx::query("DELETE FROM branches WHERE id = ? AND sandbox_expires_at IS NOT NULL")
+        .bind(branch_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete sandbox branch: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete sandbox branch: {}", e))
+}
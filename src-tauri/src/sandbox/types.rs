@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// A temporary branch for trying something out
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxBranch {
+    pub id: String,
+    pub story_id: String,
+    pub name: String,
+    pub parent_branch_id: Option<String>,
+    pub fork_entry_id: String,
+    /// Deleted after this unless promoted
+    pub expires_at: i64,
+    pub created_at: i64,
+}
+
+/// Stored under the `sandbox_settings` key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SandboxSettings {
+    /// Days a sandbox is kept after it's created
+    pub keep_days: i64,
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self { keep_days: 3 }
+    }
+}
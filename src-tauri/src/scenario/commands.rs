@@ -0,0 +1,455 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use sqlx::Row;
+use std::collections::{BTreeSet, HashMap};
+use tauri::State;
+use uuid::Uuid;
+
+use super::template::{self, Node, RenderContext};
+use super::types::{
+    InstantiateScenarioRequest, InstantiatedScenario, ScenarioNpc, ScenarioTemplateSpec,
+    VaultLorebookEntry,
+};
+use crate::attribution::store::pack_credit;
+use crate::attribution::types::Provenance;
+use crate::db::{now_millis, DbState};
+
+/// Template fields of a scenario, parsed up front so validation covers every field
+struct ParsedScenario {
+    setting: Vec<Node>,
+    description: Option<Vec<Node>>,
+    opening: Option<Vec<Node>>,
+    npcs: Vec<ParsedNpc>,
+}
+
+struct ParsedNpc {
+    name: Vec<Node>,
+    role: Vec<Node>,
+    description: Vec<Node>,
+    relationship: Vec<Node>,
+    traits: Vec<Vec<Node>>,
+}
+
+fn parse_field(field: &str, source: &str) -> Result<Vec<Node>, String> {
+    template::parse(source).map_err(|e| format!("Template error in {}: {}", field, e))
+}
+
+fn parse_npc(npc: &ScenarioNpc) -> Result<ParsedNpc, String> {
+    let field = format!("NPC '{}'", npc.name);
+    Ok(ParsedNpc {
+        name: parse_field(&field, &npc.name)?,
+        role: parse_field(&field, &npc.role)?,
+        description: parse_field(&field, &npc.description)?,
+        relationship: parse_field(&field, &npc.relationship)?,
+        traits: npc
+            .traits
+            .iter()
+            .map(|t| parse_field(&field, t))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+impl ParsedScenario {
+    fn referenced_variables(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        template::collect_variables(&self.setting, &mut vars);
+        for nodes in self.description.iter().chain(self.opening.iter()) {
+            template::collect_variables(nodes, &mut vars);
+        }
+        for npc in &self.npcs {
+            for nodes in [&npc.name, &npc.role, &npc.description, &npc.relationship] {
+                template::collect_variables(nodes, &mut vars);
+            }
+            for nodes in &npc.traits {
+                template::collect_variables(nodes, &mut vars);
+            }
+        }
+        vars
+    }
+}
+
+/// Resolve variable values from the request, declared defaults, and built-ins.
+/// Returns the names of required variables that have no value.
+fn resolve_values(
+    spec: &ScenarioTemplateSpec,
+    provided: &HashMap<String, String>,
+    builtins: &[(&str, &str)],
+) -> (HashMap<String, String>, Vec<String>) {
+    let mut values: HashMap<String, String> = builtins
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let mut missing = Vec::new();
+
+    for (name, value) in provided {
+        if !value.trim().is_empty() {
+            values.insert(name.clone(), value.trim().to_string());
+        }
+    }
+
+    for variable in &spec.variables {
+        if values.contains_key(&variable.name) {
+            continue;
+        }
+        match variable.default_value {
+            Some(ref default) if !default.is_empty() => {
+                values.insert(variable.name.clone(), default.clone());
+            }
+            _ if variable.required => missing.push(variable.name.clone()),
+            _ => {
+                values.insert(variable.name.clone(), String::new());
+            }
+        }
+    }
+
+    (values, missing)
+}
+
+fn default_entry_state(entry_type: &str) -> serde_json::Value {
+    match entry_type {
+        "character" => json!({
+            "type": "character",
+            "isPresent": false,
+            "lastSeenLocation": null,
+            "currentDisposition": null,
+            "relationship": { "level": 0, "status": "neutral", "history": [] },
+            "knownFacts": [],
+            "revealedSecrets": [],
+        }),
+        "location" => json!({
+            "type": "location",
+            "isCurrentLocation": false,
+            "visitCount": 0,
+            "changes": [],
+            "presentCharacters": [],
+            "presentItems": [],
+        }),
+        "item" => json!({
+            "type": "item",
+            "inInventory": false,
+            "currentLocation": null,
+            "condition": null,
+            "uses": [],
+        }),
+        "faction" => json!({
+            "type": "faction",
+            "playerStanding": 0,
+            "status": "unknown",
+            "knownMembers": [],
+        }),
+        "event" => json!({
+            "type": "event",
+            "occurred": false,
+            "occurredAt": null,
+            "witnesses": [],
+            "consequences": [],
+        }),
+        _ => json!({
+            "type": "concept",
+            "revealed": false,
+            "comprehensionLevel": "unknown",
+            "relatedEntries": [],
+        }),
+    }
+}
+
+/// Instantiate a vault scenario into a new story.
+///
+/// Template variables are validated before anything is written; the story,
+/// its characters, lorebook entries, and opening narration are then inserted
+/// in a single transaction.
+#[tauri::command]
+pub async fn instantiate_scenario(
+    db: State<'_, DbState>,
+    request: InstantiateScenarioRequest,
+) -> Result<InstantiatedScenario, String> {
+    let row = sqlx::query(
+        "SELECT name, description, setting_seed, npcs, primary_character_name, first_message, \
+         alternate_greetings, metadata, template FROM scenario_vault WHERE id = ?",
+    )
+    .bind(&request.scenario_id)
+    .fetch_optional(db.pool())
+    .await
+    .map_err(|e| format!("Failed to load scenario: {}", e))?
+    .ok_or_else(|| format!("Scenario not found: {}", request.scenario_id))?;
+
+    let name: String = row.get("name");
+    let description: Option<String> = row.get("description");
+    let setting_seed: String = row.get("setting_seed");
+    let primary_character: String = row.get("primary_character_name");
+    let first_message: Option<String> = row.get("first_message");
+
+    let npcs: Vec<ScenarioNpc> = serde_json::from_str(row.get("npcs"))
+        .map_err(|e| format!("Invalid scenario NPCs: {}", e))?;
+    let greetings: Vec<String> =
+        serde_json::from_str(row.get("alternate_greetings")).unwrap_or_default();
+    let metadata: serde_json::Value = row
+        .get::<Option<String>, _>("metadata")
+        .and_then(|m| serde_json::from_str(&m).ok())
+        .unwrap_or_default();
+    let spec: ScenarioTemplateSpec = match row.get::<Option<String>, _>("template") {
+        Some(raw) => serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid scenario template definition: {}", e))?,
+        None => ScenarioTemplateSpec::default(),
+    };
+
+    let opening = match request.greeting_index {
+        Some(index) => Some(
+            greetings
+                .get(index)
+                .cloned()
+                .ok_or_else(|| format!("Alternate greeting {} does not exist", index))?,
+        ),
+        None => first_message,
+    };
+
+    let parsed = ParsedScenario {
+        setting: parse_field("setting", &setting_seed)?,
+        description: description
+            .as_deref()
+            .map(|d| parse_field("description", d))
+            .transpose()?,
+        opening: opening
+            .as_deref()
+            .map(|o| parse_field("opening", o))
+            .transpose()?,
+        npcs: npcs.iter().map(parse_npc).collect::<Result<_, _>>()?,
+    };
+
+    // Validate before rendering: every substituted variable needs a value
+    let (mut values, mut missing) = resolve_values(
+        &spec,
+        &request.variables,
+        &[("scenario_name", &name), ("primary_character", &primary_character)],
+    );
+    for variable in parsed.referenced_variables() {
+        if variable != "setting" && !values.contains_key(&variable) && !missing.contains(&variable) {
+            missing.push(variable);
+        }
+    }
+    if !missing.is_empty() {
+        missing.sort();
+        return Err(format!(
+            "Missing required scenario variables: {}",
+            missing.join(", ")
+        ));
+    }
+
+    let mut rng = match request.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let setting = {
+        let mut ctx = RenderContext {
+            values: &values,
+            tables: &spec.tables,
+            rng: &mut rng,
+        };
+        template::render(&parsed.setting, &mut ctx)?
+    };
+    values.entry("setting".to_string()).or_insert(setting);
+
+    let mut ctx = RenderContext {
+        values: &values,
+        tables: &spec.tables,
+        rng: &mut rng,
+    };
+    let story_description = match parsed.description {
+        Some(ref nodes) => template::render(nodes, &mut ctx)?,
+        None => ctx.values["setting"].clone(),
+    };
+    let opening_text = parsed
+        .opening
+        .as_ref()
+        .map(|nodes| template::render(nodes, &mut ctx))
+        .transpose()?;
+    let mut rendered_npcs = Vec::with_capacity(parsed.npcs.len());
+    for npc in &parsed.npcs {
+        rendered_npcs.push(ScenarioNpc {
+            name: template::render(&npc.name, &mut ctx)?,
+            role: template::render(&npc.role, &mut ctx)?,
+            description: template::render(&npc.description, &mut ctx)?,
+            relationship: template::render(&npc.relationship, &mut ctx)?,
+            traits: npc
+                .traits
+                .iter()
+                .map(|t| template::render(t, &mut ctx))
+                .collect::<Result<_, _>>()?,
+        });
+    }
+
+    let linked_lorebook_id = metadata.get("linkedLorebookId").and_then(|v| v.as_str());
+    let (lorebook_entries, lorebook_metadata): (Vec<VaultLorebookEntry>, serde_json::Value) =
+        match linked_lorebook_id {
+            Some(lorebook_id) => {
+                let raw: Option<(String, Option<String>)> =
+                    sqlx::query_as("SELECT entries, metadata FROM lorebook_vault WHERE id = ?")
+                        .bind(lorebook_id)
+                        .fetch_optional(db.pool())
+                        .await
+                        .map_err(|e| format!("Failed to load linked lorebook: {}", e))?;
+                match raw {
+                    Some((entries, lorebook_metadata)) => (
+                        serde_json::from_str(&entries).unwrap_or_default(),
+                        lorebook_metadata
+                            .and_then(|m| serde_json::from_str(&m).ok())
+                            .unwrap_or_default(),
+                    ),
+                    None => Default::default(),
+                }
+            }
+            None => Default::default(),
+        };
+
+    // Recorded on the story for the attribution on shared exports
+    let mut provenance = Provenance {
+        scenario_id: Some(request.scenario_id.clone()),
+        scenario_name: Some(name.clone()),
+        scenario_creator: ["creator", "author"]
+            .iter()
+            .find_map(|key| metadata.get(*key).and_then(|v| v.as_str()))
+            .map(str::to_string),
+        scenario_source_url: metadata
+            .get("sourceUrl")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        lorebook_id: linked_lorebook_id.map(str::to_string),
+        packs: Vec::new(),
+    };
+    for source in [&metadata, &lorebook_metadata] {
+        if let Some(credit) = pack_credit(db.pool(), source).await? {
+            if !provenance.packs.iter().any(|p| p.id == credit.id) {
+                provenance.packs.push(credit);
+            }
+        }
+    }
+
+    let story_id = Uuid::new_v4().to_string();
+    let title = request
+        .title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| name.clone());
+    let mode = request.mode.unwrap_or_else(|| "adventure".to_string());
+    let now = now_millis();
+
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO stories (id, title, description, genre, template_id, mode, created_at, updated_at, pack_id, provenance) \
+         VALUES (?, ?, ?, NULL, NULL, ?, ?, ?, 'default-pack', ?)",
+    )
+    .bind(&story_id)
+    .bind(&title)
+    .bind(&story_description)
+    .bind(&mode)
+    .bind(now)
+    .bind(now)
+    .bind(serde_json::to_string(&provenance).unwrap_or_default())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create story: {}", e))?;
+
+    let mut character_ids = Vec::new();
+    if let Some(player_name) = values.get("player_name").filter(|n| !n.is_empty()) {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO characters (id, story_id, name, description, relationship, traits, visual_descriptors, status) \
+             VALUES (?, ?, ?, ?, 'self', '[]', '{}', 'active')",
+        )
+        .bind(&id)
+        .bind(&story_id)
+        .bind(player_name)
+        .bind(values.get("player_description").filter(|d| !d.is_empty()))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create protagonist: {}", e))?;
+        character_ids.push(id);
+    }
+
+    for npc in &rendered_npcs {
+        let id = Uuid::new_v4().to_string();
+        let traits = serde_json::to_string(&npc.traits).unwrap_or_else(|_| "[]".to_string());
+        let metadata = json!({ "role": npc.role }).to_string();
+        sqlx::query(
+            "INSERT INTO characters (id, story_id, name, description, relationship, traits, visual_descriptors, status, metadata) \
+             VALUES (?, ?, ?, ?, ?, ?, '{}', 'active', ?)",
+        )
+        .bind(&id)
+        .bind(&story_id)
+        .bind(&npc.name)
+        .bind(&npc.description)
+        .bind(&npc.relationship)
+        .bind(traits)
+        .bind(metadata)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create character '{}': {}", npc.name, e))?;
+        character_ids.push(id);
+    }
+
+    let mut lorebook_entry_ids = Vec::new();
+    for entry in &lorebook_entries {
+        let id = Uuid::new_v4().to_string();
+        let injection = json!({
+            "mode": entry.injection_mode,
+            "keywords": entry.keywords,
+            "priority": entry.priority,
+        });
+        sqlx::query(
+            "INSERT INTO entries (id, story_id, name, type, description, aliases, state, injection, \
+             mention_count, created_by, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, 'import', ?, ?)",
+        )
+        .bind(&id)
+        .bind(&story_id)
+        .bind(&entry.name)
+        .bind(&entry.entry_type)
+        .bind(&entry.description)
+        .bind(serde_json::to_string(&entry.aliases).unwrap_or_else(|_| "[]".to_string()))
+        .bind(default_entry_state(&entry.entry_type).to_string())
+        .bind(injection.to_string())
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create lorebook entry '{}': {}", entry.name, e))?;
+        lorebook_entry_ids.push(id);
+    }
+
+    let opening_entry_id = match opening_text.filter(|t| !t.trim().is_empty()) {
+        Some(text) => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO story_entries (id, story_id, type, content, parent_id, position, created_at) \
+                 VALUES (?, ?, 'narration', ?, NULL, 0, ?)",
+            )
+            .bind(&id)
+            .bind(&story_id)
+            .bind(text)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to create opening entry: {}", e))?;
+            Some(id)
+        }
+        None => None,
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit scenario: {}", e))?;
+
+    Ok(InstantiatedScenario {
+        story_id,
+        title,
+        opening_entry_id,
+        character_ids,
+        lorebook_entry_ids,
+    })
+}
@@ -0,0 +1,239 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::{BTreeSet, HashMap};
+
+/// A parsed piece of scenario template text
+#[derive(Debug, Clone)]
+pub enum Node {
+    /// Literal text copied through unchanged
+    Text(String),
+    /// `{{name}}` - substituted with the variable value
+    Var(String),
+    /// `{{random:table}}` or `{{random:a|b|c}}` - one option picked at random
+    Random(String),
+    /// `{{#if cond}}...{{else}}...{{/if}}` and its `#unless` counterpart
+    Conditional {
+        condition: Condition,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// Condition of a conditional block: a variable that is non-empty, or equals a literal
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub variable: String,
+    pub equals: Option<String>,
+}
+
+/// Values and random tables available while rendering
+pub struct RenderContext<'a> {
+    pub values: &'a HashMap<String, String>,
+    pub tables: &'a HashMap<String, Vec<String>>,
+    pub rng: &'a mut StdRng,
+}
+
+struct Frame {
+    condition: Condition,
+    negate: bool,
+    closing: &'static str,
+    then: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+impl Frame {
+    fn current(&mut self) -> &mut Vec<Node> {
+        match self.otherwise {
+            Some(ref mut nodes) => nodes,
+            None => &mut self.then,
+        }
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn parse_condition(expr: &str) -> Result<Condition, String> {
+    let (variable, equals) = match expr.split_once("==") {
+        Some((name, value)) => {
+            let value = value.trim().trim_matches('"').to_string();
+            (name.trim(), Some(value))
+        }
+        None => (expr.trim(), None),
+    };
+
+    if !is_valid_name(variable) {
+        return Err(format!("Invalid condition: '{}'", expr));
+    }
+
+    Ok(Condition {
+        variable: variable.to_string(),
+        equals,
+    })
+}
+
+/// Parse template text into nodes, checking that every block is closed
+pub fn parse(source: &str) -> Result<Vec<Node>, String> {
+    let mut root: Vec<Node> = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut rest = source;
+
+    macro_rules! push {
+        ($node:expr) => {
+            match stack.last_mut() {
+                Some(frame) => frame.current().push($node),
+                None => root.push($node),
+            }
+        };
+    }
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            push!(Node::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| "Unclosed '{{' in template".to_string())?;
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(expr) = tag.strip_prefix("#if ") {
+            stack.push(Frame {
+                condition: parse_condition(expr)?,
+                negate: false,
+                closing: "/if",
+                then: Vec::new(),
+                otherwise: None,
+            });
+        } else if let Some(expr) = tag.strip_prefix("#unless ") {
+            stack.push(Frame {
+                condition: parse_condition(expr)?,
+                negate: true,
+                closing: "/unless",
+                then: Vec::new(),
+                otherwise: None,
+            });
+        } else if tag == "else" {
+            let frame = stack
+                .last_mut()
+                .ok_or_else(|| "'{{else}}' outside of a conditional block".to_string())?;
+            if frame.otherwise.is_some() {
+                return Err("Duplicate '{{else}}' in conditional block".to_string());
+            }
+            frame.otherwise = Some(Vec::new());
+        } else if tag == "/if" || tag == "/unless" {
+            let frame = stack
+                .pop()
+                .ok_or_else(|| format!("Unexpected '{{{{{}}}}}'", tag))?;
+            if frame.closing != tag {
+                return Err(format!(
+                    "Mismatched block: expected '{{{{{}}}}}', found '{{{{{}}}}}'",
+                    frame.closing, tag
+                ));
+            }
+            push!(Node::Conditional {
+                condition: frame.condition,
+                negate: frame.negate,
+                then: frame.then,
+                otherwise: frame.otherwise.unwrap_or_default(),
+            });
+        } else if let Some(table) = tag.strip_prefix("random:") {
+            let table = table.trim();
+            if table.is_empty() {
+                return Err("Empty random table reference".to_string());
+            }
+            push!(Node::Random(table.to_string()));
+        } else if is_valid_name(tag) {
+            push!(Node::Var(tag.to_string()));
+        } else {
+            return Err(format!("Invalid template tag: '{{{{{}}}}}'", tag));
+        }
+    }
+
+    if !rest.is_empty() {
+        push!(Node::Text(rest.to_string()));
+    }
+
+    if let Some(frame) = stack.pop() {
+        return Err(format!("Unclosed block, missing '{{{{{}}}}}'", frame.closing));
+    }
+
+    Ok(root)
+}
+
+/// Collect the names of variables whose values are substituted into the output
+pub fn collect_variables(nodes: &[Node], out: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::Var(name) => {
+                out.insert(name.clone());
+            }
+            Node::Conditional { then, otherwise, .. } => {
+                collect_variables(then, out);
+                collect_variables(otherwise, out);
+            }
+            Node::Text(_) | Node::Random(_) => {}
+        }
+    }
+}
+
+/// Render parsed nodes. Missing variables are an error; callers validate first.
+pub fn render(nodes: &[Node], ctx: &mut RenderContext<'_>) -> Result<String, String> {
+    let mut out = String::new();
+    render_into(nodes, ctx, &mut out)?;
+    Ok(out)
+}
+
+fn render_into(nodes: &[Node], ctx: &mut RenderContext<'_>, out: &mut String) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => {
+                let value = ctx
+                    .values
+                    .get(name)
+                    .ok_or_else(|| format!("Missing value for variable '{}'", name))?;
+                out.push_str(value);
+            }
+            Node::Random(table) => {
+                let inline: Vec<String>;
+                let options = match ctx.tables.get(table) {
+                    Some(options) => options,
+                    None if table.contains('|') => {
+                        inline = table.split('|').map(|s| s.trim().to_string()).collect();
+                        &inline
+                    }
+                    None => return Err(format!("Unknown random table '{}'", table)),
+                };
+                if let Some(choice) = options.choose(ctx.rng) {
+                    out.push_str(choice);
+                }
+            }
+            Node::Conditional {
+                condition,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let value = ctx
+                    .values
+                    .get(&condition.variable)
+                    .map(|v| v.trim())
+                    .unwrap_or("");
+                let matched = match condition.equals {
+                    Some(ref expected) => value.eq_ignore_ascii_case(expected),
+                    None => !value.is_empty() && value != "false",
+                };
+                let branch = if matched != *negate { then } else { otherwise };
+                render_into(branch, ctx, out)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Template definition stored alongside a vault scenario (`scenario_vault.template`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScenarioTemplateSpec {
+    /// Variables the user fills in when starting a story from this scenario
+    pub variables: Vec<ScenarioVariable>,
+    /// Named random tables, referenced as `{{random:name}}`
+    pub tables: HashMap<String, Vec<String>>,
+}
+
+/// A declared scenario variable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioVariable {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default_value: Option<String>,
+}
+
+/// NPC as stored in `scenario_vault.npcs`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScenarioNpc {
+    pub name: String,
+    pub role: String,
+    pub description: String,
+    pub relationship: String,
+    pub traits: Vec<String>,
+}
+
+/// Lorebook entry as stored in `lorebook_vault.entries`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLorebookEntry {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default = "default_injection_mode")]
+    pub injection_mode: String,
+    #[serde(default)]
+    pub priority: i64,
+}
+
+fn default_injection_mode() -> String {
+    "keyword".to_string()
+}
+
+/// Request to start a new story from a vault scenario
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiateScenarioRequest {
+    pub scenario_id: String,
+    /// Story title; defaults to the scenario name
+    pub title: Option<String>,
+    /// Story mode ('adventure' or 'creative-writing')
+    pub mode: Option<String>,
+    /// Values for the scenario's template variables
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Index into the alternate greetings to use as the opening (None = first message)
+    pub greeting_index: Option<usize>,
+    /// Seed for random tables, for reproducible instantiation
+    pub seed: Option<u64>,
+}
+
+/// Result of instantiating a scenario
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiatedScenario {
+    pub story_id: String,
+    pub title: String,
+    pub opening_entry_id: Option<String>,
+    pub character_ids: Vec<String>,
+    pub lorebook_entry_ids: Vec<String>,
+}
@@ -0,0 +1,201 @@
+//! Milestones measured from the stories and the activity tables. They're
+//! checked on a schedule and whenever the list is asked for; an unlock is
+//! stored once and announced on `achievement-unlocked`.
+
+use futures_util::TryStreamExt;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::store;
+use super::types::Achievement;
+use crate::db::{now_millis, DbState};
+
+const EVALUATE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Measure {
+    /// Words across every story entry
+    Words,
+    /// Longest run of active days
+    Streak,
+    /// Branches with at least one entry of their own
+    Branches,
+    /// Stories marked finished
+    Finished,
+}
+
+struct Milestone {
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    measure: Measure,
+    target: i64,
+}
+
+const MILESTONES: &[Milestone] = &[
+    Milestone {
+        id: "words_10k",
+        title: "Ten Thousand Words",
+        description: "Reach 10,000 words across your stories",
+        measure: Measure::Words,
+        target: 10_000,
+    },
+    Milestone {
+        id: "words_100k",
+        title: "Novelist",
+        description: "Reach 100,000 words across your stories",
+        measure: Measure::Words,
+        target: 100_000,
+    },
+    Milestone {
+        id: "streak_7",
+        title: "A Week Running",
+        description: "Write or read on 7 days in a row",
+        measure: Measure::Streak,
+        target: 7,
+    },
+    Milestone {
+        id: "streak_30",
+        title: "Habit Formed",
+        description: "Write or read on 30 days in a row",
+        measure: Measure::Streak,
+        target: 30,
+    },
+    Milestone {
+        id: "streak_100",
+        title: "Hundred Days",
+        description: "Write or read on 100 days in a row",
+        measure: Measure::Streak,
+        target: 100,
+    },
+    Milestone {
+        id: "branches_10",
+        title: "Roads Not Taken",
+        description: "Explore 10 branches",
+        measure: Measure::Branches,
+        target: 10,
+    },
+    Milestone {
+        id: "finished_1",
+        title: "The End",
+        description: "Finish a story",
+        measure: Measure::Finished,
+        target: 1,
+    },
+];
+
+async fn total_words(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let mut contents =
+        sqlx::query_scalar::<_, String>("SELECT content FROM story_entries").fetch(pool);
+    let mut words = 0;
+    while let Some(content) = contents.try_next().await? {
+        words += content.split_whitespace().count() as i64;
+    }
+    Ok(words)
+}
+
+async fn measure(pool: &SqlitePool, measure: Measure) -> Result<i64, String> {
+    let fail = |e: sqlx::Error| format!("Failed to check achievements: {}", e);
+    match measure {
+        Measure::Words => total_words(pool).await.map_err(fail),
+        Measure::Streak => Ok(store::streak(pool, None).await?.longest as i64),
+        Measure::Branches => sqlx::query_scalar(
+            "SELECT COUNT(*) FROM branches b WHERE b.sandbox_expires_at IS NULL \
+             AND EXISTS (SELECT 1 FROM story_entries e WHERE e.branch_id = b.id)",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(fail),
+        Measure::Finished => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM stories WHERE finished_at IS NOT NULL")
+                .fetch_one(pool)
+                .await
+                .map_err(fail)
+        }
+    }
+}
+
+/// Measure every milestone, store and announce new unlocks, and return
+/// the full list
+pub async fn evaluate(app: &AppHandle, pool: &SqlitePool) -> Result<Vec<Achievement>, String> {
+    let unlocked: HashMap<String, (i64, i64)> = sqlx::query_as::<_, (String, i64, i64)>(
+        "SELECT id, progress, unlocked_at FROM achievements",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load achievements: {}", e))?
+    .into_iter()
+    .map(|(id, progress, unlocked_at)| (id, (progress, unlocked_at)))
+    .collect();
+
+    let mut measured = HashMap::new();
+    let mut achievements = Vec::with_capacity(MILESTONES.len());
+    for milestone in MILESTONES {
+        let progress = match measured.get(&milestone.measure) {
+            Some(progress) => *progress,
+            None => {
+                let progress = measure(pool, milestone.measure).await?;
+                measured.insert(milestone.measure, progress);
+                progress
+            }
+        };
+        let mut achievement = Achievement {
+            id: milestone.id.to_string(),
+            title: milestone.title.to_string(),
+            description: milestone.description.to_string(),
+            progress: progress.min(milestone.target),
+            target: milestone.target,
+            unlocked_at: None,
+        };
+        if let Some((_, unlocked_at)) = unlocked.get(milestone.id) {
+            // Unlocks stay even if the stories behind them are deleted
+            achievement.progress = milestone.target;
+            achievement.unlocked_at = Some(*unlocked_at);
+        } else if progress >= milestone.target {
+            let now = now_millis();
+            sqlx::query(
+                "INSERT OR IGNORE INTO achievements (id, progress, unlocked_at) VALUES (?, ?, ?)",
+            )
+            .bind(milestone.id)
+            .bind(progress)
+            .bind(now)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to save achievement: {}", e))?;
+            achievement.unlocked_at = Some(now);
+            if let Err(e) = app.emit("achievement-unlocked", &achievement) {
+                eprintln!("Failed to emit achievement event: {}", e);
+            }
+        }
+        achievements.push(achievement);
+    }
+    Ok(achievements)
+}
+
+/// Check the milestones every little while
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        loop {
+            // Fails until the frontend has run migrations
+            let _ = evaluate(&app, &pool).await;
+            tokio::time::sleep(EVALUATE_INTERVAL).await;
+        }
+    });
+}
+
+pub async fn set_finished(pool: &SqlitePool, story_id: &str, finished: bool) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE stories SET finished_at = CASE WHEN ? THEN COALESCE(finished_at, ?) END \
+         WHERE id = ?",
+    )
+    .bind(finished)
+    .bind(now_millis())
+    .bind(story_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update story: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use tauri::{AppHandle, State};
+
+use super::achievements;
+use super::store;
+use super::tracker::{self, SessionTracker};
+use super::types::{Achievement, ActivityHeatmap, ActivityKind, DayActivity, WritingStreak};
+use crate::db::DbState;
+
+/// Heartbeat while the user types in or reads a story; send one at least
+/// every minute of activity. Returns today's time and words for the story.
+#[tauri::command]
+pub async fn record_activity(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    tracker: State<'_, SessionTracker>,
+    story_id: String,
+    kind: ActivityKind,
+) -> Result<Option<DayActivity>, String> {
+    tracker::heartbeat(&app, db.pool(), &tracker, Some(&story_id), kind).await
+}
+
+/// The user left the story or the window lost focus
+#[tauri::command]
+pub async fn end_activity(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    tracker: State<'_, SessionTracker>,
+) -> Result<(), String> {
+    tracker::heartbeat(&app, db.pool(), &tracker, None, ActivityKind::Reading).await?;
+    Ok(())
+}
+
+/// Activity per story per day between two dates (YYYY-MM-DD, inclusive)
+#[tauri::command]
+pub async fn get_writing_activity(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+    from: String,
+    to: String,
+) -> Result<Vec<DayActivity>, String> {
+    let parse = |day: &str| {
+        day.parse()
+            .map_err(|e| format!("Invalid date '{}': {}", day, e))
+    };
+    store::activity(db.pool(), story_id.as_deref(), parse(&from)?, parse(&to)?).await
+}
+
+/// Day streaks for one story, or across all of them
+#[tauri::command]
+pub async fn get_writing_streak(
+    db: State<'_, DbState>,
+    story_id: Option<String>,
+) -> Result<WritingStreak, String> {
+    store::streak(db.pool(), story_id.as_deref()).await
+}
+
+/// Entries, words and active time per day of a year, for a contribution
+/// calendar
+#[tauri::command]
+pub async fn get_activity_heatmap(
+    db: State<'_, DbState>,
+    year: i32,
+) -> Result<ActivityHeatmap, String> {
+    store::heatmap(db.pool(), year).await
+}
+
+/// A story's own daily word goal; `None` or 0 removes it. The goal across
+/// all stories lives in `writing_goal_settings`.
+#[tauri::command]
+pub async fn set_story_word_goal(
+    db: State<'_, DbState>,
+    story_id: String,
+    goal: Option<u32>,
+) -> Result<(), String> {
+    store::set_story_goal(db.pool(), &story_id, goal).await
+}
+
+/// Every milestone with its progress, checked now
+#[tauri::command]
+pub async fn get_achievements(
+    app: AppHandle,
+    db: State<'_, DbState>,
+) -> Result<Vec<Achievement>, String> {
+    achievements::evaluate(&app, db.pool()).await
+}
+
+/// Mark a story finished, or not
+#[tauri::command]
+pub async fn set_story_finished(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_id: String,
+    finished: bool,
+) -> Result<(), String> {
+    achievements::set_finished(db.pool(), &story_id, finished).await?;
+    if finished {
+        achievements::evaluate(&app, db.pool()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod achievements;
+pub mod commands;
+pub mod store;
+pub mod tracker;
+pub mod types;
+
+pub use tracker::SessionTracker;
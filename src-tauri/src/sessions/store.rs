@@ -0,0 +1,340 @@
+//! The `writing_activity` table: time is added by the tracker, words are
+//! recounted from the story's entries so edits and deletions are reflected.
+
+use chrono::{Duration as Days, Local, NaiveDate, NaiveTime, TimeZone};
+use futures_util::TryStreamExt;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter};
+
+use super::types::{
+    ActivityHeatmap, ActivityKind, DayActivity, GoalReached, HeatmapDay, WritingGoalSettings,
+    WritingStreak,
+};
+use crate::db::now_millis;
+use crate::llm::config::get_setting;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<WritingGoalSettings, String> {
+    Ok(get_setting(pool, "writing_goal_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Millisecond range of a local day
+fn day_bounds(day: NaiveDate) -> (i64, i64) {
+    let start = |day: NaiveDate| {
+        day.and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .map_or(0, |t| t.timestamp_millis())
+    };
+    (start(day), start(day + Days::days(1)))
+}
+
+fn activity_from_row(r: &SqliteRow) -> DayActivity {
+    DayActivity {
+        story_id: r.get("story_id"),
+        day: r.get("day"),
+        writing_ms: r.get("writing_ms"),
+        reading_ms: r.get("reading_ms"),
+        words_written: r.get("words_written"),
+        goal_met_at: r.get("goal_met_at"),
+    }
+}
+
+pub async fn add_time(
+    pool: &SqlitePool,
+    story_id: &str,
+    day: NaiveDate,
+    kind: ActivityKind,
+    ms: i64,
+) -> Result<(), String> {
+    let column = match kind {
+        ActivityKind::Writing => "writing_ms",
+        ActivityKind::Reading => "reading_ms",
+    };
+    sqlx::query(&format!(
+        "INSERT INTO writing_activity (story_id, day, {0}, updated_at) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(story_id, day) DO UPDATE SET {0} = {0} + excluded.{0}, \
+         updated_at = excluded.updated_at",
+        column
+    ))
+    .bind(story_id)
+    .bind(day.to_string())
+    .bind(ms)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record activity: {}", e))?;
+    Ok(())
+}
+
+/// Count the words of the entries added to a story on `day` and check the
+/// daily goals against the new count
+pub async fn update_words(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    day: NaiveDate,
+) -> Result<i64, String> {
+    let (start, end) = day_bounds(day);
+    let contents: Vec<String> = sqlx::query_scalar(
+        "SELECT content FROM story_entries WHERE story_id = ? AND created_at >= ? AND created_at < ?",
+    )
+    .bind(story_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to count words: {}", e))?;
+    let words = contents
+        .iter()
+        .map(|content| content.split_whitespace().count() as i64)
+        .sum::<i64>();
+    sqlx::query(
+        "INSERT INTO writing_activity (story_id, day, words_written, updated_at) \
+         VALUES (?, ?, ?, ?) ON CONFLICT(story_id, day) DO UPDATE SET \
+         words_written = excluded.words_written, updated_at = excluded.updated_at",
+    )
+    .bind(story_id)
+    .bind(day.to_string())
+    .bind(words)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record words: {}", e))?;
+
+    check_goals(app, pool, story_id, day, words).await?;
+    Ok(words)
+}
+
+fn emit_goal(app: &AppHandle, event: GoalReached) {
+    if let Err(e) = app.emit("writing-goal", &event) {
+        eprintln!("Failed to emit writing goal event: {}", e);
+    }
+}
+
+async fn check_goals(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    story_id: &str,
+    day: NaiveDate,
+    words: i64,
+) -> Result<(), String> {
+    let day_key = day.to_string();
+    let story_goal: Option<i64> =
+        sqlx::query_scalar("SELECT daily_word_goal FROM stories WHERE id = ?")
+            .bind(story_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load word goal: {}", e))?
+            .flatten();
+    if let Some(goal) = story_goal.filter(|goal| *goal > 0 && words >= *goal) {
+        // Only the first update past the goal marks it
+        let marked = sqlx::query(
+            "UPDATE writing_activity SET goal_met_at = ? \
+             WHERE story_id = ? AND day = ? AND goal_met_at IS NULL",
+        )
+        .bind(now_millis())
+        .bind(story_id)
+        .bind(&day_key)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to record word goal: {}", e))?;
+        if marked.rows_affected() > 0 {
+            emit_goal(
+                app,
+                GoalReached {
+                    story_id: Some(story_id.to_string()),
+                    day: day_key.clone(),
+                    words,
+                    goal: goal as u32,
+                },
+            );
+        }
+    }
+
+    let Some(goal) = load_settings(pool)
+        .await?
+        .daily_words
+        .filter(|goal| *goal > 0)
+    else {
+        return Ok(());
+    };
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(words_written), 0) FROM writing_activity WHERE day = ?",
+    )
+    .bind(&day_key)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count words: {}", e))?;
+    if total < goal as i64 {
+        return Ok(());
+    }
+    let marked = sqlx::query(
+        "INSERT OR IGNORE INTO writing_goal_days (day, words, met_at) VALUES (?, ?, ?)",
+    )
+    .bind(&day_key)
+    .bind(total)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record word goal: {}", e))?;
+    if marked.rows_affected() > 0 {
+        emit_goal(
+            app,
+            GoalReached {
+                story_id: None,
+                day: day_key,
+                words: total,
+                goal,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Activity between two days inclusive, for one story or all of them
+pub async fn activity(
+    pool: &SqlitePool,
+    story_id: Option<&str>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DayActivity>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM writing_activity WHERE (? IS NULL OR story_id = ?) \
+         AND day >= ? AND day <= ? ORDER BY day, story_id",
+    )
+    .bind(story_id)
+    .bind(story_id)
+    .bind(from.to_string())
+    .bind(to.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load writing activity: {}", e))?;
+    Ok(rows.iter().map(activity_from_row).collect())
+}
+
+/// Streaks over the days with any time or words, for one story or all
+pub async fn streak(pool: &SqlitePool, story_id: Option<&str>) -> Result<WritingStreak, String> {
+    let days: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT day FROM writing_activity WHERE (? IS NULL OR story_id = ?) \
+         AND (writing_ms > 0 OR reading_ms > 0 OR words_written > 0) ORDER BY day",
+    )
+    .bind(story_id)
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load writing activity: {}", e))?;
+    let days: Vec<NaiveDate> = days.iter().filter_map(|day| day.parse().ok()).collect();
+    Ok(streak_of(&days, today()))
+}
+
+/// Streaks over sorted, distinct days
+fn streak_of(days: &[NaiveDate], today: NaiveDate) -> WritingStreak {
+    let Some(&last) = days.last() else {
+        return WritingStreak::default();
+    };
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous {
+            Some(previous) if day - previous == Days::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+    // The run ending at the last day only counts while it's still going
+    let current = if today - last <= Days::days(1) {
+        run
+    } else {
+        0
+    };
+    WritingStreak {
+        current,
+        longest,
+        active_today: last == today,
+        total_days: days.len() as u32,
+        last_day: Some(last.to_string()),
+    }
+}
+
+/// Entries and words per local day of a year, across all stories
+pub async fn heatmap(pool: &SqlitePool, year: i32) -> Result<ActivityHeatmap, String> {
+    let first =
+        NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| format!("Invalid year: {}", year))?;
+    let last =
+        NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| format!("Invalid year: {}", year))?;
+    let (start, _) = day_bounds(first);
+    let (_, end) = day_bounds(last);
+    let fail = |e: sqlx::Error| format!("Failed to load activity: {}", e);
+
+    let mut days: BTreeMap<NaiveDate, HeatmapDay> = BTreeMap::new();
+    let mut entries = sqlx::query_as::<_, (i64, String)>(
+        "SELECT created_at, content FROM story_entries WHERE created_at >= ? AND created_at < ?",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch(pool);
+    while let Some((created_at, content)) = entries.try_next().await.map_err(fail)? {
+        let Some(day) = Local.timestamp_millis_opt(created_at).earliest() else {
+            continue;
+        };
+        let bucket = days.entry(day.date_naive()).or_default();
+        bucket.entries += 1;
+        bucket.words += content.split_whitespace().count() as i64;
+    }
+    drop(entries);
+
+    let active: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT day, SUM(writing_ms + reading_ms) FROM writing_activity \
+         WHERE day >= ? AND day <= ? GROUP BY day",
+    )
+    .bind(first.to_string())
+    .bind(last.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(fail)?;
+    for (day, ms) in active {
+        if let Ok(day) = day.parse() {
+            days.entry(day).or_default().active_ms = ms;
+        }
+    }
+
+    let days: Vec<HeatmapDay> = days
+        .into_iter()
+        .map(|(day, bucket)| HeatmapDay {
+            day: day.to_string(),
+            ..bucket
+        })
+        .collect();
+    Ok(ActivityHeatmap {
+        year,
+        max_words: days.iter().map(|d| d.words).max().unwrap_or(0),
+        total_words: days.iter().map(|d| d.words).sum(),
+        total_entries: days.iter().map(|d| d.entries).sum(),
+        days,
+    })
+}
+
+pub async fn set_story_goal(
+    pool: &SqlitePool,
+    story_id: &str,
+    goal: Option<u32>,
+) -> Result<(), String> {
+    sqlx::query("UPDATE stories SET daily_word_goal = ? WHERE id = ?")
+        .bind(goal.filter(|goal| *goal > 0))
+        .bind(story_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save word goal: {}", e))?;
+    Ok(())
+}
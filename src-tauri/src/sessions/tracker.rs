@@ -0,0 +1,76 @@
+//! Active time, from heartbeats the frontend sends while the user types or
+//! reads. The gap since the last heartbeat counts toward what the user was
+//! doing then, unless it's longer than the idle limit.
+
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use super::store;
+use super::types::{ActivityKind, DayActivity};
+use crate::db::now_millis;
+
+struct Stretch {
+    story_id: String,
+    kind: ActivityKind,
+    last_seen: i64,
+}
+
+#[derive(Default)]
+pub struct SessionTracker {
+    current: Mutex<Option<Stretch>>,
+}
+
+impl SessionTracker {
+    /// Start or continue a stretch; returns the time to credit to the one
+    /// it continues
+    fn beat(
+        &self,
+        story_id: Option<&str>,
+        kind: ActivityKind,
+        idle_ms: i64,
+    ) -> Option<(String, ActivityKind, i64)> {
+        let now = now_millis();
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let credit = current
+            .take()
+            .map(|stretch| (stretch.story_id, stretch.kind, now - stretch.last_seen))
+            .filter(|(_, _, elapsed)| *elapsed > 0 && *elapsed <= idle_ms);
+        *current = story_id.map(|story_id| Stretch {
+            story_id: story_id.to_string(),
+            kind,
+            last_seen: now,
+        });
+        credit
+    }
+}
+
+/// Record a heartbeat for a story, or the end of activity with `None`.
+/// Returns today's activity for the story.
+pub async fn heartbeat(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    tracker: &SessionTracker,
+    story_id: Option<&str>,
+    kind: ActivityKind,
+) -> Result<Option<DayActivity>, String> {
+    let settings = store::load_settings(pool).await?;
+    let idle_ms = settings.idle_minutes.max(1) as i64 * 60_000;
+    let today = store::today();
+    if let Some((previous, previous_kind, elapsed)) = tracker.beat(story_id, kind, idle_ms) {
+        store::add_time(pool, &previous, today, previous_kind, elapsed).await?;
+        if previous_kind == ActivityKind::Writing {
+            store::update_words(app, pool, &previous, today).await?;
+        }
+    }
+    let Some(story_id) = story_id else {
+        return Ok(None);
+    };
+    if kind == ActivityKind::Writing {
+        store::update_words(app, pool, story_id, today).await?;
+    }
+    Ok(store::activity(pool, Some(story_id), today, today)
+        .await?
+        .into_iter()
+        .next())
+}
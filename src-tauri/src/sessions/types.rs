@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Writing,
+    Reading,
+}
+
+/// Time and words for one story on one local day
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayActivity {
+    pub story_id: String,
+    /// YYYY-MM-DD
+    pub day: String,
+    pub writing_ms: i64,
+    pub reading_ms: i64,
+    /// Words in the story entries added that day
+    pub words_written: i64,
+    pub goal_met_at: Option<i64>,
+}
+
+/// Consecutive days with any writing or reading
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WritingStreak {
+    /// Days up to today, or up to yesterday while today is still open
+    pub current: u32,
+    pub longest: u32,
+    pub active_today: bool,
+    pub total_days: u32,
+    pub last_day: Option<String>,
+}
+
+/// Goal options, stored in `writing_goal_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WritingGoalSettings {
+    /// Words a day across all stories
+    pub daily_words: Option<u32>,
+    /// A pause longer than this ends a stretch of activity
+    pub idle_minutes: u32,
+}
+
+impl Default for WritingGoalSettings {
+    fn default() -> Self {
+        Self {
+            daily_words: None,
+            idle_minutes: 3,
+        }
+    }
+}
+
+/// Emitted on `writing-goal` the first time a day's goal is reached
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalReached {
+    /// The story whose own goal was reached, or `None` for the global goal
+    pub story_id: Option<String>,
+    pub day: String,
+    pub words: i64,
+    pub goal: u32,
+}
+
+/// A milestone and how close the user is to it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub progress: i64,
+    pub target: i64,
+    /// Emitted on `achievement-unlocked` when this is first set
+    pub unlocked_at: Option<i64>,
+}
+
+/// One day of the contribution calendar
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapDay {
+    /// YYYY-MM-DD
+    pub day: String,
+    pub entries: i64,
+    pub words: i64,
+    /// Writing and reading time the tracker recorded
+    pub active_ms: i64,
+}
+
+/// A year of activity; days without any are left out
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityHeatmap {
+    pub year: i32,
+    pub days: Vec<HeatmapDay>,
+    /// The busiest day's words, to scale the colors by
+    pub max_words: i64,
+    pub total_words: i64,
+    pub total_entries: i64,
+}
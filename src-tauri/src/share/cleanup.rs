@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+
+use super::commands::load_settings;
+use super::types::{Burn, ShareEndpoint};
+use crate::db::{now_millis, DbState};
+use crate::remote::s3::S3Client;
+use crate::remote::{paste, webdav};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Remember an upload the endpoint won't expire by itself
+pub async fn record_upload(
+    pool: &SqlitePool,
+    name: &str,
+    endpoint: &str,
+    expires_at: i64,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO share_uploads (name, endpoint, expires_at, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(endpoint)
+    .bind(expires_at)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record share: {}", e))?;
+    Ok(())
+}
+
+/// Delete an imported one-time share downloaded from `url`
+pub async fn burn(url: &str, burn: &Burn) -> Result<(), String> {
+    match burn {
+        Burn::Delete { url } => {
+            reqwest::Client::new()
+                .delete(url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Failed to delete share: {}", e))?;
+            Ok(())
+        }
+        Burn::Token { token } => paste::delete(url, token).await,
+    }
+}
+
+/// Delete expired uploads from the configured endpoint. Uploads made to an
+/// endpoint that's no longer configured wait until it is again.
+pub async fn remove_expired(pool: &SqlitePool) -> Result<usize, String> {
+    let expired: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, endpoint FROM share_uploads WHERE expires_at <= ?")
+            .bind(now_millis())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load shares: {}", e))?;
+    if expired.is_empty() {
+        return Ok(0);
+    }
+    let Some(endpoint) = load_settings(pool).await?.endpoint else {
+        return Ok(0);
+    };
+
+    let mut removed = 0;
+    for (name, kind) in expired {
+        let deleted = match (&endpoint, kind.as_str()) {
+            (ShareEndpoint::S3(config), "s3") => {
+                S3Client::new(config.clone()).delete_object(&name).await
+            }
+            (ShareEndpoint::WebDav(config), "webDav") => webdav::delete(config, &name).await,
+            _ => continue,
+        };
+        if deleted.is_ok() {
+            sqlx::query("DELETE FROM share_uploads WHERE name = ?")
+                .bind(&name)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to update shares: {}", e))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Clear out expired shares now and every so often
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        loop {
+            // Fails until the frontend has run migrations
+            let _ = remove_expired(&pool).await;
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,133 @@
+use reqwest::Url;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use super::cleanup::{burn, record_upload};
+use super::crypto;
+use super::types::{Burn, ShareEndpoint, ShareLink, ShareSettings};
+use crate::attribution::footer::attach;
+use crate::attribution::store::load_attribution;
+use crate::db::{now_millis, DbState};
+use crate::file_import::fetch::download_capped;
+use crate::llm::config::get_setting;
+use crate::redaction::redactor::redact_story_json;
+use crate::remote::s3::{S3Client, MAX_PRESIGN_SECS};
+use crate::remote::{paste, webdav};
+
+/// Largest share accepted on import, to bound memory on a bad link
+const MAX_SHARE_BYTES: u64 = 500 * 1024 * 1024;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<ShareSettings, String> {
+    Ok(get_setting(pool, "share_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Encrypt a story (Aventura export JSON), upload it to the configured
+/// endpoint and return a link carrying the key. Every share gets its own
+/// key, so one leaked link exposes only that story. Where the endpoint
+/// allows it the link also carries a way to delete the upload, which the
+/// receiver uses once imported; anything left is deleted when it expires. The story's redaction
+/// rules are applied first when `redact` is set, and `attribution` adds the
+/// story's credits to the shared copy.
+#[tauri::command]
+pub async fn export_to_encrypted_share(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    story_json: String,
+    redact: Option<bool>,
+    attribution: Option<bool>,
+) -> Result<ShareLink, String> {
+    let settings = load_settings(db.pool()).await?;
+    let story_json = if redact.unwrap_or(false) {
+        redact_story_json(db.pool(), &story_json).await?
+    } else {
+        story_json
+    };
+    let story_json = if attribution.unwrap_or(false) {
+        let story_id = serde_json::from_str::<serde_json::Value>(&story_json)
+            .ok()
+            .and_then(|export| export.pointer("/story/id")?.as_str().map(str::to_string))
+            .ok_or("The story data has no story id")?;
+        let app_version = app.package_info().version.to_string();
+        attach(
+            &story_json,
+            &load_attribution(db.pool(), &app_version, &story_id).await?,
+        )?
+    } else {
+        story_json
+    };
+    let endpoint = settings
+        .endpoint
+        .ok_or("Choose where to upload shared stories in settings first")?;
+    let (blob, key) = crypto::seal(story_json.as_bytes())?;
+    let name = format!("{}.avshare", Uuid::new_v4());
+    let expires_secs = u64::from(settings.expires_hours) * 60 * 60;
+    let now = now_millis();
+
+    let (url, expires_at, burn_with) = match endpoint {
+        ShareEndpoint::S3(config) => {
+            let client = S3Client::new(config);
+            client.put_object(&name, blob).await?;
+            let expires_secs = expires_secs.min(MAX_PRESIGN_SECS);
+            let expires_at = now + expires_secs as i64 * 1000;
+            // S3 keeps objects after their presigned links lapse
+            record_upload(db.pool(), &name, "s3", expires_at).await?;
+            let burn_with = Burn::Delete {
+                url: client.presigned_delete_url(&name, expires_secs)?,
+            };
+            (
+                client.presigned_get_url(&name, expires_secs)?,
+                expires_at,
+                Some(burn_with),
+            )
+        }
+        ShareEndpoint::WebDav(config) => {
+            let url = webdav::put(&config, &name, blob).await?;
+            let expires_at = now + expires_secs as i64 * 1000;
+            record_upload(db.pool(), &name, "webDav", expires_at).await?;
+            (url, expires_at, None)
+        }
+        ShareEndpoint::Paste(config) => {
+            let upload = paste::upload(&config, &name, blob, settings.expires_hours).await?;
+            (
+                upload.url,
+                now + expires_secs as i64 * 1000,
+                upload.token.map(|token| Burn::Token { token }),
+            )
+        }
+    };
+
+    Ok(ShareLink {
+        link: crypto::link(&url, &key, burn_with.as_ref())?,
+        expires_at,
+        one_time: burn_with.is_some(),
+    })
+}
+
+/// Download and decrypt a share link, returning the story as Aventura
+/// export JSON for the usual import. One-time links are deleted from the
+/// endpoint once the story is read.
+#[tauri::command]
+pub async fn import_from_share_link(link: String) -> Result<String, String> {
+    let (url, key, burn_with) = crypto::parse_link(&link)?;
+    let download_url = Url::parse(&url).map_err(|e| format!("Invalid share link: {}", e))?;
+    let blob = download_capped(download_url, MAX_SHARE_BYTES).await?.bytes;
+
+    let plaintext = crypto::open(&blob, &key)?;
+    let story_json =
+        String::from_utf8(plaintext).map_err(|_| "The share isn't a story".to_string())?;
+    serde_json::from_str::<serde_json::Value>(&story_json)
+        .map_err(|e| format!("The share isn't a story: {}", e))?;
+
+    if let Some(burn_with) = burn_with {
+        // The story is already here; a failed delete only leaves the
+        // upload until it expires
+        if let Err(e) = burn(&url, &burn_with).await {
+            eprintln!("Failed to delete imported share: {}", e);
+        }
+    }
+    Ok(story_json)
+}
@@ -0,0 +1,94 @@
+//! End-to-end encryption for shared stories. Each share gets a fresh
+//! AES-256-GCM key that only travels in the link's fragment, so the
+//! storage endpoint holds nothing it can read.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use super::types::Burn;
+
+/// Start of every share blob, also authenticated with the ciphertext
+const MAGIC: &[u8] = b"AVSHARE1";
+const NONCE_LEN: usize = 12;
+/// Fragment prefix, so other fragments in pasted links aren't mistaken
+/// for keys
+const KEY_PARAM: &str = "key=";
+const BURN_PARAM: &str = "burn=";
+
+pub type ShareKey = [u8; 32];
+
+/// Encrypt `plaintext` under a new key: `MAGIC || nonce || ciphertext`
+pub fn seal(plaintext: &[u8]) -> Result<(Vec<u8>, ShareKey), String> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = Aes256Gcm::new(&key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| "Failed to encrypt story".to_string())?;
+
+    let mut blob = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    blob.extend_from_slice(MAGIC);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext);
+    Ok((blob, key.into()))
+}
+
+pub fn open(blob: &[u8], key: &ShareKey) -> Result<Vec<u8>, String> {
+    let rest = blob
+        .strip_prefix(MAGIC)
+        .filter(|rest| rest.len() > NONCE_LEN)
+        .ok_or("This link doesn't point to an Aventura share")?;
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| {
+            "The share couldn't be decrypted. Check the link was copied whole.".to_string()
+        })
+}
+
+pub fn link(url: &str, key: &ShareKey, burn: Option<&Burn>) -> Result<String, String> {
+    let mut link = format!("{}#{}{}", url, KEY_PARAM, URL_SAFE_NO_PAD.encode(key));
+    if let Some(burn) = burn {
+        let burn = serde_json::to_vec(burn).map_err(|e| format!("Failed to build link: {}", e))?;
+        link.push('&');
+        link.push_str(BURN_PARAM);
+        link.push_str(&URL_SAFE_NO_PAD.encode(burn));
+    }
+    Ok(link)
+}
+
+/// Split a share link into its download URL, key and, for one-time links,
+/// how to delete the upload
+pub fn parse_link(link: &str) -> Result<(String, ShareKey, Option<Burn>), String> {
+    let (url, fragment) = link
+        .trim()
+        .rsplit_once('#')
+        .ok_or("The link is missing its key (the part after #)")?;
+    let mut params = fragment.split('&');
+    let encoded = params
+        .next()
+        .and_then(|param| param.strip_prefix(KEY_PARAM))
+        .ok_or("The link is missing its key (the part after #)")?;
+    let key = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .ok()
+        .and_then(|key| ShareKey::try_from(key).ok())
+        .ok_or("The link's key is damaged")?;
+    let burn = params
+        .find_map(|param| param.strip_prefix(BURN_PARAM))
+        .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+        .and_then(|burn| serde_json::from_slice(&burn).ok());
+    Ok((url.to_string(), key, burn))
+}
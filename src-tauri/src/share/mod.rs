@@ -0,0 +1,4 @@
+pub mod cleanup;
+pub mod commands;
+pub mod crypto;
+pub mod types;
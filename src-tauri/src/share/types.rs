@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use crate::remote::paste::PasteConfig;
+use crate::remote::s3::S3Config;
+use crate::remote::webdav::WebDavConfig;
+
+/// Where encrypted shares are uploaded. Only ever user-run or third-party
+/// storage; Aventura has no server of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ShareEndpoint {
+    S3(S3Config),
+    WebDav(WebDavConfig),
+    /// A 0x0.st-style anonymous file host
+    Paste(PasteConfig),
+}
+
+/// Stored in `share_settings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShareSettings {
+    pub endpoint: Option<ShareEndpoint>,
+    /// How long links stay valid where the endpoint can enforce it
+    /// (S3 caps this at 7 days)
+    pub expires_hours: u32,
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            expires_hours: 72,
+        }
+    }
+}
+
+/// How the receiver deletes a share once it's imported, so a link only
+/// works once. Travels in the link's fragment next to the key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Burn {
+    /// Send DELETE to this presigned URL (S3)
+    Delete { url: String },
+    /// POST this management token back to the download URL (0x0.st-style hosts)
+    Token { token: String },
+}
+
+/// Result of `export_to_encrypted_share`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareLink {
+    /// Download URL with the decryption key in the fragment, which
+    /// browsers and HTTP clients never send to the server
+    pub link: String,
+    /// When the upload is deleted if nobody has imported it
+    pub expires_at: i64,
+    /// Importing the link deletes the upload. WebDAV gives receivers no way
+    /// to delete, so those shares stay until they expire.
+    pub one_time: bool,
+}
@@ -0,0 +1,131 @@
+use serde_json::json;
+use tauri::State;
+use uuid::Uuid;
+
+use super::engine;
+use super::types::{ApplyReport, CharacterSheet, StatAttribute, StatDelta, StatRule};
+use crate::db::{now_millis, DbState};
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+/// Get the current stat sheets for all characters in a story
+#[tauri::command]
+pub async fn get_character_sheets(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<CharacterSheet>, String> {
+    engine::load_sheets(db.pool(), &story_id).await
+}
+
+/// Apply stat deltas directly (manual edits from the UI)
+#[tauri::command]
+pub async fn apply_stat_deltas(
+    db: State<'_, DbState>,
+    story_id: String,
+    deltas: Vec<StatDelta>,
+) -> Result<ApplyReport, String> {
+    engine::apply_deltas(db.pool(), &story_id, &deltas, "manual").await
+}
+
+/// Queue a background job that extracts stat changes from a story entry
+#[tauri::command]
+pub async fn queue_stat_extraction(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::StatExtraction,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
+
+/// Set a stat's value and bounds for a character (manual correction)
+#[tauri::command]
+pub async fn set_character_stat(
+    db: State<'_, DbState>,
+    story_id: String,
+    character_id: String,
+    stat: StatAttribute,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO character_stats (id, story_id, character_id, name, value, min_value, max_value, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(character_id, name) DO UPDATE SET value = excluded.value, \
+         min_value = excluded.min_value, max_value = excluded.max_value, updated_at = excluded.updated_at",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&story_id)
+    .bind(&character_id)
+    .bind(stat.name.trim())
+    .bind(stat.value)
+    .bind(stat.min_value)
+    .bind(stat.max_value)
+    .bind(now_millis())
+    .execute(db.pool())
+    .await
+    .map_err(|e| format!("Failed to save stat: {}", e))?;
+    Ok(())
+}
+
+/// Get the threshold rules configured for a story
+#[tauri::command]
+pub async fn get_stat_rules(db: State<'_, DbState>, story_id: String) -> Result<Vec<StatRule>, String> {
+    engine::load_rules(db.pool(), &story_id)
+        .await
+        .map_err(|e| format!("Failed to load stat rules: {}", e))
+}
+
+/// Replace the threshold rules for a story
+#[tauri::command]
+pub async fn set_stat_rules(
+    db: State<'_, DbState>,
+    story_id: String,
+    rules: Vec<StatRule>,
+) -> Result<(), String> {
+    if let Some(rule) = rules
+        .iter()
+        .find(|r| !["<=", "<", ">=", ">", "="].contains(&r.comparison.as_str()))
+    {
+        return Err(format!("Invalid comparison in stat rule: '{}'", rule.comparison));
+    }
+
+    let mut tx = db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    sqlx::query("DELETE FROM stat_rules WHERE story_id = ?")
+        .bind(&story_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to clear stat rules: {}", e))?;
+
+    let now = now_millis();
+    for (i, rule) in rules.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO stat_rules (id, story_id, stat_name, comparison, threshold, condition_name, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&story_id)
+        .bind(rule.stat_name.trim())
+        .bind(&rule.comparison)
+        .bind(rule.threshold)
+        .bind(rule.condition_name.trim())
+        .bind(now + i as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save stat rule: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit stat rules: {}", e))
+}
@@ -0,0 +1,407 @@
+use serde_json::json;
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use super::parser::parse_stat_blocks;
+use super::types::{
+    ApplyReport, CharacterSheet, HeldItem, StatAttribute, StatCondition, StatDelta, StatRule,
+};
+use crate::context::branch::characters;
+use crate::db::now_millis;
+use crate::jobs::types::BackgroundJob;
+
+/// Map lowercase character names (and ids) to character ids on the story's current branch
+async fn character_lookup(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<HashMap<String, String>, String> {
+    let mut lookup = HashMap::new();
+    for character in characters(pool, story_id).await? {
+        lookup.insert(character.name.to_lowercase(), character.id.clone());
+        lookup.insert(character.id.clone(), character.id);
+    }
+    Ok(lookup)
+}
+
+pub async fn load_rules(pool: &SqlitePool, story_id: &str) -> Result<Vec<StatRule>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT stat_name, comparison, threshold, condition_name FROM stat_rules \
+         WHERE story_id = ? ORDER BY created_at",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| StatRule {
+            stat_name: r.get("stat_name"),
+            comparison: r.get("comparison"),
+            threshold: r.get("threshold"),
+            condition_name: r.get("condition_name"),
+        })
+        .collect())
+}
+
+/// Apply deltas to the story's character sheets in one transaction, then
+/// re-evaluate threshold rules for every character that changed.
+pub async fn apply_deltas(
+    pool: &SqlitePool,
+    story_id: &str,
+    deltas: &[StatDelta],
+    source: &str,
+) -> Result<ApplyReport, String> {
+    let rules = load_rules(pool, story_id)
+        .await
+        .map_err(|e| format!("Failed to load stat rules: {}", e))?;
+    let lookup = character_lookup(pool, story_id).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let mut report = ApplyReport::default();
+    let mut touched = BTreeSet::new();
+    let now = now_millis();
+
+    for delta in deltas {
+        let name = delta.character().trim();
+        let Some(character_id) = lookup
+            .get(&name.to_lowercase())
+            .or_else(|| lookup.get(name))
+        else {
+            if !report.unknown_characters.iter().any(|c| c == name) {
+                report.unknown_characters.push(name.to_string());
+            }
+            continue;
+        };
+
+        apply_one(&mut tx, story_id, character_id, delta, source, now)
+            .await
+            .map_err(|e| format!("Failed to apply stat change: {}", e))?;
+        touched.insert(character_id.clone());
+        report.applied += 1;
+    }
+
+    for character_id in &touched {
+        let changes = evaluate_rules(&mut tx, story_id, character_id, &rules, now)
+            .await
+            .map_err(|e| format!("Failed to evaluate stat rules: {}", e))?;
+        report.rule_changes.extend(changes);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit stat changes: {}", e))?;
+
+    Ok(report)
+}
+
+async fn apply_one(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: &str,
+    character_id: &str,
+    delta: &StatDelta,
+    source: &str,
+    now: i64,
+) -> Result<(), sqlx::Error> {
+    match delta {
+        StatDelta::Attribute {
+            stat,
+            amount,
+            absolute,
+            ..
+        } => {
+            let existing = sqlx::query(
+                "SELECT value, min_value, max_value FROM character_stats WHERE character_id = ? AND name = ?",
+            )
+            .bind(character_id)
+            .bind(stat.trim())
+            .fetch_optional(&mut **tx)
+            .await?;
+
+            let (current, min, max) = match existing {
+                Some(row) => (
+                    row.get::<f64, _>("value"),
+                    row.get::<Option<f64>, _>("min_value"),
+                    row.get::<Option<f64>, _>("max_value"),
+                ),
+                None => (0.0, None, None),
+            };
+            let mut value = if *absolute { *amount } else { current + amount };
+            if let Some(min) = min {
+                value = value.max(min);
+            }
+            if let Some(max) = max {
+                value = value.min(max);
+            }
+
+            sqlx::query(
+                "INSERT INTO character_stats (id, story_id, character_id, name, value, updated_at) \
+                 VALUES (?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(character_id, name) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(story_id)
+            .bind(character_id)
+            .bind(stat.trim())
+            .bind(value)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+        }
+        StatDelta::Condition { name, active, .. } => {
+            if *active {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO character_conditions (id, story_id, character_id, name, source, created_at) \
+                     VALUES (?, ?, ?, ?, ?, ?)",
+                )
+                .bind(Uuid::new_v4().to_string())
+                .bind(story_id)
+                .bind(character_id)
+                .bind(name.trim())
+                .bind(source)
+                .bind(now)
+                .execute(&mut **tx)
+                .await?;
+            } else {
+                sqlx::query("DELETE FROM character_conditions WHERE character_id = ? AND name = ?")
+                    .bind(character_id)
+                    .bind(name.trim())
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+        StatDelta::Item { name, quantity, .. } => {
+            sqlx::query(
+                "INSERT INTO inventory_items (id, story_id, character_id, name, quantity, created_at, updated_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(character_id, name) DO UPDATE SET \
+                 quantity = quantity + excluded.quantity, updated_at = excluded.updated_at",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(story_id)
+            .bind(character_id)
+            .bind(name.trim())
+            .bind(quantity)
+            .bind(now)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+
+            sqlx::query("DELETE FROM inventory_items WHERE character_id = ? AND quantity <= 0")
+                .bind(character_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply or lift rule-driven conditions for one character
+async fn evaluate_rules(
+    tx: &mut Transaction<'_, Sqlite>,
+    story_id: &str,
+    character_id: &str,
+    rules: &[StatRule],
+    now: i64,
+) -> Result<Vec<StatDelta>, sqlx::Error> {
+    let mut changes = Vec::new();
+
+    for rule in rules {
+        let value: Option<f64> = sqlx::query_scalar(
+            "SELECT value FROM character_stats WHERE character_id = ? AND name = ?",
+        )
+        .bind(character_id)
+        .bind(&rule.stat_name)
+        .fetch_optional(&mut **tx)
+        .await?;
+        let Some(value) = value else { continue };
+
+        if rule.matches(value) {
+            let inserted = sqlx::query(
+                "INSERT OR IGNORE INTO character_conditions (id, story_id, character_id, name, source, created_at) \
+                 VALUES (?, ?, ?, ?, 'rule', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(story_id)
+            .bind(character_id)
+            .bind(&rule.condition_name)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+            if inserted.rows_affected() > 0 {
+                changes.push(StatDelta::Condition {
+                    character: character_id.to_string(),
+                    name: rule.condition_name.clone(),
+                    active: true,
+                });
+            }
+        } else {
+            let removed = sqlx::query(
+                "DELETE FROM character_conditions WHERE character_id = ? AND name = ? AND source = 'rule'",
+            )
+            .bind(character_id)
+            .bind(&rule.condition_name)
+            .execute(&mut **tx)
+            .await?;
+            if removed.rows_affected() > 0 {
+                changes.push(StatDelta::Condition {
+                    character: character_id.to_string(),
+                    name: rule.condition_name.clone(),
+                    active: false,
+                });
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Load every character sheet on the story's current branch that has at least one stat,
+/// condition, or item. Stats of other branches' copies of a character are left out.
+pub async fn load_sheets(pool: &SqlitePool, story_id: &str) -> Result<Vec<CharacterSheet>, String> {
+    let mut roster = characters(pool, story_id).await?;
+    roster.sort_by_key(|c| (c.relationship.as_deref() != Some("self"), c.name.clone()));
+
+    let stats = sqlx::query(
+        "SELECT character_id, name, value, min_value, max_value FROM character_stats \
+         WHERE story_id = ? ORDER BY name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load character stats: {}", e))?;
+    let conditions = sqlx::query(
+        "SELECT character_id, name, description, source FROM character_conditions \
+         WHERE story_id = ? ORDER BY created_at",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load character conditions: {}", e))?;
+    let items = sqlx::query(
+        "SELECT character_id, name, quantity FROM inventory_items WHERE story_id = ? ORDER BY name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load inventory: {}", e))?;
+
+    let mut sheets: Vec<CharacterSheet> = roster
+        .into_iter()
+        .map(|c| CharacterSheet {
+            character_id: c.id,
+            name: c.name,
+            attributes: Vec::new(),
+            conditions: Vec::new(),
+            inventory: Vec::new(),
+        })
+        .collect();
+    let index: HashMap<String, usize> = sheets
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.character_id.clone(), i))
+        .collect();
+
+    for row in &stats {
+        if let Some(&i) = index.get(&row.get::<String, _>("character_id")) {
+            sheets[i].attributes.push(StatAttribute {
+                name: row.get("name"),
+                value: row.get("value"),
+                min_value: row.get("min_value"),
+                max_value: row.get("max_value"),
+            });
+        }
+    }
+    for row in &conditions {
+        if let Some(&i) = index.get(&row.get::<String, _>("character_id")) {
+            sheets[i].conditions.push(StatCondition {
+                name: row.get("name"),
+                description: row.get("description"),
+                source: row.get("source"),
+            });
+        }
+    }
+    for row in &items {
+        if let Some(&i) = index.get(&row.get::<String, _>("character_id")) {
+            sheets[i].inventory.push(HeldItem {
+                name: row.get("name"),
+                quantity: row.get("quantity"),
+            });
+        }
+    }
+
+    sheets.retain(|s| !s.attributes.is_empty() || !s.conditions.is_empty() || !s.inventory.is_empty());
+    Ok(sheets)
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Render attributes and conditions as a compact block for prompt injection,
+/// one line per character. Inventories are summarized separately.
+pub fn format_stat_block(sheets: &[CharacterSheet]) -> String {
+    sheets
+        .iter()
+        .filter(|sheet| !sheet.attributes.is_empty() || !sheet.conditions.is_empty())
+        .map(|sheet| {
+            let mut parts = Vec::new();
+            if !sheet.attributes.is_empty() {
+                let attributes: Vec<String> = sheet
+                    .attributes
+                    .iter()
+                    .map(|a| match a.max_value {
+                        Some(max) => format!("{} {}/{}", a.name, format_number(a.value), format_number(max)),
+                        None => format!("{} {}", a.name, format_number(a.value)),
+                    })
+                    .collect();
+                parts.push(attributes.join(", "));
+            }
+            if !sheet.conditions.is_empty() {
+                let names: Vec<&str> = sheet.conditions.iter().map(|c| c.name.as_str()).collect();
+                parts.push(format!("Conditions: {}", names.join(", ")));
+            }
+            format!("{}: {}", sheet.name, parts.join(" | "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Background job: parse `<stats>` blocks from a story entry and apply them
+pub async fn run_extraction_job(pool: &SqlitePool, job: &BackgroundJob) -> Result<serde_json::Value, String> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or("Stat extraction job is missing entryId")?;
+
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let deltas = parse_stat_blocks(&content);
+    if deltas.is_empty() {
+        return Ok(json!({ "entryId": entry_id, "applied": 0 }));
+    }
+
+    let report = apply_deltas(pool, &story_id, &deltas, "extraction").await?;
+    Ok(json!({
+        "entryId": entry_id,
+        "applied": report.applied,
+        "unknownCharacters": report.unknown_characters,
+        "ruleChanges": report.rule_changes,
+    }))
+}
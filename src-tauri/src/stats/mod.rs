@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod engine;
+pub mod parser;
+pub mod types;
@@ -0,0 +1,94 @@
+use super::types::StatDelta;
+
+/// Extract stat deltas from `<stats>` blocks in a response.
+///
+/// One change per line, prefixed with the character name:
+///
+/// ```text
+/// <stats>
+/// Aria: hp -5
+/// Aria: gold = 40
+/// Aria: +condition Poisoned
+/// Aria: -item Rope
+/// Aria: +item Torch x2
+/// </stats>
+/// ```
+///
+/// Lines that don't match the format are skipped.
+pub fn parse_stat_blocks(text: &str) -> Vec<StatDelta> {
+    let mut deltas = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("<stats>") {
+        let body = &rest[start + "<stats>".len()..];
+        let (block, remainder) = match body.find("</stats>") {
+            Some(end) => (&body[..end], &body[end + "</stats>".len()..]),
+            None => (body, ""),
+        };
+        deltas.extend(block.lines().filter_map(parse_line));
+        rest = remainder;
+    }
+
+    deltas
+}
+
+fn parse_line(line: &str) -> Option<StatDelta> {
+    let (character, change) = line.trim().trim_start_matches(['-', '*']).split_once(':')?;
+    let character = character.trim().to_string();
+    let change = change.trim();
+    if character.is_empty() || change.is_empty() {
+        return None;
+    }
+
+    for (prefix, active) in [("+condition ", true), ("-condition ", false)] {
+        if let Some(name) = change.strip_prefix(prefix) {
+            return Some(StatDelta::Condition {
+                character,
+                name: name.trim().to_string(),
+                active,
+            });
+        }
+    }
+
+    for (prefix, sign) in [("+item ", 1), ("-item ", -1)] {
+        if let Some(item) = change.strip_prefix(prefix) {
+            let (name, quantity) = split_quantity(item.trim());
+            return Some(StatDelta::Item {
+                character,
+                name,
+                quantity: sign * quantity,
+            });
+        }
+    }
+
+    // "<stat> +N", "<stat> -N", or "<stat> = N"
+    let (stat, amount) = change.rsplit_once(char::is_whitespace)?;
+    let stat = stat.trim();
+    let (stat, absolute) = match stat.strip_suffix('=') {
+        Some(stat) => (stat.trim(), true),
+        None => (stat, false),
+    };
+    if stat.is_empty() {
+        return None;
+    }
+    if !absolute && !amount.starts_with(['+', '-']) {
+        return None;
+    }
+
+    Some(StatDelta::Attribute {
+        character,
+        stat: stat.to_string(),
+        amount: amount.trim_start_matches('+').parse().ok()?,
+        absolute,
+    })
+}
+
+/// Split "Torch x2" into ("Torch", 2); items without a count have quantity 1
+fn split_quantity(item: &str) -> (String, i64) {
+    if let Some((name, count)) = item.rsplit_once(" x") {
+        if let Ok(count) = count.trim().parse::<i64>() {
+            return (name.trim().to_string(), count.max(1));
+        }
+    }
+    (item.to_string(), 1)
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+/// A single change to a character's sheet, extracted from a response or sent by the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StatDelta {
+    /// Numeric attribute change; `absolute` sets the value instead of adding to it
+    #[serde(rename_all = "camelCase")]
+    Attribute {
+        character: String,
+        stat: String,
+        amount: f64,
+        #[serde(default)]
+        absolute: bool,
+    },
+    /// Condition gained (`active`) or lost
+    #[serde(rename_all = "camelCase")]
+    Condition {
+        character: String,
+        name: String,
+        active: bool,
+    },
+    /// Item gained (positive quantity) or lost (negative quantity)
+    #[serde(rename_all = "camelCase")]
+    Item {
+        character: String,
+        name: String,
+        quantity: i64,
+    },
+}
+
+impl StatDelta {
+    pub fn character(&self) -> &str {
+        match self {
+            StatDelta::Attribute { character, .. }
+            | StatDelta::Condition { character, .. }
+            | StatDelta::Item { character, .. } => character,
+        }
+    }
+}
+
+/// Numeric attribute on a character sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatAttribute {
+    pub name: String,
+    pub value: f64,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+/// Active condition on a character sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatCondition {
+    pub name: String,
+    pub description: Option<String>,
+    /// 'manual' | 'extraction' | 'rule'
+    pub source: String,
+}
+
+/// Item held by a character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldItem {
+    pub name: String,
+    pub quantity: i64,
+}
+
+/// Current stat sheet for one character
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterSheet {
+    pub character_id: String,
+    pub name: String,
+    pub attributes: Vec<StatAttribute>,
+    pub conditions: Vec<StatCondition>,
+    pub inventory: Vec<HeldItem>,
+}
+
+/// Threshold rule: while `stat <comparison> threshold` holds, the condition is applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatRule {
+    pub stat_name: String,
+    /// One of '<=', '<', '>=', '>', '='
+    pub comparison: String,
+    pub threshold: f64,
+    pub condition_name: String,
+}
+
+impl StatRule {
+    pub fn matches(&self, value: f64) -> bool {
+        match self.comparison.as_str() {
+            "<=" => value <= self.threshold,
+            "<" => value < self.threshold,
+            ">=" => value >= self.threshold,
+            ">" => value > self.threshold,
+            "=" => (value - self.threshold).abs() < f64::EPSILON,
+            _ => false,
+        }
+    }
+}
+
+/// Outcome of applying a batch of deltas
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    pub applied: usize,
+    /// Character names in the deltas that matched no character in the story
+    pub unknown_characters: Vec<String>,
+    /// Conditions added or removed by stat rules
+    pub rule_changes: Vec<StatDelta>,
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use super::image_gc::{self, ImageGcReport, ImageGcResult};
+use super::relocate;
+use super::report::{self, StorageReport};
+use crate::db::DbState;
+use crate::paths::{self, DataPaths};
+
+/// Move the database, images and backups of every profile to `target`,
+/// then restart on the new folder. The old folder is kept as it was and
+/// can be deleted once everything looks right.
+#[tauri::command]
+pub async fn set_data_directory(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    paths: State<'_, DataPaths>,
+    target: String,
+) -> Result<(), String> {
+    let base = paths::app_dirs(&app)?;
+    relocate::relocate(db.pool(), &base, &paths, &PathBuf::from(target)).await?;
+    app.restart()
+}
+
+/// Sizes of the database, images, backups and logs, and the space left
+#[tauri::command]
+pub async fn get_storage_report(
+    db: State<'_, DbState>,
+    paths: State<'_, DataPaths>,
+) -> Result<StorageReport, String> {
+    Ok(report::build(db.pool(), &paths).await)
+}
+
+/// Unused images a cleanup would remove, without removing them
+#[tauri::command]
+pub async fn preview_image_gc(db: State<'_, DbState>) -> Result<ImageGcReport, String> {
+    image_gc::preview(db.pool()).await
+}
+
+/// Remove unused images and give their space back
+#[tauri::command]
+pub async fn run_image_gc(db: State<'_, DbState>) -> Result<ImageGcResult, String> {
+    image_gc::run(db.pool()).await
+}
@@ -0,0 +1,319 @@
+//! Garbage collection of images nothing uses any more. Images live in the
+//! database as text, so they pile up in two places:
+//! - image rows whose entry, story, branch or checkpoint is gone
+//! - old portraits inside checkpoint and world state snapshots, left behind
+//!   each time a character's portrait is regenerated
+//!
+//! Recent snapshots and the undo journal are left alone, as is any image
+//! that's still in use somewhere else.
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+use super::space;
+use crate::db::now_millis;
+use crate::journal::types::RowImage;
+
+/// Snapshots this recent keep every portrait they hold
+const SAFETY_WINDOW_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Newest snapshots of each story kept whole, however old
+const RECENT_SNAPSHOTS: i64 = 5;
+
+/// Tables holding JSON copies of a story's characters
+const SNAPSHOT_TABLES: &[&str] = &["checkpoints", "world_state_snapshots"];
+
+/// Image rows that no longer belong to anything
+const ORPHANS: &[(&str, &str)] = &[
+    (
+        "embedded_images",
+        "entry_id NOT IN (SELECT id FROM story_entries) \
+         OR story_id NOT IN (SELECT id FROM stories)",
+    ),
+    (
+        "background_images",
+        "story_id NOT IN (SELECT id FROM stories) \
+         OR (branch_id IS NOT NULL AND branch_id NOT IN (SELECT id FROM branches)) \
+         OR (checkpoint_id IS NOT NULL AND checkpoint_id NOT IN (SELECT id FROM checkpoints))",
+    ),
+    (
+        "character_sprites",
+        "character_id NOT IN (SELECT id FROM characters) \
+         OR story_id NOT IN (SELECT id FROM stories)",
+    ),
+];
+
+/// Images in use outside the tables above
+const LIVE_IMAGES: &[(&str, &str)] = &[("characters", "portrait"), ("character_vault", "portrait")];
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageGcReport {
+    /// Images that are (or would be) removed
+    pub image_count: u64,
+    pub bytes: u64,
+    /// Image rows whose entry, story, branch or checkpoint is gone
+    pub orphaned_images: u64,
+    /// Replaced portraits kept only by old snapshots
+    pub stale_portraits: u64,
+    /// Unused images kept because something else still holds the same
+    /// image: a recent snapshot, the undo history or a live row
+    pub kept_for_safety: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageGcResult {
+    pub collected: ImageGcReport,
+    /// Whether the database file was compacted to give the space back
+    pub compacted: bool,
+}
+
+type ImageHash = [u8; 32];
+
+fn hash(image: &str) -> ImageHash {
+    Sha256::digest(image.as_bytes()).into()
+}
+
+fn is_image(value: &Value) -> Option<&str> {
+    value.as_str().filter(|image| !image.is_empty())
+}
+
+/// An orphaned image row
+struct Orphan {
+    table: &'static str,
+    id: String,
+}
+
+/// A snapshot whose character list loses some portraits
+struct SnapshotEdit {
+    table: &'static str,
+    id: String,
+    characters: String,
+}
+
+#[derive(Default)]
+struct Plan {
+    report: ImageGcReport,
+    orphans: Vec<Orphan>,
+    snapshots: Vec<SnapshotEdit>,
+}
+
+async fn fetch_strings(conn: &mut SqliteConnection, sql: &str) -> Result<Vec<String>, String> {
+    sqlx::query_scalar::<_, String>(sql)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read images: {}", e))
+}
+
+/// Portraits in a JSON list of characters
+fn portraits(characters: &str) -> Vec<String> {
+    let Ok(Value::Array(list)) = serde_json::from_str::<Value>(characters) else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter_map(|character| is_image(character.get("portrait")?).map(str::to_string))
+        .collect()
+}
+
+/// Every image the undo journal could write back
+async fn journal_images(
+    conn: &mut SqliteConnection,
+    protected: &mut HashSet<ImageHash>,
+) -> Result<(), String> {
+    for changes in fetch_strings(conn, "SELECT changes FROM operation_journal").await? {
+        let rows: Vec<RowImage> = serde_json::from_str(&changes).unwrap_or_default();
+        for image in rows {
+            for (column, value) in &image.row {
+                if column == "characters_snapshot" {
+                    for portrait in portraits(value.as_str().unwrap_or_default()) {
+                        protected.insert(hash(&portrait));
+                    }
+                } else if let Some(data) = is_image(value) {
+                    let tracked = LIVE_IMAGES
+                        .iter()
+                        .chain(super::report::IMAGE_COLUMNS)
+                        .any(|(table, col)| *table == image.table && col == column);
+                    if tracked {
+                        protected.insert(hash(data));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn build_plan(conn: &mut SqliteConnection) -> Result<Plan, String> {
+    let mut plan = Plan::default();
+    let mut protected = HashSet::new();
+
+    let mut live_portraits = HashMap::new();
+    for (table, column) in LIVE_IMAGES {
+        let sql = format!("SELECT id, COALESCE({column}, '') FROM {table}");
+        let rows: Vec<(String, String)> = sqlx::query_as(&sql)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read images: {}", e))?;
+        for (id, image) in rows {
+            if !image.is_empty() {
+                protected.insert(hash(&image));
+            }
+            if *table == "characters" {
+                live_portraits.insert(id, image);
+            }
+        }
+    }
+    for (table, condition) in ORPHANS {
+        let sql =
+            format!("SELECT image_data FROM {table} WHERE image_data != '' AND NOT ({condition})");
+        for image in fetch_strings(conn, &sql).await? {
+            protected.insert(hash(&image));
+        }
+    }
+    journal_images(conn, &mut protected).await?;
+
+    // Newest first per story, so the safety window can be applied by rank
+    let cutoff = now_millis() - SAFETY_WINDOW_MS;
+    let mut old_snapshots = Vec::new();
+    for table in SNAPSHOT_TABLES {
+        let sql = format!(
+            "SELECT id, characters_snapshot, \
+             created_at >= ? OR ROW_NUMBER() OVER (PARTITION BY story_id ORDER BY created_at DESC) <= ? \
+             FROM {table}"
+        );
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(&sql)
+            .bind(cutoff)
+            .bind(RECENT_SNAPSHOTS)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+        for (id, characters, recent) in rows {
+            if recent != 0 {
+                for portrait in portraits(&characters) {
+                    protected.insert(hash(&portrait));
+                }
+            } else {
+                old_snapshots.push((*table, id, characters));
+            }
+        }
+    }
+
+    let mut kept = HashSet::new();
+    for (table, condition) in ORPHANS {
+        let sql = format!("SELECT id, image_data FROM {table} WHERE {condition}");
+        let rows: Vec<(String, String)> = sqlx::query_as(&sql)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+        for (id, image) in rows {
+            if protected.contains(&hash(&image)) {
+                kept.insert(hash(&image));
+                continue;
+            }
+            plan.report.orphaned_images += 1;
+            plan.report.bytes += image.len() as u64;
+            plan.orphans.push(Orphan { table, id });
+        }
+    }
+
+    for (table, id, characters) in old_snapshots {
+        let Ok(Value::Array(mut list)) = serde_json::from_str::<Value>(&characters) else {
+            continue;
+        };
+        let mut changed = false;
+        for character in list.iter_mut() {
+            let Some(fields) = character.as_object_mut() else {
+                continue;
+            };
+            let Some(portrait) = fields.get("portrait").and_then(is_image) else {
+                continue;
+            };
+            // Only portraits a live character has since replaced; one that
+            // was deleted may need its old portrait back
+            let replaced = fields
+                .get("id")
+                .and_then(Value::as_str)
+                .and_then(|id| live_portraits.get(id))
+                .is_some_and(|live| live != portrait);
+            if !replaced {
+                continue;
+            }
+            if protected.contains(&hash(portrait)) {
+                kept.insert(hash(portrait));
+                continue;
+            }
+            plan.report.stale_portraits += 1;
+            plan.report.bytes += portrait.len() as u64;
+            fields.insert("portrait".to_string(), Value::Null);
+            changed = true;
+        }
+        if changed {
+            let characters = serde_json::to_string(&list)
+                .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+            plan.snapshots.push(SnapshotEdit {
+                table,
+                id,
+                characters,
+            });
+        }
+    }
+
+    plan.report.image_count = plan.report.orphaned_images + plan.report.stale_portraits;
+    plan.report.kept_for_safety = kept.len() as u64;
+    Ok(plan)
+}
+
+/// What `run` would remove, without changing anything
+pub async fn preview(pool: &SqlitePool) -> Result<ImageGcReport, String> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    Ok(build_plan(&mut conn).await?.report)
+}
+
+/// Remove unused images, then compact the database if there's room to
+pub async fn run(pool: &SqlitePool) -> Result<ImageGcResult, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start cleanup: {}", e))?;
+    let plan = build_plan(&mut tx).await?;
+    for orphan in &plan.orphans {
+        sqlx::query(&format!("DELETE FROM {} WHERE id = ?", orphan.table))
+            .bind(&orphan.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete image: {}", e))?;
+    }
+    for edit in &plan.snapshots {
+        sqlx::query(&format!(
+            "UPDATE {} SET characters_snapshot = ? WHERE id = ?",
+            edit.table
+        ))
+        .bind(&edit.characters)
+        .bind(&edit.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update snapshot: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to finish cleanup: {}", e))?;
+
+    // Deleted pages stay in the file until it's rebuilt, which needs room
+    // for a second copy
+    let compacted = plan.report.bytes > 0
+        && space::ensure_free_for_database(pool, space::used_bytes(pool).await)
+            .await
+            .is_ok()
+        && sqlx::query("VACUUM").execute(pool).await.is_ok();
+    Ok(ImageGcResult {
+        collected: plan.report,
+        compacted,
+    })
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod image_gc;
+pub mod relocate;
+pub mod report;
+pub mod space;
@@ -0,0 +1,162 @@
+//! Moving the data folder. Everything is copied to the new folder and
+//! checked there before the app is pointed at it; the old folder is left
+//! untouched, so a failed or interrupted move loses nothing.
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+use super::space;
+use crate::paths::{self, AppDirs, DataPaths, DATABASE_FILE};
+use crate::profiles::store::{profile_dir, DEFAULT_PROFILE};
+
+/// Files beside a SQLite database that belong to it
+const DATABASE_SIDECARS: &[&str] = &["-wal", "-shm", "-journal"];
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn is_database_file(path: &Path, database: &Path) -> bool {
+    path == database
+        || DATABASE_SIDECARS
+            .iter()
+            .any(|suffix| path == with_suffix(database, suffix))
+}
+
+/// Check the new folder can take the data without overwriting anything
+fn check_target(paths: &DataPaths, target: &Path) -> Result<(), String> {
+    if !target.is_absolute() {
+        return Err("Choose a full folder path".to_string());
+    }
+    if target.starts_with(&paths.root) || paths.root.starts_with(target) {
+        return Err(
+            "The new folder can't be inside the current data folder or contain it".to_string(),
+        );
+    }
+    if target.join(DATABASE_FILE).exists() || target.join("profiles.json").exists() {
+        return Err("That folder already holds Aventura data; choose an empty folder".to_string());
+    }
+    std::fs::create_dir_all(target).map_err(|e| format!("Can't use that folder: {}", e))?;
+    let probe = target.join(".aventura-write-test");
+    std::fs::write(&probe, b"")
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|e| format!("Can't write to that folder: {}", e))
+}
+
+/// Copy a folder tree, leaving out `skip` and anything for which it's true
+fn copy_tree(from: &Path, to: &Path, skip: &dyn Fn(&Path) -> bool) -> Result<u64, String> {
+    let mut copied = 0;
+    let entries =
+        std::fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    std::fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        let path = entry.path();
+        if skip(&path) {
+            continue;
+        }
+        let dest = to.join(entry.file_name());
+        if path.is_dir() {
+            copied += copy_tree(&path, &dest, skip)?;
+        } else {
+            copied += std::fs::copy(&path, &dest)
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(copied)
+}
+
+/// Copy a database that nothing has open, along with its journal files
+fn copy_closed_database(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(dir) = to.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    std::fs::copy(from, to).map_err(|e| format!("Failed to copy database: {}", e))?;
+    for suffix in DATABASE_SIDECARS {
+        let sidecar = with_suffix(from, suffix);
+        if sidecar.exists() {
+            std::fs::copy(&sidecar, with_suffix(to, suffix))
+                .map_err(|e| format!("Failed to copy database: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+async fn check_database(path: &Path) -> Result<(), String> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open the copied database: {}", e))?;
+    let status: Result<String, _> = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_one(&pool)
+        .await;
+    pool.close().await;
+    match status {
+        Ok(status) if status == "ok" => Ok(()),
+        Ok(status) => Err(format!("The copied database is damaged: {}", status)),
+        Err(e) => Err(format!("Failed to check the copied database: {}", e)),
+    }
+}
+
+/// Copy all data of every profile to `target` and point the app there
+/// from the next launch. `pool` is the open database of the active profile.
+pub async fn relocate(
+    pool: &SqlitePool,
+    base: &AppDirs,
+    paths: &DataPaths,
+    target: &Path,
+) -> Result<(), String> {
+    check_target(paths, target)?;
+    // The active database is in the data folder, so this counts it too
+    space::ensure_free(target, space::dir_size(&paths.root))?;
+    let default_database = base.config.join(DATABASE_FILE);
+    let target_database = |id: &str| profile_dir(target, id).join(DATABASE_FILE);
+
+    let root = paths.root.clone();
+    let local = paths.local.clone();
+    let active = paths.database.clone();
+    let to = target.to_path_buf();
+    let default_source = default_database.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        copy_tree(&root, &to, &|path| {
+            // The open database is copied through SQLite below, models and
+            // the location pointer stay on this machine
+            is_database_file(path, &active)
+                || (root == local
+                    && (path == local.join("translation-models")
+                        || path == local.join("models")
+                        || path == local.join("location.json")))
+                || path.extension().is_some_and(|ext| ext == "partial")
+        })?;
+        // Before profiles, the default database lived outside the data folder
+        if !default_source.starts_with(&root) && default_source != active && default_source.exists()
+        {
+            copy_closed_database(&default_source, &to.join(DATABASE_FILE))?;
+        }
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| format!("Failed to copy data: {}", e))??;
+
+    let new_active = target_database(&paths.profile_id);
+    if let Some(dir) = new_active.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    // A consistent copy even while the app is writing to it
+    sqlx::query("VACUUM INTO ?")
+        .bind(new_active.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to copy database: {}", e))?;
+    check_database(&new_active).await?;
+    if paths.profile_id != DEFAULT_PROFILE && default_database.exists() {
+        check_database(&target_database(DEFAULT_PROFILE)).await?;
+    }
+
+    paths::save_location(&paths.local, target)
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::space::{database_size, dir_size, disk_space, LOW_SPACE_BYTES};
+use crate::paths::DataPaths;
+
+/// Columns holding images, as base64 or data URLs
+pub const IMAGE_COLUMNS: &[(&str, &str)] = &[
+    ("embedded_images", "image_data"),
+    ("background_images", "image_data"),
+    ("character_sprites", "image_data"),
+    ("characters", "portrait"),
+    ("character_vault", "portrait"),
+];
+
+/// Tables that only hold diagnostics
+const LOG_TABLES: &[&str] = &[
+    "llm_request_log",
+    "prompt_cache_stats",
+    "filter_hits",
+    "turn_pipeline_runs",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    /// Database file including its write-ahead log
+    pub database_bytes: u64,
+    /// Images stored in the database (part of `database_bytes`)
+    pub image_bytes: u64,
+    pub image_count: u64,
+    pub backup_bytes: u64,
+    /// Request logs and other diagnostics (part of `database_bytes`)
+    pub log_bytes: u64,
+    pub free_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub low_space: bool,
+}
+
+async fn image_usage(pool: &SqlitePool) -> (u64, u64) {
+    let mut count = 0;
+    let mut bytes = 0;
+    for (table, column) in IMAGE_COLUMNS {
+        let sql = format!(
+            "SELECT COUNT(*), COALESCE(SUM(length({column})), 0) FROM {table} \
+             WHERE {column} IS NOT NULL AND {column} != ''"
+        );
+        // A table missing from an older schema just counts as empty
+        if let Ok((n, size)) = sqlx::query_as::<_, (i64, i64)>(&sql).fetch_one(pool).await {
+            count += n as u64;
+            bytes += size as u64;
+        }
+    }
+    (count, bytes)
+}
+
+async fn log_usage(pool: &SqlitePool) -> u64 {
+    let mut bytes = 0;
+    for table in LOG_TABLES {
+        let size: Result<Option<i64>, _> =
+            sqlx::query_scalar("SELECT SUM(pgsize) FROM dbstat WHERE name = ?")
+                .bind(table)
+                .fetch_one(pool)
+                .await;
+        bytes += size.ok().flatten().unwrap_or(0) as u64;
+    }
+    bytes
+}
+
+pub async fn build(pool: &SqlitePool, paths: &DataPaths) -> StorageReport {
+    let (image_count, image_bytes) = image_usage(pool).await;
+    let space = disk_space(&paths.database);
+    StorageReport {
+        database_bytes: database_size(&paths.database),
+        image_bytes,
+        image_count,
+        backup_bytes: dir_size(&paths.backups()),
+        log_bytes: log_usage(pool).await,
+        free_bytes: space.map(|(free, _)| free),
+        total_bytes: space.map(|(_, total)| total),
+        low_space: space.is_some_and(|(free, _)| free < LOW_SPACE_BYTES),
+    }
+}
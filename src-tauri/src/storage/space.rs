@@ -0,0 +1,101 @@
+//! Free space checks, so a full disk stops a write with a clear message
+//! instead of failing halfway through it.
+
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+/// Kept free on top of what a write needs, for the database's own growth
+pub const RESERVE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Below this the storage report warns
+pub const LOW_SPACE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// The deepest existing folder on the way to `path`, with symlinks resolved
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find_map(|p| p.canonicalize().ok())
+}
+
+/// Free and total bytes of the disk holding `path`, if the OS says
+pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    let path = existing_ancestor(path)?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| (disk.available_space(), disk.total_space()))
+}
+
+fn megabytes(bytes: u64) -> u64 {
+    bytes.div_ceil(1024 * 1024)
+}
+
+/// Fail unless the disk holding `path` has room for `needed` more bytes.
+/// Passes when free space can't be determined.
+pub fn ensure_free(path: &Path, needed: u64) -> Result<(), String> {
+    match disk_space(path) {
+        Some((free, _)) if free < needed.saturating_add(RESERVE_BYTES) => Err(format!(
+            "Not enough free space: this needs about {} MB but only {} MB is free. \
+             Delete old backups or unused images, or free up space on the device, then try again.",
+            megabytes(needed.saturating_add(RESERVE_BYTES)),
+            megabytes(free)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Total size of the files under `path`
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Size of a database including its write-ahead log
+pub fn database_size(path: &Path) -> u64 {
+    ["", "-wal"]
+        .iter()
+        .filter_map(|suffix| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(suffix);
+            std::fs::metadata(PathBuf::from(name)).ok()
+        })
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Pages in use by an open database, roughly what a `VACUUM INTO` copy takes
+pub async fn used_bytes(pool: &SqlitePool) -> u64 {
+    let pages: Result<(i64, i64), _> = sqlx::query_as(
+        "SELECT (SELECT page_count FROM pragma_page_count()) - (SELECT freelist_count FROM pragma_freelist_count()), \
+         (SELECT page_size FROM pragma_page_size())",
+    )
+    .fetch_one(pool)
+    .await;
+    pages
+        .map(|(count, size)| (count * size).max(0) as u64)
+        .unwrap_or(0)
+}
+
+/// `ensure_free` for the disk holding `pool`'s database file
+pub async fn ensure_free_for_database(pool: &SqlitePool, needed: u64) -> Result<(), String> {
+    let file: Option<String> =
+        sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to locate database: {}", e))?;
+    match file.filter(|file| !file.is_empty()) {
+        Some(file) => ensure_free(Path::new(&file), needed),
+        None => Ok(()),
+    }
+}
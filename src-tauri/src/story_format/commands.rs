@@ -0,0 +1,13 @@
+use super::types::ExportValidation;
+use super::validate::validate;
+
+/// Check a story export before importing it: reads any export version,
+/// reporting rows that don't fit the format and references that don't
+/// resolve
+#[tauri::command]
+pub async fn validate_export_file(path: String) -> Result<ExportValidation, String> {
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(validate(&content))
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod schema;
+pub mod types;
+pub mod upgrade;
+pub mod validate;
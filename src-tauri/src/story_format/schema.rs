@@ -0,0 +1,149 @@
+//! The `.avt` story export format, as written by the frontend exporter.
+//! Fields the importer relies on are typed; everything else is kept as is
+//! in `rest`, so a file round-trips without losing data this side doesn't
+//! know about.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Version the exporter writes now (`VERSION` in services/export.ts)
+pub const CURRENT_VERSION: &str = "1.10.0";
+
+/// A story export brought up to `CURRENT_VERSION`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryExport {
+    pub version: String,
+    pub exported_at: i64,
+    pub story: ExportedStory,
+    pub entries: Vec<ExportedEntry>,
+    pub characters: Vec<WorldRow>,
+    pub locations: Vec<WorldRow>,
+    pub items: Vec<WorldRow>,
+    pub story_beats: Vec<ExportedBeat>,
+    /// Since 1.1.0
+    pub lorebook_entries: Vec<WorldRow>,
+    /// Since 1.2.0
+    pub style_review_state: Option<Value>,
+    /// Since 1.4.0
+    pub embedded_images: Vec<EntryChild>,
+    /// Since 1.6.0
+    pub checkpoints: Vec<ExportedCheckpoint>,
+    /// Since 1.6.0
+    pub branches: Vec<ExportedBranch>,
+    /// Since 1.7.0
+    pub chapters: Vec<ExportedChapter>,
+    /// Since 1.8.0
+    pub current_bg_image: Option<String>,
+    /// Since 1.9.0
+    pub entry_alternatives: Vec<EntryChild>,
+    /// Since 1.10.0
+    pub entry_annotations: Vec<EntryChild>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedStory {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub current_branch_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    UserAction,
+    Narration,
+    System,
+    Retry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedEntry {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    pub content: String,
+    pub position: i64,
+    pub created_at: i64,
+    #[serde(default)]
+    pub branch_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+/// A character, location, item or lorebook entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldRow {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub branch_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedBeat {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub branch_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedCheckpoint {
+    pub id: String,
+    pub name: String,
+    pub last_entry_id: String,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedBranch {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub parent_branch_id: Option<String>,
+    pub fork_entry_id: String,
+    #[serde(default)]
+    pub checkpoint_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedChapter {
+    pub id: String,
+    pub number: i64,
+    pub start_entry_id: String,
+    pub end_entry_id: String,
+    #[serde(default)]
+    pub branch_id: Option<String>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+/// An embedded image, alternative or annotation, hanging off an entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryChild {
+    pub id: String,
+    pub entry_id: String,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The import would fail or lose the story
+    Error,
+    /// The import goes ahead but drops or detaches something
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProblem {
+    pub severity: Severity,
+    /// Where in the file, e.g. `entries[3].type`; empty for the whole file
+    pub path: String,
+    pub message: String,
+}
+
+/// What `validate_export_file` found
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportValidation {
+    /// No errors: the file can be imported
+    pub importable: bool,
+    /// Version the file says it is
+    pub version: Option<String>,
+    pub current_version: String,
+    /// Versions the file was upgraded through to be read
+    pub upgraded_through: Vec<String>,
+    pub title: Option<String>,
+    pub entries: usize,
+    pub branches: usize,
+    pub chapters: usize,
+    pub problems: Vec<ExportProblem>,
+}
@@ -0,0 +1,101 @@
+//! Upgrades from older export versions. Each step fills in what its version
+//! added, with the values the importer assumes when it's missing, so older
+//! files read as current ones.
+
+use serde_json::{json, Map, Value};
+
+use super::schema::CURRENT_VERSION;
+use crate::content_pack::archive::compare_versions;
+
+type Step = fn(&mut Map<String, Value>);
+
+/// Set `key` when it's missing or null
+fn default(object: &mut Map<String, Value>, key: &str, value: Value) {
+    if object.get(key).is_none_or(Value::is_null) {
+        object.insert(key.to_string(), value);
+    }
+}
+
+fn to_1_1(root: &mut Map<String, Value>) {
+    default(root, "lorebookEntries", json!([]));
+}
+
+fn to_1_2(root: &mut Map<String, Value>) {
+    default(root, "styleReviewState", Value::Null);
+}
+
+fn to_1_3(root: &mut Map<String, Value>) {
+    if let Some(story) = root.get_mut("story").and_then(Value::as_object_mut) {
+        default(story, "timeTracker", Value::Null);
+    }
+}
+
+fn to_1_4(root: &mut Map<String, Value>) {
+    default(root, "embeddedImages", json!([]));
+}
+
+fn to_1_5(root: &mut Map<String, Value>) {
+    if let Some(characters) = root.get_mut("characters").and_then(Value::as_array_mut) {
+        for character in characters.iter_mut().filter_map(Value::as_object_mut) {
+            default(character, "portrait", Value::Null);
+        }
+    }
+}
+
+fn to_1_6(root: &mut Map<String, Value>) {
+    default(root, "checkpoints", json!([]));
+    default(root, "branches", json!([]));
+}
+
+fn to_1_7(root: &mut Map<String, Value>) {
+    default(root, "chapters", json!([]));
+}
+
+fn to_1_8(root: &mut Map<String, Value>) {
+    default(root, "currentBgImage", Value::Null);
+}
+
+fn to_1_9(root: &mut Map<String, Value>) {
+    default(root, "entryAlternatives", json!([]));
+}
+
+fn to_1_10(root: &mut Map<String, Value>) {
+    default(root, "entryAnnotations", json!([]));
+}
+
+/// Each version and the step that brings the one before it up to it
+const STEPS: &[(&str, Step)] = &[
+    ("1.1.0", to_1_1),
+    ("1.2.0", to_1_2),
+    ("1.3.0", to_1_3),
+    ("1.4.0", to_1_4),
+    ("1.5.0", to_1_5),
+    ("1.6.0", to_1_6),
+    ("1.7.0", to_1_7),
+    ("1.8.0", to_1_8),
+    ("1.9.0", to_1_9),
+    ("1.10.0", to_1_10),
+];
+
+/// Bring an export of any version up to `CURRENT_VERSION`, returning the
+/// versions it went through. Steps only fill in what's missing, so all of
+/// them run on every file: optional lists can be left out even by current
+/// exporters. Files from a newer app keep their version.
+pub fn upgrade(root: &mut Map<String, Value>) -> Vec<&'static str> {
+    let version = root
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or("1.0.0")
+        .to_string();
+    let mut applied = Vec::new();
+    for (target, step) in STEPS {
+        if compare_versions(&version, target).is_lt() {
+            applied.push(*target);
+        }
+        step(root);
+    }
+    if compare_versions(&version, CURRENT_VERSION).is_lt() {
+        root.insert("version".to_string(), json!(CURRENT_VERSION));
+    }
+    applied
+}
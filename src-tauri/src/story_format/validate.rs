@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::schema::{StoryExport, CURRENT_VERSION};
+use super::types::{ExportProblem, ExportValidation, Severity};
+use super::upgrade::upgrade;
+use crate::content_pack::archive::compare_versions;
+
+struct Report {
+    problems: Vec<ExportProblem>,
+}
+
+impl Report {
+    fn add(&mut self, severity: Severity, path: impl Into<String>, message: impl Into<String>) {
+        self.problems.push(ExportProblem {
+            severity,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Read a list element by element, so one bad row doesn't hide the rest
+    fn list<T: DeserializeOwned>(&mut self, root: &Map<String, Value>, key: &str) -> Vec<T> {
+        let Some(value) = root.get(key) else {
+            return Vec::new();
+        };
+        let Some(items) = value.as_array() else {
+            self.add(Severity::Error, key, "should be a list");
+            return Vec::new();
+        };
+        items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| match serde_json::from_value(item.clone()) {
+                Ok(row) => Some(row),
+                Err(e) => {
+                    self.add(Severity::Error, format!("{}[{}]", key, i), e.to_string());
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn empty(current: &str, problems: Vec<ExportProblem>) -> ExportValidation {
+    ExportValidation {
+        importable: false,
+        version: None,
+        current_version: current.to_string(),
+        upgraded_through: Vec::new(),
+        title: None,
+        entries: 0,
+        branches: 0,
+        chapters: 0,
+        problems,
+    }
+}
+
+/// Check an export the way the importer will read it: upgraded to the
+/// current version, every row against the schema, then references between
+/// rows. Broken rows are errors; dangling references, which the importer
+/// detaches, are warnings.
+pub fn validate(content: &str) -> ExportValidation {
+    let mut report = Report {
+        problems: Vec::new(),
+    };
+    let content = content.trim_start_matches('\u{feff}');
+    let mut root = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(root)) => root,
+        Ok(_) => {
+            report.add(
+                Severity::Error,
+                "",
+                "Not a story export: expected a JSON object",
+            );
+            return empty(CURRENT_VERSION, report.problems);
+        }
+        Err(e) => {
+            report.add(Severity::Error, "", format!("Not valid JSON: {}", e));
+            return empty(CURRENT_VERSION, report.problems);
+        }
+    };
+    for key in ["version", "story", "entries"] {
+        if !root.contains_key(key) {
+            report.add(
+                Severity::Error,
+                key,
+                "missing; this isn't an Aventura story file",
+            );
+        }
+    }
+    if !report.problems.is_empty() {
+        return empty(CURRENT_VERSION, report.problems);
+    }
+
+    let version = root
+        .get("version")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    match &version {
+        None => report.add(Severity::Error, "version", "should be a version string"),
+        Some(v) if compare_versions(v, CURRENT_VERSION).is_gt() => report.add(
+            Severity::Warning,
+            "version",
+            format!(
+                "made by a newer version of Aventura ({}); anything newer than {} is skipped",
+                v, CURRENT_VERSION
+            ),
+        ),
+        _ => {}
+    }
+    let upgraded_through = upgrade(&mut root).into_iter().map(str::to_string).collect();
+
+    let story = match root.get("story").cloned().map(serde_json::from_value) {
+        Some(Ok(story)) => Some(story),
+        Some(Err(e)) => {
+            report.add(Severity::Error, "story", e.to_string());
+            None
+        }
+        None => None,
+    };
+    if !root.get("exportedAt").is_some_and(Value::is_i64) {
+        report.add(
+            Severity::Warning,
+            "exportedAt",
+            "missing or not a timestamp",
+        );
+        root.insert("exportedAt".to_string(), Value::from(0));
+    }
+    let export = story.map(|story| StoryExport {
+        version: version.clone().unwrap_or_default(),
+        exported_at: root.get("exportedAt").and_then(Value::as_i64).unwrap_or(0),
+        story,
+        entries: report.list(&root, "entries"),
+        characters: report.list(&root, "characters"),
+        locations: report.list(&root, "locations"),
+        items: report.list(&root, "items"),
+        story_beats: report.list(&root, "storyBeats"),
+        lorebook_entries: report.list(&root, "lorebookEntries"),
+        style_review_state: root.get("styleReviewState").cloned(),
+        embedded_images: report.list(&root, "embeddedImages"),
+        checkpoints: report.list(&root, "checkpoints"),
+        branches: report.list(&root, "branches"),
+        chapters: report.list(&root, "chapters"),
+        current_bg_image: root
+            .get("currentBgImage")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        entry_alternatives: report.list(&root, "entryAlternatives"),
+        entry_annotations: report.list(&root, "entryAnnotations"),
+        rest: Map::new(),
+    });
+    let Some(export) = export else {
+        return empty(CURRENT_VERSION, report.problems);
+    };
+    check_references(&export, &mut report);
+
+    ExportValidation {
+        importable: !report
+            .problems
+            .iter()
+            .any(|p| p.severity == Severity::Error),
+        version,
+        current_version: CURRENT_VERSION.to_string(),
+        upgraded_through,
+        title: Some(export.story.title.clone()),
+        entries: export.entries.len(),
+        branches: export.branches.len(),
+        chapters: export.chapters.len(),
+        problems: report.problems,
+    }
+}
+
+fn check_references(export: &StoryExport, report: &mut Report) {
+    if export.entries.is_empty() {
+        report.add(
+            Severity::Error,
+            "entries",
+            "the file contains no story entries",
+        );
+    }
+    let mut entries = HashSet::new();
+    for (i, entry) in export.entries.iter().enumerate() {
+        if !entries.insert(entry.id.as_str()) {
+            report.add(
+                Severity::Error,
+                format!("entries[{}].id", i),
+                format!("entry {} appears more than once", entry.id),
+            );
+        }
+    }
+    let branches: HashSet<&str> = export.branches.iter().map(|b| b.id.as_str()).collect();
+    let checkpoints: HashSet<&str> = export.checkpoints.iter().map(|c| c.id.as_str()).collect();
+    let mut warn = |path: String, message: String| report.add(Severity::Warning, path, message);
+
+    if let Some(current) = &export.story.current_branch_id {
+        if !branches.contains(current.as_str()) {
+            warn(
+                "story.currentBranchId".to_string(),
+                "unknown branch; the story opens on the main branch".to_string(),
+            );
+        }
+    }
+    for (i, entry) in export.entries.iter().enumerate() {
+        if let Some(branch) = &entry.branch_id {
+            if !branches.contains(branch.as_str()) {
+                warn(
+                    format!("entries[{}].branchId", i),
+                    format!(
+                        "unknown branch {}; the entry lands on the main branch",
+                        branch
+                    ),
+                );
+            }
+        }
+    }
+    for (i, branch) in export.branches.iter().enumerate() {
+        if let Some(parent) = &branch.parent_branch_id {
+            if !branches.contains(parent.as_str()) {
+                warn(
+                    format!("branches[{}].parentBranchId", i),
+                    format!("unknown parent branch {}; it becomes a root branch", parent),
+                );
+            }
+        }
+        if !entries.contains(branch.fork_entry_id.as_str()) {
+            warn(
+                format!("branches[{}].forkEntryId", i),
+                format!("unknown entry {}", branch.fork_entry_id),
+            );
+        }
+        if let Some(checkpoint) = &branch.checkpoint_id {
+            if !checkpoints.contains(checkpoint.as_str()) {
+                warn(
+                    format!("branches[{}].checkpointId", i),
+                    format!("unknown checkpoint {}; it's dropped", checkpoint),
+                );
+            }
+        }
+    }
+    for (i, chapter) in export.chapters.iter().enumerate() {
+        for (field, id) in [
+            ("startEntryId", &chapter.start_entry_id),
+            ("endEntryId", &chapter.end_entry_id),
+        ] {
+            if !entries.contains(id.as_str()) {
+                warn(
+                    format!("chapters[{}].{}", i, field),
+                    format!("unknown entry {}", id),
+                );
+            }
+        }
+    }
+    for (i, checkpoint) in export.checkpoints.iter().enumerate() {
+        if !entries.contains(checkpoint.last_entry_id.as_str()) {
+            warn(
+                format!("checkpoints[{}].lastEntryId", i),
+                format!("unknown entry {}", checkpoint.last_entry_id),
+            );
+        }
+    }
+    for (key, children) in [
+        ("embeddedImages", &export.embedded_images),
+        ("entryAlternatives", &export.entry_alternatives),
+        ("entryAnnotations", &export.entry_annotations),
+    ] {
+        for (i, child) in children.iter().enumerate() {
+            if !entries.contains(child.entry_id.as_str()) {
+                warn(
+                    format!("{}[{}].entryId", key, i),
+                    format!("unknown entry {}; it's skipped", child.entry_id),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Sleep;
+
+use super::SyncState;
+use crate::db::DbState;
+use crate::llm::config::get_setting;
+
+/// Smallest grant worth waking up for, so slow limits don't turn into
+/// byte-sized reads and writes
+const MIN_GRANT: usize = 1024;
+
+/// Chunk size for throttled request bodies
+const UPLOAD_CHUNK: usize = 16 * 1024;
+
+/// Stored by the frontend in `sync_bandwidth_settings`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncBandwidthSettings {
+    /// Cap for each direction; `None` is unlimited
+    pub bytes_per_second: Option<u64>,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<SyncBandwidthSettings, String> {
+    Ok(get_setting(pool, "sync_bandwidth_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Token bucket holding up to one second of traffic. A rate of 0 lets
+/// everything through.
+pub struct TokenBucket {
+    rate: AtomicU64,
+    bucket: Mutex<Bucket>,
+}
+
+impl Default for TokenBucket {
+    fn default() -> Self {
+        Self {
+            rate: AtomicU64::new(0),
+            bucket: Mutex::new(Bucket {
+                tokens: 0.0,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl TokenBucket {
+    fn set_rate(&self, bytes_per_second: u64) {
+        let mut bucket = self.bucket.lock().unwrap();
+        self.rate.store(bytes_per_second, Ordering::SeqCst);
+        bucket.tokens = bytes_per_second as f64;
+        bucket.refilled = Instant::now();
+    }
+
+    /// How many of `want` bytes may pass now, or how long until some may.
+    /// Nothing is taken until `consume`, so a short read or write only
+    /// pays for what it moved.
+    fn grant(&self, want: usize) -> Result<usize, Duration> {
+        let rate = self.rate.load(Ordering::SeqCst);
+        if rate == 0 {
+            return Ok(want);
+        }
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled).as_secs_f64() * rate as f64;
+        bucket.tokens = (bucket.tokens + refill).min(rate as f64);
+        bucket.refilled = now;
+
+        let needed = want.min(MIN_GRANT).min(rate as usize).max(1) as f64;
+        if bucket.tokens >= needed {
+            Ok((bucket.tokens as usize).min(want))
+        } else {
+            Err(Duration::from_secs_f64(
+                (needed - bucket.tokens) / rate as f64,
+            ))
+        }
+    }
+
+    /// Concurrent transfers may overdraw; the debt delays the next grant
+    fn consume(&self, bytes: usize) {
+        if self.rate.load(Ordering::SeqCst) > 0 {
+            self.bucket.lock().unwrap().tokens -= bytes as f64;
+        }
+    }
+
+    /// Wait for and take up to `want` bytes
+    async fn acquire(&self, want: usize) -> usize {
+        loop {
+            match self.grant(want) {
+                Ok(bytes) => {
+                    self.consume(bytes);
+                    return bytes;
+                }
+                Err(delay) => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+
+    /// `grant` for poll-based IO, parking the task in `wait` until tokens
+    /// are available
+    fn poll_grant(
+        &self,
+        wait: &mut Option<Pin<Box<Sleep>>>,
+        cx: &mut Context<'_>,
+        want: usize,
+    ) -> Poll<usize> {
+        loop {
+            if let Some(sleep) = wait {
+                ready!(sleep.as_mut().poll(cx));
+                *wait = None;
+            }
+            match self.grant(want) {
+                Ok(bytes) => return Poll::Ready(bytes),
+                Err(delay) => *wait = Some(Box::pin(tokio::time::sleep(delay))),
+            }
+        }
+    }
+}
+
+/// Rate limits shared by every sync transfer, seen from this device:
+/// `upload` covers bytes sent, `download` bytes received
+#[derive(Default)]
+pub struct SyncBandwidth {
+    upload: TokenBucket,
+    download: TokenBucket,
+}
+
+impl SyncBandwidth {
+    /// Takes effect immediately, including for transfers in progress
+    pub fn set_limit(&self, bytes_per_second: Option<u64>) {
+        let rate = bytes_per_second.unwrap_or(0);
+        self.upload.set_rate(rate);
+        self.download.set_rate(rate);
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        Some(self.upload.rate.load(Ordering::SeqCst)).filter(|&rate| rate > 0)
+    }
+}
+
+/// A stream whose reads and writes draw from the sync bandwidth buckets
+pub struct ThrottledStream<S> {
+    inner: S,
+    limits: Arc<SyncBandwidth>,
+    read_wait: Option<Pin<Box<Sleep>>>,
+    write_wait: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> ThrottledStream<S> {
+    pub fn new(inner: S, limits: Arc<SyncBandwidth>) -> Self {
+        Self {
+            inner,
+            limits,
+            read_wait: None,
+            write_wait: None,
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ThrottledStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        }
+        let allowed =
+            ready!(this
+                .limits
+                .download
+                .poll_grant(&mut this.read_wait, cx, buf.remaining()));
+
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(allowed));
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let read = limited.filled().len();
+        this.limits.download.consume(read);
+        buf.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ThrottledStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if buf.is_empty() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        let allowed = ready!(this
+            .limits
+            .upload
+            .poll_grant(&mut this.write_wait, cx, buf.len()));
+
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..allowed]))?;
+        this.limits.upload.consume(written);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Listener for the sync server that throttles every accepted connection
+pub struct ThrottledListener {
+    inner: TcpListener,
+    limits: Arc<SyncBandwidth>,
+}
+
+impl ThrottledListener {
+    pub fn new(inner: TcpListener, limits: Arc<SyncBandwidth>) -> Self {
+        Self { inner, limits }
+    }
+}
+
+impl axum::serve::Listener for ThrottledListener {
+    type Io = ThrottledStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, addr) = axum::serve::Listener::accept(&mut self.inner).await;
+        (ThrottledStream::new(stream, self.limits.clone()), addr)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// Request body that feeds `data` out no faster than the upload limit
+pub fn throttled_body(data: Vec<u8>, limits: Arc<SyncBandwidth>) -> reqwest::Body {
+    if limits.limit().is_none() {
+        return data.into();
+    }
+    let chunks = stream::unfold((data, 0, limits), |(data, sent, limits)| async move {
+        if sent >= data.len() {
+            return None;
+        }
+        let len = limits
+            .upload
+            .acquire((data.len() - sent).min(UPLOAD_CHUNK))
+            .await;
+        let chunk = data[sent..sent + len].to_vec();
+        Some((Ok::<_, io::Error>(chunk), (data, sent + len, limits)))
+    });
+    reqwest::Body::wrap_stream(chunks)
+}
+
+/// Read a response body no faster than the download limit
+pub async fn read_throttled(
+    mut response: reqwest::Response,
+    limits: &SyncBandwidth,
+) -> Result<Vec<u8>, reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let mut owed = chunk.len();
+        while owed > 0 {
+            owed -= limits.download.acquire(owed).await;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Apply the saved limit once the settings table exists
+pub fn restore(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        // The settings table only exists once the frontend has run migrations
+        let settings = loop {
+            match load_settings(&pool).await {
+                Ok(settings) => break settings,
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        app.state::<SyncState>()
+            .bandwidth()
+            .set_limit(settings.bytes_per_second);
+    });
+}
@@ -0,0 +1,272 @@
+//! Bluetooth LE fallback for devices with no shared WiFi. It carries
+//! pairing data and small, text-only story syncs. Anything bigger should go
+//! over WiFi. Only Android has a Bluetooth stack wired up (`SyncBlePlugin`).
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::plugin::{Builder, PluginApi, TauriPlugin};
+use tauri::{AppHandle, Wry};
+
+use super::core::{ServerState, SyncTransport};
+use super::types::{SyncRequest, SyncResponse};
+
+/// Largest request or response sent over Bluetooth. At typical LE speeds
+/// this is well under a minute.
+pub const MAX_BLE_PAYLOAD: usize = 256 * 1024;
+
+/// A device advertising the Aventura sync service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BleDevice {
+    pub address: String,
+    pub name: Option<String>,
+    pub rssi: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BleStatus {
+    /// Whether this platform can sync over Bluetooth
+    pub supported: bool,
+    pub advertising: bool,
+}
+
+fn check_size(len: usize) -> Result<(), String> {
+    if len > MAX_BLE_PAYLOAD {
+        return Err(format!(
+            "Too large for Bluetooth ({} KB, limit {} KB). Connect both devices to WiFi to sync this story.",
+            len / 1024,
+            MAX_BLE_PAYLOAD / 1024
+        ));
+    }
+    Ok(())
+}
+
+/// Strip images and checkpoints from an Aventura export so a story fits
+/// through Bluetooth. They can be synced later over WiFi.
+pub fn text_only(story_json: &str) -> Result<String, String> {
+    let mut export: Value =
+        serde_json::from_str(story_json).map_err(|e| format!("Invalid story data: {}", e))?;
+    if let Some(fields) = export.as_object_mut() {
+        fields.remove("embeddedImages");
+        fields.remove("checkpoints");
+        fields.remove("currentBgImage");
+        for list in ["characters", "locations", "items"] {
+            let entries = fields.get_mut(list).and_then(Value::as_array_mut);
+            for entry in entries.into_iter().flatten() {
+                if let Some(portrait) = entry.get_mut("portrait") {
+                    *portrait = Value::Null;
+                }
+            }
+        }
+    }
+    serde_json::to_string(&export).map_err(|e| format!("Failed to serialize story: {}", e))
+}
+
+/// Shrink a server response so it fits the Bluetooth limit
+fn fit_response(response: SyncResponse) -> SyncResponse {
+    let response = match response {
+        SyncResponse::StoryData { data } => match text_only(&data) {
+            Ok(data) => SyncResponse::StoryData { data },
+            Err(message) => SyncResponse::Error { message },
+        },
+        other => other,
+    };
+    let size = serde_json::to_vec(&response).map(|r| r.len()).unwrap_or(0);
+    match check_size(size) {
+        Ok(()) => response,
+        Err(message) => SyncResponse::Error { message },
+    }
+}
+
+#[cfg(target_os = "android")]
+struct SyncBle(tauri::plugin::PluginHandle<Wry>);
+
+#[cfg(target_os = "android")]
+fn register(app: &AppHandle, api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    use tauri::Manager;
+
+    let handle = api.register_android_plugin("com.karelian.aventura", "SyncBlePlugin")?;
+    app.manage(SyncBle(handle));
+    Ok(())
+}
+
+#[cfg(not(target_os = "android"))]
+fn register(_app: &AppHandle, _api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Registers the Android Bluetooth LE sync plugin
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("sync-ble").setup(register).build()
+}
+
+/// Plugin calls can wait on the radio for a while, so they run off the
+/// async runtime
+#[cfg(target_os = "android")]
+async fn run(app: &AppHandle, command: &'static str, payload: Value) -> Result<Value, String> {
+    use tauri::Manager;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<SyncBle>()
+            .0
+            .run_mobile_plugin(command, payload)
+            .map_err(|e| format!("Bluetooth error: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Bluetooth error: {}", e))?
+}
+
+#[cfg(not(target_os = "android"))]
+async fn run(_app: &AppHandle, _command: &'static str, _payload: Value) -> Result<Value, String> {
+    Err("Bluetooth sync is only available on Android".to_string())
+}
+
+fn decode_payload(value: &Value) -> Result<Vec<u8>, String> {
+    let encoded = value
+        .get("payload")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid Bluetooth payload: {}", e))
+}
+
+pub async fn status(app: &AppHandle) -> Result<BleStatus, String> {
+    if cfg!(not(target_os = "android")) {
+        return Ok(BleStatus {
+            supported: false,
+            advertising: false,
+        });
+    }
+    let response = run(app, "status", Value::Null).await?;
+    Ok(BleStatus {
+        supported: true,
+        advertising: response
+            .get("advertising")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    })
+}
+
+/// Advertise `server` over Bluetooth, offering `pairing` to anyone who
+/// reads it, and answer requests until `stop_advertising`
+pub async fn start_advertising(
+    app: &AppHandle,
+    server: ServerState,
+    pairing: String,
+) -> Result<(), String> {
+    run(
+        app,
+        "startAdvertising",
+        serde_json::json!({ "pairing": pairing }),
+    )
+    .await?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let incoming = match run(&app, "nextRequest", Value::Null).await {
+                Ok(incoming) => incoming,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    break;
+                }
+            };
+            if incoming
+                .get("stopped")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+            {
+                break;
+            }
+            let id = incoming
+                .get("id")
+                .and_then(Value::as_i64)
+                .unwrap_or_default();
+
+            let response = match decode_payload(&incoming).and_then(|bytes| {
+                serde_json::from_slice::<SyncRequest>(&bytes).map_err(|e| e.to_string())
+            }) {
+                Ok(request) => Some(fit_response(
+                    super::core::handle_request(&server, request).await,
+                )),
+                // Like the HTTP server's 422, an empty reply means "not understood"
+                Err(_) => None,
+            };
+            let payload = match response.map(|r| serde_json::to_vec(&r)) {
+                Some(Ok(bytes)) => STANDARD.encode(bytes),
+                _ => String::new(),
+            };
+            let reply = serde_json::json!({ "id": id, "payload": payload });
+            if let Err(e) = run(&app, "respond", reply).await {
+                eprintln!("{}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+pub async fn stop_advertising(app: &AppHandle) -> Result<(), String> {
+    if cfg!(not(target_os = "android")) {
+        return Ok(());
+    }
+    run(app, "stopAdvertising", Value::Null).await.map(|_| ())
+}
+
+/// Look for advertising devices for `seconds`
+pub async fn scan(app: &AppHandle, seconds: u32) -> Result<Vec<BleDevice>, String> {
+    let response = run(app, "scan", serde_json::json!({ "seconds": seconds })).await?;
+    let devices = response
+        .get("devices")
+        .cloned()
+        .unwrap_or(Value::Array(Vec::new()));
+    serde_json::from_value(devices).map_err(|e| format!("Invalid scan result: {}", e))
+}
+
+/// Read the pairing data a device advertises
+pub async fn read_pairing(app: &AppHandle, address: &str) -> Result<String, String> {
+    let response = run(
+        app,
+        "readPairing",
+        serde_json::json!({ "address": address }),
+    )
+    .await?;
+    response
+        .get("pairing")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "The device sent no pairing data".to_string())
+}
+
+/// Sync requests to one device over Bluetooth
+pub struct BleTransport {
+    app: AppHandle,
+    address: String,
+}
+
+impl BleTransport {
+    pub fn new(app: AppHandle, address: String) -> Self {
+        Self { app, address }
+    }
+}
+
+impl SyncTransport for BleTransport {
+    async fn send(&self, request: &SyncRequest) -> Result<Option<SyncResponse>, String> {
+        let payload = serde_json::to_vec(request)
+            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+        check_size(payload.len())?;
+
+        let args =
+            serde_json::json!({ "address": self.address, "payload": STANDARD.encode(payload) });
+        let reply = decode_payload(&run(&self.app, "request", args).await?)?;
+        if reply.is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_slice(&reply)
+            .map(Some)
+            .map_err(|e| format!("Invalid response: {}", e))
+    }
+}
@@ -1,14 +1,28 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
 use image::Luma;
 use qrcode::QrCode;
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-use super::server::{bind_listener, build_router, spawn_server, ServerState, StoriesData};
-use super::types::{QrCodeData, SyncAction, SyncRequest, SyncResponse, SyncServerInfo, SyncStoryPreview};
+use super::bandwidth::SyncBandwidth;
+use super::ble::{self, BleDevice, BleStatus, BleTransport};
+use super::core::{ServerState, StoriesData, SyncClient, SyncTransport};
+use super::direct::{self, DirectLink};
+use super::http::HttpTransport;
+use super::local_network::{self, LocalNetworkAccess};
+use super::protocol::{SyncPeerInfo, PROTOCOL_VERSION};
+use super::server::{bind_listener, build_router, spawn_server};
+use super::service::{self, SyncServiceStatus};
+use super::types::{
+    BlePairingData, QrCodeData, QrWifiData, SyncDevice, SyncSendProgress, SyncSendResult, SyncSendStage,
+    SyncServerInfo, SyncStoryListing, SyncStoryPreview,
+};
+use crate::db::DbState;
+use crate::redaction::redactor::redact_story_json;
 
 /// State managed by Tauri for sync operations
 pub struct SyncState {
@@ -16,6 +30,14 @@ pub struct SyncState {
     server_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
     /// Current server state (for accessing received stories)
     server_state: Arc<Mutex<Option<ServerState>>>,
+    /// `ip:port` the running server listens on
+    server_address: Arc<Mutex<Option<String>>>,
+    /// Transfer rate limits for both the server and the client
+    bandwidth: Arc<SyncBandwidth>,
+    /// Handshake results for remote servers, by `ip:port`
+    peers: Arc<Mutex<HashMap<String, SyncPeerInfo>>>,
+    /// Last Local Network permission probe (Apple platforms)
+    local_network: Arc<Mutex<LocalNetworkAccess>>,
 }
 
 impl Default for SyncState {
@@ -23,12 +45,31 @@ impl Default for SyncState {
         Self {
             server_handle: Arc::new(Mutex::new(None)),
             server_state: Arc::new(Mutex::new(None)),
+            server_address: Arc::new(Mutex::new(None)),
+            bandwidth: Arc::new(SyncBandwidth::default()),
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            local_network: Arc::new(Mutex::new(if cfg!(any(target_os = "ios", target_os = "macos")) {
+                LocalNetworkAccess::Unknown
+            } else {
+                LocalNetworkAccess::NotRequired
+            })),
         }
     }
 }
 
+impl SyncState {
+    /// Whether the sync server is running
+    pub async fn is_running(&self) -> bool {
+        self.server_handle.lock().await.is_some()
+    }
+
+    pub fn bandwidth(&self) -> &SyncBandwidth {
+        &self.bandwidth
+    }
+}
+
 /// Generate a QR code as base64-encoded PNG
-fn generate_qr_code(data: &str) -> Result<String, String> {
+pub(crate) fn generate_qr_code(data: &str) -> Result<String, String> {
     let code = QrCode::new(data.as_bytes()).map_err(|e| format!("Failed to create QR code: {}", e))?;
 
     let image = code.render::<Luma<u8>>().min_dimensions(256, 256).build();
@@ -42,7 +83,7 @@ fn generate_qr_code(data: &str) -> Result<String, String> {
 }
 
 /// Get the local IP address
-fn get_local_ip() -> Result<String, String> {
+pub(crate) fn get_local_ip() -> Result<String, String> {
     local_ip_address::local_ip()
         .map(|ip| ip.to_string())
         .map_err(|e| format!("Failed to get local IP: {}", e))
@@ -82,6 +123,25 @@ fn parse_story_preview(json: &str) -> Result<SyncStoryPreview, String> {
     })
 }
 
+/// Offer stories (Aventura export JSON) from a server, skipping ones that
+/// can't be parsed
+async fn add_stories(server_state: &ServerState, stories: Vec<String>) {
+    let mut stories_data = server_state.stories.lock().await;
+    for story_json in stories {
+        match parse_story_preview(&story_json) {
+            Ok(preview) => {
+                stories_data.push(StoriesData {
+                    preview,
+                    full_data: story_json,
+                });
+            }
+            Err(e) => {
+                eprintln!("Failed to parse story: {}", e);
+            }
+        }
+    }
+}
+
 /// Start the sync server with available stories
 #[tauri::command]
 pub async fn start_sync_server(
@@ -90,30 +150,49 @@ pub async fn start_sync_server(
     stories_json: Option<Vec<String>>,
 ) -> Result<SyncServerInfo, String> {
     // Stop any existing server first
-    stop_sync_server(state.clone()).await?;
+    stop_sync_server(app.clone(), state.clone()).await?;
+
+    let ip = get_local_ip()?;
+    serve(&app, &state, stories_json, ip, None).await
+}
+
+/// Start the sync server on a network this device hosts itself, for when
+/// there's no router both devices can use. The QR code carries the network
+/// credentials along with the usual connection data.
+#[tauri::command]
+pub async fn start_direct_sync_server(
+    app: AppHandle,
+    state: State<'_, SyncState>,
+    stories_json: Option<Vec<String>>,
+) -> Result<SyncServerInfo, String> {
+    stop_sync_server(app.clone(), state.clone()).await?;
 
+    let link = direct::start(&app).await?;
+    let ip = link.owner_address.clone();
+    let info = serve(&app, &state, stories_json, ip, Some(link)).await;
+    if info.is_err() {
+        direct::stop(&app).await?;
+    }
+    info
+}
+
+/// Run a server reachable at `ip`, hosted on `link` in direct mode
+async fn serve(
+    app: &AppHandle,
+    state: &SyncState,
+    stories_json: Option<Vec<String>>,
+    ip: String,
+    link: Option<DirectLink>,
+) -> Result<SyncServerInfo, String> {
     // Generate a new token
     let token = Uuid::new_v4().to_string();
 
     // Create server state
-    let server_state = ServerState::new(token.clone());
+    let server_state = ServerState::new(token.clone(), app.package_info().version.to_string());
 
     // Add stories if provided
     if let Some(stories) = stories_json {
-        let mut stories_data = server_state.stories.lock().await;
-        for story_json in stories {
-            match parse_story_preview(&story_json) {
-                Ok(preview) => {
-                    stories_data.push(StoriesData {
-                        preview,
-                        full_data: story_json,
-                    });
-                }
-                Err(e) => {
-                    eprintln!("Failed to parse story: {}", e);
-                }
-            }
-        }
+        add_stories(&server_state, stories).await;
     }
 
     // Bind listener before starting the server task
@@ -121,9 +200,6 @@ pub async fn start_sync_server(
     let addr = listener
         .local_addr()
         .map_err(|e| format!("Failed to get local address: {}", e))?;
-
-    // Get local IP for QR data
-    let ip = get_local_ip()?;
     let port = addr.port();
 
     // Generate QR code with connection data
@@ -132,35 +208,72 @@ pub async fn start_sync_server(
         port,
         token: token.clone(),
         version: app.package_info().version.to_string(),
+        protocol_version: PROTOCOL_VERSION,
+        wifi: link.as_ref().map(|link| QrWifiData {
+            ssid: link.ssid.clone(),
+            passphrase: link.passphrase.clone(),
+        }),
     };
     let qr_json = serde_json::to_string(&qr_data).map_err(|e| format!("Failed to serialize QR data: {}", e))?;
     let qr_code_base64 = generate_qr_code(&qr_json)?;
 
     // Start the server after QR data is ready
-    let app = build_router(server_state.clone());
-    let handle = spawn_server(listener, app);
+    let router = build_router(server_state.clone());
+    let handle = spawn_server(listener, router, state.bandwidth.clone());
 
     // Store handles
     *state.server_handle.lock().await = Some(handle);
     *state.server_state.lock().await = Some(server_state);
+    *state.server_address.lock().await = Some(format!("{}:{}", ip, port));
 
     Ok(SyncServerInfo {
         ip,
         port,
         token,
         qr_code_base64,
+        direct: link,
     })
 }
 
-/// Stop the sync server
+/// Stop the sync server, and its background service on Android
 #[tauri::command]
-pub async fn stop_sync_server(state: State<'_, SyncState>) -> Result<(), String> {
+pub async fn stop_sync_server(app: AppHandle, state: State<'_, SyncState>) -> Result<(), String> {
     let mut handle = state.server_handle.lock().await;
     if let Some(h) = handle.take() {
         h.abort();
     }
     *state.server_state.lock().await = None;
-    Ok(())
+    *state.server_address.lock().await = None;
+    ble::stop_advertising(&app).await?;
+    direct::stop(&app).await?;
+    service::stop(&app)
+}
+
+/// The network this device hosts for direct sync, if any
+#[tauri::command]
+pub async fn get_direct_link(app: AppHandle) -> Result<Option<DirectLink>, String> {
+    direct::status(&app).await
+}
+
+/// Keep the running sync server reachable while the app is in the
+/// background (Android foreground service with a notification)
+#[tauri::command]
+pub async fn start_sync_service(app: AppHandle, state: State<'_, SyncState>) -> Result<SyncServiceStatus, String> {
+    let address = state.server_address.lock().await.clone().ok_or("The sync server is not running")?;
+    service::start(&app, &address)?;
+    service::status(&app)
+}
+
+/// Leave background mode; the server keeps running while the app is open
+#[tauri::command]
+pub async fn stop_sync_service(app: AppHandle) -> Result<SyncServiceStatus, String> {
+    service::stop(&app)?;
+    service::status(&app)
+}
+
+#[tauri::command]
+pub async fn get_sync_service_status(app: AppHandle) -> Result<SyncServiceStatus, String> {
+    service::status(&app)
 }
 
 /// Get stories that were pushed to this server
@@ -186,107 +299,354 @@ pub async fn clear_received_stories(state: State<'_, SyncState>) -> Result<(), S
     Ok(())
 }
 
-/// Connect to a remote sync server and list available stories
+/// Cap sync transfers at `bytes_per_second` in each direction, or lift the
+/// cap with `None`. Applies to transfers already running. The frontend
+/// keeps the value in `sync_bandwidth_settings`, which is read again at launch.
 #[tauri::command]
-pub async fn sync_connect(ip: String, port: u16, token: String) -> Result<Vec<SyncStoryPreview>, String> {
-    let url = format!("http://{}:{}/sync", ip, port);
+pub async fn set_sync_bandwidth_limit(state: State<'_, SyncState>, bytes_per_second: Option<u64>) -> Result<(), String> {
+    if bytes_per_second == Some(0) {
+        return Err("Bandwidth limit must be greater than zero".to_string());
+    }
+    state.bandwidth.set_limit(bytes_per_second);
+    Ok(())
+}
 
-    let request = SyncRequest {
-        token,
-        action: SyncAction::ListStories,
-    };
+#[tauri::command]
+pub async fn get_sync_bandwidth_limit(state: State<'_, SyncState>) -> Result<Option<u64>, String> {
+    Ok(state.bandwidth.limit())
+}
+
+/// Whether the app may use the local network, as of the last check. Apple
+/// platforms report `unknown` until `request_local_network_access` runs.
+#[tauri::command]
+pub async fn get_local_network_access(state: State<'_, SyncState>) -> Result<LocalNetworkAccess, String> {
+    Ok(*state.local_network.lock().await)
+}
+
+/// Check Local Network access, which makes iOS and macOS ask the user the
+/// first time. Call it when the user starts syncing, not at launch, and
+/// again after they come back from Settings.
+#[tauri::command]
+pub async fn request_local_network_access(state: State<'_, SyncState>) -> Result<LocalNetworkAccess, String> {
+    let access = local_network::probe();
+    *state.local_network.lock().await = access;
+    Ok(access)
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-        .map_err(|e| format!("Connection failed: {}", e))?;
-
-    let sync_response: SyncResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Invalid response: {}", e))?;
-
-    match sync_response {
-        SyncResponse::StoriesList { stories } => Ok(stories),
-        SyncResponse::Error { message } => Err(message),
-        _ => Err("Unexpected response type".to_string()),
+/// Client for a remote server, reusing an earlier handshake with it
+async fn client(state: &SyncState, ip: &str, port: u16, token: String) -> SyncClient<HttpTransport> {
+    let peer = state.peers.lock().await.get(&format!("{}:{}", ip, port)).cloned();
+    SyncClient::new(HttpTransport::new(ip, port, state.bandwidth.clone()), token).with_peer(peer)
+}
+
+/// Keep a client's handshake for the next command against the same server
+async fn remember<T: SyncTransport>(state: &SyncState, key: String, client: &SyncClient<T>) {
+    if let Some(peer) = client.peer() {
+        state.peers.lock().await.insert(key, peer.clone());
     }
 }
 
+/// Check that a remote server speaks a compatible protocol and report its
+/// version and capabilities
+#[tauri::command]
+pub async fn sync_handshake(
+    state: State<'_, SyncState>,
+    ip: String,
+    port: u16,
+    token: String,
+) -> Result<SyncPeerInfo, String> {
+    let mut client = SyncClient::new(HttpTransport::new(&ip, port, state.bandwidth.clone()), token);
+    let peer = client.handshake().await.map_err(local_network::explain)?;
+    remember(&state, format!("{}:{}", ip, port), &client).await;
+    Ok(peer)
+}
+
+/// Connect to a remote sync server and list available stories
+#[tauri::command]
+pub async fn sync_connect(
+    state: State<'_, SyncState>,
+    ip: String,
+    port: u16,
+    token: String,
+) -> Result<Vec<SyncStoryPreview>, String> {
+    // Always handshake on connect, the other device may have been updated
+    let mut client = SyncClient::new(HttpTransport::new(&ip, port, state.bandwidth.clone()), token);
+    client.handshake().await.map_err(local_network::explain)?;
+    remember(&state, format!("{}:{}", ip, port), &client).await;
+    client.list_stories().await
+}
+
+/// List a remote server's stories unless they still match `since_hash`,
+/// for refreshing the list without downloading it again each time
+#[tauri::command]
+pub async fn sync_list_stories_if_changed(
+    state: State<'_, SyncState>,
+    ip: String,
+    port: u16,
+    token: String,
+    since_hash: Option<String>,
+) -> Result<SyncStoryListing, String> {
+    let mut client = client(&state, &ip, port, token).await;
+    let listing = client.list_stories_if_changed(since_hash).await;
+    remember(&state, format!("{}:{}", ip, port), &client).await;
+    listing
+}
+
 /// Pull a story from a remote server
 #[tauri::command]
 pub async fn sync_pull_story(
+    state: State<'_, SyncState>,
     ip: String,
     port: u16,
     token: String,
     story_id: String,
 ) -> Result<String, String> {
-    let url = format!("http://{}:{}/sync", ip, port);
-
-    let request = SyncRequest {
-        token,
-        action: SyncAction::PullStory { story_id },
-    };
+    let mut client = client(&state, &ip, port, token).await;
+    let story = client.pull_story(story_id).await;
+    remember(&state, format!("{}:{}", ip, port), &client).await;
+    story
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("Connection failed: {}", e))?;
-
-    let sync_response: SyncResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Invalid response: {}", e))?;
-
-    match sync_response {
-        SyncResponse::StoryData { data } => Ok(data),
-        SyncResponse::Error { message } => Err(message),
-        _ => Err("Unexpected response type".to_string()),
+/// Apply the story's redaction rules to a story about to be pushed when
+/// `redact` is set
+async fn outgoing(db: &DbState, story_json: String, redact: Option<bool>) -> Result<String, String> {
+    if redact.unwrap_or(false) {
+        redact_story_json(db.pool(), &story_json).await
+    } else {
+        Ok(story_json)
     }
 }
 
-/// Push a story to a remote server
+/// Push a story to a remote server, redacted first when `redact` is set
 #[tauri::command]
 pub async fn sync_push_story(
+    db: State<'_, DbState>,
+    state: State<'_, SyncState>,
     ip: String,
     port: u16,
     token: String,
     story_json: String,
+    redact: Option<bool>,
 ) -> Result<(), String> {
-    let url = format!("http://{}:{}/sync", ip, port);
+    let story_json = outgoing(&db, story_json, redact).await?;
+    let mut client = client(&state, &ip, port, token).await;
+    let pushed = client.push_story(story_json).await;
+    remember(&state, format!("{}:{}", ip, port), &client).await;
+    pushed
+}
 
-    let request = SyncRequest {
-        token,
-        action: SyncAction::PushStory {
-            story_data: story_json,
-        },
+/// Share stories over Bluetooth LE, for devices with no WiFi in common.
+/// Reuses the running sync server's stories and token when there is one,
+/// otherwise offers `stories_json` under a new token.
+#[tauri::command]
+pub async fn start_ble_advertising(
+    app: AppHandle,
+    state: State<'_, SyncState>,
+    stories_json: Option<Vec<String>>,
+) -> Result<BlePairingData, String> {
+    if !ble::status(&app).await?.supported {
+        return Err("Bluetooth sync is only available on Android".to_string());
+    }
+    let existing = state.server_state.lock().await.clone();
+    let server_state = match existing {
+        Some(server_state) => server_state,
+        None => {
+            let server_state = ServerState::new(Uuid::new_v4().to_string(), app.package_info().version.to_string());
+            add_stories(&server_state, stories_json.unwrap_or_default()).await;
+            *state.server_state.lock().await = Some(server_state.clone());
+            server_state
+        }
     };
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .json(&request)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("Connection failed: {}", e))?;
-
-    let sync_response: SyncResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Invalid response: {}", e))?;
-
-    match sync_response {
-        SyncResponse::Success { .. } => Ok(()),
-        SyncResponse::Error { message } => Err(message),
-        _ => Err("Unexpected response type".to_string()),
+    let pairing = BlePairingData {
+        token: server_state.token.clone(),
+        version: server_state.app_version.clone(),
+        protocol_version: PROTOCOL_VERSION,
+        wifi_address: state.server_address.lock().await.clone(),
+    };
+    let pairing_json = serde_json::to_string(&pairing).map_err(|e| format!("Failed to serialize pairing data: {}", e))?;
+    ble::start_advertising(&app, server_state, pairing_json).await?;
+    Ok(pairing)
+}
+
+#[tauri::command]
+pub async fn stop_ble_advertising(app: AppHandle) -> Result<(), String> {
+    ble::stop_advertising(&app).await
+}
+
+#[tauri::command]
+pub async fn get_ble_status(app: AppHandle) -> Result<BleStatus, String> {
+    ble::status(&app).await
+}
+
+/// Find nearby devices sharing stories over Bluetooth
+#[tauri::command]
+pub async fn ble_scan(app: AppHandle, seconds: Option<u32>) -> Result<Vec<BleDevice>, String> {
+    ble::scan(&app, seconds.unwrap_or(8)).await
+}
+
+/// Read a nearby device's pairing data: its token, versions and WiFi
+/// address if it has one
+#[tauri::command]
+pub async fn ble_pair(app: AppHandle, address: String) -> Result<BlePairingData, String> {
+    let pairing = ble::read_pairing(&app, &address).await?;
+    serde_json::from_str(&pairing).map_err(|e| format!("Invalid pairing data: {}", e))
+}
+
+async fn ble_client(app: &AppHandle, state: &SyncState, address: &str, token: String) -> SyncClient<BleTransport> {
+    let peer = state.peers.lock().await.get(&format!("ble:{}", address)).cloned();
+    SyncClient::new(BleTransport::new(app.clone(), address.to_string()), token).with_peer(peer)
+}
+
+/// List a device's stories over Bluetooth
+#[tauri::command]
+pub async fn ble_sync_connect(
+    app: AppHandle,
+    state: State<'_, SyncState>,
+    address: String,
+    token: String,
+) -> Result<Vec<SyncStoryPreview>, String> {
+    let mut client = SyncClient::new(BleTransport::new(app, address.clone()), token);
+    client.handshake().await?;
+    remember(&state, format!("ble:{}", address), &client).await;
+    client.list_stories().await
+}
+
+/// Pull a story over Bluetooth. The sender strips images and checkpoints.
+#[tauri::command]
+pub async fn ble_sync_pull_story(
+    app: AppHandle,
+    state: State<'_, SyncState>,
+    address: String,
+    token: String,
+    story_id: String,
+) -> Result<String, String> {
+    let mut client = ble_client(&app, &state, &address, token).await;
+    let story = client.pull_story(story_id).await;
+    remember(&state, format!("ble:{}", address), &client).await;
+    story
+}
+
+/// Push a story over Bluetooth without its images and checkpoints
+#[tauri::command]
+pub async fn ble_sync_push_story(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    state: State<'_, SyncState>,
+    address: String,
+    token: String,
+    story_json: String,
+    redact: Option<bool>,
+) -> Result<(), String> {
+    let story_json = ble::text_only(&outgoing(&db, story_json, redact).await?)?;
+    let mut client = ble_client(&app, &state, &address, token).await;
+    let pushed = client.push_story(story_json).await;
+    remember(&state, format!("ble:{}", address), &client).await;
+    pushed
+}
+
+/// Reports `send_story_to_device` progress for one story
+struct SendProgress<'a> {
+    app: &'a AppHandle,
+    story_id: &'a str,
+}
+
+impl SendProgress<'_> {
+    fn emit(&self, stage: SyncSendStage, transport: Option<&str>) {
+        let progress = SyncSendProgress {
+            story_id: self.story_id.to_string(),
+            stage,
+            transport: transport.map(str::to_string),
+        };
+        if let Err(e) = self.app.emit("sync-send-progress", &progress) {
+            eprintln!("Failed to emit sync progress: {}", e);
+        }
     }
 }
+
+/// Handshake, push and check the receipt over one transport
+async fn send_with<T: SyncTransport>(
+    progress: &SendProgress<'_>,
+    state: &SyncState,
+    key: String,
+    mut client: SyncClient<T>,
+    story_json: String,
+    transport: &str,
+) -> Result<bool, String> {
+    progress.emit(SyncSendStage::Connecting, Some(transport));
+    client.handshake().await?;
+    remember(state, key, &client).await;
+    progress.emit(SyncSendStage::Sending, Some(transport));
+    client.push_story_confirmed(story_json).await
+}
+
+/// Bluetooth half of `send_story_to_device`. Returns the transport used,
+/// whether the push was confirmed and whether it went text-only.
+async fn send_to_ble_device(
+    progress: &SendProgress<'_>,
+    state: &SyncState,
+    address: &str,
+    story_json: String,
+) -> Result<(&'static str, bool, bool), String> {
+    progress.emit(SyncSendStage::Pairing, Some("bluetooth"));
+    let pairing: BlePairingData = serde_json::from_str(&ble::read_pairing(progress.app, address).await?)
+        .map_err(|e| format!("Invalid pairing data: {}", e))?;
+
+    let wifi = pairing
+        .wifi_address
+        .as_deref()
+        .and_then(|address| address.rsplit_once(':'))
+        .and_then(|(ip, port)| Some((ip.to_string(), port.parse::<u16>().ok()?)));
+    if let Some((ip, port)) = wifi {
+        let client = client(state, &ip, port, pairing.token.clone()).await;
+        let key = format!("{}:{}", ip, port);
+        // Often the devices aren't on the same network; fall back quietly
+        if let Ok(confirmed) = send_with(progress, state, key, client, story_json.clone(), "wifi").await {
+            return Ok(("wifi", confirmed, false));
+        }
+    }
+
+    let client = ble_client(progress.app, state, address, pairing.token).await;
+    let story_json = ble::text_only(&story_json)?;
+    let key = format!("ble:{}", address);
+    let confirmed = send_with(progress, state, key, client, story_json, "bluetooth").await?;
+    Ok(("bluetooth", confirmed, true))
+}
+
+/// Send a story (Aventura export JSON) to another device in one step:
+/// pair if needed, handshake, push, and confirm the device stored it.
+/// Bluetooth devices that also run a WiFi server get the full story over
+/// WiFi when it's reachable, otherwise a text-only copy over Bluetooth.
+/// Emits `sync-send-progress` along the way. The story's redaction rules
+/// are applied first when `redact` is set.
+#[tauri::command]
+pub async fn send_story_to_device(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    state: State<'_, SyncState>,
+    story_id: String,
+    story_json: String,
+    device: SyncDevice,
+    redact: Option<bool>,
+) -> Result<SyncSendResult, String> {
+    let story_json = outgoing(&db, story_json, redact).await?;
+    let progress = SendProgress {
+        app: &app,
+        story_id: &story_id,
+    };
+    let (transport, confirmed, text_only) = match device {
+        SyncDevice::Wifi { ip, port, token } => {
+            let client = client(&state, &ip, port, token).await;
+            let key = format!("{}:{}", ip, port);
+            let confirmed = send_with(&progress, &state, key, client, story_json, "wifi").await?;
+            ("wifi", confirmed, false)
+        }
+        SyncDevice::Bluetooth { address } => send_to_ble_device(&progress, &state, &address, story_json).await?,
+    };
+    progress.emit(SyncSendStage::Done, Some(transport));
+    Ok(SyncSendResult {
+        transport: transport.to_string(),
+        confirmed,
+        text_only,
+    })
+}
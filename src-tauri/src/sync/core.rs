@@ -0,0 +1,504 @@
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::protocol::{
+    negotiate, SyncPeerInfo, CAPABILITIES, CAP_CONDITIONAL_LISTING, CAP_PUSH_RECEIPT,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use super::types::{SyncAction, SyncRequest, SyncResponse, SyncStoryListing, SyncStoryPreview};
+
+/// Shared state for the sync server
+#[derive(Clone)]
+pub struct ServerState {
+    /// Authentication token
+    pub token: String,
+    /// Reported to clients in the handshake
+    pub app_version: String,
+    /// Stories available on this server (JSON strings in Aventura format)
+    pub stories: Arc<Mutex<Vec<StoriesData>>>,
+    /// Stories received from clients (pushed stories)
+    pub received_stories: Arc<Mutex<Vec<String>>>,
+}
+
+/// Data about a story available on the server
+#[derive(Clone)]
+pub struct StoriesData {
+    pub preview: SyncStoryPreview,
+    pub full_data: String,
+}
+
+impl ServerState {
+    pub fn new(token: String, app_version: String) -> Self {
+        Self {
+            token,
+            app_version,
+            stories: Arc::new(Mutex::new(Vec::new())),
+            received_stories: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+/// Hash identifying a story listing, so clients can skip unchanged ones
+fn listing_hash(previews: &[SyncStoryPreview]) -> String {
+    let mut hasher = Sha256::new();
+    for preview in previews {
+        // A preview only holds strings and numbers, so this can't fail
+        hasher.update(serde_json::to_vec(preview).unwrap_or_default());
+        hasher.update(b"\n");
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn checksum(data: &str) -> String {
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Answer a sync request. Transports decode the request, call this and
+/// encode the response; the protocol itself lives only here.
+pub async fn handle_request(state: &ServerState, request: SyncRequest) -> SyncResponse {
+    // Validate token
+    if request.token != state.token {
+        return SyncResponse::Error {
+            message: "Invalid authentication token".to_string(),
+        };
+    }
+
+    if request.protocol_version < MIN_PROTOCOL_VERSION {
+        return SyncResponse::Error {
+            message: "This app is too old to sync with the other device. Update it to sync."
+                .to_string(),
+        };
+    }
+
+    match request.action {
+        SyncAction::Hello => SyncResponse::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            app_version: state.app_version.clone(),
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        },
+        SyncAction::ListStories => {
+            let stories = state.stories.lock().await;
+            let previews: Vec<SyncStoryPreview> =
+                stories.iter().map(|s| s.preview.clone()).collect();
+            let hash = listing_hash(&previews);
+            SyncResponse::StoriesList {
+                stories: previews,
+                hash,
+            }
+        }
+        SyncAction::ListStoriesIfChanged { since_hash } => {
+            let stories = state.stories.lock().await;
+            let previews: Vec<SyncStoryPreview> =
+                stories.iter().map(|s| s.preview.clone()).collect();
+            let hash = listing_hash(&previews);
+            if hash == since_hash {
+                SyncResponse::NotModified { hash }
+            } else {
+                SyncResponse::StoriesList {
+                    stories: previews,
+                    hash,
+                }
+            }
+        }
+        SyncAction::PullStory { story_id } => {
+            let stories = state.stories.lock().await;
+            if let Some(story) = stories.iter().find(|s| s.preview.id == story_id) {
+                SyncResponse::StoryData {
+                    data: story.full_data.clone(),
+                }
+            } else {
+                SyncResponse::Error {
+                    message: format!("Story not found: {}", story_id),
+                }
+            }
+        }
+        SyncAction::PushStory { story_data } => {
+            let mut received = state.received_stories.lock().await;
+            received.push(story_data);
+            SyncResponse::Success {
+                message: "Story received successfully".to_string(),
+            }
+        }
+        SyncAction::PushStoryWithReceipt { story_data } => {
+            let sha256 = checksum(&story_data);
+            state.received_stories.lock().await.push(story_data);
+            SyncResponse::Received { sha256 }
+        }
+    }
+}
+
+/// Carries sync requests to a server. Implementations only move requests
+/// and responses; versioning and capabilities are handled by `SyncClient`.
+pub trait SyncTransport: Send + Sync {
+    /// Deliver `request` and return the server's response, or `None` when
+    /// the server couldn't parse the request. That is how servers from
+    /// before the handshake answer `Hello`.
+    fn send(
+        &self,
+        request: &SyncRequest,
+    ) -> impl Future<Output = Result<Option<SyncResponse>, String>> + Send;
+}
+
+/// Client side of the sync protocol over any transport
+pub struct SyncClient<T> {
+    transport: T,
+    token: String,
+    peer: Option<SyncPeerInfo>,
+}
+
+impl<T: SyncTransport> SyncClient<T> {
+    pub fn new(transport: T, token: String) -> Self {
+        Self {
+            transport,
+            token,
+            peer: None,
+        }
+    }
+
+    /// Reuse an earlier handshake with the same server
+    pub fn with_peer(mut self, peer: Option<SyncPeerInfo>) -> Self {
+        self.peer = peer;
+        self
+    }
+
+    /// The handshake result, once there is one
+    pub fn peer(&self) -> Option<&SyncPeerInfo> {
+        self.peer.as_ref()
+    }
+
+    async fn request(&self, action: SyncAction) -> Result<SyncResponse, String> {
+        let request = SyncRequest::new(self.token.clone(), action);
+        match self.transport.send(&request).await? {
+            Some(SyncResponse::Error { message }) => Err(message),
+            Some(response) => Ok(response),
+            None => Err(
+                "The other device didn't understand the request. Update its app to sync."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Exchange protocol versions with the server. Servers from before the
+    /// handshake can't parse `Hello` and are treated as protocol version 1.
+    pub async fn handshake(&mut self) -> Result<SyncPeerInfo, String> {
+        let request = SyncRequest::new(self.token.clone(), SyncAction::Hello);
+        let peer = match self.transport.send(&request).await? {
+            None => SyncPeerInfo::legacy(),
+            Some(SyncResponse::Hello {
+                protocol_version,
+                min_protocol_version,
+                app_version,
+                capabilities,
+            }) => SyncPeerInfo {
+                protocol_version: negotiate(protocol_version, min_protocol_version)?,
+                app_version: Some(app_version),
+                capabilities,
+            },
+            Some(SyncResponse::Error { message }) => return Err(message),
+            Some(_) => return Err("Unexpected response type".to_string()),
+        };
+        if peer.protocol_version < MIN_PROTOCOL_VERSION {
+            return Err(
+                "The app on the other device is too old to sync with this one. Update it to sync."
+                    .to_string(),
+            );
+        }
+        self.peer = Some(peer.clone());
+        Ok(peer)
+    }
+
+    async fn ensure_peer(&mut self) -> Result<SyncPeerInfo, String> {
+        match &self.peer {
+            Some(peer) => Ok(peer.clone()),
+            None => self.handshake().await,
+        }
+    }
+
+    pub async fn list_stories(&mut self) -> Result<Vec<SyncStoryPreview>, String> {
+        self.ensure_peer().await?;
+        match self.request(SyncAction::ListStories).await? {
+            SyncResponse::StoriesList { stories, .. } => Ok(stories),
+            _ => Err("Unexpected response type".to_string()),
+        }
+    }
+
+    /// List stories unless they still match `since_hash`. Servers without
+    /// conditional listing always return the full list.
+    pub async fn list_stories_if_changed(
+        &mut self,
+        since_hash: Option<String>,
+    ) -> Result<SyncStoryListing, String> {
+        let peer = self.ensure_peer().await?;
+        let action = match since_hash {
+            Some(since_hash) if peer.supports(CAP_CONDITIONAL_LISTING) => {
+                SyncAction::ListStoriesIfChanged { since_hash }
+            }
+            _ => SyncAction::ListStories,
+        };
+        match self.request(action).await? {
+            SyncResponse::StoriesList { stories, hash } => Ok(SyncStoryListing {
+                hash,
+                stories: Some(stories),
+            }),
+            SyncResponse::NotModified { hash } => Ok(SyncStoryListing {
+                hash,
+                stories: None,
+            }),
+            _ => Err("Unexpected response type".to_string()),
+        }
+    }
+
+    pub async fn pull_story(&mut self, story_id: String) -> Result<String, String> {
+        self.ensure_peer().await?;
+        match self.request(SyncAction::PullStory { story_id }).await? {
+            SyncResponse::StoryData { data } => Ok(data),
+            _ => Err("Unexpected response type".to_string()),
+        }
+    }
+
+    pub async fn push_story(&mut self, story_json: String) -> Result<(), String> {
+        self.ensure_peer().await?;
+        let action = SyncAction::PushStory {
+            story_data: story_json,
+        };
+        match self.request(action).await? {
+            SyncResponse::Success { .. } => Ok(()),
+            _ => Err("Unexpected response type".to_string()),
+        }
+    }
+
+    /// Push a story and check the server stored it intact. Returns whether
+    /// that could be confirmed: servers without receipts only acknowledge.
+    pub async fn push_story_confirmed(&mut self, story_json: String) -> Result<bool, String> {
+        let peer = self.ensure_peer().await?;
+        if !peer.supports(CAP_PUSH_RECEIPT) {
+            return self.push_story(story_json).await.map(|_| false);
+        }
+        let expected = checksum(&story_json);
+        let action = SyncAction::PushStoryWithReceipt {
+            story_data: story_json,
+        };
+        match self.request(action).await? {
+            SyncResponse::Received { sha256 } if sha256 == expected => Ok(true),
+            SyncResponse::Received { .. } => {
+                Err("The other device received a damaged copy of the story. Try again.".to_string())
+            }
+            _ => Err("Unexpected response type".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::testing::{story, MemoryTransport, TOKEN};
+
+    #[tokio::test]
+    async fn handshake_negotiates_current_version() {
+        let (transport, _) = MemoryTransport::with_stories(vec![]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let peer = client.handshake().await.unwrap();
+        assert_eq!(peer.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(peer.app_version.as_deref(), Some("1.0.0"));
+        assert!(peer.supports(CAP_CONDITIONAL_LISTING));
+        assert!(client.peer().is_some());
+    }
+
+    #[tokio::test]
+    async fn handshake_treats_unparsed_hello_as_legacy() {
+        let (transport, _) = MemoryTransport::legacy(vec![]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let peer = client.handshake().await.unwrap();
+        assert_eq!(peer.protocol_version, 1);
+        assert_eq!(peer.app_version, None);
+        assert!(!peer.supports(CAP_CONDITIONAL_LISTING));
+    }
+
+    #[tokio::test]
+    async fn wrong_token_is_rejected() {
+        let (transport, _) = MemoryTransport::with_stories(vec![story("a", 1)]);
+        let mut client = SyncClient::new(transport, "wrong".to_string());
+
+        let err = client.list_stories().await.unwrap_err();
+        assert_eq!(err, "Invalid authentication token");
+    }
+
+    #[tokio::test]
+    async fn requests_below_minimum_version_are_rejected() {
+        let (_, state) = MemoryTransport::with_stories(vec![]);
+        let mut request = SyncRequest::new(TOKEN.to_string(), SyncAction::ListStories);
+        request.protocol_version = MIN_PROTOCOL_VERSION - 1;
+
+        match handle_request(&state, request).await {
+            SyncResponse::Error { message } => assert!(message.contains("too old")),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn negotiate_names_the_device_to_update() {
+        assert_eq!(negotiate(PROTOCOL_VERSION, 1), Ok(PROTOCOL_VERSION));
+        assert_eq!(negotiate(PROTOCOL_VERSION + 5, 1), Ok(PROTOCOL_VERSION));
+        assert!(negotiate(MIN_PROTOCOL_VERSION - 1, 0)
+            .unwrap_err()
+            .contains("other device"));
+        assert!(negotiate(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 1)
+            .unwrap_err()
+            .starts_with("This app"));
+    }
+
+    #[tokio::test]
+    async fn lists_stories_in_order() {
+        let (transport, _) = MemoryTransport::with_stories(vec![story("a", 1), story("b", 2)]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let stories = client.list_stories().await.unwrap();
+        let ids: Vec<&str> = stories.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn conditional_listing_skips_unchanged_lists() {
+        let (transport, state) = MemoryTransport::with_stories(vec![story("a", 1)]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let first = client.list_stories_if_changed(None).await.unwrap();
+        assert_eq!(first.stories.as_ref().map(Vec::len), Some(1));
+
+        let again = client
+            .list_stories_if_changed(Some(first.hash.clone()))
+            .await
+            .unwrap();
+        assert_eq!(again.hash, first.hash);
+        assert!(again.stories.is_none());
+
+        state.stories.lock().await.push(story("b", 2));
+        let changed = client
+            .list_stories_if_changed(Some(first.hash.clone()))
+            .await
+            .unwrap();
+        assert_ne!(changed.hash, first.hash);
+        assert_eq!(changed.stories.map(|s| s.len()), Some(2));
+    }
+
+    #[tokio::test]
+    async fn listing_hash_tracks_story_updates() {
+        let (transport, state) = MemoryTransport::with_stories(vec![story("a", 1)]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+        let first = client.list_stories_if_changed(None).await.unwrap();
+
+        state.stories.lock().await[0] = story("a", 2);
+        let updated = client
+            .list_stories_if_changed(Some(first.hash.clone()))
+            .await
+            .unwrap();
+        assert_ne!(updated.hash, first.hash);
+        assert!(updated.stories.is_some());
+    }
+
+    #[tokio::test]
+    async fn legacy_servers_get_full_listings() {
+        let (transport, _) = MemoryTransport::legacy(vec![story("a", 1)]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let listing = client
+            .list_stories_if_changed(Some("stale".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(listing.stories.map(|s| s.len()), Some(1));
+    }
+
+    #[tokio::test]
+    async fn pulls_known_stories_only() {
+        let (transport, _) = MemoryTransport::with_stories(vec![story("a", 1)]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        let data = client.pull_story("a".to_string()).await.unwrap();
+        assert!(data.contains("\"id\":\"a\""));
+
+        let err = client.pull_story("missing".to_string()).await.unwrap_err();
+        assert_eq!(err, "Story not found: missing");
+    }
+
+    #[tokio::test]
+    async fn pushed_stories_are_received() {
+        let (transport, state) = MemoryTransport::with_stories(vec![]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        client
+            .push_story("{\"story\":1}".to_string())
+            .await
+            .unwrap();
+        client
+            .push_story("{\"story\":2}".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            *state.received_stories.lock().await,
+            ["{\"story\":1}", "{\"story\":2}"]
+        );
+    }
+
+    #[tokio::test]
+    async fn confirmed_pushes_check_the_receipt() {
+        let (transport, state) = MemoryTransport::with_stories(vec![]);
+        let sent = transport.sent.clone();
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        assert!(client
+            .push_story_confirmed("{\"story\":1}".to_string())
+            .await
+            .unwrap());
+        assert_eq!(*state.received_stories.lock().await, ["{\"story\":1}"]);
+        assert!(sent
+            .lock()
+            .await
+            .iter()
+            .any(|r| matches!(r.action, SyncAction::PushStoryWithReceipt { .. })));
+    }
+
+    #[tokio::test]
+    async fn legacy_servers_get_unconfirmed_pushes() {
+        let (transport, state) = MemoryTransport::legacy(vec![]);
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        assert!(!client
+            .push_story_confirmed("{\"story\":1}".to_string())
+            .await
+            .unwrap());
+        assert_eq!(state.received_stories.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handshake_happens_once_per_client() {
+        let (transport, _) = MemoryTransport::with_stories(vec![story("a", 1)]);
+        let sent = transport.sent.clone();
+        let mut client = SyncClient::new(transport, TOKEN.to_string());
+
+        client.list_stories().await.unwrap();
+        client.pull_story("a".to_string()).await.unwrap();
+        let hellos = sent
+            .lock()
+            .await
+            .iter()
+            .filter(|r| matches!(r.action, SyncAction::Hello))
+            .count();
+        assert_eq!(hellos, 1);
+    }
+
+    #[tokio::test]
+    async fn remembered_peer_skips_the_handshake() {
+        let (transport, _) = MemoryTransport::legacy(vec![story("a", 1)]);
+        let sent = transport.sent.clone();
+        let mut client =
+            SyncClient::new(transport, TOKEN.to_string()).with_peer(Some(SyncPeerInfo::legacy()));
+
+        client.list_stories().await.unwrap();
+        assert_eq!(sent.lock().await.len(), 1);
+    }
+}
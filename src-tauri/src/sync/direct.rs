@@ -0,0 +1,92 @@
+//! Direct device-to-device connections with no router in between. The
+//! phone hosts its own network (`DirectLinkPlugin`), the other device joins
+//! it with the credentials from the QR code, and sync runs over HTTP as
+//! usual. Only Android can host one.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::plugin::{Builder, PluginApi, TauriPlugin};
+use tauri::{AppHandle, Wry};
+
+/// A network this device is hosting for direct sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectLink {
+    /// `wifiDirect` for a WiFi Direct group, `hotspot` for a local-only hotspot
+    pub mode: String,
+    pub ssid: String,
+    pub passphrase: String,
+    /// This device's address on the network, where the sync server is reached
+    pub owner_address: String,
+}
+
+#[cfg(target_os = "android")]
+struct DirectLinkHandle(tauri::plugin::PluginHandle<Wry>);
+
+#[cfg(target_os = "android")]
+fn register(app: &AppHandle, api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    use tauri::Manager;
+
+    let handle = api.register_android_plugin("com.karelian.aventura", "DirectLinkPlugin")?;
+    app.manage(DirectLinkHandle(handle));
+    Ok(())
+}
+
+#[cfg(not(target_os = "android"))]
+fn register(_app: &AppHandle, _api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Registers the Android plugin that hosts direct connections
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("sync-direct").setup(register).build()
+}
+
+/// Creating a group waits on the WiFi driver, so calls run off the async
+/// runtime
+#[cfg(target_os = "android")]
+async fn run(app: &AppHandle, command: &'static str) -> Result<Value, String> {
+    use tauri::Manager;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<DirectLinkHandle>()
+            .0
+            .run_mobile_plugin(command, Value::Null)
+            .map_err(|e| format!("Direct connection error: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Direct connection error: {}", e))?
+}
+
+#[cfg(not(target_os = "android"))]
+async fn run(_app: &AppHandle, _command: &'static str) -> Result<Value, String> {
+    Err("Direct connections are only available on Android".to_string())
+}
+
+fn parse_link(value: Value) -> Result<DirectLink, String> {
+    serde_json::from_value(value).map_err(|e| format!("Invalid direct connection: {}", e))
+}
+
+/// Host a network for direct sync, replacing any running one
+pub async fn start(app: &AppHandle) -> Result<DirectLink, String> {
+    parse_link(run(app, "start").await?)
+}
+
+pub async fn stop(app: &AppHandle) -> Result<(), String> {
+    if cfg!(not(target_os = "android")) {
+        return Ok(());
+    }
+    run(app, "stop").await.map(|_| ())
+}
+
+/// The network being hosted, if any
+pub async fn status(app: &AppHandle) -> Result<Option<DirectLink>, String> {
+    if cfg!(not(target_os = "android")) {
+        return Ok(None);
+    }
+    match run(app, "status").await?.get("link") {
+        Some(link) if !link.is_null() => parse_link(link.clone()).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::bandwidth::{read_throttled, throttled_body, SyncBandwidth};
+use super::core::SyncTransport;
+use super::types::{SyncAction, SyncRequest, SyncResponse};
+
+/// Client side of the HTTP server in `server`, one POST per request
+pub struct HttpTransport {
+    url: String,
+    bandwidth: Arc<SyncBandwidth>,
+}
+
+impl HttpTransport {
+    pub fn new(ip: &str, port: u16, bandwidth: Arc<SyncBandwidth>) -> Self {
+        Self {
+            url: format!("http://{}:{}/sync", ip, port),
+            bandwidth,
+        }
+    }
+}
+
+/// Stories take longer to move than listings
+fn timeout_for(action: &SyncAction) -> Duration {
+    match action {
+        SyncAction::PullStory { .. }
+        | SyncAction::PushStory { .. }
+        | SyncAction::PushStoryWithReceipt { .. } => Duration::from_secs(30),
+        _ => Duration::from_secs(10),
+    }
+}
+
+impl SyncTransport for HttpTransport {
+    async fn send(&self, request: &SyncRequest) -> Result<Option<SyncResponse>, String> {
+        let body = serde_json::to_vec(request)
+            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+
+        // A throttled transfer can legitimately outlast the usual timeout, so
+        // only give up when the connection stalls
+        let timeout = timeout_for(&request.action);
+        let client = match self.bandwidth.limit() {
+            Some(_) => reqwest::Client::builder().read_timeout(timeout).build(),
+            None => reqwest::Client::builder().timeout(timeout).build(),
+        }
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(throttled_body(body, self.bandwidth.clone()))
+            .send()
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?;
+
+        // axum rejects bodies it can't deserialize with 422
+        let status = response.status();
+        if status == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(format!("Sync server returned {}", status));
+        }
+
+        let bytes = read_throttled(response, &self.bandwidth)
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?;
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| format!("Invalid response: {}", e))
+    }
+}
@@ -0,0 +1,73 @@
+//! iOS and macOS block LAN traffic until the user allows Local Network
+//! access, and apps aren't told: connections just fail. There's no API to
+//! read the setting, so it's inferred from whether a multicast packet can
+//! be sent. Sending one is also what makes the OS show its prompt, so
+//! `probe` should only run once the user has asked to sync.
+
+use serde::Serialize;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LocalNetworkAccess {
+    /// This platform doesn't gate the local network
+    NotRequired,
+    /// Not probed yet, so the OS may not have asked
+    Unknown,
+    Granted,
+    /// Refused, or the prompt hasn't been answered yet
+    Denied,
+}
+
+/// mDNS, the multicast group the OS watches for this
+const MDNS: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(224, 0, 0, 251), 5353);
+
+/// `EHOSTUNREACH` on Apple platforms, which is what a blocked send returns
+const HOST_UNREACHABLE: i32 = 65;
+
+/// An mDNS question for `_aventura._tcp.local` PTR records, so the probe
+/// is an ordinary query to anything listening
+fn query() -> Vec<u8> {
+    // ID, flags, one question, no answer/authority/additional records
+    let mut packet = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in ["_aventura", "_tcp", "local"] {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    // End of name, type PTR, class IN
+    packet.extend_from_slice(&[0, 0, 12, 0, 1]);
+    packet
+}
+
+/// Whether the app may use the local network, prompting the user the
+/// first time on Apple platforms
+pub fn probe() -> LocalNetworkAccess {
+    if cfg!(not(any(target_os = "ios", target_os = "macos"))) {
+        return LocalNetworkAccess::NotRequired;
+    }
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) {
+        Ok(socket) => socket,
+        Err(_) => return LocalNetworkAccess::Unknown,
+    };
+    match socket.send_to(&query(), MDNS) {
+        Ok(_) => LocalNetworkAccess::Granted,
+        Err(e)
+            if e.raw_os_error() == Some(HOST_UNREACHABLE)
+                || e.kind() == std::io::ErrorKind::PermissionDenied =>
+        {
+            LocalNetworkAccess::Denied
+        }
+        // No network at all says nothing about the permission
+        Err(_) => LocalNetworkAccess::Unknown,
+    }
+}
+
+/// Explain a failed connection when it's down to Local Network access
+pub fn explain(error: String) -> String {
+    if probe() == LocalNetworkAccess::Denied {
+        return "Aventura isn't allowed on your local network, so it can't reach other devices. \
+                Allow it under Settings > Privacy & Security > Local Network."
+            .to_string();
+    }
+    error
+}
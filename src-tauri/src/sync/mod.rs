@@ -1,5 +1,16 @@
+pub mod bandwidth;
+pub mod ble;
 pub mod commands;
+pub mod core;
+pub mod direct;
+pub mod http;
+pub mod local_network;
+pub mod protocol;
 pub mod server;
+pub mod service;
+pub mod stream;
+#[cfg(test)]
+pub mod testing;
 pub mod types;
 
 pub use commands::SyncState;
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the sync protocol this build speaks. Bump it when requests or
+/// responses change in a way older builds can't handle, and keep serving
+/// the previous version until `MIN_PROTOCOL_VERSION` moves up.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol still spoken. Version 1 is the protocol from before the
+/// handshake existed.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Listing with `ListStoriesIfChanged` and a hash on `StoriesList`
+pub const CAP_CONDITIONAL_LISTING: &str = "conditionalListing";
+
+/// `PushStoryWithReceipt`, answered with a checksum of what was stored
+pub const CAP_PUSH_RECEIPT: &str = "pushReceipt";
+
+/// Optional features this build's server supports. Names unknown to a peer
+/// are ignored, so capabilities can be added without a version bump.
+pub const CAPABILITIES: &[&str] = &[CAP_CONDITIONAL_LISTING, CAP_PUSH_RECEIPT];
+
+/// Requests and QR codes without a version come from builds before
+/// versioning
+pub fn legacy_version() -> u32 {
+    1
+}
+
+/// What a peer reported about itself in the handshake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPeerInfo {
+    /// Version both sides will speak
+    pub protocol_version: u32,
+    /// `None` for peers that predate the handshake
+    pub app_version: Option<String>,
+    pub capabilities: Vec<String>,
+}
+
+impl SyncPeerInfo {
+    /// A peer that answers requests but doesn't know the handshake
+    pub fn legacy() -> Self {
+        Self {
+            protocol_version: legacy_version(),
+            app_version: None,
+            capabilities: Vec::new(),
+        }
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+/// Pick the version to speak with a peer, or explain which side needs
+/// updating
+pub fn negotiate(peer_version: u32, peer_min_version: u32) -> Result<u32, String> {
+    if peer_version < MIN_PROTOCOL_VERSION {
+        return Err(
+            "The app on the other device is too old to sync with this one. Update it to sync."
+                .to_string(),
+        );
+    }
+    if PROTOCOL_VERSION < peer_min_version {
+        return Err(
+            "This app is too old to sync with the other device. Update it to sync.".to_string(),
+        );
+    }
+    Ok(PROTOCOL_VERSION.min(peer_version))
+}
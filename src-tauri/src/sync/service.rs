@@ -0,0 +1,84 @@
+use serde::Serialize;
+use tauri::plugin::{Builder, PluginApi, TauriPlugin};
+use tauri::{AppHandle, Wry};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncServiceStatus {
+    /// Whether this platform has a background service
+    pub supported: bool,
+    pub running: bool,
+}
+
+#[cfg(target_os = "android")]
+pub struct SyncService(tauri::plugin::PluginHandle<Wry>);
+
+#[cfg(target_os = "android")]
+fn register(app: &AppHandle, api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    use tauri::Manager;
+
+    let handle = api.register_android_plugin("com.karelian.aventura", "SyncServicePlugin")?;
+    app.manage(SyncService(handle));
+    Ok(())
+}
+
+#[cfg(not(target_os = "android"))]
+fn register(_app: &AppHandle, _api: PluginApi<Wry, ()>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Registers the Android foreground service that keeps the sync server
+/// reachable with the app in the background. Other platforms don't suspend
+/// the server, so there the service is reported as unsupported.
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("sync-service").setup(register).build()
+}
+
+#[cfg(target_os = "android")]
+fn run(app: &AppHandle, command: &str, payload: serde_json::Value) -> Result<serde_json::Value, String> {
+    use tauri::Manager;
+
+    app.state::<SyncService>()
+        .0
+        .run_mobile_plugin(command, payload)
+        .map_err(|e| format!("Sync service error: {}", e))
+}
+
+/// Move the sync server into a foreground service, showing `address` in
+/// its notification
+#[cfg(target_os = "android")]
+pub fn start(app: &AppHandle, address: &str) -> Result<(), String> {
+    run(app, "start", serde_json::json!({ "address": address })).map(|_| ())
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn start(_app: &AppHandle, _address: &str) -> Result<(), String> {
+    Err("Background sync is only available on Android".to_string())
+}
+
+#[cfg(target_os = "android")]
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    run(app, "stop", serde_json::Value::Null).map(|_| ())
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn stop(_app: &AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+pub fn status(app: &AppHandle) -> Result<SyncServiceStatus, String> {
+    let response = run(app, "status", serde_json::Value::Null)?;
+    Ok(SyncServiceStatus {
+        supported: true,
+        running: response.get("running").and_then(|r| r.as_bool()).unwrap_or(false),
+    })
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn status(_app: &AppHandle) -> Result<SyncServiceStatus, String> {
+    Ok(SyncServiceStatus {
+        supported: false,
+        running: false,
+    })
+}
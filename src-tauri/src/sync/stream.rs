@@ -0,0 +1,158 @@
+//! Sync over any byte stream, for transports that hold one connection open
+//! (relays, tunnels). Each message is a big-endian `u32` length followed
+//! by that many bytes of JSON; an empty frame in reply means the server
+//! couldn't parse the request.
+// Nothing opens stream connections yet; the in-memory tests cover it
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use super::core::{handle_request, ServerState, SyncTransport};
+use super::types::{SyncRequest, SyncResponse};
+
+/// Same ceiling as the HTTP server's body limit
+const MAX_FRAME: usize = 100 * 1024 * 1024;
+
+async fn write_frame<S: AsyncWrite + Unpin>(stream: &mut S, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_FRAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Sync message too large"))?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(payload).await?;
+    stream.flush().await
+}
+
+/// `None` when the other side closed the stream between frames
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Sync message too large",
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
+
+/// Answer requests on `stream` until the client disconnects
+pub async fn serve_stream<S>(state: ServerState, mut stream: S) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(payload) = read_frame(&mut stream).await? {
+        let reply = match serde_json::from_slice::<SyncRequest>(&payload) {
+            Ok(request) => serde_json::to_vec(&handle_request(&state, request).await)?,
+            Err(_) => Vec::new(),
+        };
+        write_frame(&mut stream, &reply).await?;
+    }
+    Ok(())
+}
+
+/// Client side of `serve_stream`. Requests on one connection are answered
+/// in order, so they're sent one at a time.
+pub struct StreamTransport<S> {
+    stream: Mutex<S>,
+}
+
+impl<S> StreamTransport<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream: Mutex::new(stream),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> SyncTransport for StreamTransport<S> {
+    async fn send(&self, request: &SyncRequest) -> Result<Option<SyncResponse>, String> {
+        let payload = serde_json::to_vec(request)
+            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+        let mut stream = self.stream.lock().await;
+        write_frame(&mut *stream, &payload)
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?;
+
+        let reply = read_frame(&mut *stream)
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?
+            .ok_or("Connection closed by the other device")?;
+        if reply.is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_slice(&reply)
+            .map(Some)
+            .map_err(|e| format!("Invalid response: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::core::SyncClient;
+    use crate::sync::protocol::PROTOCOL_VERSION;
+    use crate::sync::testing::{story, TOKEN};
+
+    fn connect(state: &ServerState) -> StreamTransport<tokio::io::DuplexStream> {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(serve_stream(state.clone(), server));
+        StreamTransport::new(client)
+    }
+
+    #[tokio::test]
+    async fn runs_the_protocol_over_a_stream() {
+        let state = ServerState::new(TOKEN.to_string(), "1.0.0".to_string());
+        state.stories.lock().await.push(story("a", 1));
+        let mut client = SyncClient::new(connect(&state), TOKEN.to_string());
+
+        let peer = client.handshake().await.unwrap();
+        assert_eq!(peer.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(client.list_stories().await.unwrap().len(), 1);
+        assert!(client.pull_story("a".to_string()).await.is_ok());
+        client.push_story("{}".to_string()).await.unwrap();
+        assert_eq!(state.received_stories.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn answers_unparseable_requests_with_an_empty_frame() {
+        let state = ServerState::new(TOKEN.to_string(), "1.0.0".to_string());
+        let (mut client, server) = tokio::io::duplex(1024);
+        tokio::spawn(serve_stream(state, server));
+
+        write_frame(&mut client, b"{\"action\":{\"type\":\"fromTheFuture\"}}")
+            .await
+            .unwrap();
+        assert_eq!(read_frame(&mut client).await.unwrap(), Some(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn reports_a_closed_connection() {
+        let (client, server) = tokio::io::duplex(1024);
+        drop(server);
+        let mut client = SyncClient::new(StreamTransport::new(client), TOKEN.to_string());
+
+        assert!(client.handshake().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn frames_survive_small_buffers() {
+        let state = ServerState::new(TOKEN.to_string(), "1.0.0".to_string());
+        let (client, server) = tokio::io::duplex(7);
+        tokio::spawn(serve_stream(state.clone(), server));
+        let mut client = SyncClient::new(StreamTransport::new(client), TOKEN.to_string());
+
+        let big = format!("{{\"text\":\"{}\"}}", "x".repeat(100_000));
+        client.push_story(big.clone()).await.unwrap();
+        assert_eq!(*state.received_stories.lock().await, [big]);
+    }
+}
@@ -0,0 +1,76 @@
+//! In-memory sync server for exercising the protocol without sockets
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::core::{handle_request, ServerState, StoriesData, SyncTransport};
+use super::types::{SyncAction, SyncRequest, SyncResponse, SyncStoryPreview};
+
+pub const TOKEN: &str = "test-token";
+
+/// A story as the server would hold it after `start_sync_server`
+pub fn story(id: &str, updated_at: i64) -> StoriesData {
+    StoriesData {
+        preview: SyncStoryPreview {
+            id: id.to_string(),
+            title: format!("Story {}", id),
+            genre: None,
+            updated_at,
+            entry_count: 0,
+        },
+        full_data: format!("{{\"story\":{{\"id\":\"{}\"}}}}", id),
+    }
+}
+
+/// Hands requests straight to `handle_request`, recording them
+pub struct MemoryTransport {
+    state: ServerState,
+    /// Refuse actions added after protocol version 1, like servers from
+    /// before the handshake
+    legacy: bool,
+    pub sent: Arc<Mutex<Vec<SyncRequest>>>,
+}
+
+impl MemoryTransport {
+    pub fn with_stories(stories: Vec<StoriesData>) -> (Self, ServerState) {
+        Self::build(stories, false)
+    }
+
+    pub fn legacy(stories: Vec<StoriesData>) -> (Self, ServerState) {
+        Self::build(stories, true)
+    }
+
+    fn build(stories: Vec<StoriesData>, legacy: bool) -> (Self, ServerState) {
+        let state = ServerState::new(TOKEN.to_string(), "1.0.0".to_string());
+        state.stories.try_lock().unwrap().extend(stories);
+        let transport = Self {
+            state: state.clone(),
+            legacy,
+            sent: Arc::new(Mutex::new(Vec::new())),
+        };
+        (transport, state)
+    }
+}
+
+impl SyncTransport for MemoryTransport {
+    async fn send(&self, request: &SyncRequest) -> Result<Option<SyncResponse>, String> {
+        self.sent.lock().await.push(request.clone());
+        let unknown = matches!(
+            request.action,
+            SyncAction::Hello
+                | SyncAction::ListStoriesIfChanged { .. }
+                | SyncAction::PushStoryWithReceipt { .. }
+        );
+        if self.legacy && unknown {
+            return Ok(None);
+        }
+        // Round-trip through JSON like a real transport would
+        let wire = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        let request = serde_json::from_str(&wire).map_err(|e| e.to_string())?;
+        let response = handle_request(&self.state, request).await;
+        let wire = serde_json::to_string(&response).map_err(|e| e.to_string())?;
+        serde_json::from_str(&wire)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+}
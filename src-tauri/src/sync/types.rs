@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::direct::DirectLink;
+use super::protocol::{legacy_version, PROTOCOL_VERSION};
+
 /// Information about the sync server, returned when starting a server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -8,6 +11,8 @@ pub struct SyncServerInfo {
     pub port: u16,
     pub token: String,
     pub qr_code_base64: String,
+    /// Network this device hosts for the server, when started in direct mode
+    pub direct: Option<DirectLink>,
 }
 
 /// Preview of a story available for sync
@@ -26,34 +31,124 @@ pub struct SyncStoryPreview {
 pub struct SyncRequest {
     pub token: String,
     pub action: SyncAction,
+    /// Protocol version of the sender
+    #[serde(default = "legacy_version")]
+    pub protocol_version: u32,
+}
+
+impl SyncRequest {
+    pub fn new(token: String, action: SyncAction) -> Self {
+        Self {
+            token,
+            action,
+            protocol_version: PROTOCOL_VERSION,
+        }
+    }
 }
 
 /// Actions that can be performed on the sync server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum SyncAction {
+    /// Exchange protocol versions and capabilities before anything else
+    Hello,
     /// List all available stories on the server
     ListStories,
+    /// List stories only if the listing no longer matches `since_hash`
+    ListStoriesIfChanged { since_hash: String },
     /// Pull a specific story by ID
     PullStory { story_id: String },
     /// Push a story to the server
     PushStory { story_data: String },
+    /// Push a story and get back a checksum of what the server stored
+    PushStoryWithReceipt { story_data: String },
 }
 
 /// Response from the sync server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum SyncResponse {
-    /// List of available stories
-    StoriesList { stories: Vec<SyncStoryPreview> },
+    /// The server's protocol versions, app version and capabilities
+    Hello {
+        protocol_version: u32,
+        min_protocol_version: u32,
+        app_version: String,
+        capabilities: Vec<String>,
+    },
+    /// List of available stories, with a hash identifying this listing
+    StoriesList {
+        stories: Vec<SyncStoryPreview>,
+        /// Empty from servers that predate conditional listing
+        #[serde(default)]
+        hash: String,
+    },
+    /// The listing still matches the hash the client sent
+    NotModified { hash: String },
     /// Full story data (Aventura export JSON)
     StoryData { data: String },
+    /// A pushed story was stored; `sha256` is the hex digest of its data
+    Received { sha256: String },
     /// Operation succeeded
     Success { message: String },
     /// Operation failed
     Error { message: String },
 }
 
+/// Result of a conditional listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStoryListing {
+    pub hash: String,
+    /// `None` when the listing hasn't changed since the hash the caller sent
+    pub stories: Option<Vec<SyncStoryPreview>>,
+}
+
+/// A device to send a story to, from a QR code or a Bluetooth scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SyncDevice {
+    /// A sync server on the network
+    Wifi {
+        ip: String,
+        port: u16,
+        token: String,
+    },
+    /// A device advertising over Bluetooth; its token comes from pairing
+    Bluetooth { address: String },
+}
+
+/// Where `send_story_to_device` has got to, emitted as `sync-send-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSendProgress {
+    pub story_id: String,
+    pub stage: SyncSendStage,
+    /// `wifi` or `bluetooth`, once the route is known
+    pub transport: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncSendStage {
+    Pairing,
+    Connecting,
+    /// Pushing the story and waiting for the receipt
+    Sending,
+    Done,
+}
+
+/// Outcome of `send_story_to_device`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSendResult {
+    pub transport: String,
+    /// Whether the receiver confirmed it stored exactly what was sent.
+    /// Older apps only acknowledge the push.
+    pub confirmed: bool,
+    /// Images and checkpoints were left out to fit through Bluetooth
+    pub text_only: bool,
+}
+
 /// Data encoded in the QR code
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QrCodeData {
@@ -61,4 +156,29 @@ pub struct QrCodeData {
     pub port: u16,
     pub token: String,
     pub version: String, // App version for compatibility check
+    /// Sync protocol version of the server
+    #[serde(rename = "protocolVersion", default = "legacy_version")]
+    pub protocol_version: u32,
+    /// Network to join first when the server hosts a direct connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wifi: Option<QrWifiData>,
+}
+
+/// Credentials for the network a device hosts in direct mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrWifiData {
+    pub ssid: String,
+    pub passphrase: String,
+}
+
+/// What a device shares with anyone who pairs over Bluetooth
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlePairingData {
+    pub token: String,
+    /// App version
+    pub version: String,
+    pub protocol_version: u32,
+    /// `ip:port` of its WiFi sync server, if running, for larger transfers
+    pub wifi_address: Option<String>,
 }
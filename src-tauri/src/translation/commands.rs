@@ -0,0 +1,231 @@
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use super::glossary::{load_glossary, GlossaryEntry, GlossaryInput, GlossarySuggestion};
+use super::local::{load_settings, AvailableModel, LocalTranslator, MODEL_KIND};
+use super::types::TranslationPayload;
+use crate::db::{now_millis, DbState};
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+use crate::models::ModelManager;
+
+/// Queue background translation of a story's untranslated narration.
+///
+/// Pass `entry_ids` to translate only those entries (e.g. one just generated).
+#[tauri::command]
+pub async fn queue_translation(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    language: String,
+    entry_ids: Option<Vec<String>>,
+) -> Result<String, String> {
+    let payload = TranslationPayload {
+        story_id,
+        language,
+        entry_ids,
+    };
+    let value = serde_json::to_value(&payload)
+        .map_err(|e| format!("Failed to serialize translation job: {}", e))?;
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&payload.story_id),
+            JobKind::Translation,
+            value,
+        )
+        .await
+}
+
+/// Drop cached translations, optionally only for one language
+#[tauri::command]
+pub async fn clear_translation_cache(
+    db: State<'_, DbState>,
+    language: Option<String>,
+) -> Result<u64, String> {
+    let result = sqlx::query("DELETE FROM translation_cache WHERE ?1 IS NULL OR language = ?1")
+        .bind(language)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to clear translation cache: {}", e))?;
+    Ok(result.rows_affected())
+}
+
+/// Offline translation state: installed pairs and whether it is enabled
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalTranslationStatus {
+    pub enabled: bool,
+    pub engine_configured: bool,
+    pub installed_pairs: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn get_local_translation_status(
+    db: State<'_, DbState>,
+    local: State<'_, LocalTranslator>,
+) -> Result<LocalTranslationStatus, String> {
+    let settings = load_settings(db.pool()).await?;
+    Ok(LocalTranslationStatus {
+        enabled: settings.enabled,
+        engine_configured: settings.engine_path.is_some_and(|p| !p.trim().is_empty()),
+        installed_pairs: local.installed_pairs(),
+    })
+}
+
+/// List the models offered by the configured model index
+#[tauri::command]
+pub async fn list_translation_models(
+    db: State<'_, DbState>,
+    local: State<'_, LocalTranslator>,
+) -> Result<Vec<AvailableModel>, String> {
+    let settings = load_settings(db.pool()).await?;
+    let index = LocalTranslator::fetch_index(&settings).await?;
+    let installed = local.installed_pairs();
+    Ok(index
+        .models
+        .into_iter()
+        .map(|m| AvailableModel {
+            installed: installed.contains(&m.pair),
+            size: m.files.iter().filter_map(|f| f.size).sum(),
+            pair: m.pair,
+        })
+        .collect())
+}
+
+/// Download and install an offline model for a language pair (e.g. "en-de").
+///
+/// Progress is emitted on `model-download-progress`, and an interrupted
+/// download resumes where it stopped.
+#[tauri::command]
+pub async fn download_translation_model(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    local: State<'_, LocalTranslator>,
+    models: State<'_, ModelManager>,
+    lang_pair: String,
+) -> Result<(), String> {
+    let settings = load_settings(db.pool()).await?;
+    let index = LocalTranslator::fetch_index(&settings).await?;
+    let model = index
+        .models
+        .into_iter()
+        .find(|m| m.pair == lang_pair)
+        .ok_or_else(|| format!("No model available for {}", lang_pair))?;
+    local.download(&app, &models, &model).await
+}
+
+/// Remove an installed offline model
+#[tauri::command]
+pub async fn delete_translation_model(
+    models: State<'_, ModelManager>,
+    lang_pair: String,
+) -> Result<(), String> {
+    models.remove(MODEL_KIND, &lang_pair)
+}
+
+/// Get a story's glossary, optionally for one language
+#[tauri::command]
+pub async fn get_translation_glossary(
+    db: State<'_, DbState>,
+    story_id: String,
+    language: Option<String>,
+) -> Result<Vec<GlossaryEntry>, String> {
+    load_glossary(db.pool(), &story_id, language.as_deref())
+        .await
+        .map_err(|e| format!("Failed to load glossary: {}", e))
+}
+
+/// Create or update a glossary entry, returning its id
+#[tauri::command]
+pub async fn save_glossary_entry(
+    db: State<'_, DbState>,
+    story_id: String,
+    entry: GlossaryInput,
+) -> Result<String, String> {
+    let source = entry.source_term.trim();
+    let target = entry.target_term.trim();
+    if source.is_empty() || target.is_empty() {
+        return Err("Glossary terms cannot be empty".to_string());
+    }
+
+    let id = entry.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    sqlx::query(
+        "INSERT INTO translation_glossary (id, story_id, language, source_term, target_term, notes, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET language = excluded.language, source_term = excluded.source_term, \
+         target_term = excluded.target_term, notes = excluded.notes",
+    )
+    .bind(&id)
+    .bind(&story_id)
+    .bind(&entry.language)
+    .bind(source)
+    .bind(target)
+    .bind(&entry.notes)
+    .bind(now_millis())
+    .execute(db.pool())
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            format!("'{}' already has a glossary entry for this language", source)
+        }
+        e => format!("Failed to save glossary entry: {}", e),
+    })?;
+    Ok(id)
+}
+
+/// Delete a glossary entry
+#[tauri::command]
+pub async fn delete_glossary_entry(db: State<'_, DbState>, entry_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM translation_glossary WHERE id = ?")
+        .bind(&entry_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete glossary entry: {}", e))?;
+    Ok(())
+}
+
+/// Suggest glossary terms from lorebook entry names and aliases that have no
+/// entry for `language` yet
+#[tauri::command]
+pub async fn suggest_glossary_terms(
+    db: State<'_, DbState>,
+    story_id: String,
+    language: String,
+) -> Result<Vec<GlossarySuggestion>, String> {
+    let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT name, type, aliases FROM entries WHERE story_id = ? AND deleted = 0 ORDER BY type, name",
+    )
+    .bind(&story_id)
+    .fetch_all(db.pool())
+    .await
+    .map_err(|e| format!("Failed to load lorebook: {}", e))?;
+
+    let existing: Vec<String> = load_glossary(db.pool(), &story_id, Some(&language))
+        .await
+        .map_err(|e| format!("Failed to load glossary: {}", e))?
+        .into_iter()
+        .map(|g| g.source_term.to_lowercase())
+        .collect();
+
+    let mut seen = std::collections::HashSet::new();
+    let mut suggestions = Vec::new();
+    for (name, origin, aliases) in rows {
+        let aliases: Vec<String> = aliases
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        for term in std::iter::once(name).chain(aliases) {
+            let term = term.trim().to_string();
+            let key = term.to_lowercase();
+            if term.is_empty() || existing.contains(&key) || !seen.insert(key) {
+                continue;
+            }
+            suggestions.push(GlossarySuggestion {
+                term,
+                origin: origin.clone(),
+            });
+        }
+    }
+    Ok(suggestions)
+}
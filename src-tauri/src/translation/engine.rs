@@ -0,0 +1,183 @@
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use super::glossary::{apply_terms, fingerprint, load_glossary, prompt_block, relevant_terms};
+use super::local::{load_settings, LocalTranslator};
+use crate::db::now_millis;
+use crate::llm::client::{self, ChatMessage};
+use crate::llm::config;
+
+/// Mirrors the frontend's translate-narration template
+const NARRATION_PROMPT: &str = "You are a professional literary translator. Translate the following narrative text to {language}.
+
+Rules:
+1. Preserve the original meaning, tone, and literary style
+2. Keep proper nouns and character names unchanged
+3. Maintain the narrative voice (POV, tense)
+4. Do not add, remove, or interpret content
+5. If the text contains HTML tags or <pic> tags, preserve them EXACTLY as-is including all attributes. Only translate the text content OUTSIDE of tags.
+   - <pic prompt=\"...\" characters=\"...\"></pic> tags must be copied EXACTLY without any changes to the tag, attributes, or attribute values
+   - These tags contain English image prompts that must NOT be translated
+
+Respond with ONLY the translated text, no explanations or notes.";
+
+/// Hex SHA-256 of source text, the cache key alongside the language
+pub fn content_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+/// English name for a language code, for prompts; unknown codes pass through
+pub fn language_name(code: &str) -> &str {
+    match code {
+        "en" => "English",
+        "es" => "Spanish",
+        "fr" => "French",
+        "de" => "German",
+        "it" => "Italian",
+        "pt" => "Portuguese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "zh" => "Chinese",
+        "ru" => "Russian",
+        "ar" => "Arabic",
+        "hi" => "Hindi",
+        "nl" => "Dutch",
+        "pl" => "Polish",
+        "tr" => "Turkish",
+        "vi" => "Vietnamese",
+        "th" => "Thai",
+        "id" => "Indonesian",
+        "sv" => "Swedish",
+        "da" => "Danish",
+        "no" => "Norwegian",
+        "fi" => "Finnish",
+        "cs" => "Czech",
+        "el" => "Greek",
+        "he" => "Hebrew",
+        "uk" => "Ukrainian",
+        "ro" => "Romanian",
+        "hu" => "Hungarian",
+        "bg" => "Bulgarian",
+        "hr" => "Croatian",
+        "sk" => "Slovak",
+        "sl" => "Slovenian",
+        "et" => "Estonian",
+        "lv" => "Latvian",
+        "lt" => "Lithuanian",
+        "ms" => "Malay",
+        "fil" => "Filipino",
+        "bn" => "Bengali",
+        "ta" => "Tamil",
+        "te" => "Telugu",
+        other => other,
+    }
+}
+
+pub async fn cached_translation(
+    pool: &SqlitePool,
+    hash: &str,
+    language: &str,
+) -> Result<Option<String>, String> {
+    sqlx::query_scalar(
+        "SELECT translated FROM translation_cache WHERE content_hash = ? AND language = ?",
+    )
+    .bind(hash)
+    .bind(language)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to read translation cache: {}", e))
+}
+
+async fn store_translation(
+    pool: &SqlitePool,
+    hash: &str,
+    language: &str,
+    translated: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO translation_cache (content_hash, language, translated, created_at) \
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(hash)
+    .bind(language)
+    .bind(translated)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to write translation cache: {}", e))?;
+    Ok(())
+}
+
+/// Translate narrative text, serving repeats from the cache.
+///
+/// Uses an installed offline model when local translation is enabled and
+/// falls back to the remote provider otherwise. The story's glossary is
+/// passed to the remote model as constraints, substituted up front for
+/// offline models, and enforced on the result either way. Returns the
+/// translation and whether it came from the cache.
+pub async fn translate_narration(
+    pool: &SqlitePool,
+    local: &LocalTranslator,
+    story_id: &str,
+    text: &str,
+    language: &str,
+) -> Result<(String, bool), String> {
+    if language == "en" || text.trim().is_empty() {
+        return Ok((text.to_string(), true));
+    }
+
+    let glossary = load_glossary(pool, story_id, Some(language))
+        .await
+        .map_err(|e| format!("Failed to load glossary: {}", e))?;
+    let terms = relevant_terms(&glossary, text);
+
+    let hash = match terms.is_empty() {
+        true => content_hash(text),
+        false => content_hash(&format!("{}\u{1e}{}", text, fingerprint(&terms))),
+    };
+    if let Some(cached) = cached_translation(pool, &hash, language).await? {
+        return Ok((cached, true));
+    }
+
+    let settings = load_settings(pool).await?;
+    if settings.enabled {
+        if let Some(route) = local.route("en", language) {
+            let source = apply_terms(text, &terms);
+            match local.translate(&settings, &route, &source).await {
+                Ok(translated) => {
+                    let translated = apply_terms(&translated, &terms);
+                    store_translation(pool, &hash, language, &translated).await?;
+                    return Ok((translated, false));
+                }
+                Err(e) => eprintln!("Local translation failed, using remote provider: {}", e),
+            }
+        }
+    }
+
+    let llm = config::resolve_story_service(
+        pool,
+        Some(story_id),
+        "translation:narration",
+        "translation",
+    )
+    .await?;
+    let mut system = NARRATION_PROMPT.replace("{language}", language_name(language));
+    if !terms.is_empty() {
+        system.push_str(&prompt_block(&terms));
+    }
+    let reply = client::complete_logged(
+        pool,
+        "translation:narration",
+        Some(story_id),
+        &llm,
+        &[ChatMessage::system(system), ChatMessage::user(text)],
+    )
+    .await?;
+    let translated = apply_terms(reply.trim(), &terms);
+    if translated.is_empty() {
+        return Err("Translation model returned an empty reply".to_string());
+    }
+
+    store_translation(pool, &hash, language, &translated).await?;
+    Ok((translated, false))
+}
@@ -0,0 +1,136 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+/// A fixed rendering of a term in one target language
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryEntry {
+    pub id: String,
+    pub story_id: String,
+    pub language: String,
+    pub source_term: String,
+    pub target_term: String,
+    pub notes: Option<String>,
+    pub created_at: i64,
+}
+
+/// Input for creating or updating a glossary entry
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryInput {
+    pub id: Option<String>,
+    pub language: String,
+    pub source_term: String,
+    pub target_term: String,
+    pub notes: Option<String>,
+}
+
+/// A lorebook name or alias that has no glossary entry yet
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossarySuggestion {
+    pub term: String,
+    /// Lorebook entry type ('character', 'location', ...) the term came from
+    pub origin: String,
+}
+
+pub async fn load_glossary(
+    pool: &SqlitePool,
+    story_id: &str,
+    language: Option<&str>,
+) -> Result<Vec<GlossaryEntry>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, story_id, language, source_term, target_term, notes, created_at \
+         FROM translation_glossary WHERE story_id = ?1 AND (?2 IS NULL OR language = ?2) \
+         ORDER BY language, source_term",
+    )
+    .bind(story_id)
+    .bind(language)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| GlossaryEntry {
+            id: r.get("id"),
+            story_id: r.get("story_id"),
+            language: r.get("language"),
+            source_term: r.get("source_term"),
+            target_term: r.get("target_term"),
+            notes: r.get("notes"),
+            created_at: r.get("created_at"),
+        })
+        .collect())
+}
+
+fn term_pattern(term: &str) -> Option<Regex> {
+    RegexBuilder::new(&format!(r"\b{}\b", regex::escape(term)))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+/// Glossary entries whose source term appears in `text`, longest first so
+/// "Iron Keep" wins over "Keep"
+pub fn relevant_terms<'a>(glossary: &'a [GlossaryEntry], text: &str) -> Vec<&'a GlossaryEntry> {
+    let mut terms: Vec<&GlossaryEntry> = glossary
+        .iter()
+        .filter(|g| term_pattern(&g.source_term).is_some_and(|p| p.is_match(text)))
+        .collect();
+    terms.sort_by_key(|g| std::cmp::Reverse(g.source_term.len()));
+    terms
+}
+
+/// Stable description of the terms in play, folded into the cache key so
+/// glossary edits invalidate earlier translations
+pub fn fingerprint(terms: &[&GlossaryEntry]) -> String {
+    terms
+        .iter()
+        .map(|g| format!("{}={}", g.source_term.to_lowercase(), g.target_term))
+        .collect::<Vec<_>>()
+        .join("\u{1f}")
+}
+
+/// Constraint block appended to the translation model's instructions
+pub fn prompt_block(terms: &[&GlossaryEntry]) -> String {
+    let lines = terms
+        .iter()
+        .map(|g| match &g.notes {
+            Some(notes) if !notes.trim().is_empty() => {
+                format!("- {} → {} ({})", g.source_term, g.target_term, notes.trim())
+            }
+            _ => format!("- {} → {}", g.source_term, g.target_term),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "\n\nGlossary (always translate these terms exactly as given):\n{}",
+        lines
+    )
+}
+
+/// Replace every source term with its target rendering.
+///
+/// Used before offline translation, which cannot follow instructions, and
+/// after any translation to fix terms the model left untranslated.
+pub fn apply_terms(text: &str, terms: &[&GlossaryEntry]) -> String {
+    let mut result = text.to_string();
+    for term in terms {
+        // Skip terms whose rendering contains the source, or correct output
+        // would be substituted twice ("Keep" → "Keep Castle Castle")
+        if term
+            .target_term
+            .to_lowercase()
+            .contains(&term.source_term.to_lowercase())
+        {
+            continue;
+        }
+        if let Some(pattern) = term_pattern(&term.source_term) {
+            result = pattern
+                .replace_all(&result, NoExpand(&term.target_term))
+                .into_owned();
+        }
+    }
+    result
+}
@@ -0,0 +1,104 @@
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::engine::translate_narration;
+use super::local::LocalTranslator;
+use super::types::{TranslationEvent, TranslationPayload};
+use crate::jobs::types::{BackgroundJob, JobKind};
+use crate::jobs::JobQueue;
+
+/// Entries translated per job; larger backlogs continue in a follow-up job
+const BATCH_SIZE: i64 = 20;
+
+/// Background job: translate a batch of untranslated narration entries.
+///
+/// Each translation is saved and announced on `translation` as soon as it
+/// lands, so a failure part-way keeps earlier results and a retry only
+/// picks up what is still missing.
+pub async fn run_translation_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, String> {
+    let payload: TranslationPayload = serde_json::from_value(job.payload.clone())
+        .map_err(|e| format!("Invalid translation job payload: {}", e))?;
+    let entry_filter = payload
+        .entry_ids
+        .as_ref()
+        .map(|ids| serde_json::to_string(ids).unwrap_or_default());
+
+    let rows = sqlx::query(
+        "SELECT id, content FROM story_entries \
+         WHERE story_id = ?1 AND type = 'narration' AND TRIM(content) != '' \
+         AND (translated_content IS NULL OR translation_language IS NOT ?2) \
+         AND (?3 IS NULL OR id IN (SELECT value FROM json_each(?3))) \
+         ORDER BY position LIMIT ?4",
+    )
+    .bind(&payload.story_id)
+    .bind(&payload.language)
+    .bind(&entry_filter)
+    .bind(BATCH_SIZE + 1)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load entries: {}", e))?;
+
+    let local = app.state::<LocalTranslator>();
+    let has_more = rows.len() as i64 > BATCH_SIZE;
+    let mut translated = 0;
+    let mut cache_hits = 0;
+
+    for row in rows.iter().take(BATCH_SIZE as usize) {
+        let entry_id: String = row.get("id");
+        let content: String = row.get("content");
+        let (text, cached) =
+            translate_narration(pool, &local, &payload.story_id, &content, &payload.language)
+                .await?;
+
+        sqlx::query("UPDATE story_entries SET translated_content = ?, translation_language = ? WHERE id = ?")
+            .bind(&text)
+            .bind(&payload.language)
+            .bind(&entry_id)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to save translation: {}", e))?;
+
+        let event = TranslationEvent {
+            story_id: payload.story_id.clone(),
+            entry_id,
+            language: payload.language.clone(),
+            translated_content: text,
+        };
+        if let Err(e) = app.emit("translation", &event) {
+            eprintln!("Failed to emit translation event: {}", e);
+        }
+
+        translated += 1;
+        if cached {
+            cache_hits += 1;
+        }
+    }
+
+    let follow_up = if has_more {
+        let queue = app.state::<JobQueue>();
+        Some(
+            queue
+                .enqueue(
+                    pool,
+                    Some(&payload.story_id),
+                    JobKind::Translation,
+                    job.payload.clone(),
+                )
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "language": payload.language,
+        "translated": translated,
+        "cacheHits": cache_hits,
+        "followUpJobId": follow_up,
+    }))
+}
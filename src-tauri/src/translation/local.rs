@@ -0,0 +1,305 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::LazyLock;
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::llm::config::get_setting;
+use crate::models::types::{ModelFileSpec, ModelSpec};
+use crate::models::ModelManager;
+
+/// Model manager kind translation models are installed under, one per
+/// language pair
+pub const MODEL_KIND: &str = "translation";
+
+/// HTML and <pic> tags pass through local models untouched
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+/// User settings for offline translation (`local_translation_settings`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalTranslationSettings {
+    pub enabled: bool,
+    /// Path to a bergamot-translator command line binary
+    pub engine_path: Option<String>,
+    /// URL of a model index (see `ModelIndex`)
+    pub model_index_url: Option<String>,
+    pub threads: Option<u32>,
+}
+
+/// Downloadable model index: `{"models": [{"pair": "en-de", "files": [...]}]}`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ModelIndex {
+    pub models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEntry {
+    /// Source and target language codes, e.g. "en-de"
+    pub pair: String,
+    pub files: Vec<ModelFileSpec>,
+}
+
+/// A model from the index, with whether it is already installed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableModel {
+    pub pair: String,
+    pub size: u64,
+    pub installed: bool,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<LocalTranslationSettings, String> {
+    Ok(get_setting(pool, "local_translation_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Offline translation through downloaded bergamot models.
+///
+/// Each language pair is a model manager model of kind `translation`, living
+/// in `<models_dir>/<pair>/` with a generated `config.yml` the engine binary
+/// is pointed at.
+pub struct LocalTranslator {
+    models_dir: PathBuf,
+}
+
+impl LocalTranslator {
+    pub fn new(models_dir: PathBuf) -> Self {
+        Self { models_dir }
+    }
+
+    fn pair_dir(&self, pair: &str) -> PathBuf {
+        self.models_dir.join(pair)
+    }
+
+    pub fn installed_pairs(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.models_dir) else {
+            return Vec::new();
+        };
+        let mut pairs: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().join("config.yml").exists())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Pairs to chain for a translation, pivoting through English when no
+    /// direct model is installed
+    pub fn route(&self, source: &str, target: &str) -> Option<Vec<String>> {
+        let installed = self.installed_pairs();
+        let direct = format!("{}-{}", source, target);
+        if installed.contains(&direct) {
+            return Some(vec![direct]);
+        }
+        let first = format!("{}-en", source);
+        let second = format!("en-{}", target);
+        (source != "en"
+            && target != "en"
+            && installed.contains(&first)
+            && installed.contains(&second))
+        .then(|| vec![first, second])
+    }
+
+    /// Translate text, keeping tags and line structure intact
+    pub async fn translate(
+        &self,
+        settings: &LocalTranslationSettings,
+        route: &[String],
+        text: &str,
+    ) -> Result<String, String> {
+        let engine = settings
+            .engine_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .ok_or("No local translation engine configured")?;
+
+        // Split every line into tag and text segments; only text is translated
+        let mut segments: Vec<(bool, String)> = Vec::new();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                segments.push((false, "\n".to_string()));
+            }
+            let mut last = 0;
+            for tag in TAG.find_iter(line) {
+                segments.push((true, line[last..tag.start()].to_string()));
+                segments.push((false, tag.as_str().to_string()));
+                last = tag.end();
+            }
+            segments.push((true, line[last..].to_string()));
+        }
+
+        let mut sources: Vec<String> = segments
+            .iter()
+            .filter(|(translate, s)| *translate && !s.trim().is_empty())
+            .map(|(_, s)| s.clone())
+            .collect();
+        for pair in route {
+            sources = self
+                .run_engine(engine, settings.threads, pair, &sources)
+                .await?;
+        }
+
+        let mut translated = sources.into_iter();
+        Ok(segments
+            .into_iter()
+            .map(|(translate, s)| match translate && !s.trim().is_empty() {
+                true => translated.next().unwrap_or(s),
+                false => s,
+            })
+            .collect())
+    }
+
+    async fn run_engine(
+        &self,
+        engine: &str,
+        threads: Option<u32>,
+        pair: &str,
+        lines: &[String],
+    ) -> Result<Vec<String>, String> {
+        if lines.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let config = self.pair_dir(pair).join("config.yml");
+        let mut child = Command::new(engine)
+            .arg("--model-config-paths")
+            .arg(&config)
+            .arg("--cpu-threads")
+            .arg(threads.unwrap_or(2).to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start translation engine: {}", e))?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or("Translation engine has no stdin")?;
+        let mut stdout = child
+            .stdout
+            .take()
+            .ok_or("Translation engine has no stdout")?;
+        let input = lines
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // Write and read concurrently so a long input cannot fill both pipes
+        let write = async move {
+            let result = stdin.write_all(format!("{}\n", input).as_bytes()).await;
+            drop(stdin);
+            result
+        };
+        let mut output = String::new();
+        let (written, read) = tokio::join!(write, stdout.read_to_string(&mut output));
+        written.map_err(|e| format!("Failed to send text to translation engine: {}", e))?;
+        read.map_err(|e| format!("Failed to read translation engine output: {}", e))?;
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| format!("Translation engine failed: {}", e))?;
+        if !status.success() {
+            return Err(format!("Translation engine exited with {}", status));
+        }
+
+        let translated: Vec<String> = output.lines().map(String::from).collect();
+        if translated.len() != lines.len() {
+            return Err(format!(
+                "Translation engine returned {} lines for {} inputs",
+                translated.len(),
+                lines.len()
+            ));
+        }
+
+        // Keep the whitespace that surrounded each segment
+        Ok(lines
+            .iter()
+            .zip(translated)
+            .map(|(source, text)| {
+                let leading = &source[..source.len() - source.trim_start().len()];
+                let trailing = &source[source.trim_end().len()..];
+                format!("{}{}{}", leading, text.trim(), trailing)
+            })
+            .collect())
+    }
+
+    pub async fn fetch_index(settings: &LocalTranslationSettings) -> Result<ModelIndex, String> {
+        let url = settings
+            .model_index_url
+            .as_deref()
+            .filter(|u| !u.trim().is_empty())
+            .ok_or("No translation model index configured")?;
+        reqwest::get(url)
+            .await
+            .map_err(|e| format!("Failed to fetch model index: {}", e))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to fetch model index: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid model index: {}", e))
+    }
+
+    /// Download a pair's model through the model manager, which resumes
+    /// interrupted downloads and checks every file's checksum, then write
+    /// its engine config. Progress is emitted on `model-download-progress`.
+    pub async fn download(
+        &self,
+        app: &AppHandle,
+        models: &ModelManager,
+        model: &ModelEntry,
+    ) -> Result<(), String> {
+        let spec = ModelSpec {
+            kind: MODEL_KIND.to_string(),
+            id: model.pair.clone(),
+            name: Some(format!("Translation ({})", model.pair)),
+            files: model.files.clone(),
+        };
+        let installed = models.download(app, spec).await?;
+        write_engine_config(Path::new(&installed.path), &model.files)
+    }
+}
+
+/// Write the bergamot config that ties a pair's model, vocabulary, and
+/// shortlist files together
+fn write_engine_config(dir: &Path, files: &[ModelFileSpec]) -> Result<(), String> {
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let find =
+        |pred: &dyn Fn(&str) -> bool| files.iter().map(|f| f.name.as_str()).find(|n| pred(n));
+
+    let model = find(&|n| n.starts_with("model") && n.ends_with(".bin"))
+        .ok_or("Model index entry has no model file")?;
+    let src_vocab = find(&|n| n.starts_with("srcvocab") || n.starts_with("vocab"))
+        .ok_or("Model index entry has no vocabulary file")?;
+    let trg_vocab = find(&|n| n.starts_with("trgvocab")).unwrap_or(src_vocab);
+
+    let mut config = format!(
+        "models:\n  - {}\nvocabs:\n  - {}\n  - {}\n",
+        path(model),
+        path(src_vocab),
+        path(trg_vocab)
+    );
+    if let Some(lex) = find(&|n| n.starts_with("lex")) {
+        config.push_str(&format!("shortlist:\n  - {}\n  - false\n", path(lex)));
+    }
+    config.push_str(
+        "beam-size: 1\nnormalize: 1.0\nword-penalty: 0\nmax-length-break: 128\nmini-batch-words: 1024\n\
+         workspace: 128\nmax-length-factor: 2.0\nskip-cost: true\nquiet: true\nquiet-translation: true\n\
+         gemm-precision: int8shiftAlphaAll\n",
+    );
+
+    std::fs::write(dir.join("config.yml"), config)
+        .map_err(|e| format!("Failed to write model config: {}", e))
+}
@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod engine;
+pub mod glossary;
+pub mod job;
+pub mod local;
+pub mod types;
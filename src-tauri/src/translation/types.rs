@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Payload of a translation job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationPayload {
+    pub story_id: String,
+    pub language: String,
+    /// Limit the job to these entries; all untranslated narration when absent
+    #[serde(default)]
+    pub entry_ids: Option<Vec<String>>,
+}
+
+/// Emitted on `translation` as each entry's translation is saved
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationEvent {
+    pub story_id: String,
+    pub entry_id: String,
+    pub language: String,
+    pub translated_content: String,
+}
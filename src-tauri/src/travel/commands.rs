@@ -0,0 +1,132 @@
+use serde_json::json;
+use tauri::State;
+
+use super::store::{self, load_graph, load_routes};
+use super::types::{LocationRoute, PartyPosition, TravelEstimate, TravelWarning};
+use crate::calendar::commands::load_calendar;
+use crate::calendar::parser::parse_duration;
+use crate::context::branch::visible_entries;
+use crate::db::DbState;
+use crate::jobs::types::JobKind;
+use crate::jobs::JobQueue;
+
+#[tauri::command]
+pub async fn get_location_routes(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<LocationRoute>, String> {
+    load_routes(db.pool(), &story_id).await
+}
+
+/// Add a route between two locations, or change its travel time.
+/// `travel_time` is a duration in the story's calendar ("3 hours", "2 days").
+#[tauri::command]
+pub async fn set_location_route(
+    db: State<'_, DbState>,
+    story_id: String,
+    from: String,
+    to: String,
+    travel_time: String,
+    notes: Option<String>,
+) -> Result<(), String> {
+    let pool = db.pool();
+    let calendar = load_calendar(pool, &story_id).await?;
+    let minutes = parse_duration(&calendar, &travel_time)?;
+    store::save_route(pool, &story_id, &from, &to, minutes, notes.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn delete_location_route(db: State<'_, DbState>, route_id: String) -> Result<(), String> {
+    sqlx::query("DELETE FROM location_routes WHERE id = ?")
+        .bind(&route_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to delete route: {}", e))?;
+    Ok(())
+}
+
+/// The quickest known way between two locations, if they're connected
+#[tauri::command]
+pub async fn get_travel_time(
+    db: State<'_, DbState>,
+    story_id: String,
+    from: String,
+    to: String,
+) -> Result<Option<TravelEstimate>, String> {
+    let pool = db.pool();
+    let Some((minutes, path)) = load_graph(pool, &story_id).await?.shortest(&from, &to) else {
+        return Ok(None);
+    };
+    let calendar = load_calendar(pool, &story_id).await?;
+    Ok(Some(TravelEstimate {
+        minutes,
+        formatted: calendar.format_duration(minutes),
+        path,
+    }))
+}
+
+#[tauri::command]
+pub async fn get_party_location(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Option<PartyPosition>, String> {
+    store::current_position(db.pool(), &story_id).await
+}
+
+/// Put the party somewhere by hand, as of the latest entry
+#[tauri::command]
+pub async fn set_party_location(
+    db: State<'_, DbState>,
+    story_id: String,
+    location_name: String,
+) -> Result<PartyPosition, String> {
+    let pool = db.pool();
+    if location_name.trim().is_empty() {
+        return Err("Location name cannot be empty".to_string());
+    }
+    let entry = visible_entries(pool, &story_id)
+        .await?
+        .pop()
+        .ok_or("This story has no entries yet")?;
+    let now = store::story_minutes(pool, &story_id).await?;
+    store::record_position(pool, &story_id, &entry.id, &location_name, now).await
+}
+
+#[tauri::command]
+pub async fn get_travel_warnings(
+    db: State<'_, DbState>,
+    story_id: String,
+) -> Result<Vec<TravelWarning>, String> {
+    store::load_warnings(db.pool(), &story_id).await
+}
+
+#[tauri::command]
+pub async fn dismiss_travel_warning(
+    db: State<'_, DbState>,
+    warning_id: String,
+) -> Result<(), String> {
+    sqlx::query("UPDATE travel_warnings SET dismissed = 1 WHERE id = ?")
+        .bind(&warning_id)
+        .execute(db.pool())
+        .await
+        .map_err(|e| format!("Failed to dismiss travel warning: {}", e))?;
+    Ok(())
+}
+
+/// Queue background extraction of the party's location from an entry
+#[tauri::command]
+pub async fn queue_location_update(
+    db: State<'_, DbState>,
+    queue: State<'_, JobQueue>,
+    story_id: String,
+    entry_id: String,
+) -> Result<String, String> {
+    queue
+        .enqueue(
+            db.pool(),
+            Some(&story_id),
+            JobKind::LocationUpdate,
+            json!({ "entryId": entry_id }),
+        )
+        .await
+}
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Row, SqlitePool};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::store::{
+    current_position, known_locations, load_graph, load_settings, record_position, save_warning,
+    story_minutes,
+};
+use super::types::TravelWarning;
+use crate::db::now_millis;
+use crate::jobs::types::{BackgroundJob, JobError, JobKind};
+use crate::llm::client::ChatMessage;
+use crate::llm::config;
+use crate::llm::structured::{complete_structured, OutputSchema};
+
+const SYSTEM_PROMPT: &str = "You track where the player's party is in an interactive story. \
+Given the known locations, where the party was before and the latest passage, name the location \
+the party is at by the end of the passage. Prefer a known location's exact name; name a new place \
+only when the party clearly arrives somewhere not on the list. Respond with JSON only, in this \
+shape: {\"location\": \"<name>\"}. Use an empty location when the passage doesn't show where \
+the party is.";
+
+/// How long to wait for the entry's time update before checking travel
+const TIME_UPDATE_WAIT: Duration = Duration::from_secs(5);
+
+fn reply_schema() -> OutputSchema {
+    OutputSchema {
+        name: "location_update",
+        schema: json!({
+            "type": "object",
+            "required": ["location"],
+            "properties": {
+                "location": { "type": "string" }
+            }
+        }),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LocationReply {
+    location: String,
+}
+
+/// Background job: ask the classification model where the party ends up
+/// after an entry and record it. A move faster than the location graph
+/// allows, given the story time that passed, is stored and emitted on
+/// `travel-warning`. The position is emitted on `party-location`.
+pub async fn run_location_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &BackgroundJob,
+) -> Result<serde_json::Value, JobError> {
+    let entry_id = job
+        .payload
+        .get("entryId")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JobError::Failed("Location update job is missing entryId".to_string()))?;
+
+    // The elapsed time only counts once the entry's own time update is in
+    let waiting: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM background_jobs WHERE kind = ? \
+         AND status IN ('pending', 'running') AND json_extract(payload, '$.entryId') = ?)",
+    )
+    .bind(JobKind::TimeUpdate.as_str())
+    .bind(entry_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| JobError::Failed(format!("Failed to load jobs: {}", e)))?;
+    if waiting {
+        return Err(JobError::Deferred {
+            delay: TIME_UPDATE_WAIT,
+            reason: "Waiting for the entry's time update".to_string(),
+        });
+    }
+
+    update_location(app, pool, entry_id)
+        .await
+        .map_err(JobError::Failed)
+}
+
+async fn update_location(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<serde_json::Value, String> {
+    let row = sqlx::query("SELECT story_id, content FROM story_entries WHERE id = ?")
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load entry: {}", e))?
+        .ok_or_else(|| format!("Entry not found: {}", entry_id))?;
+    let story_id: String = row.get("story_id");
+    let content: String = row.get("content");
+
+    let known = known_locations(pool, &story_id).await?;
+    let previous = current_position(pool, &story_id)
+        .await?
+        .filter(|p| p.entry_id != entry_id);
+    let prompt = format!(
+        "Known locations: {}\n\nParty was at: {}\n\nPassage:\n{}",
+        if known.is_empty() {
+            "(none yet)".to_string()
+        } else {
+            known.join(", ")
+        },
+        previous
+            .as_ref()
+            .map_or("(unknown)", |p| p.location_name.as_str()),
+        content
+    );
+
+    let llm = config::resolve_service(pool, "locationUpdate", "classification").await?;
+    let reply: LocationReply = complete_structured(
+        pool,
+        "locationUpdate",
+        Some(&story_id),
+        &llm,
+        &[
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(prompt),
+        ],
+        &reply_schema(),
+    )
+    .await?;
+
+    // Stay put when the passage doesn't say
+    let reported = reply.location.trim();
+    let location = match known.iter().find(|k| k.eq_ignore_ascii_case(reported)) {
+        Some(name) => name.clone(),
+        None if !reported.is_empty() => reported.to_string(),
+        None => match &previous {
+            Some(p) => p.location_name.clone(),
+            None => return Ok(json!({ "entryId": entry_id, "location": null })),
+        },
+    };
+
+    let now = story_minutes(pool, &story_id).await?;
+    let position = record_position(pool, &story_id, entry_id, &location, now).await?;
+    if let Err(e) = app.emit("party-location", &position) {
+        eprintln!("Failed to emit party location event: {}", e);
+    }
+
+    let mut warning = None;
+    if let Some(previous) = previous.filter(|p| !p.location_name.eq_ignore_ascii_case(&location)) {
+        let graph = load_graph(pool, &story_id).await?;
+        let settings = load_settings(pool).await?;
+        let elapsed = now - previous.story_minutes;
+        if let Some((required, _)) = graph.shortest(&previous.location_name, &location) {
+            if (elapsed as f64) < required as f64 * settings.tolerance {
+                let found = TravelWarning {
+                    id: Uuid::new_v4().to_string(),
+                    story_id: story_id.clone(),
+                    entry_id: entry_id.to_string(),
+                    from_name: previous.location_name,
+                    to_name: location.clone(),
+                    elapsed_minutes: elapsed,
+                    required_minutes: required,
+                    dismissed: false,
+                    created_at: now_millis(),
+                };
+                save_warning(pool, &found).await?;
+                if let Err(e) = app.emit("travel-warning", &found) {
+                    eprintln!("Failed to emit travel warning event: {}", e);
+                }
+                warning = Some(found);
+            }
+        }
+    }
+    Ok(json!({ "entryId": entry_id, "location": location, "warning": warning }))
+}
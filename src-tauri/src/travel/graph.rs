@@ -0,0 +1,97 @@
+//! Shortest travel times over a story's routes. Locations are matched by
+//! name, ignoring case.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::types::LocationRoute;
+
+/// Routes as an adjacency list keyed by lowercase name, plus the name each
+/// key is displayed with
+pub struct LocationGraph {
+    edges: HashMap<String, Vec<(String, i64)>>,
+    names: HashMap<String, String>,
+}
+
+impl LocationGraph {
+    pub fn new(routes: &[LocationRoute]) -> Self {
+        let mut graph = Self {
+            edges: HashMap::new(),
+            names: HashMap::new(),
+        };
+        for route in routes {
+            let from = graph.add_name(&route.from_name);
+            let to = graph.add_name(&route.to_name);
+            let minutes = route.travel_minutes.max(0);
+            graph
+                .edges
+                .entry(from.clone())
+                .or_default()
+                .push((to.clone(), minutes));
+            graph.edges.entry(to).or_default().push((from, minutes));
+        }
+        graph
+    }
+
+    fn add_name(&mut self, name: &str) -> String {
+        let key = name.trim().to_lowercase();
+        self.names
+            .entry(key.clone())
+            .or_insert_with(|| name.trim().to_string());
+        key
+    }
+
+    /// Quickest travel time from `from` to every location it reaches, with
+    /// the step each was reached from
+    fn explore(&self, from: &str) -> HashMap<String, (i64, Option<String>)> {
+        let start = from.trim().to_lowercase();
+        let mut best: HashMap<String, (i64, Option<String>)> = HashMap::new();
+        if !self.edges.contains_key(&start) {
+            return best;
+        }
+        let mut heap = BinaryHeap::new();
+        best.insert(start.clone(), (0, None));
+        heap.push(Reverse((0, start)));
+        while let Some(Reverse((cost, node))) = heap.pop() {
+            if best.get(&node).is_some_and(|(known, _)| cost > *known) {
+                continue;
+            }
+            for (next, minutes) in self.edges.get(&node).into_iter().flatten() {
+                let total = cost + minutes;
+                if best.get(next).is_none_or(|(known, _)| total < *known) {
+                    best.insert(next.clone(), (total, Some(node.clone())));
+                    heap.push(Reverse((total, next.clone())));
+                }
+            }
+        }
+        best
+    }
+
+    /// Quickest way between two locations: minutes and the display names
+    /// along the way. `None` when either is unknown or they aren't connected.
+    pub fn shortest(&self, from: &str, to: &str) -> Option<(i64, Vec<String>)> {
+        let best = self.explore(from);
+        let mut node = to.trim().to_lowercase();
+        let (minutes, _) = *best.get(&node)?;
+        let mut path = vec![self.names[&node].clone()];
+        while let Some((_, Some(previous))) = best.get(&node) {
+            node = previous.clone();
+            path.push(self.names[&node].clone());
+        }
+        path.reverse();
+        Some((minutes, path))
+    }
+
+    /// Locations reachable from `from`, nearest first, without `from` itself
+    pub fn nearest(&self, from: &str) -> Vec<(String, i64)> {
+        let start = from.trim().to_lowercase();
+        let mut reachable: Vec<(String, i64)> = self
+            .explore(from)
+            .into_iter()
+            .filter(|(key, _)| *key != start)
+            .map(|(key, (minutes, _))| (self.names[&key].clone(), minutes))
+            .collect();
+        reachable.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        reachable
+    }
+}
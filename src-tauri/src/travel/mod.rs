@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod extract;
+pub mod graph;
+pub mod store;
+pub mod types;
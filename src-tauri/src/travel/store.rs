@@ -0,0 +1,261 @@
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::graph::LocationGraph;
+use super::types::{LocationRoute, PartyPosition, TravelSettings, TravelWarning};
+use crate::calendar::commands::{load_calendar, load_time_tracker};
+use crate::context::branch::visible_entries;
+use crate::db::now_millis;
+use crate::llm::config::get_setting;
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<TravelSettings, String> {
+    Ok(get_setting(pool, "travel_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+pub async fn load_routes(pool: &SqlitePool, story_id: &str) -> Result<Vec<LocationRoute>, String> {
+    sqlx::query_as(
+        "SELECT id, story_id, from_name, to_name, travel_minutes, notes, created_at, updated_at \
+         FROM location_routes WHERE story_id = ? ORDER BY from_name, to_name",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load routes: {}", e))
+}
+
+pub async fn load_graph(pool: &SqlitePool, story_id: &str) -> Result<LocationGraph, String> {
+    Ok(LocationGraph::new(&load_routes(pool, story_id).await?))
+}
+
+/// Add a route or change the travel time of the one between the same pair
+pub async fn save_route(
+    pool: &SqlitePool,
+    story_id: &str,
+    from: &str,
+    to: &str,
+    travel_minutes: i64,
+    notes: Option<&str>,
+) -> Result<(), String> {
+    let (from, to) = (from.trim(), to.trim());
+    if from.is_empty() || to.is_empty() {
+        return Err("A route needs a location at both ends".to_string());
+    }
+    if from.eq_ignore_ascii_case(to) {
+        return Err("A route must join two different locations".to_string());
+    }
+    if travel_minutes < 0 {
+        return Err("Travel time cannot be negative".to_string());
+    }
+    let (from, to) = if from.to_lowercase() <= to.to_lowercase() {
+        (from, to)
+    } else {
+        (to, from)
+    };
+    let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+
+    let now = now_millis();
+    let existing: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM location_routes WHERE story_id = ? \
+         AND from_name = ? COLLATE NOCASE AND to_name = ? COLLATE NOCASE",
+    )
+    .bind(story_id)
+    .bind(from)
+    .bind(to)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load route: {}", e))?;
+    match existing {
+        Some(id) => sqlx::query(
+            "UPDATE location_routes SET from_name = ?, to_name = ?, travel_minutes = ?, \
+             notes = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(from)
+        .bind(to)
+        .bind(travel_minutes)
+        .bind(notes)
+        .bind(now)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update route: {}", e))?,
+        None => sqlx::query(
+            "INSERT INTO location_routes \
+             (id, story_id, from_name, to_name, travel_minutes, notes, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(story_id)
+        .bind(from)
+        .bind(to)
+        .bind(travel_minutes)
+        .bind(notes)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to create route: {}", e))?,
+    };
+    Ok(())
+}
+
+/// Whether the story has anywhere to travel: locations or routes
+pub async fn has_locations(pool: &SqlitePool, story_id: &str) -> Result<bool, String> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM locations WHERE story_id = ?1 AND deleted = 0) \
+         OR EXISTS(SELECT 1 FROM location_routes WHERE story_id = ?1)",
+    )
+    .bind(story_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to load locations: {}", e))
+}
+
+/// Location names the story knows of, from its locations and its routes
+pub async fn known_locations(pool: &SqlitePool, story_id: &str) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM locations WHERE story_id = ?1 AND deleted = 0 \
+         UNION SELECT from_name FROM location_routes WHERE story_id = ?1 \
+         UNION SELECT to_name FROM location_routes WHERE story_id = ?1",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load locations: {}", e))?;
+    names.sort_by_key(|n| n.to_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(names)
+}
+
+/// The party's latest position on the current branch
+pub async fn current_position(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Option<PartyPosition>, String> {
+    let positions: Vec<PartyPosition> = sqlx::query_as(
+        "SELECT entry_id, story_id, location_name, story_minutes, created_at \
+         FROM party_positions WHERE story_id = ?",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load party positions: {}", e))?;
+    if positions.is_empty() {
+        return Ok(None);
+    }
+    let order: HashMap<String, usize> = visible_entries(pool, story_id)
+        .await?
+        .into_iter()
+        .enumerate()
+        .map(|(index, e)| (e.id, index))
+        .collect();
+    Ok(positions
+        .into_iter()
+        .filter_map(|p| Some((*order.get(&p.entry_id)?, p)))
+        .max_by_key(|(index, _)| *index)
+        .map(|(_, p)| p))
+}
+
+pub async fn record_position(
+    pool: &SqlitePool,
+    story_id: &str,
+    entry_id: &str,
+    location_name: &str,
+    story_minutes: i64,
+) -> Result<PartyPosition, String> {
+    let position = PartyPosition {
+        entry_id: entry_id.to_string(),
+        story_id: story_id.to_string(),
+        location_name: location_name.trim().to_string(),
+        story_minutes,
+        created_at: now_millis(),
+    };
+    sqlx::query(
+        "INSERT INTO party_positions (entry_id, story_id, location_name, story_minutes, created_at) \
+         VALUES (?, ?, ?, ?, ?) ON CONFLICT(entry_id) DO UPDATE SET \
+         location_name = excluded.location_name, story_minutes = excluded.story_minutes, \
+         created_at = excluded.created_at",
+    )
+    .bind(&position.entry_id)
+    .bind(&position.story_id)
+    .bind(&position.location_name)
+    .bind(position.story_minutes)
+    .bind(position.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save party position: {}", e))?;
+    Ok(position)
+}
+
+/// The story clock in minutes since the start
+pub async fn story_minutes(pool: &SqlitePool, story_id: &str) -> Result<i64, String> {
+    let calendar = load_calendar(pool, story_id).await?;
+    Ok(calendar.minutes_since_start(&load_time_tracker(pool, story_id).await?))
+}
+
+pub async fn save_warning(pool: &SqlitePool, warning: &TravelWarning) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO travel_warnings (id, story_id, entry_id, from_name, to_name, \
+         elapsed_minutes, required_minutes, dismissed, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?)",
+    )
+    .bind(&warning.id)
+    .bind(&warning.story_id)
+    .bind(&warning.entry_id)
+    .bind(&warning.from_name)
+    .bind(&warning.to_name)
+    .bind(warning.elapsed_minutes)
+    .bind(warning.required_minutes)
+    .bind(warning.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save travel warning: {}", e))?;
+    Ok(())
+}
+
+/// Warnings not dismissed yet, newest first
+pub async fn load_warnings(
+    pool: &SqlitePool,
+    story_id: &str,
+) -> Result<Vec<TravelWarning>, String> {
+    sqlx::query_as(
+        "SELECT id, story_id, entry_id, from_name, to_name, elapsed_minutes, required_minutes, \
+         dismissed, created_at FROM travel_warnings WHERE story_id = ? AND dismissed = 0 \
+         ORDER BY created_at DESC",
+    )
+    .bind(story_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load travel warnings: {}", e))
+}
+
+/// Travel times from the party's location for prompt injection, when the
+/// user wants them and the party's location is on the graph
+pub async fn travel_hints(pool: &SqlitePool, story_id: &str) -> Result<Option<String>, String> {
+    let settings = load_settings(pool).await?;
+    if !settings.hints_in_context {
+        return Ok(None);
+    }
+    let Some(position) = current_position(pool, story_id).await? else {
+        return Ok(None);
+    };
+    let graph = load_graph(pool, story_id).await?;
+    let nearest = graph.nearest(&position.location_name);
+    if nearest.is_empty() {
+        return Ok(None);
+    }
+    let calendar = load_calendar(pool, story_id).await?;
+    let destinations = nearest
+        .into_iter()
+        .take(settings.max_hints)
+        .map(|(name, minutes)| format!("{} ({})", name, calendar.format_duration(minutes)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(Some(format!(
+        "The party is at {}. Travel times from here: {}",
+        position.location_name, destinations
+    )))
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// A way between two locations, usable in both directions
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationRoute {
+    pub id: String,
+    pub story_id: String,
+    pub from_name: String,
+    pub to_name: String,
+    pub travel_minutes: i64,
+    pub notes: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Where the party was at the end of an entry
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PartyPosition {
+    pub entry_id: String,
+    pub story_id: String,
+    pub location_name: String,
+    /// Story clock, in minutes since the start, when the position was taken
+    pub story_minutes: i64,
+    pub created_at: i64,
+}
+
+/// The quickest known way between two locations
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TravelEstimate {
+    pub minutes: i64,
+    /// The duration in calendar terms
+    pub formatted: String,
+    /// Locations passed through, both ends included
+    pub path: Vec<String>,
+}
+
+/// A move the story made faster than the location graph allows. Emitted on
+/// `travel-warning`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TravelWarning {
+    pub id: String,
+    pub story_id: String,
+    pub entry_id: String,
+    pub from_name: String,
+    pub to_name: String,
+    pub elapsed_minutes: i64,
+    pub required_minutes: i64,
+    pub dismissed: bool,
+    pub created_at: i64,
+}
+
+/// User settings for travel validation (`travel_settings`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TravelSettings {
+    /// Add travel times from the party's location to the context
+    pub hints_in_context: bool,
+    /// Most destinations listed in the hints, nearest first
+    pub max_hints: usize,
+    /// Warn when a move takes less than this share of the route's travel time
+    pub tolerance: f64,
+}
+
+impl Default for TravelSettings {
+    fn default() -> Self {
+        Self {
+            hints_in_context: false,
+            max_hints: 6,
+            tolerance: 0.5,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::DbState;
+use crate::llm::config::get_setting;
+
+/// Tray options, stored in `tray_settings`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TraySettings {
+    /// Closing the window hides it to the tray, so the sync server and
+    /// background jobs keep running
+    pub close_to_tray: bool,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<TraySettings, String> {
+    Ok(get_setting(pool, "tray_settings")
+        .await?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Read from the window close handler, which can't wait on the database
+#[derive(Default)]
+pub struct TrayState {
+    close_to_tray: AtomicBool,
+}
+
+impl TrayState {
+    pub fn close_to_tray(&self) -> bool {
+        self.close_to_tray.load(Ordering::Relaxed)
+    }
+}
+
+/// Apply the saved tray settings once the settings table exists
+pub fn restore(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbState>().pool().clone();
+        let settings = loop {
+            match load_settings(&pool).await {
+                Ok(settings) => break settings,
+                Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        };
+        app.state::<TrayState>()
+            .close_to_tray
+            .store(settings.close_to_tray, Ordering::Relaxed);
+    });
+}
+
+/// Apply `closeToTray` right away; the frontend keeps it in `tray_settings`
+#[tauri::command]
+pub fn set_close_to_tray(state: State<'_, TrayState>, enabled: bool) {
+    state.close_to_tray.store(enabled, Ordering::Relaxed);
+}
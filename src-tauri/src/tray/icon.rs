@@ -0,0 +1,190 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Emitter, Manager, Wry};
+
+use crate::db::DbState;
+use crate::sync::commands::stop_sync_server;
+use crate::sync::SyncState;
+
+const TRAY_ID: &str = "main";
+const STATUS_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Payload of the `tray-action` event, for actions the frontend carries out
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+enum TrayAction {
+    /// Serving stories needs their exports, which the frontend builds
+    StartSync,
+    #[serde(rename_all = "camelCase")]
+    OpenStory { story_id: String },
+}
+
+/// Menu items whose text follows the app's state
+struct StatusItems {
+    sync: MenuItem<Wry>,
+    jobs: MenuItem<Wry>,
+    sync_toggle: MenuItem<Wry>,
+}
+
+fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn emit_action(app: &AppHandle, action: TrayAction) {
+    show_window(app);
+    if let Err(e) = app.emit("tray-action", &action) {
+        eprintln!("Failed to emit tray-action: {}", e);
+    }
+}
+
+async fn last_story_id(pool: &SqlitePool) -> Option<String> {
+    sqlx::query_scalar("SELECT id FROM stories ORDER BY updated_at DESC LIMIT 1")
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+async fn pending_jobs(pool: &SqlitePool) -> i64 {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM background_jobs WHERE status IN ('pending', 'running')",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(0)
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "show" => show_window(app),
+        "sync-toggle" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if app.state::<SyncState>().is_running().await {
+                    if let Err(e) = stop_sync_server(app.clone(), app.state()).await {
+                        eprintln!("{}", e);
+                    }
+                } else {
+                    emit_action(&app, TrayAction::StartSync);
+                }
+            });
+        }
+        "open-last-story" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let pool = app.state::<DbState>().pool().clone();
+                match last_story_id(&pool).await {
+                    Some(story_id) => emit_action(&app, TrayAction::OpenStory { story_id }),
+                    None => show_window(&app),
+                }
+            });
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Keep the status lines, toggle label and tooltip current
+async fn refresh_status(app: AppHandle, items: StatusItems) {
+    loop {
+        let pool = app.state::<DbState>().pool().clone();
+        let syncing = app.state::<SyncState>().is_running().await;
+        let jobs = pending_jobs(&pool).await;
+
+        let sync_text = match syncing {
+            true => "Sync server running",
+            false => "Sync server stopped",
+        };
+        let jobs_text = match jobs {
+            0 => "No background jobs".to_string(),
+            1 => "1 background job pending".to_string(),
+            n => format!("{} background jobs pending", n),
+        };
+        let _ = items.sync.set_text(sync_text);
+        let _ = items.jobs.set_text(&jobs_text);
+        let _ = items.sync_toggle.set_text(match syncing {
+            true => "Stop sync server",
+            false => "Start sync server",
+        });
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let _ = tray.set_tooltip(Some(format!("Aventuras\n{}\n{}", sync_text, jobs_text)));
+        }
+        tokio::time::sleep(STATUS_INTERVAL).await;
+    }
+}
+
+/// Add the tray icon with its status lines and quick actions
+pub fn create(app: &App) -> tauri::Result<()> {
+    let items = StatusItems {
+        sync: MenuItem::with_id(
+            app,
+            "sync-status",
+            "Sync server stopped",
+            false,
+            None::<&str>,
+        )?,
+        jobs: MenuItem::with_id(
+            app,
+            "jobs-status",
+            "No background jobs",
+            false,
+            None::<&str>,
+        )?,
+        sync_toggle: MenuItem::with_id(
+            app,
+            "sync-toggle",
+            "Start sync server",
+            true,
+            None::<&str>,
+        )?,
+    };
+    let menu = Menu::with_items(
+        app,
+        &[
+            &items.sync,
+            &items.jobs,
+            &PredefinedMenuItem::separator(app)?,
+            &items.sync_toggle,
+            &MenuItem::with_id(
+                app,
+                "open-last-story",
+                "Open last story",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(app, "show", "Show Aventuras", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Aventuras")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    tauri::async_runtime::spawn(refresh_status(app.handle().clone(), items));
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+#[cfg(desktop)]
+pub mod icon;
+
+pub use commands::TrayState;
@@ -70,6 +70,7 @@ export const runtimeSamples: Record<string, string> = {
   // Style Reviewer
   passageCount: '5',
   passages: '[Formatted passages for style review...]',
+  styleMetrics: '- Flesch reading ease 72, grade level 6.8\n- Dialogue: 31% of text',
 
   // Lore Management
   entrySummary: '[Summary of lorebook entries...]',
@@ -50,6 +50,8 @@ export interface RelevantEntry {
   description: string | null
   tier: 1 | 2 | 3
   priority: number
+  /** Why the entry was selected, e.g. 'current location' or 'matched: Mira' */
+  matchReason?: string
   metadata?: Record<string, any>
 }
 
@@ -58,6 +60,8 @@ export interface ContextResult {
   tier2: RelevantEntry[]
   tier3: RelevantEntry[]
   all: RelevantEntry[]
+  /** Entries that qualified but were cut by the per-tier limit */
+  dropped: RelevantEntry[]
   contextBlock: string
 }
 
@@ -93,15 +97,17 @@ export class EntryInjector extends BaseAIService {
       recentEntriesCount: recentEntries.length,
     })
 
+    const dropped: RelevantEntry[] = []
+
     // Tier 1: Always inject - state-based entries
-    const tier1 = this.getTier1Entries(worldState)
+    const tier1 = this.getTier1Entries(worldState, dropped)
     log('Tier 1 entries:', tier1.length)
 
     // Get IDs already in tier 1 to avoid duplicates
     const tier1Ids = new Set(tier1.map((e) => e.id))
 
     // Tier 2: Name matching - fuzzy match against input and recent messages
-    const tier2 = this.getTier2Entries(worldState, userInput, recentEntries, tier1Ids)
+    const tier2 = this.getTier2Entries(worldState, userInput, recentEntries, tier1Ids, dropped)
     log('Tier 2 entries:', tier2.length)
 
     // Get IDs in tier 1 + 2
@@ -116,7 +122,7 @@ export class EntryInjector extends BaseAIService {
         remainingEntries: remainingCount,
         threshold: this.config.llmThreshold,
       })
-      tier3 = await this.getTier3Entries(worldState, userInput, recentEntries, tier12Ids)
+      tier3 = await this.getTier3Entries(worldState, userInput, recentEntries, tier12Ids, dropped)
       log('Tier 3 entries:', tier3.length)
     }
 
@@ -126,7 +132,15 @@ export class EntryInjector extends BaseAIService {
     // Build the context block
     const contextBlock = this.buildContextBlock(tier1, tier2, tier3, retrievedChapterContext)
 
-    return { tier1, tier2, tier3, all, contextBlock }
+    return { tier1, tier2, tier3, all, dropped, contextBlock }
+  }
+
+  /**
+   * Apply the per-tier limit, recording whatever didn't fit.
+   */
+  private limit(entries: RelevantEntry[], dropped: RelevantEntry[]): RelevantEntry[] {
+    dropped.push(...entries.slice(this.config.maxEntriesPerTier))
+    return entries.slice(0, this.config.maxEntriesPerTier)
   }
 
   /**
@@ -136,7 +150,7 @@ export class EntryInjector extends BaseAIService {
    * - Inventory items
    * - Active story beats/quests
    */
-  private getTier1Entries(worldState: WorldState): RelevantEntry[] {
+  private getTier1Entries(worldState: WorldState, dropped: RelevantEntry[]): RelevantEntry[] {
     const entries: RelevantEntry[] = []
 
     // Current location
@@ -148,6 +162,7 @@ export class EntryInjector extends BaseAIService {
         description: worldState.currentLocation.description,
         tier: 1,
         priority: 100,
+        matchReason: 'current location',
         metadata: { current: true },
       })
     }
@@ -156,54 +171,66 @@ export class EntryInjector extends BaseAIService {
     const activeChars = worldState.characters.filter(
       (c) => c.status === 'active' && c.relationship !== 'self',
     )
-    for (const char of activeChars.slice(0, this.config.maxEntriesPerTier)) {
-      entries.push({
-        type: 'character',
-        id: char.id,
-        name: char.name,
-        description: char.description,
-        tier: 1,
-        priority: 90,
-        metadata: {
-          relationship: char.relationship,
-          traits: char.traits,
-          visualDescriptors: char.visualDescriptors,
-        },
-      })
-    }
+    entries.push(
+      ...this.limit(
+        activeChars.map((char) => ({
+          type: 'character' as const,
+          id: char.id,
+          name: char.name,
+          description: char.description,
+          tier: 1 as const,
+          priority: 90,
+          matchReason: 'present character',
+          metadata: {
+            relationship: char.relationship,
+            traits: char.traits,
+            visualDescriptors: char.visualDescriptors,
+          },
+        })),
+        dropped,
+      ),
+    )
 
     // Inventory items
     const inventoryItems = worldState.items.filter((i) => i.location === 'inventory')
-    for (const item of inventoryItems.slice(0, this.config.maxEntriesPerTier)) {
-      entries.push({
-        type: 'item',
-        id: item.id,
-        name: item.name,
-        description: item.description,
-        tier: 1,
-        priority: 70,
-        metadata: {
-          quantity: item.quantity,
-          equipped: item.equipped,
-        },
-      })
-    }
+    entries.push(
+      ...this.limit(
+        inventoryItems.map((item) => ({
+          type: 'item' as const,
+          id: item.id,
+          name: item.name,
+          description: item.description,
+          tier: 1 as const,
+          priority: 70,
+          matchReason: 'in inventory',
+          metadata: {
+            quantity: item.quantity,
+            equipped: item.equipped,
+          },
+        })),
+        dropped,
+      ),
+    )
 
     // Active story beats/quests
     const activeBeats = worldState.storyBeats.filter(
       (b) => b.status === 'active' || b.status === 'pending',
     )
-    for (const beat of activeBeats.slice(0, this.config.maxEntriesPerTier)) {
-      entries.push({
-        type: 'storyBeat',
-        id: beat.id,
-        name: beat.title,
-        description: beat.description,
-        tier: 1,
-        priority: 80,
-        metadata: { type: beat.type, status: beat.status },
-      })
-    }
+    entries.push(
+      ...this.limit(
+        activeBeats.map((beat) => ({
+          type: 'storyBeat' as const,
+          id: beat.id,
+          name: beat.title,
+          description: beat.description,
+          tier: 1 as const,
+          priority: 80,
+          matchReason: `${beat.status} story beat`,
+          metadata: { type: beat.type, status: beat.status },
+        })),
+        dropped,
+      ),
+    )
 
     return entries
   }
@@ -217,6 +244,7 @@ export class EntryInjector extends BaseAIService {
     userInput: string,
     recentEntries: StoryEntry[],
     excludeIds: Set<string>,
+    dropped: RelevantEntry[],
   ): RelevantEntry[] {
     const entries: RelevantEntry[] = []
 
@@ -238,6 +266,7 @@ export class EntryInjector extends BaseAIService {
           description: char.description,
           tier: 2,
           priority: 60,
+          matchReason: `matched: ${char.name}`,
           metadata: {
             relationship: char.relationship,
             traits: char.traits,
@@ -258,6 +287,7 @@ export class EntryInjector extends BaseAIService {
           description: loc.description,
           tier: 2,
           priority: 50,
+          matchReason: `matched: ${loc.name}`,
           metadata: { visited: loc.visited },
         })
       }
@@ -274,6 +304,7 @@ export class EntryInjector extends BaseAIService {
           description: item.description,
           tier: 2,
           priority: 40,
+          matchReason: `matched: ${item.name}`,
           metadata: { quantity: item.quantity, location: item.location },
         })
       }
@@ -290,12 +321,13 @@ export class EntryInjector extends BaseAIService {
           description: beat.description,
           tier: 2,
           priority: 45,
+          matchReason: `matched: ${beat.title}`,
           metadata: { type: beat.type, status: beat.status },
         })
       }
     }
 
-    return entries.slice(0, this.config.maxEntriesPerTier)
+    return this.limit(entries, dropped)
   }
 
   /**
@@ -307,6 +339,7 @@ export class EntryInjector extends BaseAIService {
     userInput: string,
     recentEntries: StoryEntry[],
     excludeIds: Set<string>,
+    dropped: RelevantEntry[],
   ): Promise<RelevantEntry[]> {
     // Collect all remaining entries not in Tier 1 or 2
     const candidates: {
@@ -402,6 +435,7 @@ export class EntryInjector extends BaseAIService {
             description: candidate.description,
             tier: 3,
             priority: 30,
+            matchReason: 'LLM selected',
           })
         }
       }
@@ -412,7 +446,7 @@ export class EntryInjector extends BaseAIService {
         reasoning: result.reasoning,
       })
 
-      return entries.slice(0, this.config.maxEntriesPerTier)
+      return this.limit(entries, dropped)
     } catch (error) {
       log('Tier 3 LLM selection failed', error)
       return []
@@ -11,11 +11,13 @@
  * Uses ContextBuilder for prompt generation through the unified Liquid template pipeline.
  */
 
+import { invoke } from '@tauri-apps/api/core'
 import { streamNarrative, generateNarrative } from '../sdk/generate'
 import { ContextBuilder } from '$lib/services/context'
 import { StyleReviewerService } from './StyleReviewerService'
 import { createLogger } from '../core/config'
 import { stripPicTags } from '$lib/utils/inlineImageParser'
+import { countTokens } from '$lib/services/tokenizer'
 import type { StreamChunk } from '../core/types'
 import type {
   Story,
@@ -227,6 +229,20 @@ export function buildChapterSummariesBlock(
   return block
 }
 
+/** A lore entry that made it into the prompt, and why */
+export interface LoreActivation {
+  entryId?: string
+  name: string
+  reason: string
+}
+
+/** Something left out of the prompt to stay within budget */
+export interface TruncationDecision {
+  item: string
+  reason: string
+  tokens: number
+}
+
 /**
  * Options for narrative generation.
  */
@@ -241,6 +257,10 @@ export interface NarrativeOptions {
   signal?: AbortSignal
   /** Timeline fill result for Q&A injection */
   timelineFillResult?: TimelineFillResult | null
+  /** Lore selected for this turn, recorded with the request for debugging */
+  lore?: LoreActivation[]
+  /** Entries dropped by the selection limits, recorded with the request */
+  truncation?: TruncationDecision[]
 }
 
 /**
@@ -298,12 +318,37 @@ export class NarrativeService {
     const mode = story?.mode ?? 'adventure'
     const inlineImageMode = story?.settings?.imageGenerationMode === 'inline'
     const userPrompt = this.buildUserPrompt(entries, mode, inlineImageMode)
+    const prompt = `${primingMessage}\n\n${userPrompt}`
+
+    // Keep the exact request for "what got sent?" debugging; never block generation on it
+    const debugId = invoke<string>('record_request_debug', {
+      request: {
+        storyId: story?.id ?? null,
+        source: 'narrative',
+        messages: [
+          { role: 'system', content: systemPrompt },
+          { role: 'user', content: prompt },
+        ],
+        sections: [
+          { name: 'system', tokens: countTokens(systemPrompt) },
+          { name: 'priming', tokens: countTokens(primingMessage) },
+          { name: 'story', tokens: countTokens(userPrompt) },
+        ],
+        lore: options.lore ?? [],
+        truncation: options.truncation ?? [],
+      },
+    }).catch(() => null)
+    const finishDebug = (outcome: { response: string } | { error: string }) =>
+      debugId.then((requestId) => {
+        if (requestId) invoke('finish_request_debug', { requestId, ...outcome }).catch(() => {})
+      })
+    let responseText = ''
 
     try {
       // Stream using the main narrative profile
       const stream = streamNarrative({
         system: systemPrompt,
-        prompt: `${primingMessage}\n\n${userPrompt}`,
+        prompt,
         signal,
       })
 
@@ -316,14 +361,18 @@ export class NarrativeService {
           yield { content: '', reasoning: (part as { text?: string }).text, done: false }
         } else if (part.type === 'text-delta') {
           // Regular text content
-          yield { content: (part as { text?: string }).text || '', done: false }
+          const text = (part as { text?: string }).text || ''
+          responseText += text
+          yield { content: text, done: false }
         }
         // Ignore other part types (reasoning-start, reasoning-end, tool calls, finish, etc.)
       }
 
+      finishDebug({ response: responseText })
       yield { content: '', done: true }
     } catch (error) {
       log('stream error', error)
+      finishDebug({ error: String(error) })
       // Re-throw to let caller handle the error
       throw error
     }
@@ -5,6 +5,7 @@
  * Uses the Vercel AI SDK for structured output with Zod schema validation.
  */
 
+import { invoke } from '@tauri-apps/api/core'
 import type { StoryEntry, StoryMode, POV, Tense } from '$lib/types'
 import { BaseAIService } from '../BaseAIService'
 import { ContextBuilder } from '$lib/services/context'
@@ -90,6 +91,14 @@ export class StyleReviewerService extends BaseAIService {
       .map((e, i) => `--- Passage ${i + 1} ---\n${e.content}`)
       .join('\n\n')
 
+    // Measured numbers give the reviewer something firmer than impressions
+    const styleMetrics = await invoke<{ summary: string }>('measure_style', {
+      texts: narrationEntries.map((e) => e.content),
+      pov,
+    })
+      .then((metrics) => metrics.summary)
+      .catch(() => '')
+
     const ctx = new ContextBuilder()
     ctx.add({
       mode,
@@ -97,6 +106,7 @@ export class StyleReviewerService extends BaseAIService {
       tense,
       passageCount: narrationEntries.length.toString(),
       passages,
+      styleMetrics,
     })
     const { system, user: prompt } = await ctx.render('style-reviewer')
 
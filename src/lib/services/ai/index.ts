@@ -24,6 +24,7 @@
 import { settings } from '$lib/stores/settings.svelte'
 import { story } from '$lib/stores/story.svelte'
 import { database } from '$lib/services/database'
+import { countTokens } from '$lib/services/tokenizer'
 import type { StoryMode, POV, Tense } from '$lib/types'
 import type { PromptContext } from '../generation/phases/PostGenerationPhase'
 import { DEFAULT_FALLBACK_STYLE_PROMPT } from './image/constants'
@@ -103,11 +104,24 @@ import type {
 import { createLogger } from './core/config'
 import { serviceFactory } from './core/factory'
 import { NarrativeService } from './generation/NarrativeService'
-import type { WorldStateContext } from './generation/NarrativeService'
+import type {
+  LoreActivation,
+  TruncationDecision,
+  WorldStateContext,
+} from './generation/NarrativeService'
 import { parseImageSize } from './image/imageUtils'
 
 const log = createLogger('AIService')
 
+/**
+ * Describe why a tiered entry was injected, for the request debugger.
+ */
+function loreReason(tier: 1 | 2 | 3, matchReason?: string): string {
+  if (tier === 1) return matchReason ? `always active: ${matchReason}` : 'always active'
+  if (tier === 2) return `keyword: ${matchReason?.replace(/^matched: /, '') ?? 'name'}`
+  return 'retrieved by model'
+}
+
 interface WorldState extends WorldStateContext {
   memoryConfig?: MemoryConfig
   lorebookEntries?: Entry[]
@@ -144,6 +158,7 @@ class AIService {
     retrievedChapterContext?: string | null,
     signal?: AbortSignal,
     timelineFillResult?: TimelineFillResult | null,
+    loreRetrieval?: EntryRetrievalResult | null,
   ): AsyncIterable<StreamChunk> {
     log('streamNarrative called', {
       entriesCount: entries.length,
@@ -153,6 +168,18 @@ class AIService {
       hasTimelineFill: !!timelineFillResult,
     })
 
+    // Lorebook selection was made upstream; record why each entry got in and what the limit cut
+    const lore: LoreActivation[] = (loreRetrieval?.all ?? []).map((e) => ({
+      entryId: e.entry.id,
+      name: e.entry.name,
+      reason: loreReason(e.tier, e.matchReason),
+    }))
+    const truncation: TruncationDecision[] = (loreRetrieval?.dropped ?? []).map((e) => ({
+      item: e.entry.name,
+      reason: `over the tier ${e.tier} entry limit`,
+      tokens: countTokens(e.entry.description),
+    }))
+
     // Build tiered context if requested
     let tieredContextBlock: string | undefined
     if (useTieredContext) {
@@ -165,6 +192,16 @@ class AIService {
         retrievedChapterContext ?? undefined,
       )
       tieredContextBlock = contextResult.contextBlock
+      for (const e of contextResult.all) {
+        lore.push({ entryId: e.id, name: e.name, reason: loreReason(e.tier, e.matchReason) })
+      }
+      for (const e of contextResult.dropped) {
+        truncation.push({
+          item: e.name,
+          reason: `over the tier ${e.tier} entry limit`,
+          tokens: countTokens(e.description ?? ''),
+        })
+      }
     }
 
     // Delegate to NarrativeService
@@ -174,6 +211,8 @@ class AIService {
       retrievedChapterContext,
       signal,
       timelineFillResult,
+      lore,
+      truncation,
     })
   }
 
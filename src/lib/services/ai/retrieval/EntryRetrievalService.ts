@@ -124,6 +124,8 @@ export interface EntryRetrievalResult {
   tier2: RetrievedEntry[]
   tier3: RetrievedEntry[]
   all: RetrievedEntry[]
+  /** Entries the LLM selected past the Tier 3 limit */
+  dropped: RetrievedEntry[]
   contextBlock: string
 }
 
@@ -228,6 +230,7 @@ export class EntryRetrievalService extends BaseAIService {
 
     // Tier 3: LLM selection - runs when there are remaining entries and LLM selection is enabled
     let tier3: RetrievedEntry[] = []
+    let dropped: RetrievedEntry[] = []
 
     if (this.config.enableLLMSelection && remainingEntries.length > 0) {
       log('Tier 3 LLM selection triggered', {
@@ -239,6 +242,13 @@ export class EntryRetrievalService extends BaseAIService {
         recentStoryEntries,
         signal,
       )
+
+      // Apply limit if configured
+      if (this.config.maxTier3Entries > 0) {
+        dropped = tier3.slice(this.config.maxTier3Entries)
+        tier3 = tier3.slice(0, this.config.maxTier3Entries)
+      }
+
       log(
         'Tier 3 entries:',
         tier3.length,
@@ -264,7 +274,7 @@ export class EntryRetrievalService extends BaseAIService {
     // Build context block
     const contextBlock = this.buildContextBlock(tier1, tier2, tier3)
 
-    return { tier1, tier2, tier3, all, contextBlock }
+    return { tier1, tier2, tier3, all, dropped, contextBlock }
   }
 
   /**
@@ -641,10 +651,6 @@ export class EntryRetrievalService extends BaseAIService {
         reasoning: result.reasoning,
       })
 
-      // Apply limit if configured
-      if (this.config.maxTier3Entries > 0) {
-        return entries.slice(0, this.config.maxTier3Entries)
-      }
       return entries
     } catch (error) {
       log('Tier 3 LLM selection failed', error)
@@ -4,6 +4,7 @@
  * Designed for extensibility to support multiple TTS providers.
  */
 
+import { invoke } from '@tauri-apps/api/core'
 import { PROVIDERS } from '../sdk/providers/config'
 import { corsFetch } from '$lib/services/discovery/utils'
 
@@ -857,16 +858,28 @@ export class AITTSService {
   }
 
   /**
-   * Generate and play TTS audio
+   * Swap in a story's pronunciation dictionary entries before synthesis.
+   * None of the providers take SSML, so only respellings apply.
+   */
+  async applyPronunciations(text: string, storyId?: string): Promise<string> {
+    if (!storyId) return text
+    return invoke<string>('apply_pronunciations', { storyId, text, format: 'text' })
+  }
+
+  /**
+   * Generate and play TTS audio. With `storyId` the story's pronunciation
+   * dictionary is applied first.
    */
   async generateAndPlay(
     text: string,
     voice?: string,
     onProgress?: (progress: number) => void,
+    storyId?: string,
   ): Promise<void> {
     if (!this.provider || !this.settings) {
       throw new Error('TTS service not ready')
     }
+    text = await this.applyPronunciations(text, storyId)
 
     const voiceToUse = voice || this.settings.voice
     // Speed is always applied client-side via playbackRate since not all
@@ -893,10 +906,11 @@ export class AITTSService {
   /**
    * Generate TTS audio without playing
    */
-  async generateSpeech(text: string, voice?: string): Promise<Blob[]> {
+  async generateSpeech(text: string, voice?: string, storyId?: string): Promise<Blob[]> {
     if (!this.provider || !this.settings) {
       throw new Error('TTS service not ready')
     }
+    text = await this.applyPronunciations(text, storyId)
 
     const voiceToUse = voice || this.settings.voice
     return this.provider.generateSpeech(text, voiceToUse)
@@ -68,10 +68,9 @@ class BackupService {
       }
     } catch (error) {
       console.warn('[Backup] VACUUM INTO failed, falling back to direct file copy:', error)
-      // Fallback: try reading the DB file directly from app data dir
+      // Fallback: try reading the DB file directly
       try {
-        const appDataDir = await path.appDataDir()
-        const dbPath = await path.join(appDataDir, 'aventura.db')
+        const dbPath = await database.getPath()
         if (await exists(dbPath)) {
           dbBytes = await readFile(dbPath)
         }
@@ -225,11 +224,11 @@ class BackupService {
     await database.close()
 
     // 5. Overwrite the database file
-    const appDataDir = await path.appDataDir()
-    const dbPath = await path.join(appDataDir, 'aventura.db')
+    const dbPath = await database.getPath()
+    const dbDir = await path.dirname(dbPath)
 
     // Write a safety copy of the current DB first
-    const safetyPath = await path.join(appDataDir, 'aventura-pre-restore.db')
+    const safetyPath = await path.join(dbDir, 'aventura-pre-restore.db')
     try {
       if (await exists(dbPath)) {
         const currentDb = await readFile(dbPath)
@@ -247,7 +246,7 @@ class BackupService {
     // Also clean up WAL/SHM files that could conflict with the restored DB
     for (const suffix of ['-wal', '-shm']) {
       try {
-        const walPath = await path.join(appDataDir, `aventura.db${suffix}`)
+        const walPath = `${dbPath}${suffix}`
         if (await exists(walPath)) {
           await remove(walPath)
           console.log(`[Restore] Removed ${suffix} file`)
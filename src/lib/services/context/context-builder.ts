@@ -9,6 +9,7 @@
  * Services fetch those directly from the pack and inject data programmatically.
  */
 
+import { invoke } from '@tauri-apps/api/core'
 import { database } from '$lib/services/database'
 import { templateEngine } from '$lib/services/templates/engine'
 import { createLogger } from '$lib/services/ai/core/config'
@@ -88,6 +89,13 @@ export class ContextBuilder {
     const storyBeats = await database.getStoryBeats(storyId)
     await builder.loadRuntimeVariableContext(characters, locations, items, storyBeats, protagonist)
 
+    // Natively built blocks (character stat sheets, ...)
+    try {
+      builder.add(await invoke<Record<string, string>>('get_context_blocks', { storyId }))
+    } catch (error) {
+      log('forStory: native context blocks unavailable', { error })
+    }
+
     log('forStory complete', {
       storyId,
       packId,
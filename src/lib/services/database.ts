@@ -1,4 +1,5 @@
 import Database from '@tauri-apps/plugin-sql'
+import { invoke } from '@tauri-apps/api/core'
 import type {
   Story,
   StoryEntry,
@@ -115,11 +116,21 @@ class DatabaseService {
 
   async init(): Promise<void> {
     if (this.db) return
-    this.db = await Database.load('sqlite:aventura.db')
+    // The active profile decides which database file is opened
+    const { databaseUrl } = await invoke<{ databaseUrl: string }>('get_data_paths')
+    this.db = await Database.load(databaseUrl)
     // Enable foreign key enforcement (SQLite disables by default)
     await this.db.execute('PRAGMA foreign_keys = ON')
   }
 
+  /**
+   * Path of the active profile's database file.
+   */
+  async getPath(): Promise<string> {
+    const { database } = await invoke<{ database: string }>('get_data_paths')
+    return database
+  }
+
   /**
    * Close the database connection. After calling this, the next
    * getDb() / init() call will re-open the connection.
@@ -1236,7 +1247,7 @@ class DatabaseService {
       values.push(updates.title)
     }
     if (updates.summary !== undefined) {
-      setClauses.push('summary = ?')
+      setClauses.push('summary = ?', 'summary_stale = 0')
       values.push(updates.summary)
     }
     if (updates.startTime !== undefined) {
@@ -1668,6 +1679,7 @@ class DatabaseService {
       checkpointId: row.checkpoint_id || null,
       createdAt: row.created_at,
       snapshotComplete: row.snapshot_complete === 1,
+      sandboxExpiresAt: row.sandbox_expires_at ?? null,
     }
   }
 
@@ -2562,6 +2574,7 @@ class DatabaseService {
       plotThreads: row.plot_threads ? JSON.parse(row.plot_threads) : [],
       emotionalTone: row.emotional_tone,
       branchId: row.branch_id || null,
+      summaryStale: row.summary_stale === 1,
       createdAt: row.created_at,
     }
   }
@@ -1,5 +1,6 @@
 import { save, open } from '@tauri-apps/plugin-dialog'
 import { writeTextFile, readTextFile } from '@tauri-apps/plugin-fs'
+import { invoke } from '@tauri-apps/api/core'
 import { database } from './database'
 import type {
   Story,
@@ -14,6 +15,8 @@ import type {
   Branch,
   PersistentStyleReviewState,
   EmbeddedImage,
+  EntryAlternative,
+  EntryAnnotation,
 } from '$lib/types'
 
 export interface AventuraExport {
@@ -33,6 +36,30 @@ export interface AventuraExport {
   branches?: Branch[] // Added in v1.6.0
   chapters?: Chapter[] // Added in v1.7.0
   currentBgImage?: string | null // Added in v1.8.0
+  entryAlternatives?: EntryAlternative[] // Added in v1.9.0
+  entryAnnotations?: EntryAnnotation[] // Added in v1.10.0
+  attribution?: ExportAttribution // Only on copies made for sharing
+}
+
+/** Credits for a shared story, from the `get_export_attribution` command */
+export interface ExportAttribution {
+  appVersion: string
+  scenario: { name: string; creator: string | null; sourceUrl: string | null } | null
+  packs: { id: string; name: string; version: string; author: string | null }[]
+  exportedAt: string
+}
+
+/** Result of the `validate_export_file` command */
+interface ExportValidation {
+  importable: boolean
+  version: string | null
+  currentVersion: string
+  upgradedThrough: string[]
+  title: string | null
+  entries: number
+  branches: number
+  chapters: number
+  problems: { severity: 'error' | 'warning'; path: string; message: string }[]
 }
 
 // Version history for import compatibility
@@ -44,9 +71,13 @@ export interface AventuraExport {
 // v1.5.0 - Added character portraits
 // v1.6.0 - Added checkpoints and branches
 // v1.7.0 - Added chapters (memory system)
+// v1.8.0 - Added currentBgImage
+// v1.9.0 - Added entryAlternatives (regenerated variants of entries)
+// v1.10.0 - Added entryAnnotations (writer's notes on entries)
+// Keep in step with CURRENT_VERSION and the upgrade steps in src-tauri/src/story_format
 
 class ExportService {
-  private readonly VERSION = '1.7.0'
+  private readonly VERSION = '1.10.0'
 
   /**
    * Compare semantic versions. Returns:
@@ -109,9 +140,30 @@ class ExportService {
         `[Import] File from v${importVersion} predates current background image (v1.8.0). Current background image will not be restored.`,
       )
     }
+    if (this.compareVersions(importVersion, '1.9.0') < 0) {
+      console.warn(
+        `[Import] File from v${importVersion} predates entry alternatives (v1.9.0). Only the active version of each entry will be restored.`,
+      )
+    }
+    if (this.compareVersions(importVersion, '1.10.0') < 0) {
+      console.warn(
+        `[Import] File from v${importVersion} predates entry annotations (v1.10.0). No annotations will be restored.`,
+      )
+    }
+  }
+
+  // The attribution for a shared export of a story, with its footer rendered
+  // for `format`
+  private async attribution(
+    storyId: string,
+    format: 'text' | 'markdown' | 'html',
+  ): Promise<{ attribution: ExportAttribution; footer: string }> {
+    return invoke('get_export_attribution', { storyId, format })
   }
 
-  // Export to Aventura format (.avt - JSON)
+  // Export to Aventura format (.avt - JSON). With `redact` the story's
+  // redaction rules are applied to the file; `attribution` adds the story's
+  // credits for sharing.
   async exportToAventura(
     story: Story,
     entries: StoryEntry[],
@@ -125,7 +177,16 @@ class ExportService {
     branches: Branch[] = [],
     chapters: Chapter[] = [],
     currentBgImage: string | null = null,
+    redact = false,
+    attribution = false,
   ): Promise<boolean> {
+    const entryAlternatives = await invoke<EntryAlternative[]>('get_story_alternatives', {
+      storyId: story.id,
+    }).catch(() => [])
+    const entryAnnotations = await invoke<EntryAnnotation[]>('get_shared_annotations', {
+      storyId: story.id,
+      purpose: 'export',
+    }).catch(() => [])
     const exportData: AventuraExport = {
       version: this.VERSION,
       exportedAt: Date.now(),
@@ -142,6 +203,11 @@ class ExportService {
       branches,
       chapters,
       currentBgImage,
+      entryAlternatives,
+      entryAnnotations,
+    }
+    if (attribution) {
+      exportData.attribution = (await this.attribution(story.id, 'text')).attribution
     }
 
     const filePath = await save({
@@ -154,7 +220,12 @@ class ExportService {
 
     if (!filePath) return false
 
-    await writeTextFile(filePath, JSON.stringify(exportData, null, 2))
+    let content = JSON.stringify(exportData, null, 2)
+    if (redact) {
+      const redacted = await invoke<string>('redact_story_export', { storyJson: content })
+      content = JSON.stringify(JSON.parse(redacted), null, 2)
+    }
+    await writeTextFile(filePath, content)
     return true
   }
 
@@ -165,6 +236,7 @@ class ExportService {
     characters: Character[],
     locations: Location[],
     includeWorldState: boolean = false,
+    attribution = false,
   ): Promise<boolean> {
     let markdown = `# ${story.title}\n\n`
 
@@ -221,9 +293,13 @@ class ExportService {
       }
     }
 
-    // Add export metadata
-    markdown += `---\n\n`
-    markdown += `*Exported from Aventura on ${new Date().toLocaleDateString()}*\n`
+    // Add export metadata, or the full attribution when sharing
+    if (attribution) {
+      markdown += (await this.attribution(story.id, 'markdown')).footer
+    } else {
+      markdown += `---\n\n`
+      markdown += `*Exported from Aventura on ${new Date().toLocaleDateString()}*\n`
+    }
 
     const filePath = await save({
       defaultPath: `${this.sanitizeFilename(story.title)}.md`,
@@ -240,7 +316,7 @@ class ExportService {
   }
 
   // Export to plain text
-  async exportToText(story: Story, entries: StoryEntry[]): Promise<boolean> {
+  async exportToText(story: Story, entries: StoryEntry[], attribution = false): Promise<boolean> {
     let text = `${story.title}\n${'='.repeat(story.title.length)}\n\n`
 
     if (story.description) {
@@ -262,6 +338,10 @@ class ExportService {
       }
     }
 
+    if (attribution) {
+      text += (await this.attribution(story.id, 'text')).footer
+    }
+
     const filePath = await save({
       defaultPath: `${this.sanitizeFilename(story.title)}.txt`,
       filters: [{ name: 'Text', extensions: ['txt'] }],
@@ -288,6 +368,15 @@ class ExportService {
     }
 
     try {
+      // Catch broken or unreadable files before anything is written
+      const validation = await invoke<ExportValidation>('validate_export_file', { path: filePath })
+      if (!validation.importable) {
+        const errors = validation.problems
+          .filter((p) => p.severity === 'error')
+          .slice(0, 3)
+          .map((p) => (p.path ? `${p.path}: ${p.message}` : p.message))
+        return { success: false, error: `Invalid story file: ${errors.join('; ')}` }
+      }
       const content = await readTextFile(filePath)
       return this.importFromContent(content)
     } catch (error) {
@@ -305,6 +394,8 @@ class ExportService {
     content: string,
     skipImportedSuffix: boolean = false,
   ): Promise<{ success: boolean; storyId?: string; error?: string }> {
+    // Set once the story row exists, so a failed import can be rolled back
+    let createdStoryId: string | null = null
     try {
       let data: AventuraExport
       try {
@@ -391,6 +482,7 @@ class ExportService {
       }
 
       await database.createStory(importedStory)
+      createdStoryId = newStoryId
 
       const mapBranchId = (branchId: string | null | undefined) =>
         branchId ? (branchIdMap.get(branchId) ?? null) : null
@@ -785,9 +877,41 @@ class ExportService {
         }
       }
 
+      // Import entry alternatives (added in v1.9.0)
+      if (data.entryAlternatives?.length) {
+        try {
+          await invoke('import_entry_alternatives', {
+            storyId: newStoryId,
+            alternatives: data.entryAlternatives,
+            entryIds: Object.fromEntries(oldToNewId),
+          })
+        } catch (error) {
+          console.warn('[Import] Failed to import entry alternatives:', error)
+        }
+      }
+
+      // Import entry annotations (added in v1.10.0)
+      if (data.entryAnnotations?.length) {
+        try {
+          await invoke('import_entry_annotations', {
+            storyId: newStoryId,
+            annotations: data.entryAnnotations,
+            entryIds: Object.fromEntries(oldToNewId),
+          })
+        } catch (error) {
+          console.warn('[Import] Failed to import entry annotations:', error)
+        }
+      }
+
       return { success: true, storyId: newStoryId }
     } catch (error) {
       console.error('Import failed:', error)
+      // Don't leave half a story behind; its rows cascade with it
+      if (createdStoryId) {
+        await database
+          .deleteStory(createdStoryId)
+          .catch((e) => console.error('[Import] Failed to roll back partial import:', e))
+      }
       return {
         success: false,
         error: error instanceof Error ? error.message : 'Failed to import file',
@@ -35,6 +35,7 @@ export interface NarrativeDependencies {
     retrievedContext: string | null | undefined,
     signal: AbortSignal | undefined,
     timelineFillResult: RetrievalResult['timelineFillResult'],
+    loreRetrieval: RetrievalResult['lorebookRetrievalResult'],
   ) => AsyncIterable<StreamChunk>
 }
 
@@ -93,6 +94,7 @@ export class NarrativePhase {
           retrievalResult.combinedContext,
           abortSignal,
           retrievalResult.timelineFillResult,
+          retrievalResult.lorebookRetrievalResult,
         )) {
           if (abortSignal?.aborted) {
             yield { type: 'aborted', phase: 'narrative' } satisfies AbortedEvent
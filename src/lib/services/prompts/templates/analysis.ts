@@ -202,7 +202,10 @@ Identify overused phrases, sentence patterns, structural repetition, and stylist
 - Focus on actionable improvements`,
   userContent: `Analyze these {{ passageCount }} passages for repetitive phrases, structural patterns, and style issues. Each passage is a separate AI-generated narrative response.
 
-{{ passages }}`,
+{% if styleMetrics != '' %}## Measured Style Metrics
+{{ styleMetrics }}
+
+{% endif %}{{ passages }}`,
 }
 
 const lorebookClassifierPromptTemplate: PromptTemplate = {
@@ -1,5 +1,18 @@
 import { invoke } from '@tauri-apps/api/core'
-import type { SyncServerInfo, SyncStoryPreview, SyncConnectionData } from '$lib/types/sync'
+import type {
+  SyncServerInfo,
+  SyncStoryPreview,
+  SyncStoryListing,
+  SyncConnectionData,
+  SyncPeerInfo,
+  BleDevice,
+  BleStatus,
+  BlePairingData,
+  DirectLink,
+  SyncDevice,
+  SyncSendResult,
+  LocalNetworkAccess,
+} from '$lib/types/sync'
 import type { AventuraExport } from './export'
 import { database } from './database'
 import { story } from '$lib/stores/story.svelte'
@@ -17,6 +30,21 @@ class SyncService {
     return invoke('start_sync_server', { storiesJson })
   }
 
+  /**
+   * Start the sync server on a network this device hosts (Android only),
+   * for when there's no shared router. The QR code includes the network.
+   */
+  async startDirectServer(storiesJson: string[]): Promise<SyncServerInfo> {
+    return invoke('start_direct_sync_server', { storiesJson })
+  }
+
+  /**
+   * Network this device is hosting for direct sync, if any
+   */
+  async getDirectLink(): Promise<DirectLink | null> {
+    return invoke('get_direct_link')
+  }
+
   /**
    * Stop the sync server
    */
@@ -38,6 +66,34 @@ class SyncService {
     return invoke('clear_received_stories')
   }
 
+  /**
+   * Local Network access as of the last check, without prompting
+   */
+  async getLocalNetworkAccess(): Promise<LocalNetworkAccess> {
+    return invoke('get_local_network_access')
+  }
+
+  /**
+   * Check Local Network access, showing the OS prompt the first time on
+   * iOS/macOS. Call when the user starts syncing, and again after they
+   * return from Settings.
+   */
+  async requestLocalNetworkAccess(): Promise<LocalNetworkAccess> {
+    return invoke('request_local_network_access')
+  }
+
+  /**
+   * Check that a remote server speaks a compatible protocol. Rejects with a
+   * message saying which device to update when it doesn't.
+   */
+  async handshake(connection: SyncConnectionData): Promise<SyncPeerInfo> {
+    return invoke('sync_handshake', {
+      ip: connection.ip,
+      port: connection.port,
+      token: connection.token,
+    })
+  }
+
   /**
    * Connect to a remote sync server and list available stories
    */
@@ -49,6 +105,22 @@ class SyncService {
     })
   }
 
+  /**
+   * List a remote server's stories, skipping the download when the listing
+   * still matches `sinceHash` from a previous call
+   */
+  async listStoriesIfChanged(
+    connection: SyncConnectionData,
+    sinceHash?: string,
+  ): Promise<SyncStoryListing> {
+    return invoke('sync_list_stories_if_changed', {
+      ip: connection.ip,
+      port: connection.port,
+      token: connection.token,
+      sinceHash: sinceHash ?? null,
+    })
+  }
+
   /**
    * Pull a story from a remote server
    * @returns Story JSON in Aventura export format
@@ -63,17 +135,89 @@ class SyncService {
   }
 
   /**
-   * Push a story to a remote server
+   * Push a story to a remote server. With `redact` the story's redaction
+   * rules are applied before it leaves the device.
    */
-  async pushStory(connection: SyncConnectionData, storyJson: string): Promise<void> {
+  async pushStory(
+    connection: SyncConnectionData,
+    storyJson: string,
+    redact = false,
+  ): Promise<void> {
     return invoke('sync_push_story', {
       ip: connection.ip,
       port: connection.port,
       token: connection.token,
       storyJson,
+      redact,
     })
   }
 
+  /**
+   * Share stories over Bluetooth LE (Android only). Reuses the running sync
+   * server's stories and token if there is one.
+   */
+  async startBleAdvertising(storiesJson?: string[]): Promise<BlePairingData> {
+    return invoke('start_ble_advertising', { storiesJson: storiesJson ?? null })
+  }
+
+  async stopBleAdvertising(): Promise<void> {
+    return invoke('stop_ble_advertising')
+  }
+
+  async getBleStatus(): Promise<BleStatus> {
+    return invoke('get_ble_status')
+  }
+
+  /**
+   * Find nearby devices sharing stories over Bluetooth
+   */
+  async bleScan(seconds?: number): Promise<BleDevice[]> {
+    return invoke('ble_scan', { seconds: seconds ?? null })
+  }
+
+  /**
+   * Read a device's pairing data (token, versions, WiFi address)
+   */
+  async blePair(address: string): Promise<BlePairingData> {
+    return invoke('ble_pair', { address })
+  }
+
+  async bleConnect(address: string, token: string): Promise<SyncStoryPreview[]> {
+    return invoke('ble_sync_connect', { address, token })
+  }
+
+  /**
+   * Pull a story over Bluetooth; it arrives without images or checkpoints
+   */
+  async blePullStory(address: string, token: string, storyId: string): Promise<string> {
+    return invoke('ble_sync_pull_story', { address, token, storyId })
+  }
+
+  /**
+   * Push a story over Bluetooth; images and checkpoints are left out
+   */
+  async blePushStory(
+    address: string,
+    token: string,
+    storyJson: string,
+    redact = false,
+  ): Promise<void> {
+    return invoke('ble_sync_push_story', { address, token, storyJson, redact })
+  }
+
+  /**
+   * Send a story to a device in one step: pairing, handshake, push and
+   * receipt check all happen in Rust. Listen for `sync-send-progress`.
+   */
+  async sendStoryToDevice(
+    storyId: string,
+    device: SyncDevice,
+    redact = false,
+  ): Promise<SyncSendResult> {
+    const storyJson = await this.exportStoryToJson(storyId)
+    return invoke('send_story_to_device', { storyId, storyJson, device, redact })
+  }
+
   /**
    * Create a pre-sync backup checkpoint for a story
    */
@@ -120,21 +264,43 @@ class SyncService {
       database.getChapters(storyId),
     ])
 
+    const entryAlternatives = await invoke<AventuraExport['entryAlternatives']>(
+      'get_story_alternatives',
+      { storyId },
+    ).catch(() => [])
+    const entryAnnotations = await invoke<AventuraExport['entryAnnotations']>(
+      'get_shared_annotations',
+      { storyId, purpose: 'sync' },
+    ).catch(() => [])
+
+    // Sandbox branches and everything on them stay on this device
+    const sandboxes = branches.filter((b) => b.sandboxExpiresAt)
+    const sandboxIds = new Set(sandboxes.map((b) => b.id))
+    const kept = <T extends { branchId?: string | null }>(rows: T[]): T[] =>
+      rows.filter((row) => !row.branchId || !sandboxIds.has(row.branchId))
+    const keptEntries = kept(entries)
+    const keptEntryIds = new Set(keptEntries.map((e) => e.id))
+    const currentSandbox = sandboxes.find((b) => b.id === storyData.currentBranchId)
+
     const exportData: AventuraExport = {
-      version: '1.7.0',
+      version: '1.10.0',
       exportedAt: Date.now(),
-      story: storyData,
-      entries,
-      characters,
-      locations,
-      items,
-      storyBeats,
-      lorebookEntries,
+      story: currentSandbox
+        ? { ...storyData, currentBranchId: currentSandbox.parentBranchId }
+        : storyData,
+      entries: keptEntries,
+      characters: kept(characters),
+      locations: kept(locations),
+      items: kept(items),
+      storyBeats: kept(storyBeats),
+      lorebookEntries: kept(lorebookEntries),
       styleReviewState: storyData.styleReviewState,
-      embeddedImages,
+      embeddedImages: embeddedImages.filter((i) => keptEntryIds.has(i.entryId)),
       checkpoints,
-      branches,
-      chapters,
+      branches: branches.filter((b) => !sandboxIds.has(b.id)),
+      chapters: kept(chapters),
+      entryAlternatives: entryAlternatives?.filter((a) => keptEntryIds.has(a.entryId)),
+      entryAnnotations: entryAnnotations?.filter((a) => keptEntryIds.has(a.entryId)),
     }
 
     return JSON.stringify(exportData)
@@ -173,6 +339,8 @@ class SyncService {
         port: parsed.port,
         token: parsed.token,
         version: parsed.version, // May be undefined for older QR codes
+        protocolVersion: parsed.protocolVersion,
+        wifi: parsed.wifi,
       }
     } catch {
       throw new Error('Invalid QR code data')
@@ -491,6 +491,13 @@ export const RUNTIME_VARIABLES: VariableDefinition[] = [
     description: 'Formatted passages for style review',
     required: false,
   },
+  {
+    name: 'styleMetrics',
+    type: 'text',
+    category: 'runtime',
+    description: 'Measured readability and style numbers for the reviewed passages',
+    required: false,
+  },
 
   // === Lore Management Service ===
   {
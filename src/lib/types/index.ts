@@ -117,6 +117,7 @@ export interface StorySettings {
   imageGenerationMode?: 'none' | 'agentic' | 'inline' // Image generation strategy
   backgroundImagesEnabled?: boolean
   referenceMode?: boolean
+  inventoryInContext?: boolean // Include the tracked inventory summary in prompts
 }
 
 export interface StoryEntry {
@@ -140,6 +141,32 @@ export interface StoryEntry {
   suggestedActions?: string | null // JSON blob: ActionChoice[] or Suggestion[] depending on story mode
 }
 
+/** A generated variant of a story entry; the active one mirrors the entry content */
+export interface EntryAlternative {
+  id: string
+  storyId: string
+  entryId: string
+  content: string
+  generationParams: string | null
+  active: boolean
+  createdAt: number
+}
+
+/** A writer's note on a story entry, kept apart from its text */
+export interface EntryAnnotation {
+  id: string
+  storyId: string
+  entryId: string
+  body: string
+  quote: string | null
+  instruct: boolean
+  includeInSync: boolean
+  includeInExport: boolean
+  resolved: boolean
+  createdAt: number
+  updatedAt: number
+}
+
 export interface EntryMetadata {
   tokenCount?: number
   model?: string
@@ -402,6 +429,7 @@ export interface Chapter {
   emotionalTone: string | null
 
   branchId: string | null // Branch this chapter belongs to (null = main branch for legacy)
+  summaryStale?: boolean // Set by split/merge until the chapter is summarized again
 
   createdAt: number
 }
@@ -442,6 +470,7 @@ export interface Branch {
   checkpointId: string | null // Checkpoint for world state restoration
   createdAt: number
   snapshotComplete?: boolean // When true, branch has its own complete entity set (no lineage resolution needed)
+  sandboxExpiresAt?: number | null // Set on sandbox branches, which are deleted after this unless promoted
 }
 
 // ===== Entry/Lorebook System (per design doc section 3.2) =====
@@ -495,8 +524,19 @@ export interface Entry {
 
 export interface EntryInjection {
   mode: EntryInjectionMode
-  keywords: string[]
+  keywords: string[] // Plain keys, or /pattern/flags for a regex key
   priority: number // Higher = inject first
+  // Advanced activation (evaluated by the native lore engine)
+  secondaryKeys?: string[]
+  secondaryLogic?: 'andAny' | 'andAll' | 'notAny' | 'notAll'
+  probability?: number // 0-100 chance a keyword match fires the entry
+  sticky?: number // Turns the entry stays active after firing
+  cooldown?: number // Turns after the sticky period when keywords are ignored
+  excludeRecursion?: boolean // Only story text can fire this entry
+  preventRecursion?: boolean // This entry's text never fires other entries
+  budgetClass?: 'always' | 'high' | 'normal' | 'low' // Lower classes are cut first when lore is over budget
+  validFrom?: TimeTracker // In-story time the entry becomes true
+  validUntil?: TimeTracker // In-story time the entry stops being injected
 }
 
 // Base entry state (common fields)
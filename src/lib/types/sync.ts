@@ -10,6 +10,17 @@ export interface SyncServerInfo {
   port: number
   token: string
   qrCodeBase64: string
+  direct: DirectLink | null // Network this device hosts, in direct mode
+}
+
+/**
+ * A network the phone hosts so another device can sync without a router
+ */
+export interface DirectLink {
+  mode: 'wifiDirect' | 'hotspot'
+  ssid: string
+  passphrase: string
+  ownerAddress: string
 }
 
 /**
@@ -23,6 +34,15 @@ export interface SyncStoryPreview {
   entryCount: number
 }
 
+/**
+ * Result of a conditional story listing
+ */
+export interface SyncStoryListing {
+  hash: string
+  /** null when the listing still matches the hash that was sent */
+  stories: SyncStoryPreview[] | null
+}
+
 /**
  * Data encoded in the QR code for connection
  */
@@ -31,6 +51,72 @@ export interface SyncConnectionData {
   port: number
   token: string
   version?: string // App version for compatibility check (optional for backwards compat)
+  protocolVersion?: number // Sync protocol version (missing from QR codes of older apps)
+  wifi?: { ssid: string; passphrase: string } // Network to join first, for direct connections
+}
+
+/**
+ * A remote server's protocol version and capabilities, from the handshake
+ */
+export interface SyncPeerInfo {
+  protocolVersion: number
+  appVersion: string | null
+  capabilities: string[]
+}
+
+/**
+ * Whether the app may use the local network. iOS and macOS block it until
+ * the user allows it, without telling the app; elsewhere it's `notRequired`.
+ */
+export type LocalNetworkAccess = 'notRequired' | 'unknown' | 'granted' | 'denied'
+
+/**
+ * A nearby device sharing stories over Bluetooth LE
+ */
+export interface BleDevice {
+  address: string
+  name: string | null
+  rssi: number
+}
+
+export interface BleStatus {
+  supported: boolean
+  advertising: boolean
+}
+
+/**
+ * What a device shares when paired over Bluetooth
+ */
+export interface BlePairingData {
+  token: string
+  version: string
+  protocolVersion: number
+  /** ip:port of its WiFi sync server, if one is running */
+  wifiAddress: string | null
+}
+
+/**
+ * A device to send a story to, from a QR code or a Bluetooth scan
+ */
+export type SyncDevice =
+  | { kind: 'wifi'; ip: string; port: number; token: string }
+  | { kind: 'bluetooth'; address: string }
+
+/**
+ * Payload of the `sync-send-progress` event
+ */
+export interface SyncSendProgress {
+  storyId: string
+  stage: 'pairing' | 'connecting' | 'sending' | 'done'
+  transport: 'wifi' | 'bluetooth' | null
+}
+
+export interface SyncSendResult {
+  transport: 'wifi' | 'bluetooth'
+  /** The receiver confirmed it stored exactly what was sent (older apps can't) */
+  confirmed: boolean
+  /** Images and checkpoints were left out to fit through Bluetooth */
+  textOnly: boolean
 }
 
 /**